@@ -0,0 +1,7 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    claude_python_guardrails::fuzzing::fuzz_config_yaml(data);
+});
@@ -0,0 +1,360 @@
+use crate::discovery::PythonEnvironment;
+use crate::toolchain::ToolCommand;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+use which::which;
+
+/// Lockfiles whose pins are audited when edited, besides `requirements*.txt`
+const LOCKFILES: &[&str] = &["poetry.lock", "uv.lock"];
+
+/// Whether an edited file pins dependencies: `requirements*.txt`, `poetry.lock` or
+/// `uv.lock`
+pub fn is_dependency_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    LOCKFILES.contains(&name) || (name.starts_with("requirements") && name.ends_with(".txt"))
+}
+
+/// Whether an edited file is the project metadata lockfiles are generated from
+pub fn is_project_metadata(path: &Path) -> bool {
+    path.file_name().and_then(|name| name.to_str()) == Some("pyproject.toml")
+}
+
+/// Command verifying a lockfile matches pyproject.toml
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockfileCheck {
+    pub lockfile: &'static str,
+    pub command: ToolCommand,
+    /// Command that regenerates the lockfile, for the failure message
+    pub relock: &'static str,
+}
+
+/// Consistency check for the project's lockfile (`uv lock --check` or
+/// `poetry check --lock`), when one exists and its tool is installed
+pub fn lockfile_check(root: &Path) -> Option<LockfileCheck> {
+    let checks = [
+        ("uv.lock", "uv", ["lock", "--check"], "uv lock"),
+        ("poetry.lock", "poetry", ["check", "--lock"], "poetry lock"),
+    ];
+    checks
+        .into_iter()
+        .find(|(lockfile, tool, _, _)| root.join(lockfile).is_file() && which(tool).is_ok())
+        .map(|(lockfile, tool, args, relock)| LockfileCheck {
+            lockfile,
+            command: ToolCommand::new(tool, &args),
+            relock,
+        })
+}
+
+/// A dependency pinned to an exact version
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PinnedPackage {
+    /// Normalized (PEP 503) distribution name
+    pub name: String,
+    pub version: String,
+}
+
+impl PinnedPackage {
+    fn new(name: &str, version: &str) -> Self {
+        Self {
+            name: normalize_name(name),
+            version: version.to_string(),
+        }
+    }
+}
+
+/// A known vulnerability affecting a pinned dependency
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vulnerability {
+    pub package: PinnedPackage,
+    pub id: String,
+    /// Versions that fix it; empty when no fix is released
+    pub fix_versions: Vec<String>,
+}
+
+/// Exact pins in a requirements file (`name==version`) or a poetry/uv lockfile
+pub fn pinned_packages(file_name: &str, content: &str) -> Vec<PinnedPackage> {
+    if LOCKFILES.contains(&file_name) {
+        lockfile_pins(content)
+    } else {
+        requirements_pins(content)
+    }
+}
+
+fn requirements_pins(content: &str) -> Vec<PinnedPackage> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let requirement = line.split('#').next()?.split(';').next()?.trim();
+            if requirement.starts_with('-') {
+                return None;
+            }
+            let (name, version) = requirement.split_once("==")?;
+            let name = name.split('[').next()?.trim();
+            let version = version
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .next()?;
+            (!name.is_empty() && !version.is_empty()).then(|| PinnedPackage::new(name, version))
+        })
+        .collect()
+}
+
+/// `name`/`version` pairs of the `[[package]]` tables shared by poetry.lock and uv.lock
+fn lockfile_pins(content: &str) -> Vec<PinnedPackage> {
+    let mut pins = Vec::new();
+    let mut in_package = false;
+    let mut name = None;
+    let mut version = None;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[[package]]";
+            name = None;
+            version = None;
+            continue;
+        }
+        if !in_package {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"');
+        match key.trim() {
+            "name" => name = Some(value.to_string()),
+            "version" => version = Some(value.to_string()),
+            _ => continue,
+        }
+        if let (Some(name), Some(version)) = (&name, &version) {
+            pins.push(PinnedPackage::new(name, version));
+            in_package = false;
+        }
+    }
+
+    pins
+}
+
+fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase().replace(['_', '.'], "-")
+}
+
+/// Pins in the committed (HEAD) version of the file
+///
+/// Returns `None` when git can't provide one (not a repository, git missing, file not
+/// committed yet), in which case every pin counts as new.
+pub fn committed_pins(file: &Path) -> Option<Vec<PinnedPackage>> {
+    let file_name = file.file_name()?.to_str()?;
+    let output = Command::new("git")
+        .arg("show")
+        .arg(format!("HEAD:./{file_name}"))
+        .current_dir(file.parent()?)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| pinned_packages(file_name, &String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Pins added or changed compared to the committed file
+pub fn new_pins(
+    current: &[PinnedPackage],
+    committed: Option<&[PinnedPackage]>,
+) -> Vec<PinnedPackage> {
+    let committed: HashSet<&PinnedPackage> = committed.unwrap_or_default().iter().collect();
+    current
+        .iter()
+        .filter(|pin| !committed.contains(pin))
+        .cloned()
+        .collect()
+}
+
+/// Requirements file contents pinning exactly the given packages, for auditing
+/// lockfiles pip-audit can't read directly
+pub fn requirements_for(pins: &[PinnedPackage]) -> String {
+    pins.iter()
+        .map(|pin| format!("{}=={}\n", pin.name, pin.version))
+        .collect()
+}
+
+/// Command auditing a fully pinned requirements file without installing it: pip-audit
+/// from the project's environment or PATH, or run through `uvx`
+pub fn audit_command(
+    environment: &PythonEnvironment,
+    requirements: &Path,
+) -> Option<(String, Vec<String>)> {
+    let args: Vec<String> = [
+        "-r",
+        &requirements.to_string_lossy(),
+        "--no-deps",
+        "--disable-pip",
+        "--format",
+        "json",
+        "--progress-spinner",
+        "off",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+
+    if environment.find_tool("pip-audit").is_some() {
+        return Some((environment.resolve("pip-audit"), args));
+    }
+    which("uvx").ok()?;
+    Some((
+        "uvx".to_string(),
+        std::iter::once("pip-audit".to_string())
+            .chain(args)
+            .collect(),
+    ))
+}
+
+/// Vulnerabilities in `pip-audit --format json` output: the `{"dependencies": [...]}`
+/// object, or the bare list older versions print
+pub fn parse_pip_audit_json(output: &str) -> Option<Vec<Vulnerability>> {
+    let start = output.find(['{', '['])?;
+    let json: serde_json::Value = serde_json::from_str(&output[start..]).ok()?;
+    let dependencies = json.get("dependencies").unwrap_or(&json).as_array()?;
+
+    let mut vulnerabilities = Vec::new();
+    for dependency in dependencies {
+        let (Some(name), Some(version)) = (
+            dependency.get("name").and_then(|name| name.as_str()),
+            dependency
+                .get("version")
+                .and_then(|version| version.as_str()),
+        ) else {
+            continue;
+        };
+        let package = PinnedPackage::new(name, version);
+        let vulns = dependency.get("vulns").and_then(|vulns| vulns.as_array());
+        for vuln in vulns.into_iter().flatten() {
+            let Some(id) = vuln.get("id").and_then(|id| id.as_str()) else {
+                continue;
+            };
+            let fix_versions = vuln
+                .get("fix_versions")
+                .and_then(|versions| versions.as_array())
+                .map(|versions| {
+                    versions
+                        .iter()
+                        .filter_map(|version| version.as_str().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default();
+            vulnerabilities.push(Vulnerability {
+                package: package.clone(),
+                id: id.to_string(),
+                fix_versions,
+            });
+        }
+    }
+
+    Some(vulnerabilities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dependency_file_detection() {
+        assert!(is_dependency_file(Path::new("requirements.txt")));
+        assert!(is_dependency_file(Path::new("deps/requirements-dev.txt")));
+        assert!(is_dependency_file(Path::new("poetry.lock")));
+        assert!(is_dependency_file(Path::new("uv.lock")));
+        assert!(!is_dependency_file(Path::new("Cargo.lock")));
+        assert!(!is_dependency_file(Path::new("notes.txt")));
+        assert!(is_project_metadata(Path::new("app/pyproject.toml")));
+        assert!(!is_project_metadata(Path::new("poetry.lock")));
+    }
+
+    #[test]
+    fn test_lockfile_check_needs_a_lockfile() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert_eq!(lockfile_check(temp_dir.path()), None);
+
+        // The check is only offered when the lockfile's tool is installed
+        std::fs::write(temp_dir.path().join("uv.lock"), "version = 1\n").unwrap();
+        let check = lockfile_check(temp_dir.path());
+        assert_eq!(check.is_some(), which("uv").is_ok());
+        if let Some(check) = check {
+            assert_eq!(check.command.display(), "uv lock --check");
+            assert_eq!(check.relock, "uv lock");
+        }
+    }
+
+    #[test]
+    fn test_pinned_packages() {
+        let requirements = "# deps\n-r base.txt\nDjango==4.2.1  # web\nrequests[socks]==2.31.0 ; python_version >= '3.8'\nflask>=2\n";
+        assert_eq!(
+            pinned_packages("requirements.txt", requirements),
+            vec![
+                PinnedPackage::new("django", "4.2.1"),
+                PinnedPackage::new("requests", "2.31.0"),
+            ]
+        );
+
+        let lockfile = "version = 1\n\n[[package]]\nname = \"Jinja2\"\nversion = \"2.11.2\"\nsource = { registry = \"https://pypi.org/simple\" }\ndependencies = [\n    { name = \"markupsafe\" },\n]\n\n[package.dependencies]\nname = \"ignored\"\n\n[[package]]\nname = \"typing_extensions\"\nversion = \"4.8.0\"\n";
+        assert_eq!(
+            pinned_packages("uv.lock", lockfile),
+            vec![
+                PinnedPackage::new("jinja2", "2.11.2"),
+                PinnedPackage::new("typing-extensions", "4.8.0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_new_pins() {
+        let current = vec![
+            PinnedPackage::new("django", "4.2.1"),
+            PinnedPackage::new("requests", "2.31.0"),
+        ];
+        let committed = vec![
+            PinnedPackage::new("django", "4.2.0"),
+            PinnedPackage::new("requests", "2.31.0"),
+        ];
+        assert_eq!(
+            new_pins(&current, Some(&committed)),
+            vec![PinnedPackage::new("django", "4.2.1")]
+        );
+        assert_eq!(new_pins(&current, None), current);
+        assert_eq!(
+            requirements_for(&current),
+            "django==4.2.1\nrequests==2.31.0\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_pip_audit_json() {
+        let output = r#"{"dependencies": [
+            {"name": "jinja2", "version": "2.11.2", "vulns": [
+                {"id": "PYSEC-2021-66", "fix_versions": ["2.11.3"], "aliases": ["CVE-2020-28493"]}
+            ]},
+            {"name": "requests", "version": "2.31.0", "vulns": []},
+            {"name": "local-pkg", "skip_reason": "not on PyPI"}
+        ], "fixes": []}"#;
+        assert_eq!(
+            parse_pip_audit_json(output).unwrap(),
+            vec![Vulnerability {
+                package: PinnedPackage::new("jinja2", "2.11.2"),
+                id: "PYSEC-2021-66".to_string(),
+                fix_versions: vec!["2.11.3".to_string()],
+            }]
+        );
+
+        // Older pip-audit prints the dependency list on its own
+        let legacy = r#"[{"name": "Flask", "version": "0.12", "vulns": [{"id": "PYSEC-2018-66", "fix_versions": []}]}]"#;
+        let vulnerabilities = parse_pip_audit_json(legacy).unwrap();
+        assert_eq!(
+            vulnerabilities[0].package,
+            PinnedPackage::new("flask", "0.12")
+        );
+        assert!(vulnerabilities[0].fix_versions.is_empty());
+
+        assert!(parse_pip_audit_json("pip-audit: error").is_none());
+    }
+}
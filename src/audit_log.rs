@@ -0,0 +1,181 @@
+use crate::state::StateDir;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Size at which the log is rotated to `ai-audit.1.jsonl`
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Characters of the reply kept in a record
+const RESPONSE_SUMMARY_CHARS: usize = 200;
+
+/// One AI request as recorded in the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    pub provider: String,
+    pub model: String,
+    /// Analysis type: exclusion, lint or test
+    pub analysis: String,
+    /// First 16 hex digits of the SHA-256 of the prompt as sent (after redaction)
+    pub prompt_hash: String,
+    pub prompt_chars: usize,
+    pub latency_ms: u64,
+    /// Decision fields of the reply (e.g. `should_exclude_test=true`), or the error
+    pub decision: String,
+    /// Start of the reply
+    pub response: String,
+}
+
+impl AuditRecord {
+    /// Hash identifying a prompt without storing its content
+    pub fn prompt_hash(prompt: &str) -> String {
+        let digest = format!("{:x}", Sha256::digest(prompt.as_bytes()));
+        digest[..16].to_string()
+    }
+
+    /// Summarize a reply: its top-level boolean decisions, or the number of batched analyses
+    pub fn summarize_decision(response: &str) -> String {
+        let Ok(value) =
+            serde_json::from_str::<serde_json::Value>(crate::cerebras::extract_json(response))
+        else {
+            return "unparseable response".to_string();
+        };
+        if let Some(analyses) = value.get("analyses").and_then(|a| a.as_array()) {
+            return format!("{} batched analyses", analyses.len());
+        }
+        let decisions: Vec<String> = value
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, value)| Some(format!("{key}={}", value.as_bool()?)))
+            .collect();
+        decisions.join(", ")
+    }
+
+    /// Start of a reply, on one line
+    pub fn summarize_response(response: &str) -> String {
+        response
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .chars()
+            .take(RESPONSE_SUMMARY_CHARS)
+            .collect()
+    }
+}
+
+/// Opt-in record of every AI request, kept in the workspace's state directory for
+/// compliance review (`ai audit`)
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+    max_bytes: u64,
+}
+
+impl AuditLog {
+    /// The audit log of a workspace
+    pub fn for_workspace(workspace_dir: &Path) -> Result<Self> {
+        let path = StateDir::for_workspace(workspace_dir)?.file("ai-audit.jsonl");
+        Ok(Self::at(path, MAX_LOG_BYTES))
+    }
+
+    fn at(path: PathBuf, max_bytes: u64) -> Self {
+        Self { path, max_bytes }
+    }
+
+    fn rotated_path(&self) -> PathBuf {
+        self.path.with_extension("1.jsonl")
+    }
+
+    /// Append a record, rotating the log once it exceeds its size limit
+    pub fn record(&self, record: &AuditRecord) -> Result<()> {
+        if fs::metadata(&self.path).is_ok_and(|metadata| metadata.len() >= self.max_bytes) {
+            fs::rename(&self.path, self.rotated_path()).context("Failed to rotate AI audit log")?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("Failed to open AI audit log")?;
+        writeln!(file, "{}", serde_json::to_string(record)?).context("Failed to write AI audit log")
+    }
+
+    /// The most recent `limit` records, oldest first
+    pub fn recent(&self, limit: usize) -> Vec<AuditRecord> {
+        let records: Vec<AuditRecord> = [self.rotated_path(), self.path.clone()]
+            .iter()
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .flat_map(|content| {
+                content
+                    .lines()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        records[records.len().saturating_sub(limit)..].to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(decision: &str) -> AuditRecord {
+        AuditRecord {
+            timestamp: Utc::now(),
+            provider: "Cerebras".to_string(),
+            model: "qwen-3-coder-480b".to_string(),
+            analysis: "lint".to_string(),
+            prompt_hash: AuditRecord::prompt_hash("prompt"),
+            prompt_chars: 6,
+            latency_ms: 120,
+            decision: decision.to_string(),
+            response: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_record_rotate_and_read_recent() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = AuditLog::at(temp_dir.path().join("ai-audit.jsonl"), 300);
+
+        for index in 0..5 {
+            log.record(&record(&format!("call {index}"))).unwrap();
+        }
+        assert!(temp_dir.path().join("ai-audit.1.jsonl").exists());
+
+        let recent = log.recent(3);
+        let decisions: Vec<&str> = recent.iter().map(|r| r.decision.as_str()).collect();
+        assert_eq!(decisions, ["call 2", "call 3", "call 4"]);
+        assert_eq!(recent[0].prompt_hash.len(), 16);
+    }
+
+    #[test]
+    fn test_summaries() {
+        assert_eq!(
+            AuditRecord::summarize_decision(
+                r#"{"has_real_issues": false, "issue_count": 2, "reasoning": "style"}"#
+            ),
+            "has_real_issues=false"
+        );
+        assert_eq!(
+            AuditRecord::summarize_decision(r#"{"analyses": [{}, {}]}"#),
+            "2 batched analyses"
+        );
+        assert_eq!(
+            AuditRecord::summarize_decision("oops"),
+            "unparseable response"
+        );
+        assert_eq!(
+            AuditRecord::summarize_response("{\n  \"a\":   1\n}"),
+            "{ \"a\": 1 }"
+        );
+    }
+}
@@ -1,13 +1,46 @@
 use anyhow::{Context, Result};
-use std::path::Path;
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus};
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use crate::cerebras::{CerebrasConfig, SmartExclusionAnalyzer};
-use crate::discovery::PythonProject;
-use crate::locking::LockGuard;
+use crate::audit;
+use crate::automation_report::{AiAnalysisReport, AutomationReport, RunTrace, StepReport};
+use crate::backup::FileBackup;
+use crate::cerebras::{CerebrasConfig, LintAnalysis, SmartExclusionAnalyzer};
+use crate::checks;
+use crate::discovery::{
+    Framework, PythonFormatter, PythonProject, PythonSourceKind, PythonTester, PythonTypeChecker,
+    SearchLimits,
+};
+use crate::history::History;
+use crate::lint::{
+    filter_ignored_rules, parse_findings, render_ruff_json, severity_summary, LintFinding,
+    LintSeverity,
+};
+use crate::locking::{CooldownScope, LockGuard, LockStatus, ProcessLock};
+use crate::messages::{self, OutputStyle};
+use crate::mutation::{self, MutationTool, SurvivingMutant, MAX_REPORTED_MUTANTS};
+use crate::path_scope::PathScope;
+use crate::preview::{FilePreview, OperationPreview};
 use crate::protocol::HookInput;
-use crate::GuardrailsChecker;
+use crate::pytest::{PytestResults, TestCase};
+use crate::removal::{self, RemovedModule};
+use crate::result_cache::{cache_key, ResultCache};
+use crate::session_edits::SessionEdits;
+use crate::snapshot::{self, AutoUpdateSnapshots, SnapshotPlugin};
+use crate::state::StateDir;
+use crate::suppressions::Suppressions;
+use crate::test_map::TestFileMap;
+use crate::testgen;
+use crate::toolchain::{toolchain_for, LanguageToolchain, ToolCommand};
+use crate::{
+    AdviceFinding, CheckSeverity, DocstringConfig, FindingBehavior, FindingCategory,
+    GuardrailsChecker, GuardrailsError, ImportHygieneConfig, SeverityConfig,
+};
 
 /// Output from running a command including exit status and captured output
 #[derive(Debug)]
@@ -17,6 +50,15 @@ pub struct CommandOutput {
     pub stderr: String,
 }
 
+/// What a tool that rewrites a file in place did to it
+#[derive(Debug)]
+enum Rewrite {
+    Unchanged,
+    Changed,
+    /// The tool broke the file and it was restored; the failure to report
+    RolledBack(AutomationResult),
+}
+
 /// Configuration for automation behavior
 #[derive(Debug, Clone)]
 pub struct AutomationConfig {
@@ -26,6 +68,94 @@ pub struct AutomationConfig {
     pub test_cooldown_seconds: u64,
     pub lint_timeout_seconds: u64,
     pub test_timeout_seconds: u64,
+    /// Overrides the nox session / tox env name for session-based testers
+    pub test_session: Option<String>,
+    /// Run doctests found in the edited module as part of smart-test
+    pub test_doctests: bool,
+    /// Have AI test analysis evaluate property coverage for Hypothesis tests
+    pub test_property_analysis: bool,
+    /// Select tests referencing the functions/classes changed since HEAD with `-k`
+    pub test_changed_functions_only: bool,
+    /// Warn about assertion-free tests, catch-all exception checks and sleeps
+    pub test_smells: bool,
+    /// Packages whose edited modules must come with test edits in the same session
+    pub test_require_updates: Vec<String>,
+    /// Whether snapshot-only failures are updated automatically, left to the user or asked about
+    pub test_auto_update_snapshots: AutoUpdateSnapshots,
+    /// Fail passing runs when the edited module's pytest-cov coverage is below this
+    pub test_min_coverage_percent: Option<f64>,
+    /// Block when a new or modified function's cyclomatic complexity exceeds this
+    pub lint_max_complexity: Option<u32>,
+    /// Report public functions/classes missing docstrings
+    pub lint_docstrings: DocstringConfig,
+    /// Compiled `lint.docstrings.exclude`
+    pub lint_docstrings_exclude: PathScope,
+    /// Report wildcard, banned and circular imports
+    pub lint_imports: ImportHygieneConfig,
+    /// Identical lint failures in one session before escalating (0 disables)
+    pub lint_escalate_after: u32,
+    /// Identical test failures in one session before escalating (0 disables)
+    pub test_escalate_after: u32,
+    /// Relay test runner output to stderr while it runs
+    pub test_stream_output: bool,
+    /// Linter rules whose findings never fail the lint check
+    pub lint_ignore_rules: Vec<String>,
+    /// Apply the linter's safe auto-fixes before checking
+    pub lint_autofix: bool,
+    /// Let auto-fix apply ruff's unsafe fixes too
+    pub lint_unsafe_fixes: bool,
+    /// Format edited files before linting
+    pub format_enabled: bool,
+    /// Formatter to use; the first one installed when unset
+    pub format_tool: Option<PythonFormatter>,
+    /// Sort imports (isort or ruff's isort rules) before formatting
+    pub format_sort_imports: bool,
+    /// Run a type checker alongside the linter
+    pub typecheck_enabled: bool,
+    /// Type checker to use; the first one installed when unset
+    pub typecheck_tool: Option<PythonTypeChecker>,
+    /// Arguments appended to the Python linter invocation
+    pub lint_extra_args: Vec<String>,
+    /// Arguments appended to the Python test runner invocation
+    pub test_extra_args: Vec<String>,
+    /// Write a JUnit XML file for each pytest run (relative to the project root)
+    pub test_junit_xml: Option<PathBuf>,
+    /// Declared source → test file mappings, consulted before the test file search
+    pub test_map: TestFileMap,
+    /// Whether the lint cooldown covers the project or only the edited file
+    pub lint_cooldown_scope: CooldownScope,
+    /// Whether the test cooldown covers the project or only the edited file
+    pub test_cooldown_scope: CooldownScope,
+    /// Seconds to wait for an in-flight lint run instead of skipping (0 skips)
+    pub lint_lock_wait_seconds: u64,
+    /// Seconds to wait for an in-flight test run instead of skipping (0 skips)
+    pub test_lock_wait_seconds: u64,
+    /// Audit new pins in edited requirements/lock files for known vulnerabilities
+    pub lint_dependency_audit: bool,
+    /// Verify the uv/poetry lockfile still matches an edited pyproject.toml
+    pub lint_lockfile_check: bool,
+    /// Run mutation testing spot checks in smart-mutate
+    pub mutate_enabled: bool,
+    /// Mutation testing tool; the first one installed when unset
+    pub mutate_tool: Option<MutationTool>,
+    /// Time budget for one mutation run
+    pub mutate_timeout_seconds: u64,
+    /// Wall-clock budget for one lint hook invocation across all of its steps
+    pub total_budget_seconds: Option<u64>,
+    /// Reuse the verdict of the last passing lint run for a file re-saved unchanged
+    pub cache_results: bool,
+    /// Findings delivered as non-blocking advice instead of interrupting Claude
+    pub advice: Vec<AdviceFinding>,
+    /// Behavior (block, warn, advice, silent) per category of findings
+    pub severity: SeverityConfig,
+    /// Skipped directories and depth limit for recursive test file searches
+    pub search: SearchLimits,
+    /// Files smart-lint runs for
+    pub lint_scope: PathScope,
+    /// Files smart-test runs for
+    pub test_scope: PathScope,
+    /// Files smart-mutate runs for
+    pub mutate_scope: PathScope,
 }
 
 impl Default for AutomationConfig {
@@ -37,19 +167,332 @@ impl Default for AutomationConfig {
             test_cooldown_seconds: 2,
             lint_timeout_seconds: 20,
             test_timeout_seconds: 20,
+            test_session: None,
+            test_doctests: false,
+            test_property_analysis: true,
+            test_changed_functions_only: false,
+            test_smells: true,
+            test_require_updates: vec![],
+            test_auto_update_snapshots: AutoUpdateSnapshots::default(),
+            test_min_coverage_percent: None,
+            lint_max_complexity: None,
+            lint_docstrings: DocstringConfig::default(),
+            lint_docstrings_exclude: PathScope::default(),
+            lint_imports: ImportHygieneConfig::default(),
+            lint_escalate_after: 3,
+            test_escalate_after: 3,
+            test_stream_output: false,
+            lint_ignore_rules: vec![],
+            lint_autofix: true,
+            lint_unsafe_fixes: false,
+            format_enabled: true,
+            format_tool: None,
+            format_sort_imports: true,
+            typecheck_enabled: false,
+            typecheck_tool: None,
+            lint_extra_args: vec![],
+            test_extra_args: vec![],
+            test_junit_xml: None,
+            test_map: TestFileMap::default(),
+            lint_cooldown_scope: CooldownScope::Project,
+            test_cooldown_scope: CooldownScope::Project,
+            lint_lock_wait_seconds: 0,
+            test_lock_wait_seconds: 0,
+            lint_dependency_audit: true,
+            lint_lockfile_check: true,
+            mutate_enabled: false,
+            mutate_tool: None,
+            mutate_timeout_seconds: 60,
+            total_budget_seconds: None,
+            cache_results: true,
+            advice: Vec::new(),
+            severity: SeverityConfig::default(),
+            search: SearchLimits::default(),
+            lint_scope: PathScope::default(),
+            test_scope: PathScope::default(),
+            mutate_scope: PathScope::default(),
+        }
+    }
+}
+
+impl AutomationConfig {
+    /// This config with the settings that differ between `base` and `changed` (both built
+    /// from YAML, before and after merging `overrides`) taken from `changed`
+    fn with_changes(&self, base: &AutomationConfig, changed: AutomationConfig) -> Self {
+        let mut merged = self.clone();
+        macro_rules! take_changed {
+            ($($field:ident),* $(,)?) => {
+                // Destructuring fails to compile when a field is missing from the list
+                let AutomationConfig { $($field),* } = changed;
+                $(
+                    if $field != base.$field {
+                        merged.$field = $field;
+                    }
+                )*
+            };
         }
+        take_changed!(
+            lint_enabled,
+            test_enabled,
+            lint_cooldown_seconds,
+            test_cooldown_seconds,
+            lint_timeout_seconds,
+            test_timeout_seconds,
+            test_session,
+            test_doctests,
+            test_property_analysis,
+            test_changed_functions_only,
+            test_smells,
+            test_require_updates,
+            test_auto_update_snapshots,
+            test_min_coverage_percent,
+            lint_max_complexity,
+            lint_docstrings,
+            lint_docstrings_exclude,
+            lint_imports,
+            lint_escalate_after,
+            test_escalate_after,
+            test_stream_output,
+            lint_ignore_rules,
+            lint_autofix,
+            lint_unsafe_fixes,
+            format_enabled,
+            format_tool,
+            format_sort_imports,
+            typecheck_enabled,
+            typecheck_tool,
+            lint_extra_args,
+            test_extra_args,
+            test_junit_xml,
+            test_map,
+            lint_cooldown_scope,
+            test_cooldown_scope,
+            lint_lock_wait_seconds,
+            test_lock_wait_seconds,
+            lint_dependency_audit,
+            lint_lockfile_check,
+            mutate_enabled,
+            mutate_tool,
+            mutate_timeout_seconds,
+            total_budget_seconds,
+            cache_results,
+            advice,
+            severity,
+            search,
+            lint_scope,
+            test_scope,
+            mutate_scope,
+        );
+        merged
     }
 }
 
 /// Main automation orchestrator
+///
+/// Its methods return `anyhow::Result`: a run chains many tools and providers, whose
+/// failures are wrapped with context. Failures with a category carry a
+/// [`GuardrailsError`] to `downcast_ref` to.
 pub struct AutomationRunner {
     config: AutomationConfig,
     checker: GuardrailsChecker,
     analyzer: SmartExclusionAnalyzer,
+    /// Steps, findings and analyses of the report being built
+    trace: Mutex<RunTrace>,
+    /// Session working directory (the hook's `cwd`), relative paths resolve against it
+    working_dir: PathBuf,
+}
+
+/// Minimum gap between relayed output lines; lines arriving faster are counted, not shown
+const STREAM_MIN_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Most failed tests given a reproduction command in a failure message
+const MAX_REPRODUCE_COMMANDS: usize = 10;
+
+/// Project files configuring the linters, formatters and test runners; a cached verdict
+/// is dropped when any of them changes
+const TOOL_CONFIG_FILES: &[&str] = &[
+    "pyproject.toml",
+    "setup.cfg",
+    "tox.ini",
+    "ruff.toml",
+    ".ruff.toml",
+    ".flake8",
+    ".pylintrc",
+    ".isort.cfg",
+    "mypy.ini",
+    "pytest.ini",
+    "conftest.py",
+];
+
+/// Relays command output to stderr while it runs, rate-limited so fast output
+/// doesn't flood the terminal
+struct OutputRelay {
+    min_interval: Duration,
+    last_line: Option<Instant>,
+    skipped: usize,
+}
+
+impl OutputRelay {
+    fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            last_line: None,
+            skipped: 0,
+        }
+    }
+
+    /// Relay a line, or count it as skipped when it arrives within the interval
+    fn line(&mut self, line: &str) -> bool {
+        let now = Instant::now();
+        if self
+            .last_line
+            .is_some_and(|last| now.duration_since(last) < self.min_interval)
+        {
+            self.skipped += 1;
+            return false;
+        }
+
+        self.flush_skipped();
+        eprintln!("{line}");
+        self.last_line = Some(now);
+        true
+    }
+
+    fn flush_skipped(&mut self) {
+        if self.skipped > 0 {
+            eprintln!("  … {} more lines", self.skipped);
+            self.skipped = 0;
+        }
+    }
+
+    /// Report lines still held back once the command has finished
+    fn finish(&mut self) {
+        self.flush_skipped();
+    }
+}
+
+/// Wall-clock budget for one hook invocation (`automation.total_budget_seconds`): step
+/// timeouts are clamped to what's left, and optional steps are skipped once it runs low
+struct TimeBudget {
+    started: Instant,
+    total: Option<Duration>,
+    skipped: Mutex<Vec<&'static str>>,
+}
+
+impl TimeBudget {
+    fn new(total_seconds: Option<u64>) -> Self {
+        Self {
+            started: Instant::now(),
+            total: total_seconds.map(Duration::from_secs),
+            skipped: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn unlimited() -> Self {
+        Self::new(None)
+    }
+
+    fn remaining(&self) -> Option<Duration> {
+        self.total
+            .map(|total| total.saturating_sub(self.started.elapsed()))
+    }
+
+    /// Whether an optional step still fits: the last fifth of the budget is kept for the
+    /// lint check itself. Skipped steps are recorded for the message
+    fn allows(&self, step: &'static str) -> bool {
+        let Some((total, remaining)) = self.total.zip(self.remaining()) else {
+            return true;
+        };
+        if remaining * 5 >= total {
+            return true;
+        }
+        log::debug!("Skipping {step}: {remaining:?} of the time budget left");
+        if let Ok(mut skipped) = self.skipped.lock() {
+            skipped.push(step);
+        }
+        false
+    }
+
+    /// Step timeout clamped to the rest of the budget (at least a second)
+    fn timeout(&self, step_seconds: u64) -> u64 {
+        match self.remaining() {
+            Some(remaining) => step_seconds.min(remaining.as_secs().max(1)),
+            None => step_seconds,
+        }
+    }
+
+    /// Note listing the optional steps skipped to stay within the budget
+    fn skipped_steps(&self) -> Vec<String> {
+        self.skipped
+            .lock()
+            .map(|skipped| skipped.iter().map(|step| step.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    fn skipped_section(&self) -> Option<String> {
+        let skipped = self.skipped.lock().ok()?;
+        let total = self.total?;
+        (!skipped.is_empty()).then(|| {
+            format!(
+                "⏱️ Skipped {} to stay within the {}s time budget (`automation.total_budget_seconds`).",
+                skipped.join(", "),
+                total.as_secs()
+            )
+        })
+    }
+}
+
+/// Persist the findings an AI lint analysis explicitly listed as false positives; a
+/// finding merely missing from its filtered output (paraphrased, summarised) is kept
+fn remember_false_positives(
+    suppressions: &mut Suppressions,
+    lint_output: &str,
+    analysis: &LintAnalysis,
+) {
+    if analysis.false_positives.is_empty() {
+        return;
+    }
+
+    let false_positives: Vec<LintFinding> = parse_findings(lint_output)
+        .into_iter()
+        .filter(|finding| {
+            analysis
+                .false_positives
+                .iter()
+                .any(|listed| listed.trim() == finding.header.trim())
+        })
+        .collect();
+    match suppressions.add(&false_positives) {
+        Ok(0) => {}
+        Ok(added) => log::debug!("Remembered {added} false-positive lint finding(s)"),
+        Err(e) => log::warn!("Could not save lint suppressions: {e}"),
+    }
+}
+
+/// Read a child pipe to the end on a background thread, relaying each line if requested
+fn spawn_output_reader<R: Read + Send + 'static>(
+    pipe: R,
+    relay: Option<Arc<Mutex<OutputRelay>>>,
+) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut captured = Vec::new();
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line).unwrap_or(0) > 0 {
+            if let Some(relay) = &relay {
+                if let Ok(mut relay) = relay.lock() {
+                    relay.line(String::from_utf8_lossy(&line).trim_end());
+                }
+            }
+            captured.append(&mut line);
+        }
+        String::from_utf8_lossy(&captured).to_string()
+    })
 }
 
 /// Result of running an automation command
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "outcome", content = "message", rename_all = "snake_case")]
 pub enum AutomationResult {
     /// No command found or file excluded - exit silently
     NoAction,
@@ -57,66 +500,161 @@ pub enum AutomationResult {
     Success(String),
     /// Command failed - show error message and exit 2
     Failure(String),
+    /// Informational notes only - passed to Claude as additional context on stdout,
+    /// exit 0 so it isn't interrupted
+    Advice(String),
     /// Should skip due to concurrency control
     Skipped,
 }
 
 impl AutomationRunner {
-    /// Create a new automation runner
-    pub fn new(config: AutomationConfig, checker: GuardrailsChecker) -> Self {
-        let cerebras_config = CerebrasConfig::default();
-        let analyzer = SmartExclusionAnalyzer::new(cerebras_config);
+    /// Create a new automation runner for a session working in `working_dir` (the hook
+    /// input's `cwd`, or the process's working directory outside hooks)
+    pub fn new(config: AutomationConfig, checker: GuardrailsChecker, working_dir: &Path) -> Self {
+        let cerebras_config = CerebrasConfig {
+            property_test_analysis: config.test_property_analysis,
+            ..CerebrasConfig::from_ai_config(&checker.config().ai)
+        };
+        let working_dir =
+            std::path::absolute(working_dir).unwrap_or_else(|_| working_dir.to_path_buf());
+        // Keyed like the CLI's `--dir` commands: the project containing the working directory
+        let workspace = PythonProject::discover(&working_dir)
+            .map_or_else(|_| working_dir.clone(), |project| project.root);
+        let analyzer = SmartExclusionAnalyzer::new(cerebras_config).with_workspace(&workspace);
 
         Self {
             config,
             checker,
             analyzer,
+            trace: Mutex::default(),
+            working_dir,
         }
     }
 
-    /// Handle smart-lint command from Claude Code hook
-    pub async fn handle_smart_lint(&self) -> Result<AutomationResult> {
+    /// Decoration style for this runner's messages (`output.style`)
+    pub fn output_style(&self) -> OutputStyle {
+        self.checker.config().output.style
+    }
+
+    /// Create a runner backed by a checker shared with other runners; the compiled
+    /// matchers are not rebuilt
+    pub fn with_shared_checker(
+        config: AutomationConfig,
+        checker: &Arc<GuardrailsChecker>,
+        working_dir: &Path,
+    ) -> Self {
+        Self::new(config, GuardrailsChecker::clone(checker), working_dir)
+    }
+
+    /// Handle a lint hook event (PostToolUse for an edit tool)
+    pub async fn handle_smart_lint(&self, hook_input: &HookInput) -> Result<AutomationReport> {
         if std::env::var("DEBUG").unwrap_or_default() == "1" {
             log::debug!("handle_smart_lint called");
         }
 
-        if !self.config.lint_enabled {
+        if !self.config.lint_enabled && !self.checker.has_automation_overrides() {
             log::debug!("Smart lint is disabled");
-            return Ok(AutomationResult::NoAction);
+            return Ok(AutomationReport::no_action("lint"));
         }
 
-        let hook_input = match HookInput::from_stdin() {
-            Ok(input) => input,
-            Err(_) => {
-                log::debug!("No input available on stdin");
-                return Ok(AutomationResult::NoAction);
-            }
-        };
+        if hook_input.tool_failed() {
+            log::debug!(
+                "{} call failed or was rejected, skipping",
+                hook_input.tool_name
+            );
+            return Ok(AutomationReport::no_action("lint"));
+        }
 
         if !hook_input.should_process() {
             log::debug!("Ignoring event type: {}", hook_input.hook_event_name);
-            return Ok(AutomationResult::NoAction);
+            return Ok(AutomationReport::no_action("lint"));
         }
 
         let file_path = match hook_input.file_path() {
             Some(path) => path,
             None => {
                 log::debug!("No file path found in JSON input");
-                return Ok(AutomationResult::NoAction);
+                return Ok(AutomationReport::no_action("lint"));
             }
         };
 
+        self.lint_report(&file_path, hook_input.session_id.as_deref())
+            .await
+    }
+
+    /// Lint one file, reporting the commands run, findings and AI analyses along with
+    /// the result
+    ///
+    /// The library entry point for running the lint hook without hook input. A runner
+    /// records one report at a time; concurrent runs need separate runners.
+    pub async fn run_lint_for_file(&self, file_path: &Path) -> Result<AutomationReport> {
+        self.lint_report(file_path, None).await
+    }
+
+    /// `run_lint_for_file` on behalf of a Claude Code session, whose failures escalate
+    /// and count toward its history
+    async fn lint_report(
+        &self,
+        file_path: &Path,
+        session_id: Option<&str>,
+    ) -> Result<AutomationReport> {
+        let started = self.start_report();
+        let result = self.lint_file(file_path, session_id).await?;
+        Ok(self.finish_report("lint", file_path, started, result))
+    }
+
+    /// Run the lint pipeline (exclusions, toolchain, lock, history) for one file
+    pub async fn lint_file(
+        &self,
+        file_path: &Path,
+        session_id: Option<&str>,
+    ) -> Result<AutomationResult> {
+        if let Some(runner) = self.overridden_for(file_path) {
+            let result = runner.lint_file_as_configured(file_path, session_id).await;
+            self.adopt_trace(runner);
+            return result;
+        }
+        self.lint_file_as_configured(file_path, session_id).await
+    }
+
+    /// `lint_file` with this runner's settings, whatever `overrides` say about the file
+    async fn lint_file_as_configured(
+        &self,
+        file_path: &Path,
+        session_id: Option<&str>,
+    ) -> Result<AutomationResult> {
+        if !self.config.lint_enabled
+            || !in_scope(&self.config.lint_scope, file_path, &self.working_dir)
+        {
+            return Ok(AutomationResult::NoAction);
+        }
+        // Findings are this run's alone, for the failure signature and report
+        self.record(|trace| trace.findings.clear());
+        let budget = TimeBudget::new(self.config.total_budget_seconds);
+
         if !file_path.exists() {
             log::debug!("File does not exist: {}", file_path.display());
             return Ok(AutomationResult::NoAction);
         }
 
         // Check if file should be excluded from linting
-        if self.checker.should_exclude_lint(&file_path)? {
-            log::debug!("File should be skipped: {}", file_path.display());
+        if let Some(reason) = self.checker.check_lint(file_path)?.reason() {
+            log::debug!("File should be skipped: {} ({reason})", file_path.display());
             return Ok(AutomationResult::NoAction);
         }
 
+        // Dependency metadata is audited and checked against its lockfile instead of linted
+        if audit::is_dependency_file(file_path) || audit::is_project_metadata(file_path) {
+            return self.check_dependency_file(file_path, session_id);
+        }
+
+        // Files in other languages go through their own toolchain
+        if let Some(toolchain) = toolchain_for(file_path) {
+            return self
+                .run_with_toolchain(toolchain.as_ref(), "lint", file_path, session_id)
+                .await;
+        }
+
         // Change to file's directory
         let file_dir = file_path
             .parent()
@@ -124,66 +662,454 @@ impl AutomationRunner {
             .to_path_buf();
 
         // Discover Python project
-        let project = PythonProject::discover(&file_dir)?;
+        let project = PythonProject::discover_cached(&file_dir)?;
         if std::env::var("DEBUG").unwrap_or_default() == "1" {
             log::debug!("Discovered Python project at: {}", project.root.display());
         }
 
+        if project.is_django_migration(file_path) {
+            log::debug!("Skipping Django migration: {}", file_path.display());
+            return Ok(AutomationResult::NoAction);
+        }
+
+        if let Some(result) = self.cached_verdict(&project, file_path) {
+            return Ok(result);
+        }
+
         // Try to acquire lock
-        let _guard =
-            match LockGuard::try_acquire(&project.root, "lint", self.config.lint_cooldown_seconds)?
-            {
-                Some(guard) => guard,
-                None => return Ok(AutomationResult::Skipped),
-            };
+        let _guard = match self.acquire_lock(&project.root, "lint", file_path)? {
+            Some(guard) => guard,
+            None => return Ok(AutomationResult::Skipped),
+        };
 
         // Find and run linter for the specific file
-        self.run_lint_command(&project, &file_path).await
+        let started = Instant::now();
+        let result = self.run_lint_command(&project, file_path, &budget).await?;
+        self.record(|trace| trace.skipped_steps = budget.skipped_steps());
+        let linter = project
+            .lint_command_for(file_path)
+            .map(|(program, _)| program);
+        let result = self.with_template("lint", file_path, &project.root, linter, result);
+        self.remember_verdict(&project, file_path, &result);
+        Ok(self.record_history(
+            &project.root,
+            "lint",
+            file_path,
+            session_id,
+            started,
+            result,
+        ))
+    }
+
+    /// Audit the pins an edit added to a requirements file or lockfile, or verify the
+    /// lockfile still matches an edited pyproject.toml
+    fn check_dependency_file(
+        &self,
+        file_path: &Path,
+        session_id: Option<&str>,
+    ) -> Result<AutomationResult> {
+        let metadata = audit::is_project_metadata(file_path);
+        let enabled = if metadata {
+            self.config.lint_lockfile_check
+        } else {
+            self.config.lint_dependency_audit
+        };
+        if !enabled {
+            log::debug!("Dependency check is disabled for {}", file_path.display());
+            return Ok(AutomationResult::NoAction);
+        }
+
+        let project = PythonProject::discover_cached(file_path.parent().unwrap_or(Path::new(".")))?;
+        let _guard = match self.acquire_lock(&project.root, "lint", file_path)? {
+            Some(guard) => guard,
+            None => return Ok(AutomationResult::Skipped),
+        };
+
+        let started = Instant::now();
+        let result = if metadata {
+            self.run_lockfile_check(&project)?
+        } else {
+            self.run_dependency_audit(&project, file_path)?
+        };
+        Ok(self.record_history(
+            &project.root,
+            "lint",
+            file_path,
+            session_id,
+            started,
+            result,
+        ))
+    }
+
+    /// Block when the project's uv/poetry lockfile no longer matches pyproject.toml
+    fn run_lockfile_check(&self, project: &PythonProject) -> Result<AutomationResult> {
+        let Some(check) = audit::lockfile_check(&project.root) else {
+            log::debug!("No lockfile with an available tool to check it");
+            return Ok(AutomationResult::NoAction);
+        };
+
+        log::debug!("Checking lockfile with {}", check.command.display());
+        let output = self.run_tool_command(
+            &check.command,
+            &project.root,
+            self.config.lint_timeout_seconds,
+            false,
+        )?;
+        if output.success {
+            return Ok(AutomationResult::Success(format!(
+                "👉 {} is in sync with pyproject.toml. Continue with your task.",
+                check.lockfile
+            )));
+        }
+
+        let details = format!("{}\n{}", output.stdout.trim(), output.stderr.trim());
+        Ok(AutomationResult::Failure(format!(
+            "⛔ LOCKFILE OUT OF SYNC: {} no longer matches pyproject.toml\n\n{}\n\n⛔ Run `{}` to update the lockfile before continuing",
+            check.lockfile,
+            details.trim(),
+            check.relock
+        )))
+    }
+
+    fn run_dependency_audit(
+        &self,
+        project: &PythonProject,
+        file_path: &Path,
+    ) -> Result<AutomationResult> {
+        let file_name = file_path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let content = std::fs::read_to_string(file_path)
+            .with_context(|| format!("Failed to read {}", file_path.display()))?;
+        let pins = audit::pinned_packages(&file_name, &content);
+        let new_pins = audit::new_pins(&pins, audit::committed_pins(file_path).as_deref());
+        if new_pins.is_empty() {
+            log::debug!("No new pins in {}", file_path.display());
+            return Ok(AutomationResult::NoAction);
+        }
+
+        // pip-audit reads requirements files only, so lockfile pins are written out as one
+        let requirements = std::env::temp_dir().join(format!(
+            "claude-python-guardrails-audit-{}.txt",
+            std::process::id()
+        ));
+        let Some((program, args)) = audit::audit_command(&project.environment, &requirements)
+        else {
+            log::debug!("pip-audit not found, skipping dependency audit");
+            return Ok(AutomationResult::NoAction);
+        };
+        std::fs::write(&requirements, audit::requirements_for(&new_pins))
+            .with_context(|| format!("Failed to write {}", requirements.display()))?;
+
+        log::debug!(
+            "Auditing {} new pin(s) in {}",
+            new_pins.len(),
+            file_path.display()
+        );
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let output = self.run_command_with_timeout(
+            &program,
+            &args,
+            &project.root,
+            self.config.lint_timeout_seconds,
+        );
+        let _ = std::fs::remove_file(&requirements);
+        let output = output?;
+
+        let Some(vulnerabilities) = audit::parse_pip_audit_json(&output.stdout) else {
+            // Network or resolver problems aren't the edit's fault
+            log::warn!(
+                "Dependency audit produced no report: {}",
+                output.stderr.trim()
+            );
+            return Ok(AutomationResult::NoAction);
+        };
+
+        if vulnerabilities.is_empty() {
+            return Ok(AutomationResult::Success(format!(
+                "👉 No known vulnerabilities in the {} new pin(s) of {}. Continue with your task.",
+                new_pins.len(),
+                file_name
+            )));
+        }
+
+        let mut message = format!("⛔ VULNERABLE DEPENDENCIES pinned in {file_name}:\n\n");
+        for vulnerability in &vulnerabilities {
+            let package = &vulnerability.package;
+            let fix = match vulnerability.fix_versions.first() {
+                Some(version) => format!("upgrade to {}=={version}", package.name),
+                None => "no fixed release yet; consider an alternative".to_string(),
+            };
+            message.push_str(&format!(
+                "  • {}=={}: {} ({fix})\n",
+                package.name, package.version, vulnerability.id
+            ));
+        }
+        message.push_str("\n⛔ Pin fixed versions before continuing");
+        Ok(AutomationResult::Failure(message))
     }
 
-    /// Handle smart-test command from Claude Code hook
-    pub async fn handle_smart_test(&self) -> Result<AutomationResult> {
+    /// Handle a test hook event (PostToolUse for an edit tool, or a Bash command that
+    /// removed or renamed modules)
+    pub async fn handle_smart_test(&self, hook_input: &HookInput) -> Result<AutomationReport> {
         if std::env::var("DEBUG").unwrap_or_default() == "1" {
             log::debug!("handle_smart_test called");
         }
 
-        if !self.config.test_enabled {
+        if !self.config.test_enabled && !self.checker.has_automation_overrides() {
             log::debug!("Smart test is disabled");
-            return Ok(AutomationResult::NoAction);
+            return Ok(AutomationReport::no_action("test"));
         }
 
-        let hook_input = match HookInput::from_stdin() {
-            Ok(input) => input,
-            Err(_) => {
-                log::debug!("No input available on stdin");
-                return Ok(AutomationResult::NoAction);
+        if hook_input.tool_failed() {
+            log::debug!(
+                "{} call failed or was rejected, skipping",
+                hook_input.tool_name
+            );
+            return Ok(AutomationReport::no_action("test"));
+        }
+
+        // `rm`/`mv` commands delete or rename modules without an edit tool
+        if hook_input.is_post_bash() {
+            let Some(command) = hook_input.command() else {
+                return Ok(AutomationReport::no_action("test"));
+            };
+            let cwd = hook_input.working_dir()?;
+            let mut result = AutomationResult::NoAction;
+            for removed in removal::removed_by_command(command, &cwd) {
+                // `mv` leaves no trace of the destination when it was renamed again; git
+                // knows about staged renames
+                let removed = match removed.renamed_to {
+                    Some(_) => removed,
+                    None => removal::git_removal(&removed.path).unwrap_or(removed),
+                };
+                let removed_result = self
+                    .test_removed_module(&removed, hook_input.session_id.as_deref())
+                    .await?;
+                result = match removed_result {
+                    AutomationResult::Failure(message) => result.with_failure_section(&message),
+                    AutomationResult::Success(message) => result.with_warning_section(&message),
+                    AutomationResult::Advice(message) => result.with_advice_section(&message),
+                    AutomationResult::NoAction | AutomationResult::Skipped => result,
+                };
             }
-        };
+            return Ok(AutomationReport::new("test", None, result));
+        }
 
         if !hook_input.should_process() {
             log::debug!("Ignoring event type: {}", hook_input.hook_event_name);
-            return Ok(AutomationResult::NoAction);
+            return Ok(AutomationReport::no_action("test"));
         }
 
         let file_path = match hook_input.file_path() {
             Some(path) => path,
             None => {
                 log::debug!("No file path found in JSON input");
-                return Ok(AutomationResult::NoAction);
+                return Ok(AutomationReport::no_action("test"));
+            }
+        };
+
+        self.record_session_edit(&file_path, hook_input.session_id.as_deref());
+        self.test_report(&file_path, hook_input.session_id.as_deref())
+            .await
+    }
+
+    /// Remember an edit for the tests-updated policy checked by the Stop hook
+    fn record_session_edit(&self, file_path: &Path, session_id: Option<&str>) {
+        if self.config.test_require_updates.is_empty() {
+            return;
+        }
+        let (Some(session_id), Some(dir)) = (session_id, file_path.parent()) else {
+            return;
+        };
+        let Ok(project) = PythonProject::discover_cached(dir) else {
+            return;
+        };
+        let recorded = SessionEdits::load(&project.root)
+            .and_then(|mut edits| edits.record(session_id, file_path));
+        if let Err(e) = recorded {
+            log::warn!("Failed to record edit of {}: {}", file_path.display(), e);
+        }
+    }
+
+    /// Handle the Stop hook: block finishing while modules in `test_require_updates`
+    /// packages were edited this session without their tests
+    pub async fn handle_stop(&self, hook_input: &HookInput) -> Result<AutomationResult> {
+        if self.config.test_require_updates.is_empty() {
+            return Ok(AutomationResult::NoAction);
+        }
+
+        // Claude is already continuing because of a Stop hook; blocking again could loop
+        if !hook_input.is_stop() || hook_input.stop_hook_active {
+            return Ok(AutomationResult::NoAction);
+        }
+        let Some(session_id) = hook_input.session_id.as_deref() else {
+            return Ok(AutomationResult::NoAction);
+        };
+
+        let project = PythonProject::discover_cached(&hook_input.working_dir()?)?;
+        self.check_tests_updated(&project.root, session_id).await
+    }
+
+    /// Modules in `test_require_updates` packages edited in a session whose tests weren't,
+    /// unless exclusions or the exclusion analysis say they need no tests
+    pub async fn check_tests_updated(
+        &self,
+        project_root: &Path,
+        session_id: &str,
+    ) -> Result<AutomationResult> {
+        let mut packages = globset::GlobSetBuilder::new();
+        for pattern in &self.config.test_require_updates {
+            match globset::Glob::new(pattern) {
+                Ok(glob) => {
+                    packages.add(glob);
+                }
+                Err(e) => log::warn!("Invalid require_updated_tests pattern {}: {}", pattern, e),
             }
+        }
+        let packages = packages.build()?;
+        let in_packages = |relative: &Path| {
+            packages.is_match(relative)
+                || self
+                    .config
+                    .test_require_updates
+                    .iter()
+                    .any(|package| relative.starts_with(package))
         };
 
+        let edits = SessionEdits::load(project_root)?;
+        let edited = edits.files(session_id);
+        let edited_tests: Vec<&PathBuf> = edited
+            .iter()
+            .filter(|file| removal::is_test_file(file))
+            .collect();
+
+        let mut missing = Vec::new();
+        for file in edited {
+            let relative = file.strip_prefix(project_root).unwrap_or(file);
+            if removal::is_test_file(file)
+                || !file.exists()
+                || PythonSourceKind::of(file) != Some(PythonSourceKind::Module)
+                || !in_packages(relative)
+                || self.checker.check_test(file)?.reason().is_some()
+            {
+                continue;
+            }
+
+            let test_file = self.find_test_file_for_source(file, project_root);
+            let module = testgen::module_path(file, project_root);
+            let tests_updated = test_file
+                .as_ref()
+                .is_some_and(|test_file| edited.contains(test_file))
+                || edited_tests
+                    .iter()
+                    .any(|test_file| !removal::import_lines(test_file, &module).is_empty());
+            if tests_updated {
+                continue;
+            }
+            let needs_no_tests = self
+                .analyzer
+                .analyze_file(file)
+                .await
+                .is_ok_and(|analysis| {
+                    analysis.should_exclude_test || analysis.should_exclude_general
+                });
+            if !needs_no_tests {
+                missing.push((relative.to_path_buf(), test_file));
+            }
+        }
+
+        if missing.is_empty() {
+            return Ok(AutomationResult::NoAction);
+        }
+        let mut message = String::from(
+            "⛔ TESTS NOT UPDATED:\n\nThese modules changed in this session but their tests didn't:\n",
+        );
+        for (file, test_file) in &missing {
+            let tests = match test_file {
+                Some(test_file) => format!(
+                    "tests: {}",
+                    test_file
+                        .strip_prefix(project_root)
+                        .unwrap_or(test_file)
+                        .display()
+                ),
+                None => "no test file yet".to_string(),
+            };
+            message.push_str(&format!("  • {} ({tests})\n", file.display()));
+        }
+        message.push_str("\n⛔ Update or add tests for these changes before finishing; modules that need no tests belong in exclude.python.test_skip");
+        Ok(AutomationResult::Failure(message))
+    }
+
+    /// Test one file, reporting the commands run, failed tests and AI analyses along
+    /// with the result (one report at a time per runner, like `run_lint_for_file`)
+    pub async fn run_test_for_file(&self, file_path: &Path) -> Result<AutomationReport> {
+        self.test_report(file_path, None).await
+    }
+
+    /// `run_test_for_file` on behalf of a Claude Code session
+    async fn test_report(
+        &self,
+        file_path: &Path,
+        session_id: Option<&str>,
+    ) -> Result<AutomationReport> {
+        let started = self.start_report();
+        let result = self.test_file(file_path, session_id).await?;
+        Ok(self.finish_report("test", file_path, started, result))
+    }
+
+    /// Run the test pipeline (exclusions, toolchain, lock, history) for one file
+    pub async fn test_file(
+        &self,
+        file_path: &Path,
+        session_id: Option<&str>,
+    ) -> Result<AutomationResult> {
+        if let Some(runner) = self.overridden_for(file_path) {
+            let result = runner.test_file_as_configured(file_path, session_id).await;
+            self.adopt_trace(runner);
+            return result;
+        }
+        self.test_file_as_configured(file_path, session_id).await
+    }
+
+    /// `test_file` with this runner's settings, whatever `overrides` say about the file
+    async fn test_file_as_configured(
+        &self,
+        file_path: &Path,
+        session_id: Option<&str>,
+    ) -> Result<AutomationResult> {
+        if !self.config.test_enabled
+            || !in_scope(&self.config.test_scope, file_path, &self.working_dir)
+        {
+            return Ok(AutomationResult::NoAction);
+        }
+        // Failed tests are this run's alone, for the failure signature and report
+        self.record(|trace| trace.failed_tests.clear());
+
         if !file_path.exists() {
+            if let Some(removed) = removal::git_removal(file_path) {
+                return self.test_removed_module(&removed, session_id).await;
+            }
             log::debug!("File does not exist: {}", file_path.display());
             return Ok(AutomationResult::NoAction);
         }
 
         // Check if file should be excluded from testing
-        if self.checker.should_exclude_test(&file_path)? {
-            log::debug!("File should be skipped: {}", file_path.display());
+        if let Some(reason) = self.checker.check_test(file_path)?.reason() {
+            log::debug!("File should be skipped: {} ({reason})", file_path.display());
             return Ok(AutomationResult::NoAction);
         }
 
+        // Files in other languages go through their own toolchain
+        if let Some(toolchain) = toolchain_for(file_path) {
+            return self
+                .run_with_toolchain(toolchain.as_ref(), "test", file_path, session_id)
+                .await;
+        }
+
         // Change to file's directory
         let file_dir = file_path
             .parent()
@@ -191,55 +1117,1416 @@ impl AutomationRunner {
             .to_path_buf();
 
         // Discover Python project
-        let project = PythonProject::discover(&file_dir)?;
+        let project = PythonProject::discover_cached(&file_dir)?;
         if std::env::var("DEBUG").unwrap_or_default() == "1" {
             log::debug!("Discovered Python project at: {}", project.root.display());
         }
 
+        if project.is_django_migration(file_path) {
+            log::debug!("Skipping Django migration: {}", file_path.display());
+            return Ok(AutomationResult::NoAction);
+        }
+
         // Try to acquire lock
-        let _guard =
-            match LockGuard::try_acquire(&project.root, "test", self.config.test_cooldown_seconds)?
-            {
-                Some(guard) => guard,
-                None => return Ok(AutomationResult::Skipped),
-            };
+        let _guard = match self.acquire_lock(&project.root, "test", file_path)? {
+            Some(guard) => guard,
+            None => return Ok(AutomationResult::Skipped),
+        };
 
         // Find and run test command for the specific file
-        self.run_test_command(&project, &file_path).await
+        let started = Instant::now();
+        let result = self.run_test_command(&project, file_path).await?;
+        let tester = project
+            .preferred_tester()
+            .map(|tester| tester.command().to_string());
+        let result = self.with_template("test", file_path, &project.root, tester, result);
+        Ok(self.record_history(
+            &project.root,
+            "test",
+            file_path,
+            session_id,
+            started,
+            result,
+        ))
     }
 
-    /// Run linting command for a specific file in the project
-    async fn run_lint_command(
+    /// Run the tests that covered a deleted or renamed module (and the renamed module's own
+    /// tests), and block on tests that still import it
+    pub async fn test_removed_module(
         &self,
-        project: &PythonProject,
-        source_file: &Path,
+        removed: &RemovedModule,
+        session_id: Option<&str>,
     ) -> Result<AutomationResult> {
-        let linter = match project.preferred_linter() {
-            Some(linter) => {
-                if std::env::var("DEBUG").unwrap_or_default() == "1" {
-                    log::debug!(
-                        "Found linter: {} (command: {})",
-                        linter.display_name(),
-                        linter.command()
-                    );
-                }
-                linter
-            }
-            None => {
-                log::debug!("No Python linter found in project");
-                return Ok(AutomationResult::NoAction);
+        if !self.config.test_enabled
+            || PythonSourceKind::of(&removed.path) != Some(PythonSourceKind::Module)
+        {
+            return Ok(AutomationResult::NoAction);
+        }
+        if let Some(reason) = self.checker.check_test(&removed.path)?.reason() {
+            log::debug!(
+                "File should be skipped: {} ({reason})",
+                removed.path.display()
+            );
+            return Ok(AutomationResult::NoAction);
+        }
+        let Some(dir) = removed.path.ancestors().skip(1).find(|dir| dir.is_dir()) else {
+            return Ok(AutomationResult::NoAction);
+        };
+
+        let mut result = match &removed.renamed_to {
+            Some(new_path) if new_path.exists() => {
+                Box::pin(self.test_file(new_path, session_id)).await?
             }
+            _ => AutomationResult::NoAction,
+        };
+
+        let project = PythonProject::discover_cached(dir)?;
+        let _guard = match self.acquire_lock(&project.root, "test", &removed.path)? {
+            Some(guard) => guard,
+            None => return Ok(AutomationResult::Skipped),
+        };
+        let started = Instant::now();
+
+        let module = testgen::module_path(&removed.path, &project.root);
+        let references = removal::stale_references(&project.root, &module);
+        let mut covering_tests: Vec<PathBuf> = self
+            .find_test_file_for_source(&removed.path, &project.root)
+            .filter(|test_file| test_file.exists())
+            .into_iter()
+            .collect();
+        for (test_file, _) in &references {
+            if !covering_tests.contains(test_file) {
+                covering_tests.push(test_file.clone());
+            }
+        }
+
+        let mut tests_failed = false;
+        for test_file in &covering_tests {
+            if let AutomationResult::Failure(message) =
+                self.run_unit_tests(&project, test_file).await?
+            {
+                tests_failed = true;
+                result = result.with_failure_section(&message);
+            }
+        }
+
+        let change = match &removed.renamed_to {
+            Some(new_path) => format!("was renamed to {}", new_path.display()),
+            None => "was deleted".to_string(),
+        };
+        if !references.is_empty() {
+            let listed: Vec<String> = references
+                .iter()
+                .map(|(test_file, line)| {
+                    let relative = test_file.strip_prefix(&project.root).unwrap_or(test_file);
+                    format!("  • {}:{line}", relative.display())
+                })
+                .collect();
+            result = result.with_failure_section(&format!(
+                "⛔ TESTS STILL IMPORT A REMOVED MODULE:\n\n{} {change} but tests still import `{module}`:\n{}\n\n⛔ Update or remove these imports before continuing",
+                removed.path.display(),
+                listed.join("\n")
+            ));
+        } else if !covering_tests.is_empty() && !tests_failed {
+            result = result.with_warning_section(&format!(
+                "👉 {} {change}; the tests that covered it still pass. Continue with your task.",
+                removed.path.display()
+            ));
+        }
+
+        Ok(self.record_history(
+            &project.root,
+            "test",
+            &removed.path,
+            session_id,
+            started,
+            result,
+        ))
+    }
+
+    /// Handle a mutate hook event (PostToolUse for an edit tool)
+    pub async fn handle_smart_mutate(&self, hook_input: &HookInput) -> Result<AutomationResult> {
+        if !self.config.mutate_enabled {
+            log::debug!("Smart mutate is disabled");
+            return Ok(AutomationResult::NoAction);
+        }
+
+        if !hook_input.should_process() {
+            log::debug!("Ignoring event type: {}", hook_input.hook_event_name);
+            return Ok(AutomationResult::NoAction);
+        }
+
+        let Some(file_path) = hook_input.file_path() else {
+            log::debug!("No file path found in JSON input");
+            return Ok(AutomationResult::NoAction);
+        };
+
+        self.mutate_file(&file_path, hook_input.session_id.as_deref())
+    }
+
+    /// Mutation-test a module against its test file within the time budget and report
+    /// the mutants in edited functions that the tests let survive
+    pub fn mutate_file(
+        &self,
+        file_path: &Path,
+        session_id: Option<&str>,
+    ) -> Result<AutomationResult> {
+        if !self.config.mutate_enabled
+            || !in_scope(&self.config.mutate_scope, file_path, &self.working_dir)
+            || !file_path.exists()
+            || PythonSourceKind::of(file_path) != Some(PythonSourceKind::Module)
+        {
+            return Ok(AutomationResult::NoAction);
+        }
+        if let Some(reason) = self.checker.check_test(file_path)?.reason() {
+            log::debug!("File should be skipped: {} ({reason})", file_path.display());
+            return Ok(AutomationResult::NoAction);
+        }
+        let Some(tool) = MutationTool::detect(self.config.mutate_tool) else {
+            log::debug!("Neither mutmut nor cosmic-ray is installed");
+            return Ok(AutomationResult::NoAction);
+        };
+
+        let project = PythonProject::discover_cached(file_path.parent().unwrap_or(Path::new(".")))?;
+        let Some(tester) = project.preferred_tester() else {
+            log::debug!("No Python tester found in project");
+            return Ok(AutomationResult::NoAction);
+        };
+        // Mutants are judged by the module's tests; test files themselves aren't mutated
+        let Some(test_file) = self
+            .find_test_file_for_source(file_path, &project.root)
+            .filter(|test_file| test_file != file_path)
+        else {
+            log::debug!("No test file found for: {}", file_path.display());
+            return Ok(AutomationResult::NoAction);
+        };
+
+        let _guard = match self.acquire_lock(&project.root, "mutate", file_path)? {
+            Some(guard) => guard,
+            None => return Ok(AutomationResult::Skipped),
+        };
+        let started = Instant::now();
+
+        let (test_command, test_args) = project.environment.command(
+            tester.command(),
+            tester.test_file_args(&test_file, &project.root),
+        );
+        let runner = std::iter::once(test_command)
+            .chain(test_args)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let (total, mut survivors) = match tool {
+            MutationTool::Mutmut => self.run_mutmut(&project, file_path, &runner)?,
+            MutationTool::CosmicRay => self.run_cosmic_ray(&project, file_path, &runner)?,
         };
+        let timed_out =
+            started.elapsed() >= Duration::from_secs(self.config.mutate_timeout_seconds);
 
-        // Only lint Python files (.py extension)
-        if source_file.extension().and_then(|ext| ext.to_str()) != Some("py") {
+        // Spot check: only mutants inside functions changed since HEAD count
+        if let (Some(ranges), Ok(source)) = (
+            checks::changed_line_ranges(file_path, &project.root),
+            std::fs::read_to_string(file_path),
+        ) {
+            let edited: Vec<_> = checks::analyze_complexity(&source)
+                .into_iter()
+                .filter(|function| function.overlaps(&ranges))
+                .collect();
+            survivors.retain(|mutant| {
+                mutant.line.is_none_or(|line| {
+                    edited
+                        .iter()
+                        .any(|function| (function.line..=function.end_line).contains(&line))
+                })
+            });
+        }
+
+        let result = mutation_result(
+            file_path,
+            total,
+            &survivors,
+            timed_out.then_some(self.config.mutate_timeout_seconds),
+        );
+        Ok(self.record_history(
+            &project.root,
+            "mutate",
+            file_path,
+            session_id,
+            started,
+            result,
+        ))
+    }
+
+    /// Run mutmut on one module with `runner` as the test command, then look up the
+    /// surviving mutants' diffs
+    fn run_mutmut(
+        &self,
+        project: &PythonProject,
+        source_file: &Path,
+        runner: &str,
+    ) -> Result<(Option<usize>, Vec<SurvivingMutant>)> {
+        let relative = source_file
+            .strip_prefix(&project.root)
+            .unwrap_or(source_file);
+        let mutmut = |args: Vec<String>, timeout_seconds: u64| {
+            let (command, args) = project.environment.command("mutmut", args);
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            self.run_command_with_timeout(&command, &args, &project.root, timeout_seconds)
+        };
+
+        // Results are cached in .mutmut-cache, so a run cut short still reports the
+        // mutants it got through
+        mutmut(
+            vec![
+                "run".to_string(),
+                "--paths-to-mutate".to_string(),
+                relative.to_string_lossy().into_owned(),
+                "--runner".to_string(),
+                runner.to_string(),
+            ],
+            self.config.mutate_timeout_seconds,
+        )?;
+        let results = mutmut(
+            vec!["results".to_string()],
+            self.config.test_timeout_seconds,
+        )?;
+
+        let survivors = mutation::parse_mutmut_survivors(&results.stdout)
+            .into_iter()
+            .take(MAX_REPORTED_MUTANTS * 4)
+            .map(|id| {
+                let diff = mutmut(
+                    vec!["show".to_string(), id.clone()],
+                    self.config.test_timeout_seconds,
+                )
+                .map(|output| output.stdout)
+                .unwrap_or_default();
+                mutation::parse_mutant_diff(&id, &diff)
+            })
+            .collect();
+        Ok((None, survivors))
+    }
+
+    /// Run a cosmic-ray session for one module with `runner` as the test command
+    fn run_cosmic_ray(
+        &self,
+        project: &PythonProject,
+        source_file: &Path,
+        runner: &str,
+    ) -> Result<(Option<usize>, Vec<SurvivingMutant>)> {
+        let relative = source_file
+            .strip_prefix(&project.root)
+            .unwrap_or(source_file);
+        let stem = std::env::temp_dir().join(format!(
+            "claude-python-guardrails-cosmic-ray-{}",
+            std::process::id()
+        ));
+        let config = stem.with_extension("toml");
+        let session = stem.with_extension("sqlite");
+        std::fs::write(
+            &config,
+            format!(
+                "[cosmic-ray]\nmodule-path = {:?}\ntimeout = 10.0\nexcluded-modules = []\ntest-command = {:?}\n\n[cosmic-ray.distributor]\nname = \"local\"\n",
+                relative.to_string_lossy(),
+                runner
+            ),
+        )?;
+
+        let config_arg = config.to_string_lossy().into_owned();
+        let session_arg = session.to_string_lossy().into_owned();
+        let run = |program: &str, args: &[&str], timeout_seconds: u64| {
+            let (command, args) = project
+                .environment
+                .command(program, args.iter().map(|arg| arg.to_string()).collect());
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            self.run_command_with_timeout(&command, &args, &project.root, timeout_seconds)
+        };
+        let budget = self.config.mutate_timeout_seconds;
+        let report = run("cosmic-ray", &["init", &config_arg, &session_arg], budget)
+            .and_then(|_| run("cosmic-ray", &["exec", &config_arg, &session_arg], budget))
+            .and_then(|_| {
+                run(
+                    "cr-report",
+                    &[&session_arg],
+                    self.config.test_timeout_seconds,
+                )
+            });
+        let _ = std::fs::remove_file(&config);
+        let _ = std::fs::remove_file(&session);
+
+        Ok(mutation::parse_cosmic_ray_report(&report?.stdout))
+    }
+
+    /// Run lint or test for a file handled by a non-Python toolchain, with the same
+    /// locking, history and AI analysis as the Python path
+    async fn run_with_toolchain(
+        &self,
+        toolchain: &dyn LanguageToolchain,
+        operation: &str,
+        file_path: &Path,
+        session_id: Option<&str>,
+    ) -> Result<AutomationResult> {
+        let Some(root) = toolchain.project_root(file_path) else {
             log::debug!(
-                "Skipping linting for non-Python file: {}",
-                source_file.display()
+                "No {:?} project found for: {}",
+                toolchain.language(),
+                file_path.display()
             );
             return Ok(AutomationResult::NoAction);
+        };
+
+        let _guard = match self.acquire_lock(&root, operation, file_path)? {
+            Some(guard) => guard,
+            None => return Ok(AutomationResult::Skipped),
+        };
+
+        let started = Instant::now();
+        let result = match operation {
+            "lint" => self.run_toolchain_lint(toolchain, &root, file_path).await?,
+            _ => {
+                self.run_toolchain_tests(toolchain, &root, file_path)
+                    .await?
+            }
+        };
+        Ok(self.record_history(&root, operation, file_path, session_id, started, result))
+    }
+
+    /// Format, auto-fix and lint a file with its toolchain
+    async fn run_toolchain_lint(
+        &self,
+        toolchain: &dyn LanguageToolchain,
+        root: &Path,
+        source_file: &Path,
+    ) -> Result<AutomationResult> {
+        let Some(lint) = toolchain.lint_command(root, source_file) else {
+            log::debug!("No {:?} linter found", toolchain.language());
+            return Ok(AutomationResult::NoAction);
+        };
+
+        let format_commands = toolchain.format_commands(root, source_file);
+        for command in &format_commands {
+            log::debug!("Formatting with {}", command.display());
+            // Don't fail on format errors - the lint run reports what's left
+            let _format_output =
+                self.run_tool_command(command, root, self.config.lint_timeout_seconds, false)?;
+        }
+
+        log::debug!("Running {} for: {}", lint.display(), source_file.display());
+        let output = self.run_tool_command(&lint, root, self.config.lint_timeout_seconds, false)?;
+
+        if output.success {
+            let message = if format_commands.is_empty() {
+                "👉 Lints pass. Continue with your task."
+            } else {
+                "✨ Formatted and lints verified. Continue with your task."
+            };
+            Ok(AutomationResult::Success(message.to_string()))
+        } else {
+            Ok(self
+                .lint_failure_result(&output, root, &TimeBudget::unlimited())
+                .await)
+        }
+    }
+
+    /// Run the toolchain's tests covering the edited file
+    async fn run_toolchain_tests(
+        &self,
+        toolchain: &dyn LanguageToolchain,
+        root: &Path,
+        source_file: &Path,
+    ) -> Result<AutomationResult> {
+        let Some(test) = toolchain.test_command(root, source_file) else {
+            log::debug!("No {:?} test runner found", toolchain.language());
+            return Ok(AutomationResult::NoAction);
+        };
+
+        log::debug!("Running {} for: {}", test.display(), source_file.display());
+        let output = self.run_tool_command(
+            &test,
+            root,
+            self.config.test_timeout_seconds,
+            self.config.test_stream_output,
+        )?;
+
+        Ok(self
+            .test_output_result(&output, root, source_file, None)
+            .await)
+    }
+
+    fn run_tool_command(
+        &self,
+        command: &ToolCommand,
+        working_dir: &Path,
+        timeout_seconds: u64,
+        stream: bool,
+    ) -> Result<CommandOutput> {
+        let args: Vec<&str> = command.args.iter().map(String::as_str).collect();
+        self.run_command(
+            &command.program,
+            &args,
+            working_dir,
+            timeout_seconds,
+            stream,
+            &[],
+        )
+    }
+
+    /// Take the operation's cooldown lock for the project (or just the file, with
+    /// `cooldown_scope: file`), waiting for an in-flight run when `lock.wait_seconds` is
+    /// set; `None` means the run should be skipped
+    fn acquire_lock(
+        &self,
+        project_root: &Path,
+        operation: &str,
+        file_path: &Path,
+    ) -> Result<Option<LockGuard>> {
+        let (scope, cooldown_seconds, wait_seconds) = match operation {
+            "lint" => (
+                self.config.lint_cooldown_scope,
+                self.config.lint_cooldown_seconds,
+                self.config.lint_lock_wait_seconds,
+            ),
+            _ => (
+                self.config.test_cooldown_scope,
+                self.config.test_cooldown_seconds,
+                self.config.test_lock_wait_seconds,
+            ),
+        };
+        Ok(LockGuard::try_acquire_scoped(
+            project_root,
+            operation,
+            scope,
+            file_path,
+            cooldown_seconds,
+            Duration::from_secs(wait_seconds),
+        )?)
+    }
+
+    /// Runner with the `automation` settings of the `overrides` blocks matching a file;
+    /// `None` when no block changes them
+    ///
+    /// Only the settings the blocks change are taken over; everything else keeps this
+    /// runner's config, which may have been built programmatically.
+    fn overridden_for(&self, file_path: &Path) -> Option<AutomationRunner> {
+        let automation = self.checker.automation_for(file_path)?;
+        let base = AutomationConfig::from(&self.checker.config().automation);
+        Some(Self::new(
+            self.config
+                .with_changes(&base, AutomationConfig::from(&automation)),
+            self.checker.clone(),
+            &self.working_dir,
+        ))
+    }
+
+    /// Take over what a runner recorded while running on this runner's behalf
+    fn adopt_trace(&self, runner: AutomationRunner) {
+        let trace = runner.trace.into_inner().unwrap_or_default();
+        self.record(|current| *current = trace);
+    }
+
+    /// Add to the report being built
+    fn record(&self, update: impl FnOnce(&mut RunTrace)) {
+        if let Ok(mut trace) = self.trace.lock() {
+            update(&mut trace);
+        }
+    }
+
+    /// Failure identity of the run just traced: failed test node ids for test runs, finding
+    /// fingerprints for lint runs; `None` when the run recorded neither
+    fn failure_signature(&self, operation: &str) -> Option<String> {
+        let trace = self.trace.lock().ok()?;
+        let mut ids: Vec<String> = match operation {
+            "lint" => trace
+                .findings
+                .iter()
+                .map(LintFinding::fingerprint)
+                .collect(),
+            "test" => trace.failed_tests.clone(),
+            _ => vec![],
+        };
+        ids.sort();
+        ids.dedup();
+        (!ids.is_empty()).then(|| ids.join("\n"))
+    }
+
+    fn start_report(&self) -> Instant {
+        self.record(|trace| *trace = RunTrace::default());
+        Instant::now()
+    }
+
+    fn finish_report(
+        &self,
+        operation: &str,
+        file_path: &Path,
+        started: Instant,
+        result: AutomationResult,
+    ) -> AutomationReport {
+        let trace = self
+            .trace
+            .lock()
+            .map(|mut trace| std::mem::take(&mut *trace))
+            .unwrap_or_default();
+        AutomationReport::from_trace(
+            operation,
+            Some(file_path.to_path_buf()),
+            result,
+            started.elapsed(),
+            trace,
+        )
+    }
+
+    /// Verdict of the last passing lint run when neither the file nor anything else the
+    /// run depended on changed since
+    ///
+    /// Test verdicts are never reused: they also depend on conftest.py files and on every
+    /// module the tests import, which a content key can't track.
+    fn cached_verdict(
+        &self,
+        project: &PythonProject,
+        file_path: &Path,
+    ) -> Option<AutomationResult> {
+        if !self.config.cache_results {
+            return None;
+        }
+        let operation = "lint";
+        let key = self.verdict_key(project, file_path)?;
+        let cache = ResultCache::load(&project.root)
+            .map_err(|e| log::debug!("Result cache unavailable: {e}"))
+            .ok()?;
+        let verdict = cache.lookup(operation, file_path, &key)?;
+        self.record(|trace| trace.cache_hit = true);
+        log::debug!(
+            "{} unchanged since its last passing {operation} run",
+            file_path.display()
+        );
+        let message = format!(
+            "♻️ Unchanged since the last passing {operation} run ({}), reusing its verdict: {}",
+            verdict.timestamp.format("%H:%M UTC"),
+            verdict.message
+        );
+        Some(if verdict.advice {
+            AutomationResult::Advice(message)
+        } else {
+            AutomationResult::Success(message)
+        })
+    }
+
+    /// Replace a lint/test result's message with the `output.templates` template for its
+    /// outcome, when one is set
+    fn with_template(
+        &self,
+        operation: &str,
+        file_path: &Path,
+        project_root: &Path,
+        tool: Option<String>,
+        result: AutomationResult,
+    ) -> AutomationResult {
+        let templates = &self.checker.config().output.templates;
+        let (Some(template), Some(message)) = (
+            templates.for_outcome(operation, result.is_failure()),
+            result.message(),
+        ) else {
+            return result;
+        };
+
+        let count = match operation {
+            "lint" => parse_findings(message).len(),
+            _ => PytestResults::parse(message).failures().count(),
+        };
+        // Tools resolved into a virtualenv are named by their executable
+        let tool = tool
+            .map(|tool| {
+                Path::new(&tool)
+                    .file_name()
+                    .map_or(tool.clone(), |name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_default();
+        let mut values = vec![
+            (
+                "file",
+                file_path
+                    .strip_prefix(project_root)
+                    .unwrap_or(file_path)
+                    .display()
+                    .to_string(),
+            ),
+            ("tool", tool),
+            ("operation", operation.to_string()),
+            ("count", count.to_string()),
+            ("message", message.trim().to_string()),
+        ];
+        values.extend(messages::message_parts(message));
+        let filled = messages::fill_template(template, &values);
+
+        match result {
+            AutomationResult::Success(_) => AutomationResult::Success(filled),
+            AutomationResult::Failure(_) => AutomationResult::Failure(filled),
+            AutomationResult::Advice(_) => AutomationResult::Advice(filled),
+            AutomationResult::NoAction | AutomationResult::Skipped => result,
+        }
+    }
+
+    /// Cache a passing lint verdict under the file's post-run key (formatting may have
+    /// changed it), or drop the file's verdict when the run didn't pass
+    fn remember_verdict(
+        &self,
+        project: &PythonProject,
+        file_path: &Path,
+        result: &AutomationResult,
+    ) {
+        let operation = "lint";
+        if !self.config.cache_results {
+            return;
+        }
+        let Ok(mut cache) = ResultCache::load(&project.root) else {
+            return;
+        };
+        let saved = match result {
+            AutomationResult::Success(message) | AutomationResult::Advice(message) => {
+                let advice = matches!(result, AutomationResult::Advice(_));
+                match self.verdict_key(project, file_path) {
+                    Some(key) => cache.store(operation, file_path, key, message, advice),
+                    None => Ok(()),
+                }
+            }
+            AutomationResult::Failure(_) => cache.forget(operation, file_path),
+            AutomationResult::NoAction | AutomationResult::Skipped => Ok(()),
+        };
+        if let Err(e) = saved {
+            log::warn!("Failed to update the result cache: {e}");
+        }
+    }
+
+    /// Key a lint verdict is cached under: the file's content plus the guardrails
+    /// settings, the project's tool config files and the tool binaries
+    fn verdict_key(&self, project: &PythonProject, file_path: &Path) -> Option<String> {
+        let content = std::fs::read(file_path).ok()?;
+        let settings = format!(
+            "{:?}{}",
+            self.config,
+            serde_json::to_string(self.checker.config()).ok()?
+        );
+
+        let mut tools = Vec::new();
+        tools.extend(project.preferred_linter().map(|linter| linter.command()));
+        tools.extend(self.formatter(project).map(|formatter| formatter.command()));
+        tools.extend(["isort", "mypy", "pyright"]);
+        let inputs: Vec<PathBuf> = TOOL_CONFIG_FILES
+            .iter()
+            .map(|name| project.root.join(name))
+            .collect();
+        // An upgraded tool reports a new version (and has a new mtime), which
+        // invalidates its verdicts
+        let mut tool_ids: String = tools
+            .iter()
+            .map(|tool| {
+                let path = project.environment.find_tool(tool);
+                let modified = path
+                    .as_ref()
+                    .and_then(|path| std::fs::metadata(path).ok()?.modified().ok());
+                format!("{tool}={path:?}@{modified:?};")
+            })
+            .collect();
+        for version in project.tool_versions() {
+            tool_ids.push_str(&format!("{}={};", version.tool, version.version));
+        }
+        let inputs: Vec<Vec<u8>> = inputs
+            .iter()
+            .map(|path| std::fs::read(path).unwrap_or_default())
+            .collect();
+
+        Some(cache_key(
+            [content.as_slice(), settings.as_bytes(), tool_ids.as_bytes()]
+                .into_iter()
+                .chain(inputs.iter().map(Vec::as_slice)),
+        ))
+    }
+
+    /// Remember the outcome so the next session can pick up outstanding failures, and
+    /// escalate when the same failure keeps recurring within this session
+    fn record_history(
+        &self,
+        project_root: &Path,
+        operation: &str,
+        file: &Path,
+        session_id: Option<&str>,
+        started: Instant,
+        result: AutomationResult,
+    ) -> AutomationResult {
+        let escalate_after = match operation {
+            "lint" => self.config.lint_escalate_after,
+            _ => self.config.test_escalate_after,
+        };
+        let mut history = match History::load(project_root) {
+            Ok(history) => history,
+            Err(e) => {
+                log::warn!("Failed to load {} history: {}", operation, e);
+                return result;
+            }
+        };
+        let signature = self.failure_signature(operation);
+        if let Err(e) = history.record(
+            operation,
+            file,
+            session_id,
+            &result,
+            started.elapsed(),
+            signature,
+        ) {
+            log::warn!("Failed to record {} history: {}", operation, e);
+            return result;
+        }
+
+        let repeats = match session_id {
+            Some(session_id) if result.is_failure() && escalate_after > 0 => {
+                history.repeated_failures(session_id, operation, file)
+            }
+            _ => return result,
+        };
+        if repeats < escalate_after as usize {
+            return result;
+        }
+
+        result.with_failure_section(&format!(
+            "🔁 REPEATED FAILURE: this {} failure in {} has come back unchanged {} times this session.\n\n  • Stop retrying the same fix; re-read the error and the surrounding code\n  • If the cause is outside your control (environment, flaky test, unclear requirement), stop and ask the user to intervene",
+            operation,
+            file.display(),
+            repeats
+        ))
+    }
+
+    /// Lint a file without formatting, auto-fixing or AI triage and return the findings
+    /// left after `ignore_rules`, with file paths resolved against the project root
+    ///
+    /// Used for CI output (`lint --format github`) where files must not be modified.
+    pub fn lint_findings(&self, file_path: &Path) -> Result<Vec<LintFinding>> {
+        if self.checker.should_exclude_lint(file_path)? {
+            return Ok(vec![]);
+        }
+
+        let (root, output) = match toolchain_for(file_path) {
+            Some(toolchain) => {
+                let Some(root) = toolchain.project_root(file_path) else {
+                    return Ok(vec![]);
+                };
+                let Some(lint) = toolchain.lint_command(&root, file_path) else {
+                    return Ok(vec![]);
+                };
+                let output =
+                    self.run_tool_command(&lint, &root, self.config.lint_timeout_seconds, false)?;
+                (root, output)
+            }
+            None => {
+                let file_dir = file_path.parent().unwrap_or_else(|| Path::new("."));
+                let project = PythonProject::discover_cached(file_dir)?;
+                if project.is_django_migration(file_path) {
+                    return Ok(vec![]);
+                }
+                let Some((program, mut args)) = project.lint_command_for(file_path) else {
+                    return Ok(vec![]);
+                };
+                if PythonSourceKind::of(file_path) == Some(PythonSourceKind::Module) {
+                    args.extend(self.config.lint_extra_args.iter().cloned());
+                }
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                let output = self.run_command_with_timeout(
+                    &program,
+                    &args,
+                    &project.root,
+                    self.config.lint_timeout_seconds,
+                )?;
+                (project.root, output)
+            }
+        };
+
+        if output.success {
+            return Ok(vec![]);
+        }
+        let stdout =
+            render_ruff_json(&output.stdout, &root).unwrap_or_else(|| output.stdout.clone());
+        Ok(parse_findings(&format!("{}\n{}", stdout, output.stderr))
+            .into_iter()
+            .filter(|finding| !finding.mentions_rule(&self.config.lint_ignore_rules))
+            .map(|mut finding| {
+                finding.file = finding
+                    .file
+                    .map(|file| root.join(file).to_string_lossy().to_string());
+                finding
+            })
+            .collect())
+    }
+
+    /// Describe what the lint and test hooks would do for a file without running any
+    /// tool: exclusions, commands, the mapped test file and lock/cooldown state
+    pub fn preview(&self, file_path: &Path) -> Result<FilePreview> {
+        let reason = |decision: crate::ExclusionDecision| decision.reason().map(|r| r.to_string());
+        let mut lint_reason = reason(self.checker.check_lint(file_path)?);
+        let mut test_reason = reason(self.checker.check_test(file_path)?);
+        let exclusion_reason = reason(self.checker.check(file_path)?);
+
+        let (root, formatter, lint_command, test_command, test_file, tool_versions) =
+            match toolchain_for(file_path) {
+                Some(toolchain) => {
+                    let root = toolchain.project_root(file_path);
+                    let commands = root.as_deref().map(|root| {
+                        (
+                            toolchain
+                                .format_commands(root, file_path)
+                                .iter()
+                                .map(ToolCommand::display)
+                                .collect::<Vec<_>>(),
+                            toolchain.lint_command(root, file_path),
+                            toolchain.test_command(root, file_path),
+                        )
+                    });
+                    let (formatters, lint, test) = commands.unwrap_or_default();
+                    (
+                        root,
+                        (!formatters.is_empty()).then(|| formatters.join(" && ")),
+                        lint.map(|command| command.display()),
+                        test.map(|command| command.display()),
+                        None,
+                        Vec::new(),
+                    )
+                }
+                None => {
+                    let project = PythonProject::discover_cached(
+                        file_path.parent().unwrap_or(Path::new(".")),
+                    )?;
+                    if project.is_django_migration(file_path) {
+                        lint_reason.get_or_insert_with(|| "Django migration".to_string());
+                        test_reason.get_or_insert_with(|| "Django migration".to_string());
+                    }
+                    let is_module =
+                        PythonSourceKind::of(file_path) == Some(PythonSourceKind::Module);
+
+                    let lint_command =
+                        project.lint_command_for(file_path).map(|(program, args)| {
+                            let extra = self.config.lint_extra_args.iter().filter(|_| is_module);
+                            std::iter::once(&program)
+                                .chain(&args)
+                                .chain(extra)
+                                .cloned()
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        });
+                    let test_file = self
+                        .find_test_file_for_source(file_path, &project.root)
+                        .or_else(|| django_app_tests(&project, file_path))
+                        .filter(|_| is_module);
+                    let tester =
+                        project
+                            .preferred_tester()
+                            .map(|tester| match &self.config.test_session {
+                                Some(session) => tester.with_session(session),
+                                None => tester.clone(),
+                            });
+                    let test_command = tester.zip(test_file.as_ref()).map(|(tester, test_file)| {
+                        let mut args = tester.test_file_args(test_file, &project.root);
+                        args.extend(self.config.test_extra_args.iter().cloned());
+                        let (program, args) = project.environment.command(tester.command(), args);
+                        std::iter::once(program)
+                            .chain(args)
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    });
+                    let formatter = self
+                        .formatter(&project)
+                        .filter(|_| {
+                            matches!(
+                                PythonSourceKind::of(file_path),
+                                Some(PythonSourceKind::Module | PythonSourceKind::Stub)
+                            )
+                        })
+                        .map(|formatter| formatter.command().to_string());
+                    let tool_versions = project.tool_versions().to_vec();
+                    (
+                        Some(project.root),
+                        formatter,
+                        lint_command,
+                        test_command,
+                        test_file,
+                        tool_versions,
+                    )
+                }
+            };
+
+        let lock_status = |operation: &str| -> Option<LockStatus> {
+            let root = root.as_deref()?;
+            let (scope, cooldown_seconds) = match operation {
+                "lint" => (
+                    self.config.lint_cooldown_scope,
+                    self.config.lint_cooldown_seconds,
+                ),
+                _ => (
+                    self.config.test_cooldown_scope,
+                    self.config.test_cooldown_seconds,
+                ),
+            };
+            let file = (scope == CooldownScope::File).then_some(file_path);
+            ProcessLock::for_file(root, operation, file, cooldown_seconds)
+                .and_then(|lock| lock.status())
+                .map_err(|e| log::debug!("No {operation} lock status: {e}"))
+                .ok()
+        };
+
+        Ok(FilePreview {
+            file: file_path.to_path_buf(),
+            lint: OperationPreview::new(
+                self.config.lint_enabled,
+                lint_reason,
+                lint_command,
+                lock_status("lint"),
+            ),
+            test: OperationPreview::new(
+                self.config.test_enabled,
+                test_reason,
+                test_command,
+                lock_status("test"),
+            ),
+            project_root: root,
+            excluded: exclusion_reason.is_some(),
+            exclusion_reason,
+            formatter,
+            test_file,
+            tool_versions,
+        })
+    }
+
+    /// Describe the project for a SessionStart hook: detected tools, test layout,
+    /// guardrails exclusions and failures left over from the last lint/test runs
+    pub fn session_context(&self, start_dir: &Path) -> Result<String> {
+        let project = PythonProject::discover_cached(start_dir)?;
+        let mut lines = vec![format!(
+            "🐍 Python project at {} ({:?})",
+            project.root.display(),
+            project.project_type
+        )];
+
+        let names = |tools: Vec<&'static str>| {
+            if tools.is_empty() {
+                "none detected".to_string()
+            } else {
+                tools.join(", ")
+            }
+        };
+        lines.push(format!(
+            "  • Linters: {}",
+            names(
+                project
+                    .available_linters
+                    .iter()
+                    .map(|l| l.command())
+                    .collect()
+            )
+        ));
+        lines.push(format!(
+            "  • Formatters: {}",
+            names(
+                project
+                    .available_formatters
+                    .iter()
+                    .map(|f| f.command())
+                    .collect()
+            )
+        ));
+        lines.push(format!(
+            "  • Python environment: {}",
+            project.environment.describe()
+        ));
+        let versions: Vec<&str> = project
+            .tool_versions()
+            .iter()
+            .map(|version| version.version.as_str())
+            .collect();
+        if !versions.is_empty() {
+            lines.push(format!("  • Tool versions: {}", versions.join("; ")));
+        }
+        if let Some(version) = project.python_version {
+            lines.push(format!("  • Target Python: {version}"));
+        }
+        if let Some(framework) = &project.framework {
+            lines.push(format!("  • Framework: {}", framework.describe()));
+        }
+        lines.push(format!(
+            "  • Test runner: {}",
+            project
+                .preferred_tester()
+                .map(|t| t.display_name())
+                .unwrap_or("none detected")
+        ));
+
+        let test_dirs: Vec<&str> = ["tests", "test"]
+            .into_iter()
+            .filter(|dir| project.root.join(dir).is_dir())
+            .collect();
+        let layout = if test_dirs.is_empty() {
+            "no tests/ directory (tests live next to sources)".to_string()
+        } else {
+            test_dirs
+                .iter()
+                .map(|dir| format!("{dir}/"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let conftest = if project.root.join("conftest.py").exists()
+            || test_dirs
+                .iter()
+                .any(|dir| project.root.join(dir).join("conftest.py").exists())
+        {
+            " with conftest.py"
+        } else {
+            ""
+        };
+        lines.push(format!("  • Test layout: {layout}{conftest}"));
+
+        let exclude = &self.checker.config().exclude;
+        if !exclude.patterns.is_empty() {
+            lines.push(format!(
+                "  • Guardrails excludes: {}",
+                exclude.patterns.join(", ")
+            ));
+        }
+        if !exclude.python.lint_skip.is_empty() {
+            lines.push(format!(
+                "  • Skipped by lint: {}",
+                exclude.python.lint_skip.join(", ")
+            ));
+        }
+        if !exclude.python.test_skip.is_empty() {
+            lines.push(format!(
+                "  • Skipped by tests: {}",
+                exclude.python.test_skip.join(", ")
+            ));
+        }
+
+        let history = History::load(&project.root)?;
+        for operation in ["lint", "test"] {
+            if let Some(entry) = history.last(operation).filter(|entry| !entry.success) {
+                lines.push(format!(
+                    "\n⛔ Outstanding {} failure in {} ({}):\n\n{}",
+                    operation,
+                    entry.file.display(),
+                    entry.timestamp.format("%Y-%m-%d %H:%M UTC"),
+                    entry.message.trim_end()
+                ));
+            }
+        }
+
+        Ok(lines.join("\n"))
+    }
+
+    /// Run linting command for a specific file in the project, followed by the complexity gate
+    async fn run_lint_command(
+        &self,
+        project: &PythonProject,
+        source_file: &Path,
+        budget: &TimeBudget,
+    ) -> Result<AutomationResult> {
+        // Insert a missing license header before formatting so lint sees the final file
+        let license_section = self.check_license_header(project, source_file);
+
+        let result = self.run_linter(project, source_file, budget).await?;
+        let mut result = self.as_advice(AdviceFinding::PassingLint, result);
+
+        if let Some((section, severity)) = license_section {
+            result = result.with_check_section(&section, severity);
+        }
+        if let Some(section) = self.check_complexity(project, source_file) {
+            result = result.with_failure_section(&section);
+        }
+        if let Some(section) = self.check_docstrings(project, source_file) {
+            result = result.with_check_section(&section, self.config.lint_docstrings.severity);
+        }
+        if let Some(section) = self.check_imports(project, source_file) {
+            result = result.with_check_section(&section, self.config.lint_imports.severity);
+        }
+        if let Some(section) = budget.skipped_section() {
+            result = self.with_note(result, AdviceFinding::SkippedSteps, &section);
+        }
+
+        Ok(result)
+    }
+
+    /// Report a result that consists only of `category` findings with the behavior
+    /// `automation.severity` assigns to it
+    fn with_severity(
+        &self,
+        category: FindingCategory,
+        result: AutomationResult,
+    ) -> AutomationResult {
+        let message = match result {
+            AutomationResult::Failure(message)
+            | AutomationResult::Success(message)
+            | AutomationResult::Advice(message) => message,
+            AutomationResult::NoAction | AutomationResult::Skipped => return result,
+        };
+        match self.config.severity.behavior(category) {
+            FindingBehavior::Block => AutomationResult::Failure(message),
+            FindingBehavior::Warn => AutomationResult::Success(message),
+            FindingBehavior::Advice => AutomationResult::Advice(message),
+            FindingBehavior::Silent => {
+                log::debug!("Dropping {category:?} findings (automation.severity: silent)");
+                AutomationResult::NoAction
+            }
+        }
+    }
+
+    /// Fold a section of `category` findings into the result with the behavior
+    /// `automation.severity` assigns to it
+    fn with_finding(
+        &self,
+        result: AutomationResult,
+        category: FindingCategory,
+        section: &str,
+    ) -> AutomationResult {
+        match self.config.severity.behavior(category) {
+            FindingBehavior::Block => result.with_failure_section(section),
+            FindingBehavior::Warn => result.with_warning_section(section),
+            FindingBehavior::Advice => result.with_advice_section(section),
+            FindingBehavior::Silent => result,
+        }
+    }
+
+    /// Deliver a passing result as advice when `finding` is listed in `automation.advice`
+    fn as_advice(&self, finding: AdviceFinding, result: AutomationResult) -> AutomationResult {
+        match result {
+            AutomationResult::Success(message) if self.config.advice.contains(&finding) => {
+                AutomationResult::Advice(message)
+            }
+            result => result,
+        }
+    }
+
+    /// Fold a non-blocking note into the result, as advice when `finding` is listed in
+    /// `automation.advice` and as a warning otherwise
+    fn with_note(
+        &self,
+        result: AutomationResult,
+        finding: AdviceFinding,
+        section: &str,
+    ) -> AutomationResult {
+        if self.config.advice.contains(&finding) {
+            result.with_advice_section(section)
+        } else {
+            result.with_warning_section(section)
+        }
+    }
+
+    /// Verify the file starts with the configured license header, inserting it when
+    /// `auto_insert` is set; inserted headers are reported as a warning
+    fn check_license_header(
+        &self,
+        project: &PythonProject,
+        source_file: &Path,
+    ) -> Option<(String, CheckSeverity)> {
+        let rules = self.checker.rules_for(source_file);
+        let config = rules.license_header.as_ref()?;
+        if !self
+            .checker
+            .license_header_covers(&config.globs, source_file, &project.root)
+        {
+            return None;
+        }
+
+        let source = std::fs::read_to_string(source_file).ok()?;
+        if checks::has_license_header(&source, &config.template) {
+            return None;
+        }
+
+        if config.auto_insert {
+            let year = chrono::Datelike::year(&chrono::Utc::now());
+            let updated = checks::insert_license_header(&source, &config.template, year);
+            match std::fs::write(source_file, updated) {
+                Ok(()) => {
+                    return Some((
+                        "📝 Inserted the required license header.".to_string(),
+                        CheckSeverity::Warn,
+                    ))
+                }
+                Err(e) => log::warn!("Failed to insert license header: {}", e),
+            }
+        }
+
+        Some((
+            format!(
+                "⛔ MISSING LICENSE HEADER:\n\n{} must start with:\n\n{}\n\n⛔ Add the license header before continuing",
+                source_file.display(),
+                config.template.trim_end()
+            ),
+            CheckSeverity::Block,
+        ))
+    }
+
+    /// Describe wildcard, banned and circular imports in the edited file
+    fn check_imports(&self, project: &PythonProject, source_file: &Path) -> Option<String> {
+        let config = &self.config.lint_imports;
+        if !config.enabled || PythonSourceKind::of(source_file) != Some(PythonSourceKind::Module) {
+            return None;
         }
 
+        let source = std::fs::read_to_string(source_file).ok()?;
+        let issues =
+            checks::import_issues(source_file, &source, &project.root, &config.banned_modules);
+        if issues.is_empty() {
+            return None;
+        }
+
+        let icon = match config.severity {
+            CheckSeverity::Block => "⛔",
+            CheckSeverity::Warn => "⚠️",
+            CheckSeverity::Advice => "💡",
+        };
+        let mut section = format!("{icon} IMPORT ISSUES:\n\n");
+        for issue in &issues {
+            section.push_str(&format!("  • line {}: {}\n", issue.line, issue.message));
+        }
+        section.push_str(&format!(
+            "\n{icon} Fix these imports{}",
+            match config.severity {
+                CheckSeverity::Block => " before continuing",
+                CheckSeverity::Warn | CheckSeverity::Advice => "",
+            }
+        ));
+        Some(section)
+    }
+
+    /// Describe public functions/classes in the file that are missing docstrings
+    fn check_docstrings(&self, project: &PythonProject, source_file: &Path) -> Option<String> {
+        let config = &self.config.lint_docstrings;
+        if !config.enabled || PythonSourceKind::of(source_file) != Some(PythonSourceKind::Module) {
+            return None;
+        }
+        // `exclude` is a skip list matched relative to the project root
+        if !self
+            .config
+            .lint_docstrings_exclude
+            .allows(source_file, &project.root)
+        {
+            return None;
+        }
+
+        let source = std::fs::read_to_string(source_file).ok()?;
+        let missing = checks::missing_docstrings(&source);
+        if missing.is_empty() {
+            return None;
+        }
+
+        let icon = match config.severity {
+            CheckSeverity::Block => "⛔",
+            CheckSeverity::Warn => "⚠️",
+            CheckSeverity::Advice => "💡",
+        };
+        let mut section = format!("{icon} MISSING DOCSTRINGS:\n\n");
+        for item in &missing {
+            section.push_str(&format!(
+                "  • {} {} (line {})\n",
+                item.kind, item.name, item.line
+            ));
+        }
+        section.push_str(&format!(
+            "\n{icon} Document public functions and classes{}",
+            match config.severity {
+                CheckSeverity::Block => " before continuing",
+                CheckSeverity::Warn | CheckSeverity::Advice => "",
+            }
+        ));
+        Some(section)
+    }
+
+    /// Compute complexity of new/modified functions (radon if installed, otherwise the
+    /// built-in line-based estimate) and describe the ones over `lint_max_complexity`
+    fn check_complexity(&self, project: &PythonProject, source_file: &Path) -> Option<String> {
+        let threshold = self.config.lint_max_complexity?;
+        if PythonSourceKind::of(source_file) != Some(PythonSourceKind::Module) {
+            return None;
+        }
+
+        let radon_functions = if which::which("radon").is_ok() {
+            let file_path_str = source_file.to_string_lossy();
+            self.run_command_with_timeout(
+                "radon",
+                &["cc", "-j", &file_path_str],
+                &project.root,
+                self.config.lint_timeout_seconds,
+            )
+            .ok()
+            .filter(|output| output.success)
+            .and_then(|output| checks::parse_radon_output(&output.stdout))
+        } else {
+            None
+        };
+
+        let functions = match radon_functions {
+            Some(functions) => functions,
+            None => checks::analyze_complexity(&std::fs::read_to_string(source_file).ok()?),
+        };
+
+        let changed = checks::changed_line_ranges(source_file, &project.root);
+        let violations: Vec<_> = functions
+            .iter()
+            .filter(|function| function.complexity > threshold)
+            .filter(|function| {
+                changed
+                    .as_ref()
+                    .is_none_or(|ranges| function.overlaps(ranges))
+            })
+            .collect();
+
+        if violations.is_empty() {
+            return None;
+        }
+
+        let mut section = String::from("⛔ COMPLEXITY TOO HIGH:\n\n");
+        for function in violations {
+            section.push_str(&format!(
+                "  • {} (line {}): complexity {} (max {})\n",
+                function.name, function.line, function.complexity, threshold
+            ));
+        }
+        section.push_str("\n⛔ Simplify these functions before continuing");
+        Some(section)
+    }
+
+    /// Run the project's formatter and linter on a specific file
+    async fn run_linter(
+        &self,
+        project: &PythonProject,
+        source_file: &Path,
+        budget: &TimeBudget,
+    ) -> Result<AutomationResult> {
+        match PythonSourceKind::of(source_file) {
+            Some(PythonSourceKind::Module) => {}
+            Some(kind) => {
+                return self
+                    .run_source_kind_linter(project, source_file, kind, budget)
+                    .await
+            }
+            None => {
+                log::debug!(
+                    "Skipping linting for non-Python file: {}",
+                    source_file.display()
+                );
+                return Ok(AutomationResult::NoAction);
+            }
+        }
+
+        let linter = match project.preferred_linter() {
+            Some(linter) => {
+                if std::env::var("DEBUG").unwrap_or_default() == "1" {
+                    log::debug!(
+                        "Found linter: {} (command: {})",
+                        linter.display_name(),
+                        linter.command()
+                    );
+                }
+                linter
+            }
+            None => {
+                log::debug!("No Python linter found in project");
+                return Ok(AutomationResult::NoAction);
+            }
+        };
+
         log::debug!(
             "Running {} on file: {}",
             linter.display_name(),
@@ -248,143 +2535,536 @@ impl AutomationRunner {
 
         let file_path_str = source_file.to_string_lossy();
 
-        // Step 1: Try formatting first (if formatter available)
-        if let Some(formatter) = project.preferred_formatter() {
+        // Step 0: Sort imports before formatting, so the formatter has the last word
+        let formatter = self.formatter(project);
+        let sorted_imports = match self.sort_imports(project, source_file, formatter, budget)? {
+            Ok(sorted_imports) => sorted_imports,
+            Err(rolled_back) => return Ok(rolled_back),
+        };
+
+        // Step 1: Try formatting first (if enabled and a formatter is available)
+        let formatter = formatter.filter(|_| budget.allows("formatting"));
+        if let Some(formatter) = formatter {
             log::debug!("Formatting file with {}", formatter.display_name());
-            let format_args = formatter.format_args(&file_path_str);
-            let format_args_str: Vec<&str> = format_args.iter().map(|s| s.as_str()).collect();
+            let mut format_args = formatter.format_args(&file_path_str);
+            format_args.extend(project.target_version_args(formatter.command()));
 
-            let _format_output = self.run_command_with_timeout(
-                formatter.command(),
-                &format_args_str,
-                &project.root,
-                self.config.lint_timeout_seconds,
-            )?;
             // Don't fail on format errors - just log and continue
+            if let Rewrite::RolledBack(result) = self.rewrite_file(
+                project,
+                source_file,
+                formatter.display_name(),
+                formatter.command(),
+                &format_args,
+                budget,
+            )? {
+                return Ok(result);
+            }
             log::debug!("Formatting completed, now checking for lint issues");
         }
 
-        // Step 2: Try auto-fix linting issues (if supported)
-        if linter.supports_autofix() {
-            log::debug!("Attempting auto-fix with {}", linter.command());
-            let fix_args = linter.fix_args(&file_path_str);
-            let fix_args_str: Vec<&str> = fix_args.iter().map(|s| s.as_str()).collect();
+        // Step 2: Try auto-fix linting issues (if enabled and supported)
+        let autofix =
+            self.config.lint_autofix && linter.supports_autofix() && budget.allows("auto-fix");
+        if autofix {
+            log::debug!("Attempting auto-fix with {}", linter.command());
+            let mut fix_args = linter.fix_args(&file_path_str, self.config.lint_unsafe_fixes);
+            fix_args.extend(project.target_version_args(linter.command()));
+
+            // Don't fail on fix errors - just log and continue to check
+            if let Rewrite::RolledBack(result) = self.rewrite_file(
+                project,
+                source_file,
+                &format!("{} auto-fix", linter.display_name()),
+                linter.command(),
+                &fix_args,
+                budget,
+            )? {
+                return Ok(result);
+            }
+            log::debug!("Auto-fix completed, now checking for remaining issues");
+        }
+
+        // Step 3: Run linter on the specific file to check remaining issues, with the type
+        // checker (when enabled) alongside so their latencies overlap instead of adding up
+        let mut file_args = linter.file_args(&file_path_str);
+        file_args.extend(project.target_version_args(linter.command()));
+        file_args.extend(self.config.lint_extra_args.iter().cloned());
+        let file_args_str: Vec<&str> = file_args.iter().map(|s| s.as_str()).collect();
+
+        let type_checker = self
+            .config
+            .typecheck_enabled
+            .then(|| PythonTypeChecker::detect(self.config.typecheck_tool, &project.environment))
+            .flatten()
+            .filter(|_| budget.allows("type check"));
+        let (output, typecheck) = thread::scope(|scope| {
+            let typecheck = type_checker.map(|checker| {
+                scope.spawn(move || self.run_type_checker(project, source_file, checker, budget))
+            });
+            let output = self.run_command_with_timeout(
+                &project.environment.resolve(linter.command()),
+                &file_args_str,
+                &project.root,
+                budget.timeout(self.config.lint_timeout_seconds),
+            );
+            (output, typecheck.and_then(|handle| handle.join().ok()))
+        });
+        let output = output?;
+        let typecheck = typecheck.flatten();
+
+        let result = if output.success {
+            let message = match (formatter.is_some(), autofix) {
+                (true, true) => {
+                    "✨ Formatted, auto-fixed, and verified. Continue with your task.".to_string()
+                }
+                (true, false) => {
+                    "✨ Formatted and lints verified. Continue with your task.".to_string()
+                }
+                (false, true) => {
+                    "✨ Auto-fixed lint issues and verified. Continue with your task.".to_string()
+                }
+                (false, false) => "👉 Lints pass. Continue with your task.".to_string(),
+            };
+            let message = match &typecheck {
+                Some((checker, _)) => format!("🔎 Types check ({}). {message}", checker.command()),
+                None => message,
+            };
+            let message = match &sorted_imports {
+                Some(sorter) => format!("🔀 Imports reordered ({sorter}). {message}"),
+                None => message,
+            };
+            AutomationResult::Success(message)
+        } else {
+            let result = self
+                .lint_failure_result(&output, &project.root, budget)
+                .await;
+            match sorted_imports {
+                Some(sorter) => result.with_warning_section(&format!(
+                    "🔀 Imports reordered ({sorter}) before linting."
+                )),
+                None => result,
+            }
+        };
+        Ok(match typecheck {
+            Some((checker, errors)) if !errors.success => self.with_finding(
+                result,
+                FindingCategory::TypeError,
+                &type_error_section(checker, &errors, &project.root),
+            ),
+            _ => result,
+        })
+    }
+
+    /// Type check the file; `None` when the checker couldn't be run
+    fn run_type_checker(
+        &self,
+        project: &PythonProject,
+        source_file: &Path,
+        checker: PythonTypeChecker,
+        budget: &TimeBudget,
+    ) -> Option<(PythonTypeChecker, CommandOutput)> {
+        log::debug!(
+            "Type checking {} with {}",
+            source_file.display(),
+            checker.command()
+        );
+        let args = checker.file_args(&source_file.to_string_lossy());
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        self.run_command_with_timeout(
+            &project.environment.resolve(checker.command()),
+            &args,
+            &project.root,
+            budget.timeout(self.config.lint_timeout_seconds),
+        )
+        .map_err(|e| log::debug!("{} failed to run: {e}", checker.command()))
+        .ok()
+        .map(|output| (checker, output))
+    }
+
+    /// Sort the file's imports when `format.sort_imports` is on and the project has an
+    /// import sorter; returns the sorter's name when the file changed, or the rollback
+    /// failure when sorting broke it
+    fn sort_imports(
+        &self,
+        project: &PythonProject,
+        source_file: &Path,
+        formatter: Option<&PythonFormatter>,
+        budget: &TimeBudget,
+    ) -> Result<std::result::Result<Option<String>, AutomationResult>> {
+        if !self.config.format_sort_imports {
+            return Ok(Ok(None));
+        }
+        let Some(sorter) = project
+            .import_sorter()
+            .filter(|_| budget.allows("import sorting"))
+        else {
+            return Ok(Ok(None));
+        };
+        log::debug!("Sorting imports with {}", sorter.display_name());
+
+        // isort's default style fights black and ruff format; match them when they run
+        let default_profile = formatter.map(|_| "black");
+        let args = sorter.sort_args(&source_file.to_string_lossy(), default_profile);
+        Ok(
+            match self.rewrite_file(
+                project,
+                source_file,
+                &sorter.display_name(),
+                sorter.command(),
+                &args,
+                budget,
+            )? {
+                Rewrite::Changed => Ok(Some(sorter.display_name())),
+                Rewrite::Unchanged => Ok(None),
+                Rewrite::RolledBack(result) => Err(result),
+            },
+        )
+    }
+
+    /// Run a tool that rewrites `source_file` in place (import sorter, formatter or
+    /// auto-fix) with a backup, restoring the file when the tool leaves it broken or no
+    /// longer compiling
+    fn rewrite_file(
+        &self,
+        project: &PythonProject,
+        source_file: &Path,
+        tool_name: &str,
+        command: &str,
+        args: &[String],
+        budget: &TimeBudget,
+    ) -> Result<Rewrite> {
+        let backup = FileBackup::capture(source_file)
+            .map_err(|e| log::debug!("No backup of {}: {e}", source_file.display()))
+            .ok();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let _output = self.run_command_with_timeout(
+            &project.environment.resolve(command),
+            &args,
+            &project.root,
+            budget.timeout(self.config.lint_timeout_seconds),
+        )?;
+
+        let Some(backup) = backup else {
+            return Ok(Rewrite::Unchanged);
+        };
+        // Structural damage is caught without Python; real syntax errors need the compiler
+        let broken = backup.corruption().or_else(|| {
+            let interpreter = project.environment.interpreter()?;
+            backup.syntax_breakage(&interpreter)
+        });
+        if let Some(reason) = broken {
+            log::warn!(
+                "{tool_name} corrupted {} ({reason}); restoring it",
+                source_file.display()
+            );
+            backup.restore().with_context(|| {
+                format!(
+                    "Failed to restore {} after {tool_name} corrupted it",
+                    source_file.display()
+                )
+            })?;
+            return Ok(Rewrite::RolledBack(AutomationResult::Failure(format!(
+                "⛔ {tool_name} BROKE THE FILE, ROLLED BACK\n\n{tool_name} left {} broken ({reason}), so it was restored to its content from before {tool_name} ran. Your edit is intact; check the file, and turn the step off under `automation.format` or `automation.lint.autofix` if it keeps happening.",
+                source_file.display()
+            ))));
+        }
+        Ok(if backup.changed() {
+            Rewrite::Changed
+        } else {
+            Rewrite::Unchanged
+        })
+    }
+
+    /// Formatter for the project: none when formatting is disabled, the configured tool
+    /// when it's installed, otherwise the first one found
+    fn formatter<'a>(&self, project: &'a PythonProject) -> Option<&'a PythonFormatter> {
+        if !self.config.format_enabled {
+            return None;
+        }
+        match &self.config.format_tool {
+            Some(tool) => project
+                .available_formatters
+                .iter()
+                .find(|formatter| *formatter == tool),
+            None => project.preferred_formatter(),
+        }
+    }
+
+    /// Lint a type stub (formatted first) or Cython file with the tools that understand it
+    async fn run_source_kind_linter(
+        &self,
+        project: &PythonProject,
+        source_file: &Path,
+        kind: PythonSourceKind,
+        budget: &TimeBudget,
+    ) -> Result<AutomationResult> {
+        let Some((program, args)) = project.lint_command_for(source_file) else {
+            log::debug!("No linter for {:?} file: {}", kind, source_file.display());
+            return Ok(AutomationResult::NoAction);
+        };
+
+        let formatter = self
+            .formatter(project)
+            .filter(|_| kind == PythonSourceKind::Stub && budget.allows("formatting"));
+        if let Some(formatter) = formatter {
+            log::debug!("Formatting stub with {}", formatter.display_name());
+            let mut format_args = formatter.format_args(&source_file.to_string_lossy());
+            format_args.extend(project.target_version_args(formatter.command()));
+            if let Rewrite::RolledBack(result) = self.rewrite_file(
+                project,
+                source_file,
+                formatter.display_name(),
+                formatter.command(),
+                &format_args,
+                budget,
+            )? {
+                return Ok(result);
+            }
+        }
+
+        log::debug!("Running {} on file: {}", program, source_file.display());
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let output = self.run_command_with_timeout(
+            &program,
+            &args,
+            &project.root,
+            budget.timeout(self.config.lint_timeout_seconds),
+        )?;
+
+        if !output.success {
+            return Ok(self
+                .lint_failure_result(&output, &project.root, budget)
+                .await);
+        }
+        let message = if formatter.is_some() {
+            "✨ Formatted and lints verified. Continue with your task."
+        } else {
+            "👉 Lints pass. Continue with your task."
+        };
+        Ok(AutomationResult::Success(message.to_string()))
+    }
+
+    /// Turn failed linter output into a blocking result, letting AI analysis downgrade
+    /// overzealous lint findings to a success
+    async fn lint_failure_result(
+        &self,
+        output: &CommandOutput,
+        project_root: &Path,
+        budget: &TimeBudget,
+    ) -> AutomationResult {
+        // Use AI analysis for comprehensive lint failure analysis
+        let stdout =
+            render_ruff_json(&output.stdout, project_root).unwrap_or_else(|| output.stdout.clone());
+        let mut combined_output = if !output.stderr.is_empty() {
+            format!("{}\n{}", stdout, output.stderr)
+        } else {
+            stdout
+        };
+
+        // Deterministically drop findings for ignored rules before anything else
+        if !self.config.lint_ignore_rules.is_empty() {
+            let filtered = filter_ignored_rules(&combined_output, &self.config.lint_ignore_rules);
+            if filtered.only_ignored() {
+                return AutomationResult::Success(format!(
+                    "👉 Only ignored lint rules reported ({} finding(s)). Continue with your task.",
+                    filtered.ignored
+                ));
+            }
+            combined_output = filtered.output;
+        }
+
+        // Findings the AI already triaged as false positives don't need another call
+        let mut suppressions = Suppressions::load(project_root)
+            .map_err(|e| log::debug!("Suppressions unavailable: {e}"))
+            .ok();
+        if let Some(suppressions) = &suppressions {
+            let filtered = suppressions.filter(&combined_output);
+            if filtered.only_ignored() {
+                return AutomationResult::Success(format!(
+                    "👉 Only known false positives reported ({} finding(s), see `suppressions list`). Continue with your task.",
+                    filtered.ignored
+                ));
+            }
+            combined_output = filtered.output;
+        }
+
+        // Errors can't be false positives, so they block without consulting AI
+        let findings = parse_findings(&combined_output);
+        self.record(|trace| trace.findings = findings.clone());
+        if findings
+            .iter()
+            .any(|finding| finding.severity() == LintSeverity::Error)
+        {
+            return self.with_severity(
+                FindingCategory::LintError,
+                AutomationResult::Failure(format!(
+                    "⛔ LINT ERRORS FOUND ({}):\n\n{}\n\n⛔ Must fix these errors before continuing",
+                    severity_summary(&findings),
+                    combined_output.trim()
+                )),
+            );
+        }
+        // No need to triage findings that won't be shown
+        if self.config.severity.lint_warning == FindingBehavior::Silent {
+            log::debug!("Dropping lint warnings (automation.severity: silent)");
+            return AutomationResult::NoAction;
+        }
+
+        // Run AI analysis if available
+        let message = if !combined_output.trim().is_empty() {
+            let analysis = if !budget.allows("AI analysis") {
+                Err(anyhow::anyhow!("skipped to stay within the time budget"))
+            } else {
+                let analysis = self
+                    .analyzer
+                    .analyze_lint_output(&combined_output, Some(project_root));
+                match budget.remaining() {
+                    Some(remaining) => tokio::time::timeout(remaining, analysis)
+                        .await
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("ran past the time budget"))),
+                    None => analysis.await,
+                }
+            };
+            match analysis {
+                Ok(analysis) => {
+                    self.record(|trace| {
+                        trace
+                            .ai_analyses
+                            .push(AiAnalysisReport::Lint(analysis.clone()))
+                    });
+                    if let Some(suppressions) = &mut suppressions {
+                        remember_false_positives(suppressions, &combined_output, &analysis);
+                    }
+
+                    let mut detailed_message = String::new();
+                    detailed_message.push_str("⛔ LINT ISSUES FOUND:\n\n");
 
-            let _fix_output = self.run_command_with_timeout(
-                linter.command(),
-                &fix_args_str,
-                &project.root,
-                self.config.lint_timeout_seconds,
-            )?;
-            // Don't fail on fix errors - just log and continue to check
-            log::debug!("Auto-fix completed, now checking for remaining issues");
-        }
+                    if analysis.has_real_issues {
+                        // Show filtered output with only real issues
+                        if !analysis.filtered_output.trim().is_empty() {
+                            detailed_message.push_str(&analysis.filtered_output);
+                            detailed_message.push_str("\n\n");
+                        }
 
-        // Step 3: Run linter on the specific file to check remaining issues
-        let file_args = linter.file_args(&file_path_str);
-        let file_args_str: Vec<&str> = file_args.iter().map(|s| s.as_str()).collect();
+                        // Add AI reasoning about whether linter is being overzealous
+                        if !analysis.reasoning.trim().is_empty() {
+                            detailed_message.push_str("💡 **Analysis:**\n");
+                            detailed_message.push_str(&analysis.reasoning);
 
-        let output = self.run_command_with_timeout(
-            linter.command(),
-            &file_args_str,
-            &project.root,
-            self.config.lint_timeout_seconds,
-        )?;
+                            // Check if linter might be overzealous
+                            if analysis.reasoning.contains("style")
+                                || analysis.reasoning.contains("convention")
+                                || analysis.reasoning.contains("optional")
+                            {
+                                detailed_message.push_str("\n\n🤔 **Note:** Some of these might be style preferences rather than real issues.");
+                            }
+                        }
+                    } else {
+                        detailed_message.push_str("✅ **AI Analysis Result:**\n");
+                        detailed_message.push_str(&analysis.reasoning);
+                        detailed_message.push_str(
+                            "\n\n👉 Linter appears overzealous. You can continue with your task.",
+                        );
 
-        if output.success {
-            let has_formatter = project.preferred_formatter().is_some();
-            let has_autofix = linter.supports_autofix();
+                        // Return success if no real issues found
+                        return AutomationResult::Success(detailed_message);
+                    }
 
-            let message = match (has_formatter, has_autofix) {
-                (true, true) => {
-                    "✨ Formatted, auto-fixed, and verified. Continue with your task.".to_string()
-                }
-                (true, false) => {
-                    "✨ Formatted and lints verified. Continue with your task.".to_string()
+                    detailed_message
                 }
-                (false, true) => {
-                    "✨ Auto-fixed lint issues and verified. Continue with your task.".to_string()
+                Err(e) => {
+                    log::warn!("AI analysis failed: {}", e);
+                    // Fallback to showing raw output
+                    format!(
+                        "⛔ LINT FAILURES:\n\n{}\n\n⚠️ Could not determine if linter is being overzealous (AI unavailable)",
+                        combined_output.trim()
+                    )
                 }
-                (false, false) => "👉 Lints pass. Continue with your task.".to_string(),
-            };
-            Ok(AutomationResult::Success(message))
+            }
         } else {
-            // Use AI analysis for comprehensive lint failure analysis
-            let combined_output = if !output.stderr.is_empty() {
-                format!("{}\n{}", output.stdout, output.stderr)
-            } else {
-                output.stdout
-            };
+            "⛔ Lint check failed".to_string()
+        };
 
-            // Run AI analysis if available
-            let message = if !combined_output.trim().is_empty() {
-                match self
-                    .analyzer
-                    .analyze_lint_output(&combined_output, Some(&project.root))
-                    .await
-                {
-                    Ok(analysis) => {
-                        let mut detailed_message = String::new();
-                        detailed_message.push_str("⛔ LINT ISSUES FOUND:\n\n");
-
-                        if analysis.has_real_issues {
-                            // Show filtered output with only real issues
-                            if !analysis.filtered_output.trim().is_empty() {
-                                detailed_message.push_str(&analysis.filtered_output);
-                                detailed_message.push_str("\n\n");
-                            }
+        self.with_severity(
+            FindingCategory::LintWarning,
+            AutomationResult::Failure(message),
+        )
+    }
 
-                            // Add AI reasoning about whether linter is being overzealous
-                            if !analysis.reasoning.trim().is_empty() {
-                                detailed_message.push_str("💡 **Analysis:**\n");
-                                detailed_message.push_str(&analysis.reasoning);
-
-                                // Check if linter might be overzealous
-                                if analysis.reasoning.contains("style")
-                                    || analysis.reasoning.contains("convention")
-                                    || analysis.reasoning.contains("optional")
-                                {
-                                    detailed_message.push_str("\n\n🤔 **Note:** Some of these might be style preferences rather than real issues.");
-                                }
-                            }
-                        } else {
-                            detailed_message.push_str("✅ **AI Analysis Result:**\n");
-                            detailed_message.push_str(&analysis.reasoning);
-                            detailed_message.push_str(
-                                "\n\n👉 Linter appears overzealous. You can continue with your task.",
-                            );
+    /// Run test command for a specific file in the project, followed by its doctests
+    async fn run_test_command(
+        &self,
+        project: &PythonProject,
+        source_file: &Path,
+    ) -> Result<AutomationResult> {
+        let result = self.run_unit_tests(project, source_file).await?;
 
-                            // Return success if no real issues found
-                            return Ok(AutomationResult::Success(detailed_message));
-                        }
+        if !self.config.test_doctests {
+            return Ok(result);
+        }
 
-                        detailed_message
-                    }
-                    Err(e) => {
-                        log::warn!("AI analysis failed: {}", e);
-                        // Fallback to showing raw output
-                        format!(
-                            "⛔ LINT FAILURES:\n\n{}\n\n⚠️ Could not determine if linter is being overzealous (AI unavailable)",
-                            combined_output.trim()
-                        )
-                    }
-                }
-            } else {
-                "⛔ Lint check failed".to_string()
-            };
+        match self.run_doctests(project, source_file) {
+            Some(doctest_output) => Ok(result.with_failure_section(&format!(
+                "⛔ DOCTESTS FAILED:\n\n{}\n\n⛔ Must fix all doctest failures before continuing",
+                doctest_output.trim()
+            ))),
+            None => Ok(result),
+        }
+    }
+
+    /// Run `python -m doctest` (or pytest --doctest-modules) on a module containing
+    /// `>>>` examples, returning the failure output if any doctest failed
+    fn run_doctests(&self, project: &PythonProject, source_file: &Path) -> Option<String> {
+        if PythonSourceKind::of(source_file) != Some(PythonSourceKind::Module)
+            || !has_doctests(source_file)
+        {
+            return None;
+        }
+
+        let file_path_str = source_file.to_string_lossy().to_string();
+        let (command, args) = match project.preferred_tester() {
+            Some(tester) if tester.is_pytest() => {
+                let mut args: Vec<String> = tester.args().iter().map(|a| a.to_string()).collect();
+                args.push("--doctest-modules".to_string());
+                args.push(file_path_str);
+                project.environment.command(tester.command(), args)
+            }
+            _ => project.environment.command(
+                "python",
+                vec!["-m".to_string(), "doctest".to_string(), file_path_str],
+            ),
+        };
+        let args_str: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
-            Ok(AutomationResult::Failure(message))
+        log::debug!("Running doctests: {} {}", command, args.join(" "));
+        match self.run_command_with_timeout(
+            &command,
+            &args_str,
+            &project.root,
+            self.config.test_timeout_seconds,
+        ) {
+            Ok(output) if output.success => None,
+            Ok(output) => Some(format!("{}\n{}", output.stdout, output.stderr)),
+            Err(e) => {
+                log::warn!("Failed to run doctests: {}", e);
+                None
+            }
         }
     }
 
-    /// Run test command for a specific file in the project
-    async fn run_test_command(
+    /// Run the unit tests that correspond to a specific source file
+    async fn run_unit_tests(
         &self,
         project: &PythonProject,
         source_file: &Path,
     ) -> Result<AutomationResult> {
         let tester = match project.preferred_tester() {
             Some(tester) => {
+                let tester = match &self.config.test_session {
+                    Some(session) => tester.with_session(session),
+                    None => tester.clone(),
+                };
                 if std::env::var("DEBUG").unwrap_or_default() == "1" {
                     log::debug!(
                         "Found tester: {} (command: {})",
@@ -400,26 +3080,40 @@ impl AutomationRunner {
             }
         };
 
-        // Only test Python files (.py extension)
-        if source_file.extension().and_then(|ext| ext.to_str()) != Some("py") {
+        // Only modules have tests; stubs hold no logic and Cython needs a rebuild first
+        if PythonSourceKind::of(source_file) != Some(PythonSourceKind::Module) {
             log::debug!(
-                "Skipping tests for non-Python file: {}",
+                "Skipping tests for non-module file: {}",
                 source_file.display()
             );
             return Ok(AutomationResult::NoAction);
         }
 
         // Find the corresponding test file for the edited source file
-        let test_file = match self.find_test_file_for_source(source_file, &project.root) {
+        let test_file = match self
+            .find_test_file_for_source(source_file, &project.root)
+            .or_else(|| django_app_tests(project, source_file))
+        {
             Some(test_file) => test_file,
             None => {
                 log::debug!("No test file found for: {}", source_file.display());
-                return Ok(AutomationResult::Success(format!(
-                    "📝 No tests found for {}.\n\n💡 Consider creating tests at:\n  • tests/test_{}.py\n  • tests/unit/test_{}.py\n\n👉 Continue with your task.",
+                let framework = project.framework.clone().or_else(|| {
+                    std::fs::read_to_string(source_file)
+                        .ok()
+                        .and_then(|source| Framework::from_imports(&source))
+                });
+                let result = AutomationResult::Success(format!(
+                    "📝 No tests found for {}.\n\n💡 Consider creating tests at:\n  • tests/test_{}.py\n  • tests/unit/test_{}.py{}\n\n👉 Continue with your task.",
                     source_file.file_name().unwrap_or_default().to_string_lossy(),
                     source_file.file_stem().unwrap_or_default().to_string_lossy(),
-                    source_file.file_stem().unwrap_or_default().to_string_lossy()
-                )));
+                    source_file.file_stem().unwrap_or_default().to_string_lossy(),
+                    framework
+                        .as_ref()
+                        .map(framework_test_suggestions)
+                        .unwrap_or_default()
+                ));
+                let result = self.with_severity(FindingCategory::MissingTests, result);
+                return Ok(self.as_advice(AdviceFinding::MissingTests, result));
             }
         };
 
@@ -429,37 +3123,290 @@ impl AutomationRunner {
             test_file.display()
         );
 
-        // Create command arguments that include the specific test file
-        let base_args = tester.args();
-        let test_file_str = test_file.to_string_lossy();
+        // Create command arguments that target the specific test file
+        let mut test_args = tester.test_file_args(&test_file, &project.root);
+        test_args.extend(self.config.test_extra_args.iter().cloned());
+
+        if self.config.test_changed_functions_only && tester.is_pytest() {
+            if let Some(selection) = changed_test_selection(source_file, &test_file, &project.root)
+            {
+                log::debug!("Running only the tests for changed code: {selection}");
+                test_args.push("-k".to_string());
+                test_args.push(selection);
+            }
+        }
 
-        // Build combined args by collecting base args and adding the test file
-        let mut combined_args: Vec<&str> = base_args.to_vec();
-        combined_args.push(&test_file_str);
+        // Collect pytest-cov data for the edited module when a coverage gate is set; without
+        // pytest-cov installed `--cov` would fail the run, so the gate is skipped instead
+        let mut coverage_skipped = false;
+        let coverage_report = match self.config.test_min_coverage_percent {
+            Some(_) if tester.is_pytest() && project.has_pytest_cov(&tester) => {
+                let report = StateDir::for_workspace(&project.root)?.file("coverage.json");
+                let cov_source = source_file.parent().unwrap_or(&project.root);
+                test_args.push(format!("--cov={}", cov_source.display()));
+                test_args.push(format!("--cov-report=json:{}", report.display()));
+                Some(report)
+            }
+            Some(_) => {
+                coverage_skipped = true;
+                None
+            }
+            None => None,
+        };
 
-        let output = self.run_command_with_timeout(
-            tester.command(),
+        // List every outcome in the short summary so passing tests can be named in the XML
+        let junit_xml = self
+            .config
+            .test_junit_xml
+            .as_ref()
+            .filter(|_| tester.is_pytest());
+        if junit_xml.is_some() {
+            test_args.push("-rA".to_string());
+        }
+
+        log::debug!("Python environment: {}", project.environment.describe());
+        for version in project.tool_versions() {
+            log::debug!("Tool version: {}", version.version);
+        }
+        let (command, test_args) = project.environment.command(tester.command(), test_args);
+        let combined_args: Vec<&str> = test_args.iter().map(|s| s.as_str()).collect();
+
+        let output = self.run_command(
+            &command,
             &combined_args,
             &project.root,
             self.config.test_timeout_seconds,
+            self.config.test_stream_output,
+            &project.test_env(&tester),
         )?;
 
+        if let Some(junit_xml) = junit_xml {
+            let path = project.root.join(junit_xml);
+            let suite = test_file.strip_prefix(&project.root).unwrap_or(&test_file);
+            let xml = PytestResults::parse(&output.stdout).to_junit_xml(&suite.to_string_lossy());
+            if let Err(e) = std::fs::write(&path, xml) {
+                log::warn!("Failed to write JUnit XML to {}: {}", path.display(), e);
+            }
+        }
+
+        // Deterministic coverage gate - only applies to passing runs with coverage data
+        let mut coverage_section = None;
+        if let (Some(report), Some(threshold)) =
+            (&coverage_report, self.config.test_min_coverage_percent)
+        {
+            let coverage = FileCoverage::from_report(report, source_file, &project.root);
+            let _ = std::fs::remove_file(report);
+
+            if let Some(coverage) = coverage.filter(|_| output.success) {
+                if coverage.percent_covered < threshold {
+                    let section = format!(
+                        "⛔ COVERAGE BELOW THRESHOLD:\n\n{} is {:.1}% covered (minimum {:.1}%)\n\n📋 **Uncovered lines**: {}\n\n⛔ Add tests for the uncovered lines before continuing",
+                        source_file.display(),
+                        coverage.percent_covered,
+                        threshold,
+                        format_line_ranges(&coverage.missing_lines)
+                    );
+                    // A blocking shortfall replaces the passing summary, so skip analyzing it
+                    if self.config.severity.coverage_shortfall == FindingBehavior::Block {
+                        return Ok(AutomationResult::Failure(section));
+                    }
+                    coverage_section = Some(section);
+                }
+            }
+        }
+
+        let snapshot_result = if output.success || !tester.is_pytest() {
+            None
+        } else {
+            self.snapshot_result(&output, project, &tester, &test_file)?
+        };
+        let result = match snapshot_result {
+            Some(result) => result,
+            None => {
+                let runner = tester.is_pytest().then(|| tester.display_name());
+                let result = self
+                    .test_output_result(&output, &project.root, source_file, runner)
+                    .await;
+                self.as_advice(AdviceFinding::PassingTests, result)
+            }
+        };
+        let mut result = if result.is_failure() {
+            self.with_severity(FindingCategory::TestFailure, result)
+        } else {
+            result
+        };
+        if let Some(section) = coverage_section {
+            result = self.with_finding(result, FindingCategory::CoverageShortfall, &section);
+        }
+        if coverage_skipped {
+            result = self.with_note(
+                result,
+                AdviceFinding::SkippedSteps,
+                &format!(
+                    "⚠️ Coverage gate skipped: {} doesn't have pytest-cov installed, so `automation.test.min_coverage_percent` can't be checked.",
+                    tester.display_name()
+                ),
+            );
+        }
+        Ok(match self.check_test_smells(&test_file, &project.root) {
+            Some(section) => self.with_note(result, AdviceFinding::TestSmells, &section),
+            None => result,
+        })
+    }
+
+    /// Explain (or, with `auto_update_snapshots: always`, apply) snapshot updates when every
+    /// failure in a run is a syrupy/pytest-snapshot mismatch
+    fn snapshot_result(
+        &self,
+        output: &CommandOutput,
+        project: &PythonProject,
+        tester: &PythonTester,
+        test_file: &Path,
+    ) -> Result<Option<AutomationResult>> {
+        let test_source = std::fs::read_to_string(test_file).unwrap_or_default();
+        let Some(plugin) = SnapshotPlugin::detect(&project.root, &test_source) else {
+            return Ok(None);
+        };
+        let Some(failures) =
+            snapshot::snapshot_failures(&format!("{}\n{}", output.stdout, output.stderr))
+        else {
+            return Ok(None);
+        };
+
+        let relative = test_file.strip_prefix(&project.root).unwrap_or(test_file);
+        let snapshot_dir = relative
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(plugin.snapshot_dir());
+        let update_command = format!(
+            "{} {} {}",
+            tester.display_name(),
+            relative.display(),
+            snapshot::UPDATE_FLAG
+        );
+
+        if self.config.test_auto_update_snapshots == AutoUpdateSnapshots::Always {
+            let mut args = tester.test_file_args(test_file, &project.root);
+            args.push(snapshot::UPDATE_FLAG.to_string());
+            let (command, args) = project.environment.command(tester.command(), args);
+            let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+            let update = self.run_command(
+                &command,
+                &args,
+                &project.root,
+                self.config.test_timeout_seconds,
+                self.config.test_stream_output,
+                &project.test_env(tester),
+            )?;
+            if !update.success {
+                return Ok(None);
+            }
+            let mut message = format!(
+                "📸 Updated {} snapshot(s) with {}:\n\n",
+                failures.len(),
+                plugin.name()
+            );
+            for failure in &failures {
+                message.push_str(&format!("  • {}\n", failure.node_id));
+            }
+            message.push_str(&format!(
+                "\n💡 Review the new snapshots with `git diff -- {}` and revert any that aren't intended\n\n👉 Continue with your task.",
+                snapshot_dir.display()
+            ));
+            return Ok(Some(AutomationResult::Success(message)));
+        }
+
+        let mut message = format!(
+            "⛔ SNAPSHOT MISMATCH ({}):\n\nThese tests no longer match their stored snapshots in {}:\n",
+            plugin.name(),
+            snapshot_dir.display()
+        );
+        for failure in &failures {
+            message.push_str(&format!("  • {}", failure.node_id));
+            if !failure.message.is_empty() {
+                message.push_str(&format!(" - {}", failure.message));
+            }
+            message.push('\n');
+        }
+        message.push_str(&format!(
+            "\n💡 If the code is right, fix it so the output matches again; if the new output is intended, `{update_command}` rewrites the snapshots (review them with `git diff` afterwards)\n\n"
+        ));
+        message.push_str(match self.config.test_auto_update_snapshots {
+            AutoUpdateSnapshots::Never => {
+                "⛔ Don't update snapshots yourself: fix the code or leave the update to the user"
+            }
+            _ => "⛔ Ask the user whether this output change is intended before updating the snapshots",
+        });
+        Ok(Some(AutomationResult::Failure(message)))
+    }
+
+    /// Describe assertion density and smells of the test file that was run
+    fn check_test_smells(&self, test_file: &Path, project_root: &Path) -> Option<String> {
+        if !self.config.test_smells {
+            return None;
+        }
+        let smells = checks::test_smells(&std::fs::read_to_string(test_file).ok()?);
+        if smells.findings.is_empty() {
+            return None;
+        }
+
+        let relative = test_file.strip_prefix(project_root).unwrap_or(test_file);
+        let mut section = format!(
+            "⚠️ TEST SMELLS in {} ({} tests, {:.1} assertions per test):\n\n",
+            relative.display(),
+            smells.tests,
+            smells.assertions as f64 / smells.tests as f64
+        );
+        for smell in &smells.findings {
+            section.push_str(&format!(
+                "  • {} (line {}): {}\n",
+                smell.test, smell.line, smell.message
+            ));
+        }
+        section.push_str("\n💡 Tighten these tests so they fail when the code is wrong");
+        Some(section)
+    }
+
+    /// Summarize a test run, using AI analysis for coverage notes and failure explanations
+    async fn test_output_result(
+        &self,
+        output: &CommandOutput,
+        project_root: &Path,
+        source_file: &Path,
+        pytest_runner: Option<&str>,
+    ) -> AutomationResult {
         // Always combine stdout/stderr output for analysis
         let combined_output = if !output.stderr.is_empty() {
             format!("{}\n{}", output.stdout, output.stderr)
         } else {
-            output.stdout
+            output.stdout.clone()
+        };
+        let reproduce = match pytest_runner {
+            Some(runner) if !output.success => reproduce_section(&combined_output, runner),
+            _ => String::new(),
         };
+        self.record(|trace| {
+            trace.failed_tests.extend(
+                PytestResults::parse(&combined_output)
+                    .failures()
+                    .map(|case| case.node_id.clone()),
+            )
+        });
 
         // Now that tests have been run, analyze the output with AI
         // We already have the source file as a parameter, no need to search for it
 
         match self
             .analyzer
-            .analyze_test_output(&combined_output, &project.root, Some(source_file))
+            .analyze_test_output(&combined_output, project_root, Some(source_file))
             .await
         {
             Ok(analysis) => {
+                self.record(|trace| {
+                    trace
+                        .ai_analyses
+                        .push(AiAnalysisReport::Test(analysis.clone()))
+                });
                 if output.success {
                     // Tests passed - check for edge case coverage
                     let mut message = String::new();
@@ -494,7 +3441,7 @@ impl AutomationRunner {
 
                     message.push_str("👉 Continue with your task.");
 
-                    Ok(AutomationResult::Success(message))
+                    AutomationResult::Success(message)
                 } else {
                     // Tests failed - provide comprehensive failure analysis
                     let mut detailed_message = String::new();
@@ -525,6 +3472,7 @@ impl AutomationRunner {
                         ));
                     }
 
+                    detailed_message.push_str(&reproduce);
                     detailed_message.push_str("📄 **Full Output**:\n");
                     detailed_message.push_str(combined_output.trim());
 
@@ -532,25 +3480,24 @@ impl AutomationRunner {
                     detailed_message
                         .push_str("\n\n⛔ Must fix all test failures before continuing");
 
-                    Ok(AutomationResult::Failure(detailed_message))
+                    AutomationResult::Failure(detailed_message)
                 }
             }
             Err(e) => {
                 log::warn!("AI analysis failed: {}", e);
                 // Fallback to basic behavior when AI analysis fails
                 if output.success {
-                    Ok(AutomationResult::Success(
-                        "👉 Tests pass. Continue with your task.".to_string(),
-                    ))
+                    AutomationResult::Success("👉 Tests pass. Continue with your task.".to_string())
                 } else if !combined_output.trim().is_empty() {
-                    Ok(AutomationResult::Failure(format!(
-                        "⛔ TESTS FAILED:\n\n{}\n\n⛔ Must fix all test failures before continuing",
-                        combined_output.trim()
-                    )))
+                    AutomationResult::Failure(format!(
+                        "⛔ TESTS FAILED:\n\n{}\n\n{}⛔ Must fix all test failures before continuing",
+                        combined_output.trim(),
+                        reproduce
+                    ))
                 } else {
-                    Ok(AutomationResult::Failure(
+                    AutomationResult::Failure(
                         "⛔ Test failures detected. Must fix before continuing".to_string(),
-                    ))
+                    )
                 }
             }
         }
@@ -563,6 +3510,20 @@ impl AutomationRunner {
         args: &[&str],
         working_dir: &Path,
         timeout_seconds: u64,
+    ) -> Result<CommandOutput> {
+        self.run_command(command, args, working_dir, timeout_seconds, false, &[])
+    }
+
+    /// Run a command with timeout, capturing output and optionally relaying it to
+    /// stderr line by line while it runs
+    fn run_command(
+        &self,
+        command: &str,
+        args: &[&str],
+        working_dir: &Path,
+        timeout_seconds: u64,
+        stream: bool,
+        env: &[(String, String)],
     ) -> Result<CommandOutput> {
         // Debug logging to see exactly what command is being executed
         if std::env::var("DEBUG").unwrap_or_default() == "1" {
@@ -574,47 +3535,94 @@ impl AutomationRunner {
             );
         }
 
-        // Create command
+        let started = Instant::now();
         let mut cmd = Command::new(command);
         cmd.args(args)
+            .envs(env.iter().map(|(key, value)| (key, value)))
             .current_dir(working_dir)
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
 
         // Spawn process
-        let mut child = cmd.spawn().context(format!(
-            "Failed to spawn command: {} {} (working dir: {})",
-            command,
-            args.join(" "),
-            working_dir.display()
-        ))?;
+        let mut child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(GuardrailsError::ToolNotFound {
+                    tool: command.to_string(),
+                }
+                .into())
+            }
+            Err(e) => {
+                return Err(e).context(format!(
+                    "Failed to spawn command: {} {} (working dir: {})",
+                    command,
+                    args.join(" "),
+                    working_dir.display()
+                ))
+            }
+        };
+
+        // Drain both pipes while waiting so chatty commands can't block on a full pipe
+        let relay = stream.then(|| Arc::new(Mutex::new(OutputRelay::new(STREAM_MIN_INTERVAL))));
+        let stdout_reader = child
+            .stdout
+            .take()
+            .map(|stdout| spawn_output_reader(stdout, relay.clone()));
+        let stderr_reader = child
+            .stderr
+            .take()
+            .map(|stderr| spawn_output_reader(stderr, relay.clone()));
 
         // Wait with timeout
         let result = self.wait_with_timeout(&mut child, Duration::from_secs(timeout_seconds))?;
+        if result.is_none() {
+            // Timeout - kill the process
+            let _ = child.kill();
+            let _ = child.wait();
+        }
 
-        match result {
-            Some(status) => {
-                // Get output
-                let output = child
-                    .wait_with_output()
-                    .context("Failed to get command output")?;
-                Ok(CommandOutput {
-                    success: status.success(),
-                    stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-                    stderr: String::from_utf8_lossy(&output.stderr).to_string(),
-                })
-            }
-            None => {
-                // Timeout - kill the process
-                let _ = child.kill();
-                let _ = child.wait();
-                Ok(CommandOutput {
-                    success: false,
-                    stdout: String::new(),
-                    stderr: "Command timed out".to_string(),
-                })
+        let join = |reader: Option<thread::JoinHandle<String>>| {
+            reader
+                .and_then(|reader| reader.join().ok())
+                .unwrap_or_default()
+        };
+        let stdout = join(stdout_reader);
+        let stderr = join(stderr_reader);
+        if let Some(relay) = relay {
+            if let Ok(mut relay) = relay.lock() {
+                relay.finish();
             }
         }
+
+        self.record(|trace| {
+            trace.steps.push(StepReport {
+                program: Path::new(command)
+                    .file_name()
+                    .map_or(command.to_string(), |name| {
+                        name.to_string_lossy().into_owned()
+                    }),
+                command: std::iter::once(command)
+                    .chain(args.iter().copied())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                duration_ms: started.elapsed().as_millis() as u64,
+                success: result.is_some_and(|status| status.success()),
+                timed_out: result.is_none(),
+            })
+        });
+
+        match result {
+            Some(status) => Ok(CommandOutput {
+                success: status.success(),
+                stdout,
+                stderr,
+            }),
+            None => Ok(CommandOutput {
+                success: false,
+                stdout: String::new(),
+                stderr: "Command timed out".to_string(),
+            }),
+        }
     }
 
     /// Wait for process with timeout
@@ -623,9 +3631,6 @@ impl AutomationRunner {
         child: &mut std::process::Child,
         timeout: Duration,
     ) -> Result<Option<ExitStatus>> {
-        use std::thread;
-        use std::time::Instant;
-
         let start = Instant::now();
 
         loop {
@@ -660,6 +3665,15 @@ impl AutomationRunner {
             }
         }
 
+        // Declared mappings are exact: a mapped test file that doesn't exist means no tests
+        if let Some(test_file) = self.config.test_map.resolve(source_file, project_root) {
+            log::debug!(
+                "Test file mapped by automation.test.map: {}",
+                test_file.display()
+            );
+            return test_file.is_file().then_some(test_file);
+        }
+
         // List of possible test file patterns
         let test_patterns = vec![
             format!("test_{}.py", source_name),
@@ -667,76 +3681,451 @@ impl AutomationRunner {
             format!("test{}.py", source_name),
         ];
 
-        // Base test directories to search recursively
-        let base_test_directories = vec![
-            project_root.join("tests"),
-            project_root.join("test"),
-            project_root.to_path_buf(), // Same directory as source
-            source_file.parent()?.to_path_buf(), // Source file's directory
-        ];
+        // Base test directories to search recursively; the project root covers the rest
+        // of the tree, the others let the depth limit start further down
+        let base_test_directories = [
+            project_root.join("tests"),
+            project_root.join("test"),
+            project_root.to_path_buf(),
+            source_file.parent()?.to_path_buf(), // Source file's directory
+        ];
+
+        let mut candidates: Vec<PathBuf> = base_test_directories
+            .iter()
+            .flat_map(|base_dir| self.config.search.find_files(base_dir, &test_patterns))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        // In a monorepo several packages can have a test_<name>.py; take the one closest
+        // to the source module rather than whichever the walk reached first
+        let best = candidates
+            .into_iter()
+            .min_by_key(|candidate| TestCandidateRank::new(candidate, source_file, project_root));
+        if let Some(test_file) = best {
+            log::debug!("Found test file: {}", test_file.display());
+            return Some(test_file);
+        }
+
+        log::debug!(
+            "No test file found for source file: {}",
+            source_file.display()
+        );
+        None
+    }
+}
+
+/// Files marking the directory pytest treats as a test tree's rootdir
+const PYTEST_ROOTDIR_MARKERS: &[&str] = &[
+    "conftest.py",
+    "pytest.ini",
+    "pyproject.toml",
+    "setup.cfg",
+    "setup.py",
+    "tox.ini",
+];
+
+/// Directory names that organize tests rather than mirror the source package
+const TEST_LAYOUT_DIRS: &[&str] = &["tests", "test", "src"];
+
+/// How well a test file candidate matches a source module; smaller is better
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct TestCandidateRank {
+    /// Outside the source's nearest conftest/pytest rootdir
+    outside_rootdir: bool,
+    /// Outside the source's top-level package (the `__init__.py` chain it belongs to)
+    outside_package: bool,
+    /// Directories shared with the source, from the project root down (negated)
+    common_prefix: isize,
+    /// Package directory names shared with the source anywhere in the path, e.g.
+    /// `core` in `src/app/core/x.py` and `tests/core/test_x.py` (negated)
+    shared_names: isize,
+    depth: usize,
+}
+
+impl TestCandidateRank {
+    fn new(candidate: &Path, source_file: &Path, project_root: &Path) -> Self {
+        let source_dir = source_file.parent().unwrap_or(project_root);
+        let dirs = |path: &Path| -> Vec<String> {
+            path.strip_prefix(project_root)
+                .unwrap_or(path)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                .collect()
+        };
+        let source_dirs = dirs(source_dir);
+        let candidate_dirs = dirs(candidate.parent().unwrap_or(project_root));
+
+        let rootdir = source_dir
+            .ancestors()
+            .take_while(|dir| dir.starts_with(project_root))
+            .find(|dir| {
+                PYTEST_ROOTDIR_MARKERS
+                    .iter()
+                    .any(|marker| dir.join(marker).is_file())
+            })
+            .unwrap_or(project_root);
+        let package = source_dir
+            .ancestors()
+            .take_while(|dir| dir.starts_with(project_root) && dir.join("__init__.py").is_file())
+            .last();
+
+        let common_prefix = source_dirs
+            .iter()
+            .zip(&candidate_dirs)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let shared_names = candidate_dirs
+            .iter()
+            .filter(|dir| !TEST_LAYOUT_DIRS.contains(&dir.as_str()) && source_dirs.contains(dir))
+            .count();
+
+        Self {
+            outside_rootdir: !candidate.starts_with(rootdir),
+            outside_package: package.is_some_and(|package| !candidate.starts_with(package)),
+            common_prefix: -(common_prefix as isize),
+            shared_names: -(shared_names as isize),
+            depth: candidate_dirs.len(),
+        }
+    }
+}
+
+/// Coverage of a single file taken from a pytest-cov JSON report
+#[derive(Debug, PartialEq)]
+pub struct FileCoverage {
+    pub percent_covered: f64,
+    pub missing_lines: Vec<u64>,
+}
+
+impl FileCoverage {
+    /// Read the coverage entry for `source_file` from a `--cov-report=json` report
+    pub fn from_report(report: &Path, source_file: &Path, project_root: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(report).ok()?;
+        let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+        Self::from_json(&json, source_file, project_root)
+    }
+
+    /// Find the entry for `source_file` in parsed report JSON (keys are root-relative)
+    fn from_json(
+        json: &serde_json::Value,
+        source_file: &Path,
+        project_root: &Path,
+    ) -> Option<Self> {
+        let files = json.get("files")?.as_object()?;
+        let relative = source_file
+            .strip_prefix(project_root)
+            .unwrap_or(source_file);
+
+        let entry = files.iter().find_map(|(name, entry)| {
+            let name = Path::new(name);
+            (name == source_file || name == relative || project_root.join(name) == source_file)
+                .then_some(entry)
+        })?;
+
+        Some(Self {
+            percent_covered: entry.get("summary")?.get("percent_covered")?.as_f64()?,
+            missing_lines: entry
+                .get("missing_lines")
+                .and_then(|lines| lines.as_array())
+                .map(|lines| lines.iter().filter_map(|line| line.as_u64()).collect())
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// `-k` expression selecting the tests in `test_file` that reference the functions and
+/// classes changed in `source_file`, or `None` to run the whole test file
+fn changed_test_selection(
+    source_file: &Path,
+    test_file: &Path,
+    project_root: &Path,
+) -> Option<String> {
+    if source_file == test_file {
+        return None;
+    }
+    let ranges = checks::changed_line_ranges(source_file, project_root)?;
+    let changed = checks::changed_definitions(&std::fs::read_to_string(source_file).ok()?, &ranges);
+    if changed.module_level || changed.names.is_empty() {
+        return None;
+    }
+    let tests =
+        checks::tests_referencing(&std::fs::read_to_string(test_file).ok()?, &changed.names)?;
+    Some(tests.join(" or "))
+}
+
+/// Commands rerunning each failed pytest test on its own, with the failing
+/// parametrizations listed per test function
+fn reproduce_section(output: &str, runner: &str) -> String {
+    let results = PytestResults::parse(output);
+    let failures: Vec<&TestCase> = results.failures().collect();
+    if failures.is_empty() {
+        return String::new();
+    }
+
+    let mut section = String::new();
+    let mut functions: Vec<(&str, Vec<&str>)> = Vec::new();
+    for failure in &failures {
+        let Some(param_id) = failure.param_id() else {
+            continue;
+        };
+        match functions
+            .iter_mut()
+            .find(|(function, _)| *function == failure.function_id())
+        {
+            Some((_, ids)) => ids.push(param_id),
+            None => functions.push((failure.function_id(), vec![param_id])),
+        }
+    }
+    if !functions.is_empty() {
+        section.push_str("🧩 **Failing Parameters**:\n");
+        for (function, ids) in &functions {
+            section.push_str(&format!("  • {function}: {}\n", ids.join(", ")));
+        }
+        section.push('\n');
+    }
+
+    section.push_str("🔁 **Reproduce**:\n");
+    for failure in failures.iter().take(MAX_REPRODUCE_COMMANDS) {
+        section.push_str(&format!("  • {}\n", failure.reproduce_command(runner)));
+    }
+    if failures.len() > MAX_REPRODUCE_COMMANDS {
+        section.push_str(&format!(
+            "  • ...and {} more\n",
+            failures.len() - MAX_REPRODUCE_COMMANDS
+        ));
+    }
+    section.push('\n');
+    section
+}
+
+/// Blocking section listing the type checker's errors, with paths relative to the
+/// project root
+fn type_error_section(checker: PythonTypeChecker, output: &CommandOutput, root: &Path) -> String {
+    let prefix = format!("{}/", root.display());
+    let errors = format!("{}\n{}", output.stdout.trim(), output.stderr.trim())
+        .trim()
+        .replace(&prefix, "");
+    format!(
+        "⛔ TYPE ERRORS ({})\n\n```\n{errors}\n```\n\nFix the type errors, then continue with your task.",
+        checker.command()
+    )
+}
+
+/// Report surviving mutants as evidence of weak tests; never blocks, since mutation
+/// results are a quality signal rather than a failure
+fn mutation_result(
+    source_file: &Path,
+    total: Option<usize>,
+    survivors: &[SurvivingMutant],
+    budget_exhausted: Option<u64>,
+) -> AutomationResult {
+    let file_name = source_file
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let of_total = total
+        .map(|total| format!(" (of {total} generated)"))
+        .unwrap_or_default();
+    let mut message = if survivors.is_empty() {
+        format!(
+            "🧬 No mutants in the edited functions of {file_name} survived{of_total}.\n\n🎯 **Quality**: The tests catch changes to the edited code.\n"
+        )
+    } else {
+        let mut message = format!(
+            "🧬 MUTATION SPOT CHECK: {} mutant(s) in the edited functions of {file_name} survived{of_total}:\n\n",
+            survivors.len()
+        );
+        for mutant in survivors.iter().take(MAX_REPORTED_MUTANTS) {
+            match mutant.line {
+                Some(line) => {
+                    message.push_str(&format!("  • line {line}: {}\n", mutant.description))
+                }
+                None => message.push_str(&format!("  • {}\n", mutant.description)),
+            }
+        }
+        if survivors.len() > MAX_REPORTED_MUTANTS {
+            message.push_str(&format!(
+                "  • ...and {} more\n",
+                survivors.len() - MAX_REPORTED_MUTANTS
+            ));
+        }
+        message.push_str("\n🎯 **Quality**: The tests still pass with these changes to the code, so they don't pin down its behavior.\n\n⚠️ **STRONGLY CONSIDER**: Add assertions that would fail for the mutants above.\n");
+        message
+    };
+    if let Some(seconds) = budget_exhausted {
+        message.push_str(&format!(
+            "\n⏱️ Mutation testing stopped at its {seconds}s budget; results are partial.\n"
+        ));
+    }
+    message.push_str("\n👉 Continue with your task.");
+    AutomationResult::Success(message)
+}
+
+/// Compress sorted line numbers into ranges, e.g. `[3, 4, 5, 9]` -> `3-5, 9`
+fn format_line_ranges(lines: &[u64]) -> String {
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+    for &line in lines {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == line => *end = line,
+            _ => ranges.push((line, line)),
+        }
+    }
+
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{start}-{end}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Whether a command scoped to `scope` runs for `file_path` (relative paths are in
+/// `working_dir`), matched relative to the file's project root
+fn in_scope(scope: &PathScope, file_path: &Path, working_dir: &Path) -> bool {
+    if scope.is_unrestricted() {
+        return true;
+    }
+    let file = working_dir.join(file_path);
+    let root = PythonProject::find_project_root(file.parent().unwrap_or(Path::new(".")));
+    let allowed = scope.allows(&file, root.as_deref().unwrap_or(Path::new("/")));
+    if !allowed {
+        log::debug!("Automation not scoped to {}", file_path.display());
+    }
+    allowed
+}
+
+/// Check if a Python module contains doctest examples
+fn has_doctests(file_path: &Path) -> bool {
+    std::fs::read_to_string(file_path)
+        .map(|content| {
+            content
+                .lines()
+                .any(|line| line.trim_start().starts_with(">>>"))
+        })
+        .unwrap_or(false)
+}
+
+/// Test patterns suited to the project's web framework, appended to the "No tests
+/// found" suggestions
+fn framework_test_suggestions(framework: &Framework) -> String {
+    let patterns: &[&str] = match framework {
+        Framework::Django { .. } => &[
+            "Subclass django.test.TestCase so each test runs in a rolled-back transaction",
+            "Request views with self.client and assert on status codes and templates",
+        ],
+        Framework::FastApi => &[
+            "Add a `client` fixture returning fastapi.testclient.TestClient(app)",
+            "Swap dependencies with app.dependency_overrides instead of patching",
+            "Cover each endpoint's success, 404 and 422 validation responses",
+        ],
+        Framework::Flask => &[
+            "Add an `app` fixture from your app factory with TESTING=True",
+            "Add a `client` fixture returning app.test_client()",
+            "Cover each route's success and error responses",
+        ],
+    };
+    let mut suggestions = format!("\n\n💡 {} test patterns:", framework.describe());
+    for pattern in patterns {
+        suggestions.push_str(&format!("\n  • {pattern}"));
+    }
+    suggestions
+}
+
+/// A Django app's `tests.py` next to the edited module, when the project uses Django
+fn django_app_tests(project: &PythonProject, source_file: &Path) -> Option<PathBuf> {
+    let Some(Framework::Django { .. }) = project.framework else {
+        return None;
+    };
+    let tests = source_file.parent()?.join("tests.py");
+    tests.is_file().then_some(tests)
+}
 
-        // Search recursively in test directories
-        for base_dir in &base_test_directories {
-            if let Some(test_file) = Self::find_test_file_recursive(base_dir, &test_patterns) {
-                log::debug!("Found test file: {}", test_file.display());
-                return Some(test_file);
+impl AutomationResult {
+    /// Fold an additional blocking section into this result, kept separate from the
+    /// primary tool output
+    pub fn with_failure_section(self, section: &str) -> Self {
+        match self {
+            AutomationResult::Failure(message) => {
+                AutomationResult::Failure(format!("{message}\n\n{section}"))
             }
+            AutomationResult::Success(_)
+            | AutomationResult::Advice(_)
+            | AutomationResult::NoAction
+            | AutomationResult::Skipped => AutomationResult::Failure(section.to_string()),
         }
-
-        log::debug!(
-            "No test file found for source file: {}",
-            source_file.display()
-        );
-        None
     }
 
-    /// Recursively search for test files matching the given patterns in a directory tree
-    fn find_test_file_recursive(dir: &Path, patterns: &[String]) -> Option<std::path::PathBuf> {
-        if !dir.exists() || !dir.is_dir() {
-            return None;
+    /// Fold an additional non-blocking section into this result
+    pub fn with_warning_section(self, section: &str) -> Self {
+        match self {
+            AutomationResult::Failure(message) => {
+                AutomationResult::Failure(format!("{message}\n\n{section}"))
+            }
+            AutomationResult::Success(message) | AutomationResult::Advice(message) => {
+                AutomationResult::Success(format!("{message}\n\n{section}"))
+            }
+            AutomationResult::NoAction | AutomationResult::Skipped => {
+                AutomationResult::Success(section.to_string())
+            }
         }
+    }
 
-        // First, check current directory for matching test files
-        for pattern in patterns {
-            let test_file_path = dir.join(pattern);
-            if test_file_path.exists() && test_file_path.is_file() {
-                return Some(test_file_path);
+    /// Fold an informational section into this result; on its own it becomes advice,
+    /// which reaches Claude without interrupting it
+    pub fn with_advice_section(self, section: &str) -> Self {
+        match self {
+            AutomationResult::Failure(message) => {
+                AutomationResult::Failure(format!("{message}\n\n{section}"))
+            }
+            AutomationResult::Success(message) => {
+                AutomationResult::Success(format!("{message}\n\n{section}"))
+            }
+            AutomationResult::Advice(message) => {
+                AutomationResult::Advice(format!("{message}\n\n{section}"))
+            }
+            AutomationResult::NoAction | AutomationResult::Skipped => {
+                AutomationResult::Advice(section.to_string())
             }
         }
+    }
 
-        // Then recursively search subdirectories
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.is_dir() {
-                    // Skip hidden directories and common non-test directories
-                    if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
-                        if dir_name.starts_with('.')
-                            || dir_name == "__pycache__"
-                            || dir_name == "node_modules"
-                            || dir_name == ".git"
-                        {
-                            continue;
-                        }
-                    }
+    /// Fold a check section into this result as advice, a warning or a failure per its
+    /// severity
+    pub fn with_check_section(self, section: &str, severity: CheckSeverity) -> Self {
+        match severity {
+            CheckSeverity::Advice => self.with_advice_section(section),
+            CheckSeverity::Warn => self.with_warning_section(section),
+            CheckSeverity::Block => self.with_failure_section(section),
+        }
+    }
 
-                    // Recursively search the subdirectory
-                    if let Some(test_file) = Self::find_test_file_recursive(&path, patterns) {
-                        return Some(test_file);
-                    }
-                }
+    /// Rewrite the message's decorations in the configured `output.style`
+    pub fn render(self, style: OutputStyle) -> Self {
+        match self {
+            AutomationResult::Success(msg) => {
+                AutomationResult::Success(messages::render(&msg, style))
+            }
+            AutomationResult::Failure(msg) => {
+                AutomationResult::Failure(messages::render(&msg, style))
+            }
+            AutomationResult::Advice(msg) => {
+                AutomationResult::Advice(messages::render(&msg, style))
             }
+            AutomationResult::NoAction | AutomationResult::Skipped => self,
         }
-
-        None
     }
-}
 
-impl AutomationResult {
     /// Convert to appropriate exit code for Claude Code hooks
     pub fn exit_code(&self) -> i32 {
         match self {
-            AutomationResult::NoAction | AutomationResult::Skipped => 0,
+            AutomationResult::NoAction
+            | AutomationResult::Skipped
+            | AutomationResult::Advice(_) => 0,
             AutomationResult::Success(_) | AutomationResult::Failure(_) => 2,
         }
     }
@@ -744,7 +4133,9 @@ impl AutomationResult {
     /// Get message to display to user (if any)
     pub fn message(&self) -> Option<&str> {
         match self {
-            AutomationResult::Success(msg) | AutomationResult::Failure(msg) => Some(msg),
+            AutomationResult::Success(msg)
+            | AutomationResult::Failure(msg)
+            | AutomationResult::Advice(msg) => Some(msg),
             AutomationResult::NoAction | AutomationResult::Skipped => None,
         }
     }
@@ -764,7 +4155,7 @@ mod tests {
     fn create_test_runner() -> AutomationRunner {
         let config = AutomationConfig::default();
         let checker = GuardrailsChecker::from_config(default_config()).unwrap();
-        AutomationRunner::new(config, checker)
+        AutomationRunner::new(config, checker, Path::new("."))
     }
 
     #[test]
@@ -784,6 +4175,89 @@ mod tests {
         assert_eq!(AutomationResult::Skipped.exit_code(), 0);
         assert_eq!(AutomationResult::Success("test".to_string()).exit_code(), 2);
         assert_eq!(AutomationResult::Failure("test".to_string()).exit_code(), 2);
+        assert_eq!(AutomationResult::Advice("test".to_string()).exit_code(), 0);
+    }
+
+    #[test]
+    fn test_severity_mapping() {
+        let config = AutomationConfig {
+            severity: SeverityConfig {
+                test_failure: FindingBehavior::Warn,
+                missing_tests: FindingBehavior::Advice,
+                type_error: FindingBehavior::Silent,
+                ..SeverityConfig::default()
+            },
+            ..AutomationConfig::default()
+        };
+        let runner = AutomationRunner::new(
+            config,
+            GuardrailsChecker::from_config(default_config()).unwrap(),
+            Path::new("."),
+        );
+        let failure = || AutomationResult::Failure("⛔ finding".to_string());
+
+        assert!(runner
+            .with_severity(FindingCategory::LintError, failure())
+            .is_failure());
+        assert!(matches!(
+            runner.with_severity(FindingCategory::TestFailure, failure()),
+            AutomationResult::Success(_)
+        ));
+        assert!(matches!(
+            runner.with_severity(FindingCategory::MissingTests, failure()),
+            AutomationResult::Advice(_)
+        ));
+        assert!(matches!(
+            runner.with_severity(FindingCategory::TypeError, failure()),
+            AutomationResult::NoAction
+        ));
+
+        let passing = AutomationResult::Success("👉 Lints pass.".to_string());
+        let result = runner.with_finding(passing, FindingCategory::TypeError, "⛔ TYPE ERRORS");
+        assert!(matches!(&result, AutomationResult::Success(message) if !message.contains("TYPE")));
+        assert!(runner
+            .with_finding(result, FindingCategory::CoverageShortfall, "⛔ COVERAGE")
+            .is_failure());
+    }
+
+    #[test]
+    fn test_advice_sections() {
+        let advice = AutomationResult::NoAction.with_advice_section("💡 note");
+        assert!(matches!(&advice, AutomationResult::Advice(message) if message == "💡 note"));
+
+        // A warning or failure alongside advice still interrupts Claude
+        let warned = advice.with_warning_section("⚠️ warning");
+        assert!(
+            matches!(&warned, AutomationResult::Success(message) if message.contains("💡 note"))
+        );
+        let failed =
+            AutomationResult::Failure("⛔ broken".to_string()).with_advice_section("💡 note");
+        assert!(failed.is_failure());
+        assert!(matches!(
+            AutomationResult::Skipped.with_check_section("💡 docstrings", CheckSeverity::Advice),
+            AutomationResult::Advice(_)
+        ));
+
+        let config = AutomationConfig {
+            advice: vec![AdviceFinding::PassingLint, AdviceFinding::SkippedSteps],
+            ..AutomationConfig::default()
+        };
+        let runner = AutomationRunner::new(
+            config,
+            GuardrailsChecker::from_config(default_config()).unwrap(),
+            Path::new("."),
+        );
+        let passing = AutomationResult::Success("👉 Lints pass.".to_string());
+        let advice = runner.as_advice(AdviceFinding::PassingLint, passing);
+        assert!(matches!(advice, AutomationResult::Advice(_)));
+        let noted = runner.with_note(advice, AdviceFinding::SkippedSteps, "⏱️ skipped");
+        assert!(matches!(noted, AutomationResult::Advice(_)));
+        let noted = runner.with_note(noted, AdviceFinding::TestSmells, "⚠️ smells");
+        assert!(matches!(noted, AutomationResult::Success(_)));
+        let failure = AutomationResult::Failure("⛔ E501".to_string());
+        assert!(runner
+            .as_advice(AdviceFinding::PassingLint, failure)
+            .is_failure());
     }
 
     #[test]
@@ -800,6 +4274,447 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_doctest_detection_and_reporting() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let module = temp_dir.path().join("mathutils.py");
+        std::fs::write(
+            &module,
+            "def double(x):\n    \"\"\"\n    >>> double(2)\n    4\n    \"\"\"\n    return x * 2\n",
+        )?;
+        assert!(has_doctests(&module));
+
+        let plain = temp_dir.path().join("plain.py");
+        std::fs::write(&plain, "def double(x):\n    return x * 2\n")?;
+        assert!(!has_doctests(&plain));
+
+        let result =
+            AutomationResult::Success("ok".to_string()).with_failure_section("⛔ DOCTESTS FAILED");
+        assert!(result.is_failure());
+        assert_eq!(result.message(), Some("⛔ DOCTESTS FAILED"));
+
+        let result = AutomationResult::Failure("⛔ TESTS FAILED".to_string())
+            .with_failure_section("⛔ DOCTESTS FAILED");
+        let message = result.message().unwrap();
+        assert!(message.starts_with("⛔ TESTS FAILED"));
+        assert!(message.contains("DOCTESTS FAILED"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_formatter_selection() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut project = PythonProject::discover(temp_dir.path())?;
+        project.available_formatters = vec![PythonFormatter::Black, PythonFormatter::Ruff];
+        let runner = |config: AutomationConfig| {
+            AutomationRunner::new(
+                config,
+                GuardrailsChecker::from_config(default_config()).unwrap(),
+                Path::new("."),
+            )
+        };
+
+        let default = runner(AutomationConfig::default());
+        assert_eq!(default.formatter(&project), Some(&PythonFormatter::Black));
+
+        let ruff = runner(AutomationConfig {
+            format_tool: Some(PythonFormatter::Ruff),
+            ..AutomationConfig::default()
+        });
+        assert_eq!(ruff.formatter(&project), Some(&PythonFormatter::Ruff));
+
+        let disabled = runner(AutomationConfig {
+            format_enabled: false,
+            ..AutomationConfig::default()
+        });
+        assert_eq!(disabled.formatter(&project), None);
+
+        project.available_formatters = vec![PythonFormatter::Black];
+        assert_eq!(ruff.formatter(&project), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reproduce_section() {
+        let output = "FAILED tests/test_x.py::test_y[case-3] - assert 3 == 4\nFAILED tests/test_x.py::test_y[case-5] - assert 5 == 6\nFAILED tests/test_x.py::test_z - KeyError: 'a'\n";
+        let section = reproduce_section(output, "python -m pytest");
+        assert!(section.contains("  • tests/test_x.py::test_y: case-3, case-5\n"));
+        assert!(section.contains("  • python -m pytest 'tests/test_x.py::test_y[case-5]' -x -vv\n"));
+        assert!(section.contains("  • python -m pytest 'tests/test_x.py::test_z' -x -vv\n"));
+        assert!(!section.contains("test_z:"));
+        assert_eq!(reproduce_section("1 passed in 0.01s\n", "pytest"), "");
+    }
+
+    #[test]
+    fn test_changed_test_selection() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(root)
+                .output()
+        };
+        git(&["init", "-q"])?;
+        let source = root.join("calc.py");
+        let test_file = root.join("test_calc.py");
+        std::fs::write(
+            &source,
+            "def add(a, b):\n    return a + b\n\n\ndef sub(a, b):\n    return a - b\n",
+        )?;
+        std::fs::write(
+            &test_file,
+            "from calc import add, sub\n\n\ndef test_add():\n    assert add(1, 2) == 3\n\n\ndef test_sub():\n    assert sub(3, 2) == 1\n",
+        )?;
+        git(&["add", "."])?;
+        git(&["commit", "-qm", "initial"])?;
+
+        assert_eq!(changed_test_selection(&source, &test_file, root), None);
+        std::fs::write(
+            &source,
+            "def add(a, b):\n    return a + b\n\n\ndef sub(a, b):\n    return b - a\n",
+        )?;
+        assert_eq!(
+            changed_test_selection(&source, &test_file, root),
+            Some("test_sub".to_string())
+        );
+
+        // Module-level changes may affect every test
+        std::fs::write(
+            &source,
+            "import math\n\n\ndef add(a, b):\n    return a + b\n\n\ndef sub(a, b):\n    return b - a\n",
+        )?;
+        assert_eq!(changed_test_selection(&source, &test_file, root), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mutation_result_reports_survivors() {
+        let survivors: Vec<SurvivingMutant> = (1..=7)
+            .map(|id| SurvivingMutant {
+                id: id.to_string(),
+                line: Some(id + 1),
+                description: "`return a + b` → `return a - b`".to_string(),
+            })
+            .collect();
+        let result = mutation_result(Path::new("src/calc.py"), Some(20), &survivors, Some(60));
+        assert!(!result.is_failure());
+        let message = result.message().unwrap();
+        assert!(message
+            .contains("7 mutant(s) in the edited functions of calc.py survived (of 20 generated)"));
+        assert!(message.contains("  • line 2: `return a + b` → `return a - b`"));
+        assert!(!message.contains("line 7:"));
+        assert!(message.contains("...and 2 more"));
+        assert!(message.contains("stopped at its 60s budget"));
+
+        let clean = mutation_result(Path::new("src/calc.py"), None, &[], None);
+        assert!(clean
+            .message()
+            .unwrap()
+            .starts_with("🧬 No mutants in the edited functions of calc.py survived."));
+    }
+
+    #[test]
+    fn test_test_smells_section() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let test_file = temp_dir.path().join("tests/test_calc.py");
+        std::fs::create_dir_all(test_file.parent().unwrap())?;
+        std::fs::write(
+            &test_file,
+            "def test_add():\n    assert add(1, 2) == 3\n    assert add(0, 0) == 0\n\n\ndef test_sub():\n    sub(1, 2)\n",
+        )?;
+
+        let runner = create_test_runner();
+        let section = runner
+            .check_test_smells(&test_file, temp_dir.path())
+            .unwrap();
+        assert!(section.starts_with(
+            "⚠️ TEST SMELLS in tests/test_calc.py (2 tests, 1.0 assertions per test):"
+        ));
+        assert!(section.contains("  • test_sub (line 6): no assertions"));
+
+        let quiet = AutomationRunner::new(
+            AutomationConfig {
+                test_smells: false,
+                ..AutomationConfig::default()
+            },
+            GuardrailsChecker::from_config(default_config())?,
+            Path::new("."),
+        );
+        assert!(quiet
+            .check_test_smells(&test_file, temp_dir.path())
+            .is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_check_tests_updated() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        std::fs::write(root.join("pyproject.toml"), "[project]\nname = \"shop\"\n")?;
+        std::fs::create_dir_all(root.join("src/shop"))?;
+        std::fs::create_dir_all(root.join("tests"))?;
+        let source = root.join("src/shop/orders.py");
+        let test_file = root.join("tests/test_orders.py");
+        std::fs::write(&source, "def total(order):\n    return sum(order)\n")?;
+        std::fs::write(&test_file, "from shop.orders import total\n")?;
+
+        let runner = AutomationRunner::new(
+            AutomationConfig {
+                test_require_updates: vec!["src/shop".to_string()],
+                ..AutomationConfig::default()
+            },
+            GuardrailsChecker::from_config(default_config())?,
+            Path::new("."),
+        );
+        let mut edits = SessionEdits::load(root)?;
+        edits.record("s1", &source)?;
+
+        let result = runner.check_tests_updated(root, "s1").await?;
+        assert!(matches!(result, AutomationResult::Failure(_)));
+        let message = result.message().unwrap();
+        assert!(message.starts_with("⛔ TESTS NOT UPDATED:"));
+        assert!(message.contains("  • src/shop/orders.py (tests: tests/test_orders.py)"));
+
+        edits.record("s1", &test_file)?;
+        assert!(matches!(
+            runner.check_tests_updated(root, "s1").await?,
+            AutomationResult::NoAction
+        ));
+        assert!(matches!(
+            runner.check_tests_updated(root, "other").await?,
+            AutomationResult::NoAction
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_coverage_report_parsing() {
+        let root = Path::new("/project");
+        let report = serde_json::json!({
+            "files": {
+                "src/app.py": {
+                    "summary": {"percent_covered": 62.5},
+                    "missing_lines": [3, 4, 5, 9, 12, 13]
+                }
+            }
+        });
+
+        let coverage = FileCoverage::from_json(&report, &root.join("src/app.py"), root).unwrap();
+        assert_eq!(coverage.percent_covered, 62.5);
+        assert_eq!(format_line_ranges(&coverage.missing_lines), "3-5, 9, 12-13");
+
+        assert!(FileCoverage::from_json(&report, &root.join("src/other.py"), root).is_none());
+        assert_eq!(format_line_ranges(&[]), "");
+    }
+
+    #[test]
+    fn test_complexity_gate() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        std::fs::write(temp_dir.path().join("pyproject.toml"), "")?;
+        let module = temp_dir.path().join("logic.py");
+        std::fs::write(
+            &module,
+            "def tangled(a, b):\n    if a and b:\n        return 1\n    elif a or b:\n        return 2\n    return 3\n",
+        )?;
+        let project = PythonProject::discover(temp_dir.path())?;
+
+        let config = AutomationConfig {
+            lint_max_complexity: Some(3),
+            ..AutomationConfig::default()
+        };
+        let checker = GuardrailsChecker::from_config(default_config())?;
+        let runner = AutomationRunner::new(config, checker, Path::new("."));
+
+        let section = runner.check_complexity(&project, &module).unwrap();
+        assert!(section.contains("tangled (line 1): complexity 5 (max 3)"));
+
+        let lenient = AutomationRunner::new(
+            AutomationConfig {
+                lint_max_complexity: Some(5),
+                ..AutomationConfig::default()
+            },
+            GuardrailsChecker::from_config(default_config())?,
+            Path::new("."),
+        );
+        assert!(lenient.check_complexity(&project, &module).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_docstring_check_severity() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        std::fs::write(temp_dir.path().join("pyproject.toml"), "")?;
+        std::fs::create_dir_all(temp_dir.path().join("migrations"))?;
+        let module = temp_dir.path().join("api.py");
+        let migration = temp_dir.path().join("migrations/0001_initial.py");
+        for file in [&module, &migration] {
+            std::fs::write(file, "def handler(event):\n    return event\n")?;
+        }
+        let project = PythonProject::discover(temp_dir.path())?;
+
+        let runner_with = |docstrings: DocstringConfig| {
+            AutomationRunner::new(
+                AutomationConfig {
+                    lint_docstrings_exclude: PathScope::new(vec![], docstrings.exclude.clone()),
+                    lint_docstrings: docstrings,
+                    ..AutomationConfig::default()
+                },
+                GuardrailsChecker::from_config(default_config()).unwrap(),
+                Path::new("."),
+            )
+        };
+
+        assert!(runner_with(DocstringConfig::default())
+            .check_docstrings(&project, &module)
+            .is_none());
+
+        let runner = runner_with(DocstringConfig {
+            enabled: true,
+            severity: CheckSeverity::Warn,
+            exclude: vec![],
+        });
+        let section = runner.check_docstrings(&project, &module).unwrap();
+        assert!(section.contains("function handler (line 1)"));
+        let result = AutomationResult::Success("👉 Lints pass.".to_string())
+            .with_check_section(&section, CheckSeverity::Warn);
+        assert!(!result.is_failure());
+
+        let result = AutomationResult::Success("👉 Lints pass.".to_string())
+            .with_check_section(&section, CheckSeverity::Block);
+        assert!(result.is_failure());
+
+        // Exclusions are relative to the project root
+        let excluded = runner_with(DocstringConfig {
+            enabled: true,
+            severity: CheckSeverity::Block,
+            exclude: vec!["migrations/*.py".to_string()],
+        });
+        assert!(excluded.check_docstrings(&project, &migration).is_none());
+        assert!(excluded.check_docstrings(&project, &module).is_some());
+
+        // Invalid patterns are config errors
+        let invalid = "exclude:\n  patterns: []\nautomation:\n  lint:\n    docstrings:\n      exclude: [\"[\"]\n";
+        assert!(GuardrailsChecker::from_yaml(invalid).is_err());
+        assert!(GuardrailsChecker::from_yaml(&invalid.replace("\"[\"", "tests/**")).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_license_header_enforcement() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        std::fs::write(temp_dir.path().join("pyproject.toml"), "")?;
+        std::fs::create_dir_all(temp_dir.path().join("src/app"))?;
+        let module = temp_dir.path().join("src/app/service.py");
+        let script = temp_dir.path().join("scripts/deploy.py");
+        std::fs::create_dir_all(temp_dir.path().join("scripts"))?;
+        std::fs::write(&module, "import os\n")?;
+        std::fs::write(&script, "import os\n")?;
+        let project = PythonProject::discover(temp_dir.path())?;
+
+        // Globs are relative to the project root
+        let yaml = |auto_insert: bool| {
+            format!(
+                "exclude:\n  patterns: []\nrules:\n  license_header:\n    template: \"# Copyright {{year}} Example\"\n    globs: [\"src/**/*.py\"]\n    auto_insert: {auto_insert}\n"
+            )
+        };
+
+        let runner = AutomationRunner::new(
+            AutomationConfig::default(),
+            GuardrailsChecker::from_yaml(&yaml(false))?,
+            Path::new("."),
+        );
+        let (section, severity) = runner.check_license_header(&project, &module).unwrap();
+        assert_eq!(severity, CheckSeverity::Block);
+        assert!(section.contains("MISSING LICENSE HEADER"));
+        assert!(runner.check_license_header(&project, &script).is_none());
+
+        let runner = AutomationRunner::new(
+            AutomationConfig::default(),
+            GuardrailsChecker::from_yaml(&yaml(true))?,
+            Path::new("."),
+        );
+        let (_, severity) = runner.check_license_header(&project, &module).unwrap();
+        assert_eq!(severity, CheckSeverity::Warn);
+        assert!(std::fs::read_to_string(&module)?.starts_with("# Copyright "));
+        assert!(runner.check_license_header(&project, &module).is_none());
+        assert_eq!(std::fs::read_to_string(&script)?, "import os\n");
+
+        // Invalid globs are config errors
+        let invalid = "exclude:\n  patterns: []\nrules:\n  license_header:\n    template: \"# Copyright\"\n    globs: [\"[\"]\n";
+        assert!(GuardrailsChecker::from_yaml(invalid).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_test_file_prefers_closest_package() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        std::fs::write(root.join("pyproject.toml"), "")?;
+        let files = [
+            "services/billing/pyproject.toml",
+            "services/billing/src/billing/__init__.py",
+            "services/billing/src/billing/core/__init__.py",
+            "services/billing/src/billing/core/models.py",
+            "services/billing/tests/core/test_models.py",
+            "services/billing/tests/test_models.py",
+            "services/auth/tests/test_models.py",
+            "tests/test_models.py",
+        ];
+        for file in files {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(path, "")?;
+        }
+
+        let runner = AutomationRunner::new(
+            AutomationConfig::default(),
+            GuardrailsChecker::from_config(default_config())?,
+            Path::new("."),
+        );
+        let source = root.join("services/billing/src/billing/core/models.py");
+        assert_eq!(
+            runner.find_test_file_for_source(&source, root),
+            Some(root.join("services/billing/tests/core/test_models.py"))
+        );
+
+        // A test inside the source's own package beats the sub-project's tests directory
+        let colocated = root.join("services/billing/src/billing/tests/test_models.py");
+        std::fs::create_dir_all(colocated.parent().unwrap())?;
+        std::fs::write(&colocated, "")?;
+        assert_eq!(
+            runner.find_test_file_for_source(&source, root),
+            Some(colocated)
+        );
+
+        // Declared mappings win over the search, and a missing mapped file means no tests
+        let config = AutomationConfig {
+            test_map: TestFileMap::new(vec![(
+                "services/*/src/**/*.py".to_string(),
+                "services/auth/tests/test_{stem}.py".to_string(),
+            )]),
+            ..AutomationConfig::default()
+        };
+        let runner = AutomationRunner::new(
+            config,
+            GuardrailsChecker::from_config(default_config())?,
+            Path::new("."),
+        );
+        assert_eq!(
+            runner.find_test_file_for_source(&source, root),
+            Some(root.join("services/auth/tests/test_models.py"))
+        );
+        let unmapped = root.join("services/billing/src/billing/core/__init__.py");
+        assert_eq!(runner.find_test_file_for_source(&unmapped, root), None);
+
+        Ok(())
+    }
+
     #[test]
     fn test_command_timeout() -> Result<()> {
         let runner = create_test_runner();
@@ -816,6 +4731,223 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_streamed_command_still_captures_output() -> Result<()> {
+        let runner = create_test_runner();
+        let temp_dir = TempDir::new()?;
+
+        let output = runner.run_command(
+            "sh",
+            &["-c", "seq 1 5000; echo done >&2"],
+            temp_dir.path(),
+            5,
+            true,
+            &[],
+        )?;
+        assert!(output.success);
+        assert_eq!(output.stdout.lines().count(), 5000);
+        assert_eq!(output.stderr.trim(), "done");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lint_ignore_rules_pass_only_ignored_findings() {
+        let config = AutomationConfig {
+            lint_ignore_rules: vec!["E501".to_string()],
+            ..AutomationConfig::default()
+        };
+        let checker = GuardrailsChecker::from_config(default_config()).unwrap();
+        let runner = AutomationRunner::new(config, checker, Path::new("."));
+        let output = CommandOutput {
+            success: false,
+            stdout: "app.py:3:89: E501 Line too long (120 > 88)\nFound 1 error.".to_string(),
+            stderr: String::new(),
+        };
+
+        let result = runner
+            .lint_failure_result(&output, Path::new("."), &TimeBudget::unlimited())
+            .await;
+        assert!(!result.is_failure());
+    }
+
+    #[tokio::test]
+    async fn test_lint_errors_block_without_ai() {
+        let runner = create_test_runner();
+        let output = CommandOutput {
+            success: false,
+            stdout: r#"[{"code": "F821", "message": "Undefined name `foo`", "filename": "app.py", "location": {"row": 2, "column": 5}, "fix": null}]"#.to_string(),
+            stderr: String::new(),
+        };
+
+        let result = runner
+            .lint_failure_result(&output, Path::new("."), &TimeBudget::unlimited())
+            .await;
+        assert!(result.is_failure());
+        let message = result.message().unwrap();
+        assert!(message.starts_with("⛔ LINT ERRORS FOUND (1 error(s))"));
+        assert!(message.contains("app.py:2:5: F821 Undefined name `foo`"));
+    }
+
+    #[tokio::test]
+    async fn test_library_entry_points_without_stdin() -> Result<()> {
+        let runner = create_test_runner();
+        let temp_dir = TempDir::new()?;
+        let missing = temp_dir.path().join("missing.py");
+
+        let report = runner.run_lint_for_file(&missing).await?;
+        assert_eq!(report.operation, "lint");
+        assert_eq!(report.file.as_deref(), Some(missing.as_path()));
+        assert!(report.message().is_none());
+        assert!(report.steps.is_empty());
+        let report = runner.run_test_for_file(&missing).await?;
+        assert_eq!(report.exit_code(), 0);
+
+        // Hook events other than edits are ignored
+        let hook_input = HookInput::builder("PreToolUse")
+            .tool("Edit")
+            .file_path("app.py")
+            .build();
+        let report = runner.handle_smart_lint(&hook_input).await?;
+        assert!(report.file.is_none());
+        assert!(matches!(report.result, AutomationResult::NoAction));
+        Ok(())
+    }
+
+    #[test]
+    fn test_remember_false_positives() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut suppressions = Suppressions::load(temp_dir.path())?;
+        let output = "app.py:1:8: F401 `os` imported but unused\n\
+                      app.py:3:89: E501 Line too long (120 > 88)";
+        // A paraphrased filtered output doesn't make the omitted finding a false positive
+        let analysis = LintAnalysis {
+            has_real_issues: true,
+            filtered_output: "Remove the unused `os` import".to_string(),
+            reasoning: String::new(),
+            issue_count: 1,
+            recommendations: String::new(),
+            false_positives: vec![],
+            confidence: 1.0,
+        };
+        remember_false_positives(&mut suppressions, output, &analysis);
+        assert!(suppressions.entries().is_empty());
+
+        let analysis = LintAnalysis {
+            false_positives: vec![" app.py:3:89: E501 Line too long (120 > 88)".to_string()],
+            ..analysis
+        };
+        remember_false_positives(&mut suppressions, output, &analysis);
+        assert_eq!(suppressions.entries().len(), 1);
+        assert_eq!(suppressions.entries()[0].rule.as_deref(), Some("E501"));
+
+        // Nothing is inferred from an "all false positives" verdict without the list
+        let analysis = LintAnalysis {
+            has_real_issues: false,
+            filtered_output: String::new(),
+            false_positives: vec![],
+            ..analysis
+        };
+        remember_false_positives(&mut suppressions, output, &analysis);
+        assert_eq!(suppressions.entries().len(), 1);
+
+        suppressions.clear()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_budget_skips_optional_steps_when_low() {
+        let unlimited = TimeBudget::unlimited();
+        assert!(unlimited.allows("formatting"));
+        assert_eq!(unlimited.timeout(20), 20);
+        assert_eq!(unlimited.skipped_section(), None);
+
+        let budget = TimeBudget::new(Some(30));
+        assert!(budget.allows("formatting"));
+        assert_eq!(budget.timeout(20), 20);
+        assert!(budget.timeout(60) <= 30);
+
+        let exhausted = TimeBudget {
+            started: Instant::now() - Duration::from_secs(28),
+            ..TimeBudget::new(Some(30))
+        };
+        assert!(!exhausted.allows("type check"));
+        assert!(!exhausted.allows("AI analysis"));
+        assert!(exhausted.timeout(20) <= 2);
+        assert_eq!(
+            exhausted.skipped_section().as_deref(),
+            Some("⏱️ Skipped type check, AI analysis to stay within the 30s time budget (`automation.total_budget_seconds`).")
+        );
+
+        let spent = TimeBudget {
+            started: Instant::now() - Duration::from_secs(40),
+            ..TimeBudget::new(Some(30))
+        };
+        assert_eq!(spent.timeout(20), 1);
+    }
+
+    #[test]
+    fn test_output_relay_rate_limits_lines() {
+        let mut relay = OutputRelay::new(Duration::from_secs(60));
+        assert!(relay.line("first"));
+        assert!(!relay.line("second"));
+        assert!(!relay.line("third"));
+        assert_eq!(relay.skipped, 2);
+        relay.finish();
+        assert_eq!(relay.skipped, 0);
+
+        let mut relay = OutputRelay::new(Duration::ZERO);
+        assert!(relay.line("first"));
+        assert!(relay.line("second"));
+    }
+
+    #[test]
+    fn test_in_scope() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        std::fs::write(root.join("pyproject.toml"), "[project]\nname = \"shop\"\n")?;
+        std::fs::create_dir_all(root.join("tests"))?;
+        std::fs::create_dir_all(root.join("examples"))?;
+
+        let scope = PathScope::new(vec![], vec!["tests/**".to_string(), "examples".to_string()]);
+        assert!(in_scope(&scope, &root.join("app.py"), root));
+        assert!(!in_scope(&scope, &root.join("tests/test_app.py"), root));
+        assert!(!in_scope(&scope, &root.join("examples/demo.py"), root));
+        // Relative paths are in the session's working directory
+        assert!(!in_scope(&scope, Path::new("tests/test_app.py"), root));
+        assert!(in_scope(
+            &PathScope::default(),
+            &root.join("tests/test_app.py"),
+            root
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_overrides_merge_into_runner_config() -> Result<()> {
+        let checker = GuardrailsChecker::from_yaml(
+            "exclude:\n  patterns: []\noverrides:\n  - files: \"**/scripts/**\"\n    automation:\n      test:\n        enabled: false\n",
+        )?;
+        let config = AutomationConfig {
+            lint_enabled: false,
+            test_timeout_seconds: 7,
+            ..AutomationConfig::default()
+        };
+        let runner = AutomationRunner::new(config, checker, Path::new("."));
+
+        // Only the overridden setting changes; the programmatic ones survive
+        let overridden = runner
+            .overridden_for(Path::new("/project/scripts/seed.py"))
+            .unwrap();
+        assert!(!overridden.config.test_enabled);
+        assert!(!overridden.config.lint_enabled);
+        assert_eq!(overridden.config.test_timeout_seconds, 7);
+        assert!(runner
+            .overridden_for(Path::new("/project/app.py"))
+            .is_none());
+        Ok(())
+    }
+
     #[test]
     fn test_runner_creation() {
         let config = AutomationConfig {
@@ -825,14 +4957,68 @@ mod tests {
             test_cooldown_seconds: 3,
             lint_timeout_seconds: 30,
             test_timeout_seconds: 25,
+            test_session: Some("unit".to_string()),
+            test_doctests: true,
+            test_property_analysis: false,
+            test_changed_functions_only: true,
+            test_smells: false,
+            test_require_updates: vec!["src/shop".to_string()],
+            test_auto_update_snapshots: AutoUpdateSnapshots::Never,
+            test_min_coverage_percent: Some(80.0),
+            lint_max_complexity: Some(10),
+            lint_docstrings: DocstringConfig::default(),
+            lint_docstrings_exclude: PathScope::default(),
+            lint_imports: ImportHygieneConfig::default(),
+            lint_escalate_after: 0,
+            test_escalate_after: 5,
+            test_stream_output: true,
+            lint_ignore_rules: vec!["E501".to_string()],
+            lint_autofix: false,
+            lint_unsafe_fixes: true,
+            format_enabled: false,
+            format_tool: Some(PythonFormatter::Ruff),
+            format_sort_imports: false,
+            typecheck_enabled: true,
+            typecheck_tool: Some(PythonTypeChecker::Pyright),
+            lint_extra_args: vec!["--select".to_string(), "E,F".to_string()],
+            test_extra_args: vec!["-x".to_string()],
+            test_junit_xml: Some(PathBuf::from("junit.xml")),
+            test_map: TestFileMap::new(vec![(
+                "src/*.py".to_string(),
+                "tests/test_{stem}.py".to_string(),
+            )]),
+            lint_cooldown_scope: CooldownScope::File,
+            test_cooldown_scope: CooldownScope::Project,
+            lint_lock_wait_seconds: 0,
+            test_lock_wait_seconds: 0,
+            lint_dependency_audit: true,
+            lint_lockfile_check: true,
+            mutate_enabled: true,
+            mutate_tool: Some(MutationTool::Mutmut),
+            mutate_timeout_seconds: 30,
+            total_budget_seconds: Some(45),
+            cache_results: false,
+            advice: vec![AdviceFinding::MissingTests],
+            severity: SeverityConfig {
+                lint_warning: FindingBehavior::Warn,
+                ..SeverityConfig::default()
+            },
+            search: SearchLimits {
+                skip_dirs: vec!["fixtures".to_string()],
+                max_depth: 2,
+            },
+            lint_scope: PathScope::new(vec!["src/**".to_string()], vec![]),
+            test_scope: PathScope::new(vec![], vec!["tests/**".to_string()]),
+            mutate_scope: PathScope::default(),
         };
 
         let checker = GuardrailsChecker::from_config(default_config()).unwrap();
-        let runner = AutomationRunner::new(config.clone(), checker);
+        let runner = AutomationRunner::new(config.clone(), checker, Path::new("."));
 
         assert!(!runner.config.lint_enabled);
         assert!(runner.config.test_enabled);
         assert_eq!(runner.config.lint_cooldown_seconds, 5);
         assert_eq!(runner.config.test_cooldown_seconds, 3);
+        assert_eq!(runner.config.test_session.as_deref(), Some("unit"));
     }
 }
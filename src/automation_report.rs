@@ -0,0 +1,140 @@
+use crate::automation::AutomationResult;
+use crate::cerebras::{LintAnalysis, TestFailureAnalysis};
+use crate::lint::LintFinding;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// An external command a run executed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepReport {
+    /// Executable name, e.g. `ruff`
+    pub program: String,
+    /// Full command line
+    pub command: String,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub timed_out: bool,
+}
+
+/// An AI analysis a run received
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AiAnalysisReport {
+    Lint(LintAnalysis),
+    Test(TestFailureAnalysis),
+}
+
+/// Everything a lint or test hook run did, with the message shown to Claude derived
+/// from it; `lint --json` and `test --json` print it
+#[derive(Debug, Clone, Serialize)]
+pub struct AutomationReport {
+    /// "lint" or "test"
+    pub operation: String,
+    /// File the run was for; `None` when the hook input named none
+    pub file: Option<PathBuf>,
+    /// Outcome and message
+    pub result: AutomationResult,
+    pub duration_ms: u64,
+    /// Whether a cached verdict was reused instead of running tools
+    pub cache_hit: bool,
+    /// Commands run, in order of completion
+    pub steps: Vec<StepReport>,
+    /// Optional steps left out to stay within `automation.total_budget_seconds`
+    pub skipped_steps: Vec<String>,
+    /// Linter findings that made the run fail
+    pub findings: Vec<LintFinding>,
+    /// Node ids of failed tests
+    pub failed_tests: Vec<String>,
+    pub ai_analyses: Vec<AiAnalysisReport>,
+}
+
+impl AutomationReport {
+    /// Report of a run that produced `result` without anything recorded
+    pub fn new(operation: &str, file: Option<PathBuf>, result: AutomationResult) -> Self {
+        Self::from_trace(operation, file, result, Duration::ZERO, RunTrace::default())
+    }
+
+    /// Report of a run that had nothing to do
+    pub fn no_action(operation: &str) -> Self {
+        Self::new(operation, None, AutomationResult::NoAction)
+    }
+
+    pub(crate) fn from_trace(
+        operation: &str,
+        file: Option<PathBuf>,
+        result: AutomationResult,
+        duration: Duration,
+        trace: RunTrace,
+    ) -> Self {
+        Self {
+            operation: operation.to_string(),
+            file,
+            result,
+            duration_ms: duration.as_millis() as u64,
+            cache_hit: trace.cache_hit,
+            steps: trace.steps,
+            skipped_steps: trace.skipped_steps,
+            findings: trace.findings,
+            failed_tests: trace.failed_tests,
+            ai_analyses: trace.ai_analyses,
+        }
+    }
+
+    /// Message to show Claude (if any)
+    pub fn message(&self) -> Option<&str> {
+        self.result.message()
+    }
+
+    /// Exit code for Claude Code hooks
+    pub fn exit_code(&self) -> i32 {
+        self.result.exit_code()
+    }
+}
+
+/// What the runner records while a report is being built
+#[derive(Debug, Default)]
+pub(crate) struct RunTrace {
+    pub cache_hit: bool,
+    pub steps: Vec<StepReport>,
+    pub skipped_steps: Vec<String>,
+    pub findings: Vec<LintFinding>,
+    pub failed_tests: Vec<String>,
+    pub ai_analyses: Vec<AiAnalysisReport>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_json() {
+        let trace = RunTrace {
+            steps: vec![StepReport {
+                program: "ruff".to_string(),
+                command: "ruff check app.py".to_string(),
+                duration_ms: 12,
+                success: false,
+                timed_out: false,
+            }],
+            findings: crate::lint::parse_findings("app.py:1:1: F821 Undefined name `y`"),
+            ..RunTrace::default()
+        };
+        let report = AutomationReport::from_trace(
+            "lint",
+            Some(PathBuf::from("app.py")),
+            AutomationResult::Failure("⛔ LINT ERRORS FOUND".to_string()),
+            Duration::from_millis(40),
+            trace,
+        );
+
+        assert_eq!(report.message(), Some("⛔ LINT ERRORS FOUND"));
+        assert_eq!(report.exit_code(), 2);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["result"]["outcome"], "failure");
+        assert_eq!(json["result"]["message"], "⛔ LINT ERRORS FOUND");
+        assert_eq!(json["steps"][0]["program"], "ruff");
+        assert_eq!(json["findings"][0]["rule"], "F821");
+        assert_eq!(json["duration_ms"], 40);
+    }
+}
@@ -0,0 +1,253 @@
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Compiles stdin (without writing bytecode) and prints the syntax error, if any
+const COMPILE_SCRIPT: &str = "import sys
+try:
+    compile(sys.stdin.buffer.read(), sys.argv[1], 'exec')
+except SyntaxError as e:
+    print(f'SyntaxError: {e.msg} on line {e.lineno}')
+    sys.exit(1)
+";
+
+/// Copy of a file taken before an import sorter, formatter or auto-fix rewrites it, so a
+/// tool that corrupts the file can't lose the user's code
+#[derive(Debug, Clone)]
+pub struct FileBackup {
+    path: PathBuf,
+    content: Vec<u8>,
+}
+
+impl FileBackup {
+    /// Snapshot the current content of `path`
+    pub fn capture(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            content: std::fs::read(path)?,
+        })
+    }
+
+    /// Whether the file differs from the backup (a missing file counts as changed)
+    pub fn changed(&self) -> bool {
+        std::fs::read(&self.path).ok().as_deref() != Some(self.content.as_slice())
+    }
+
+    /// Why the rewritten file looks corrupted, if it does
+    ///
+    /// Only problems the backup didn't already have count, so a file the user left
+    /// half-edited isn't blamed on the tool.
+    pub fn corruption(&self) -> Option<String> {
+        let current = match std::fs::read(&self.path) {
+            Ok(current) => current,
+            Err(e) => return Some(format!("file unreadable: {e}")),
+        };
+        if current == self.content || structure_error(&self.content).is_some() {
+            return None;
+        }
+        if current.is_empty() {
+            return Some("file emptied".to_string());
+        }
+        structure_error(&current)
+    }
+
+    /// Syntax error the rewritten file has and the backup didn't, found by compiling
+    /// both with `interpreter`; `None` when the interpreter can't be run
+    pub fn syntax_breakage(&self, interpreter: &Path) -> Option<String> {
+        let current = std::fs::read(&self.path).ok()?;
+        if current == self.content {
+            return None;
+        }
+        let name = self.path.to_string_lossy();
+        let error = python_syntax_error(interpreter, &current, &name)?;
+        python_syntax_error(interpreter, &self.content, &name)
+            .is_none()
+            .then_some(error)
+    }
+
+    /// Write the backed-up content back to the file
+    pub fn restore(&self) -> io::Result<()> {
+        std::fs::write(&self.path, &self.content)
+    }
+}
+
+/// First structural problem in Python source: bytes that aren't UTF-8, NUL bytes, an
+/// unterminated string, or unbalanced brackets
+pub fn structure_error(content: &[u8]) -> Option<String> {
+    let Ok(text) = std::str::from_utf8(content) else {
+        return Some("not valid UTF-8".to_string());
+    };
+    if text.contains('\0') {
+        return Some("contains NUL bytes".to_string());
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut brackets: Vec<(char, usize)> = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\n' => line += 1,
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            quote @ ('"' | '\'') => {
+                let triple = chars[i..].starts_with(&[quote; 3]);
+                let start = line;
+                i += if triple { 3 } else { 1 };
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Some(format!("unterminated string starting on line {start}"))
+                        }
+                        Some('\\') => {
+                            if chars.get(i + 1) == Some(&'\n') {
+                                line += 1;
+                            }
+                            i += 2;
+                            continue;
+                        }
+                        Some('\n') if !triple => {
+                            return Some(format!("unterminated string starting on line {start}"))
+                        }
+                        Some('\n') => line += 1,
+                        Some(&c) if c == quote && !triple => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&c) if c == quote && chars[i..].starts_with(&[quote; 3]) => {
+                            i += 3;
+                            break;
+                        }
+                        _ => {}
+                    }
+                    i += 1;
+                }
+                continue;
+            }
+            open @ ('(' | '[' | '{') => brackets.push((open, line)),
+            close @ (')' | ']' | '}') => {
+                let expected = match close {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                match brackets.pop() {
+                    Some((open, _)) if open == expected => {}
+                    _ => return Some(format!("unmatched '{close}' on line {line}")),
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    brackets
+        .pop()
+        .map(|(open, line)| format!("'{open}' on line {line} is never closed"))
+}
+
+/// Compile `content` with `interpreter` like `python -m py_compile`, but without writing
+/// bytecode next to the file; the error message when it doesn't compile
+pub fn python_syntax_error(interpreter: &Path, content: &[u8], name: &str) -> Option<String> {
+    let mut child = Command::new(interpreter)
+        .args(["-c", COMPILE_SCRIPT, name])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| log::debug!("Can't compile with {}: {e}", interpreter.display()))
+        .ok()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(content);
+    }
+    let output = child.wait_with_output().ok()?;
+    let error = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!output.status.success() && !error.is_empty()).then_some(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_structure_error() {
+        let valid = "def f(x):\n    \"\"\"Doc (with an unbalanced paren\n    \"\"\"\n    return {'a': [x, \")\"]}  # ]\n";
+        assert_eq!(structure_error(valid.as_bytes()), None);
+        assert_eq!(structure_error(b"x = 'a\\\nb'\n"), None);
+
+        assert_eq!(
+            structure_error(b"x = [1,\n     2\ny = 3\n").as_deref(),
+            Some("'[' on line 1 is never closed")
+        );
+        assert_eq!(
+            structure_error(b"x = (1))\n").as_deref(),
+            Some("unmatched ')' on line 1")
+        );
+        assert_eq!(
+            structure_error(b"x = 1\ns = \"open\n").as_deref(),
+            Some("unterminated string starting on line 2")
+        );
+        assert_eq!(
+            structure_error(b"x = \xff\n").as_deref(),
+            Some("not valid UTF-8")
+        );
+    }
+
+    #[test]
+    fn test_backup_detects_corruption_and_restores() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("app.py");
+        std::fs::write(&file, "x = [1, 2]\n").unwrap();
+
+        let backup = FileBackup::capture(&file).unwrap();
+        assert!(!backup.changed());
+        assert_eq!(backup.corruption(), None);
+
+        std::fs::write(&file, "x = [1, 2, 3]\n").unwrap();
+        assert!(backup.changed());
+        assert_eq!(backup.corruption(), None);
+
+        std::fs::write(&file, "x = [1, 2\n").unwrap();
+        assert!(backup.corruption().is_some());
+        std::fs::write(&file, "").unwrap();
+        assert_eq!(backup.corruption().as_deref(), Some("file emptied"));
+
+        backup.restore().unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "x = [1, 2]\n");
+
+        // A file that was already broken isn't blamed on the tool
+        std::fs::write(&file, "x = [\n").unwrap();
+        let broken = FileBackup::capture(&file).unwrap();
+        std::fs::write(&file, "x = [[\n").unwrap();
+        assert_eq!(broken.corruption(), None);
+    }
+
+    #[test]
+    fn test_syntax_breakage() {
+        let Ok(python) = which::which("python3") else {
+            return;
+        };
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("app.py");
+        std::fs::write(&file, "def run():\n    return 1\n").unwrap();
+        let backup = FileBackup::capture(&file).unwrap();
+
+        // Balanced but not Python: only the compiler notices
+        std::fs::write(&file, "def run():\nreturn 1\n").unwrap();
+        assert_eq!(backup.corruption(), None);
+        let error = backup.syntax_breakage(&python).unwrap();
+        assert!(error.starts_with("SyntaxError:"), "{error}");
+        assert!(error.ends_with("on line 2"), "{error}");
+
+        std::fs::write(&file, "def run():\n    return 2\n").unwrap();
+        assert_eq!(backup.syntax_breakage(&python), None);
+        assert_eq!(
+            python_syntax_error(Path::new("/nonexistent/python"), b"def", "app.py"),
+            None
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use crate::pytest::{Outcome, PytestResults};
+
+/// Rough characters per token for code and English text; good enough to keep prompts
+/// inside a context window without a tokenizer for every provider
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Estimated token count of `text`
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Python source reduced to imports, decorators and class/function signatures, with
+/// function bodies replaced by `...`
+pub fn outline_source(source: &str) -> String {
+    let mut outline = Vec::new();
+    // Indent of a function whose signature spans several lines
+    let mut open_signature: Option<usize> = None;
+    let mut open_import = false;
+    for line in source.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if open_import {
+            outline.push(line.to_string());
+            open_import = !line.contains(')');
+            continue;
+        }
+        if let Some(def_indent) = open_signature {
+            outline.push(line.to_string());
+            if trimmed.trim_end().ends_with(':') {
+                outline.push(format!("{}    ...", " ".repeat(def_indent)));
+                open_signature = None;
+            }
+            continue;
+        }
+
+        let is_def = trimmed.starts_with("def ") || trimmed.starts_with("async def ");
+        if is_def || trimmed.starts_with("class ") {
+            outline.push(line.to_string());
+            if !trimmed.trim_end().ends_with(':') {
+                open_signature = Some(indent);
+            } else if is_def {
+                outline.push(format!("{}    ...", " ".repeat(indent)));
+            }
+        } else if trimmed.starts_with('@')
+            || (indent == 0 && (trimmed.starts_with("import ") || trimmed.starts_with("from ")))
+        {
+            outline.push(line.to_string());
+            open_import = line.contains('(') && !line.contains(')');
+        }
+    }
+    outline.join("\n")
+}
+
+/// A test module with the bodies of tests that didn't fail replaced by `...`
+pub fn drop_passing_test_bodies(test_source: &str, failing: &[String]) -> String {
+    let mut kept = Vec::new();
+    // Indent of the passing test whose body is being skipped
+    let mut skipping: Option<usize> = None;
+    for line in test_source.lines() {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+
+        if let Some(test_indent) = skipping {
+            if trimmed.is_empty() || indent > test_indent {
+                continue;
+            }
+            skipping = None;
+        }
+
+        kept.push(line.to_string());
+        let name = trimmed
+            .strip_prefix("def ")
+            .or_else(|| trimmed.strip_prefix("async def "))
+            .and_then(|rest| rest.split('(').next());
+        if let Some(name) = name.filter(|name| name.starts_with("test")) {
+            if trimmed.trim_end().ends_with(':') && !failing.iter().any(|f| f == name) {
+                kept.push(format!("{}    ...", " ".repeat(indent)));
+                skipping = Some(indent);
+            }
+        }
+    }
+    kept.join("\n")
+}
+
+/// Names of the test functions that failed or errored, without parametrize ids
+pub fn failing_test_names(output: &str) -> Vec<String> {
+    PytestResults::parse(output)
+        .cases
+        .iter()
+        .filter(|case| matches!(case.outcome, Outcome::Failed | Outcome::Error))
+        .filter_map(|case| {
+            let name = case.node_id.rsplit("::").next()?;
+            Some(name.split('[').next().unwrap_or(name).to_string())
+        })
+        .collect()
+}
+
+/// Test runner output without the lines reporting passing tests; failure and error
+/// sections and the summary are kept whole
+pub fn condense_test_output(output: &str) -> String {
+    let mut kept = Vec::new();
+    let mut omitted = 0;
+    for line in output.lines() {
+        let passing = line.contains(" PASSED") || line.starts_with("PASSED ");
+        if passing {
+            omitted += 1;
+        } else {
+            kept.push(line);
+        }
+    }
+    if omitted == 0 {
+        return output.to_string();
+    }
+    format!(
+        "[... {omitted} passing test lines omitted ...]\n{}",
+        kept.join("\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline_source() {
+        let source = r#"import os
+from typing import (
+    Any,
+)
+
+CONSTANT = 1
+
+
+@dataclass
+class Order:
+    """An order."""
+
+    def total(self, tax: float) -> float:
+        return sum(self.items) * tax
+
+    async def submit(
+        self,
+        client: Any,
+    ) -> None:
+        await client.post(self)
+"#;
+        assert_eq!(
+            outline_source(source),
+            r#"import os
+from typing import (
+    Any,
+)
+@dataclass
+class Order:
+    def total(self, tax: float) -> float:
+        ...
+    async def submit(
+        self,
+        client: Any,
+    ) -> None:
+        ..."#
+        );
+        assert_eq!(estimate_tokens("12345678"), 2);
+        assert_eq!(estimate_tokens("123456789"), 3);
+    }
+
+    #[test]
+    fn test_drop_passing_test_bodies() {
+        let output = "FAILED tests/test_orders.py::test_refund[card] - assert 1 == 2\n";
+        let failing = failing_test_names(output);
+        assert_eq!(failing, ["test_refund"]);
+
+        let source = r#"def test_total():
+    order = Order()
+
+    assert order.total(1.0) == 0
+
+
+def test_refund(method):
+    assert refund(method) == 2
+"#;
+        assert_eq!(
+            drop_passing_test_bodies(source, &failing),
+            r#"def test_total():
+    ...
+def test_refund(method):
+    assert refund(method) == 2"#
+        );
+
+        let condensed = condense_test_output(
+            "tests/test_a.py::test_x PASSED [ 50%]\ntests/test_a.py::test_y FAILED [100%]\n",
+        );
+        assert_eq!(
+            condensed,
+            "[... 1 passing test lines omitted ...]\ntests/test_a.py::test_y FAILED [100%]"
+        );
+    }
+}
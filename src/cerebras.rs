@@ -1,28 +1,288 @@
 use anyhow::{Context, Result};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+use crate::audit_log::{AuditLog, AuditRecord};
+use crate::budget::{
+    condense_test_output, drop_passing_test_bodies, estimate_tokens, failing_test_names,
+    outline_source, CHARS_PER_TOKEN,
+};
+use crate::checks;
+use crate::circuit::CircuitBreaker;
+use crate::discovery::{Framework, PythonSourceKind, PythonVersion};
+use crate::mock::MockProvider;
+use crate::patch::ProposedFix;
+use crate::prompt_context::ProjectSnapshot;
+use crate::pytest::PytestResults;
+use crate::redact::Redactor;
+use crate::testgen::{module_path, GeneratedTests, TestFramework};
+use crate::{
+    AiConfig, AiModels, CircuitBreakerConfig, GuardrailsError, PromptTokenBudgets, RedactionConfig,
+    SecondOpinionConfig,
+};
+
+/// Whether AI analysis may reach the network
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiMode {
+    /// Hosted Cerebras API
+    #[default]
+    Online,
+    /// Local model server only (air-gapped environments)
+    Offline,
+}
+
+/// Backend that serves AI analysis requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AiProvider {
+    /// Cerebras chat completions API with JSON-schema output
+    #[default]
+    Cerebras,
+    /// Local Ollama server (`/api/chat`)
+    Ollama,
+    /// Local llama.cpp server (OpenAI-compatible `/v1/chat/completions`)
+    LlamaCpp,
+    /// Deterministic canned/rule-based analyses, no network calls
+    Mock,
+}
+
+impl std::str::FromStr for AiProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "cerebras" => Ok(AiProvider::Cerebras),
+            "ollama" => Ok(AiProvider::Ollama),
+            "llamacpp" => Ok(AiProvider::LlamaCpp),
+            "mock" => Ok(AiProvider::Mock),
+            other => Err(format!(
+                "unknown AI provider '{other}' (expected cerebras, ollama, llamacpp or mock)"
+            )),
+        }
+    }
+}
+
+impl AiProvider {
+    fn default_base_url(self) -> &'static str {
+        match self {
+            AiProvider::Cerebras => "https://api.cerebras.ai/v1",
+            AiProvider::Ollama => "http://localhost:11434",
+            AiProvider::LlamaCpp => "http://localhost:8080",
+            AiProvider::Mock => "",
+        }
+    }
+
+    fn default_model(self) -> &'static str {
+        match self {
+            AiProvider::Cerebras => "qwen-3-coder-480b",
+            AiProvider::Ollama => "qwen2.5-coder:7b",
+            AiProvider::LlamaCpp => "local",
+            AiProvider::Mock => "mock",
+        }
+    }
+
+    /// Local providers run smaller models with shorter context windows
+    fn is_local(self) -> bool {
+        matches!(self, AiProvider::Ollama | AiProvider::LlamaCpp)
+    }
+}
+
+/// Prompt size cap for local models, in characters
+const DEFAULT_LOCAL_MAX_PROMPT_CHARS: usize = 12_000;
+
+/// Provider requests running longer than this are cancelled
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Environment variable overriding `ai.model`
+pub const MODEL_ENV: &str = "CEREBRAS_MODEL";
+
+/// Environment variable overriding `ai.base_url`
+pub const BASE_URL_ENV: &str = "CEREBRAS_BASE_URL";
+
+/// Files sent together in one batched exclusion analysis prompt
+const MAX_BATCH_FILES: usize = 8;
+
+/// Per-file provider requests in flight at once when a batch doesn't cover every file
+const MAX_CONCURRENT_ANALYSES: usize = 4;
+
+/// Files up to this many lines are always sent whole rather than as a diff
+const DIFF_MIN_LINES: usize = 60;
+
+/// Lines from the top of a file (imports, module docstring) sent along with its diff
+const DIFF_HEADER_LINES: usize = 15;
+
+/// Unchanged lines shown around each diff hunk
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Characters of each conftest.py included in test analysis prompts
+const MAX_CONFTEST_CHARS: usize = 4_000;
+
+/// Kind of analysis a provider request serves; each may use its own model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisKind {
+    Exclusion,
+    Lint,
+    Test,
+}
 
 /// Configuration for the Cerebras AI integration
 #[derive(Debug, Clone)]
 pub struct CerebrasConfig {
     pub api_key: String,
     pub base_url: String,
+    /// Default model for every analysis type
     pub model: String,
+    /// Per-analysis overrides of `model`
+    pub models: AiModels,
     pub enabled: bool,
+    /// Ask test analysis to evaluate property coverage and shrinking output
+    /// when the tests use Hypothesis
+    pub property_test_analysis: bool,
+    pub provider: AiProvider,
+    /// Prompts longer than this are truncated in the middle
+    pub max_prompt_chars: Option<usize>,
+    /// Estimated-token budgets per analysis type
+    pub max_prompt_tokens: PromptTokenBudgets,
+    /// Sampling overrides; each analysis type has its own defaults
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    /// Hard limit on each provider request, after which basic analysis is used
+    pub request_timeout: Duration,
+    /// When repeated failures pause requests (see [`SmartExclusionAnalyzer::with_workspace`])
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Content masked in every prompt
+    pub redaction: RedactionConfig,
+    /// Globs of files whose content is never sent
+    pub never_send: Vec<String>,
+    /// Record requests in the workspace's audit log
+    pub audit_log: bool,
+    /// Analyses the model is less confident in are replaced by basic analysis
+    pub min_confidence: Option<f32>,
+    /// Confirm destructive decisions with a second request
+    pub second_opinion: SecondOpinionConfig,
+    /// Show exclusion and lint analysis the diff of longer edited files rather than
+    /// their whole content
+    pub diff_analysis: bool,
+    /// Directory of canned responses for the mock provider
+    pub mock_responses: Option<PathBuf>,
 }
 
 impl Default for CerebrasConfig {
     fn default() -> Self {
         Self {
             api_key: std::env::var("CEREBRAS_API_KEY").unwrap_or_default(),
-            base_url: "https://api.cerebras.ai/v1".to_string(),
-            model: "qwen-3-coder-480b".to_string(),
+            base_url: AiProvider::Cerebras.default_base_url().to_string(),
+            model: AiProvider::Cerebras.default_model().to_string(),
+            models: AiModels::default(),
             enabled: std::env::var("CEREBRAS_API_KEY").is_ok(),
+            property_test_analysis: true,
+            provider: AiProvider::Cerebras,
+            max_prompt_chars: None,
+            max_prompt_tokens: PromptTokenBudgets::default(),
+            temperature: None,
+            top_p: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            circuit_breaker: CircuitBreakerConfig::default(),
+            redaction: RedactionConfig::default(),
+            never_send: Vec::new(),
+            audit_log: false,
+            min_confidence: None,
+            second_opinion: SecondOpinionConfig::default(),
+            diff_analysis: true,
+            mock_responses: None,
+        }
+    }
+}
+
+impl CerebrasConfig {
+    /// Build the runtime config from the `ai` section of guardrails.yaml
+    ///
+    /// `CEREBRAS_MODEL` and `CEREBRAS_BASE_URL` take precedence over the file. Offline
+    /// mode never reaches the hosted API: an explicit `cerebras` provider disables AI
+    /// analysis instead.
+    pub fn from_ai_config(ai: &AiConfig) -> Self {
+        let provider = ai.provider.unwrap_or(match ai.mode {
+            AiMode::Online => AiProvider::Cerebras,
+            AiMode::Offline => AiProvider::Ollama,
+        });
+        let network_blocked = ai.mode == AiMode::Offline && provider == AiProvider::Cerebras;
+        if network_blocked {
+            log::warn!(
+                "AI provider 'cerebras' needs network access, which ai.mode: offline forbids; AI analysis is disabled"
+            );
         }
+        let defaults = Self::default();
+
+        Self {
+            base_url: env_override(BASE_URL_ENV)
+                .or_else(|| ai.base_url.clone())
+                .unwrap_or_else(|| provider.default_base_url().to_string()),
+            model: env_override(MODEL_ENV)
+                .or_else(|| ai.model.clone())
+                .unwrap_or_else(|| provider.default_model().to_string()),
+            // Local servers and the mock need no API key
+            enabled: !network_blocked && (provider != AiProvider::Cerebras || defaults.enabled),
+            provider,
+            max_prompt_chars: ai.max_prompt_chars.or(provider
+                .is_local()
+                .then_some(DEFAULT_LOCAL_MAX_PROMPT_CHARS)),
+            max_prompt_tokens: ai.max_prompt_tokens.clone(),
+            models: ai.models.clone(),
+            temperature: ai.temperature,
+            top_p: ai.top_p,
+            request_timeout: ai
+                .request_timeout_seconds
+                .map_or(DEFAULT_REQUEST_TIMEOUT, Duration::from_secs),
+            circuit_breaker: ai.circuit_breaker.clone(),
+            redaction: ai.redaction.clone(),
+            never_send: ai.never_send.clone(),
+            audit_log: ai.audit_log,
+            min_confidence: ai.min_confidence,
+            second_opinion: ai.second_opinion.clone(),
+            diff_analysis: !ai.whole_file_prompts,
+            mock_responses: ai.mock_responses.clone(),
+            ..defaults
+        }
+    }
+}
+
+impl CerebrasConfig {
+    /// Model used for the given kind of analysis
+    pub fn model_for(&self, kind: AnalysisKind) -> &str {
+        let model = match kind {
+            AnalysisKind::Exclusion => &self.models.exclusion,
+            AnalysisKind::Lint => &self.models.lint,
+            AnalysisKind::Test => &self.models.test,
+        };
+        model.as_deref().unwrap_or(&self.model)
+    }
+}
+
+/// Message noting that AI analysis degraded to basic analysis, when `error` is a timeout
+/// or an open circuit breaker
+fn degradation_note(error: &anyhow::Error) -> Option<String> {
+    match error.downcast_ref::<GuardrailsError>()? {
+        GuardrailsError::Timeout { seconds, .. } => Some(format!(
+            "⚠️ AI analysis timed out after {seconds}s, so basic analysis was used instead."
+        )),
+        GuardrailsError::AiUnavailable { until } => Some(format!(
+            "⚠️ AI analysis is paused after repeated provider failures (until {}), so basic analysis was used instead.",
+            until.format("%H:%M:%S UTC")
+        )),
+        _ => None,
     }
 }
 
+/// Non-empty value of an environment variable
+fn env_override(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
 /// Request structure for Cerebras Chat API
 #[derive(Debug, Serialize)]
 struct ChatRequest {
@@ -56,6 +316,29 @@ struct JsonSchema {
     schema: serde_json::Value,
 }
 
+/// Request body for Ollama's `/api/chat`
+#[derive(Debug, Serialize)]
+struct OllamaChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+    /// JSON schema the reply must follow (Ollama structured outputs)
+    format: serde_json::Value,
+    options: OllamaOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    temperature: f32,
+    top_p: f32,
+}
+
+/// Response from Ollama's `/api/chat`
+#[derive(Debug, Deserialize)]
+struct OllamaChatResponse {
+    message: ChatResponseMessage,
+}
+
 /// Response from Cerebras API
 #[derive(Debug, Deserialize)]
 struct ChatResponse {
@@ -84,6 +367,13 @@ pub struct ExclusionAnalysis {
     pub file_type: String,
     pub purpose: String,
     pub exclusion_recommendation: String,
+    /// Model's confidence in these decisions, 0.0-1.0 (1.0 for analyses made without AI)
+    #[serde(default = "full_confidence")]
+    pub confidence: f32,
+}
+
+pub(crate) fn full_confidence() -> f32 {
+    1.0
 }
 
 /// Analysis result for comprehensive test analysis
@@ -97,6 +387,9 @@ pub struct TestFailureAnalysis {
     pub coverage_analysis: String,
     pub missing_tests: Vec<String>,
     pub quality_assessment: String,
+    /// Model's confidence in these decisions, 0.0-1.0 (1.0 for analyses made without AI)
+    #[serde(default = "full_confidence")]
+    pub confidence: f32,
 }
 
 /// Details of a specific failed test
@@ -116,40 +409,260 @@ pub struct LintAnalysis {
     pub reasoning: String,
     pub issue_count: u32,
     pub recommendations: String,
+    /// Linter output lines the model explicitly judged false positives, quoted verbatim;
+    /// only these are remembered as suppressions
+    #[serde(default)]
+    pub false_positives: Vec<String>,
+    /// Model's confidence in these decisions, 0.0-1.0 (1.0 for analyses made without AI)
+    #[serde(default = "full_confidence")]
+    pub confidence: f32,
+}
+
+/// Reply to a batched exclusion analysis prompt
+#[derive(Debug, Deserialize)]
+struct BatchExclusionAnalysis {
+    analyses: Vec<BatchEntry>,
+}
+
+/// One file's analysis within a batched reply
+#[derive(Debug, Deserialize)]
+struct BatchEntry {
+    file_path: String,
+    #[serde(flatten)]
+    analysis: ExclusionAnalysis,
+}
+
+/// Reply to a second-opinion request
+#[derive(Debug, Deserialize)]
+struct SecondOpinion {
+    agrees: bool,
+    reasoning: String,
+}
+
+/// Exclusion analysis from the file's name and extension (and an extensionless file's
+/// shebang), the verdict `analyze` gives without AI (`--no-ai`, no API key, or a file in
+/// `ai.never_send`)
+///
+/// Needs no [`SmartExclusionAnalyzer`] or configuration and never touches the network,
+/// so offline tooling and tests can depend on it; the result always has full confidence.
+pub fn heuristic_analysis(file_path: &Path) -> ExclusionAnalysis {
+    let file_name = file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    let extension = file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("");
+
+    // Basic heuristics for common file types
+    let (should_exclude_general, should_exclude_lint, should_exclude_test, reasoning) =
+        match (file_name, extension) {
+            (name, _) if name.starts_with("test_") || name.ends_with("_test.py") => {
+                (false, true, true, "Test files should be excluded from testing requirements and may have relaxed linting")
+            },
+            (_, "pyc") | (_, "pyo") | (_, "pyd") => {
+                (true, true, true, "Compiled Python files should be excluded from all processing")
+            },
+            (name, _) if name.contains("__pycache__") => {
+                (true, true, true, "Python cache files should be excluded from all processing")
+            },
+            (name, _) if name.starts_with(".") => {
+                (true, true, true, "Hidden files typically don't require processing")
+            },
+            (_, "py") => {
+                (false, false, false, "Regular Python files should be processed normally")
+            },
+            (_, "pyi") => {
+                (false, false, true, "Type stubs are linted but contain no logic to test")
+            },
+            (_, "") if PythonSourceKind::of(file_path) == Some(PythonSourceKind::Module) => {
+                (false, false, false, "Extensionless Python script (detected from its shebang or modeline) should be processed normally")
+            },
+            (_, "pyx") | (_, "pxd") => {
+                (false, false, false, "Cython sources are linted and tested through the compiled module")
+            },
+            _ => {
+                (true, true, true, "Non-Python files excluded from Python-specific processing")
+            }
+        };
+
+    ExclusionAnalysis {
+        should_exclude_general,
+        should_exclude_lint,
+        should_exclude_test,
+        reasoning: reasoning.to_string(),
+        file_type: format!("{} file", extension),
+        purpose: "Unknown (analyzed without AI)".to_string(),
+        exclusion_recommendation: format!(
+            "Based on file pattern analysis: general={}, lint={}, test={}",
+            should_exclude_general, should_exclude_lint, should_exclude_test
+        ),
+        confidence: 1.0,
+    }
 }
 
 /// Smart exclusion analyzer using Cerebras AI
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SmartExclusionAnalyzer {
     client: Client,
     config: CerebrasConfig,
+    breaker: Option<CircuitBreaker>,
+    audit_log: Option<AuditLog>,
+    redactor: Redactor,
+    never_send: globset::GlobSet,
 }
 
 impl SmartExclusionAnalyzer {
     /// Create a new analyzer with the given configuration
     pub fn new(config: CerebrasConfig) -> Self {
+        // An unreadable never_send list must not leak the files it was meant to protect
+        let never_send = crate::build_globset(&config.never_send).unwrap_or_else(|e| {
+            log::warn!("{}; no file content will be sent to the AI", e);
+            crate::build_globset(&["**".to_string()]).unwrap_or_else(|_| globset::GlobSet::empty())
+        });
         Self {
             client: Client::new(),
+            redactor: Redactor::new(&config.redaction),
+            never_send,
             config,
+            breaker: None,
+            audit_log: None,
+        }
+    }
+
+    /// Whether a file's content may be sent to the provider (not listed in `ai.never_send`)
+    fn may_send(&self, file_path: &Path) -> bool {
+        let relative = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| file_path.strip_prefix(cwd).ok().map(Path::to_path_buf));
+        !(self.never_send.is_match(file_path)
+            || relative.is_some_and(|relative| self.never_send.is_match(relative)))
+    }
+
+    /// Keep per-workspace AI state: failure counts shared with other hook invocations,
+    /// pausing requests once the provider keeps failing, and the audit log when enabled
+    pub fn with_workspace(mut self, workspace_dir: &Path) -> Self {
+        if self.config.enabled && self.config.audit_log {
+            self.audit_log = AuditLog::for_workspace(workspace_dir)
+                .map_err(|e| log::debug!("AI audit log unavailable: {}", e))
+                .ok();
+        }
+
+        let settings = &self.config.circuit_breaker;
+        if self.config.enabled && settings.failures > 0 {
+            self.breaker = CircuitBreaker::for_workspace(
+                workspace_dir,
+                settings.failures,
+                Duration::from_secs(settings.cooldown_seconds),
+            )
+            .map_err(|e| log::debug!("AI circuit breaker unavailable: {}", e))
+            .ok();
         }
+        self
     }
 
     /// Analyze a file to determine appropriate exclusion patterns
     pub async fn analyze_file(&self, file_path: &Path) -> Result<ExclusionAnalysis> {
-        if !self.config.enabled {
-            return Ok(self.heuristic_analysis(file_path));
+        if let Some(mock) = self.mock() {
+            return Ok(mock.exclusion_analysis(heuristic_analysis(file_path)));
+        }
+
+        if !self.config.enabled || !self.may_send(file_path) {
+            return Ok(heuristic_analysis(file_path));
         }
 
         let file_content = self.read_file_content(file_path)?;
 
         // Handle API errors gracefully with conservative defaults
         match self.call_cerebras_api(file_path, &file_content).await {
-            Ok(analysis) => Ok(analysis),
-            Err(e) => {
-                eprintln!("Warning: Cerebras API call failed: {}", e);
-                Ok(self.conservative_analysis(file_path, "API error occurred"))
+            Ok(analysis) => Ok(self
+                .trusted_exclusion(file_path, &file_content, analysis)
+                .await),
+            Err(e) => match degradation_note(&e) {
+                Some(note) => Ok(self.degraded_analysis(file_path, &note)),
+                None => {
+                    eprintln!("Warning: Cerebras API call failed: {}", e);
+                    Ok(self.conservative_analysis(file_path, "API error occurred"))
+                }
+            },
+        }
+    }
+
+    /// Analyze every file changed by one hook event, returning analyses in input order
+    ///
+    /// Files go to the provider in batched prompts instead of one request each, so a
+    /// large MultiEdit finishes inside the hook timeout. Files a batch reply misses are
+    /// analyzed individually, at most [`MAX_CONCURRENT_ANALYSES`] at a time.
+    pub async fn analyze_files(&self, files: &[PathBuf]) -> Result<Vec<ExclusionAnalysis>> {
+        if files.len() <= 1 || self.mock().is_some() || !self.config.enabled {
+            let mut analyses = Vec::with_capacity(files.len());
+            for file in files {
+                analyses.push(self.analyze_file(file).await?);
+            }
+            return Ok(analyses);
+        }
+
+        let mut analyses: Vec<Option<ExclusionAnalysis>> = files
+            .iter()
+            .map(|file| (!self.may_send(file)).then(|| heuristic_analysis(file)))
+            .collect();
+        let sendable: Vec<usize> = (0..files.len())
+            .filter(|&index| analyses[index].is_none())
+            .collect();
+        for chunk in sendable.chunks(MAX_BATCH_FILES) {
+            let contents = chunk
+                .iter()
+                .map(|&index| Ok((files[index].clone(), self.read_file_content(&files[index])?)))
+                .collect::<Result<Vec<_>>>()?;
+
+            match self.call_batch_api(&contents).await {
+                Ok(mut by_path) => {
+                    for (&index, (file, content)) in chunk.iter().zip(&contents) {
+                        if let Some(analysis) = by_path.remove(&file.display().to_string()) {
+                            analyses[index] =
+                                Some(self.trusted_exclusion(file, content, analysis).await);
+                        }
+                    }
+                }
+                Err(e) => match degradation_note(&e) {
+                    // Individual requests would only time out in turn
+                    Some(note) => {
+                        for &index in chunk {
+                            analyses[index] = Some(self.degraded_analysis(&files[index], &note));
+                        }
+                    }
+                    None => eprintln!(
+                        "Warning: batched Cerebras analysis failed, analyzing files individually: {}",
+                        e
+                    ),
+                },
+            }
+        }
+
+        let missing: Vec<usize> = (0..files.len())
+            .filter(|&index| analyses[index].is_none())
+            .collect();
+        for group in missing.chunks(MAX_CONCURRENT_ANALYSES) {
+            let mut tasks = JoinSet::new();
+            for &index in group {
+                let analyzer = self.clone();
+                let file = files[index].clone();
+                tasks.spawn(async move { (index, analyzer.analyze_file(&file).await) });
+            }
+            while let Some(joined) = tasks.join_next().await {
+                let (index, analysis) = joined.context("File analysis task failed")?;
+                analyses[index] = Some(analysis?);
             }
         }
+
+        Ok(analyses.into_iter().flatten().collect())
+    }
+
+    /// Mock provider, when configured in place of a real model
+    fn mock(&self) -> Option<MockProvider> {
+        (self.config.provider == AiProvider::Mock)
+            .then(|| MockProvider::new(self.config.mock_responses.clone()))
     }
 
     /// Read file content with error handling for binary/large files
@@ -177,6 +690,122 @@ impl SmartExclusionAnalyzer {
         }
     }
 
+    /// An AI exclusion analysis as it should be acted on: replaced by heuristics when the
+    /// model isn't confident, and by conservative defaults when a second opinion
+    /// doesn't back an exclusion
+    async fn trusted_exclusion(
+        &self,
+        file_path: &Path,
+        file_content: &str,
+        analysis: ExclusionAnalysis,
+    ) -> ExclusionAnalysis {
+        if let Some(note) = self.low_confidence_note(analysis.confidence) {
+            return self.degraded_analysis(file_path, &note);
+        }
+
+        let contexts: Vec<&str> = [
+            (analysis.should_exclude_general, "all processing"),
+            (analysis.should_exclude_lint, "linting"),
+            (analysis.should_exclude_test, "testing"),
+        ]
+        .iter()
+        .filter(|(excluded, _)| *excluded)
+        .map(|(_, context)| *context)
+        .collect();
+        if contexts.is_empty() {
+            return analysis;
+        }
+
+        let claim = format!(
+            "{} should be excluded from {}. Reasoning: {}",
+            file_path.display(),
+            contexts.join(" and "),
+            analysis.reasoning
+        );
+        let subject = format!(
+            "File content:\n```{}\n{}\n```",
+            file_path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or(""),
+            file_content
+        );
+        if self
+            .second_opinion_agrees(AnalysisKind::Exclusion, &claim, &subject)
+            .await
+        {
+            analysis
+        } else {
+            self.conservative_analysis(
+                file_path,
+                "A second AI opinion didn't confirm the exclusion",
+            )
+        }
+    }
+
+    /// Whether a second request backs `claim` about `subject`; always true when second
+    /// opinions are disabled, false when the request fails
+    async fn second_opinion_agrees(&self, kind: AnalysisKind, claim: &str, subject: &str) -> bool {
+        if !self.config.second_opinion.enabled {
+            return true;
+        }
+
+        let mut reviewer = self.clone();
+        if let Some(model) = &self.config.second_opinion.model {
+            reviewer.config.models = AiModels {
+                exclusion: Some(model.clone()),
+                lint: Some(model.clone()),
+                test: Some(model.clone()),
+            };
+        }
+
+        let prompt = format!(
+            r#"You are double-checking another reviewer's decision before it is acted on. The decision skips code quality checks, so be skeptical: agree only if the evidence clearly supports it.
+
+**Claim:** {claim}
+
+{subject}
+
+Do you agree with the claim?"#
+        );
+        let json_schema = JsonSchema {
+            name: "second_opinion".to_string(),
+            description: "Independent verification of a decision".to_string(),
+            schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "agrees": {
+                        "type": "boolean",
+                        "description": "Whether the claim is correct"
+                    },
+                    "reasoning": {
+                        "type": "string",
+                        "description": "Why the claim is or isn't correct"
+                    }
+                },
+                "required": ["agrees", "reasoning"]
+            }),
+        };
+
+        let verdict = reviewer
+            .complete_json(kind, prompt, 0.2, 0.9, json_schema)
+            .await
+            .and_then(|content| {
+                serde_json::from_str::<SecondOpinion>(extract_json(&content))
+                    .context("Failed to parse second opinion")
+            });
+        match verdict {
+            Ok(verdict) => {
+                log::debug!("Second opinion on \"{}\": {}", claim, verdict.reasoning);
+                verdict.agrees
+            }
+            Err(e) => {
+                eprintln!("Warning: second AI opinion failed: {}", e);
+                false
+            }
+        }
+    }
+
     /// Make API call to Cerebras for file analysis
     async fn call_cerebras_api(
         &self,
@@ -185,107 +814,279 @@ impl SmartExclusionAnalyzer {
     ) -> Result<ExclusionAnalysis> {
         let prompt = self.create_analysis_prompt(file_path, file_content);
 
-        let request = ChatRequest {
-            model: self.config.model.clone(),
-            messages: vec![ChatMessage {
-                role: "user".to_string(),
-                content: prompt,
-            }],
-            temperature: 0.7,
-            top_p: 0.8,
-            response_format: ResponseFormat {
-                format_type: "json_schema".to_string(),
-                json_schema: JsonSchema {
-                    name: "exclusion_analysis".to_string(),
-                    description: "Analysis of file exclusion requirements".to_string(),
-                    schema: serde_json::json!({
-                        "type": "object",
-                        "properties": {
-                            "should_exclude_general": {
-                                "type": "boolean",
-                                "description": "Whether file should be excluded from general processing"
-                            },
-                            "should_exclude_lint": {
-                                "type": "boolean",
-                                "description": "Whether file should be excluded from linting"
-                            },
-                            "should_exclude_test": {
-                                "type": "boolean",
-                                "description": "Whether file should be excluded from testing"
-                            },
-                            "reasoning": {
-                                "type": "string",
-                                "description": "Detailed reasoning for exclusion recommendations"
-                            },
-                            "file_type": {
-                                "type": "string",
-                                "description": "Detected file type/category"
-                            },
-                            "purpose": {
-                                "type": "string",
-                                "description": "Primary purpose of the file"
-                            },
-                            "exclusion_recommendation": {
-                                "type": "string",
-                                "description": "Specific recommendation for guardrails configuration"
-                            }
-                        },
-                        "required": [
-                            "should_exclude_general",
-                            "should_exclude_lint",
-                            "should_exclude_test",
-                            "reasoning",
-                            "file_type",
-                            "purpose",
-                            "exclusion_recommendation"
-                        ]
-                    }),
+        let json_schema = JsonSchema {
+            name: "exclusion_analysis".to_string(),
+            description: "Analysis of file exclusion requirements".to_string(),
+            schema: exclusion_analysis_schema(),
+        };
+
+        let content = self
+            .complete_json(AnalysisKind::Exclusion, prompt, 0.7, 0.8, json_schema)
+            .await?;
+
+        let analysis: ExclusionAnalysis = serde_json::from_str(extract_json(&content))
+            .with_context(|| "Failed to parse exclusion analysis from Cerebras response")?;
+
+        Ok(analysis)
+    }
+
+    /// Make one API call analyzing several files, keyed by the paths given
+    async fn call_batch_api(
+        &self,
+        files: &[(PathBuf, String)],
+    ) -> Result<HashMap<String, ExclusionAnalysis>> {
+        let mut prompt = self.create_batch_analysis_prompt(files);
+        if self.over_budget(AnalysisKind::Exclusion, &prompt) {
+            let condensed: Vec<(PathBuf, String)> = files
+                .iter()
+                .map(|(path, content)| (path.clone(), condensed_source(content)))
+                .collect();
+            prompt = self.create_batch_analysis_prompt(&condensed);
+        }
+
+        let mut entry = exclusion_analysis_schema();
+        entry["properties"]["file_path"] = serde_json::json!({
+            "type": "string",
+            "description": "Path of the analyzed file, exactly as given in the prompt"
+        });
+        if let Some(required) = entry["required"].as_array_mut() {
+            required.push("file_path".into());
+        }
+
+        let json_schema = JsonSchema {
+            name: "batch_exclusion_analysis".to_string(),
+            description: "Exclusion analysis of each file changed together".to_string(),
+            schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "analyses": {
+                        "type": "array",
+                        "items": entry
+                    }
                 },
-            },
+                "required": ["analyses"]
+            }),
+        };
+
+        let content = self
+            .complete_json(AnalysisKind::Exclusion, prompt, 0.7, 0.8, json_schema)
+            .await?;
+        parse_batch_analysis(&content)
+    }
+
+    /// Send a prompt to the configured provider and return the raw JSON reply, cancelling
+    /// the request once it exceeds the configured timeout
+    async fn complete_json(
+        &self,
+        kind: AnalysisKind,
+        prompt: String,
+        temperature: f32,
+        top_p: f32,
+        json_schema: JsonSchema,
+    ) -> Result<String> {
+        if let Some(until) = self
+            .breaker
+            .as_ref()
+            .and_then(|breaker| breaker.open_until())
+        {
+            return Err(GuardrailsError::AiUnavailable { until }.into());
+        }
+
+        let prompt = self.redactor.redact(&prompt).into_owned();
+        let max_chars = [
+            self.config.max_prompt_chars,
+            self.token_budget(kind)
+                .map(|tokens| tokens * CHARS_PER_TOKEN),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        let prompt = match max_chars {
+            Some(max_chars) => fit_prompt(&prompt, max_chars),
+            None => prompt,
+        };
+        let prompt_hash = AuditRecord::prompt_hash(&prompt);
+        let prompt_chars = prompt.chars().count();
+
+        let started = std::time::Instant::now();
+        let request = self.request_completion(kind, prompt, temperature, top_p, json_schema);
+        let result = tokio::time::timeout(self.config.request_timeout, request)
+            .await
+            .unwrap_or_else(|_| {
+                Err(GuardrailsError::Timeout {
+                    operation: format!("{:?} request", self.config.provider),
+                    seconds: self.config.request_timeout.as_secs(),
+                }
+                .into())
+            });
+
+        if let Some(audit_log) = &self.audit_log {
+            let (decision, response) = match &result {
+                Ok(content) => (
+                    AuditRecord::summarize_decision(content),
+                    AuditRecord::summarize_response(content),
+                ),
+                Err(e) => (format!("error: {e}"), String::new()),
+            };
+            let record = AuditRecord {
+                timestamp: chrono::Utc::now(),
+                provider: format!("{:?}", self.config.provider),
+                model: self.config.model_for(kind).to_string(),
+                analysis: format!("{kind:?}").to_lowercase(),
+                prompt_hash,
+                prompt_chars,
+                latency_ms: started.elapsed().as_millis() as u64,
+                decision,
+                response,
+            };
+            if let Err(e) = audit_log.record(&record) {
+                log::warn!("Could not write AI audit log: {}", e);
+            }
+        }
+
+        if let Some(breaker) = &self.breaker {
+            match &result {
+                Ok(_) => breaker.record_success(),
+                Err(_) => {
+                    if breaker.record_failure() {
+                        eprintln!(
+                            "Warning: pausing AI requests for {}s after repeated provider failures",
+                            self.config.circuit_breaker.cooldown_seconds
+                        );
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    async fn request_completion(
+        &self,
+        kind: AnalysisKind,
+        prompt: String,
+        temperature: f32,
+        top_p: f32,
+        json_schema: JsonSchema,
+    ) -> Result<String> {
+        let temperature = self.config.temperature.unwrap_or(temperature);
+        let top_p = self.config.top_p.unwrap_or(top_p);
+        let messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: prompt,
+        }];
+
+        let request = match self.config.provider {
+            AiProvider::Mock => {
+                return Err(anyhow::anyhow!("The mock provider doesn't make requests"))
+            }
+            AiProvider::Cerebras | AiProvider::LlamaCpp => {
+                let url = match self.config.provider {
+                    AiProvider::LlamaCpp => format!("{}/v1/chat/completions", self.config.base_url),
+                    _ => format!("{}/chat/completions", self.config.base_url),
+                };
+                self.client
+                    .post(url)
+                    .header("Authorization", format!("Bearer {}", self.config.api_key))
+                    .json(&ChatRequest {
+                        model: self.config.model_for(kind).to_string(),
+                        messages,
+                        temperature,
+                        top_p,
+                        response_format: ResponseFormat {
+                            format_type: "json_schema".to_string(),
+                            json_schema,
+                        },
+                    })
+            }
+            AiProvider::Ollama => self
+                .client
+                .post(format!("{}/api/chat", self.config.base_url))
+                .json(&OllamaChatRequest {
+                    model: self.config.model_for(kind).to_string(),
+                    messages,
+                    stream: false,
+                    format: json_schema.schema,
+                    options: OllamaOptions { temperature, top_p },
+                }),
         };
 
-        let response = self
-            .client
-            .post(format!("{}/chat/completions", self.config.base_url))
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
+        let response = request
             .header("Content-Type", "application/json")
-            .json(&request)
             .send()
             .await
-            .with_context(|| "Failed to send request to Cerebras API")?;
+            .map_err(|e| {
+                GuardrailsError::AiProvider(format!(
+                    "Failed to send request to {:?}: {e}",
+                    self.config.provider
+                ))
+            })?;
 
         if !response.status().is_success() {
             let status = response.status();
             let error_text = response.text().await.unwrap_or_default();
-            return Err(anyhow::anyhow!(
-                "Cerebras API request failed with status {}: {}",
-                status,
-                error_text
-            ));
+            return Err(GuardrailsError::AiProvider(format!(
+                "{:?} request failed with status {}: {}",
+                self.config.provider, status, error_text
+            ))
+            .into());
         }
 
-        let chat_response: ChatResponse = response
-            .json()
-            .await
-            .with_context(|| "Failed to parse Cerebras API response")?;
+        let content = match self.config.provider {
+            AiProvider::Ollama => {
+                let chat_response: OllamaChatResponse = response.json().await.map_err(|e| {
+                    GuardrailsError::AiProvider(format!("Failed to parse Ollama response: {e}"))
+                })?;
+                chat_response.message.content
+            }
+            AiProvider::Cerebras | AiProvider::LlamaCpp | AiProvider::Mock => {
+                let chat_response: ChatResponse = response.json().await.map_err(|e| {
+                    GuardrailsError::AiProvider(format!("Failed to parse response JSON: {e}"))
+                })?;
+                chat_response
+                    .choices
+                    .into_iter()
+                    .next()
+                    .and_then(|choice| choice.message.content)
+            }
+        };
 
-        let content = chat_response
-            .choices
-            .first()
-            .and_then(|choice| choice.message.content.as_ref())
-            .ok_or_else(|| anyhow::anyhow!("No content in Cerebras API response"))?;
+        content.ok_or_else(|| {
+            GuardrailsError::AiProvider("No content in API response".to_string()).into()
+        })
+    }
 
-        let analysis: ExclusionAnalysis = serde_json::from_str(content)
-            .with_context(|| "Failed to parse exclusion analysis from Cerebras response")?;
+    /// Estimated-token budget for prompts of an analysis type: `ai.max_prompt_tokens`,
+    /// else derived from `ai.max_prompt_chars`
+    fn token_budget(&self, kind: AnalysisKind) -> Option<usize> {
+        let budgets = &self.config.max_prompt_tokens;
+        let budget = match kind {
+            AnalysisKind::Exclusion => budgets.exclusion,
+            AnalysisKind::Lint => budgets.lint,
+            AnalysisKind::Test => budgets.test,
+        };
+        budget.or_else(|| {
+            self.config
+                .max_prompt_chars
+                .map(|chars| chars / CHARS_PER_TOKEN)
+        })
+    }
 
-        Ok(analysis)
+    /// Whether a prompt exceeds the budget of its analysis type
+    fn over_budget(&self, kind: AnalysisKind, prompt: &str) -> bool {
+        self.token_budget(kind)
+            .is_some_and(|budget| estimate_tokens(prompt) > budget)
     }
 
     /// Create the analysis prompt for the given file
     fn create_analysis_prompt(&self, file_path: &Path, file_content: &str) -> String {
         // For now, we'll use a comprehensive prompt that covers all aspects
         // This will be split into separate prompts for each context in the future
-        self.create_comprehensive_analysis_prompt(file_path, file_content)
+        let prompt = self.create_comprehensive_analysis_prompt(file_path, file_content);
+        if !self.over_budget(AnalysisKind::Exclusion, &prompt) {
+            return prompt;
+        }
+        // Signatures are enough to tell what a file is for
+        self.create_comprehensive_analysis_prompt(file_path, &condensed_source(file_content))
     }
 
     /// Create test exclusion analysis prompt (based on test-filter.py)
@@ -301,7 +1102,8 @@ impl SmartExclusionAnalyzer {
             .unwrap_or("");
 
         // Get project context - look for existing tests
-        let project_root = file_path.parent().unwrap_or(Path::new("."));
+        let project_root = ProjectSnapshot::for_path(file_path).root;
+        let project_root = project_root.as_path();
         let mut test_context = String::new();
 
         // Look for test directory and existing tests
@@ -353,7 +1155,7 @@ impl SmartExclusionAnalyzer {
 
 File: {}
 File name: {}
-File type: {}{}
+File type: {}{}{}
 
 File content:
 ```{}
@@ -388,6 +1190,7 @@ IMPORTANT: If tests are needed but missing, use strong, clear language that make
             file_path.display(),
             file_name,
             extension,
+            project_context(file_path),
             test_context,
             extension,
             file_content
@@ -411,7 +1214,7 @@ IMPORTANT: If tests are needed but missing, use strong, clear language that make
 
 File: {}
 File name: {}
-File type: {}
+File type: {}{}
 
 File content should be analyzed to determine exclusion.
 
@@ -441,7 +1244,8 @@ Determine if this file should be excluded from linting based on:
 Analyze this file and provide a clear recommendation."#,
             file_path.display(),
             file_name,
-            extension
+            extension,
+            project_context(file_path)
         )
     }
 
@@ -462,7 +1266,7 @@ Analyze this file and provide a clear recommendation."#,
 
 File: {}
 File name: {}
-File type: {}
+File type: {}{}
 
 File content:
 ```{extension}
@@ -497,7 +1301,8 @@ Only exclude files that are:
 User-authored code should almost always be included in quality processing."#,
             file_path.display(),
             file_name,
-            extension
+            extension,
+            project_context(file_path)
         )
     }
 
@@ -512,135 +1317,108 @@ User-authored code should almost always be included in quality processing."#,
             .and_then(|ext| ext.to_str())
             .unwrap_or("");
 
-        // Get project context - look for existing configuration
-        let project_root = file_path.parent().unwrap_or(Path::new("."));
-        let mut context_info = String::new();
-
-        // Check for common project files to understand context
-        if project_root.join("pyproject.toml").exists() {
-            context_info.push_str("Project uses pyproject.toml configuration.\n");
-        }
-        if project_root.join("setup.py").exists() {
-            context_info.push_str("Project uses setup.py configuration.\n");
-        }
-        if project_root.join("requirements.txt").exists() {
-            context_info.push_str("Project uses requirements.txt for dependencies.\n");
-        }
-
         format!(
             r#"You are an expert software developer analyzing Python files for intelligent exclusion patterns in a code quality toolchain.
 
 File: {}
 File name: {}
-File type: {}{}
+File type: {}
 
-File content:
-```{}
 {}
-```
 
 **YOUR TASK:** Analyze this file and make SPECIFIC, CLEAR decisions for each exclusion context:
 
-**CONTEXT 1: GENERAL EXCLUSION** (exclude from ALL processing)
-EXCLUDE if file is:
-- Generated/compiled: *_pb2.py, *_pb2_grpc.py, migrations, .pyc, .pyo, .pyd
-- Binary/non-text: images, databases, compiled artifacts  
-- Cache/temporary: __pycache__, .pytest_cache, build artifacts
-- Third-party vendor code that shouldn't be modified
+{}
 
-**CONTEXT 2: LINT EXCLUSION** (exclude from linting/formatting)
-EXCLUDE if file is:
-- Generated code that can't be reformatted (migrations, protobuf)
-- Test files that intentionally break style rules for readability
-- Example/demo code that breaks conventions for illustration  
-- Legacy code being gradually phased out
-- Vendor/third-party code
+**PROJECT CONTEXT:**{}
 
-**CONTEXT 3: TEST EXCLUSION** (exclude from test requirements)  
-EXCLUDE if file is:
-- Pure configuration with ONLY constants/settings (no logic)
-- Simple data models without business logic
-- Test files themselves (test_*.py, *_test.py)
-- Files with only imports/exports
-- Example/demo files
-- Generated files
-
-**CRITICAL ANALYSIS POINTS:**
-- Files with business logic, algorithms, complex operations → NEED TESTS
-- Configuration files → usually DON'T need tests but DO need linting
-- Utility functions/helpers → NEED TESTS and linting  
-- Generated files → usually exclude from EVERYTHING
-- User-authored Python code → usually include in linting, may need tests
-
-**PROJECT CONTEXT:**
-{}
-
-**REQUIRED OUTPUT:** For each exclusion type, provide:
-1. Clear YES/NO decision with STRONG reasoning
-2. Specific actionable recommendation
-3. Use warning emojis (⚠️) and capital letters for emphasis when files NEED tests
+**REQUIRED OUTPUT:** For each exclusion type, provide:
+1. Clear YES/NO decision with STRONG reasoning
+2. Specific actionable recommendation
+3. Use warning emojis (⚠️) and capital letters for emphasis when files NEED tests
 
 Be DIRECTIVE and use CLEAR language. If unsure, err on the side of INCLUDING files in quality checks."#,
             file_path.display(),
             file_name,
             extension,
-            if !context_info.is_empty() {
-                format!("\n\nProject context:\n{}", context_info)
-            } else {
-                String::new()
-            },
-            extension,
-            file_content,
-            context_info
+            self.content_section(file_path, file_content),
+            EXCLUSION_CRITERIA,
+            project_context(file_path)
         )
     }
 
-    /// Provide heuristic analysis when Cerebras API is not configured
-    fn heuristic_analysis(&self, file_path: &Path) -> ExclusionAnalysis {
-        let file_name = file_path
-            .file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or("");
+    /// The file as shown to exclusion analysis: for longer files with uncommitted
+    /// changes, the file's header and the diff against HEAD instead of the whole content
+    fn content_section(&self, file_path: &Path, file_content: &str) -> String {
         let extension = file_path
             .extension()
             .and_then(|ext| ext.to_str())
             .unwrap_or("");
+        let diff = (self.config.diff_analysis && file_content.lines().count() > DIFF_MIN_LINES)
+            .then(|| checks::file_diff(file_path, DIFF_CONTEXT_LINES))
+            .flatten();
+        match diff {
+            Some(diff) => {
+                let header: Vec<&str> = file_content.lines().take(DIFF_HEADER_LINES).collect();
+                format!(
+                    "File header (first {} of {} lines):\n```{}\n{}\n```\n\nChanges since the last commit:\n```diff\n{}\n```\n\nFocus on what the change does; the rest of the file is unchanged.",
+                    header.len(),
+                    file_content.lines().count(),
+                    extension,
+                    header.join("\n"),
+                    diff
+                )
+            }
+            None => format!("File content:\n```{}\n{}\n```", extension, file_content),
+        }
+    }
 
-        // Basic heuristics for common file types
-        let (should_exclude_general, should_exclude_lint, should_exclude_test, reasoning) =
-            match (file_name, extension) {
-                (name, _) if name.starts_with("test_") || name.ends_with("_test.py") => {
-                    (false, true, true, "Test files should be excluded from testing requirements and may have relaxed linting")
-                },
-                (_, "pyc") | (_, "pyo") | (_, "pyd") => {
-                    (true, true, true, "Compiled Python files should be excluded from all processing")
-                },
-                (name, _) if name.contains("__pycache__") => {
-                    (true, true, true, "Python cache files should be excluded from all processing")
-                },
-                (name, _) if name.starts_with(".") => {
-                    (true, true, true, "Hidden files typically don't require processing")
-                },
-                (_, "py") => {
-                    (false, false, false, "Regular Python files should be processed normally")
-                },
-                _ => {
-                    (true, true, true, "Non-Python files excluded from Python-specific processing")
-                }
-            };
+    /// Create one prompt analyzing several files changed by the same hook event
+    fn create_batch_analysis_prompt(&self, files: &[(PathBuf, String)]) -> String {
+        let mut prompt = format!(
+            r#"You are an expert software developer analyzing Python files for intelligent exclusion patterns in a code quality toolchain.
 
-        ExclusionAnalysis {
-            should_exclude_general,
-            should_exclude_lint,
-            should_exclude_test,
-            reasoning: reasoning.to_string(),
-            file_type: format!("{} file", extension),
-            purpose: "Unknown (analyzed without AI)".to_string(),
-            exclusion_recommendation: format!(
-                "Based on file pattern analysis: general={}, lint={}, test={}",
-                should_exclude_general, should_exclude_lint, should_exclude_test
-            ),
+The following {} files were changed together. Analyze EACH file separately and return one entry per file in `analyses`, with `file_path` copied exactly from its heading.
+"#,
+            files.len()
+        );
+        if let Some((file_path, _)) = files.first() {
+            prompt.push_str(&format!(
+                "\n**PROJECT CONTEXT:**{}\n",
+                project_context(file_path)
+            ));
+        }
+
+        for (file_path, file_content) in files {
+            prompt.push_str(&format!(
+                "\n## File: {}\n{}\n",
+                file_path.display(),
+                self.content_section(file_path, file_content)
+            ));
         }
+
+        prompt.push_str(&format!(
+            "\n**YOUR TASK:** For each file, make SPECIFIC, CLEAR decisions for each exclusion context:\n\n{}\n\nBe DIRECTIVE and use CLEAR language. If unsure, err on the side of INCLUDING files in quality checks.",
+            EXCLUSION_CRITERIA
+        ));
+        prompt
+    }
+
+    /// Note explaining why an analysis below `ai.min_confidence` isn't trusted
+    fn low_confidence_note(&self, confidence: f32) -> Option<String> {
+        let min_confidence = self.config.min_confidence?;
+        (confidence < min_confidence).then(|| {
+            format!(
+                "ℹ️ AI confidence {confidence:.2} is below ai.min_confidence {min_confidence:.2}, so basic analysis was used instead."
+            )
+        })
+    }
+
+    /// Heuristic analysis standing in for an AI analysis that couldn't be used
+    fn degraded_analysis(&self, file_path: &Path, note: &str) -> ExclusionAnalysis {
+        let mut analysis = heuristic_analysis(file_path);
+        analysis.reasoning = format!("{note} {}", analysis.reasoning);
+        analysis
     }
 
     /// Conservative analysis when API fails - assumes files need full processing
@@ -653,6 +1431,7 @@ Be DIRECTIVE and use CLEAR language. If unsure, err on the side of INCLUDING fil
             file_type: "Unknown (API unavailable)".to_string(),
             purpose: "Unknown - assuming requires full validation".to_string(),
             exclusion_recommendation: "⚠️ Could not analyze file due to API error. File will be processed normally. Ensure tests exist for this file if it contains business logic.".to_string(),
+            confidence: 1.0,
         }
     }
 
@@ -663,7 +1442,11 @@ Be DIRECTIVE and use CLEAR language. If unsure, err on the side of INCLUDING fil
         project_path: &Path,
         source_file: Option<&Path>,
     ) -> Result<TestFailureAnalysis> {
-        if !self.config.enabled {
+        if let Some(mock) = self.mock() {
+            return Ok(mock.test_analysis(output));
+        }
+
+        if !self.config.enabled || source_file.is_some_and(|file| !self.may_send(file)) {
             return Ok(self.basic_test_failure_analysis(output));
         }
 
@@ -672,30 +1455,282 @@ Be DIRECTIVE and use CLEAR language. If unsure, err on the side of INCLUDING fil
             .call_cerebras_comprehensive_test_analysis(output, project_path, source_file)
             .await
         {
-            Ok(analysis) => Ok(analysis),
+            Ok(analysis) => Ok(match self.low_confidence_note(analysis.confidence) {
+                Some(note) => {
+                    let mut basic = self.basic_test_failure_analysis(output);
+                    basic.coverage_analysis = format!("{note} {}", basic.coverage_analysis);
+                    basic
+                }
+                None => analysis,
+            }),
             Err(e) => {
                 eprintln!("Warning: Cerebras test analysis failed: {}", e);
-                Ok(self.basic_test_failure_analysis(output))
+                let mut analysis = self.basic_test_failure_analysis(output);
+                if let Some(note) = degradation_note(&e) {
+                    analysis.coverage_analysis = format!("{note} {}", analysis.coverage_analysis);
+                }
+                Ok(analysis)
             }
         }
     }
 
+    /// Propose tests for a module and write them as a complete test file; without AI
+    /// (or when it fails) the file holds a placeholder test per public function
+    pub async fn generate_tests(
+        &self,
+        source_file: &Path,
+        project_root: &Path,
+        framework: TestFramework,
+    ) -> Result<GeneratedTests> {
+        let source = self.read_file_content(source_file)?;
+        let module = module_path(source_file, project_root);
+        let skeleton = GeneratedTests::skeleton(&source, &module, framework);
+
+        if let Some(mock) = self.mock() {
+            return Ok(mock.generated_tests(skeleton));
+        }
+
+        if !self.config.enabled || !self.may_send(source_file) {
+            return Ok(skeleton);
+        }
+
+        match self
+            .call_test_generation(source_file, &source, &module, project_root, framework)
+            .await
+        {
+            Ok(tests) => match self.low_confidence_note(tests.confidence) {
+                Some(note) => {
+                    eprintln!("{note}");
+                    Ok(skeleton)
+                }
+                None => Ok(tests),
+            },
+            Err(e) => {
+                match degradation_note(&e) {
+                    Some(note) => eprintln!("{note}"),
+                    None => eprintln!("Warning: AI test generation failed: {}", e),
+                }
+                Ok(skeleton)
+            }
+        }
+    }
+
+    /// Make API call to generate tests for a module
+    async fn call_test_generation(
+        &self,
+        source_file: &Path,
+        source: &str,
+        module: &str,
+        project_root: &Path,
+        framework: TestFramework,
+    ) -> Result<GeneratedTests> {
+        let fixture_dirs: Vec<PathBuf> = std::iter::once(project_root.join("tests"))
+            .chain(source_file.parent().map(Path::to_path_buf))
+            .collect();
+        let fixture_context = match framework {
+            TestFramework::Pytest => self.fixture_context(project_root, &fixture_dirs),
+            TestFramework::Unittest => String::new(),
+        };
+        let prompt = format!(
+            r#"You are an expert Python developer writing tests for an existing module.
+
+Source file: {}
+Import it as: `{module}`{}
+
+Source code:
+```python
+{source}
+```{fixture_context}
+
+Write a complete {} test file for this module:
+- Cover each public function and class: the main behavior, edge cases and error handling
+- Only call functions, classes and fixtures that exist in the code above; don't invent helpers
+- Mock network, filesystem and database access instead of relying on real services
+- Use descriptive test names starting with `test_`
+
+List every test case with what it verifies, and return the full file content in `test_code`."#,
+            source_file.display(),
+            project_context(source_file),
+            framework.name(),
+        );
+
+        let json_schema = JsonSchema {
+            name: "generated_tests".to_string(),
+            description: "Tests generated for a module".to_string(),
+            schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "test_cases": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "name": {"type": "string"},
+                                "description": {"type": "string"}
+                            },
+                            "required": ["name", "description"]
+                        }
+                    },
+                    "test_code": {
+                        "type": "string",
+                        "description": "Complete content of the test file"
+                    },
+                    "confidence": {
+                        "type": "number",
+                        "description": "Confidence in these tests, from 0.0 (guess) to 1.0 (certain)"
+                    }
+                },
+                "required": ["test_cases", "test_code", "confidence"]
+            }),
+        };
+
+        let content = self
+            .complete_json(AnalysisKind::Test, prompt, 0.3, 0.9, json_schema)
+            .await?;
+
+        serde_json::from_str(extract_json(&content)).context("Failed to parse generated tests")
+    }
+
+    /// Ask the provider for a unified diff to `file_path` fixing a recorded lint or test
+    /// failure; unlike the analyses there is no fallback, so this fails without AI
+    pub async fn propose_fix(
+        &self,
+        file_path: &Path,
+        kind: AnalysisKind,
+        failure: &str,
+    ) -> Result<ProposedFix> {
+        if let Some(mock) = self.mock() {
+            return mock.proposed_fix().ok_or_else(|| {
+                GuardrailsError::AiProvider("The mock provider has no canned fix.json".to_string())
+                    .into()
+            });
+        }
+        if !self.config.enabled {
+            return Err(GuardrailsError::AiProvider(
+                "AI analysis is disabled; set CEREBRAS_API_KEY or configure a local ai.provider"
+                    .to_string(),
+            )
+            .into());
+        }
+        if !self.may_send(file_path) {
+            return Err(GuardrailsError::AiProvider(format!(
+                "{} matches ai.never_send",
+                file_path.display()
+            ))
+            .into());
+        }
+
+        let content = self.read_file_content(file_path)?;
+        let numbered: Vec<String> = content
+            .lines()
+            .enumerate()
+            .map(|(index, line)| format!("{:>4} {line}", index + 1))
+            .collect();
+        let failure_type = match kind {
+            AnalysisKind::Test => "test run",
+            _ => "linter",
+        };
+        let prompt = format!(
+            r#"You are an expert Python developer fixing a failure reported by the {failure_type}.
+
+File: {}{}
+
+Current content (line numbers are for reference only, not part of the file):
+```
+{}
+```
+
+Failure report:
+```
+{failure}
+```
+
+Propose the smallest change to THIS FILE ONLY that fixes the failure. Return it as a unified diff (`--- a/<file>`, `+++ b/<file>`, `@@` hunks with 3 lines of context) whose context and removed lines match the current content exactly. Don't reformat unrelated code. If the failure can't be fixed in this file, return an empty diff and explain why."#,
+            file_path.display(),
+            project_context(file_path),
+            numbered.join("\n"),
+        );
+
+        let json_schema = JsonSchema {
+            name: "proposed_fix".to_string(),
+            description: "Patch fixing a lint or test failure".to_string(),
+            schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "explanation": {
+                        "type": "string",
+                        "description": "What the patch changes and why"
+                    },
+                    "diff": {
+                        "type": "string",
+                        "description": "Unified diff against the file, empty when no fix is possible"
+                    },
+                    "confidence": {
+                        "type": "number",
+                        "description": "Confidence in the patch, from 0.0 (guess) to 1.0 (certain)"
+                    }
+                },
+                "required": ["explanation", "diff", "confidence"]
+            }),
+        };
+
+        let content = self
+            .complete_json(kind, prompt, 0.2, 0.9, json_schema)
+            .await?;
+        serde_json::from_str(extract_json(&content)).context("Failed to parse proposed fix")
+    }
+
     /// Analyze lint output using Cerebras AI
     pub async fn analyze_lint_output(
         &self,
         output: &str,
         file_path: Option<&Path>,
     ) -> Result<LintAnalysis> {
-        if !self.config.enabled {
+        if let Some(mock) = self.mock() {
+            return Ok(mock.lint_analysis(output));
+        }
+
+        if !self.config.enabled || file_path.is_some_and(|file| !self.may_send(file)) {
             return Ok(self.basic_lint_analysis(output));
         }
 
         // Handle API errors gracefully with basic analysis
         match self.call_cerebras_lint_analysis(output, file_path).await {
-            Ok(analysis) => Ok(analysis),
+            // Low confidence shows every finding rather than trusting the filtering
+            Ok(analysis) => {
+                let note = match self.low_confidence_note(analysis.confidence) {
+                    Some(note) => Some(note),
+                    None if !analysis.has_real_issues && !output.trim().is_empty() => {
+                        let claim = format!(
+                            "Every finding in this linter output is a false positive that needs no fix. Reasoning: {}",
+                            analysis.reasoning
+                        );
+                        let subject = format!("Linter output:\n```\n{output}\n```");
+                        (!self
+                            .second_opinion_agrees(AnalysisKind::Lint, &claim, &subject)
+                            .await)
+                            .then(|| {
+                                "⚠️ A second AI opinion didn't confirm that these findings are false positives.".to_string()
+                            })
+                    }
+                    None => None,
+                };
+                Ok(match note {
+                    Some(note) => {
+                        let mut basic = self.basic_lint_analysis(output);
+                        basic.reasoning = format!("{note} {}", basic.reasoning);
+                        basic
+                    }
+                    None => analysis,
+                })
+            }
             Err(e) => {
                 eprintln!("Warning: Cerebras lint analysis failed: {}", e);
-                Ok(self.basic_lint_analysis(output))
+                let mut analysis = self.basic_lint_analysis(output);
+                if let Some(note) = degradation_note(&e) {
+                    analysis.reasoning = format!("{note} {}", analysis.reasoning);
+                }
+                Ok(analysis)
             }
         }
     }
@@ -709,98 +1744,69 @@ Be DIRECTIVE and use CLEAR language. If unsure, err on the side of INCLUDING fil
     ) -> Result<TestFailureAnalysis> {
         let prompt = self.create_comprehensive_test_prompt(output, project_path, source_file);
 
-        let request = ChatRequest {
-            model: self.config.model.clone(),
-            messages: vec![ChatMessage {
-                role: "user".to_string(),
-                content: prompt,
-            }],
-            temperature: 0.3,
-            top_p: 0.9,
-            response_format: ResponseFormat {
-                format_type: "json_schema".to_string(),
-                json_schema: JsonSchema {
-                    name: "test_failure_analysis".to_string(),
-                    description: "Analysis of test failure output".to_string(),
-                    schema: serde_json::json!({
-                        "type": "object",
-                        "properties": {
-                            "has_failures": {
-                                "type": "boolean",
-                                "description": "Whether there are actual test failures"
-                            },
-                            "summary": {
-                                "type": "string",
-                                "description": "Brief summary of test execution results"
+        let json_schema = JsonSchema {
+            name: "test_failure_analysis".to_string(),
+            description: "Analysis of test failure output".to_string(),
+            schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "has_failures": {
+                        "type": "boolean",
+                        "description": "Whether there are actual test failures"
+                    },
+                    "summary": {
+                        "type": "string",
+                        "description": "Brief summary of test execution results"
+                    },
+                    "failed_tests": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "test_name": {"type": "string"},
+                                "error_type": {"type": "string"},
+                                "error_message": {"type": "string"},
+                                "suggested_fix": {"type": "string"}
                             },
-                            "failed_tests": {
-                                "type": "array",
-                                "items": {
-                                    "type": "object",
-                                    "properties": {
-                                        "test_name": {"type": "string"},
-                                        "error_type": {"type": "string"},
-                                        "error_message": {"type": "string"},
-                                        "suggested_fix": {"type": "string"}
-                                    },
-                                    "required": ["test_name", "error_type", "error_message", "suggested_fix"]
-                                }
-                            },
-                            "analysis": {
-                                "type": "string",
-                                "description": "Detailed analysis of test execution and failures"
-                            },
-                            "recommendations": {
-                                "type": "string",
-                                "description": "Specific actionable recommendations for immediate fixes"
-                            },
-                            "coverage_analysis": {
-                                "type": "string",
-                                "description": "Analysis of test coverage gaps and missing scenarios"
-                            },
-                            "missing_tests": {
-                                "type": "array",
-                                "items": {"type": "string"},
-                                "description": "List of specific test functions or scenarios that should be added"
-                            },
-                            "quality_assessment": {
-                                "type": "string",
-                                "description": "Assessment of overall test quality and completeness"
-                            }
-                        },
-                        "required": ["has_failures", "summary", "failed_tests", "analysis", "recommendations", "coverage_analysis", "missing_tests", "quality_assessment"]
-                    }),
+                            "required": ["test_name", "error_type", "error_message", "suggested_fix"]
+                        }
+                    },
+                    "analysis": {
+                        "type": "string",
+                        "description": "Detailed analysis of test execution and failures"
+                    },
+                    "recommendations": {
+                        "type": "string",
+                        "description": "Specific actionable recommendations for immediate fixes"
+                    },
+                    "coverage_analysis": {
+                        "type": "string",
+                        "description": "Analysis of test coverage gaps and missing scenarios"
+                    },
+                    "missing_tests": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "List of specific test functions or scenarios that should be added"
+                    },
+                    "quality_assessment": {
+                        "type": "string",
+                        "description": "Assessment of overall test quality and completeness"
+                    },
+                    "confidence": {
+                        "type": "number",
+                        "description": "Confidence in these decisions, from 0.0 (guess) to 1.0 (certain)"
+                    }
                 },
-            },
+                "required": ["has_failures", "summary", "failed_tests", "analysis", "recommendations", "coverage_analysis", "missing_tests", "quality_assessment", "confidence"]
+            }),
         };
 
-        let response = self
-            .client
-            .post(format!("{}/chat/completions", self.config.base_url))
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .context("Failed to send request to Cerebras API")?;
+        let content = self
+            .complete_json(AnalysisKind::Test, prompt, 0.3, 0.9, json_schema)
+            .await?;
 
-        if !response.status().is_success() {
-            return Err(anyhow::anyhow!("API request failed: {}", response.status()));
-        }
-
-        let chat_response: ChatResponse = response
-            .json()
-            .await
-            .context("Failed to parse response JSON")?;
-
-        let content = chat_response
-            .choices
-            .first()
-            .and_then(|choice| choice.message.content.as_ref())
-            .context("No content in API response")?;
-
-        let analysis: TestFailureAnalysis =
-            serde_json::from_str(content).context("Failed to parse analysis JSON")?;
+        let analysis: TestFailureAnalysis = serde_json::from_str(extract_json(&content))
+            .context("Failed to parse analysis JSON")?;
 
         Ok(analysis)
     }
@@ -813,76 +1819,52 @@ Be DIRECTIVE and use CLEAR language. If unsure, err on the side of INCLUDING fil
     ) -> Result<LintAnalysis> {
         let prompt = self.create_lint_output_prompt(output, file_path);
 
-        let request = ChatRequest {
-            model: self.config.model.clone(),
-            messages: vec![ChatMessage {
-                role: "user".to_string(),
-                content: prompt,
-            }],
-            temperature: 0.3,
-            top_p: 0.9,
-            response_format: ResponseFormat {
-                format_type: "json_schema".to_string(),
-                json_schema: JsonSchema {
-                    name: "lint_analysis".to_string(),
-                    description: "Analysis of linter output".to_string(),
-                    schema: serde_json::json!({
-                        "type": "object",
-                        "properties": {
-                            "has_real_issues": {
-                                "type": "boolean",
-                                "description": "Whether there are real issues that need fixing"
-                            },
-                            "filtered_output": {
-                                "type": "string",
-                                "description": "Linter output with only real issues (empty if no real issues)"
-                            },
-                            "reasoning": {
-                                "type": "string",
-                                "description": "Brief explanation of what was filtered and why"
-                            },
-                            "issue_count": {
-                                "type": "integer",
-                                "description": "Number of real issues found"
-                            },
-                            "recommendations": {
-                                "type": "string",
-                                "description": "Specific recommendations for fixing the issues"
-                            }
-                        },
-                        "required": ["has_real_issues", "filtered_output", "reasoning", "issue_count", "recommendations"]
-                    }),
+        let json_schema = JsonSchema {
+            name: "lint_analysis".to_string(),
+            description: "Analysis of linter output".to_string(),
+            schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "has_real_issues": {
+                        "type": "boolean",
+                        "description": "Whether there are real issues that need fixing"
+                    },
+                    "filtered_output": {
+                        "type": "string",
+                        "description": "Linter output with only real issues (empty if no real issues)"
+                    },
+                    "false_positives": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "description": "Linter output lines that are false positives, each copied verbatim"
+                    },
+                    "reasoning": {
+                        "type": "string",
+                        "description": "Brief explanation of what was filtered and why"
+                    },
+                    "issue_count": {
+                        "type": "integer",
+                        "description": "Number of real issues found"
+                    },
+                    "recommendations": {
+                        "type": "string",
+                        "description": "Specific recommendations for fixing the issues"
+                    },
+                    "confidence": {
+                        "type": "number",
+                        "description": "Confidence in these decisions, from 0.0 (guess) to 1.0 (certain)"
+                    }
                 },
-            },
+                "required": ["has_real_issues", "filtered_output", "false_positives", "reasoning", "issue_count", "recommendations", "confidence"]
+            }),
         };
 
-        let response = self
-            .client
-            .post(format!("{}/chat/completions", self.config.base_url))
-            .header("Authorization", format!("Bearer {}", self.config.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await
-            .context("Failed to send request to Cerebras API")?;
-
-        if !response.status().is_success() {
-            return Err(anyhow::anyhow!("API request failed: {}", response.status()));
-        }
-
-        let chat_response: ChatResponse = response
-            .json()
-            .await
-            .context("Failed to parse response JSON")?;
-
-        let content = chat_response
-            .choices
-            .first()
-            .and_then(|choice| choice.message.content.as_ref())
-            .context("No content in API response")?;
+        let content = self
+            .complete_json(AnalysisKind::Lint, prompt, 0.3, 0.9, json_schema)
+            .await?;
 
-        let analysis: LintAnalysis =
-            serde_json::from_str(content).context("Failed to parse analysis JSON")?;
+        let analysis: LintAnalysis = serde_json::from_str(extract_json(&content))
+            .context("Failed to parse analysis JSON")?;
 
         Ok(analysis)
     }
@@ -894,18 +1876,58 @@ Be DIRECTIVE and use CLEAR language. If unsure, err on the side of INCLUDING fil
         project_path: &Path,
         source_file: Option<&Path>,
     ) -> String {
+        let prompt = self.build_test_prompt(output, project_path, source_file, false);
+        if !self.over_budget(AnalysisKind::Test, &prompt) {
+            return prompt;
+        }
+        self.build_test_prompt(output, project_path, source_file, true)
+    }
+
+    /// Test analysis prompt; `condense` keeps only the failing parts of the output and
+    /// tests, and the signatures of the source
+    fn build_test_prompt(
+        &self,
+        output: &str,
+        project_path: &Path,
+        source_file: Option<&Path>,
+        condense: bool,
+    ) -> String {
+        let failing = failing_test_names(output);
+        let condensed_output;
+        let output = if condense {
+            condensed_output = condense_test_output(output);
+            condensed_output.as_str()
+        } else {
+            output
+        };
         let mut source_content = String::new();
         let mut test_content = String::new();
         let mut file_context = String::new();
+        let mut uses_hypothesis = imports_hypothesis(output);
+        let mut import_context = String::new();
+        let mut fixture_dirs = Vec::new();
 
         // Read source file if provided
         if let Some(source_path) = source_file {
             file_context = format!("Source file: {}", source_path.display());
 
             if let Ok(content) = self.read_file_content(source_path) {
+                let imports = import_lines(&content);
+                if !imports.is_empty() {
+                    import_context = format!(
+                        "\n\nSource module imports:\n```python\n{}\n```",
+                        imports.join("\n")
+                    );
+                }
+                let content = if condense {
+                    condensed_source(&content)
+                } else {
+                    content
+                };
                 source_content =
                     format!("\n\nSource code being tested:\n```python\n{}\n```", content);
             }
+            fixture_dirs.extend(source_path.parent().map(Path::to_path_buf));
 
             // Try to find corresponding test file
             let source_name = source_path
@@ -921,8 +1943,15 @@ Be DIRECTIVE and use CLEAR language. If unsure, err on the side of INCLUDING fil
 
             for test_path_str in &possible_test_paths {
                 let test_path = project_path.join(test_path_str);
-                if test_path.exists() {
+                if test_path.exists() && self.may_send(&test_path) {
+                    fixture_dirs.extend(test_path.parent().map(Path::to_path_buf));
                     if let Ok(content) = self.read_file_content(&test_path) {
+                        uses_hypothesis |= imports_hypothesis(&content);
+                        let content = if condense {
+                            drop_passing_test_bodies(&content, &failing)
+                        } else {
+                            content
+                        };
                         test_content = format!(
                             "\n\nExisting test file ({}): \n```python\n{}\n```",
                             test_path.display(),
@@ -937,12 +1966,42 @@ Be DIRECTIVE and use CLEAR language. If unsure, err on the side of INCLUDING fil
                 test_content = "\n\n⚠️ No test file found for this source file.".to_string();
             }
         }
+        if fixture_dirs.is_empty() {
+            fixture_dirs.push(project_path.join("tests"));
+        }
+        let fixture_context = self.fixture_context(project_path, &fixture_dirs);
+
+        let property_context = if uses_hypothesis && self.config.property_test_analysis {
+            r#"
+
+## Property-Based Testing (Hypothesis)
+The tests use Hypothesis. In the coverage analysis, explicitly evaluate:
+- **Property coverage**: Which invariants/properties of the source code are asserted, and which important properties are missing
+- **Strategies**: Whether the input strategies cover the real input domain (boundaries, empty values, unicode, large values)
+- **Shrinking output**: For failures, interpret the "Falsifying example" as the minimal counterexample and explain what it reveals
+Do not give generic edge-case advice that Hypothesis strategies already cover."#
+        } else if uses_hypothesis {
+            "\n\nNote: the tests use Hypothesis property-based testing."
+        } else {
+            ""
+        };
+
+        let context_path = source_file.unwrap_or(project_path);
+        let endpoint_context = match detect_framework(context_path) {
+            Some(Framework::FastApi | Framework::Flask) => {
+                r#"
+
+## Endpoint Coverage
+This is a web application. In the coverage analysis, list the routes defined in the source and, for each one, whether the tests exercise it through the test client, including error responses (404, validation errors, auth failures) and not just the success path."#
+            }
+            _ => "",
+        };
 
         format!(
             r#"You are an expert Python developer conducting a comprehensive test analysis.
 
-Project: {}
-{}{}{}
+Project: {}{}
+{}{}{}{}{}
 
 Test execution output:
 ```
@@ -984,19 +2043,87 @@ Rate the current test suite (if tests exist):
 - Quality: Are tests well-written and maintainable?
 - Missing scenarios: What important cases are untested?
 
-Focus on being COMPREHENSIVE, SPECIFIC, and ACTIONABLE. Even if tests pass, suggest improvements and additional test coverage."#,
+Focus on being COMPREHENSIVE, SPECIFIC, and ACTIONABLE. Even if tests pass, suggest improvements and additional test coverage.{}{}"#,
             project_path.display(),
+            project_context(context_path),
             file_context,
             source_content,
+            import_context,
             test_content,
-            output
+            fixture_context,
+            output,
+            property_context,
+            endpoint_context
+        )
+    }
+
+    /// The conftest.py files whose fixtures the tests in `dirs` can use (from each
+    /// directory up to the project root, root first), with an instruction to rely on them
+    fn fixture_context(&self, project_path: &Path, dirs: &[PathBuf]) -> String {
+        let mut conftests: Vec<PathBuf> = Vec::new();
+        for dir in dirs {
+            let mut chain: Vec<PathBuf> = dir
+                .ancestors()
+                .take_while(|ancestor| ancestor.starts_with(project_path))
+                .map(|ancestor| ancestor.join("conftest.py"))
+                .filter(|conftest| conftest.is_file())
+                .collect();
+            chain.reverse();
+            for conftest in chain {
+                if !conftests.contains(&conftest) {
+                    conftests.push(conftest);
+                }
+            }
+        }
+
+        let sections: Vec<String> = conftests
+            .iter()
+            .filter(|conftest| self.may_send(conftest))
+            .filter_map(|conftest| {
+                let content = self.read_file_content(conftest).ok()?;
+                let content = if content.chars().count() > MAX_CONFTEST_CHARS {
+                    let kept: String = content.chars().take(MAX_CONFTEST_CHARS).collect();
+                    format!("{kept}\n# ... (truncated)")
+                } else {
+                    content
+                };
+                Some(format!(
+                    "### {}\n```python\n{}\n```",
+                    conftest
+                        .strip_prefix(project_path)
+                        .unwrap_or(conftest)
+                        .display(),
+                    content
+                ))
+            })
+            .collect();
+        if sections.is_empty() {
+            return "\n\nNo conftest.py fixtures apply to these tests; don't suggest fixtures that would need to be created without saying so.".to_string();
+        }
+        format!(
+            "\n\nFixtures available to these tests (conftest.py):\n{}\n\nWhen suggesting fixes, only use the fixtures and helpers shown above or imported by the source module; don't invent ones that don't exist.",
+            sections.join("\n")
         )
     }
 
     /// Create prompt for lint output analysis  
     fn create_lint_output_prompt(&self, output: &str, file_path: Option<&Path>) -> String {
         let file_context = if let Some(path) = file_path {
-            format!("\nFile being linted: {}", path.display())
+            let diff = self
+                .config
+                .diff_analysis
+                .then(|| checks::file_diff(path, DIFF_CONTEXT_LINES))
+                .flatten()
+                .map(|diff| {
+                    format!("\n\nChanges since the last commit (findings outside these lines predate this edit, so weigh them less):\n```diff\n{diff}\n```")
+                })
+                .unwrap_or_default();
+            format!(
+                "\nFile being linted: {}{}{}",
+                path.display(),
+                project_context(path),
+                diff
+            )
         } else {
             String::new()
         };
@@ -1024,6 +2151,7 @@ Provide:
 2. **Issue Count**: Number of real issues found
 3. **Reasoning**: Brief explanation of what was filtered and why
 4. **Recommendations**: Specific suggestions for fixing the real issues
+5. **False Positives**: Each linter output line you are sure is a false positive, copied verbatim (leave out anything you are unsure about)
 
 If all issues are false positives, return empty filtered_output and explain why in the reasoning."#,
             file_context, output
@@ -1032,18 +2160,34 @@ If all issues are false positives, return empty filtered_output and explain why
 
     /// Basic test failure analysis when AI is not available
     fn basic_test_failure_analysis(&self, output: &str) -> TestFailureAnalysis {
-        let has_failures =
-            output.contains("FAILED") || output.contains("ERROR") || output.contains("FAIL");
+        let failed_tests: Vec<FailedTest> = PytestResults::parse(output)
+            .failures()
+            .map(|case| FailedTest {
+                test_name: case.node_id.clone(),
+                error_type: case.error_type(),
+                error_message: case.error_message(),
+                suggested_fix: format!(
+                    "See the traceback for {} in the output below",
+                    case.node_id
+                ),
+            })
+            .collect();
+        let has_failures = !failed_tests.is_empty()
+            || output.contains("FAILED")
+            || output.contains("ERROR")
+            || output.contains("FAIL");
         let line_count = output.lines().count();
 
         TestFailureAnalysis {
             has_failures,
-            summary: if has_failures {
+            summary: if !failed_tests.is_empty() {
+                format!("{} test(s) failed", failed_tests.len())
+            } else if has_failures {
                 format!("Test failures detected in {} lines of output", line_count)
             } else {
                 "No clear test failures detected".to_string()
             },
-            failed_tests: vec![], // Can't parse specific tests without AI
+            failed_tests,
             analysis: "Basic analysis without AI - full output shown".to_string(),
             recommendations: if has_failures {
                 "Review the test output above for specific failure details. Run tests individually with -v flag for more details.".to_string()
@@ -1054,6 +2198,7 @@ If all issues are false positives, return empty filtered_output and explain why
                 "AI analysis not available. Consider manually reviewing test coverage.".to_string(),
             missing_tests: vec![], // Can't determine without AI analysis
             quality_assessment: "Unable to assess test quality without AI analysis.".to_string(),
+            confidence: 1.0,
         }
     }
 
@@ -1072,16 +2217,312 @@ If all issues are false positives, return empty filtered_output and explain why
             } else {
                 "No linting issues detected.".to_string()
             },
+            false_positives: vec![],
+            confidence: 1.0,
         }
     }
 }
 
+/// Prompt lines describing the project, shared by every prompt builder: a snapshot of
+/// its root, layout, dependencies and tests, then the target Python version (so
+/// suggestions don't use newer syntax or APIs) and framework conventions
+fn project_context(path: &Path) -> String {
+    let dir = if path.is_file() {
+        path.parent().unwrap_or(path)
+    } else {
+        path
+    };
+
+    let mut context = format!("\n{}", ProjectSnapshot::for_path(path).render());
+    if let Some(version) = dir.ancestors().find_map(PythonVersion::detect) {
+        context.push_str(&format!(
+            "\nTarget Python version: {version} (only suggest syntax and standard library APIs available in Python {version})"
+        ));
+    }
+    if let Some(framework) = detect_framework(path) {
+        context.push_str(&format!(
+            "\nFramework: {}\n{}",
+            framework.describe(),
+            framework_guidance(&framework)
+        ));
+    }
+    context
+}
+
+/// Framework of the project containing `path`, falling back to the imports of `path`
+/// itself when it's a module of a project that doesn't declare its dependencies
+fn detect_framework(path: &Path) -> Option<Framework> {
+    let dir = if path.is_file() {
+        path.parent().unwrap_or(path)
+    } else {
+        path
+    };
+    dir.ancestors().find_map(Framework::detect).or_else(|| {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|source| Framework::from_imports(&source))
+    })
+}
+
+/// JSON schema of a single [`ExclusionAnalysis`]
+fn exclusion_analysis_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {
+            "should_exclude_general": {
+                "type": "boolean",
+                "description": "Whether file should be excluded from general processing"
+            },
+            "should_exclude_lint": {
+                "type": "boolean",
+                "description": "Whether file should be excluded from linting"
+            },
+            "should_exclude_test": {
+                "type": "boolean",
+                "description": "Whether file should be excluded from testing"
+            },
+            "reasoning": {
+                "type": "string",
+                "description": "Detailed reasoning for exclusion recommendations"
+            },
+            "file_type": {
+                "type": "string",
+                "description": "Detected file type/category"
+            },
+            "purpose": {
+                "type": "string",
+                "description": "Primary purpose of the file"
+            },
+            "exclusion_recommendation": {
+                "type": "string",
+                "description": "Specific recommendation for guardrails configuration"
+            },
+            "confidence": {
+                "type": "number",
+                "description": "Confidence in these decisions, from 0.0 (guess) to 1.0 (certain)"
+            }
+        },
+        "required": [
+            "should_exclude_general",
+            "should_exclude_lint",
+            "should_exclude_test",
+            "reasoning",
+            "file_type",
+            "purpose",
+            "exclusion_recommendation",
+            "confidence"
+        ]
+    })
+}
+
+/// Parse a batched exclusion analysis reply into analyses keyed by file path
+fn parse_batch_analysis(content: &str) -> Result<HashMap<String, ExclusionAnalysis>> {
+    let batch: BatchExclusionAnalysis = serde_json::from_str(extract_json(content))
+        .context("Failed to parse batched exclusion analysis from Cerebras response")?;
+    Ok(batch
+        .analyses
+        .into_iter()
+        .map(|entry| (entry.file_path, entry.analysis))
+        .collect())
+}
+
+/// Decision rules shared by the single-file and batched exclusion prompts
+const EXCLUSION_CRITERIA: &str = r#"**CONTEXT 1: GENERAL EXCLUSION** (exclude from ALL processing)
+EXCLUDE if file is:
+- Generated/compiled: *_pb2.py, *_pb2_grpc.py, migrations, .pyc, .pyo, .pyd
+- Binary/non-text: images, databases, compiled artifacts  
+- Cache/temporary: __pycache__, .pytest_cache, build artifacts
+- Third-party vendor code that shouldn't be modified
+
+**CONTEXT 2: LINT EXCLUSION** (exclude from linting/formatting)
+EXCLUDE if file is:
+- Generated code that can't be reformatted (migrations, protobuf)
+- Test files that intentionally break style rules for readability
+- Example/demo code that breaks conventions for illustration  
+- Legacy code being gradually phased out
+- Vendor/third-party code
+
+**CONTEXT 3: TEST EXCLUSION** (exclude from test requirements)  
+EXCLUDE if file is:
+- Pure configuration with ONLY constants/settings (no logic)
+- Simple data models without business logic
+- Test files themselves (test_*.py, *_test.py)
+- Files with only imports/exports
+- Example/demo files
+- Generated files
+
+**CRITICAL ANALYSIS POINTS:**
+- Files with business logic, algorithms, complex operations → NEED TESTS
+- Configuration files → usually DON'T need tests but DO need linting
+- Utility functions/helpers → NEED TESTS and linting  
+- Generated files → usually exclude from EVERYTHING
+- User-authored Python code → usually include in linting, may need tests"#;
+
+/// Framework conventions the analysis should respect
+fn framework_guidance(framework: &Framework) -> &'static str {
+    match framework {
+        Framework::Django { .. } => {
+            "Django conventions: tests that touch the database use django.test.TestCase or pytest-django's `db` fixture, the test client is `django.test.Client`, and migrations are generated files that shouldn't be edited by hand."
+        }
+        Framework::FastApi => {
+            "FastAPI conventions: tests call endpoints through `fastapi.testclient.TestClient(app)` (or httpx.AsyncClient for async tests) created in a pytest fixture, and dependencies are replaced with `app.dependency_overrides` rather than patched."
+        }
+        Framework::Flask => {
+            "Flask conventions: tests use an `app` fixture (the application factory with `TESTING=True`) and a `client` fixture from `app.test_client()`; request-context code runs inside `app.app_context()`."
+        }
+    }
+}
+
+/// Strip markdown fences or chatter around a JSON object, which local models often add
+/// even in JSON mode
+pub(crate) fn extract_json(content: &str) -> &str {
+    match (content.find('{'), content.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => content.trim(),
+    }
+}
+
+/// Shorten a prompt to `max_chars` by cutting the middle, keeping the instructions at
+/// the start and the response format at the end
+fn fit_prompt(prompt: &str, max_chars: usize) -> String {
+    let total = prompt.chars().count();
+    if total <= max_chars {
+        return prompt.to_string();
+    }
+
+    let marker = "\n\n[... truncated to fit the model's context ...]\n\n";
+    let keep = max_chars.saturating_sub(marker.len());
+    let head: String = prompt.chars().take(keep / 2).collect();
+    let tail: String = prompt.chars().skip(total - (keep - keep / 2)).collect();
+    format!("{head}{marker}{tail}")
+}
+
+/// Python source outlined to fit a token budget, marked so the model knows bodies are
+/// missing
+fn condensed_source(content: &str) -> String {
+    format!(
+        "# [condensed to fit the prompt budget: signatures only]\n{}",
+        outline_source(content)
+    )
+}
+
+/// Top-level import statements of a Python module (parenthesized imports included)
+fn import_lines(source: &str) -> Vec<&str> {
+    let mut imports = Vec::new();
+    let mut in_parentheses = false;
+    for line in source.lines() {
+        if in_parentheses {
+            imports.push(line);
+            in_parentheses = !line.contains(')');
+        } else if line.starts_with("import ") || line.starts_with("from ") {
+            imports.push(line);
+            in_parentheses = line.contains('(') && !line.contains(')');
+        }
+    }
+    imports
+}
+
+/// Check if Python source imports the Hypothesis property-testing library
+fn imports_hypothesis(content: &str) -> bool {
+    content.lines().any(|line| {
+        let line = line.trim_start();
+        line.starts_with("import hypothesis") || line.starts_with("from hypothesis")
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn test_offline_mode_config() {
+        let ai = AiConfig {
+            mode: AiMode::Offline,
+            ..AiConfig::default()
+        };
+        let config = CerebrasConfig::from_ai_config(&ai);
+        assert_eq!(config.provider, AiProvider::Ollama);
+        assert_eq!(config.base_url, "http://localhost:11434");
+        assert!(config.enabled);
+        assert_eq!(
+            config.max_prompt_chars,
+            Some(DEFAULT_LOCAL_MAX_PROMPT_CHARS)
+        );
+
+        let ai = AiConfig {
+            mode: AiMode::Offline,
+            provider: Some(AiProvider::LlamaCpp),
+            model: Some("codellama".to_string()),
+            max_prompt_chars: Some(4000),
+            ..AiConfig::default()
+        };
+        let config = CerebrasConfig::from_ai_config(&ai);
+        assert_eq!(config.provider, AiProvider::LlamaCpp);
+        assert_eq!(config.model, "codellama");
+        assert_eq!(config.max_prompt_chars, Some(4000));
+        assert_eq!(config.temperature, None);
+
+        // The hosted API is never used offline, even when asked for explicitly
+        let ai = AiConfig {
+            mode: AiMode::Offline,
+            provider: Some(AiProvider::Cerebras),
+            ..AiConfig::default()
+        };
+        assert!(!CerebrasConfig::from_ai_config(&ai).enabled);
+        let ai = AiConfig {
+            mode: AiMode::Offline,
+            provider: Some(AiProvider::Mock),
+            ..AiConfig::default()
+        };
+        assert!(CerebrasConfig::from_ai_config(&ai).enabled);
+
+        let ai = AiConfig {
+            temperature: Some(0.1),
+            top_p: Some(0.5),
+            request_timeout_seconds: Some(15),
+            ..AiConfig::default()
+        };
+        let config = CerebrasConfig::from_ai_config(&ai);
+        assert_eq!(config.temperature, Some(0.1));
+        assert_eq!(config.top_p, Some(0.5));
+        assert_eq!(config.request_timeout, Duration::from_secs(15));
+
+        let ai = AiConfig {
+            model: Some("large".to_string()),
+            models: AiModels {
+                lint: Some("small".to_string()),
+                ..AiModels::default()
+            },
+            ..AiConfig::default()
+        };
+        let config = CerebrasConfig::from_ai_config(&ai);
+        assert_eq!(config.model_for(AnalysisKind::Lint), "small");
+        assert_eq!(config.model_for(AnalysisKind::Test), "large");
+        assert_eq!(config.model_for(AnalysisKind::Exclusion), "large");
+
+        let config = CerebrasConfig::from_ai_config(&AiConfig::default());
+        assert_eq!(config.provider, AiProvider::Cerebras);
+        assert_eq!(config.max_prompt_chars, None);
+    }
+
+    #[test]
+    fn test_extract_json_and_fit_prompt() {
+        assert_eq!(
+            extract_json("```json\n{\"has_real_issues\": false}\n```"),
+            "{\"has_real_issues\": false}"
+        );
+        assert_eq!(extract_json(" {\"a\": {\"b\": 1}} "), "{\"a\": {\"b\": 1}}");
+
+        assert_eq!(fit_prompt("short", 100), "short");
+        let prompt = format!("INSTRUCTIONS{}RESPOND IN JSON", "x".repeat(1000));
+        let fitted = fit_prompt(&prompt, 200);
+        assert!(fitted.chars().count() <= 200);
+        assert!(fitted.starts_with("INSTRUCTIONS"));
+        assert!(fitted.ends_with("RESPOND IN JSON"));
+    }
+
     #[test]
     fn test_default_config() {
         let config = CerebrasConfig::default();
@@ -1100,26 +2541,20 @@ mod tests {
 
     #[test]
     fn test_default_analysis_patterns() {
-        let config = CerebrasConfig {
-            enabled: false, // Force fallback analysis
-            ..CerebrasConfig::default()
-        };
-        let analyzer = SmartExclusionAnalyzer::new(config);
-
         // Test Python cache file
-        let analysis = analyzer.heuristic_analysis(Path::new("__pycache__/module.pyc"));
+        let analysis = heuristic_analysis(Path::new("__pycache__/module.pyc"));
         assert!(analysis.should_exclude_general);
         assert!(analysis.should_exclude_lint);
         assert!(analysis.should_exclude_test);
 
         // Test regular Python file
-        let analysis = analyzer.heuristic_analysis(Path::new("src/main.py"));
+        let analysis = heuristic_analysis(Path::new("src/main.py"));
         assert!(!analysis.should_exclude_general);
         assert!(!analysis.should_exclude_lint);
         assert!(!analysis.should_exclude_test);
 
         // Test test file
-        let analysis = analyzer.heuristic_analysis(Path::new("test_module.py"));
+        let analysis = heuristic_analysis(Path::new("test_module.py"));
         assert!(!analysis.should_exclude_general);
         assert!(analysis.should_exclude_lint);
         assert!(analysis.should_exclude_test);
@@ -1156,6 +2591,508 @@ mod tests {
         assert!(analysis.purpose.contains("analyzed without AI"));
     }
 
+    #[tokio::test]
+    async fn test_basic_test_analysis_lists_failed_tests() {
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig {
+            enabled: false,
+            ..CerebrasConfig::default()
+        });
+        let output = "FAILED tests/test_calc.py::test_add[1-2] - assert 4 == 3\nFAILED tests/test_calc.py::test_div - ZeroDivisionError: division by zero\n==== 2 failed in 0.02s ====\n";
+
+        let analysis = analyzer
+            .analyze_test_output(output, Path::new("."), None)
+            .await
+            .unwrap();
+        assert!(analysis.has_failures);
+        assert_eq!(analysis.summary, "2 test(s) failed");
+        assert_eq!(analysis.failed_tests.len(), 2);
+        assert_eq!(
+            analysis.failed_tests[0].test_name,
+            "tests/test_calc.py::test_add[1-2]"
+        );
+        assert_eq!(analysis.failed_tests[0].error_type, "AssertionError");
+        assert_eq!(analysis.failed_tests[1].error_type, "ZeroDivisionError");
+        assert_eq!(
+            analysis.failed_tests[1].error_message,
+            "ZeroDivisionError: division by zero"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_falls_back_to_basic_analysis() {
+        // Accepts connections but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig {
+            enabled: true,
+            provider: AiProvider::LlamaCpp,
+            base_url: format!("http://{}", listener.local_addr().unwrap()),
+            request_timeout: Duration::from_secs(1),
+            ..CerebrasConfig::default()
+        });
+
+        let analysis = analyzer
+            .analyze_lint_output("app.py:1:1: F401 unused import", None)
+            .await
+            .unwrap();
+        assert!(analysis.has_real_issues);
+        assert!(analysis.reasoning.contains("timed out after 1s"));
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join("app.py");
+        std::fs::write(&file, "def run(): return 1\n").unwrap();
+        let analysis = analyzer.analyze_file(&file).await.unwrap();
+        assert!(analysis.reasoning.contains("basic analysis was used"));
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_skips_requests_after_failures() {
+        // Nothing listens on a port freed right after binding
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let workspace = tempfile::tempdir().unwrap();
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig {
+            enabled: true,
+            provider: AiProvider::LlamaCpp,
+            base_url: format!("http://{address}"),
+            circuit_breaker: CircuitBreakerConfig {
+                failures: 1,
+                cooldown_seconds: 60,
+            },
+            audit_log: true,
+            ..CerebrasConfig::default()
+        })
+        .with_workspace(workspace.path());
+
+        let analysis = analyzer.analyze_lint_output("E501", None).await.unwrap();
+        assert!(!analysis.reasoning.contains("paused"));
+        let analysis = analyzer.analyze_lint_output("E501", None).await.unwrap();
+        assert!(analysis
+            .reasoning
+            .contains("paused after repeated provider failures"));
+
+        // Only the request that was actually made is audited
+        let records = AuditLog::for_workspace(workspace.path())
+            .unwrap()
+            .recent(10);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].analysis, "lint");
+        assert!(records[0].decision.starts_with("error:"));
+    }
+
+    #[tokio::test]
+    async fn test_never_send_files_use_heuristic_analysis() {
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig {
+            enabled: true,
+            provider: AiProvider::LlamaCpp,
+            base_url: "http://127.0.0.1:9".to_string(),
+            never_send: vec!["**/secrets/**".to_string()],
+            ..CerebrasConfig::default()
+        });
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file = temp_dir.path().join("secrets/keys.py");
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, "KEY = 'abc'\n").unwrap();
+
+        let analysis = analyzer.analyze_file(&file).await.unwrap();
+        assert!(analysis.purpose.contains("analyzed without AI"));
+        let analysis = analyzer
+            .analyze_lint_output("keys.py:1:1: F401", Some(&file))
+            .await
+            .unwrap();
+        assert!(analysis.reasoning.starts_with("Basic analysis"));
+    }
+
+    /// Serve one OpenAI-style chat completion per connection with the given message
+    /// contents, in order; returns the base URL
+    fn serve_completions(contents: Vec<serde_json::Value>) -> String {
+        use std::io::{BufRead, BufReader, Read};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for content in contents {
+                let Ok((mut stream, _)) = listener.accept() else {
+                    return;
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+                let mut body = vec![0; content_length];
+                let _ = reader.read_exact(&mut body);
+
+                let reply = serde_json::json!({
+                    "choices": [{"message": {"content": content.to_string()}}]
+                })
+                .to_string();
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    reply.len(),
+                    reply
+                );
+            }
+        });
+        format!("http://{address}")
+    }
+
+    #[tokio::test]
+    async fn test_low_confidence_falls_back_to_basic_analysis() {
+        let lint_reply = |confidence: f32| {
+            serde_json::json!({
+                "has_real_issues": false,
+                "filtered_output": "",
+                "reasoning": "All findings are style nits",
+                "issue_count": 0,
+                "recommendations": "",
+                "confidence": confidence
+            })
+        };
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig {
+            enabled: true,
+            provider: AiProvider::LlamaCpp,
+            base_url: serve_completions(vec![lint_reply(0.9), lint_reply(0.3)]),
+            min_confidence: Some(0.6),
+            ..CerebrasConfig::default()
+        });
+        let output = "app.py:1:1: F401 `os` imported but unused";
+
+        let analysis = analyzer.analyze_lint_output(output, None).await.unwrap();
+        assert!(!analysis.has_real_issues);
+        assert_eq!(analysis.confidence, 0.9);
+
+        let analysis = analyzer.analyze_lint_output(output, None).await.unwrap();
+        assert!(analysis.has_real_issues);
+        assert_eq!(analysis.filtered_output, output);
+        assert!(analysis
+            .reasoning
+            .contains("AI confidence 0.30 is below ai.min_confidence 0.60"));
+    }
+
+    #[tokio::test]
+    async fn test_second_opinion_must_confirm_false_positives() {
+        let lint_reply = serde_json::json!({
+            "has_real_issues": false,
+            "filtered_output": "",
+            "reasoning": "All findings are style nits",
+            "issue_count": 0,
+            "recommendations": "",
+            "confidence": 0.9
+        });
+        let verdict = |agrees: bool| serde_json::json!({"agrees": agrees, "reasoning": "checked"});
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig {
+            enabled: true,
+            provider: AiProvider::LlamaCpp,
+            base_url: serve_completions(vec![
+                lint_reply.clone(),
+                verdict(true),
+                lint_reply,
+                verdict(false),
+            ]),
+            second_opinion: SecondOpinionConfig {
+                enabled: true,
+                model: Some("reviewer".to_string()),
+            },
+            ..CerebrasConfig::default()
+        });
+        let output = "app.py:1:1: F401 `os` imported but unused";
+
+        let analysis = analyzer.analyze_lint_output(output, None).await.unwrap();
+        assert!(!analysis.has_real_issues);
+
+        let analysis = analyzer.analyze_lint_output(output, None).await.unwrap();
+        assert!(analysis.has_real_issues);
+        assert_eq!(analysis.filtered_output, output);
+        assert!(analysis
+            .reasoning
+            .starts_with("⚠️ A second AI opinion didn't confirm"));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_files_keeps_input_order() {
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig {
+            enabled: false,
+            ..CerebrasConfig::default()
+        });
+        let temp_dir = tempfile::tempdir().unwrap();
+        let module = temp_dir.path().join("service.py");
+        let test = temp_dir.path().join("test_service.py");
+        std::fs::write(&module, "def run(): return 1\n").unwrap();
+        std::fs::write(&test, "def test_run(): assert True\n").unwrap();
+
+        let analyses = analyzer
+            .analyze_files(&[module.clone(), test.clone()])
+            .await
+            .unwrap();
+        assert_eq!(analyses.len(), 2);
+        assert!(!analyses[0].should_exclude_test);
+        assert!(analyses[1].should_exclude_test);
+    }
+
+    #[test]
+    fn test_batch_analysis_prompt_and_reply() {
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig::default());
+        let prompt = analyzer.create_batch_analysis_prompt(&[
+            (PathBuf::from("/src/a.py"), "import os".to_string()),
+            (PathBuf::from("/src/b.py"), "import sys".to_string()),
+        ]);
+        assert!(prompt.contains("The following 2 files were changed together"));
+        assert!(prompt.contains("## File: /src/a.py\nFile content:\n```py\nimport os"));
+        assert!(prompt.contains("## File: /src/b.py"));
+        assert!(prompt.contains("CONTEXT 3: TEST EXCLUSION"));
+
+        let reply = r#"```json
+{"analyses": [{"file_path": "/src/b.py", "should_exclude_general": false,
+  "should_exclude_lint": false, "should_exclude_test": true, "reasoning": "constants",
+  "file_type": "config", "purpose": "settings", "exclusion_recommendation": "none"}]}
+```"#;
+        let by_path = parse_batch_analysis(reply).unwrap();
+        assert_eq!(by_path.len(), 1);
+        assert!(by_path["/src/b.py"].should_exclude_test);
+        assert!(parse_batch_analysis("not json").is_err());
+    }
+
+    #[test]
+    fn test_hypothesis_prompt_context() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = temp_dir.path().join("codec.py");
+        std::fs::write(&source, "def encode(s): return s").unwrap();
+        std::fs::write(
+            temp_dir.path().join("test_codec.py"),
+            "from hypothesis import given, strategies as st\n",
+        )
+        .unwrap();
+
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig::default());
+        let prompt =
+            analyzer.create_comprehensive_test_prompt("1 passed", temp_dir.path(), Some(&source));
+        assert!(prompt.contains("Property coverage"));
+        assert!(prompt.contains("Shrinking output"));
+
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig {
+            property_test_analysis: false,
+            ..CerebrasConfig::default()
+        });
+        let prompt =
+            analyzer.create_comprehensive_test_prompt("1 passed", temp_dir.path(), Some(&source));
+        assert!(!prompt.contains("Property coverage"));
+        assert!(prompt.contains("Hypothesis"));
+
+        // Hypothesis seen in the run output isn't forgotten when the test file doesn't import it
+        std::fs::write(
+            temp_dir.path().join("test_codec.py"),
+            "from codec_strategies import round_trip\n",
+        )
+        .unwrap();
+        let prompt = analyzer.create_comprehensive_test_prompt(
+            "Falsifying example: test_round_trip(s='')\nfrom hypothesis import given",
+            temp_dir.path(),
+            Some(&source),
+        );
+        assert!(prompt.contains("Hypothesis"));
+
+        assert!(!imports_hypothesis("import pytest\n"));
+    }
+
+    #[test]
+    fn test_python_version_in_prompts() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig::default());
+
+        let prompt = analyzer.create_lint_output_prompt("E501", Some(temp_dir.path()));
+        assert!(!prompt.contains("Target Python version"));
+
+        std::fs::write(
+            temp_dir.path().join("pyproject.toml"),
+            "[project]\nrequires-python = \">=3.8\"\n",
+        )
+        .unwrap();
+        let prompt = analyzer.create_lint_output_prompt("E501", Some(temp_dir.path()));
+        assert!(prompt.contains("Target Python version: 3.8"));
+        let prompt = analyzer.create_comprehensive_test_prompt("1 passed", temp_dir.path(), None);
+        assert!(prompt.contains("Target Python version: 3.8"));
+        assert!(!prompt.contains("Framework:"));
+
+        std::fs::write(temp_dir.path().join("manage.py"), "import django\n").unwrap();
+        let prompt = analyzer.create_comprehensive_test_prompt("1 passed", temp_dir.path(), None);
+        assert!(prompt.contains("Framework: Django"));
+        assert!(prompt.contains("django.test.TestCase"));
+    }
+
+    #[test]
+    fn test_test_prompt_includes_imports_and_fixtures() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("app")).unwrap();
+        std::fs::create_dir_all(root.join("tests/api")).unwrap();
+        let source = root.join("app/orders.py");
+        std::fs::write(
+            &source,
+            "from decimal import Decimal\nfrom app.db import (\n    Session,\n)\n\ndef total(): pass\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("conftest.py"), "import pytest\n").unwrap();
+        std::fs::write(
+            root.join("tests/conftest.py"),
+            "@pytest.fixture\ndef db_session(): ...\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("tests/test_orders.py"),
+            "def test_total(db_session): ...\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("tests/api/conftest.py"),
+            "@pytest.fixture\ndef client(): ...\n",
+        )
+        .unwrap();
+
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig::default());
+        let prompt = analyzer.create_comprehensive_test_prompt("1 failed", root, Some(&source));
+        assert!(prompt.contains(
+            "Source module imports:\n```python\nfrom decimal import Decimal\nfrom app.db import (\n    Session,\n)\n```"
+        ));
+        let root_conftest = prompt.find("### conftest.py").unwrap();
+        let tests_conftest = prompt.find("### tests/conftest.py").unwrap();
+        assert!(root_conftest < tests_conftest);
+        assert!(prompt.contains("def db_session()"));
+        // Fixtures of unrelated test directories aren't in scope
+        assert!(!prompt.contains("def client()"));
+        assert!(prompt.contains("don't invent ones that don't exist"));
+    }
+
+    #[test]
+    fn test_over_budget_test_prompt_is_condensed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let source = root.join("orders.py");
+        let body = "    x = 1\n".repeat(400);
+        std::fs::write(&source, format!("def total(order):\n{body}    return x\n")).unwrap();
+        std::fs::write(
+            root.join("test_orders.py"),
+            format!("def test_total():\n{body}\ndef test_refund():\n    assert refund() == 2\n"),
+        )
+        .unwrap();
+        let output = format!(
+            "{}test_orders.py::test_refund FAILED [100%]\nFAILED test_orders.py::test_refund - assert 1 == 2\n",
+            "test_orders.py::test_total PASSED [ 50%]\n".repeat(200)
+        );
+
+        let unlimited = SmartExclusionAnalyzer::new(CerebrasConfig::default());
+        let prompt = unlimited.create_comprehensive_test_prompt(&output, root, Some(&source));
+        assert!(prompt.contains("    x = 1\n    x = 1"));
+
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig {
+            max_prompt_tokens: PromptTokenBudgets {
+                test: Some(2_000),
+                ..PromptTokenBudgets::default()
+            },
+            ..CerebrasConfig::default()
+        });
+        let prompt = analyzer.create_comprehensive_test_prompt(&output, root, Some(&source));
+        assert!(estimate_tokens(&prompt) <= 2_000);
+        assert!(prompt.contains("def total(order):\n    ...\n```"));
+        assert!(prompt
+            .contains("def test_total():\n    ...\ndef test_refund():\n    assert refund() == 2"));
+        assert!(prompt.contains("[... 200 passing test lines omitted ...]"));
+        assert!(prompt.contains("FAILED test_orders.py::test_refund - assert 1 == 2"));
+    }
+
+    #[test]
+    fn test_exclusion_and_lint_prompts_show_the_diff() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(temp_dir.path())
+                .output()
+                .unwrap()
+        };
+        git(&["init", "-q"]);
+        let file = temp_dir.path().join("service.py");
+        let lines: Vec<String> = (0..100).map(|i| format!("VALUE_{i} = {i}")).collect();
+        std::fs::write(&file, lines.join("\n")).unwrap();
+        git(&["add", "."]);
+        git(&["commit", "-qm", "initial"]);
+        let mut edited = lines.clone();
+        edited[80] = "VALUE_80 = compute()".to_string();
+        let content = edited.join("\n");
+        std::fs::write(&file, &content).unwrap();
+
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig::default());
+        let prompt = analyzer.create_analysis_prompt(&file, &content);
+        assert!(prompt.contains("File header (first 15 of 100 lines)"));
+        assert!(prompt.contains("-VALUE_80 = 80\n+VALUE_80 = compute()"));
+        assert!(!prompt.contains("VALUE_50 = 50"));
+        let prompt = analyzer.create_lint_output_prompt("service.py:81:12: F821", Some(&file));
+        assert!(prompt.contains("+VALUE_80 = compute()"));
+
+        let whole_file = SmartExclusionAnalyzer::new(CerebrasConfig {
+            diff_analysis: false,
+            ..CerebrasConfig::default()
+        });
+        let prompt = whole_file.create_analysis_prompt(&file, &content);
+        assert!(prompt.contains("VALUE_50 = 50"));
+        assert!(!prompt.contains("Changes since the last commit"));
+    }
+
+    #[test]
+    fn test_exclusion_prompt_describes_project_root() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig::default());
+        std::fs::write(
+            temp_dir.path().join("pyproject.toml"),
+            "[project]\ndependencies = [\"requests\"]\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("src/app")).unwrap();
+        std::fs::create_dir_all(temp_dir.path().join("tests")).unwrap();
+        std::fs::write(temp_dir.path().join("tests/test_client.py"), "").unwrap();
+        let file = temp_dir.path().join("src/app/client.py");
+        std::fs::write(&file, "import requests\n").unwrap();
+
+        // The markers live at the project root, not next to the file
+        let prompt = analyzer.create_analysis_prompt(&file, "import requests\n");
+        assert!(prompt.contains("Root markers: pyproject.toml"));
+        assert!(prompt.contains("Dependencies: requests"));
+        assert!(prompt.contains("Test layout: tests (1 test file)"));
+    }
+
+    #[test]
+    fn test_web_framework_prompt_context() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig::default());
+        let source = temp_dir.path().join("main.py");
+        std::fs::write(&source, "from fastapi import FastAPI\n\napp = FastAPI()\n").unwrap();
+
+        // Imports identify the framework when no dependency file declares it
+        let prompt =
+            analyzer.create_comprehensive_test_prompt("1 passed", temp_dir.path(), Some(&source));
+        assert!(prompt.contains("Framework: FastAPI"));
+        assert!(prompt.contains("TestClient(app)"));
+        assert!(prompt.contains("## Endpoint Coverage"));
+
+        let prompt = analyzer.create_lint_output_prompt("E501", Some(&source));
+        assert!(prompt.contains("Framework: FastAPI"));
+        assert!(!prompt.contains("## Endpoint Coverage"));
+
+        std::fs::write(temp_dir.path().join("requirements.txt"), "Flask==3.0\n").unwrap();
+        let prompt =
+            analyzer.create_comprehensive_test_prompt("1 passed", temp_dir.path(), Some(&source));
+        assert!(prompt.contains("Framework: Flask"));
+    }
+
     #[test]
     fn test_conservative_analysis() {
         let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig::default());
@@ -1200,11 +3137,9 @@ mod tests {
 
     #[test]
     fn test_heuristic_analysis_python_files() {
-        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig::default());
-
         // Test regular Python file
         let regular_py = Path::new("src/models.py");
-        let analysis = analyzer.heuristic_analysis(regular_py);
+        let analysis = heuristic_analysis(regular_py);
         assert!(!analysis.should_exclude_general);
         assert!(!analysis.should_exclude_lint);
         assert!(!analysis.should_exclude_test);
@@ -1212,7 +3147,7 @@ mod tests {
 
         // Test test file
         let test_file = Path::new("test_example.py");
-        let analysis = analyzer.heuristic_analysis(test_file);
+        let analysis = heuristic_analysis(test_file);
         assert!(!analysis.should_exclude_general);
         assert!(analysis.should_exclude_lint);
         assert!(analysis.should_exclude_test);
@@ -1220,7 +3155,7 @@ mod tests {
 
         // Test cache file (.pyc extension matches first)
         let cache_file = Path::new("__pycache__/module.pyc");
-        let analysis = analyzer.heuristic_analysis(cache_file);
+        let analysis = heuristic_analysis(cache_file);
         assert!(analysis.should_exclude_general);
         assert!(analysis.should_exclude_lint);
         assert!(analysis.should_exclude_test);
@@ -1228,20 +3163,40 @@ mod tests {
 
         // Test cache directory file (filename contains __pycache__)
         let cache_dir_file = Path::new("module__pycache__temp.py");
-        let analysis = analyzer.heuristic_analysis(cache_dir_file);
+        let analysis = heuristic_analysis(cache_dir_file);
         assert!(analysis.should_exclude_general);
         assert!(analysis.should_exclude_lint);
         assert!(analysis.should_exclude_test);
         assert!(analysis.reasoning.contains("Python cache files"));
+
+        // Type stubs are linted but never need tests
+        let stub = Path::new("stubs/models.pyi");
+        let analysis = heuristic_analysis(stub);
+        assert!(!analysis.should_exclude_general);
+        assert!(!analysis.should_exclude_lint);
+        assert!(analysis.should_exclude_test);
+
+        // Extensionless scripts are recognized by their shebang
+        let temp_dir = tempfile::tempdir().unwrap();
+        let script = temp_dir.path().join("manage");
+        std::fs::write(&script, "#!/usr/bin/env python3\n").unwrap();
+        let analysis = heuristic_analysis(&script);
+        assert!(!analysis.should_exclude_lint);
+        assert!(analysis.reasoning.contains("shebang"));
+
+        // Cython sources are processed like modules
+        let cython = Path::new("src/fast.pyx");
+        let analysis = heuristic_analysis(cython);
+        assert!(!analysis.should_exclude_general);
+        assert!(!analysis.should_exclude_lint);
+        assert!(!analysis.should_exclude_test);
     }
 
     #[test]
     fn test_heuristic_analysis_non_python_files() {
-        let analyzer = SmartExclusionAnalyzer::new(CerebrasConfig::default());
-
         // Test Rust file
         let rust_file = Path::new("src/main.rs");
-        let analysis = analyzer.heuristic_analysis(rust_file);
+        let analysis = heuristic_analysis(rust_file);
         assert!(analysis.should_exclude_general);
         assert!(analysis.should_exclude_lint);
         assert!(analysis.should_exclude_test);
@@ -1249,7 +3204,7 @@ mod tests {
 
         // Test config file
         let config_file = Path::new("config.yaml");
-        let analysis = analyzer.heuristic_analysis(config_file);
+        let analysis = heuristic_analysis(config_file);
         assert!(analysis.should_exclude_general);
         assert!(analysis.should_exclude_lint);
         assert!(analysis.should_exclude_test);
@@ -0,0 +1,1099 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Cyclomatic complexity of a single Python function
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionComplexity {
+    pub name: String,
+    pub line: usize,
+    pub end_line: usize,
+    pub complexity: u32,
+}
+
+impl FunctionComplexity {
+    /// Check if the function body overlaps any of the given (start, end) line ranges
+    pub fn overlaps(&self, ranges: &[(usize, usize)]) -> bool {
+        ranges
+            .iter()
+            .any(|&(start, end)| start <= self.end_line && end >= self.line)
+    }
+}
+
+/// Estimate cyclomatic complexity per function with a lightweight line-based walk
+///
+/// Counts one for the function plus one per branch keyword (`if`, `elif`, `for`,
+/// `while`, `except`, `case`), per boolean operator and per inline conditional or
+/// comprehension clause. Nested functions are reported on their own and also count
+/// towards their enclosing function. This is a token-level estimate, not an AST walk:
+/// comments and string literals (docstrings included) are ignored, but statements
+/// split across lines are counted line by line.
+pub fn analyze_complexity(source: &str) -> Vec<FunctionComplexity> {
+    let code = code_lines(source);
+    let lines: Vec<&str> = code.iter().map(String::as_str).collect();
+    let mut functions = Vec::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let stripped = strip_comment(line);
+        let trimmed = stripped.trim_start();
+        let Some(name) = function_name(trimmed) else {
+            continue;
+        };
+        let end = block_end(&lines, index);
+        let complexity = 1 + lines[index + 1..=end]
+            .iter()
+            .map(|line| decision_points(line.trim()))
+            .sum::<u32>();
+
+        functions.push(FunctionComplexity {
+            name: name.to_string(),
+            line: index + 1,
+            end_line: end + 1,
+            complexity,
+        });
+    }
+
+    functions
+}
+
+/// A public function or class without a docstring
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingDocstring {
+    pub kind: &'static str,
+    pub name: String,
+    pub line: usize,
+}
+
+/// Find public functions, methods and classes that lack a docstring
+///
+/// Follows interrogate's defaults for what counts as public API: names starting
+/// with `_` (private and dunder) are ignored, as are functions nested inside other
+/// functions.
+pub fn missing_docstrings(source: &str) -> Vec<MissingDocstring> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut missing = Vec::new();
+    // Enclosing definitions as (indent, is_function)
+    let mut scopes: Vec<(usize, bool)> = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let stripped = strip_comment(lines[index]);
+        let trimmed = stripped.trim_start();
+        if trimmed.is_empty() {
+            index += 1;
+            continue;
+        }
+
+        let indent = indentation(stripped);
+        while scopes
+            .last()
+            .is_some_and(|&(scope_indent, _)| scope_indent >= indent)
+        {
+            scopes.pop();
+        }
+
+        let definition = match function_name(trimmed) {
+            Some(name) => Some(("function", name)),
+            None => class_name(trimmed).map(|name| ("class", name)),
+        };
+        let Some((kind, name)) = definition else {
+            index += 1;
+            continue;
+        };
+
+        let nested_in_function = scopes.iter().any(|&(_, is_function)| is_function);
+        let line = index + 1;
+
+        // Skip to the end of a possibly multi-line signature: the first `:` outside
+        // brackets, which may be followed by a one-line body (`def f(): ...`)
+        let mut depth: i32 = 0;
+        let mut inline_body = "";
+        'signature: while index < lines.len() {
+            let code = strip_comment(lines[index]);
+            for (offset, c) in code.char_indices() {
+                match c {
+                    '(' | '[' | '{' => depth += 1,
+                    ')' | ']' | '}' => depth -= 1,
+                    ':' if depth <= 0 => {
+                        inline_body = code[offset + 1..].trim();
+                        break 'signature;
+                    }
+                    _ => {}
+                }
+            }
+            index += 1;
+        }
+
+        let is_string = |code: &str| {
+            let code = code.trim_start_matches(['r', 'R', 'u', 'U', 'b', 'B']);
+            code.starts_with('"') || code.starts_with('\'')
+        };
+        let has_docstring = if inline_body.is_empty() {
+            lines
+                .iter()
+                .skip(index + 1)
+                .map(|line| line.trim())
+                .find(|line| !line.is_empty() && !line.starts_with('#'))
+                .is_some_and(is_string)
+        } else {
+            is_string(inline_body)
+        };
+
+        if !has_docstring && !nested_in_function && !name.starts_with('_') {
+            missing.push(MissingDocstring {
+                kind,
+                name: name.to_string(),
+                line,
+            });
+        }
+
+        scopes.push((indent, kind == "function"));
+        index += 1;
+    }
+
+    missing
+}
+
+/// A single `import` / `from ... import` statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportStatement {
+    /// Dotted module path (empty for `from . import x`)
+    pub module: String,
+    /// Names imported with `from ... import`, empty for plain `import`
+    pub names: Vec<String>,
+    /// Number of leading dots for relative imports
+    pub level: usize,
+    pub line: usize,
+}
+
+/// An import hygiene problem in the edited file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportIssue {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Parse the import statements of a Python module, including parenthesized
+/// multi-line `from` imports
+pub fn parse_imports(source: &str) -> Vec<ImportStatement> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut imports = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = index + 1;
+        let trimmed = strip_comment(lines[index]).trim();
+        index += 1;
+
+        if let Some(rest) = trimmed.strip_prefix("import ") {
+            for part in rest.split(',') {
+                let module = part.split(" as ").next().unwrap_or("").trim();
+                if !module.is_empty() {
+                    imports.push(ImportStatement {
+                        module: module.to_string(),
+                        names: vec![],
+                        level: 0,
+                        line,
+                    });
+                }
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("from ") {
+            let Some((module_part, names_part)) = rest.split_once(" import ") else {
+                continue;
+            };
+            let mut names_text = names_part.trim().to_string();
+            if names_text.starts_with('(') && !names_text.contains(')') {
+                while index < lines.len() {
+                    let next = strip_comment(lines[index]).trim();
+                    index += 1;
+                    names_text.push(' ');
+                    names_text.push_str(next);
+                    if next.contains(')') {
+                        break;
+                    }
+                }
+            }
+
+            let module_part = module_part.trim();
+            let level = module_part.len() - module_part.trim_start_matches('.').len();
+            let names = names_text
+                .trim_matches(|c| c == '(' || c == ')')
+                .split(',')
+                .map(|name| name.split(" as ").next().unwrap_or("").trim().to_string())
+                .filter(|name| !name.is_empty())
+                .collect();
+
+            imports.push(ImportStatement {
+                module: module_part.trim_start_matches('.').to_string(),
+                names,
+                level,
+                line,
+            });
+        }
+    }
+
+    imports
+}
+
+/// Resolve an import to a module file inside the project, if it is one
+pub fn resolve_import(
+    module: &str,
+    level: usize,
+    importing_file: &Path,
+    project_root: &Path,
+) -> Option<PathBuf> {
+    let mut base = if level == 0 {
+        project_root.to_path_buf()
+    } else {
+        let mut dir = importing_file.parent()?.to_path_buf();
+        for _ in 1..level {
+            dir = dir.parent()?.to_path_buf();
+        }
+        dir
+    };
+
+    for part in module.split('.').filter(|part| !part.is_empty()) {
+        base.push(part);
+    }
+
+    let module_file = base.with_extension("py");
+    if module_file.is_file() {
+        return Some(module_file);
+    }
+    let package_init = base.join("__init__.py");
+    package_init.is_file().then_some(package_init)
+}
+
+/// Project-local module files an import statement refers to
+fn resolved_targets(import: &ImportStatement, file: &Path, project_root: &Path) -> Vec<PathBuf> {
+    let mut targets = Vec::new();
+    // `from pkg import name` may import the submodule pkg.name
+    for name in &import.names {
+        let submodule = if import.module.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", import.module, name)
+        };
+        if let Some(target) = resolve_import(&submodule, import.level, file, project_root) {
+            targets.push(target);
+        }
+    }
+    if let Some(target) = resolve_import(&import.module, import.level, file, project_root) {
+        targets.push(target);
+    }
+    targets
+}
+
+/// Flag wildcard imports, imports of banned modules, and project modules that
+/// import the edited file back (circular import risk)
+pub fn import_issues(
+    file_path: &Path,
+    source: &str,
+    project_root: &Path,
+    banned_modules: &[String],
+) -> Vec<ImportIssue> {
+    let mut issues = Vec::new();
+    let canonical_file = file_path.canonicalize().ok();
+
+    for import in parse_imports(source) {
+        if import.names.iter().any(|name| name == "*") {
+            issues.push(ImportIssue {
+                line: import.line,
+                message: format!(
+                    "wildcard import from '{}{}'",
+                    ".".repeat(import.level),
+                    import.module
+                ),
+            });
+        }
+
+        if import.level == 0 {
+            if let Some(banned) = banned_modules.iter().find(|banned| {
+                import.module == **banned || import.module.starts_with(&format!("{banned}."))
+            }) {
+                issues.push(ImportIssue {
+                    line: import.line,
+                    message: format!("import of banned module '{banned}'"),
+                });
+            }
+        }
+
+        let Some(canonical_file) = &canonical_file else {
+            continue;
+        };
+        for target in resolved_targets(&import, file_path, project_root) {
+            let Ok(target_source) = std::fs::read_to_string(&target) else {
+                continue;
+            };
+            let imports_back = parse_imports(&target_source).iter().any(|back| {
+                resolved_targets(back, &target, project_root)
+                    .iter()
+                    .any(|path| path.canonicalize().ok().as_ref() == Some(canonical_file))
+            });
+            if imports_back {
+                let display = target.strip_prefix(project_root).unwrap_or(&target);
+                issues.push(ImportIssue {
+                    line: import.line,
+                    message: format!(
+                        "circular import risk: {} imports this module back",
+                        display.display()
+                    ),
+                });
+            }
+        }
+    }
+
+    issues
+}
+
+/// Check if a source file starts with the license header `template`
+///
+/// A leading shebang and/or encoding line may precede the header, and `{year}` in
+/// the template matches any four-digit year.
+pub fn has_license_header(source: &str, template: &str) -> bool {
+    let body = skip_preamble(source);
+    let header = template.trim_end();
+
+    let mut remaining = body;
+    let mut parts = header.split("{year}").peekable();
+    while let Some(part) = parts.next() {
+        let Some(rest) = remaining.strip_prefix(part) else {
+            return false;
+        };
+        remaining = rest;
+        if parts.peek().is_some() {
+            let year_len = remaining.chars().take_while(|c| c.is_ascii_digit()).count();
+            if year_len != 4 {
+                return false;
+            }
+            remaining = &remaining[year_len..];
+        }
+    }
+    true
+}
+
+/// Insert the license header (with `{year}` filled in) after any shebang/encoding lines
+pub fn insert_license_header(source: &str, template: &str, year: i32) -> String {
+    let body = skip_preamble(source);
+    let preamble = &source[..source.len() - body.len()];
+    let separator = if preamble.is_empty() || preamble.ends_with('\n') {
+        ""
+    } else {
+        "\n"
+    };
+    let header = template.trim_end().replace("{year}", &year.to_string());
+    format!("{preamble}{separator}{header}\n{body}")
+}
+
+/// The source after a leading shebang and PEP 263 encoding declaration
+fn skip_preamble(source: &str) -> &str {
+    let mut rest = source;
+    for _ in 0..2 {
+        let first_line = rest.lines().next().unwrap_or("");
+        if first_line.starts_with("#!")
+            || (first_line.starts_with('#') && first_line.contains("coding"))
+        {
+            rest = rest.split_once('\n').map_or("", |(_, after)| after);
+        }
+    }
+    rest
+}
+
+/// Parse `radon cc -j` output into per-function complexity
+pub fn parse_radon_output(output: &str) -> Option<Vec<FunctionComplexity>> {
+    let json: serde_json::Value = serde_json::from_str(output).ok()?;
+    let mut functions: Vec<FunctionComplexity> = Vec::new();
+
+    for blocks in json.as_object()?.values() {
+        let mut pending: Vec<&serde_json::Value> = blocks.as_array()?.iter().collect();
+        while let Some(block) = pending.pop() {
+            if let Some(methods) = block.get("methods").and_then(|m| m.as_array()) {
+                pending.extend(methods);
+            }
+            let block_type = block.get("type").and_then(|t| t.as_str()).unwrap_or("");
+            if block_type != "function" && block_type != "method" {
+                continue;
+            }
+
+            let function = FunctionComplexity {
+                name: block.get("name")?.as_str()?.to_string(),
+                line: block.get("lineno")?.as_u64()? as usize,
+                end_line: block.get("endline")?.as_u64()? as usize,
+                complexity: block.get("complexity")?.as_u64()? as u32,
+            };
+            if !functions.contains(&function) {
+                functions.push(function);
+            }
+        }
+    }
+
+    functions.sort_by_key(|function| function.line);
+    Some(functions)
+}
+
+/// Line ranges of `file_path` changed relative to HEAD, from `git diff -U0`
+///
+/// Returns `None` when git can't tell (not a repository, git missing) and a single
+/// whole-file range for files git doesn't track yet.
+pub fn changed_line_ranges(file_path: &Path, project_root: &Path) -> Option<Vec<(usize, usize)>> {
+    let tracked = Command::new("git")
+        .args(["ls-files", "--error-unmatch"])
+        .arg(file_path)
+        .current_dir(project_root)
+        .output()
+        .ok()?;
+    if !tracked.status.success() {
+        let inside_repo = Command::new("git")
+            .args(["rev-parse", "--is-inside-work-tree"])
+            .current_dir(project_root)
+            .output()
+            .ok()?;
+        return inside_repo.status.success().then(|| vec![(1, usize::MAX)]);
+    }
+
+    let diff = Command::new("git")
+        .args(["diff", "-U0", "HEAD", "--"])
+        .arg(file_path)
+        .current_dir(project_root)
+        .output()
+        .ok()?;
+    if !diff.status.success() {
+        return None;
+    }
+
+    Some(parse_diff_hunks(&String::from_utf8_lossy(&diff.stdout)))
+}
+
+/// Unified diff of `file_path` against HEAD with `context_lines` of context
+///
+/// Returns `None` when git can't tell or the file has no committed changes (including
+/// untracked files).
+pub fn file_diff(file_path: &Path, context_lines: usize) -> Option<String> {
+    let dir = file_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let diff = Command::new("git")
+        .arg("diff")
+        .arg(format!("-U{context_lines}"))
+        .args(["HEAD", "--"])
+        .arg(file_path.file_name()?)
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    let diff = String::from_utf8_lossy(&diff.stdout);
+    let hunks = diff.find("\n@@ ")?;
+    Some(diff[hunks + 1..].trim_end().to_string())
+}
+
+/// Extract new-side line ranges from unified diff hunk headers (`@@ -a,b +c,d @@`)
+pub fn parse_diff_hunks(diff: &str) -> Vec<(usize, usize)> {
+    diff.lines()
+        .filter_map(|line| line.strip_prefix("@@ "))
+        .filter_map(|header| header.split_whitespace().find(|part| part.starts_with('+')))
+        .filter_map(|new_side| {
+            let mut parts = new_side[1..].splitn(2, ',');
+            let start: usize = parts.next()?.parse().ok()?;
+            let count: usize = parts.next().map_or(Some(1), |c| c.parse().ok())?;
+            // Pure deletions (count 0) still touch the surrounding line
+            Some((start.max(1), start.max(1) + count.saturating_sub(1)))
+        })
+        .collect()
+}
+
+/// Functions and classes whose lines a change touched
+#[derive(Debug, Default, PartialEq)]
+pub struct ChangedDefinitions {
+    /// Changed functions and classes, with the classes enclosing changed methods
+    pub names: Vec<String>,
+    /// Whether code outside every function and class changed too
+    pub module_level: bool,
+}
+
+/// Functions and classes of `source` overlapping the changed `(start, end)` line ranges
+pub fn changed_definitions(source: &str, ranges: &[(usize, usize)]) -> ChangedDefinitions {
+    let lines: Vec<&str> = source.lines().collect();
+    // (name, first line, last line), 0-based
+    let definitions: Vec<(&str, usize, usize)> = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let trimmed = strip_comment(line).trim_start();
+            let name = function_name(trimmed).or_else(|| class_name(trimmed))?;
+            Some((name, index, block_end(&lines, index)))
+        })
+        .collect();
+
+    let mut changed = ChangedDefinitions::default();
+    for &(start, end) in ranges {
+        let changed_lines = lines
+            .iter()
+            .enumerate()
+            .take(end)
+            .skip(start.saturating_sub(1));
+        for (index, line) in changed_lines {
+            let mut enclosing = definitions
+                .iter()
+                .filter(|(_, first, last)| (*first..=*last).contains(&index))
+                .peekable();
+            if enclosing.peek().is_none() {
+                changed.module_level |= !strip_comment(line).trim().is_empty();
+            }
+            for (name, _, _) in enclosing {
+                if !changed.names.iter().any(|known| known == name) {
+                    changed.names.push(name.to_string());
+                }
+            }
+        }
+    }
+    changed
+}
+
+/// Test functions in `test_source` that mention any of `names`, or `None` when some
+/// name isn't mentioned by any test and matching would be a guess
+pub fn tests_referencing(test_source: &str, names: &[String]) -> Option<Vec<String>> {
+    let lines: Vec<&str> = test_source.lines().collect();
+    let mut tests = Vec::new();
+    let mut referenced = vec![false; names.len()];
+    for (index, line) in lines.iter().enumerate() {
+        let Some(test) =
+            function_name(strip_comment(line).trim_start()).filter(|name| name.starts_with("test"))
+        else {
+            continue;
+        };
+        let body = lines[index + 1..=block_end(&lines, index)].join("\n");
+        let mut mentions = false;
+        for (name, seen) in names.iter().zip(referenced.iter_mut()) {
+            if mentions_word(&body, name) {
+                *seen = true;
+                mentions = true;
+            }
+        }
+        if mentions && !tests.iter().any(|known| known == test) {
+            tests.push(test.to_string());
+        }
+    }
+    (!tests.is_empty() && referenced.iter().all(|seen| *seen)).then_some(tests)
+}
+
+fn mentions_word(text: &str, word: &str) -> bool {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + word.len()..].chars().next();
+        !before.is_some_and(is_identifier) && !after.is_some_and(is_identifier)
+    })
+}
+
+/// Assertion counts and smells found in a test module
+#[derive(Debug, Default, PartialEq)]
+pub struct TestSmells {
+    pub tests: usize,
+    pub assertions: usize,
+    pub findings: Vec<TestSmell>,
+}
+
+/// A test that is likely to miss bugs or be slow/flaky
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestSmell {
+    pub test: String,
+    pub line: usize,
+    pub message: &'static str,
+}
+
+/// Count assertions per test and flag tests without assertions, catch-all
+/// `pytest.raises(Exception)` and sleeps
+pub fn test_smells(test_source: &str) -> TestSmells {
+    let lines: Vec<&str> = test_source.lines().collect();
+    let mut smells = TestSmells::default();
+    for (index, line) in lines.iter().enumerate() {
+        let Some(test) =
+            function_name(strip_comment(line).trim_start()).filter(|name| name.starts_with("test"))
+        else {
+            continue;
+        };
+        let body: Vec<&str> = lines[index + 1..=block_end(&lines, index)]
+            .iter()
+            .map(|line| strip_comment(line).trim())
+            .collect();
+        let smell = |message| TestSmell {
+            test: test.to_string(),
+            line: index + 1,
+            message,
+        };
+
+        let assertions = body.iter().filter(|line| is_assertion(line)).count();
+        smells.tests += 1;
+        smells.assertions += assertions;
+        if assertions == 0 {
+            smells.findings.push(smell("no assertions"));
+        }
+        if body.iter().any(|line| {
+            ["pytest.raises(", "self.assertRaises("].iter().any(|call| {
+                line.split(call).skip(1).any(|args| {
+                    let exception = args.split([')', ',']).next().unwrap_or("").trim();
+                    exception == "Exception" || exception == "BaseException"
+                })
+            })
+        }) {
+            smells.findings.push(smell(
+                "catches any Exception; expect the specific exception type",
+            ));
+        }
+        if body
+            .iter()
+            .any(|line| line.contains("time.sleep(") || line.starts_with("sleep("))
+        {
+            smells.findings.push(smell(
+                "sleeps, which makes the suite slow and flaky; wait on a condition or fake the clock",
+            ));
+        }
+    }
+    smells
+}
+
+fn is_assertion(line: &str) -> bool {
+    line.starts_with("assert ")
+        || line.starts_with("assert(")
+        || line.contains("self.assert")
+        || line.contains(".assert_")
+        || line.starts_with("assert_")
+        || ["pytest.raises(", "pytest.warns(", "pytest.fail("]
+            .iter()
+            .any(|call| line.contains(call))
+}
+
+/// Index of the last line of the block opened at `index`: the body ends at the first
+/// code line indented at or above the opening line
+fn block_end(lines: &[&str], index: usize) -> usize {
+    let indent = indentation(strip_comment(lines[index]));
+    let mut end = index;
+    for (offset, body_line) in lines.iter().enumerate().skip(index + 1) {
+        let body = strip_comment(body_line);
+        if body.trim().is_empty() {
+            continue;
+        }
+        if indentation(body) <= indent {
+            break;
+        }
+        end = offset;
+    }
+    end
+}
+
+fn function_name(trimmed: &str) -> Option<&str> {
+    let rest = trimmed
+        .strip_prefix("async def ")
+        .or_else(|| trimmed.strip_prefix("def "))?;
+    let name = rest.split('(').next()?.trim();
+    (!name.is_empty()).then_some(name)
+}
+
+fn class_name(trimmed: &str) -> Option<&str> {
+    let rest = trimmed.strip_prefix("class ")?;
+    let name = rest.split(['(', ':']).next()?.trim();
+    (!name.is_empty()).then_some(name)
+}
+
+fn decision_points(line: &str) -> u32 {
+    let branch_keywords = [
+        "if ",
+        "elif ",
+        "for ",
+        "async for ",
+        "while ",
+        "except",
+        "case ",
+    ];
+    let mut points = 0;
+
+    let starts_with_branch = branch_keywords.iter().any(|kw| line.starts_with(kw));
+    if starts_with_branch {
+        points += 1;
+    }
+
+    let padded = format!(" {line} ");
+    points += padded.matches(" and ").count() as u32;
+    points += padded.matches(" or ").count() as u32;
+
+    // Inline conditionals and comprehension clauses
+    let inline_start = if starts_with_branch {
+        line.find(' ').unwrap_or(line.len())
+    } else {
+        0
+    };
+    let inline = format!(" {} ", &line[inline_start..]);
+    points += inline.matches(" if ").count() as u32;
+    if !starts_with_branch || !line.starts_with("for ") {
+        points += inline.matches(" for ").count() as u32;
+    }
+
+    points
+}
+
+/// Source lines with comments removed and every string literal (including multi-line
+/// triple-quoted strings) reduced to `""`, so keywords and `#` inside strings aren't
+/// mistaken for code
+fn code_lines(source: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    // Delimiter of a triple-quoted string still open from an earlier line
+    let mut open: Option<&str> = None;
+
+    for line in source.lines() {
+        let mut code = String::new();
+        let mut rest = line;
+        loop {
+            if let Some(delimiter) = open {
+                match closing_quote(rest, delimiter) {
+                    Some(end) => {
+                        rest = &rest[end..];
+                        open = None;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            let Some(start) = rest.find(['#', '"', '\'']) else {
+                code.push_str(rest);
+                break;
+            };
+            code.push_str(&rest[..start]);
+            let literal = &rest[start..];
+            if literal.starts_with('#') {
+                break;
+            }
+            code.push_str("\"\"");
+            let delimiter = ["\"\"\"", "'''", "\"", "'"]
+                .into_iter()
+                .find(|delimiter| literal.starts_with(delimiter))
+                .unwrap_or_default();
+            let body = &literal[delimiter.len()..];
+            match closing_quote(body, delimiter) {
+                Some(end) => rest = &body[end..],
+                None => {
+                    // Only triple-quoted strings continue onto the next line
+                    if delimiter.len() == 3 {
+                        open = Some(delimiter);
+                    }
+                    break;
+                }
+            }
+        }
+        lines.push(code);
+    }
+
+    lines
+}
+
+/// Byte index just past the first unescaped `delimiter` in `text`
+fn closing_quote(text: &str, delimiter: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if text[index..].starts_with(delimiter) {
+            return Some(index + delimiter.len());
+        }
+    }
+    None
+}
+
+fn strip_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(index) => &line[..index],
+        None => line,
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_complexity() {
+        let source = r#"
+def simple(x):
+    return x + 1
+
+def branchy(items):
+    total = 0
+    for item in items:  # loop
+        if item > 0 and item < 10:
+            total += item
+        elif item < 0:
+            total -= item
+    try:
+        return [i for i in items if i]
+    except ValueError:
+        return total
+
+class Service:
+    async def handle(self, request):
+        return request if request else None
+"#;
+
+        let functions = analyze_complexity(source);
+        assert_eq!(functions.len(), 3);
+
+        assert_eq!(functions[0].name, "simple");
+        assert_eq!(functions[0].complexity, 1);
+
+        // 1 + for + if + and + elif + comprehension for + comprehension if + except
+        assert_eq!(functions[1].name, "branchy");
+        assert_eq!(functions[1].complexity, 8);
+        assert_eq!(functions[1].line, 5);
+        assert_eq!(functions[1].end_line, 15);
+
+        assert_eq!(functions[2].name, "handle");
+        assert_eq!(functions[2].complexity, 2);
+
+        // Keywords and `#` inside strings and docstrings aren't code
+        let source = r#"
+def describe(kind):
+    """Return a label.
+
+if the kind is unknown or missing, for example, say so
+    """
+    label = "if # or and" if kind else 'for x in y'
+    return f"{label} and more" or label
+"#;
+        let functions = analyze_complexity(source);
+        assert_eq!(functions.len(), 1);
+        // 1 + inline if + or
+        assert_eq!(functions[0].complexity, 3);
+        assert_eq!(functions[0].end_line, 8);
+    }
+
+    #[test]
+    fn test_missing_docstrings() {
+        let source = r#"
+class Documented:
+    """A documented class."""
+
+    def public(self):
+        return 1
+
+    def _private(self):
+        return 2
+
+    def __repr__(self):
+        return "x"
+
+def load(
+    path,
+    mode="r",
+):
+    r'''Load a file.'''
+    def helper():
+        return path
+    return helper()
+
+class Bare(Base):
+    pass
+
+async def fetch():
+    # comment before body
+    return None
+"#;
+
+        let missing = missing_docstrings(source);
+        let names: Vec<(&str, &str)> = missing.iter().map(|m| (m.kind, m.name.as_str())).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("function", "public"),
+                ("class", "Bare"),
+                ("function", "fetch")
+            ]
+        );
+        assert_eq!(missing[0].line, 5);
+
+        // One-line definitions end at their own colon
+        let source = "def first(): ...\ndef second(x: int) -> dict[str, int]: return {}\n\ndef third():\n    \"\"\"Documented.\"\"\"\n\nclass Empty: pass\ndef fourth(): \"Inline docstring.\"\n";
+        let missing = missing_docstrings(source);
+        let names: Vec<(&str, usize)> = missing.iter().map(|m| (m.name.as_str(), m.line)).collect();
+        assert_eq!(names, vec![("first", 1), ("second", 2), ("Empty", 7)]);
+    }
+
+    #[test]
+    fn test_parse_imports() {
+        let source = "import os, sys as system\nfrom .models import (\n    User,\n    Group as G,\n)\nfrom pkg.sub import *\n";
+        let imports = parse_imports(source);
+
+        assert_eq!(imports.len(), 4);
+        assert_eq!(imports[0].module, "os");
+        assert_eq!(imports[1].module, "sys");
+        assert_eq!(imports[2].module, "models");
+        assert_eq!(imports[2].level, 1);
+        assert_eq!(imports[2].names, vec!["User", "Group"]);
+        assert_eq!(imports[3].names, vec!["*"]);
+        assert_eq!(imports[3].line, 6);
+    }
+
+    #[test]
+    fn test_import_issues() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir(root.join("app")).unwrap();
+        std::fs::write(root.join("app/__init__.py"), "").unwrap();
+        std::fs::write(root.join("app/models.py"), "from app import services\n").unwrap();
+        std::fs::write(root.join("app/utils.py"), "import json\n").unwrap();
+        let services = root.join("app/services.py");
+        let source =
+            "import pickle\nfrom os.path import *\nfrom .models import User\nfrom . import utils\n";
+        std::fs::write(&services, source).unwrap();
+
+        let issues = import_issues(&services, source, root, &["pickle".to_string()]);
+        let messages: Vec<&str> = issues.iter().map(|i| i.message.as_str()).collect();
+        assert_eq!(issues.len(), 3, "unexpected issues: {messages:?}");
+        assert!(messages[0].contains("banned module 'pickle'"));
+        assert!(messages[1].contains("wildcard import from 'os.path'"));
+        assert!(messages[2].contains("circular import risk: app/models.py"));
+        assert_eq!(issues[2].line, 3);
+    }
+
+    #[test]
+    fn test_license_header() {
+        let template = "# Copyright (c) {year} Example Corp\n# SPDX-License-Identifier: MIT\n";
+
+        let licensed = "#!/usr/bin/env python\n# Copyright (c) 2023 Example Corp\n# SPDX-License-Identifier: MIT\nimport os\n";
+        assert!(has_license_header(licensed, template));
+        assert!(!has_license_header("import os\n", template));
+        assert!(!has_license_header(
+            "# Copyright (c) 23 Example Corp\n# SPDX-License-Identifier: MIT\n",
+            template
+        ));
+
+        let inserted = insert_license_header("#!/usr/bin/env python\nimport os\n", template, 2026);
+        assert_eq!(
+            inserted,
+            "#!/usr/bin/env python\n# Copyright (c) 2026 Example Corp\n# SPDX-License-Identifier: MIT\nimport os\n"
+        );
+        assert!(has_license_header(&inserted, template));
+
+        // A preamble without a trailing newline keeps its own line
+        let inserted = insert_license_header("#!/usr/bin/env python", template, 2026);
+        assert_eq!(
+            inserted,
+            "#!/usr/bin/env python\n# Copyright (c) 2026 Example Corp\n# SPDX-License-Identifier: MIT\n"
+        );
+        let inserted = insert_license_header("# -*- coding: utf-8 -*-", template, 2026);
+        assert!(inserted.starts_with("# -*- coding: utf-8 -*-\n# Copyright (c) 2026"));
+        assert!(has_license_header(&inserted, template));
+    }
+
+    #[test]
+    fn test_radon_output_parsing() {
+        let output = r#"{"app.py": [
+            {"type": "function", "name": "load", "lineno": 3, "endline": 9, "complexity": 4},
+            {"type": "class", "name": "Repo", "lineno": 11, "endline": 30, "complexity": 6,
+             "methods": [{"type": "method", "name": "save", "lineno": 12, "endline": 30, "complexity": 5}]},
+            {"type": "method", "name": "save", "lineno": 12, "endline": 30, "complexity": 5}
+        ]}"#;
+
+        let functions = parse_radon_output(output).unwrap();
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0].name, "load");
+        assert_eq!(functions[1].name, "save");
+        assert_eq!(functions[1].complexity, 5);
+
+        assert!(parse_radon_output("not json").is_none());
+    }
+
+    #[test]
+    fn test_diff_hunk_parsing() {
+        let diff = "diff --git a/app.py b/app.py\n@@ -3,0 +4,2 @@ def load():\n+x\n+y\n@@ -10 +12 @@\n-a\n+b\n@@ -20,3 +21,0 @@\n";
+        assert_eq!(parse_diff_hunks(diff), vec![(4, 5), (12, 12), (21, 21)]);
+
+        let function = FunctionComplexity {
+            name: "load".to_string(),
+            line: 3,
+            end_line: 9,
+            complexity: 4,
+        };
+        assert!(function.overlaps(&[(4, 5)]));
+        assert!(!function.overlaps(&[(12, 12)]));
+    }
+
+    #[test]
+    fn test_changed_definitions_select_referencing_tests() {
+        let source = "import os\n\nRATE = 2\n\n\nclass Order:\n    def total(self):\n        return 1\n\n\ndef refund(order):\n    # full refund\n    return order\n";
+        let changed = changed_definitions(source, &[(8, 8), (12, 13)]);
+        assert_eq!(changed.names, ["Order", "total", "refund"]);
+        assert!(!changed.module_level);
+        assert!(changed_definitions(source, &[(3, 3)]).module_level);
+        assert!(!changed_definitions(source, &[(4, 5)]).module_level);
+
+        let tests = "from shop import Order, refund\n\n\ndef test_total():\n    assert Order().total() == 1\n\n\ndef test_refund():\n    assert refund(1) == 1\n\n\ndef test_refund_total():\n    assert refund_total() == 0\n";
+        let names = ["refund".to_string()];
+        assert_eq!(
+            tests_referencing(tests, &names),
+            Some(vec!["test_refund".to_string()])
+        );
+        assert_eq!(
+            tests_referencing(tests, &["total".to_string(), "refund".to_string()]),
+            Some(vec!["test_total".to_string(), "test_refund".to_string()])
+        );
+        // A changed function no test mentions makes the selection ambiguous
+        assert_eq!(tests_referencing(tests, &["cancel".to_string()]), None);
+    }
+
+    #[test]
+    fn test_test_smells() {
+        let tests = r#"import time
+
+import pytest
+
+
+def test_total():
+    order = Order()
+    assert order.total() == 0
+    assert order.items == []
+
+
+def test_submit():
+    Order().submit()
+
+
+def test_refund(mocker):
+    gateway = mocker.Mock()
+    time.sleep(1)
+    refund(gateway)
+    gateway.refund.assert_called_once()
+
+
+class TestErrors:
+    def test_invalid(self):
+        with pytest.raises(Exception):
+            Order(total=-1)
+
+    def test_missing(self):
+        with pytest.raises(KeyError, match="id"):
+            load({})
+"#;
+        let smells = test_smells(tests);
+        assert_eq!(smells.tests, 5);
+        assert_eq!(smells.assertions, 5);
+        let found: Vec<(&str, usize)> = smells
+            .findings
+            .iter()
+            .map(|smell| (smell.test.as_str(), smell.line))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("test_submit", 12),
+                ("test_refund", 16),
+                ("test_invalid", 24)
+            ]
+        );
+        assert_eq!(smells.findings[0].message, "no assertions");
+        assert!(smells.findings[1].message.starts_with("sleeps"));
+        assert!(smells.findings[2]
+            .message
+            .starts_with("catches any Exception"));
+    }
+}
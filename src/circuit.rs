@@ -0,0 +1,117 @@
+use crate::state::StateDir;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Persisted breaker state
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+struct CircuitState {
+    consecutive_failures: u32,
+    open_until: Option<DateTime<Utc>>,
+}
+
+/// Pauses AI requests for a cooldown after repeated provider failures
+///
+/// State lives in the workspace's state directory because every hook is a separate
+/// process: without it, each invocation during an outage would wait for its own
+/// failed request before falling back to basic analysis.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    path: PathBuf,
+    threshold: u32,
+    cooldown: Duration,
+}
+
+impl CircuitBreaker {
+    /// Breaker tripping after `threshold` consecutive failures for `cooldown`
+    pub fn for_workspace(workspace_dir: &Path, threshold: u32, cooldown: Duration) -> Result<Self> {
+        let path = StateDir::for_workspace(workspace_dir)?.file("ai-circuit.json");
+        Ok(Self::at(path, threshold, cooldown))
+    }
+
+    fn at(path: PathBuf, threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            path,
+            threshold: threshold.max(1),
+            cooldown,
+        }
+    }
+
+    /// When requests are paused, the time they may resume
+    pub fn open_until(&self) -> Option<DateTime<Utc>> {
+        self.load().open_until.filter(|until| *until > Utc::now())
+    }
+
+    /// A request succeeded: forget earlier failures
+    pub fn record_success(&self) {
+        if self.load() != CircuitState::default() {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+
+    /// A request failed; returns true when this failure tripped the breaker
+    pub fn record_failure(&self) -> bool {
+        let mut state = self.load();
+        state.consecutive_failures += 1;
+        let tripped = state.consecutive_failures >= self.threshold;
+        if tripped {
+            state.consecutive_failures = 0;
+            state.open_until = chrono::Duration::from_std(self.cooldown)
+                .ok()
+                .map(|cooldown| Utc::now() + cooldown);
+        }
+
+        if let Ok(content) = serde_json::to_string(&state) {
+            if let Err(e) = fs::write(&self.path, content) {
+                log::debug!("Could not save AI circuit state: {}", e);
+            }
+        }
+        tripped
+    }
+
+    fn load(&self) -> CircuitState {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_trips_after_consecutive_failures() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("ai-circuit.json");
+        let breaker = CircuitBreaker::at(path.clone(), 3, Duration::from_secs(300));
+
+        assert!(!breaker.record_failure());
+        assert!(!breaker.record_failure());
+        breaker.record_success();
+        assert!(!path.exists());
+
+        assert!(!breaker.record_failure());
+        assert!(!breaker.record_failure());
+        assert!(breaker.open_until().is_none());
+        assert!(breaker.record_failure());
+
+        // Another process sees the open circuit
+        let reopened = CircuitBreaker::at(path.clone(), 3, Duration::from_secs(300));
+        let until = reopened.open_until().unwrap();
+        assert!(until > Utc::now() + chrono::Duration::seconds(290));
+
+        // An elapsed cooldown lets requests through again
+        let expired = CircuitState {
+            consecutive_failures: 0,
+            open_until: Some(Utc::now() - chrono::Duration::seconds(1)),
+        };
+        fs::write(&path, serde_json::to_string(&expired).unwrap()).unwrap();
+        assert!(reopened.open_until().is_none());
+    }
+}
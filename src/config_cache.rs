@@ -0,0 +1,128 @@
+use crate::error::GuardrailsResult;
+use crate::state::StateDir;
+use crate::GuardrailsConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// State file holding the last parsed `guardrails.yaml`
+pub const CONFIG_CACHE_FILE: &str = "config-cache.json";
+
+/// Files modified this recently are parsed but not cached: a same-size rewrite within
+/// the filesystem's mtime granularity would otherwise keep serving the old config
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Parsed config together with the source file it was read from
+#[derive(Serialize, Deserialize)]
+struct CachedConfig {
+    source: PathBuf,
+    modified: SystemTime,
+    len: u64,
+    config: GuardrailsConfig,
+}
+
+impl GuardrailsConfig {
+    /// Load a YAML config, reusing the copy parsed on a previous invocation while the
+    /// file's path, size and modification time are unchanged
+    ///
+    /// The cache lives in `state`; a missing, stale or unreadable cache just means the
+    /// YAML is parsed (and the cache rewritten). Globsets have no serialized form, so
+    /// `GuardrailsChecker::from_config` still compiles them.
+    pub fn from_file_cached(config_path: &Path, state: &StateDir) -> GuardrailsResult<Self> {
+        let cache_path = state.file(CONFIG_CACHE_FILE);
+        let source = fs::canonicalize(config_path).unwrap_or_else(|_| config_path.to_path_buf());
+        let stamp = fs::metadata(&source)
+            .ok()
+            .and_then(|metadata| Some((metadata.modified().ok()?, metadata.len())));
+
+        if let Some((modified, len)) = stamp {
+            let cached = fs::read(&cache_path)
+                .ok()
+                .and_then(|bytes| serde_json::from_slice::<CachedConfig>(&bytes).ok());
+            if let Some(cached) = cached.filter(|cached| {
+                cached.source == source && cached.modified == modified && cached.len == len
+            }) {
+                log::debug!("Using cached config from {}", cache_path.display());
+                return Ok(cached.config);
+            }
+        }
+
+        let config = Self::from_file(&source)?;
+        let settled = |modified: SystemTime| {
+            SystemTime::now()
+                .duration_since(modified)
+                .is_ok_and(|age| age >= RACY_WINDOW)
+        };
+        let Some((modified, len)) = stamp.filter(|(modified, _)| settled(*modified)) else {
+            return Ok(config);
+        };
+        let cached = CachedConfig {
+            source,
+            modified,
+            len,
+            config,
+        };
+        let written = serde_json::to_vec(&cached)
+            .map_err(std::io::Error::other)
+            .and_then(|bytes| fs::write(&cache_path, bytes));
+        if let Err(e) = written {
+            log::debug!("Could not write config cache: {e}");
+        }
+        Ok(cached.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CONFIG_FILE_NAME;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    fn set_modified(path: &Path, modified: SystemTime) -> Result<()> {
+        fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(modified)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_cache_reused_until_source_changes() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let state = StateDir::for_workspace(temp_dir.path())?;
+        let cache_path = state.file(CONFIG_CACHE_FILE);
+        let config_path = temp_dir.path().join(CONFIG_FILE_NAME);
+        let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+
+        // A file written just now is parsed but not cached
+        fs::write(&config_path, "exclude:\n  patterns: [\"*.tmp\"]\n")?;
+        let config = GuardrailsConfig::from_file_cached(&config_path, &state)?;
+        assert_eq!(config.exclude.patterns, vec!["*.tmp"]);
+        assert!(!cache_path.exists());
+
+        set_modified(&config_path, an_hour_ago)?;
+        GuardrailsConfig::from_file_cached(&config_path, &state)?;
+        assert!(cache_path.exists());
+
+        // A matching cache entry is used without re-reading the YAML
+        let cache = fs::read_to_string(&cache_path)?;
+        fs::write(&cache_path, cache.replace("*.tmp", "*.cch"))?;
+        let config = GuardrailsConfig::from_file_cached(&config_path, &state)?;
+        assert_eq!(config.exclude.patterns, vec!["*.cch"]);
+
+        // A same-size edit with a new mtime invalidates it
+        fs::write(&config_path, "exclude:\n  patterns: [\"*.log\"]\n")?;
+        set_modified(&config_path, an_hour_ago + Duration::from_secs(1))?;
+        let config = GuardrailsConfig::from_file_cached(&config_path, &state)?;
+        assert_eq!(config.exclude.patterns, vec!["*.log"]);
+
+        // A corrupt cache falls back to parsing
+        fs::write(&cache_path, "not json")?;
+        let config = GuardrailsConfig::from_file_cached(&config_path, &state)?;
+        assert_eq!(config.exclude.patterns, vec!["*.log"]);
+
+        Ok(())
+    }
+}
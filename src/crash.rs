@@ -0,0 +1,139 @@
+use crate::state::StateDir;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::backtrace::Backtrace;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// State file panics are logged to
+pub const CRASH_LOG_FILE: &str = "crash.log";
+
+/// Size at which the crash log is rotated to `crash.1.log`
+const MAX_CRASH_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Workspace whose state directory gets the crash log (the current directory until set)
+static WORKSPACE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Most recent panic on any thread, picked up by [`catch_panic`]
+static LAST_PANIC: Mutex<Option<CaughtPanic>> = Mutex::new(None);
+
+/// A panic converted into a value by [`catch_panic`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaughtPanic {
+    /// Panic message with its source location
+    pub message: String,
+    /// Crash log the panic and its backtrace were written to, if that worked
+    pub log_path: Option<PathBuf>,
+}
+
+/// Replace the default panic output (a backtrace on stderr and exit code 101, which
+/// Claude takes for a failed check) with an entry in the workspace's crash log
+pub fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        let message = panic_message(info);
+        let workspace = WORKSPACE
+            .lock()
+            .ok()
+            .and_then(|workspace| workspace.clone())
+            .or_else(|| std::env::current_dir().ok());
+        let log_path = workspace.and_then(|workspace| {
+            let entry = crash_entry(&message, &Backtrace::force_capture().to_string());
+            log_crash(&workspace, &entry)
+                .map_err(|e| eprintln!("⚠️  Could not write crash log: {e:#}\n{entry}"))
+                .ok()
+        });
+        if let Ok(mut last) = LAST_PANIC.lock() {
+            *last = Some(CaughtPanic { message, log_path });
+        }
+    }));
+}
+
+/// Log later panics to this workspace's state directory
+pub fn set_workspace(workspace_dir: &Path) {
+    if let Ok(mut workspace) = WORKSPACE.lock() {
+        *workspace = Some(workspace_dir.to_path_buf());
+    }
+}
+
+/// Run `f`, returning its panic (already logged by the hook, if installed) as an error
+pub fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, CaughtPanic> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let caught = LAST_PANIC.lock().ok().and_then(|mut last| last.take());
+        caught.unwrap_or_else(|| CaughtPanic {
+            message: payload_message(payload.as_ref()),
+            log_path: None,
+        })
+    })
+}
+
+/// Append an entry to the workspace's crash log, returning the log's path
+pub fn log_crash(workspace_dir: &Path, entry: &str) -> Result<PathBuf> {
+    let path = StateDir::for_workspace(workspace_dir)?.file(CRASH_LOG_FILE);
+    if fs::metadata(&path).is_ok_and(|metadata| metadata.len() >= MAX_CRASH_LOG_BYTES) {
+        fs::rename(&path, path.with_extension("1.log")).context("Failed to rotate crash log")?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .context("Failed to open crash log")?;
+    writeln!(file, "{entry}").context("Failed to write crash log")?;
+    Ok(path)
+}
+
+/// Crash log entry: when, which version and command line, the panic and its backtrace
+fn crash_entry(message: &str, backtrace: &str) -> String {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    format!(
+        "[{}] v{} `{}` panicked: {message}\n{backtrace}",
+        Utc::now().to_rfc3339(),
+        env!("CARGO_PKG_VERSION"),
+        args.join(" ")
+    )
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    let message = payload_message(info.payload());
+    match info.location() {
+        Some(location) => format!("{message} at {location}"),
+        None => message,
+    }
+}
+
+fn payload_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_crash_log() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let entry = crash_entry("index out of bounds at src/lib.rs:1:1", "backtrace");
+        assert!(entry.contains("panicked: index out of bounds at src/lib.rs:1:1\nbacktrace"));
+
+        let path = log_crash(temp_dir.path(), &entry)?;
+        log_crash(temp_dir.path(), "second")?;
+        assert!(path.ends_with(CRASH_LOG_FILE));
+        let logged = fs::read_to_string(&path)?;
+        assert!(logged.starts_with(&entry));
+        assert!(logged.ends_with("second\n"));
+
+        // Without the hook installed the payload still becomes the message
+        let caught = catch_panic(|| -> u32 { std::panic::resume_unwind(Box::new("boom")) });
+        assert_eq!(caught.unwrap_err().message, "boom");
+        assert_eq!(catch_panic(|| 7), Ok(7));
+        Ok(())
+    }
+}
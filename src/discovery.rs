@@ -1,43 +1,704 @@
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
 use which::which;
 
 /// Represents different Python tools available for linting
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PythonLinter {
     Ruff,
     Flake8,
     Pylint,
 }
 
+/// Kind of Python source file, by extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PythonSourceKind {
+    /// Regular module (`.py`)
+    Module,
+    /// Type stub (`.pyi`): linted with stub rules, never needs tests
+    Stub,
+    /// Cython source (`.pyx`/`.pxd`): linted with cython-lint when it's installed
+    Cython,
+}
+
+impl PythonSourceKind {
+    /// Classify by extension; extensionless files count as modules when their shebang
+    /// or an editor modeline says they are Python (e.g. `bin/manage`)
+    pub fn of(file: &Path) -> Option<Self> {
+        let Some(extension) = file.extension() else {
+            return is_python_script(file).then_some(PythonSourceKind::Module);
+        };
+        match extension.to_str()? {
+            "py" => Some(PythonSourceKind::Module),
+            "pyi" => Some(PythonSourceKind::Stub),
+            "pyx" | "pxd" => Some(PythonSourceKind::Cython),
+            _ => None,
+        }
+    }
+}
+
+/// Bytes read when sniffing an extensionless file for a shebang or modeline
+const SCRIPT_SNIFF_BYTES: u64 = 64 * 1024;
+
+/// Whether an extensionless file is a Python script, judging by its shebang
+/// (`#!/usr/bin/env python3`) or an emacs/vim modeline
+fn is_python_script(file: &Path) -> bool {
+    use std::io::Read;
+
+    let mut content = String::new();
+    let read = std::fs::File::open(file)
+        .and_then(|f| f.take(SCRIPT_SNIFF_BYTES).read_to_string(&mut content));
+    read.is_ok() && is_python_source_header(&content)
+}
+
+/// Shebang or modeline check on the start of a file; vim also reads modelines from the
+/// last lines
+fn is_python_source_header(content: &str) -> bool {
+    let lines: Vec<&str> = content.lines().collect();
+    if let Some(interpreter) = lines.first().and_then(|line| line.strip_prefix("#!")) {
+        let mut words = interpreter.split_whitespace();
+        let program = words.next().unwrap_or("");
+        let program = if program.ends_with("/env") {
+            words.find(|word| !word.starts_with('-')).unwrap_or("")
+        } else {
+            program
+        };
+        let name = program.rsplit('/').next().unwrap_or(program);
+        if name.starts_with("python") || name == "pypy" || name.starts_with("pypy3") {
+            return true;
+        }
+    }
+
+    let emacs = lines.iter().take(2).any(|line| {
+        line.split("-*-").nth(1).is_some_and(|modes| {
+            modes.split(';').any(|mode| {
+                let mode = mode.trim().trim_start_matches("mode:").trim();
+                mode.eq_ignore_ascii_case("python")
+            })
+        })
+    });
+    let vim = lines
+        .iter()
+        .take(5)
+        .chain(lines.iter().rev().take(5))
+        .filter_map(|line| line.split_once("vim:").or_else(|| line.split_once("vi:")))
+        .any(|(_, settings)| {
+            settings
+                .split([' ', ':'])
+                .any(|setting| matches!(setting, "ft=python" | "filetype=python" | "syntax=python"))
+        });
+    emacs || vim
+}
+
 /// Represents different Python code formatters
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PythonFormatter {
     Black,
     Ruff, // Ruff can also format
 }
 
-/// Represents different Python tools available for testing
+/// Static type checkers run alongside the linter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PythonTypeChecker {
+    Mypy,
+    Pyright,
+}
+
+/// Tool that sorts imports before the formatter runs
 #[derive(Debug, Clone, PartialEq)]
+pub enum ImportSorter {
+    /// isort, with the `profile` from its configuration (e.g. `black`)
+    Isort { profile: Option<String> },
+    /// ruff's isort rules (`ruff check --select I --fix`)
+    Ruff,
+}
+
+/// Represents different Python tools available for testing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PythonTester {
     UvPytest,
     Pytest,
     PytestModule,
     Unittest,
+    NoxSession(String), // nox -s <session> -- <file>
+    ToxEnv(String),     // tox -e <env> -- <file>
+    DjangoManage,       // python manage.py test <module>
+}
+
+/// Default nox session used when the project has a noxfile.py
+pub const DEFAULT_NOX_SESSION: &str = "tests";
+
+/// Default tox environment used when the project has a tox.ini
+pub const DEFAULT_TOX_ENV: &str = "py";
+
+/// Directory names recursive searches never descend into (hidden directories are always
+/// skipped as well)
+pub const DEFAULT_SKIP_DIRS: &[&str] = &[
+    "__pycache__",
+    "node_modules",
+    ".git",
+    ".tox",
+    ".nox",
+    ".eggs",
+    ".mypy_cache",
+    "site-packages",
+    "venv",
+    "env",
+    "build",
+    "dist",
+    "target",
+];
+
+/// Directory levels a recursive search looks at by default, the starting one included
+pub const DEFAULT_SEARCH_DEPTH: usize = 6;
+
+/// Bounds for recursive walks of the project tree (test file lookup, project root
+/// detection), so a huge checkout or a stray virtualenv can't make a hook crawl
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchLimits {
+    /// Directory names skipped, in addition to hidden directories
+    pub skip_dirs: Vec<String>,
+    /// Directory levels looked at, the starting one included
+    pub max_depth: usize,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        Self {
+            skip_dirs: DEFAULT_SKIP_DIRS
+                .iter()
+                .map(|dir| dir.to_string())
+                .collect(),
+            max_depth: DEFAULT_SEARCH_DEPTH,
+        }
+    }
+}
+
+impl SearchLimits {
+    /// Whether a walk doesn't descend into `dir`
+    pub fn skips(&self, dir: &Path) -> bool {
+        dir.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                name.starts_with('.') || self.skip_dirs.iter().any(|skip| skip == name)
+            })
+    }
+
+    /// First file named one of `names` below `dir`, preferring shallower matches within
+    /// each directory
+    pub fn find_file(&self, dir: &Path, names: &[String]) -> Option<PathBuf> {
+        self.find_file_within(dir, names, self.max_depth)
+    }
+
+    fn find_file_within(&self, dir: &Path, names: &[String], depth: usize) -> Option<PathBuf> {
+        if depth == 0 || !dir.is_dir() {
+            return None;
+        }
+        if let Some(found) = names
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+        {
+            return Some(found);
+        }
+        self.subdirs(dir)
+            .find_map(|subdir| self.find_file_within(&subdir, names, depth - 1))
+    }
+
+    /// Every file named one of `names` below `dir`
+    pub fn find_files(&self, dir: &Path, names: &[String]) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        self.collect_files(dir, names, self.max_depth, &mut found);
+        found
+    }
+
+    fn collect_files(&self, dir: &Path, names: &[String], depth: usize, found: &mut Vec<PathBuf>) {
+        if depth == 0 || !dir.is_dir() {
+            return;
+        }
+        found.extend(
+            names
+                .iter()
+                .map(|name| dir.join(name))
+                .filter(|path| path.is_file()),
+        );
+        for subdir in self.subdirs(dir) {
+            self.collect_files(&subdir, names, depth - 1, found);
+        }
+    }
+
+    /// Every directory a walk from `dir` descends into, `dir` included
+    pub fn dirs(&self, dir: &Path) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        self.collect_dirs(dir, self.max_depth, &mut found);
+        found
+    }
+
+    fn collect_dirs(&self, dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+        if depth == 0 || !dir.is_dir() {
+            return;
+        }
+        found.push(dir.to_path_buf());
+        for subdir in self.subdirs(dir) {
+            self.collect_dirs(&subdir, depth - 1, found);
+        }
+    }
+
+    /// Whether a walk from `root` descends into `dir`: `dir` is below `root`, within
+    /// `max_depth` and not inside a skipped directory
+    pub fn reaches(&self, root: &Path, dir: &Path) -> bool {
+        let Ok(relative) = dir.strip_prefix(root) else {
+            return false;
+        };
+        relative.components().count() < self.max_depth
+            && relative
+                .components()
+                .all(|component| !self.skips(Path::new(component.as_os_str())))
+    }
+
+    /// Whether any file below `dir` satisfies `matches`
+    pub fn any_file(&self, dir: &Path, matches: &dyn Fn(&Path) -> bool) -> bool {
+        self.any_file_within(dir, matches, self.max_depth)
+    }
+
+    fn any_file_within(&self, dir: &Path, matches: &dyn Fn(&Path) -> bool, depth: usize) -> bool {
+        if depth == 0 {
+            return false;
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return false;
+        };
+        entries.flatten().any(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                !self.skips(&path) && self.any_file_within(&path, matches, depth - 1)
+            } else {
+                matches(&path)
+            }
+        })
+    }
+
+    fn subdirs(&self, dir: &Path) -> impl Iterator<Item = PathBuf> + '_ {
+        std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir() && !self.skips(path))
+    }
+}
+
+/// A tool the hooks run and the version it reports
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolVersion {
+    /// Command, e.g. `ruff`
+    pub tool: String,
+    /// First line of its `--version` output, e.g. `ruff 0.6.9`
+    pub version: String,
 }
 
 /// Information about a discovered Python project
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PythonProject {
     pub root: PathBuf,
     pub project_type: ProjectType,
     pub available_linters: Vec<PythonLinter>,
     pub available_testers: Vec<PythonTester>,
     pub available_formatters: Vec<PythonFormatter>,
+    pub environment: PythonEnvironment,
+    /// Python version the project targets, when declared
+    pub python_version: Option<PythonVersion>,
+    pub framework: Option<Framework>,
+    /// Versions of the preferred tools, queried on first use
+    #[serde(skip)]
+    pub(crate) tool_versions: OnceLock<Vec<ToolVersion>>,
+}
+
+/// Web framework a project is built on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Framework {
+    /// Django project (manage.py, or pytest-django settings in the test config)
+    Django {
+        /// From `DJANGO_SETTINGS_MODULE` or the default set in manage.py
+        settings_module: Option<String>,
+        /// Tests run through pytest-django rather than `manage.py test`
+        pytest_django: bool,
+    },
+    /// FastAPI application (declared as a dependency or imported)
+    FastApi,
+    /// Flask application (declared as a dependency or imported)
+    Flask,
+}
+
+/// Dependency manifests scanned for web framework requirements
+const DEPENDENCY_FILES: &[&str] = &[
+    "pyproject.toml",
+    "requirements.txt",
+    "requirements-dev.txt",
+    "setup.py",
+    "setup.cfg",
+    "Pipfile",
+];
+
+/// Files pytest-django reads `DJANGO_SETTINGS_MODULE` from
+const PYTEST_CONFIG_FILES: &[&str] = &["pytest.ini", "pyproject.toml", "setup.cfg", "tox.ini"];
+
+impl Framework {
+    pub fn detect(root: &Path) -> Option<Self> {
+        let manage_py = std::fs::read_to_string(root.join("manage.py")).ok();
+        let pytest_settings = PYTEST_CONFIG_FILES.iter().find_map(|file| {
+            let content = std::fs::read_to_string(root.join(file)).ok()?;
+            content.lines().find_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                (key.trim() == "DJANGO_SETTINGS_MODULE").then(|| {
+                    value
+                        .trim()
+                        .trim_matches(|c| c == '"' || c == '\'')
+                        .to_string()
+                })
+            })
+        });
+        if manage_py.is_none() && pytest_settings.is_none() {
+            return Self::from_dependencies(root);
+        }
+
+        let settings_module = std::env::var("DJANGO_SETTINGS_MODULE")
+            .ok()
+            .filter(|module| !module.is_empty())
+            .or_else(|| pytest_settings.clone())
+            .or_else(|| manage_py.as_deref().and_then(manage_py_settings_module));
+        Some(Framework::Django {
+            settings_module,
+            pytest_django: pytest_settings.is_some(),
+        })
+    }
+
+    /// Short description for prompts and session context
+    pub fn describe(&self) -> String {
+        match self {
+            Framework::Django {
+                settings_module: Some(module),
+                ..
+            } => format!("Django (settings: {module})"),
+            Framework::Django { .. } => "Django".to_string(),
+            Framework::FastApi => "FastAPI".to_string(),
+            Framework::Flask => "Flask".to_string(),
+        }
+    }
+
+    /// FastAPI or Flask declared in the project's dependency files
+    fn from_dependencies(root: &Path) -> Option<Self> {
+        DEPENDENCY_FILES.iter().find_map(|file| {
+            let content = std::fs::read_to_string(root.join(file))
+                .ok()?
+                .to_lowercase();
+            // Split on anything that can't be part of a distribution name, so
+            // `flask-sqlalchemy` doesn't count as `flask`
+            let mut names =
+                content.split(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+            names.find_map(|name| match name {
+                "fastapi" => Some(Framework::FastApi),
+                "flask" => Some(Framework::Flask),
+                _ => None,
+            })
+        })
+    }
+
+    /// FastAPI or Flask imported by a module, for projects that don't declare their
+    /// dependencies
+    pub fn from_imports(source: &str) -> Option<Self> {
+        source.lines().find_map(|line| {
+            let module = line
+                .trim_start()
+                .strip_prefix("from ")
+                .or_else(|| line.trim_start().strip_prefix("import "))?;
+            let package = module.split(['.', ' ', ',']).next()?;
+            match package {
+                "fastapi" => Some(Framework::FastApi),
+                "flask" => Some(Framework::Flask),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Settings module from manage.py's
+/// `os.environ.setdefault("DJANGO_SETTINGS_MODULE", "mysite.settings")`
+fn manage_py_settings_module(manage_py: &str) -> Option<String> {
+    let line = manage_py
+        .lines()
+        .find(|line| line.contains("DJANGO_SETTINGS_MODULE") && line.contains("setdefault"))?;
+    line.split(['"', '\''])
+        .skip(1)
+        .step_by(2)
+        .find(|quoted| *quoted != "DJANGO_SETTINGS_MODULE")
+        .map(str::to_string)
+}
+
+/// Python version (major.minor) a project targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PythonVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+/// Oldest and newest versions accepted by ruff's `--target-version`
+const RUFF_TARGET_VERSIONS: (PythonVersion, PythonVersion) = (
+    PythonVersion { major: 3, minor: 7 },
+    PythonVersion {
+        major: 3,
+        minor: 13,
+    },
+);
+
+impl PythonVersion {
+    /// First `X.Y` in the text, e.g. `3.11` from `3.11.4` or `python3.11`
+    pub fn parse(text: &str) -> Option<Self> {
+        let start = text.find(|c: char| c.is_ascii_digit())?;
+        let mut parts = text[start..].split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor: String = parts
+            .next()?
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        Some(Self {
+            major,
+            minor: minor.parse().ok()?,
+        })
+    }
+
+    /// Detect from `requires-python` (or Poetry's `python` dependency) in pyproject.toml,
+    /// then `.python-version`, then the project virtualenv's `pyvenv.cfg`
+    pub fn detect(root: &Path) -> Option<Self> {
+        let pyproject = std::fs::read_to_string(root.join("pyproject.toml")).unwrap_or_default();
+        requires_python(&pyproject)
+            .or_else(|| {
+                let pinned = std::fs::read_to_string(root.join(".python-version")).ok()?;
+                pinned
+                    .lines()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty() && !line.starts_with('#'))
+                    .and_then(Self::parse)
+            })
+            .or_else(|| {
+                [".venv", "venv"].iter().find_map(|venv| {
+                    let cfg = std::fs::read_to_string(root.join(venv).join("pyvenv.cfg")).ok()?;
+                    cfg.lines().find_map(|line| {
+                        let (key, value) = line.split_once('=')?;
+                        matches!(key.trim(), "version" | "version_info")
+                            .then(|| Self::parse(value))
+                            .flatten()
+                    })
+                })
+            })
+    }
+
+    /// Value for ruff's `--target-version`, e.g. `py311`
+    pub fn ruff_target(&self) -> String {
+        format!("py{}{}", self.major, self.minor)
+    }
+}
+
+impl std::fmt::Display for PythonVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Lowest version allowed by `[project] requires-python` or
+/// `[tool.poetry.dependencies] python` (e.g. `>=3.9,<4` or `^3.10`)
+fn requires_python(pyproject: &str) -> Option<PythonVersion> {
+    let mut section = "";
+    for line in pyproject.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        let declares_python = (section == "[project]" && key == "requires-python")
+            || (section == "[tool.poetry.dependencies]" && key == "python");
+        if !declares_python {
+            continue;
+        }
+
+        return value
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'')
+            .split(',')
+            .map(str::trim)
+            .filter(|clause| !clause.starts_with('<') && !clause.starts_with("!="))
+            .filter_map(PythonVersion::parse)
+            .min();
+    }
+    None
+}
+
+/// Python environment the project's tools are looked up and run in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PythonEnvironment {
+    /// Tools and interpreter from PATH
+    System,
+    /// Activated conda environment (`CONDA_PREFIX`); its bin directories are searched
+    /// before PATH
+    Conda { prefix: PathBuf },
+    /// Conda environment named by the project's environment.yml but not activated;
+    /// test commands run through `conda run -n <name>`
+    CondaRun { name: String },
+}
+
+/// Files declaring a project's conda environment
+const CONDA_ENVIRONMENT_FILES: &[&str] = &["environment.yml", "environment.yaml"];
+
+impl PythonEnvironment {
+    /// Detect the environment from `CONDA_PREFIX` and the project's environment.yml
+    pub fn detect(root: &Path) -> Self {
+        Self::detect_with(
+            root,
+            std::env::var_os("CONDA_PREFIX"),
+            which("conda").is_ok(),
+        )
+    }
+
+    fn detect_with(root: &Path, conda_prefix: Option<OsString>, conda_available: bool) -> Self {
+        if let Some(prefix) = conda_prefix.filter(|prefix| !prefix.is_empty()) {
+            return PythonEnvironment::Conda {
+                prefix: PathBuf::from(prefix),
+            };
+        }
+
+        if conda_available {
+            if let Some(name) = conda_environment_name(root) {
+                return PythonEnvironment::CondaRun { name };
+            }
+        }
+
+        PythonEnvironment::System
+    }
+
+    /// Directories searched for tools before PATH
+    fn bin_dirs(&self) -> Vec<PathBuf> {
+        match self {
+            // Windows envs keep python.exe in the prefix and tools in Scripts
+            PythonEnvironment::Conda { prefix } => {
+                vec![prefix.join("bin"), prefix.join("Scripts"), prefix.clone()]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Locate a tool, preferring the environment's own copy
+    pub fn find_tool(&self, tool: &str) -> Option<PathBuf> {
+        let bin_dirs = self.bin_dirs();
+        if !bin_dirs.is_empty() {
+            let search_path = std::env::join_paths(bin_dirs).ok()?;
+            let cwd = std::env::current_dir().ok()?;
+            if let Ok(path) = which::which_in(tool, Some(search_path), cwd) {
+                return Some(path);
+            }
+        }
+        which(tool).ok()
+    }
+
+    /// First line a tool prints for its version query (`--version` unless `args` says
+    /// otherwise), or `None` when it isn't installed or doesn't answer
+    pub fn tool_version(&self, tool: &str, args: &[&str]) -> Option<String> {
+        let output = Command::new(self.find_tool(tool)?)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        let version = [&output.stdout, &output.stderr]
+            .into_iter()
+            .find_map(|stream| {
+                String::from_utf8_lossy(stream)
+                    .lines()
+                    .map(str::trim)
+                    .find(|line| !line.is_empty())
+                    .map(str::to_string)
+            });
+        version
+    }
+
+    /// Program to spawn for a tool: its path inside an activated conda env when it has
+    /// one there, the bare name otherwise
+    pub fn resolve(&self, program: &str) -> String {
+        match self {
+            PythonEnvironment::Conda { .. } => self
+                .find_tool(program)
+                .filter(|path| self.bin_dirs().iter().any(|dir| path.starts_with(dir)))
+                .map(|path| path.to_string_lossy().to_string())
+                .unwrap_or_else(|| program.to_string()),
+            _ => program.to_string(),
+        }
+    }
+
+    /// Command line for a tool that must run on the project's interpreter (tests),
+    /// wrapped in `conda run` for an environment that isn't activated
+    pub fn command(&self, program: &str, args: Vec<String>) -> (String, Vec<String>) {
+        match self {
+            PythonEnvironment::CondaRun { name } => {
+                let mut wrapped = vec![
+                    "run".to_string(),
+                    "-n".to_string(),
+                    name.clone(),
+                    "--no-capture-output".to_string(),
+                    program.to_string(),
+                ];
+                wrapped.extend(args);
+                ("conda".to_string(), wrapped)
+            }
+            _ => (self.resolve(program), args),
+        }
+    }
+
+    /// Interpreter tests run on, when it can be determined without running conda
+    pub fn interpreter(&self) -> Option<PathBuf> {
+        match self {
+            PythonEnvironment::CondaRun { .. } => None,
+            _ => self
+                .find_tool("python")
+                .or_else(|| self.find_tool("python3")),
+        }
+    }
+
+    /// Short description for verbose output, e.g. `conda env /opt/conda/envs/app`
+    pub fn describe(&self) -> String {
+        let interpreter = self
+            .interpreter()
+            .map(|path| format!(" (python: {})", path.display()))
+            .unwrap_or_default();
+        match self {
+            PythonEnvironment::System => format!("system Python{interpreter}"),
+            PythonEnvironment::Conda { prefix } => {
+                format!("conda env {}{interpreter}", prefix.display())
+            }
+            PythonEnvironment::CondaRun { name } => format!("conda run -n {name}"),
+        }
+    }
+}
+
+/// `name:` from the project's environment.yml
+fn conda_environment_name(root: &Path) -> Option<String> {
+    CONDA_ENVIRONMENT_FILES.iter().find_map(|file| {
+        let content = std::fs::read_to_string(root.join(file)).ok()?;
+        let yaml: serde_yaml::Value = serde_yaml::from_str(&content).ok()?;
+        yaml.get("name")?.as_str().map(str::to_string)
+    })
 }
 
 /// Type of Python project detected
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ProjectType {
     Modern,    // Has pyproject.toml
     Classical, // Has setup.py
@@ -53,9 +714,13 @@ impl PythonProject {
             Self::find_project_root(start_path).context("Failed to find Python project root")?;
 
         let project_type = Self::detect_project_type(&project_root);
-        let available_linters = Self::detect_available_linters();
-        let available_testers = Self::detect_available_testers();
-        let available_formatters = Self::detect_available_formatters();
+        let environment = PythonEnvironment::detect(&project_root);
+        let python_version = PythonVersion::detect(&project_root);
+        let framework = Framework::detect(&project_root);
+        let available_linters = Self::detect_available_linters(&environment);
+        let available_testers =
+            Self::detect_available_testers(&project_root, &environment, framework.as_ref());
+        let available_formatters = Self::detect_available_formatters(&environment);
 
         Ok(Self {
             root: project_root,
@@ -63,11 +728,49 @@ impl PythonProject {
             available_linters,
             available_testers,
             available_formatters,
+            environment,
+            python_version,
+            framework,
+            tool_versions: OnceLock::new(),
+        })
+    }
+
+    /// Versions of the preferred linter, formatter and test runner, queried once per
+    /// discovered project; tools that don't answer `--version` are left out
+    pub fn tool_versions(&self) -> &[ToolVersion] {
+        self.tool_versions.get_or_init(|| {
+            let mut commands: Vec<(&str, Vec<&str>)> = Vec::new();
+            commands.extend(
+                self.preferred_linter()
+                    .map(|linter| (linter.command(), vec!["--version"])),
+            );
+            commands.extend(
+                self.preferred_formatter()
+                    .map(|formatter| (formatter.command(), vec!["--version"])),
+            );
+            commands.extend(
+                self.preferred_tester()
+                    .map(|tester| (tester.command(), tester.version_args())),
+            );
+
+            let mut versions: Vec<ToolVersion> = Vec::new();
+            for (tool, args) in commands {
+                if versions.iter().any(|known| known.tool == tool) {
+                    continue;
+                }
+                if let Some(version) = self.environment.tool_version(tool, &args) {
+                    versions.push(ToolVersion {
+                        tool: tool.to_string(),
+                        version,
+                    });
+                }
+            }
+            versions
         })
     }
 
     /// Walk up the directory tree to find the Python project root
-    fn find_project_root(start_dir: &Path) -> Option<PathBuf> {
+    pub(crate) fn find_project_root(start_dir: &Path) -> Option<PathBuf> {
         // Convert to absolute path if needed
         let absolute_start = if start_dir.is_absolute() {
             start_dir.to_path_buf()
@@ -109,6 +812,9 @@ impl PythonProject {
             || dir.join("requirements").is_dir()
             || dir.join("Pipfile").exists()
             || dir.join("poetry.lock").exists()
+            || CONDA_ENVIRONMENT_FILES
+                .iter()
+                .any(|file| dir.join(file).exists())
         {
             return true;
         }
@@ -126,30 +832,11 @@ impl PythonProject {
 
     /// Check if directory has Python files within given depth
     fn has_python_files(dir: &Path, max_depth: usize) -> bool {
-        if max_depth == 0 {
-            return false;
-        }
-
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-
-                if path.is_file() && path.extension().is_some_and(|ext| ext == "py") {
-                    return true;
-                }
-
-                if path.is_dir()
-                    && !path
-                        .file_name()
-                        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
-                    && Self::has_python_files(&path, max_depth - 1)
-                {
-                    return true;
-                }
-            }
-        }
-
-        false
+        let limits = SearchLimits {
+            max_depth,
+            ..SearchLimits::default()
+        };
+        limits.any_file(dir, &|path| path.extension().is_some_and(|ext| ext == "py"))
     }
 
     /// Detect the type of Python project
@@ -162,6 +849,9 @@ impl PythonProject {
             || root.join("requirements").is_dir()
             || root.join("Pipfile").exists()
             || root.join("poetry.lock").exists()
+            || CONDA_ENVIRONMENT_FILES
+                .iter()
+                .any(|file| root.join(file).exists())
         {
             ProjectType::Simple
         } else if root.join(".git").exists() {
@@ -172,16 +862,16 @@ impl PythonProject {
     }
 
     /// Detect available Python linting tools
-    fn detect_available_linters() -> Vec<PythonLinter> {
+    fn detect_available_linters(environment: &PythonEnvironment) -> Vec<PythonLinter> {
         let mut linters = Vec::new();
 
-        if which("ruff").is_ok() {
+        if environment.find_tool("ruff").is_some() {
             linters.push(PythonLinter::Ruff);
         }
-        if which("flake8").is_ok() {
+        if environment.find_tool("flake8").is_some() {
             linters.push(PythonLinter::Flake8);
         }
-        if which("pylint").is_ok() {
+        if environment.find_tool("pylint").is_some() {
             linters.push(PythonLinter::Pylint);
         }
 
@@ -189,14 +879,14 @@ impl PythonProject {
     }
 
     /// Detect available Python formatting tools
-    fn detect_available_formatters() -> Vec<PythonFormatter> {
+    fn detect_available_formatters(environment: &PythonEnvironment) -> Vec<PythonFormatter> {
         let mut formatters = Vec::new();
 
         // Prioritize Black first, then Ruff formatter
-        if which("black").is_ok() {
+        if environment.find_tool("black").is_some() {
             formatters.push(PythonFormatter::Black);
         }
-        if which("ruff").is_ok() {
+        if environment.find_tool("ruff").is_some() {
             formatters.push(PythonFormatter::Ruff);
         }
 
@@ -204,19 +894,51 @@ impl PythonProject {
     }
 
     /// Detect available Python testing tools
-    fn detect_available_testers() -> Vec<PythonTester> {
+    fn detect_available_testers(
+        root: &Path,
+        environment: &PythonEnvironment,
+        framework: Option<&Framework>,
+    ) -> Vec<PythonTester> {
         let mut testers = Vec::new();
 
+        // Projects that orchestrate tests through nox/tox should be tested that way
+        if root.join("noxfile.py").exists() && which("nox").is_ok() {
+            testers.push(PythonTester::NoxSession(DEFAULT_NOX_SESSION.to_string()));
+        }
+        if root.join("tox.ini").exists() && which("tox").is_ok() {
+            testers.push(PythonTester::ToxEnv(DEFAULT_TOX_ENV.to_string()));
+        }
+
+        // Django's own runner sets up the test database; pytest only works with pytest-django
+        let conda_run = matches!(environment, PythonEnvironment::CondaRun { .. });
+        if let Some(Framework::Django {
+            pytest_django: false,
+            ..
+        }) = framework
+        {
+            if root.join("manage.py").exists() && (conda_run || environment.interpreter().is_some())
+            {
+                testers.push(PythonTester::DjangoManage);
+            }
+        }
+
+        // The env's own interpreter (and its pytest) only exists behind `conda run`
+        if conda_run {
+            testers.push(PythonTester::PytestModule);
+            testers.push(PythonTester::Unittest);
+            return testers;
+        }
+
         // Prioritize uv if available (modern Python project management)
         if which("uv").is_ok() {
             testers.push(PythonTester::UvPytest);
         }
 
-        if which("pytest").is_ok() {
+        if environment.find_tool("pytest").is_some() {
             testers.push(PythonTester::Pytest);
         }
 
-        if which("python").is_ok() || which("python3").is_ok() {
+        if environment.interpreter().is_some() {
             testers.push(PythonTester::PytestModule);
             testers.push(PythonTester::Unittest);
         }
@@ -239,6 +961,154 @@ impl PythonProject {
         self.available_formatters.first()
     }
 
+    /// Check-only lint command for a Python source file, picking tools that understand
+    /// its kind
+    ///
+    /// Stubs go through ruff with the flake8-pyi (`PYI`) rules, flake8, or mypy when
+    /// the linter can't read them; Cython goes through cython-lint.
+    pub fn lint_command_for(&self, file: &Path) -> Option<(String, Vec<String>)> {
+        let file_str = file.to_string_lossy();
+        let linter_command = |linter: &PythonLinter, extra: &[&str]| {
+            let mut args = linter.file_args(&file_str);
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend(self.target_version_args(linter.command()));
+            (self.environment.resolve(linter.command()), args)
+        };
+
+        match PythonSourceKind::of(file)? {
+            PythonSourceKind::Module => Some(linter_command(self.preferred_linter()?, &[])),
+            PythonSourceKind::Stub => match self.preferred_linter() {
+                Some(linter @ PythonLinter::Ruff) => {
+                    Some(linter_command(linter, &["--extend-select", "PYI"]))
+                }
+                Some(linter @ PythonLinter::Flake8) => Some(linter_command(linter, &[])),
+                _ => {
+                    self.environment.find_tool("mypy")?;
+                    Some((self.environment.resolve("mypy"), vec![file_str.to_string()]))
+                }
+            },
+            PythonSourceKind::Cython => {
+                self.environment.find_tool("cython-lint")?;
+                Some((
+                    self.environment.resolve("cython-lint"),
+                    vec![file_str.to_string()],
+                ))
+            }
+        }
+    }
+
+    /// Whether a file is a Django migration, which is generated and never tested
+    pub fn is_django_migration(&self, file: &Path) -> bool {
+        matches!(self.framework, Some(Framework::Django { .. }))
+            && file.extension().is_some_and(|ext| ext == "py")
+            && file
+                .parent()
+                .and_then(|dir| dir.file_name())
+                .is_some_and(|dir| dir == "migrations")
+    }
+
+    /// Environment variables for running tests with `tester`: the Django settings module
+    /// for pytest-django when it isn't set already
+    pub fn test_env(&self, tester: &PythonTester) -> Vec<(String, String)> {
+        match &self.framework {
+            Some(Framework::Django {
+                settings_module: Some(module),
+                ..
+            }) if tester.is_pytest() && std::env::var_os("DJANGO_SETTINGS_MODULE").is_none() => {
+                vec![("DJANGO_SETTINGS_MODULE".to_string(), module.clone())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether pytest-cov is installed where `tester` runs pytest, from the plugin list
+    /// `pytest -VV` prints
+    pub fn has_pytest_cov(&self, tester: &PythonTester) -> bool {
+        if !tester.is_pytest() {
+            return false;
+        }
+        let mut args: Vec<String> = tester.args().iter().map(|arg| arg.to_string()).collect();
+        args.push("-VV".to_string());
+        let (program, args) = self.environment.command(tester.command(), args);
+        Command::new(program)
+            .args(args)
+            .current_dir(&self.root)
+            .output()
+            .is_ok_and(|output| {
+                [&output.stdout, &output.stderr]
+                    .iter()
+                    .any(|stream| String::from_utf8_lossy(stream).contains("pytest-cov"))
+            })
+    }
+
+    /// Extra arguments telling a tool which Python version the project targets
+    ///
+    /// Only ruff takes one, and only when the project doesn't pin its own
+    /// `target-version` in the ruff config.
+    pub fn target_version_args(&self, tool: &str) -> Vec<String> {
+        let (oldest, newest) = RUFF_TARGET_VERSIONS;
+        match self.python_version {
+            Some(version)
+                if tool == "ruff"
+                    && (oldest..=newest).contains(&version)
+                    && !self.pins_ruff_target_version() =>
+            {
+                vec!["--target-version".to_string(), version.ruff_target()]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn pins_ruff_target_version(&self) -> bool {
+        ["pyproject.toml", "ruff.toml", ".ruff.toml"]
+            .iter()
+            .filter_map(|file| std::fs::read_to_string(self.root.join(file)).ok())
+            .any(|content| {
+                content
+                    .lines()
+                    .any(|line| line.trim_start().starts_with("target-version"))
+            })
+    }
+
+    /// Import sorter for the project: isort when the project configures it, ruff when
+    /// its config enables the isort (`I`) rules, otherwise isort when it's installed
+    pub fn import_sorter(&self) -> Option<ImportSorter> {
+        let read = |file: &str| std::fs::read_to_string(self.root.join(file)).ok();
+        let has_isort = self.environment.find_tool("isort").is_some();
+        let has_ruff = self.available_linters.contains(&PythonLinter::Ruff);
+
+        let isort_settings = [
+            ("pyproject.toml", &["[tool.isort]"][..]),
+            (".isort.cfg", &["[settings]", "[isort]"][..]),
+            ("setup.cfg", &["[isort]", "[tool:isort]"][..]),
+            ("tox.ini", &["[isort]", "[tool:isort]"][..]),
+        ]
+        .iter()
+        .find_map(|(file, sections)| section_lines(&read(file)?, sections));
+        if let Some(settings) = isort_settings.filter(|_| has_isort) {
+            let profile = settings.iter().find_map(|line| {
+                let (key, value) = line.split_once(['=', ':'])?;
+                (key.trim() == "profile")
+                    .then(|| value.trim().trim_matches(['"', '\'']).to_string())
+            });
+            return Some(ImportSorter::Isort { profile });
+        }
+
+        let ruff_sorts_imports = [
+            ("pyproject.toml", &["[tool.ruff]", "[tool.ruff.lint]"][..]),
+            ("ruff.toml", &["", "[lint]"][..]),
+            (".ruff.toml", &["", "[lint]"][..]),
+        ]
+        .iter()
+        .filter_map(|(file, sections)| section_lines(&read(file)?, sections))
+        .any(|settings| selects_isort_rules(&settings));
+        if has_ruff && ruff_sorts_imports {
+            return Some(ImportSorter::Ruff);
+        }
+
+        has_isort.then_some(ImportSorter::Isort { profile: None })
+    }
+
     /// Check if the project has any linting tools available
     pub fn has_linter(&self) -> bool {
         !self.available_linters.is_empty()
@@ -270,11 +1140,19 @@ impl PythonLinter {
     }
 
     /// Get the arguments to run this linter with auto-fix on a specific file
-    pub fn fix_args(&self, file_path: &str) -> Vec<String> {
+    ///
+    /// Unsafe fixes are passed explicitly either way, so a project's own ruff setting
+    /// can't turn them on behind the hook's back
+    pub fn fix_args(&self, file_path: &str, unsafe_fixes: bool) -> Vec<String> {
         match self {
             PythonLinter::Ruff => vec![
                 "check".to_string(),
                 "--fix".to_string(),
+                if unsafe_fixes {
+                    "--unsafe-fixes".to_string()
+                } else {
+                    "--no-unsafe-fixes".to_string()
+                },
                 file_path.to_string(),
             ],
             PythonLinter::Flake8 => vec![], // Flake8 doesn't support auto-fix
@@ -291,10 +1169,16 @@ impl PythonLinter {
         }
     }
 
-    /// Get the arguments to run this linter on a specific file
+    /// Get the arguments to run this linter on a specific file (ruff reports JSON, which
+    /// `lint::render_ruff_json` turns back into concise diagnostics)
     pub fn file_args(&self, file_path: &str) -> Vec<String> {
         match self {
-            PythonLinter::Ruff => vec!["check".to_string(), file_path.to_string()],
+            PythonLinter::Ruff => vec![
+                "check".to_string(),
+                file_path.to_string(),
+                "--output-format".to_string(),
+                "json".to_string(),
+            ],
             PythonLinter::Flake8 => vec![file_path.to_string()],
             PythonLinter::Pylint => vec![file_path.to_string()],
         }
@@ -318,16 +1202,22 @@ impl PythonTester {
             PythonTester::Pytest => "pytest",
             PythonTester::PytestModule => "python",
             PythonTester::Unittest => "python",
+            PythonTester::NoxSession(_) => "nox",
+            PythonTester::ToxEnv(_) => "tox",
+            PythonTester::DjangoManage => "python",
         }
     }
 
     /// Get the arguments to run this tester
-    pub fn args(&self) -> Vec<&'static str> {
+    pub fn args(&self) -> Vec<&str> {
         match self {
             PythonTester::UvPytest => vec!["run", "pytest"],
             PythonTester::Pytest => vec![],
             PythonTester::PytestModule => vec!["-m", "pytest"],
             PythonTester::Unittest => vec!["-m", "unittest", "discover"],
+            PythonTester::NoxSession(session) => vec!["-s", session, "--"],
+            PythonTester::ToxEnv(env) => vec!["-e", env, "--"],
+            PythonTester::DjangoManage => vec!["manage.py", "test"],
         }
     }
 
@@ -338,6 +1228,167 @@ impl PythonTester {
             PythonTester::Pytest => "pytest",
             PythonTester::PytestModule => "python -m pytest",
             PythonTester::Unittest => "python -m unittest discover",
+            PythonTester::NoxSession(_) => "nox",
+            PythonTester::ToxEnv(_) => "tox",
+            PythonTester::DjangoManage => "python manage.py test",
+        }
+    }
+
+    /// Arguments making `command()` print the version of the runner behind it
+    pub fn version_args(&self) -> Vec<&str> {
+        match self {
+            PythonTester::PytestModule => vec!["-m", "pytest", "--version"],
+            _ => vec!["--version"],
+        }
+    }
+
+    /// Check if this tester runs pytest directly (so pytest plugin flags can be passed)
+    pub fn is_pytest(&self) -> bool {
+        matches!(
+            self,
+            PythonTester::UvPytest | PythonTester::Pytest | PythonTester::PytestModule
+        )
+    }
+
+    /// Get the full arguments to run a single test file with this tester
+    ///
+    /// unittest and `manage.py test` can't take a file path without scanning the whole
+    /// project, so the file is converted to its dotted module name relative to the root
+    pub fn test_file_args(&self, test_file: &Path, project_root: &Path) -> Vec<String> {
+        if matches!(self, PythonTester::Unittest | PythonTester::DjangoManage) {
+            if let Some(module) = dotted_module_name(test_file, project_root) {
+                let mut args: Vec<String> = match self {
+                    PythonTester::Unittest => vec!["-m".to_string(), "unittest".to_string()],
+                    _ => self.args().iter().map(|arg| arg.to_string()).collect(),
+                };
+                args.push(module);
+                return args;
+            }
+        }
+
+        let mut args: Vec<String> = self.args().iter().map(|arg| arg.to_string()).collect();
+        args.push(test_file.to_string_lossy().to_string());
+        args
+    }
+
+    /// Replace the nox session / tox env name; other testers are returned unchanged
+    pub fn with_session(&self, session: &str) -> Self {
+        match self {
+            PythonTester::NoxSession(_) => PythonTester::NoxSession(session.to_string()),
+            PythonTester::ToxEnv(_) => PythonTester::ToxEnv(session.to_string()),
+            other => other.clone(),
+        }
+    }
+}
+
+/// Convert a Python file path into a dotted module name relative to `project_root`,
+/// e.g. `tests/unit/test_module.py` becomes `tests.unit.test_module`
+pub fn dotted_module_name(file_path: &Path, project_root: &Path) -> Option<String> {
+    let relative = file_path.strip_prefix(project_root).unwrap_or(file_path);
+    if relative.is_absolute() {
+        return None;
+    }
+
+    let stem = relative.with_extension("");
+    let parts: Vec<&str> = stem
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+
+    if parts.is_empty() || parts.iter().any(|part| part.is_empty() || *part == "..") {
+        return None;
+    }
+
+    Some(parts.join("."))
+}
+
+/// Key lines of the given `[section]`s of a TOML/INI file (`""` is the top level), or
+/// `None` when none of them is present
+fn section_lines(content: &str, sections: &[&str]) -> Option<Vec<String>> {
+    let mut current = "";
+    let mut found = sections.contains(&"") && !content.trim().is_empty();
+    let mut lines = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') && line.ends_with(']') && !line.contains(',') {
+            current = line;
+            found |= sections.contains(&current);
+            continue;
+        }
+        if sections.contains(&current) && !line.is_empty() && !line.starts_with('#') {
+            lines.push(line.to_string());
+        }
+    }
+    found.then_some(lines)
+}
+
+/// Whether ruff's `select`/`extend-select` (possibly spanning lines) enables the isort
+/// rules, by `I`, an `I` code or `ALL`
+fn selects_isort_rules(settings: &[String]) -> bool {
+    let mut in_select = false;
+    settings.iter().any(|line| {
+        let value = match line.split_once('=') {
+            Some((key, value)) => {
+                in_select = matches!(key.trim(), "select" | "extend-select");
+                value
+            }
+            None => line,
+        };
+        let selects = in_select
+            && value
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .any(|code| {
+                    code == "ALL"
+                        || code
+                            .strip_prefix('I')
+                            .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit()))
+                });
+        if value.contains(']') {
+            in_select = false;
+        }
+        selects
+    })
+}
+
+impl ImportSorter {
+    /// Get the command to run this import sorter
+    pub fn command(&self) -> &'static str {
+        match self {
+            ImportSorter::Isort { .. } => "isort",
+            ImportSorter::Ruff => "ruff",
+        }
+    }
+
+    /// Get the arguments to sort the imports of a specific file; `default_profile` is
+    /// passed to isort when the project doesn't configure one
+    pub fn sort_args(&self, file_path: &str, default_profile: Option<&str>) -> Vec<String> {
+        match self {
+            ImportSorter::Isort { profile } => {
+                let mut args = Vec::new();
+                if let Some(profile) = profile.as_deref().or(default_profile) {
+                    args.push("--profile".to_string());
+                    args.push(profile.to_string());
+                }
+                args.push(file_path.to_string());
+                args
+            }
+            ImportSorter::Ruff => vec![
+                "check".to_string(),
+                "--select".to_string(),
+                "I".to_string(),
+                "--fix".to_string(),
+                file_path.to_string(),
+            ],
+        }
+    }
+
+    /// Get the human-readable name for messages
+    pub fn display_name(&self) -> String {
+        match self {
+            ImportSorter::Isort {
+                profile: Some(profile),
+            } => format!("isort, profile {profile}"),
+            ImportSorter::Isort { profile: None } => "isort".to_string(),
+            ImportSorter::Ruff => "ruff".to_string(),
         }
     }
 }
@@ -368,6 +1419,43 @@ impl PythonFormatter {
     }
 }
 
+impl PythonTypeChecker {
+    /// Get the command to run this type checker
+    pub fn command(self) -> &'static str {
+        match self {
+            PythonTypeChecker::Mypy => "mypy",
+            PythonTypeChecker::Pyright => "pyright",
+        }
+    }
+
+    /// Get the arguments to type check a specific file
+    pub fn file_args(self, file_path: &str) -> Vec<String> {
+        match self {
+            PythonTypeChecker::Mypy => vec![
+                "--show-column-numbers".to_string(),
+                "--no-error-summary".to_string(),
+                file_path.to_string(),
+            ],
+            PythonTypeChecker::Pyright => vec![file_path.to_string()],
+        }
+    }
+
+    /// The configured type checker when it's installed, otherwise the first one found
+    pub fn detect(
+        preferred: Option<PythonTypeChecker>,
+        environment: &PythonEnvironment,
+    ) -> Option<PythonTypeChecker> {
+        let installed =
+            |checker: &PythonTypeChecker| environment.find_tool(checker.command()).is_some();
+        match preferred {
+            Some(checker) => Some(checker).filter(installed),
+            None => [PythonTypeChecker::Mypy, PythonTypeChecker::Pyright]
+                .into_iter()
+                .find(installed),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -427,6 +1515,60 @@ mod tests {
         assert!(PythonProject::has_python_files(temp_dir.path(), 2));
     }
 
+    #[test]
+    fn test_search_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let names = vec!["test_app.py".to_string()];
+        for dir in [".tox/py311/tests", "build/lib/tests", "pkg/tests/unit"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("test_app.py"), "").unwrap();
+        }
+
+        let limits = SearchLimits::default();
+        assert!(limits.skips(&root.join(".tox")) && limits.skips(&root.join("build")));
+        assert!(!limits.skips(&root.join("pkg")));
+        assert_eq!(
+            limits.find_file(root, &names),
+            Some(root.join("pkg/tests/unit/test_app.py"))
+        );
+
+        let mut all = limits.find_files(root, &names);
+        all.sort();
+        assert_eq!(all, vec![root.join("pkg/tests/unit/test_app.py")]);
+
+        let mut dirs = limits.dirs(root);
+        dirs.sort();
+        let expected: Vec<PathBuf> = ["", "pkg", "pkg/tests", "pkg/tests/unit"]
+            .iter()
+            .map(|dir| root.join(dir))
+            .collect();
+        assert_eq!(dirs, expected);
+        assert!(limits.reaches(root, root) && limits.reaches(root, &root.join("pkg/tests")));
+        assert!(!limits.reaches(root, &root.join(".tox/py311")));
+        assert!(!limits.reaches(root, &root.join("build/lib")));
+        assert!(!limits.reaches(root, Path::new("/elsewhere")));
+
+        // Too deep to be found
+        let shallow = SearchLimits {
+            max_depth: 3,
+            ..SearchLimits::default()
+        };
+        assert_eq!(shallow.find_file(root, &names), None);
+        assert!(shallow.reaches(root, &root.join("pkg/tests")));
+        assert!(!shallow.reaches(root, &root.join("pkg/tests/unit")));
+
+        // A custom skip list replaces the defaults; hidden directories stay skipped
+        let custom = SearchLimits {
+            skip_dirs: vec!["pkg".to_string()],
+            max_depth: 4,
+        };
+        assert_eq!(
+            custom.find_file(root, &names),
+            Some(root.join("build/lib/tests/test_app.py"))
+        );
+    }
+
     #[test]
     fn test_is_python_project_root() {
         let temp_dir = TempDir::new().unwrap();
@@ -481,6 +1623,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_session_tester_commands() {
+        let nox = PythonTester::NoxSession(DEFAULT_NOX_SESSION.to_string());
+        assert_eq!(nox.command(), "nox");
+        assert_eq!(nox.args(), vec!["-s", "tests", "--"]);
+
+        let tox = PythonTester::ToxEnv(DEFAULT_TOX_ENV.to_string());
+        assert_eq!(tox.command(), "tox");
+        assert_eq!(tox.args(), vec!["-e", "py", "--"]);
+
+        assert_eq!(
+            nox.with_session("unit"),
+            PythonTester::NoxSession("unit".to_string())
+        );
+        assert_eq!(
+            tox.with_session("py312"),
+            PythonTester::ToxEnv("py312".to_string())
+        );
+        assert_eq!(
+            PythonTester::Pytest.with_session("unit"),
+            PythonTester::Pytest
+        );
+    }
+
+    #[test]
+    fn test_unittest_single_file_args() {
+        let root = Path::new("/project");
+        let test_file = root.join("tests/unit/test_module.py");
+
+        assert_eq!(
+            PythonTester::Unittest.test_file_args(&test_file, root),
+            vec!["-m", "unittest", "tests.unit.test_module"]
+        );
+        assert_eq!(
+            PythonTester::PytestModule.test_file_args(&test_file, root),
+            vec!["-m", "pytest", "/project/tests/unit/test_module.py"]
+        );
+
+        assert_eq!(
+            dotted_module_name(Path::new("test_app.py"), root),
+            Some("test_app".to_string())
+        );
+        assert_eq!(
+            dotted_module_name(Path::new("/elsewhere/test_app.py"), root),
+            None
+        );
+    }
+
     #[test]
     fn test_project_discovery() {
         let temp_dir = TempDir::new().unwrap();
@@ -497,4 +1687,440 @@ mod tests {
         assert_eq!(project.root, temp_dir.path());
         assert_eq!(project.project_type, ProjectType::Modern);
     }
+
+    #[test]
+    fn test_conda_environment_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        assert_eq!(
+            PythonEnvironment::detect_with(root, None, true),
+            PythonEnvironment::System
+        );
+
+        fs::write(
+            root.join("environment.yml"),
+            "name: analytics\ndependencies:\n  - python=3.11\n",
+        )
+        .unwrap();
+        let conda_run = PythonEnvironment::detect_with(root, None, true);
+        assert_eq!(
+            conda_run,
+            PythonEnvironment::CondaRun {
+                name: "analytics".to_string()
+            }
+        );
+        assert_eq!(
+            PythonEnvironment::detect_with(root, None, false),
+            PythonEnvironment::System
+        );
+
+        let (program, args) =
+            conda_run.command("python", vec!["-m".to_string(), "pytest".to_string()]);
+        assert_eq!(program, "conda");
+        assert_eq!(
+            args,
+            vec![
+                "run",
+                "-n",
+                "analytics",
+                "--no-capture-output",
+                "python",
+                "-m",
+                "pytest"
+            ]
+        );
+
+        // An activated env wins over environment.yml
+        assert_eq!(
+            PythonEnvironment::detect_with(root, Some(OsString::from("/opt/conda/envs/app")), true),
+            PythonEnvironment::Conda {
+                prefix: PathBuf::from("/opt/conda/envs/app")
+            }
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_conda_prefix_tools_resolved() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let bin = temp_dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+        let pytest = bin.join("pytest-guardrails-fake");
+        fs::write(&pytest, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&pytest, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let conda = PythonEnvironment::Conda {
+            prefix: temp_dir.path().to_path_buf(),
+        };
+        assert_eq!(
+            conda.find_tool("pytest-guardrails-fake"),
+            Some(pytest.clone())
+        );
+        assert_eq!(
+            conda.resolve("pytest-guardrails-fake"),
+            pytest.to_string_lossy()
+        );
+        // Tools the env doesn't have keep their bare name (PATH lookup at spawn time)
+        assert_eq!(conda.resolve("no-such-tool"), "no-such-tool");
+        assert_eq!(
+            PythonEnvironment::System.resolve("pytest-guardrails-fake"),
+            "pytest-guardrails-fake"
+        );
+    }
+
+    #[test]
+    fn test_python_version_detection() {
+        assert_eq!(
+            PythonVersion::parse("3.11.4"),
+            Some(PythonVersion {
+                major: 3,
+                minor: 11
+            })
+        );
+        assert_eq!(PythonVersion::parse("python3"), None);
+        assert_eq!(
+            requires_python("[project]\nrequires-python = \"<4,>=3.9\"\n").map(|v| v.to_string()),
+            Some("3.9".to_string())
+        );
+        assert_eq!(
+            requires_python("[tool.poetry.dependencies]\npython = \"^3.10\"\n")
+                .map(|v| v.to_string()),
+            Some("3.10".to_string())
+        );
+        assert_eq!(requires_python("[tool.other]\npython = \"3.12\"\n"), None);
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        assert_eq!(PythonVersion::detect(root), None);
+
+        fs::create_dir(root.join(".venv")).unwrap();
+        fs::write(
+            root.join(".venv/pyvenv.cfg"),
+            "home = /usr/bin\nversion = 3.12.1\n",
+        )
+        .unwrap();
+        assert_eq!(PythonVersion::detect(root).unwrap().to_string(), "3.12");
+
+        fs::write(root.join(".python-version"), "# pinned\n3.11.7\n").unwrap();
+        assert_eq!(PythonVersion::detect(root).unwrap().to_string(), "3.11");
+
+        fs::write(
+            root.join("pyproject.toml"),
+            "[project]\nname = \"app\"\nrequires-python = \">=3.9\"\n",
+        )
+        .unwrap();
+        let mut project = PythonProject::discover(root).unwrap();
+        assert_eq!(project.python_version.unwrap().ruff_target(), "py39");
+        assert_eq!(
+            project.target_version_args("ruff"),
+            vec!["--target-version", "py39"]
+        );
+        assert!(project.target_version_args("flake8").is_empty());
+
+        // An explicit ruff target-version is left to ruff
+        fs::write(root.join("ruff.toml"), "target-version = \"py310\"\n").unwrap();
+        assert!(project.target_version_args("ruff").is_empty());
+
+        // Versions ruff doesn't know aren't passed
+        fs::remove_file(root.join("ruff.toml")).unwrap();
+        project.python_version = Some(PythonVersion { major: 2, minor: 7 });
+        assert!(project.target_version_args("ruff").is_empty());
+    }
+
+    #[test]
+    fn test_django_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        assert_eq!(Framework::detect(root), None);
+
+        fs::write(
+            root.join("manage.py"),
+            "import os\n\ndef main():\n    os.environ.setdefault('DJANGO_SETTINGS_MODULE', 'mysite.settings')\n",
+        )
+        .unwrap();
+        let django = Framework::detect(root).unwrap();
+        assert_eq!(
+            django,
+            Framework::Django {
+                settings_module: Some("mysite.settings".to_string()),
+                pytest_django: false,
+            }
+        );
+        assert_eq!(django.describe(), "Django (settings: mysite.settings)");
+
+        let project = PythonProject::discover(root).unwrap();
+        assert!(project.is_django_migration(&root.join("polls/migrations/0001_initial.py")));
+        assert!(!project.is_django_migration(&root.join("polls/models.py")));
+        assert_eq!(
+            PythonTester::DjangoManage
+                .test_file_args(&root.join("polls/tests/test_models.py"), root),
+            vec!["manage.py", "test", "polls.tests.test_models"]
+        );
+        assert!(project.test_env(&PythonTester::DjangoManage).is_empty());
+
+        // pytest-django configuration means tests run through pytest with the settings set
+        fs::write(
+            root.join("pytest.ini"),
+            "[pytest]\nDJANGO_SETTINGS_MODULE = mysite.settings_test\n",
+        )
+        .unwrap();
+        let project = PythonProject::discover(root).unwrap();
+        assert_eq!(
+            project.framework,
+            Some(Framework::Django {
+                settings_module: Some("mysite.settings_test".to_string()),
+                pytest_django: true,
+            })
+        );
+        assert!(!project
+            .available_testers
+            .contains(&PythonTester::DjangoManage));
+        assert_eq!(
+            project.test_env(&PythonTester::Pytest),
+            vec![(
+                "DJANGO_SETTINGS_MODULE".to_string(),
+                "mysite.settings_test".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_web_framework_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+
+        // Extensions don't count as the framework itself
+        fs::write(root.join("requirements.txt"), "flask-sqlalchemy==3.1\n").unwrap();
+        assert_eq!(Framework::detect(root), None);
+
+        fs::write(
+            root.join("pyproject.toml"),
+            "[project]\ndependencies = [\"fastapi>=0.110\", \"uvicorn\"]\n",
+        )
+        .unwrap();
+        assert_eq!(Framework::detect(root), Some(Framework::FastApi));
+        assert_eq!(Framework::FastApi.describe(), "FastAPI");
+
+        fs::write(root.join("requirements.txt"), "Flask>=3\n").unwrap();
+        fs::remove_file(root.join("pyproject.toml")).unwrap();
+        assert_eq!(Framework::detect(root), Some(Framework::Flask));
+
+        assert_eq!(
+            Framework::from_imports("import os\nfrom flask import Flask\n"),
+            Some(Framework::Flask)
+        );
+        assert_eq!(
+            Framework::from_imports("import fastapi.testclient\n"),
+            Some(Framework::FastApi)
+        );
+        assert_eq!(Framework::from_imports("import flask_login\n"), None);
+    }
+
+    #[test]
+    fn test_source_kind_lint_commands() {
+        assert_eq!(
+            PythonSourceKind::of(Path::new("app/models.py")),
+            Some(PythonSourceKind::Module)
+        );
+        assert_eq!(
+            PythonSourceKind::of(Path::new("stubs/lib.pyi")),
+            Some(PythonSourceKind::Stub)
+        );
+        assert_eq!(
+            PythonSourceKind::of(Path::new("fast.pyx")),
+            Some(PythonSourceKind::Cython)
+        );
+        assert_eq!(PythonSourceKind::of(Path::new("README.md")), None);
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("pyproject.toml"), "[project]\n").unwrap();
+        let mut project = PythonProject::discover(temp_dir.path()).unwrap();
+        project.environment = PythonEnvironment::System;
+        project.available_linters = vec![PythonLinter::Ruff];
+
+        assert_eq!(
+            project.lint_command_for(Path::new("lib.pyi")),
+            Some((
+                "ruff".to_string(),
+                vec![
+                    "check".to_string(),
+                    "lib.pyi".to_string(),
+                    "--output-format".to_string(),
+                    "json".to_string(),
+                    "--extend-select".to_string(),
+                    "PYI".to_string(),
+                ]
+            ))
+        );
+        assert_eq!(
+            project.lint_command_for(Path::new("app.py")),
+            Some((
+                "ruff".to_string(),
+                vec![
+                    "check".to_string(),
+                    "app.py".to_string(),
+                    "--output-format".to_string(),
+                    "json".to_string(),
+                ]
+            ))
+        );
+        assert_eq!(project.lint_command_for(Path::new("notes.txt")), None);
+    }
+
+    #[test]
+    fn test_ruff_fix_args_pass_unsafe_fixes_explicitly() {
+        assert_eq!(
+            PythonLinter::Ruff.fix_args("app.py", false),
+            vec!["check", "--fix", "--no-unsafe-fixes", "app.py"]
+        );
+        assert_eq!(
+            PythonLinter::Ruff.fix_args("app.py", true),
+            vec!["check", "--fix", "--unsafe-fixes", "app.py"]
+        );
+        assert!(PythonLinter::Flake8.fix_args("app.py", true).is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_tool_versions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let prefix = temp_dir.path().join("env");
+        let bin = prefix.join("bin");
+        fs::create_dir_all(&bin).unwrap();
+        for (tool, script) in [
+            ("ruff", "#!/bin/sh\necho 'ruff 0.6.9'\n"),
+            (
+                "black",
+                "#!/bin/sh\nprintf '\\nblack, 24.8.0 (compiled: yes)\\nPython 3.12\\n'\n",
+            ),
+            ("pytest", "#!/bin/sh\necho 'pytest 8.3.2' >&2\n"),
+        ] {
+            fs::write(bin.join(tool), script).unwrap();
+            fs::set_permissions(bin.join(tool), fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        fs::write(temp_dir.path().join("pyproject.toml"), "[project]\n").unwrap();
+
+        let mut project = PythonProject::discover(temp_dir.path()).unwrap();
+        project.environment = PythonEnvironment::Conda { prefix };
+        project.available_linters = vec![PythonLinter::Ruff];
+        project.available_formatters = vec![PythonFormatter::Ruff, PythonFormatter::Black];
+        project.available_testers = vec![PythonTester::Pytest];
+
+        let versions: Vec<(&str, &str)> = project
+            .tool_versions()
+            .iter()
+            .map(|v| (v.tool.as_str(), v.version.as_str()))
+            .collect();
+        assert_eq!(
+            versions,
+            vec![("ruff", "ruff 0.6.9"), ("pytest", "pytest 8.3.2")]
+        );
+        assert_eq!(
+            project
+                .environment
+                .tool_version("black", &["--version"])
+                .as_deref(),
+            Some("black, 24.8.0 (compiled: yes)")
+        );
+        assert_eq!(project.environment.tool_version("missing-tool", &[]), None);
+    }
+
+    #[test]
+    fn test_type_checker_args() {
+        assert_eq!(
+            PythonTypeChecker::Mypy.file_args("app.py"),
+            vec!["--show-column-numbers", "--no-error-summary", "app.py"]
+        );
+        assert_eq!(
+            PythonTypeChecker::Pyright.file_args("app.py"),
+            vec!["app.py"]
+        );
+        let missing = PythonEnvironment::Conda {
+            prefix: PathBuf::from("/nonexistent/env"),
+        };
+        assert_eq!(
+            PythonTypeChecker::detect(Some(PythonTypeChecker::Pyright), &missing),
+            None
+        );
+    }
+
+    #[test]
+    fn test_import_sorter_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("pyproject.toml"),
+            "[project]\nname = \"app\"\n\n[tool.ruff.lint]\nselect = [\n    \"E\",\n    \"I\",\n]\n",
+        )
+        .unwrap();
+        let mut project = PythonProject::discover(root).unwrap();
+        project.environment = PythonEnvironment::System;
+        project.available_linters = vec![PythonLinter::Ruff];
+        assert_eq!(project.import_sorter(), Some(ImportSorter::Ruff));
+
+        project.available_linters = vec![PythonLinter::Flake8];
+        let expected = which("isort")
+            .is_ok()
+            .then_some(ImportSorter::Isort { profile: None });
+        assert_eq!(project.import_sorter(), expected);
+
+        let lines =
+            "[tool.isort]\nprofile = \"black\"\n\n[tool.ruff]\nextend-select = [\"I001\"]\n";
+        let isort = section_lines(lines, &["[tool.isort]"]).unwrap();
+        assert_eq!(isort, vec!["profile = \"black\""]);
+        assert!(selects_isort_rules(
+            &section_lines(lines, &["[tool.ruff]"]).unwrap()
+        ));
+        assert!(!selects_isort_rules(&[
+            "select = [\"E\", \"F\"]".to_string()
+        ]));
+        assert!(section_lines(lines, &["[isort]"]).is_none());
+
+        assert_eq!(
+            ImportSorter::Isort { profile: None }.sort_args("app.py", Some("black")),
+            vec!["--profile", "black", "app.py"]
+        );
+        assert_eq!(
+            ImportSorter::Isort {
+                profile: Some("google".to_string())
+            }
+            .sort_args("app.py", Some("black")),
+            vec!["--profile", "google", "app.py"]
+        );
+    }
+
+    #[test]
+    fn test_extensionless_python_scripts() {
+        assert!(is_python_source_header(
+            "#!/usr/bin/env python3\nimport sys\n"
+        ));
+        assert!(is_python_source_header("#!/usr/bin/env -S python3 -u\n"));
+        assert!(is_python_source_header("#!/usr/local/bin/python3.12\n"));
+        assert!(is_python_source_header(
+            "#!/bin/sh\n# -*- mode: python; coding: utf-8 -*-\n"
+        ));
+        assert!(is_python_source_header(
+            "print('hi')\n# vim: set ft=python :\n"
+        ));
+        assert!(!is_python_source_header("#!/bin/bash\necho python\n"));
+        assert!(!is_python_source_header("#!/usr/bin/env node\n"));
+
+        let temp_dir = TempDir::new().unwrap();
+        let script = temp_dir.path().join("manage");
+        fs::write(&script, "#!/usr/bin/env python3\nprint('hi')\n").unwrap();
+        assert_eq!(
+            PythonSourceKind::of(&script),
+            Some(PythonSourceKind::Module)
+        );
+
+        let shell = temp_dir.path().join("deploy");
+        fs::write(&shell, "#!/bin/sh\nexit 0\n").unwrap();
+        assert_eq!(PythonSourceKind::of(&shell), None);
+        assert_eq!(PythonSourceKind::of(&temp_dir.path().join("missing")), None);
+    }
 }
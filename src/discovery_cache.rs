@@ -0,0 +1,201 @@
+use crate::discovery::{PythonProject, ToolVersion};
+use crate::state::StateDir;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+/// State file holding the last discovered project
+pub const DISCOVERY_CACHE_FILE: &str = "discovery-cache.json";
+
+/// Files (relative to the project root) whose presence or content discovery depends on
+const MARKER_FILES: &[&str] = &[
+    "pyproject.toml",
+    "setup.py",
+    "setup.cfg",
+    "requirements.txt",
+    "requirements-dev.txt",
+    "requirements",
+    "Pipfile",
+    "poetry.lock",
+    "tox.ini",
+    "noxfile.py",
+    "manage.py",
+    "pytest.ini",
+    "environment.yml",
+    "environment.yaml",
+    ".python-version",
+    ".venv/pyvenv.cfg",
+    "venv/pyvenv.cfg",
+    ".venv/bin",
+    "venv/bin",
+    ".git",
+];
+
+/// Environment variables that change which tools and environment discovery finds
+const ENV_VARS: &[&str] = &[
+    "PATH",
+    "VIRTUAL_ENV",
+    "CONDA_PREFIX",
+    "DJANGO_SETTINGS_MODULE",
+];
+
+/// Modification time and size of a marker, or `None` when it doesn't exist
+type Stamp = Option<(SystemTime, u64)>;
+
+/// Everything a discovery result was derived from; a cached project is reused only
+/// while all of it is unchanged
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Fingerprint {
+    root: PathBuf,
+    markers: Vec<(String, Stamp)>,
+    env: Vec<(String, Option<OsString>)>,
+    /// PATH and conda bin directories, whose mtime changes when a tool is installed
+    bin_dirs: Vec<(PathBuf, Stamp)>,
+}
+
+impl Fingerprint {
+    fn capture(root: &Path) -> Self {
+        let markers = MARKER_FILES
+            .iter()
+            .map(|marker| (marker.to_string(), stamp(&root.join(marker))))
+            .collect();
+        let env = ENV_VARS
+            .iter()
+            .map(|var| (var.to_string(), std::env::var_os(var)))
+            .collect();
+
+        let mut dirs: Vec<PathBuf> = std::env::var_os("PATH")
+            .map(|path| std::env::split_paths(&path).collect())
+            .unwrap_or_default();
+        if let Some(prefix) = std::env::var_os("CONDA_PREFIX") {
+            let prefix = PathBuf::from(prefix);
+            dirs.extend([prefix.join("bin"), prefix.join("Scripts")]);
+        }
+        let bin_dirs = dirs
+            .into_iter()
+            .map(|dir| {
+                let stamp = stamp(&dir);
+                (dir, stamp)
+            })
+            .collect();
+
+        Self {
+            root: fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf()),
+            markers,
+            env,
+            bin_dirs,
+        }
+    }
+}
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Discovered project together with what it was discovered from
+#[derive(Serialize, Deserialize)]
+struct CachedDiscovery {
+    fingerprint: Fingerprint,
+    project: PythonProject,
+    tool_versions: Vec<ToolVersion>,
+}
+
+impl PythonProject {
+    /// Discover the project containing `start_dir`, reusing the result of a previous
+    /// invocation while the project's marker files, virtualenv, PATH directories and
+    /// environment variables are unchanged
+    ///
+    /// The cache lives in the project's state directory, so hooks skip re-probing tools
+    /// and re-reading manifests on every edit; a missing, stale or unreadable cache just
+    /// means a fresh discovery (and the cache rewritten).
+    pub fn discover_cached<P: AsRef<Path>>(start_dir: P) -> Result<Self> {
+        let root = Self::find_project_root(start_dir.as_ref())
+            .context("Failed to find Python project root")?;
+        let Ok(state) = StateDir::for_workspace(&root) else {
+            return Self::discover(&root);
+        };
+        let cache_path = state.file(DISCOVERY_CACHE_FILE);
+        let fingerprint = Fingerprint::capture(&root);
+
+        let cached = fs::read(&cache_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<CachedDiscovery>(&bytes).ok());
+        if let Some(cached) = cached.filter(|cached| cached.fingerprint == fingerprint) {
+            log::debug!(
+                "Using cached project discovery from {}",
+                cache_path.display()
+            );
+            let mut project = cached.project;
+            project.root = root;
+            project.tool_versions = OnceLock::from(cached.tool_versions);
+            return Ok(project);
+        }
+
+        let project = Self::discover(&root)?;
+        let cached = CachedDiscovery {
+            fingerprint,
+            tool_versions: project.tool_versions().to_vec(),
+            project,
+        };
+        let written = serde_json::to_vec(&cached)
+            .map_err(std::io::Error::other)
+            .and_then(|bytes| fs::write(&cache_path, bytes));
+        if let Err(e) = written {
+            log::debug!("Could not write discovery cache: {e}");
+        }
+        let mut project = cached.project;
+        project.tool_versions = OnceLock::from(cached.tool_versions);
+        Ok(project)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::ProjectType;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_discovery_cache_reused_until_markers_change() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(root.join("requirements.txt"), "requests\n")?;
+
+        let project = PythonProject::discover_cached(root)?;
+        assert_eq!(project.project_type, ProjectType::Simple);
+        let state = StateDir::for_workspace(root)?;
+        assert!(state.file(DISCOVERY_CACHE_FILE).exists());
+
+        // A matching cache entry is used without re-detecting
+        let cache = fs::read_to_string(state.file(DISCOVERY_CACHE_FILE))?;
+        fs::write(
+            state.file(DISCOVERY_CACHE_FILE),
+            cache.replace("\"Simple\"", "\"Git\""),
+        )?;
+        let project = PythonProject::discover_cached(root.join("src"))?;
+        assert_eq!(project.project_type, ProjectType::Git);
+        assert_eq!(project.root, root);
+
+        // Adding a marker file invalidates it
+        fs::write(root.join("setup.py"), "from setuptools import setup\n")?;
+        fs::File::options()
+            .write(true)
+            .open(root.join("setup.py"))?
+            .set_modified(SystemTime::now() + Duration::from_secs(10))?;
+        let project = PythonProject::discover_cached(root)?;
+        assert_eq!(project.project_type, ProjectType::Classical);
+
+        // A corrupt cache falls back to discovery
+        fs::write(state.file(DISCOVERY_CACHE_FILE), "not json")?;
+        let project = PythonProject::discover_cached(root)?;
+        assert_eq!(project.project_type, ProjectType::Classical);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// Failure categories of the library, for callers that need to tell them apart
+///
+/// The checker, config, state directory, locking and hook protocol APIs return this
+/// directly. `AutomationRunner` and the AI code deliberately stay on `anyhow`, since
+/// their pipelines wrap arbitrary tool and provider failures with context; the
+/// variants are raised inside those errors, so use
+/// `error.downcast_ref::<GuardrailsError>()` to recover the category there.
+#[derive(Debug)]
+pub enum GuardrailsError {
+    /// A config file or value could not be parsed
+    ConfigParse {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    /// A glob pattern in the config is invalid
+    InvalidPattern {
+        pattern: String,
+        source: globset::Error,
+    },
+    /// Reading or writing a file failed
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// An external tool (linter, tester, formatter...) is not installed
+    ToolNotFound { tool: String },
+    /// An external command or request ran out of time
+    Timeout { operation: String, seconds: u64 },
+    /// The AI provider failed or returned an unusable response
+    AiProvider(String),
+    /// AI requests are paused after repeated provider failures
+    AiUnavailable {
+        until: chrono::DateTime<chrono::Utc>,
+    },
+    /// Hook input is missing or malformed, or hook output could not be written
+    Protocol(String),
+}
+
+pub type GuardrailsResult<T> = std::result::Result<T, GuardrailsError>;
+
+impl GuardrailsError {
+    pub(crate) fn config_parse(path: Option<&std::path::Path>, message: impl fmt::Display) -> Self {
+        GuardrailsError::ConfigParse {
+            path: path.map(|path| path.to_path_buf()),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn io(path: &std::path::Path, source: std::io::Error) -> Self {
+        GuardrailsError::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+impl fmt::Display for GuardrailsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GuardrailsError::ConfigParse {
+                path: Some(path),
+                message,
+            } => write!(f, "Failed to parse {}: {message}", path.display()),
+            GuardrailsError::ConfigParse {
+                path: None,
+                message,
+            } => write!(f, "Failed to parse guardrails config: {message}"),
+            GuardrailsError::InvalidPattern { pattern, source } => {
+                write!(f, "Invalid glob pattern '{pattern}': {source}")
+            }
+            GuardrailsError::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            GuardrailsError::ToolNotFound { tool } => write!(f, "{tool} is not installed"),
+            GuardrailsError::Timeout { operation, seconds } => {
+                write!(f, "{operation} timed out after {seconds}s")
+            }
+            GuardrailsError::AiProvider(message) => write!(f, "AI provider error: {message}"),
+            GuardrailsError::AiUnavailable { until } => write!(
+                f,
+                "AI requests paused after repeated provider failures until {}",
+                until.format("%H:%M:%S UTC")
+            ),
+            GuardrailsError::Protocol(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for GuardrailsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GuardrailsError::InvalidPattern { source, .. } => Some(source),
+            GuardrailsError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{default_config, parse_file_size, GuardrailsChecker, GuardrailsConfig, OutputStyle};
+use arbitrary::{Arbitrary, Result, Unstructured};
+use std::path::{Path, PathBuf};
+
+/// Glob fragments generated patterns are built from, heavy on syntax globset has to
+/// reject or treat specially
+const GLOB_FRAGMENTS: &[&str] = &[
+    "*",
+    "**",
+    "**/",
+    "?",
+    "/",
+    ".",
+    "..",
+    "*.py",
+    "test_*",
+    "[a-z]",
+    "[!_]",
+    "[",
+    "]",
+    "{a,b}",
+    "{",
+    "}",
+    ",",
+    "\\",
+    "\\*",
+    "tests",
+    "src",
+    "__pycache__",
+    " ",
+    "é",
+    "\u{0}",
+    "\u{202e}",
+    "~",
+];
+
+/// Path components generated paths are built from
+const PATH_COMPONENTS: &[&str] = &[
+    "",
+    ".",
+    "..",
+    "/",
+    "//",
+    "src",
+    "tests",
+    "app.py",
+    "test_app.py",
+    "__init__.py",
+    ".hidden",
+    "a b",
+    "ünïcödé.py",
+    "*",
+    "[",
+    "{a,b}",
+    "\\",
+    "C:",
+    "~",
+    "\u{202e}yp.txt",
+    "\u{0}",
+    ".py",
+    "py.",
+];
+
+/// Units (valid, odd and bogus) appended to generated file sizes
+const SIZE_UNITS: &[&str] = &[
+    "", "B", "K", "KB", "kb", "KiB", "M", "MB", "MiB", "G", "GB", "GiB", "TB", "XB", " MB ", "M B",
+    "e3", "E", ".",
+];
+
+/// Directory generated paths are checked under for the file-based rules; it doesn't
+/// exist, so no lookup (`..` included) reaches real files such as `/dev/zero`
+const FUZZ_ROOT: &str = "/nonexistent-guardrails-fuzz-root";
+
+/// Glob-like pattern: mostly pattern syntax, sometimes raw text
+pub fn arbitrary_pattern(u: &mut Unstructured) -> Result<String> {
+    if u.ratio(1, 8)? {
+        return u.arbitrary();
+    }
+    let mut pattern = String::new();
+    for _ in 0..u.int_in_range(0..=6)? {
+        pattern.push_str(u.choose(GLOB_FRAGMENTS)?);
+    }
+    Ok(pattern)
+}
+
+/// Path with traversal, empty, unicode, glob-syntax and overlong components
+pub fn arbitrary_path(u: &mut Unstructured) -> Result<PathBuf> {
+    if u.ratio(1, 8)? {
+        return Ok(PathBuf::from(u.arbitrary::<String>()?));
+    }
+    let mut path = String::new();
+    for i in 0..u.int_in_range(0..=8)? {
+        if i > 0 {
+            path.push('/');
+        }
+        if u.ratio(1, 16)? {
+            path.push_str(&"x".repeat(u.int_in_range(200..=5000)?));
+        } else {
+            path.push_str(u.choose(PATH_COMPONENTS)?);
+        }
+    }
+    Ok(PathBuf::from(path))
+}
+
+/// `rules.max_file_size` value: a number (NaN, infinite and huge ones included) with a
+/// known or bogus unit, or raw text
+pub fn arbitrary_file_size(u: &mut Unstructured) -> Result<String> {
+    Ok(match u.int_in_range(0..=3)? {
+        0 => u.arbitrary()?,
+        1 => format!("{}{}", u.arbitrary::<u64>()?, u.choose(SIZE_UNITS)?),
+        2 => format!("{}{}", u.arbitrary::<f64>()?, u.choose(SIZE_UNITS)?),
+        _ => format!(
+            "{}{}{}",
+            u.choose(&["", " ", "+", "-", "--", "."])?,
+            u.int_in_range(0..=4096u32)?,
+            u.choose(SIZE_UNITS)?
+        ),
+    })
+}
+
+/// Default config with generated exclusion, skip and protect patterns, size limit and
+/// rules; it may well be rejected by [`GuardrailsChecker::from_config`]
+pub fn arbitrary_config(u: &mut Unstructured) -> Result<GuardrailsConfig> {
+    let mut config = default_config();
+    config.exclude.patterns = arbitrary_patterns(u)?;
+    config.exclude.python.lint_skip = arbitrary_patterns(u)?;
+    config.exclude.python.test_skip = arbitrary_patterns(u)?;
+    config.protect.patterns = arbitrary_patterns(u)?;
+    config.rules.max_file_size = arbitrary_file_size(u)?;
+    config.rules.skip_binary_files = u.arbitrary()?;
+    config.rules.binary_sample_bytes = u.arbitrary()?;
+    config.rules.skip_generated_files = u.arbitrary()?;
+    config.rules.skip_vendored = u.arbitrary()?;
+    config.rules.max_line_length = u.arbitrary()?;
+    config.output.style =
+        *u.choose(&[OutputStyle::Emoji, OutputStyle::Ascii, OutputStyle::Minimal])?;
+    Ok(config)
+}
+
+fn arbitrary_patterns(u: &mut Unstructured) -> Result<Vec<String>> {
+    let count = u.int_in_range(0..=4)?;
+    (0..count).map(|_| arbitrary_pattern(u)).collect()
+}
+
+impl<'a> Arbitrary<'a> for GuardrailsConfig {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_config(u)
+    }
+}
+
+/// Fuzz target `config_yaml`: load bytes as a config file and, when it loads, check a
+/// few paths against it
+pub fn fuzz_config_yaml(data: &[u8]) {
+    let Ok(yaml) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(checker) = GuardrailsChecker::from_yaml(yaml) {
+        for path in ["app.py", "tests/test_app.py", "../setup.py", ""] {
+            check_path(&checker, Path::new(path));
+        }
+    }
+}
+
+/// Fuzz target `file_size`: parse a generated `rules.max_file_size`, which must either
+/// fail or stay within the 1 TiB maximum
+pub fn fuzz_file_size(data: &[u8]) {
+    let Ok(size) = arbitrary_file_size(&mut Unstructured::new(data)) else {
+        return;
+    };
+    if let Ok(bytes) = parse_file_size(&size) {
+        assert!(bytes <= 1 << 40, "{size:?} parsed to {bytes} bytes");
+    }
+}
+
+/// Fuzz target `path_matching`: check generated paths against a generated config
+pub fn fuzz_path_matching(data: &[u8]) {
+    let mut u = Unstructured::new(data);
+    let Ok(config) = arbitrary_config(&mut u) else {
+        return;
+    };
+    let Ok(checker) = GuardrailsChecker::from_config(config) else {
+        return;
+    };
+    for _ in 0..8 {
+        match arbitrary_path(&mut u) {
+            Ok(path) => check_path(&checker, &path),
+            Err(_) => break,
+        }
+        if u.is_empty() {
+            break;
+        }
+    }
+}
+
+/// Run every exclusion and protection check on a path; the file-based rules only ever
+/// see it under [`FUZZ_ROOT`]
+fn check_path(checker: &GuardrailsChecker, path: &Path) {
+    let root = Path::new(FUZZ_ROOT);
+    let rooted = root.join(path.strip_prefix("/").unwrap_or(path));
+
+    let _ = checker.is_protected(path);
+    let _ = checker.is_protected_in(path, root);
+    let _ = checker.is_protected_in(&rooted, root);
+    let _ = checker.check(&rooted);
+    let _ = checker.check_lint(&rooted);
+    let _ = checker.check_test(&rooted);
+    let _ = checker.exceeds_max_line_length(&rooted);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift byte buffers standing in for fuzzer input
+    fn generated_inputs(count: usize) -> impl Iterator<Item = Vec<u8>> {
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        (0..count).map(move |_| {
+            let len = (next() % 512) as usize;
+            (0..len).map(|_| next() as u8).collect()
+        })
+    }
+
+    #[test]
+    fn test_harnesses_survive_generated_input() {
+        for data in generated_inputs(1500) {
+            fuzz_config_yaml(&data);
+            fuzz_file_size(&data);
+            fuzz_path_matching(&data);
+        }
+    }
+
+    #[test]
+    fn test_harnesses_survive_hostile_seeds() {
+        let deep_nesting = "[".repeat(10_000);
+        let seeds = [
+            "",
+            "exclude:",
+            "exclude: &a [*a]",
+            "exclude:\n  patterns: ['[', '{a,', '**/***', '\\\\']",
+            "exclude:\n  patterns: []\nrules:\n  max_file_size: \"NaN GB\"",
+            "exclude:\n  patterns: []\nrules:\n  max_file_size: 1e308",
+            "exclude:\n  patterns: []\nrules:\n  binary_sample_bytes: 18446744073709551615",
+            "exclude:\n  patterns: []\noutput:\n  templates:\n    lint_failure: '{{{{'",
+            &deep_nesting,
+        ];
+        for seed in seeds {
+            fuzz_config_yaml(seed.as_bytes());
+        }
+
+        for size in [
+            "inf",
+            "-0",
+            "1e308 GB",
+            "18446744073709551616",
+            "0x10",
+            "١٢ MB",
+            "\0",
+        ] {
+            if let Ok(bytes) = parse_file_size(size) {
+                assert!(bytes <= 1 << 40);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_configs_roundtrip_yaml() {
+        for data in generated_inputs(300) {
+            let config = arbitrary_config(&mut Unstructured::new(&data)).unwrap();
+            let yaml = serde_yaml::to_string(&config).unwrap();
+            let reparsed: GuardrailsConfig = serde_yaml::from_str(&yaml).unwrap();
+            assert_eq!(
+                serde_json::to_value(&reparsed).unwrap(),
+                serde_json::to_value(&config).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use crate::automation::AutomationResult;
+use crate::state::StateDir;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Maximum number of entries kept per workspace
+const MAX_ENTRIES: usize = 50;
+
+/// Outcome of a lint/test run recorded in the history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// "lint" or "test"
+    pub operation: String,
+    /// File the run was triggered for
+    pub file: PathBuf,
+    /// Claude Code session the run belonged to
+    #[serde(default)]
+    pub session_id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub success: bool,
+    /// Message shown to Claude for the run
+    pub message: String,
+    /// Wall-clock time the run took
+    #[serde(default)]
+    pub duration_ms: u64,
+    /// Identity of a failure independent of timings and AI wording: the failed test node
+    /// ids for test runs, the findings' rule+file fingerprints for lint runs
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+impl HistoryEntry {
+    /// What makes two failures "the same": the signature when the run had one, else the
+    /// message with numbers (durations, counts, line numbers) masked
+    fn failure_key(&self) -> String {
+        static NUMBERS: OnceLock<Regex> = OnceLock::new();
+        match &self.signature {
+            Some(signature) => signature.clone(),
+            None => NUMBERS
+                .get_or_init(|| Regex::new(r"[0-9]+").expect("valid number regex"))
+                .replace_all(&self.message, "#")
+                .into_owned(),
+        }
+    }
+}
+
+/// Per-workspace run history, stored in the project's state directory
+pub struct History {
+    path: PathBuf,
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Load the history for a workspace (empty when none was recorded yet)
+    pub fn load(workspace_dir: &Path) -> Result<Self> {
+        let path = StateDir::for_workspace(workspace_dir)?.file("history.json");
+        Ok(Self::load_from(path))
+    }
+
+    fn load_from(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    /// Append the outcome of a lint/test run, with its failure signature (see
+    /// [`HistoryEntry::signature`]); skipped and no-op runs are not recorded
+    pub fn record(
+        &mut self,
+        operation: &str,
+        file: &Path,
+        session_id: Option<&str>,
+        result: &AutomationResult,
+        duration: Duration,
+        signature: Option<String>,
+    ) -> Result<()> {
+        let (success, message) = match result {
+            AutomationResult::Success(message) | AutomationResult::Advice(message) => {
+                (true, message)
+            }
+            AutomationResult::Failure(message) => (false, message),
+            AutomationResult::NoAction | AutomationResult::Skipped => return Ok(()),
+        };
+
+        self.entries.push(HistoryEntry {
+            operation: operation.to_string(),
+            file: file.to_path_buf(),
+            session_id: session_id.map(str::to_string),
+            timestamp: Utc::now(),
+            success,
+            message: message.clone(),
+            duration_ms: duration.as_millis() as u64,
+            signature,
+        });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+
+        let content = serde_json::to_string(&self.entries)?;
+        fs::write(&self.path, content).context("Failed to write history file")
+    }
+
+    /// Number of consecutive identical failures (by signature), ending with the latest run,
+    /// for an operation on a file within a session
+    pub fn repeated_failures(&self, session_id: &str, operation: &str, file: &Path) -> usize {
+        let mut runs = self.entries.iter().rev().filter(|entry| {
+            entry.operation == operation
+                && entry.file == file
+                && entry.session_id.as_deref() == Some(session_id)
+        });
+
+        let Some(latest) = runs.next().filter(|entry| !entry.success) else {
+            return 0;
+        };
+        1 + runs
+            .take_while(|entry| !entry.success && entry.failure_key() == latest.failure_key())
+            .count()
+    }
+
+    /// All entries, oldest first
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Most recent entry for an operation on a file, if that run failed
+    pub fn last_failure(&self, operation: &str, file: &Path) -> Option<&HistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.operation == operation && entry.file == file)
+            .filter(|entry| !entry.success)
+    }
+
+    /// Most recent entry for an operation
+    pub fn last(&self, operation: &str) -> Option<&HistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| entry.operation == operation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_last() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("history.json");
+        let file = Path::new("src/app.py");
+
+        let mut history = History::load_from(path.clone());
+        assert!(history.last("lint").is_none());
+
+        history.record(
+            "lint",
+            file,
+            None,
+            &AutomationResult::Failure("E501".to_string()),
+            Duration::from_millis(120),
+            None,
+        )?;
+        history.record(
+            "test",
+            file,
+            None,
+            &AutomationResult::Success("ok".to_string()),
+            Duration::ZERO,
+            None,
+        )?;
+        history.record(
+            "lint",
+            file,
+            None,
+            &AutomationResult::Skipped,
+            Duration::ZERO,
+            None,
+        )?;
+
+        let reloaded = History::load_from(path);
+        let lint = reloaded.last("lint").unwrap();
+        assert!(!lint.success);
+        assert_eq!(lint.message, "E501");
+        assert_eq!(lint.duration_ms, 120);
+        assert!(reloaded.last("test").unwrap().success);
+        assert_eq!(reloaded.entries.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repeated_failures() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut history = History::load_from(temp_dir.path().join("history.json"));
+        let file = Path::new("src/app.py");
+        let failure = AutomationResult::Failure("test_add failed".to_string());
+
+        history.record("test", file, Some("s1"), &failure, Duration::ZERO, None)?;
+        history.record("test", file, Some("s1"), &failure, Duration::ZERO, None)?;
+        // Other sessions, files and operations don't count
+        history.record("test", file, Some("s2"), &failure, Duration::ZERO, None)?;
+        history.record(
+            "test",
+            Path::new("src/other.py"),
+            Some("s1"),
+            &failure,
+            Duration::ZERO,
+            None,
+        )?;
+        history.record("lint", file, Some("s1"), &failure, Duration::ZERO, None)?;
+        assert_eq!(history.repeated_failures("s1", "test", file), 2);
+
+        // A different failure message restarts the streak
+        let other = AutomationResult::Failure("test_sub failed".to_string());
+        history.record("test", file, Some("s1"), &other, Duration::ZERO, None)?;
+        assert_eq!(history.repeated_failures("s1", "test", file), 1);
+
+        // Same failed tests with different timings and AI wording still repeat
+        let failed = || Some("tests/test_app.py::test_add".to_string());
+        for message in [
+            "1 failed in 0.41s: off by one",
+            "1 failed in 0.57s: wrong sum",
+        ] {
+            let failure = AutomationResult::Failure(message.to_string());
+            history.record("test", file, Some("s1"), &failure, Duration::ZERO, failed())?;
+        }
+        assert_eq!(history.repeated_failures("s1", "test", file), 2);
+        history.record(
+            "test",
+            file,
+            Some("s1"),
+            &AutomationResult::Failure("1 failed in 0.41s: off by one".to_string()),
+            Duration::ZERO,
+            Some("tests/test_app.py::test_sub".to_string()),
+        )?;
+        assert_eq!(history.repeated_failures("s1", "test", file), 1);
+
+        // Without a signature, numbers in the message don't matter
+        for message in ["2 failed in 0.4s", "2 failed in 1.9s"] {
+            let failure = AutomationResult::Failure(message.to_string());
+            history.record("test", file, Some("s1"), &failure, Duration::ZERO, None)?;
+        }
+        assert_eq!(history.repeated_failures("s1", "test", file), 2);
+
+        history.record(
+            "test",
+            file,
+            Some("s1"),
+            &AutomationResult::Success("ok".to_string()),
+            Duration::ZERO,
+            None,
+        )?;
+        assert_eq!(history.repeated_failures("s1", "test", file), 0);
+
+        Ok(())
+    }
+}
@@ -1,21 +1,104 @@
-use anyhow::{Context, Result};
 use globset::{Glob, GlobSetBuilder};
+use lru::LruCache;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 // New modules for automation functionality
+pub mod audit;
+pub mod audit_log;
 pub mod automation;
+pub mod automation_report;
+pub mod backup;
+pub mod budget;
 pub mod cerebras;
+pub mod checks;
+pub mod circuit;
+pub mod config_cache;
+pub mod crash;
 pub mod discovery;
+pub mod discovery_cache;
+pub mod error;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzzing;
+pub mod history;
+pub mod lint;
 pub mod locking;
+mod lru;
+pub mod messages;
+pub mod mock;
+pub mod mutation;
+pub mod patch;
+pub mod path_scope;
+pub mod preview;
+pub mod prompt_context;
 pub mod protocol;
+pub mod pytest;
+pub mod recorder;
+pub mod redact;
+pub mod reload;
+pub mod removal;
+pub mod report;
+pub mod result_cache;
+pub mod session_edits;
+pub mod shell;
+pub mod snapshot;
+pub mod state;
+pub mod suppressions;
+pub mod test_map;
+pub mod testgen;
+pub mod toolchain;
+pub mod update;
+pub mod vendored;
+pub mod watch;
 
 // Re-export commonly used types for convenience
+pub use audit_log::{AuditLog, AuditRecord};
 pub use automation::{AutomationConfig, AutomationResult, AutomationRunner};
-pub use cerebras::{CerebrasConfig, ExclusionAnalysis, SmartExclusionAnalyzer};
-pub use discovery::{ProjectType, PythonLinter, PythonProject, PythonTester};
-pub use locking::{LockGuard, ProcessLock};
-pub use protocol::{HookInput, HookResponse};
+pub use automation_report::{AiAnalysisReport, AutomationReport, StepReport};
+pub use backup::FileBackup;
+pub use cerebras::{
+    heuristic_analysis, AiMode, AiProvider, AnalysisKind, CerebrasConfig, ExclusionAnalysis,
+    SmartExclusionAnalyzer,
+};
+pub use circuit::CircuitBreaker;
+pub use discovery::{
+    Framework, ImportSorter, ProjectType, PythonEnvironment, PythonFormatter, PythonLinter,
+    PythonProject, PythonSourceKind, PythonTester, PythonTypeChecker, PythonVersion, SearchLimits,
+    ToolVersion,
+};
+pub use error::{GuardrailsError, GuardrailsResult};
+pub use history::{History, HistoryEntry};
+pub use lint::{
+    filter_ignored_rules, parse_findings, FilteredLintOutput, LintFinding, LintOutputFormat,
+};
+pub use locking::{CooldownScope, LockGuard, LockStatus, ProcessLock};
+pub use messages::{MessageTemplates, OutputStyle};
+pub use mutation::{MutationTool, SurvivingMutant};
+pub use patch::{apply_unified_diff, ProposedFix};
+pub use path_scope::PathScope;
+pub use preview::{FilePreview, OperationPreview};
+pub use protocol::{HookInput, HookInputBuilder, HookOutput, HookResponse};
+pub use pytest::{Outcome, PytestResults, TestCase};
+pub use recorder::{HookRecorder, RecordedEvent, RECORD_ENV};
+pub use redact::Redactor;
+pub use reload::ReloadableChecker;
+pub use removal::RemovedModule;
+pub use report::{ReportFormat, RunReport};
+pub use result_cache::{CachedVerdict, ResultCache};
+pub use session_edits::SessionEdits;
+pub use shell::{CommandInspector, CommandPolicyConfig};
+pub use snapshot::{AutoUpdateSnapshots, SnapshotPlugin};
+pub use state::StateDir;
+pub use suppressions::{Suppression, Suppressions};
+pub use test_map::TestFileMap;
+pub use testgen::{GeneratedTests, ProposedTest, TestFramework};
+pub use toolchain::{Language, LanguageToolchain, ToolCommand};
+pub use update::{Release, ReleaseClient};
+pub use watch::FileWatcher;
 
 /// Main configuration structure for guardrails
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,6 +108,293 @@ pub struct GuardrailsConfig {
     pub rules: RulesConfig,
     #[serde(default)]
     pub automation: AutomationYamlConfig,
+    #[serde(default)]
+    pub protect: ProtectConfig,
+    /// Bash command policies (enforced by the PreToolUse `check-command` hook)
+    #[serde(default)]
+    pub commands: CommandPolicyConfig,
+    /// AI analysis provider settings
+    #[serde(default)]
+    pub ai: AiConfig,
+    /// How hook messages are presented
+    #[serde(default)]
+    pub output: OutputConfig,
+    /// Binary version management (`self-update`)
+    #[serde(default)]
+    pub update: UpdateConfig,
+    /// `rules` and `automation` settings for files matching a glob, e.g. no tests for
+    /// `scripts/**`
+    #[serde(default)]
+    pub overrides: Vec<ConfigOverride>,
+}
+
+/// Binary version management (`self-update`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateConfig {
+    /// Version the project expects, e.g. `1.2.0`; `self-update` installs it instead of
+    /// the latest release
+    #[serde(default)]
+    pub pin: Option<String>,
+}
+
+/// Presentation of hook messages
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutputConfig {
+    /// `emoji` (default), `ascii` for terminals and logs that mangle emoji, or `minimal`
+    #[serde(default)]
+    pub style: OutputStyle,
+    /// Custom lint/test hook messages
+    #[serde(default)]
+    pub templates: MessageTemplates,
+}
+
+/// AI analysis provider settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AiConfig {
+    /// `online` (Cerebras API, needs CEREBRAS_API_KEY) or `offline` (local model server)
+    #[serde(default)]
+    pub mode: AiMode,
+    /// Provider override: `cerebras`, `ollama`, `llamacpp` or `mock`
+    #[serde(default)]
+    pub provider: Option<AiProvider>,
+    /// Server URL (defaults per provider)
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Model name (defaults per provider)
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Models for individual analysis types, falling back to `model`
+    #[serde(default)]
+    pub models: AiModels,
+    /// Sampling temperature for every request (defaults per analysis type)
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling `top_p` for every request (defaults per analysis type)
+    #[serde(default)]
+    pub top_p: Option<f32>,
+    /// Give up on a provider request after this many seconds and fall back to basic
+    /// analysis (default 30, keeping hooks inside Claude Code's own timeout)
+    #[serde(default)]
+    pub request_timeout_seconds: Option<u64>,
+    /// Pause AI requests after repeated provider failures
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// Content masked in prompts before they are sent
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    /// Globs of files never sent to the provider; they always get heuristic analysis
+    #[serde(default)]
+    pub never_send: Vec<String>,
+    /// Record every request in the state directory's `ai-audit.jsonl` (see `ai audit`)
+    #[serde(default)]
+    pub audit_log: bool,
+    /// Double-check decisions that skip checks before acting on them
+    #[serde(default)]
+    pub second_opinion: SecondOpinionConfig,
+    /// Send edited files whole instead of their header and diff against HEAD
+    #[serde(default)]
+    pub whole_file_prompts: bool,
+    /// Analyses reporting a lower confidence (0.0-1.0) fall back to basic analysis
+    /// instead of trusting low-confidence exclusions or false-positive classifications
+    #[serde(default)]
+    pub min_confidence: Option<f32>,
+    /// Truncate prompts longer than this many characters (local providers default to 12000)
+    #[serde(default)]
+    pub max_prompt_chars: Option<usize>,
+    /// Estimated-token budget per analysis type; over-budget content is condensed
+    /// (signatures only, passing tests dropped) before the prompt is truncated
+    #[serde(default)]
+    pub max_prompt_tokens: PromptTokenBudgets,
+    /// Directory with canned `exclusion.json`/`lint.json`/`test.json` for the mock provider
+    #[serde(default)]
+    pub mock_responses: Option<PathBuf>,
+}
+
+/// Per-analysis model overrides, e.g. a small fast model for lint triage
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AiModels {
+    /// Model for file exclusion analysis
+    #[serde(default)]
+    pub exclusion: Option<String>,
+    /// Model for filtering lint output
+    #[serde(default)]
+    pub lint: Option<String>,
+    /// Model for test failure and coverage analysis
+    #[serde(default)]
+    pub test: Option<String>,
+}
+
+/// Prompt token budgets per analysis type (`ai.max_prompt_tokens`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromptTokenBudgets {
+    #[serde(default)]
+    pub exclusion: Option<usize>,
+    #[serde(default)]
+    pub lint: Option<usize>,
+    #[serde(default)]
+    pub test: Option<usize>,
+}
+
+/// When repeated provider failures pause AI requests (`ai.circuit_breaker`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that trip the breaker (0 disables it)
+    #[serde(default = "default_breaker_failures")]
+    pub failures: u32,
+    /// Seconds AI requests stay paused once tripped
+    #[serde(default = "default_breaker_cooldown_seconds")]
+    pub cooldown_seconds: u64,
+}
+
+fn default_breaker_failures() -> u32 {
+    3
+}
+
+fn default_breaker_cooldown_seconds() -> u64 {
+    300
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failures: default_breaker_failures(),
+            cooldown_seconds: default_breaker_cooldown_seconds(),
+        }
+    }
+}
+
+/// Second AI call confirming destructive decisions (`ai.second_opinion`): excluding a
+/// file from checks, or classifying every lint finding as a false positive
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecondOpinionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Model asked for the second opinion (defaults to the one that made the decision)
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// What to mask in prompts before they leave the machine (`ai.redaction`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// API keys, tokens, private keys and values assigned to secret-looking names
+    #[serde(default = "default_true")]
+    pub secrets: bool,
+    /// Email addresses
+    #[serde(default = "default_true")]
+    pub emails: bool,
+    /// Internal domains whose hostnames are masked, e.g. `corp.example.com`
+    #[serde(default)]
+    pub hostnames: Vec<String>,
+    /// Extra regexes to mask
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            secrets: true,
+            emails: true,
+            hostnames: Vec::new(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+/// Project configuration file, looked up from the edited file up to its project root
+pub const CONFIG_FILE_NAME: &str = "guardrails.yaml";
+
+impl GuardrailsConfig {
+    /// Load configuration from a YAML file
+    pub fn from_file<P: AsRef<Path>>(config_path: P) -> GuardrailsResult<Self> {
+        let config_path = config_path.as_ref();
+        let content = std::fs::read_to_string(config_path)
+            .map_err(|e| GuardrailsError::io(config_path, e))?;
+        serde_yaml::from_str(&content)
+            .map_err(|e| GuardrailsError::config_parse(Some(config_path), e))
+    }
+
+    /// Load `dir/guardrails.yaml`, or the built-in defaults when there is none
+    pub fn load_from_dir(dir: &Path) -> GuardrailsResult<Self> {
+        let config_path = dir.join(CONFIG_FILE_NAME);
+        if config_path.exists() {
+            Self::from_file(config_path)
+        } else {
+            Ok(default_config())
+        }
+    }
+}
+
+/// ESLint-style override block (`overrides`): settings for the files matching `files`
+///
+/// `rules` and `automation` hold any keys of the top-level blocks and are merged over
+/// them (nested mappings key by key). Every block matching a file applies, in file
+/// order, so later blocks win.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigOverride {
+    /// Glob or list of globs, matched against paths as given and relative to the
+    /// working directory (where guardrails.yaml is read from)
+    #[serde(deserialize_with = "one_or_many")]
+    pub files: Vec<String>,
+    #[serde(default)]
+    pub rules: serde_yaml::Mapping,
+    #[serde(default)]
+    pub automation: serde_yaml::Mapping,
+}
+
+/// A single string or a list of strings
+fn one_or_many<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+/// `base` with `overlay`'s keys merged in; mappings merge recursively, anything else
+/// is replaced
+fn merge_yaml(base: &mut serde_yaml::Value, overlay: &serde_yaml::Mapping) {
+    let serde_yaml::Value::Mapping(base) = base else {
+        *base = serde_yaml::Value::Mapping(overlay.clone());
+        return;
+    };
+    for (key, value) in overlay {
+        match (base.get_mut(key), value) {
+            (
+                Some(existing @ serde_yaml::Value::Mapping(_)),
+                serde_yaml::Value::Mapping(nested),
+            ) => merge_yaml(existing, nested),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// A config block with override blocks' keys merged in
+fn apply_overrides<'a, T: Serialize + serde::de::DeserializeOwned>(
+    base: &T,
+    overlays: impl IntoIterator<Item = &'a serde_yaml::Mapping>,
+) -> Result<T, serde_yaml::Error> {
+    let mut value = serde_yaml::to_value(base)?;
+    for overlay in overlays {
+        merge_yaml(&mut value, overlay);
+    }
+    serde_yaml::from_value(value)
+}
+
+/// Paths Claude must not edit (enforced by the PreToolUse `protect` hook)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProtectConfig {
+    /// Glob patterns of protected paths
+    #[serde(default)]
+    pub patterns: Vec<String>,
 }
 
 /// Exclusion configuration
@@ -49,7 +419,7 @@ pub struct PythonExclusions {
 }
 
 /// Additional rules configuration
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RulesConfig {
     /// Maximum file size to process
     #[serde(default = "default_max_file_size")]
@@ -57,9 +427,36 @@ pub struct RulesConfig {
     /// Skip binary files
     #[serde(default = "default_true")]
     pub skip_binary_files: bool,
+    /// Number of leading bytes inspected when sniffing for binary content
+    #[serde(default = "default_binary_sample_bytes")]
+    pub binary_sample_bytes: usize,
     /// Skip generated files
     #[serde(default = "default_true")]
     pub skip_generated_files: bool,
+    /// Skip vendored third-party code (`vendor/`, `third_party/`, `_vendor/`, or a
+    /// directory with its own LICENSE that doesn't import the project)
+    #[serde(default = "default_true")]
+    pub skip_vendored: bool,
+    /// Exclude files from linting and AI analysis when any line is longer than this
+    /// (catches minified/bundled artifacts); 0 disables the rule
+    #[serde(default = "default_max_line_length")]
+    pub max_line_length: usize,
+    /// Required license header for matching files
+    #[serde(default)]
+    pub license_header: Option<LicenseHeaderConfig>,
+}
+
+/// License header enforcement rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicenseHeaderConfig {
+    /// Header text files must start with; `{year}` matches any four-digit year
+    pub template: String,
+    /// Files the header is required for (glob patterns)
+    #[serde(default = "default_license_header_globs")]
+    pub globs: Vec<String>,
+    /// Insert the header automatically instead of blocking
+    #[serde(default)]
+    pub auto_insert: bool,
 }
 
 impl Default for RulesConfig {
@@ -67,13 +464,17 @@ impl Default for RulesConfig {
         Self {
             max_file_size: default_max_file_size(),
             skip_binary_files: default_true(),
+            binary_sample_bytes: default_binary_sample_bytes(),
             skip_generated_files: default_true(),
+            skip_vendored: default_true(),
+            max_line_length: default_max_line_length(),
+            license_header: None,
         }
     }
 }
 
 /// Automation configuration for YAML files
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AutomationYamlConfig {
     /// Linting automation settings
     #[serde(default)]
@@ -81,6 +482,113 @@ pub struct AutomationYamlConfig {
     /// Testing automation settings
     #[serde(default)]
     pub test: AutomationCommandConfig,
+    /// Formatting before linting
+    #[serde(default)]
+    pub format: FormatConfig,
+    /// Mutation testing spot checks (`smart-mutate`)
+    #[serde(default)]
+    pub mutate: MutationConfig,
+    /// Type checking alongside the linter
+    #[serde(default)]
+    pub typecheck: TypecheckConfig,
+    /// Wall-clock budget for one lint hook invocation (format, auto-fix, lint and AI
+    /// together); keep it below Claude Code's hook timeout
+    #[serde(default)]
+    pub total_budget_seconds: Option<u64>,
+    /// Whether a file re-saved unchanged reuses its last passing lint verdict
+    #[serde(default = "default_true")]
+    pub cache_results: bool,
+    /// Directories skipped and depth limit when searching the tree for test files
+    #[serde(default)]
+    pub search: SearchLimits,
+    /// Findings passed to Claude as context (exit 0) instead of interrupting it
+    #[serde(default)]
+    pub advice: Vec<AdviceFinding>,
+    /// Whether each category of findings blocks, warns, advises or stays silent
+    #[serde(default)]
+    pub severity: SeverityConfig,
+    /// Globs (relative to the project root) of files that never trigger any automation;
+    /// unlike `exclude`, the check subcommands still see them
+    #[serde(default)]
+    pub skip_paths: Vec<String>,
+}
+
+impl Default for AutomationYamlConfig {
+    fn default() -> Self {
+        Self {
+            lint: AutomationCommandConfig::default(),
+            test: AutomationCommandConfig::default(),
+            format: FormatConfig::default(),
+            mutate: MutationConfig::default(),
+            typecheck: TypecheckConfig::default(),
+            total_budget_seconds: None,
+            cache_results: default_true(),
+            search: SearchLimits::default(),
+            advice: Vec::new(),
+            severity: SeverityConfig::default(),
+            skip_paths: vec![],
+        }
+    }
+}
+
+/// Type checker run concurrently with the linter's check
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TypecheckConfig {
+    /// Whether the lint hook also type checks edited files; opt-in, since a project's
+    /// first mypy run is slow
+    #[serde(default)]
+    pub enabled: bool,
+    /// `mypy` or `pyright`; mypy, then pyright, whichever is installed when unset
+    #[serde(default)]
+    pub tool: Option<PythonTypeChecker>,
+}
+
+/// Formatter step run before the linter's auto-fix and check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatConfig {
+    /// Whether edited files are formatted before linting
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// `black` or `ruff` (`ruff format`); black, then ruff, whichever is installed when unset
+    #[serde(default)]
+    pub tool: Option<PythonFormatter>,
+    /// Whether imports are sorted (isort, or ruff's isort rules) before formatting
+    #[serde(default = "default_true")]
+    pub sort_imports: bool,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            tool: None,
+            sort_imports: default_true(),
+        }
+    }
+}
+
+/// Mutation testing spot check configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MutationConfig {
+    /// Whether `smart-mutate` runs; mutation testing is slow, so it is opt-in
+    #[serde(default)]
+    pub enabled: bool,
+    /// `mutmut` or `cosmic-ray`; the first one installed when unset
+    #[serde(default)]
+    pub tool: Option<MutationTool>,
+    /// Time budget for the whole mutation run; results so far are reported when it runs out
+    #[serde(default = "default_mutation_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+impl Default for MutationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tool: None,
+            timeout_seconds: default_mutation_timeout_seconds(),
+        }
+    }
 }
 
 /// Configuration for a specific automation command
@@ -92,11 +600,231 @@ pub struct AutomationCommandConfig {
     /// Cooldown period in seconds
     #[serde(default = "default_cooldown_seconds")]
     pub cooldown_seconds: u64,
+    /// Whether the cooldown covers the whole project or only the edited file
+    #[serde(default)]
+    pub cooldown_scope: CooldownScope,
+    /// Waiting for an in-flight run instead of skipping
+    #[serde(default)]
+    pub lock: LockWaitConfig,
     /// Timeout in seconds
     #[serde(default = "default_timeout_seconds")]
     pub timeout_seconds: u64,
     /// Preferred tool to use (optional)
     pub preferred_tool: Option<String>,
+    /// nox session / tox env to run when the project tests through nox or tox
+    #[serde(default)]
+    pub session: Option<String>,
+    /// Also run doctests in the edited module (test only)
+    #[serde(default)]
+    pub doctests: bool,
+    /// Evaluate property coverage and shrinking output for Hypothesis tests (test only)
+    #[serde(default = "default_true")]
+    pub property_analysis: bool,
+    /// Only run the tests that reference functions/classes changed since HEAD, falling back
+    /// to the whole test file when that's ambiguous (test only, pytest)
+    #[serde(default)]
+    pub changed_functions_only: bool,
+    /// Warn about tests without assertions, catch-all `pytest.raises(Exception)` and sleeps
+    /// in the discovered test file, without AI (test only)
+    #[serde(default = "default_true")]
+    pub smells: bool,
+    /// Packages (globs or directories relative to the project root) whose edited modules
+    /// need their tests edited in the same session; enforced by the `stop` hook (test only)
+    #[serde(default)]
+    pub require_updated_tests: Vec<String>,
+    /// What to do when the only failures are syrupy/pytest-snapshot mismatches: `prompt`
+    /// (ask the user), `never` or `always` rerun with `--snapshot-update` (test only)
+    #[serde(default)]
+    pub auto_update_snapshots: AutoUpdateSnapshots,
+    /// Minimum pytest-cov coverage for the edited module; passing runs below it fail, and the
+    /// gate is skipped with a note when pytest-cov isn't installed (test only)
+    #[serde(default)]
+    pub min_coverage_percent: Option<f64>,
+    /// Maximum cyclomatic complexity for new/modified functions (lint only)
+    #[serde(default)]
+    pub max_complexity: Option<u32>,
+    /// Docstring coverage check for public functions/classes (lint only)
+    #[serde(default)]
+    pub docstrings: DocstringConfig,
+    /// Built-in import hygiene rules (lint only)
+    #[serde(default)]
+    pub imports: ImportHygieneConfig,
+    /// Escalate after this many identical failures for a file within one session; 0 disables
+    #[serde(default = "default_escalate_after")]
+    pub escalate_after: u32,
+    /// Relay the runner's output to stderr while it runs (test only)
+    #[serde(default)]
+    pub stream_output: bool,
+    /// Linter rules whose findings are dropped before deciding pass/fail (lint only)
+    #[serde(default)]
+    pub ignore_rules: Vec<String>,
+    /// Apply the linter's safe auto-fixes (`ruff check --fix`) before checking (lint only)
+    #[serde(default = "default_true")]
+    pub autofix: bool,
+    /// Also apply ruff's unsafe fixes during auto-fix; off unless set here (lint only)
+    #[serde(default)]
+    pub unsafe_fixes: bool,
+    /// Arguments appended to the discovered Python linter / test runner invocation, e.g.
+    /// `["--select", "E,F"]` for ruff or `["-x", "--timeout=30"]` for pytest
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// JUnit XML file written after each pytest run, relative to the project root (test only)
+    #[serde(default)]
+    pub junit_xml: Option<PathBuf>,
+    /// Source globs mapped to test file templates, e.g. `"src/app/core/*.py":
+    /// "tests/core/test_{stem}.py"`, consulted before the test file search (test only)
+    #[serde(default)]
+    pub map: TestFileMap,
+    /// Audit new pins in edited requirements*.txt, poetry.lock and uv.lock files with
+    /// pip-audit (lint only)
+    #[serde(default = "default_true")]
+    pub dependency_audit: bool,
+    /// Check the uv/poetry lockfile is still in sync when pyproject.toml is edited
+    /// (lint only)
+    #[serde(default = "default_true")]
+    pub lockfile_check: bool,
+    /// Globs (relative to the project root) of files this command runs for; empty runs it
+    /// for every file
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Globs (relative to the project root) of files this command never runs for, e.g.
+    /// `["tests/**"]` so edited tests don't trigger smart-test
+    #[serde(default)]
+    pub skip_paths: Vec<String>,
+}
+
+/// Lock wait configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LockWaitConfig {
+    /// Wait up to this many seconds for an in-flight run, then proceed, instead of
+    /// skipping (0 skips; waiting also ignores the cooldown)
+    #[serde(default)]
+    pub wait_seconds: u64,
+}
+
+/// Import hygiene check configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportHygieneConfig {
+    /// Whether wildcard, banned and circular imports are reported
+    #[serde(default)]
+    pub enabled: bool,
+    /// Whether import problems warn or block
+    #[serde(default)]
+    pub severity: CheckSeverity,
+    /// Modules that must not be imported (submodules included), e.g. `pickle`
+    #[serde(default)]
+    pub banned_modules: Vec<String>,
+}
+
+/// Non-blocking findings that `automation.advice` can deliver as context instead of
+/// interrupting Claude (built-in checks use `severity: advice` instead)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdviceFinding {
+    /// Passing lint runs, with their formatting, import sorting and type check notes
+    PassingLint,
+    /// Passing test runs, with AI coverage and quality hints
+    PassingTests,
+    /// "No tests found" suggestions for modules without a test file
+    MissingTests,
+    /// Test smells in the discovered test file
+    TestSmells,
+    /// Steps skipped when the lint time budget ran low, or the coverage gate without pytest-cov
+    SkippedSteps,
+}
+
+/// What a category of findings does to Claude's flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingBehavior {
+    /// Stop Claude until the findings are fixed
+    Block,
+    /// Show the findings to Claude without asking it to stop
+    Warn,
+    /// Pass the findings to Claude as context without interrupting it
+    Advice,
+    /// Drop the findings
+    Silent,
+}
+
+/// Categories of findings `automation.severity` assigns behaviors to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindingCategory {
+    LintError,
+    LintWarning,
+    TestFailure,
+    CoverageShortfall,
+    MissingTests,
+    TypeError,
+}
+
+/// Behavior per finding category (`automation.severity`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeverityConfig {
+    /// Syntax errors, undefined names and other linter errors
+    pub lint_error: FindingBehavior,
+    /// Style and convention findings left after AI triage
+    pub lint_warning: FindingBehavior,
+    pub test_failure: FindingBehavior,
+    /// Coverage below `automation.test.min_coverage_percent`
+    pub coverage_shortfall: FindingBehavior,
+    /// Modules without a test file
+    pub missing_tests: FindingBehavior,
+    pub type_error: FindingBehavior,
+}
+
+impl Default for SeverityConfig {
+    fn default() -> Self {
+        Self {
+            lint_error: FindingBehavior::Block,
+            lint_warning: FindingBehavior::Block,
+            test_failure: FindingBehavior::Block,
+            coverage_shortfall: FindingBehavior::Block,
+            missing_tests: FindingBehavior::Warn,
+            type_error: FindingBehavior::Block,
+        }
+    }
+}
+
+impl SeverityConfig {
+    pub fn behavior(&self, category: FindingCategory) -> FindingBehavior {
+        match category {
+            FindingCategory::LintError => self.lint_error,
+            FindingCategory::LintWarning => self.lint_warning,
+            FindingCategory::TestFailure => self.test_failure,
+            FindingCategory::CoverageShortfall => self.coverage_shortfall,
+            FindingCategory::MissingTests => self.missing_tests,
+            FindingCategory::TypeError => self.type_error,
+        }
+    }
+}
+
+/// How a built-in check's findings are reported
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckSeverity {
+    /// Pass findings to Claude as context without interrupting it
+    Advice,
+    /// Report findings but let Claude continue
+    #[default]
+    Warn,
+    /// Report findings as a blocking failure
+    Block,
+}
+
+/// Docstring coverage check configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocstringConfig {
+    /// Whether missing docstrings are reported
+    #[serde(default)]
+    pub enabled: bool,
+    /// Whether missing docstrings warn or block
+    #[serde(default)]
+    pub severity: CheckSeverity,
+    /// Files to skip (glob patterns)
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
 impl Default for AutomationCommandConfig {
@@ -104,8 +832,33 @@ impl Default for AutomationCommandConfig {
         Self {
             enabled: default_true(),
             cooldown_seconds: default_cooldown_seconds(),
+            cooldown_scope: CooldownScope::default(),
+            lock: LockWaitConfig::default(),
             timeout_seconds: default_timeout_seconds(),
             preferred_tool: None,
+            session: None,
+            doctests: false,
+            property_analysis: default_true(),
+            changed_functions_only: false,
+            smells: default_true(),
+            require_updated_tests: vec![],
+            auto_update_snapshots: AutoUpdateSnapshots::default(),
+            min_coverage_percent: None,
+            max_complexity: None,
+            docstrings: DocstringConfig::default(),
+            imports: ImportHygieneConfig::default(),
+            escalate_after: default_escalate_after(),
+            stream_output: false,
+            ignore_rules: vec![],
+            autofix: default_true(),
+            unsafe_fixes: false,
+            extra_args: vec![],
+            junit_xml: None,
+            map: TestFileMap::default(),
+            dependency_audit: default_true(),
+            lockfile_check: default_true(),
+            paths: vec![],
+            skip_paths: vec![],
         }
     }
 }
@@ -117,8 +870,69 @@ impl From<&AutomationYamlConfig> for AutomationConfig {
             test_enabled: yaml_config.test.enabled,
             lint_cooldown_seconds: yaml_config.lint.cooldown_seconds,
             test_cooldown_seconds: yaml_config.test.cooldown_seconds,
+            lint_cooldown_scope: yaml_config.lint.cooldown_scope,
+            test_cooldown_scope: yaml_config.test.cooldown_scope,
+            lint_lock_wait_seconds: yaml_config.lint.lock.wait_seconds,
+            test_lock_wait_seconds: yaml_config.test.lock.wait_seconds,
             lint_timeout_seconds: yaml_config.lint.timeout_seconds,
             test_timeout_seconds: yaml_config.test.timeout_seconds,
+            test_session: yaml_config.test.session.clone(),
+            test_doctests: yaml_config.test.doctests,
+            test_property_analysis: yaml_config.test.property_analysis,
+            test_changed_functions_only: yaml_config.test.changed_functions_only,
+            test_smells: yaml_config.test.smells,
+            test_require_updates: yaml_config.test.require_updated_tests.clone(),
+            test_auto_update_snapshots: yaml_config.test.auto_update_snapshots,
+            test_min_coverage_percent: yaml_config.test.min_coverage_percent,
+            lint_max_complexity: yaml_config.lint.max_complexity,
+            lint_docstrings: yaml_config.lint.docstrings.clone(),
+            lint_docstrings_exclude: PathScope::new(
+                vec![],
+                yaml_config.lint.docstrings.exclude.clone(),
+            ),
+            lint_imports: yaml_config.lint.imports.clone(),
+            lint_escalate_after: yaml_config.lint.escalate_after,
+            test_escalate_after: yaml_config.test.escalate_after,
+            test_stream_output: yaml_config.test.stream_output,
+            lint_ignore_rules: yaml_config.lint.ignore_rules.clone(),
+            lint_autofix: yaml_config.lint.autofix,
+            lint_unsafe_fixes: yaml_config.lint.unsafe_fixes,
+            format_enabled: yaml_config.format.enabled,
+            format_tool: yaml_config.format.tool.clone(),
+            format_sort_imports: yaml_config.format.sort_imports,
+            typecheck_enabled: yaml_config.typecheck.enabled,
+            typecheck_tool: yaml_config.typecheck.tool,
+            lint_extra_args: yaml_config.lint.extra_args.clone(),
+            test_extra_args: yaml_config.test.extra_args.clone(),
+            test_junit_xml: yaml_config.test.junit_xml.clone(),
+            test_map: yaml_config.test.map.clone(),
+            lint_dependency_audit: yaml_config.lint.dependency_audit,
+            lint_lockfile_check: yaml_config.lint.lockfile_check,
+            mutate_enabled: yaml_config.mutate.enabled,
+            mutate_tool: yaml_config.mutate.tool,
+            mutate_timeout_seconds: yaml_config.mutate.timeout_seconds,
+            total_budget_seconds: yaml_config.total_budget_seconds,
+            cache_results: yaml_config.cache_results,
+            search: yaml_config.search.clone(),
+            advice: yaml_config.advice.clone(),
+            severity: yaml_config.severity.clone(),
+            lint_scope: PathScope::new(
+                yaml_config.lint.paths.clone(),
+                [&yaml_config.skip_paths, &yaml_config.lint.skip_paths]
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .collect(),
+            ),
+            test_scope: PathScope::new(
+                yaml_config.test.paths.clone(),
+                [&yaml_config.skip_paths, &yaml_config.test.skip_paths]
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .collect(),
+            ),
+            mutate_scope: PathScope::new(vec![], yaml_config.skip_paths.clone()),
         }
     }
 }
@@ -131,6 +945,26 @@ fn default_true() -> bool {
     true
 }
 
+fn default_mutation_timeout_seconds() -> u64 {
+    60
+}
+
+fn default_escalate_after() -> u32 {
+    3
+}
+
+fn default_binary_sample_bytes() -> usize {
+    1024
+}
+
+fn default_max_line_length() -> usize {
+    5000
+}
+
+fn default_license_header_globs() -> Vec<String> {
+    vec!["*.py".to_string()]
+}
+
 fn default_cooldown_seconds() -> u64 {
     2
 }
@@ -139,189 +973,742 @@ fn default_timeout_seconds() -> u64 {
     20
 }
 
+/// Recent binary verdicts keyed by path and sniffed byte count (`binary_sample_bytes`
+/// can differ per override), invalidated when the file's mtime changes
+type BinaryCache = LruCache<(PathBuf, usize), (SystemTime, bool)>;
+
 /// The main guardrails checker
+///
+/// Compiled matchers and the config are reference-counted, so clones are cheap and share
+/// them (and the binary-file cache) across threads and tasks.
+#[derive(Clone)]
 pub struct GuardrailsChecker {
-    config: GuardrailsConfig,
-    global_globset: globset::GlobSet,
-    lint_globset: globset::GlobSet,
-    test_globset: globset::GlobSet,
+    config: Arc<GuardrailsConfig>,
+    global_globset: Arc<globset::GlobSet>,
+    lint_globset: Arc<globset::GlobSet>,
+    test_globset: Arc<globset::GlobSet>,
+    protect_globset: Arc<globset::GlobSet>,
+    /// One matcher per `overrides` block, in config order
+    override_globsets: Arc<Vec<globset::GlobSet>>,
     max_file_size_bytes: u64,
+    binary_cache: Arc<Mutex<BinaryCache>>,
+    /// Vendored tree (if any) of recently checked directories
+    vendored_cache: Arc<Mutex<LruCache<PathBuf, Option<PathBuf>>>>,
+    /// Hash of the config, part of every decision cache key
+    config_hash: u64,
+    /// Recent exclusion decisions; see [`GuardrailsChecker::share_decision_cache`]
+    decision_cache: Arc<Mutex<LruCache<DecisionKey, ExclusionDecision>>>,
+    /// Compiled `rules.license_header.globs`, one per distinct list (top level and
+    /// `overrides` blocks)
+    license_header_scopes: Arc<HashMap<Vec<String>, PathScope>>,
+}
+
+/// Number of exclusion decisions remembered per checker
+const DECISION_CACHE_CAPACITY: usize = 4096;
+
+/// Number of binary verdicts remembered per checker
+const BINARY_CACHE_CAPACITY: usize = 4096;
+
+/// Number of directories whose vendored tree is remembered per checker
+const VENDORED_CACHE_CAPACITY: usize = 1024;
+
+/// Identifies an exclusion decision: the same path, context and file state under the
+/// same config always decides the same way
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct DecisionKey {
+    path: PathBuf,
+    context: ExclusionContext,
+    /// Modification time and size, `None` for paths that don't exist
+    stamp: Option<(SystemTime, u64)>,
+    config_hash: u64,
 }
 
 impl GuardrailsChecker {
     /// Create a new checker from a config file path
-    pub fn from_file<P: AsRef<Path>>(config_path: P) -> Result<Self> {
-        let content = std::fs::read_to_string(config_path)
-            .context("Failed to read guardrails config file")?;
-        Self::from_yaml(&content)
+    pub fn from_file<P: AsRef<Path>>(config_path: P) -> GuardrailsResult<Self> {
+        Self::from_config(GuardrailsConfig::from_file(config_path)?)
     }
 
     /// Create a new checker from YAML content
-    pub fn from_yaml(yaml_content: &str) -> Result<Self> {
-        let config: GuardrailsConfig =
-            serde_yaml::from_str(yaml_content).context("Failed to parse guardrails YAML config")?;
+    pub fn from_yaml(yaml_content: &str) -> GuardrailsResult<Self> {
+        let config: GuardrailsConfig = serde_yaml::from_str(yaml_content)
+            .map_err(|e| GuardrailsError::config_parse(None, e))?;
         Self::from_config(config)
     }
 
     /// Create a new checker from a config struct
-    pub fn from_config(config: GuardrailsConfig) -> Result<Self> {
-        // Build global pattern matcher
-        let mut global_builder = GlobSetBuilder::new();
-        for pattern in &config.exclude.patterns {
-            let glob =
-                Glob::new(pattern).with_context(|| format!("Invalid glob pattern: {pattern}"))?;
-            global_builder.add(glob);
-        }
-        let global_globset = global_builder
-            .build()
-            .context("Failed to build global glob set")?;
-
-        // Build lint-specific pattern matcher
-        let mut lint_builder = GlobSetBuilder::new();
-        for pattern in &config.exclude.python.lint_skip {
-            let glob = Glob::new(pattern)
-                .with_context(|| format!("Invalid lint skip pattern: {pattern}"))?;
-            lint_builder.add(glob);
-        }
-        let lint_globset = lint_builder
-            .build()
-            .context("Failed to build lint glob set")?;
-
-        // Build test-specific pattern matcher
-        let mut test_builder = GlobSetBuilder::new();
-        for pattern in &config.exclude.python.test_skip {
-            let glob = Glob::new(pattern)
-                .with_context(|| format!("Invalid test skip pattern: {pattern}"))?;
-            test_builder.add(glob);
-        }
-        let test_globset = test_builder
-            .build()
-            .context("Failed to build test glob set")?;
+    pub fn from_config(config: GuardrailsConfig) -> GuardrailsResult<Self> {
+        let global_globset = build_globset(&config.exclude.patterns)?;
+        let lint_globset = build_globset(&config.exclude.python.lint_skip)?;
+        let test_globset = build_globset(&config.exclude.python.test_skip)?;
+        let protect_globset = build_globset(&config.protect.patterns)?;
+        validate_automation_patterns(&config.automation)?;
 
         // Parse max file size
         let max_file_size_bytes = parse_file_size(&config.rules.max_file_size)?;
 
+        let mut license_header_scopes = HashMap::new();
+        add_license_header_scope(&mut license_header_scopes, &config.rules)?;
+
+        // Override blocks must merge into valid settings on their own
+        let mut override_globsets = Vec::with_capacity(config.overrides.len());
+        for (index, block) in config.overrides.iter().enumerate() {
+            override_globsets.push(build_globset(&block.files)?);
+            let invalid = |message: String| {
+                GuardrailsError::config_parse(None, format!("overrides[{index}]: {message}"))
+            };
+            let rules = apply_overrides(&config.rules, [&block.rules])
+                .map_err(|e| invalid(format!("invalid rules: {e}")))?;
+            parse_file_size(&rules.max_file_size).map_err(|e| invalid(e.to_string()))?;
+            add_license_header_scope(&mut license_header_scopes, &rules)?;
+            let automation = apply_overrides(&config.automation, [&block.automation])
+                .map_err(|e| invalid(format!("invalid automation: {e}")))?;
+            validate_automation_patterns(&automation)?;
+        }
+
+        if let Some(placeholder) = config.output.templates.unknown_placeholder() {
+            return Err(GuardrailsError::config_parse(
+                None,
+                format!(
+                    "Unknown placeholder {{{{{placeholder}}}}} in output.templates (expected one of: {})",
+                    messages::TEMPLATE_PLACEHOLDERS.join(", ")
+                ),
+            ));
+        }
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        serde_json::to_string(&config)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        let config_hash = hasher.finish();
+
         Ok(Self {
-            config,
-            global_globset,
-            lint_globset,
-            test_globset,
+            config: Arc::new(config),
+            global_globset: Arc::new(global_globset),
+            lint_globset: Arc::new(lint_globset),
+            test_globset: Arc::new(test_globset),
+            protect_globset: Arc::new(protect_globset),
+            override_globsets: Arc::new(override_globsets),
             max_file_size_bytes,
+            binary_cache: Arc::new(Mutex::new(LruCache::new(BINARY_CACHE_CAPACITY))),
+            vendored_cache: Arc::new(Mutex::new(LruCache::new(VENDORED_CACHE_CAPACITY))),
+            config_hash,
+            decision_cache: Arc::new(Mutex::new(LruCache::new(DECISION_CACHE_CAPACITY))),
+            license_header_scopes: Arc::new(license_header_scopes),
         })
     }
 
+    /// Reuse another checker's exclusion decision cache, e.g. across a config reload
+    ///
+    /// Entries are keyed by config hash, so decisions made under a different config are
+    /// never returned.
+    pub fn share_decision_cache(mut self, other: &GuardrailsChecker) -> Self {
+        self.decision_cache = Arc::clone(&other.decision_cache);
+        self
+    }
+
     /// Check if a file should be excluded for any operation
-    pub fn should_exclude(&self, file_path: &Path) -> Result<bool> {
+    pub fn should_exclude(&self, file_path: &Path) -> GuardrailsResult<bool> {
         self.should_exclude_context(file_path, &ExclusionContext::Any)
     }
 
     /// Check if a file should be excluded for linting
-    pub fn should_exclude_lint(&self, file_path: &Path) -> Result<bool> {
+    pub fn should_exclude_lint(&self, file_path: &Path) -> GuardrailsResult<bool> {
         self.should_exclude_context(file_path, &ExclusionContext::Lint)
     }
 
     /// Check if a file should be excluded for testing
-    pub fn should_exclude_test(&self, file_path: &Path) -> Result<bool> {
+    pub fn should_exclude_test(&self, file_path: &Path) -> GuardrailsResult<bool> {
         self.should_exclude_context(file_path, &ExclusionContext::Test)
     }
 
+    /// Check if a path matches a `protect` pattern, either as given or relative to
+    /// the current directory
+    pub fn is_protected(&self, file_path: &Path) -> bool {
+        match std::env::current_dir() {
+            Ok(cwd) => self.is_protected_in(file_path, &cwd),
+            Err(_) => self.protect_globset.is_match(file_path),
+        }
+    }
+
+    /// Check if a path matches a `protect` pattern, either as given or relative to `base_dir`
+    pub fn is_protected_in(&self, file_path: &Path, base_dir: &Path) -> bool {
+        self.protect_globset.is_match(file_path)
+            || file_path
+                .strip_prefix(base_dir)
+                .is_ok_and(|relative| self.protect_globset.is_match(relative))
+    }
+
+    /// `overrides` blocks whose `files` match a path, as given or relative to the file's
+    /// project root (like `automation` path scopes), in config order
+    fn matching_overrides<'a>(
+        &'a self,
+        file_path: &'a Path,
+    ) -> impl Iterator<Item = &'a ConfigOverride> + 'a {
+        let file = std::env::current_dir()
+            .map(|cwd| cwd.join(file_path))
+            .unwrap_or_else(|_| file_path.to_path_buf());
+        let relative =
+            discovery::PythonProject::find_project_root(file.parent().unwrap_or(Path::new(".")))
+                .and_then(|root| file.strip_prefix(root).ok().map(Path::to_path_buf));
+        self.config
+            .overrides
+            .iter()
+            .zip(self.override_globsets.iter())
+            .filter(move |(_, globset)| {
+                globset.is_match(file_path)
+                    || relative
+                        .as_deref()
+                        .is_some_and(|relative| globset.is_match(relative))
+            })
+            .map(|(block, _)| block)
+    }
+
+    /// `rules` for a file, with the `overrides` blocks matching it applied
+    pub fn rules_for(&self, file_path: &Path) -> std::borrow::Cow<'_, RulesConfig> {
+        let overlays: Vec<_> = self
+            .matching_overrides(file_path)
+            .map(|block| &block.rules)
+            .filter(|rules| !rules.is_empty())
+            .collect();
+        if overlays.is_empty() {
+            return std::borrow::Cow::Borrowed(&self.config.rules);
+        }
+        match apply_overrides(&self.config.rules, overlays) {
+            Ok(rules) => std::borrow::Cow::Owned(rules),
+            Err(e) => {
+                log::warn!("Ignoring overrides for {}: {}", file_path.display(), e);
+                std::borrow::Cow::Borrowed(&self.config.rules)
+            }
+        }
+    }
+
+    /// Whether the license header rule with `globs` covers `file_path`, matched relative
+    /// to `project_root` like automation path scopes
+    pub fn license_header_covers(
+        &self,
+        globs: &[String],
+        file_path: &Path,
+        project_root: &Path,
+    ) -> bool {
+        if globs.is_empty() {
+            return false;
+        }
+        match self.license_header_scopes.get(globs) {
+            Some(scope) => scope.allows(file_path, project_root),
+            None => PathScope::new(globs.to_vec(), vec![]).allows(file_path, project_root),
+        }
+    }
+
+    /// `automation` settings for a file with the `overrides` blocks matching it applied;
+    /// `None` when no matching block changes them
+    pub fn automation_for(&self, file_path: &Path) -> Option<AutomationYamlConfig> {
+        let overlays: Vec<_> = self
+            .matching_overrides(file_path)
+            .map(|block| &block.automation)
+            .filter(|automation| !automation.is_empty())
+            .collect();
+        if overlays.is_empty() {
+            return None;
+        }
+        apply_overrides(&self.config.automation, overlays)
+            .map_err(|e| log::warn!("Ignoring overrides for {}: {}", file_path.display(), e))
+            .ok()
+    }
+
+    /// Whether any `overrides` block changes `automation` settings
+    pub fn has_automation_overrides(&self) -> bool {
+        self.config
+            .overrides
+            .iter()
+            .any(|block| !block.automation.is_empty())
+    }
+
     /// Check exclusion with specific context
-    fn should_exclude_context(&self, file_path: &Path, context: &ExclusionContext) -> Result<bool> {
+    fn should_exclude_context(
+        &self,
+        file_path: &Path,
+        context: &ExclusionContext,
+    ) -> GuardrailsResult<bool> {
+        Ok(self.decide(file_path, context)?.is_excluded())
+    }
+
+    /// Decide whether a file is excluded for any operation, and why
+    pub fn check(&self, file_path: &Path) -> GuardrailsResult<ExclusionDecision> {
+        self.decide(file_path, &ExclusionContext::Any)
+    }
+
+    /// Decide whether a file is excluded from linting, and why
+    pub fn check_lint(&self, file_path: &Path) -> GuardrailsResult<ExclusionDecision> {
+        self.decide(file_path, &ExclusionContext::Lint)
+    }
+
+    /// Decide whether a file is excluded from testing, and why
+    pub fn check_test(&self, file_path: &Path) -> GuardrailsResult<ExclusionDecision> {
+        self.decide(file_path, &ExclusionContext::Test)
+    }
+
+    /// Cached front of [`decide_uncached`](Self::decide_uncached), for daemon, batch and
+    /// watch runs that check the same paths repeatedly; errors are never cached
+    fn decide(
+        &self,
+        file_path: &Path,
+        context: &ExclusionContext,
+    ) -> GuardrailsResult<ExclusionDecision> {
+        // `a/./b` and `a//b` decide like `a/b`
+        let path: PathBuf = file_path.components().collect();
+        let key = DecisionKey {
+            stamp: std::fs::metadata(&path)
+                .ok()
+                .and_then(|metadata| Some((metadata.modified().ok()?, metadata.len()))),
+            path,
+            context: context.clone(),
+            config_hash: self.config_hash,
+        };
+        if let Ok(mut cache) = self.decision_cache.lock() {
+            if let Some(decision) = cache.get(&key) {
+                return Ok(decision);
+            }
+        }
+
+        let decision = self.decide_uncached(&key.path, context)?;
+        if let Ok(mut cache) = self.decision_cache.lock() {
+            cache.insert(key, decision.clone());
+        }
+        Ok(decision)
+    }
+
+    fn decide_uncached(
+        &self,
+        file_path: &Path,
+        context: &ExclusionContext,
+    ) -> GuardrailsResult<ExclusionDecision> {
         // Always check global patterns first
-        if self.global_globset.is_match(file_path) {
-            return Ok(true);
+        if let Some(pattern) = first_match(
+            &self.global_globset,
+            &self.config.exclude.patterns,
+            file_path,
+        ) {
+            return Ok(ExclusionDecision::excluded(ExclusionReason::GlobalPattern(
+                pattern,
+            )));
         }
 
-        // Check context-specific patterns
-        match context {
-            ExclusionContext::Any => {
-                // For general exclusion, check both lint and test patterns
-                if self.lint_globset.is_match(file_path) || self.test_globset.is_match(file_path) {
-                    return Ok(true);
-                }
-            }
-            ExclusionContext::Lint => {
-                if self.lint_globset.is_match(file_path) {
-                    return Ok(true);
-                }
+        // Check context-specific patterns (general exclusion checks both)
+        let python = &self.config.exclude.python;
+        if !matches!(context, ExclusionContext::Test) {
+            if let Some(pattern) = first_match(&self.lint_globset, &python.lint_skip, file_path) {
+                return Ok(ExclusionDecision::excluded(ExclusionReason::LintPattern(
+                    pattern,
+                )));
             }
-            ExclusionContext::Test => {
-                if self.test_globset.is_match(file_path) {
-                    return Ok(true);
-                }
+        }
+        if !matches!(context, ExclusionContext::Lint) {
+            if let Some(pattern) = first_match(&self.test_globset, &python.test_skip, file_path) {
+                return Ok(ExclusionDecision::excluded(ExclusionReason::TestPattern(
+                    pattern,
+                )));
             }
         }
 
         // Check file-based rules
         if file_path.exists() {
+            let rules = self.rules_for(file_path);
+            let max_file_size_bytes = match &rules {
+                std::borrow::Cow::Borrowed(_) => self.max_file_size_bytes,
+                std::borrow::Cow::Owned(rules) => parse_file_size(&rules.max_file_size)?,
+            };
+
             // Check file size
             if let Ok(metadata) = std::fs::metadata(file_path) {
-                if metadata.len() > self.max_file_size_bytes {
-                    return Ok(true);
+                if metadata.len() > max_file_size_bytes {
+                    return Ok(ExclusionDecision::excluded(ExclusionReason::TooLarge {
+                        size_bytes: metadata.len(),
+                        limit_bytes: max_file_size_bytes,
+                    }));
                 }
             }
 
             // Check if binary file
-            if self.config.rules.skip_binary_files && is_binary_file(file_path)? {
-                return Ok(true);
+            if rules.skip_binary_files
+                && self.is_binary_cached(file_path, rules.binary_sample_bytes)?
+            {
+                return Ok(ExclusionDecision::excluded(ExclusionReason::Binary));
             }
 
             // Check if generated file
-            if self.config.rules.skip_generated_files && is_generated_file(file_path) {
-                return Ok(true);
+            if rules.skip_generated_files && is_generated_file(file_path) {
+                return Ok(ExclusionDecision::excluded(ExclusionReason::Generated));
+            }
+
+            if rules.skip_vendored {
+                if let Some(dir) = self.vendored_root_cached(file_path) {
+                    return Ok(ExclusionDecision::excluded(ExclusionReason::Vendored {
+                        dir,
+                    }));
+                }
+            }
+
+            // Minified/bundled files only produce noise for linting
+            if !matches!(context, ExclusionContext::Test)
+                && rules.max_line_length > 0
+                && has_long_line(file_path, rules.max_line_length)
+            {
+                return Ok(ExclusionDecision::excluded(ExclusionReason::LongLines {
+                    max_line_length: rules.max_line_length,
+                }));
             }
         }
 
-        Ok(false)
+        Ok(ExclusionDecision::Included)
+    }
+
+    /// Check if a file contains a line longer than `rules.max_line_length`
+    pub fn exceeds_max_line_length(&self, file_path: &Path) -> bool {
+        let max_line_length = self.rules_for(file_path).max_line_length;
+        max_line_length > 0 && has_long_line(file_path, max_line_length)
     }
 
     /// Get the config for inspection
     pub fn config(&self) -> &GuardrailsConfig {
         &self.config
     }
+
+    /// Shared handle to the config, for holding it past the checker's lifetime
+    pub fn shared_config(&self) -> Arc<GuardrailsConfig> {
+        Arc::clone(&self.config)
+    }
+
+    /// Move the checker behind an `Arc` for sharing across worker tasks
+    pub fn into_shared(self) -> Arc<Self> {
+        Arc::new(self)
+    }
+
+    /// Vendored-tree lookup shared by every file in the same directory
+    fn vendored_root_cached(&self, file_path: &Path) -> Option<PathBuf> {
+        let dir = file_path.parent()?.to_path_buf();
+        if let Ok(mut cache) = self.vendored_cache.lock() {
+            if let Some(root) = cache.get(&dir) {
+                return root;
+            }
+        }
+
+        let root = vendored::vendored_root(file_path);
+        if let Ok(mut cache) = self.vendored_cache.lock() {
+            cache.insert(dir, root.clone());
+        }
+        root
+    }
+
+    /// Binary check that reuses the previous verdict while the file's mtime is unchanged
+    fn is_binary_cached(&self, file_path: &Path, sample_bytes: usize) -> GuardrailsResult<bool> {
+        let mtime = std::fs::metadata(file_path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        let key = (file_path.to_path_buf(), sample_bytes);
+        if let Some(mtime) = mtime {
+            if let Ok(mut cache) = self.binary_cache.lock() {
+                if let Some((cached_mtime, verdict)) = cache.get(&key) {
+                    if cached_mtime == mtime {
+                        return Ok(verdict);
+                    }
+                }
+            }
+        }
+
+        let verdict = is_binary_file(file_path, sample_bytes)?;
+
+        if let Some(mtime) = mtime {
+            if let Ok(mut cache) = self.binary_cache.lock() {
+                cache.insert(key, (mtime, verdict));
+            }
+        }
+
+        Ok(verdict)
+    }
+}
+
+/// Outcome of an exclusion check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExclusionDecision {
+    Included,
+    Excluded { reason: ExclusionReason },
+}
+
+impl ExclusionDecision {
+    fn excluded(reason: ExclusionReason) -> Self {
+        ExclusionDecision::Excluded { reason }
+    }
+
+    pub fn is_excluded(&self) -> bool {
+        matches!(self, ExclusionDecision::Excluded { .. })
+    }
+
+    /// Why the file was excluded, if it was
+    pub fn reason(&self) -> Option<&ExclusionReason> {
+        match self {
+            ExclusionDecision::Included => None,
+            ExclusionDecision::Excluded { reason } => Some(reason),
+        }
+    }
+}
+
+/// Rule that excluded a file; pattern variants carry the matching glob
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExclusionReason {
+    /// `exclude.patterns`
+    GlobalPattern(String),
+    /// `exclude.python.lint_skip`
+    LintPattern(String),
+    /// `exclude.python.test_skip`
+    TestPattern(String),
+    /// Larger than `rules.max_file_size`
+    TooLarge { size_bytes: u64, limit_bytes: u64 },
+    /// Binary content (`rules.skip_binary_files`)
+    Binary,
+    /// Generated-code marker (`rules.skip_generated_files`)
+    Generated,
+    /// A line longer than `rules.max_line_length` (minified or bundled code)
+    LongLines { max_line_length: usize },
+    /// Inside a vendored third-party tree (`rules.skip_vendored`)
+    Vendored { dir: PathBuf },
+}
+
+impl std::fmt::Display for ExclusionReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExclusionReason::GlobalPattern(pattern) => {
+                write!(f, "matches exclude pattern '{pattern}'")
+            }
+            ExclusionReason::LintPattern(pattern) => {
+                write!(f, "matches lint_skip pattern '{pattern}'")
+            }
+            ExclusionReason::TestPattern(pattern) => {
+                write!(f, "matches test_skip pattern '{pattern}'")
+            }
+            ExclusionReason::TooLarge {
+                size_bytes,
+                limit_bytes,
+            } => write!(f, "file is {size_bytes} bytes (limit {limit_bytes})"),
+            ExclusionReason::Binary => write!(f, "binary file"),
+            ExclusionReason::Generated => write!(f, "generated file"),
+            ExclusionReason::LongLines { max_line_length } => {
+                write!(f, "has lines longer than {max_line_length} characters")
+            }
+            ExclusionReason::Vendored { dir } => {
+                write!(f, "vendored third-party code under {}", dir.display())
+            }
+        }
+    }
+}
+
+/// First pattern (in config order) of a glob set built from `patterns` matching the path
+fn first_match(globset: &globset::GlobSet, patterns: &[String], path: &Path) -> Option<String> {
+    globset
+        .matches(path)
+        .into_iter()
+        .min()
+        .and_then(|index| patterns.get(index).cloned())
 }
 
 /// Context for exclusion checking
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum ExclusionContext {
     Any,
     Lint,
     Test,
 }
 
-/// Parse file size string like "10MB" to bytes
-fn parse_file_size(size_str: &str) -> Result<u64> {
-    let size_str = size_str.trim().to_uppercase();
+/// Check the path scope and docstring exclude globs of an `automation` section compile
+fn validate_automation_patterns(automation: &AutomationYamlConfig) -> GuardrailsResult<()> {
+    for patterns in [
+        &automation.skip_paths,
+        &automation.lint.paths,
+        &automation.lint.skip_paths,
+        &automation.test.paths,
+        &automation.test.skip_paths,
+        &automation.lint.docstrings.exclude,
+    ] {
+        build_globset(patterns)?;
+    }
+    Ok(())
+}
+
+/// Compile the license header globs of `rules` into `scopes` unless already there;
+/// invalid patterns are config errors
+fn add_license_header_scope(
+    scopes: &mut HashMap<Vec<String>, PathScope>,
+    rules: &RulesConfig,
+) -> GuardrailsResult<()> {
+    if let Some(header) = &rules.license_header {
+        if !scopes.contains_key(&header.globs) {
+            let scope = PathScope::try_new(header.globs.clone(), vec![])?;
+            scopes.insert(header.globs.clone(), scope);
+        }
+    }
+    Ok(())
+}
+
+/// Compile glob patterns into one set; match indices follow `patterns` order
+fn build_globset(patterns: &[String]) -> GuardrailsResult<globset::GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|source| GuardrailsError::InvalidPattern {
+            pattern: pattern.clone(),
+            source,
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|source| GuardrailsError::InvalidPattern {
+            pattern: patterns.join(", "),
+            source,
+        })
+}
+
+/// Largest size accepted by [`parse_file_size`] (1 TiB)
+const MAX_PARSED_FILE_SIZE: f64 = 1024.0 * 1024.0 * 1024.0 * 1024.0;
+
+/// Parse a file size string like "10MB", "10MiB", "512 kB" or "1.5 GB" to bytes
+///
+/// Units are case-insensitive and always binary (KB and KiB are both 1024 bytes);
+/// a bare number is taken as bytes. Whitespace between number and unit is allowed.
+pub fn parse_file_size(size_str: &str) -> GuardrailsResult<u64> {
+    let invalid = |message: String| GuardrailsError::config_parse(None, message);
+    let trimmed = size_str.trim();
+    if trimmed.is_empty() {
+        return Err(invalid("Invalid file size: value is empty".to_string()));
+    }
+
+    let unit_start = trimmed
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+        .unwrap_or(trimmed.len());
+    let (num_str, unit_str) = trimmed.split_at(unit_start);
+    let num_str = num_str.trim();
+    let unit = unit_str.trim().to_uppercase();
+
+    let multiplier: f64 = match unit.as_str() {
+        "" | "B" => 1.0,
+        "K" | "KB" | "KIB" => 1024.0,
+        "M" | "MB" | "MIB" => 1024.0 * 1024.0,
+        "G" | "GB" | "GIB" => 1024.0 * 1024.0 * 1024.0,
+        _ => {
+            return Err(invalid(format!(
+                "Invalid file size '{trimmed}': unknown unit '{}'",
+                unit_str.trim()
+            )))
+        }
+    };
+
+    let num: f64 = num_str
+        .parse()
+        .map_err(|e| invalid(format!("Invalid file size number in '{trimmed}': {e}")))?;
+
+    if !num.is_finite() || num < 0.0 {
+        return Err(invalid(format!(
+            "Invalid file size '{trimmed}': must be a non-negative number"
+        )));
+    }
 
-    if let Some(num_str) = size_str.strip_suffix("KB") {
-        let num: f64 = num_str.parse().context("Invalid file size number")?;
-        Ok((num * 1024.0) as u64)
-    } else if let Some(num_str) = size_str.strip_suffix("MB") {
-        let num: f64 = num_str.parse().context("Invalid file size number")?;
-        Ok((num * 1024.0 * 1024.0) as u64)
-    } else if let Some(num_str) = size_str.strip_suffix("GB") {
-        let num: f64 = num_str.parse().context("Invalid file size number")?;
-        Ok((num * 1024.0 * 1024.0 * 1024.0) as u64)
-    } else {
-        // Assume bytes if no suffix
-        size_str.parse().context("Invalid file size")
+    let bytes = num * multiplier;
+    if bytes > MAX_PARSED_FILE_SIZE {
+        return Err(invalid(format!(
+            "Invalid file size '{trimmed}': exceeds the 1 TiB maximum"
+        )));
     }
+
+    Ok(bytes as u64)
 }
 
-/// Check if a file is binary by reading the first few bytes
-fn is_binary_file(file_path: &Path) -> Result<bool> {
+/// Extensions that are always treated as text without opening the file
+const TEXT_EXTENSIONS: &[&str] = &[
+    "py", "pyi", "pyx", "pxd", "txt", "md", "rst", "toml", "cfg", "ini", "yaml", "yml", "json",
+    "csv", "html", "css", "js", "ts", "sh", "rs", "sql", "xml",
+];
+
+/// Extensions that are always treated as binary without opening the file
+const BINARY_EXTENSIONS: &[&str] = &[
+    "pyc", "pyo", "pyd", "so", "dll", "dylib", "exe", "o", "a", "png", "jpg", "jpeg", "gif", "ico",
+    "pdf", "zip", "gz", "tar", "whl", "sqlite", "db", "pkl",
+];
+
+/// Check if a file is binary, using the extension fast path before sniffing content
+fn is_binary_file(file_path: &Path, sample_bytes: usize) -> GuardrailsResult<bool> {
     use std::io::Read;
 
-    let mut file =
-        std::fs::File::open(file_path).context("Failed to open file for binary check")?;
+    if let Some(ext) = file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+    {
+        if TEXT_EXTENSIONS.contains(&ext.as_str()) {
+            return Ok(false);
+        }
+        if BINARY_EXTENSIONS.contains(&ext.as_str()) {
+            return Ok(true);
+        }
+    }
+
+    let file = std::fs::File::open(file_path).map_err(|e| GuardrailsError::io(file_path, e))?;
+
+    // `rules.binary_sample_bytes` may be huge; never preallocate beyond the file
+    let file_len = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+    let mut buffer = Vec::with_capacity(file_len.min(sample_bytes as u64) as usize);
+    file.take(sample_bytes as u64)
+        .read_to_end(&mut buffer)
+        .map_err(|e| GuardrailsError::io(file_path, e))?;
+
+    Ok(is_binary_content(&buffer))
+}
+
+/// Classify a byte sample the way `content_inspector` does: a known text BOM
+/// means text (so UTF-16/UTF-32 files aren't flagged by their null bytes),
+/// a PDF magic header means binary, and otherwise any null byte means binary
+fn is_binary_content(sample: &[u8]) -> bool {
+    const TEXT_BOMS: &[&[u8]] = &[
+        &[0xEF, 0xBB, 0xBF],       // UTF-8
+        &[0x00, 0x00, 0xFE, 0xFF], // UTF-32 BE
+        &[0xFF, 0xFE, 0x00, 0x00], // UTF-32 LE
+        &[0xFE, 0xFF],             // UTF-16 BE
+        &[0xFF, 0xFE],             // UTF-16 LE
+    ];
+
+    if TEXT_BOMS.iter().any(|bom| sample.starts_with(bom)) {
+        return false;
+    }
+
+    if sample.starts_with(b"%PDF") {
+        return true;
+    }
 
-    let mut buffer = [0; 1024];
-    let bytes_read = file
-        .read(&mut buffer)
-        .context("Failed to read file for binary check")?;
+    sample.contains(&0)
+}
 
-    // Simple binary detection: look for null bytes
-    Ok(buffer[..bytes_read].contains(&0))
+/// Check if any line in the file is longer than `max_line_length` bytes
+fn has_long_line(file_path: &Path, max_line_length: usize) -> bool {
+    use std::io::{BufRead, BufReader};
+
+    let file = match std::fs::File::open(file_path) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => return false,
+            Ok(_) => {
+                let length = line.strip_suffix(b"\n").unwrap_or(&line).len();
+                if length > max_line_length {
+                    return true;
+                }
+            }
+        }
+    }
 }
 
 /// Check if a file is likely generated based on common patterns
@@ -389,12 +1776,19 @@ pub fn default_config() -> GuardrailsConfig {
         },
         rules: RulesConfig::default(),
         automation: AutomationYamlConfig::default(),
+        protect: ProtectConfig::default(),
+        commands: CommandPolicyConfig::default(),
+        ai: AiConfig::default(),
+        output: OutputConfig::default(),
+        update: UpdateConfig::default(),
+        overrides: Vec::new(),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use anyhow::Result;
     use std::fs;
     use tempfile::TempDir;
 
@@ -449,6 +1843,14 @@ mod tests {
 
         // Test with spaces
         assert_eq!(parse_file_size(" 10MB ")?, 10 * 1024 * 1024);
+        assert_eq!(parse_file_size("512 kB")?, 512 * 1024);
+        assert_eq!(parse_file_size("1.5 GB")?, 3 * 512 * 1024 * 1024);
+
+        // Binary prefixes
+        assert_eq!(parse_file_size("10MiB")?, 10 * 1024 * 1024);
+        assert_eq!(parse_file_size("4 KiB")?, 4 * 1024);
+        assert_eq!(parse_file_size("1gib")?, 1024 * 1024 * 1024);
+        assert_eq!(parse_file_size("100B")?, 100);
 
         Ok(())
     }
@@ -459,6 +1861,12 @@ mod tests {
         assert!(parse_file_size("10XB").is_err());
         assert!(parse_file_size("").is_err());
         assert!(parse_file_size("MB").is_err());
+        assert!(parse_file_size("-5MB").is_err());
+        assert!(parse_file_size("5000GB").is_err());
+        assert!(parse_file_size("1e400").is_err());
+
+        let err = parse_file_size("10 XB").unwrap_err().to_string();
+        assert!(err.contains("unknown unit"), "unexpected error: {err}");
     }
 
     #[test]
@@ -487,6 +1895,152 @@ rules:
         Ok(())
     }
 
+    #[test]
+    fn test_output_style_config() -> Result<()> {
+        let config: GuardrailsConfig =
+            serde_yaml::from_str("exclude:\n  patterns: []\noutput:\n  style: ascii\n")?;
+        assert_eq!(config.output.style, OutputStyle::Ascii);
+        assert_eq!(default_config().output.style, OutputStyle::Emoji);
+        assert!(serde_yaml::from_str::<GuardrailsConfig>(
+            "exclude:\n  patterns: []\noutput:\n  style: fancy\n"
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_overrides() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let yaml = r#"
+exclude:
+  patterns: []
+rules:
+  max_line_length: 100
+overrides:
+  - files: "**/scripts/**"
+    rules:
+      skip_generated_files: false
+      max_line_length: 0
+    automation:
+      test:
+        enabled: false
+  - files: ["**/scripts/big/**", "**/data/**"]
+    rules:
+      max_file_size: "10"
+  - files: "legacy/**"
+    rules:
+      max_line_length: 120
+"#;
+        let checker = GuardrailsChecker::from_yaml(yaml)?;
+        let scripts = temp_dir.path().join("scripts");
+        fs::create_dir_all(scripts.join("big"))?;
+
+        // Generated files and long lines are fine in scripts, not elsewhere
+        let generated = scripts.join("schema_generated.py");
+        fs::write(&generated, format!("x = '{}'\n", "a".repeat(200)))?;
+        assert!(!checker.should_exclude_lint(&generated)?);
+        let elsewhere = temp_dir.path().join("schema_generated.py");
+        fs::write(&elsewhere, "x = 1\n")?;
+        assert!(checker.should_exclude(&elsewhere)?);
+        assert!(!checker.exceeds_max_line_length(&generated));
+
+        // Later blocks apply on top of earlier ones
+        let big = scripts.join("big/load.py");
+        fs::write(&big, "x = 1  # more than ten bytes\n")?;
+        assert!(matches!(
+            checker.check(&big)?.reason(),
+            Some(ExclusionReason::TooLarge {
+                limit_bytes: 10,
+                ..
+            })
+        ));
+        assert_eq!(checker.rules_for(&big).max_line_length, 0);
+        assert_eq!(checker.rules_for(&elsewhere).max_line_length, 100);
+
+        // Relative patterns match from the file's project root, wherever the process runs
+        fs::write(
+            temp_dir.path().join("pyproject.toml"),
+            "[project]\nname = \"app\"\n",
+        )?;
+        fs::create_dir_all(temp_dir.path().join("legacy"))?;
+        let legacy = temp_dir.path().join("legacy/views.py");
+        assert_eq!(checker.rules_for(&legacy).max_line_length, 120);
+
+        assert!(checker.has_automation_overrides());
+        let automation = checker.automation_for(&generated).unwrap();
+        assert!(!automation.test.enabled);
+        assert!(automation.lint.enabled);
+        assert!(checker.automation_for(&elsewhere).is_none());
+
+        // Merging nothing keeps every setting
+        let config = GuardrailsConfig::from_file(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("guardrails.yaml"),
+        )?;
+        let merged = apply_overrides(&config.automation, [])?;
+        assert_eq!(
+            serde_json::to_value(&merged)?,
+            serde_json::to_value(&config.automation)?
+        );
+
+        // Blocks are validated when the config loads
+        for invalid in [
+            "overrides:\n  - files: \"x/**\"\n    rules:\n      max_file_size: huge\n",
+            "overrides:\n  - files: \"x/**\"\n    automation:\n      test:\n        enabled: maybe\n",
+            "overrides:\n  - files: \"[x\"\n",
+        ] {
+            let yaml = format!("exclude:\n  patterns: []\n{invalid}");
+            assert!(GuardrailsChecker::from_yaml(&yaml).is_err(), "{invalid}");
+        }
+        let error = GuardrailsChecker::from_yaml(
+            "exclude:\n  patterns: []\noverrides:\n  - files: \"x/**\"\n    rules:\n      max_file_size: huge\n",
+        )
+        .err()
+        .unwrap();
+        assert!(error.to_string().contains("overrides[0]"), "{error}");
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_pin_config() -> Result<()> {
+        let config: GuardrailsConfig =
+            serde_yaml::from_str("exclude:\n  patterns: []\nupdate:\n  pin: \"1.2.0\"\n")?;
+        assert_eq!(config.update.pin.as_deref(), Some("1.2.0"));
+        assert_eq!(default_config().update.pin, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_automation_path_scopes() -> Result<()> {
+        let yaml = r#"
+exclude:
+  patterns: []
+automation:
+  skip_paths: ["examples/**"]
+  lint:
+    paths: ["src/**"]
+  test:
+    skip_paths: ["tests/**"]
+"#;
+        let checker = GuardrailsChecker::from_yaml(yaml)?;
+        let config = AutomationConfig::from(&checker.config().automation);
+        let root = Path::new("/project");
+        let allows = |scope: &PathScope, path: &str| scope.allows(&root.join(path), root);
+
+        assert!(allows(&config.lint_scope, "src/app.py"));
+        assert!(!allows(&config.lint_scope, "scripts/tool.py"));
+        assert!(allows(&config.test_scope, "src/app.py"));
+        assert!(!allows(&config.test_scope, "tests/test_app.py"));
+        for scope in [&config.lint_scope, &config.test_scope, &config.mutate_scope] {
+            assert!(!allows(scope, "examples/demo.py"));
+        }
+        // Scopes don't exclude anything from the check subcommands
+        assert!(!checker.should_exclude_test(&root.join("tests/test_app.py"))?);
+
+        let invalid = "exclude:\n  patterns: []\nautomation:\n  test:\n    paths: [\"[\"]\n";
+        assert!(GuardrailsChecker::from_yaml(invalid).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_yaml_config_parsing_errors() {
         let invalid_yaml = r#"
@@ -504,6 +2058,36 @@ exclude:
         assert!(GuardrailsChecker::from_yaml(invalid_glob).is_err());
     }
 
+    #[test]
+    fn test_error_categories() {
+        assert!(matches!(
+            GuardrailsChecker::from_yaml("exclude: [1, 2"),
+            Err(GuardrailsError::ConfigParse { path: None, .. })
+        ));
+        assert!(matches!(
+            GuardrailsChecker::from_yaml("exclude:\n  patterns: [\"[oops\"]\n"),
+            Err(GuardrailsError::InvalidPattern { ref pattern, .. }) if pattern == "[oops"
+        ));
+        assert!(matches!(
+            GuardrailsConfig::from_file("/nonexistent/guardrails.yaml"),
+            Err(GuardrailsError::Io { .. })
+        ));
+        assert!(matches!(
+            parse_file_size("10 XB"),
+            Err(GuardrailsError::ConfigParse { .. })
+        ));
+
+        // Usable through anyhow, and recoverable from it
+        let error = anyhow::Error::from(GuardrailsError::ToolNotFound {
+            tool: "ruff".to_string(),
+        });
+        assert_eq!(error.to_string(), "ruff is not installed");
+        assert!(matches!(
+            error.downcast_ref::<GuardrailsError>(),
+            Some(GuardrailsError::ToolNotFound { .. })
+        ));
+    }
+
     #[test]
     fn test_generated_file_detection() {
         assert!(is_generated_file(Path::new("models_pb2.py")));
@@ -528,17 +2112,66 @@ exclude:
         // Create a text file
         let text_file = temp_dir.path().join("text.txt");
         fs::write(&text_file, "This is a text file\nwith multiple lines")?;
-        assert!(!is_binary_file(&text_file)?);
+        assert!(!is_binary_file(&text_file, 1024)?);
 
         // Create a binary file (with null bytes)
         let binary_file = temp_dir.path().join("binary.bin");
         fs::write(&binary_file, b"Binary\x00content\x00here")?;
-        assert!(is_binary_file(&binary_file)?);
+        assert!(is_binary_file(&binary_file, 1024)?);
 
         // Create empty file
         let empty_file = temp_dir.path().join("empty.txt");
         fs::write(&empty_file, "")?;
-        assert!(!is_binary_file(&empty_file)?);
+        assert!(!is_binary_file(&empty_file, 1024)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_detection_boms_and_extensions() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+
+        // UTF-16 LE text with BOM contains null bytes but is still text
+        let utf16_file = temp_dir.path().join("notes.dat");
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend("hello".encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        fs::write(&utf16_file, &utf16)?;
+        assert!(!is_binary_file(&utf16_file, 1024)?);
+
+        // Known text extensions never need to be opened
+        assert!(!is_binary_file(Path::new("/nonexistent/module.py"), 1024)?);
+        assert!(is_binary_file(Path::new("/nonexistent/module.pyc"), 1024)?);
+
+        // Null bytes beyond the sample size are not inspected
+        let late_null = temp_dir.path().join("late.dat");
+        let mut content = vec![b'a'; 64];
+        content.push(0);
+        fs::write(&late_null, &content)?;
+        assert!(!is_binary_file(&late_null, 32)?);
+        assert!(is_binary_file(&late_null, 128)?);
+
+        assert!(is_binary_content(b"%PDF-1.7 header"));
+
+        // A huge configured sample size reads the file without preallocating it
+        assert!(is_binary_file(&late_null, usize::MAX)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_cache_keyed_by_sample_size() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let checker = GuardrailsChecker::from_yaml("exclude:\n  patterns: []\n")?;
+
+        // The null byte is only inside the larger sample
+        let late_null = temp_dir.path().join("late_null.dat");
+        let mut content = vec![b'a'; 100];
+        content.push(0);
+        fs::write(&late_null, content)?;
+
+        assert!(!checker.is_binary_cached(&late_null, 16)?);
+        assert!(checker.is_binary_cached(&late_null, 1024)?);
+        assert!(!checker.is_binary_cached(&late_null, 16)?);
 
         Ok(())
     }
@@ -555,9 +2188,19 @@ exclude:
             rules: RulesConfig {
                 max_file_size: "10".to_string(), // 10 bytes
                 skip_binary_files: false,
+                binary_sample_bytes: 1024,
                 skip_generated_files: false,
+                skip_vendored: false,
+                max_line_length: 0,
+                license_header: None,
             },
             automation: AutomationYamlConfig::default(),
+            protect: ProtectConfig::default(),
+            commands: CommandPolicyConfig::default(),
+            ai: AiConfig::default(),
+            output: OutputConfig::default(),
+            update: UpdateConfig::default(),
+            overrides: Vec::new(),
         };
         let checker = GuardrailsChecker::from_config(config)?;
 
@@ -574,6 +2217,61 @@ exclude:
         Ok(())
     }
 
+    #[test]
+    fn test_max_line_length_rule() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let yaml = r#"
+exclude:
+  patterns: []
+rules:
+  max_line_length: 100
+"#;
+        let checker = GuardrailsChecker::from_yaml(yaml)?;
+
+        let normal_file = temp_dir.path().join("normal.py");
+        fs::write(&normal_file, "x = 1\ny = 2\n")?;
+        assert!(!checker.should_exclude_lint(&normal_file)?);
+
+        let minified_file = temp_dir.path().join("bundle.py");
+        fs::write(&minified_file, format!("x = 1\n{}\n", "a".repeat(200)))?;
+        assert!(checker.exceeds_max_line_length(&minified_file));
+        assert!(checker.should_exclude_lint(&minified_file)?);
+        assert!(checker.should_exclude(&minified_file)?);
+        assert!(!checker.should_exclude_test(&minified_file)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_protected_paths() -> Result<()> {
+        let yaml = r#"
+exclude:
+  patterns: []
+protect:
+  patterns:
+    - "migrations/**"
+    - "*.lock"
+"#;
+        let checker = GuardrailsChecker::from_yaml(yaml)?;
+
+        assert!(checker.is_protected(Path::new("migrations/0001_initial.py")));
+        assert!(checker.is_protected(Path::new("poetry.lock")));
+        assert!(!checker.is_protected(Path::new("src/app.py")));
+
+        let cwd = std::env::current_dir()?;
+        assert!(checker.is_protected(&cwd.join("migrations/0002_users.py")));
+        assert!(checker.is_protected_in(
+            Path::new("/srv/app/migrations/0003_orders.py"),
+            Path::new("/srv/app")
+        ));
+
+        // Default config protects nothing
+        let checker = GuardrailsChecker::from_config(default_config())?;
+        assert!(!checker.is_protected(Path::new("poetry.lock")));
+
+        Ok(())
+    }
+
     #[test]
     fn test_exclusion_context_combinations() -> Result<()> {
         let config = GuardrailsConfig {
@@ -586,6 +2284,12 @@ exclude:
             },
             rules: RulesConfig::default(),
             automation: AutomationYamlConfig::default(),
+            protect: ProtectConfig::default(),
+            commands: CommandPolicyConfig::default(),
+            ai: AiConfig::default(),
+            output: OutputConfig::default(),
+            update: UpdateConfig::default(),
+            overrides: Vec::new(),
         };
         let checker = GuardrailsChecker::from_config(config)?;
 
@@ -612,6 +2316,81 @@ exclude:
         Ok(())
     }
 
+    #[test]
+    fn test_exclusion_decision_reasons() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let yaml = r#"
+exclude:
+  patterns: ["*.tmp", "build/**"]
+  python:
+    lint_skip: ["migrations/**"]
+    test_skip: ["conftest.py"]
+rules:
+  max_file_size: "16"
+  skip_generated_files: true
+"#;
+        let checker = GuardrailsChecker::from_yaml(yaml)?;
+
+        assert_eq!(
+            checker.check(Path::new("build/out.py"))?,
+            ExclusionDecision::Excluded {
+                reason: ExclusionReason::GlobalPattern("build/**".to_string())
+            }
+        );
+        assert_eq!(
+            checker
+                .check_lint(Path::new("migrations/0001.py"))?
+                .reason(),
+            Some(&ExclusionReason::LintPattern("migrations/**".to_string()))
+        );
+        assert_eq!(
+            checker.check_lint(Path::new("conftest.py"))?,
+            ExclusionDecision::Included
+        );
+        assert_eq!(
+            checker.check(Path::new("conftest.py"))?.reason(),
+            Some(&ExclusionReason::TestPattern("conftest.py".to_string()))
+        );
+
+        let large = temp_dir.path().join("large.py");
+        fs::write(&large, "x = 'more than sixteen bytes'\n")?;
+        assert_eq!(
+            checker.check(&large)?.reason(),
+            Some(&ExclusionReason::TooLarge {
+                size_bytes: 30,
+                limit_bytes: 16
+            })
+        );
+
+        let generated = temp_dir.path().join("api_pb2.py");
+        fs::write(&generated, "x = 1\n")?;
+        let decision = checker.check(&generated)?;
+        assert_eq!(decision.reason(), Some(&ExclusionReason::Generated));
+        assert_eq!(decision.reason().unwrap().to_string(), "generated file");
+
+        let source = temp_dir.path().join("app.py");
+        fs::write(&source, "x = 1\n")?;
+        assert!(!checker.check(&source)?.is_excluded());
+
+        fs::write(temp_dir.path().join("setup.py"), "")?;
+        fs::create_dir_all(temp_dir.path().join("third_party"))?;
+        let vendored = temp_dir.path().join("third_party/six.py");
+        fs::write(&vendored, "x = 1\n")?;
+        let decision = checker.check(&vendored)?;
+        assert_eq!(
+            decision.reason(),
+            Some(&ExclusionReason::Vendored {
+                dir: temp_dir.path().join("third_party")
+            })
+        );
+        let no_vendored = GuardrailsChecker::from_yaml(
+            &yaml.replace("skip_generated_files: true", "skip_vendored: false"),
+        )?;
+        assert!(!no_vendored.check(&vendored)?.is_excluded());
+
+        Ok(())
+    }
+
     #[test]
     fn test_default_config_structure() {
         let config = default_config();
@@ -664,6 +2443,101 @@ exclude:
         Ok(())
     }
 
+    #[test]
+    fn test_automation_tool_options() -> Result<()> {
+        let yaml = r#"
+exclude:
+  patterns: []
+automation:
+  lint:
+    extra_args: ["--select", "E,F", "--line-length", "100"]
+  test:
+    extra_args: ["-x", "--timeout=30"]
+"#;
+
+        let checker = GuardrailsChecker::from_yaml(yaml)?;
+        let config = AutomationConfig::from(&checker.config().automation);
+        assert_eq!(
+            config.lint_extra_args,
+            vec!["--select", "E,F", "--line-length", "100"]
+        );
+        assert_eq!(config.test_extra_args, vec!["-x", "--timeout=30"]);
+        assert!(config.lint_autofix && !config.lint_unsafe_fixes);
+        assert!(config.format_enabled && config.format_tool.is_none());
+        assert!(config.format_sort_imports);
+        assert!(!config.typecheck_enabled);
+        assert!(config.cache_results && config.total_budget_seconds.is_none());
+        assert!(AutomationConfig::from(&AutomationYamlConfig::default()).cache_results);
+
+        let yaml = r#"
+exclude:
+  patterns: []
+automation:
+  format:
+    enabled: false
+    tool: ruff
+    sort_imports: false
+  typecheck:
+    enabled: true
+    tool: pyright
+  lint:
+    autofix: false
+    unsafe_fixes: true
+"#;
+        let checker = GuardrailsChecker::from_yaml(yaml)?;
+        let config = AutomationConfig::from(&checker.config().automation);
+        assert!(!config.format_enabled);
+        assert_eq!(config.format_tool, Some(PythonFormatter::Ruff));
+        assert!(!config.format_sort_imports);
+        assert!(config.typecheck_enabled);
+        assert_eq!(config.typecheck_tool, Some(PythonTypeChecker::Pyright));
+        assert!(!config.lint_autofix && config.lint_unsafe_fixes);
+        assert!(AutomationConfig::default().lint_extra_args.is_empty());
+
+        let yaml = r#"
+exclude:
+  patterns: []
+automation:
+  search:
+    skip_dirs: [fixtures]
+  advice: [missing_tests, test_smells]
+  severity:
+    lint_warning: warn
+    missing_tests: silent
+  test:
+    map:
+      "src/app/core/*.py": "tests/core/test_{stem}.py"
+"#;
+        let checker = GuardrailsChecker::from_yaml(yaml)?;
+        let config = AutomationConfig::from(&checker.config().automation);
+        assert_eq!(config.search.skip_dirs, vec!["fixtures"]);
+        assert_eq!(
+            config.advice,
+            vec![AdviceFinding::MissingTests, AdviceFinding::TestSmells]
+        );
+        assert!(AutomationConfig::default().advice.is_empty());
+        assert_eq!(config.severity.lint_warning, FindingBehavior::Warn);
+        assert_eq!(
+            config.severity.behavior(FindingCategory::MissingTests),
+            FindingBehavior::Silent
+        );
+        assert_eq!(config.severity.lint_error, FindingBehavior::Block);
+        assert_eq!(config.search.max_depth, discovery::DEFAULT_SEARCH_DEPTH);
+        assert_eq!(
+            config
+                .test_map
+                .resolve(Path::new("/repo/src/app/core/db.py"), Path::new("/repo")),
+            Some(PathBuf::from("/repo/tests/core/test_db.py"))
+        );
+        assert!(AutomationConfig::default()
+            .search
+            .skip_dirs
+            .iter()
+            .any(|dir| dir == "site-packages"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_nonexistent_file_handling() -> Result<()> {
         let config = default_config();
@@ -678,4 +2552,76 @@ exclude:
 
         Ok(())
     }
+
+    #[test]
+    fn test_decision_cache_tracks_file_and_config() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let file = temp_dir.path().join("app.py");
+        fs::write(&file, "x = 1\n")?;
+
+        let yaml = "exclude:\n  patterns: []\nrules:\n  max_file_size: \"16\"\n";
+        let checker = GuardrailsChecker::from_yaml(yaml)?;
+        assert!(!checker.check(&file)?.is_excluded());
+        assert!(!checker.check(&file)?.is_excluded());
+
+        // A changed file is decided again
+        fs::write(&file, "x = 'more than sixteen bytes'\n")?;
+        assert!(matches!(
+            checker.check(&file)?.reason(),
+            Some(ExclusionReason::TooLarge { .. })
+        ));
+
+        // A checker sharing the cache under another config doesn't see those decisions
+        let relaxed = GuardrailsChecker::from_yaml(&yaml.replace("\"16\"", "\"1MB\""))?
+            .share_decision_cache(&checker);
+        assert!(!relaxed.check(&file)?.is_excluded());
+        assert!(checker.check(&file)?.is_excluded());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vendored_cache_is_bounded() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut checker = GuardrailsChecker::from_config(default_config())?;
+        checker.vendored_cache = Arc::new(Mutex::new(LruCache::new(2)));
+
+        for dir in ["a", "b", "c"] {
+            fs::create_dir_all(temp_dir.path().join(dir))?;
+            checker.vendored_root_cached(&temp_dir.path().join(dir).join("mod.py"));
+        }
+        let mut cache = checker.vendored_cache.lock().unwrap();
+        assert_eq!(cache.len(), 2);
+        // The least recently checked directory was evicted
+        assert_eq!(cache.get(&temp_dir.path().join("a")), None);
+        assert_eq!(cache.get(&temp_dir.path().join("c")), Some(None));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_checker_shared_across_threads() -> Result<()> {
+        fn assert_shareable<T: Clone + Send + Sync>() {}
+        assert_shareable::<GuardrailsChecker>();
+
+        let checker = GuardrailsChecker::from_config(default_config())?.into_shared();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let checker = GuardrailsChecker::clone(&checker);
+                std::thread::spawn(move || checker.should_exclude(Path::new(".venv/test.py")))
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.join().expect("worker panicked")?);
+        }
+
+        // Clones share the compiled config rather than copying it
+        let clone = GuardrailsChecker::clone(&checker);
+        assert!(Arc::ptr_eq(
+            &clone.shared_config(),
+            &checker.shared_config()
+        ));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,543 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// How the `lint` command prints findings when linting files directly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LintOutputFormat {
+    /// Hook message (or plain finding lines for files given on the command line)
+    #[default]
+    Text,
+    /// GitHub Actions `::error` workflow commands
+    Github,
+}
+
+impl std::str::FromStr for LintOutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LintOutputFormat::Text),
+            "github" => Ok(LintOutputFormat::Github),
+            other => Err(format!(
+                "unknown lint format '{other}' (expected text or github)"
+            )),
+        }
+    }
+}
+
+/// Linter output with some findings removed
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilteredLintOutput {
+    /// Remaining output (summary lines are kept as-is)
+    pub output: String,
+    /// Findings left after filtering
+    pub remaining: usize,
+    /// Findings dropped
+    pub ignored: usize,
+}
+
+impl FilteredLintOutput {
+    /// Every finding the linter reported was dropped
+    pub fn only_ignored(&self) -> bool {
+        self.ignored > 0 && self.remaining == 0
+    }
+}
+
+/// `path:line:col: CODE message` or `path:line: message`
+pub(crate) fn is_diagnostic(line: &str) -> bool {
+    let mut parts = line.splitn(3, ':');
+    matches!(
+        (parts.next(), parts.next(), parts.next()),
+        (Some(path), Some(line_no), Some(_))
+            if !path.trim().is_empty() && line_no.trim().parse::<u32>().is_ok()
+    )
+}
+
+/// Header of a multi-line finding, e.g. ruff's full output `F401 [*] `os` imported but unused`
+fn is_rule_header(line: &str) -> bool {
+    rule_code_regex().is_match(line)
+}
+
+fn rule_code_regex() -> &'static Regex {
+    static RULE_CODE: OnceLock<Regex> = OnceLock::new();
+    RULE_CODE.get_or_init(|| Regex::new(r"^[A-Z]+[0-9]+\b").expect("valid rule code regex"))
+}
+
+/// How much a finding matters, judged from its rule code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintSeverity {
+    /// Code that can't run as written: syntax errors, undefined names, pylint errors
+    Error,
+    /// Likely bugs and risky patterns
+    Warning,
+    /// Formatting, naming, import order and docstring conventions
+    Style,
+}
+
+/// A single linter finding
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct LintFinding {
+    /// File the finding was reported for, as printed by the linter
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    /// Rule code, e.g. `E501`
+    pub rule: Option<String>,
+    /// Finding message without location and rule code
+    pub message: String,
+    /// First line of the finding as printed
+    pub header: String,
+}
+
+/// Split `path:line[:col][: rest]` into its parts
+fn split_location(text: &str) -> (String, Option<u32>, Option<u32>, String) {
+    let mut parts = text.split(':').peekable();
+    let file = parts.next().unwrap_or_default().trim().to_string();
+    let mut position = || {
+        let number = parts.peek()?.trim().parse::<u32>().ok()?;
+        parts.next();
+        Some(number)
+    };
+    let line = position();
+    let column = position();
+    (file, line, column, parts.collect::<Vec<_>>().join(":"))
+}
+
+impl LintFinding {
+    fn parse(header: &str) -> Self {
+        let (file, line, column, rest) = if is_diagnostic(header) {
+            let (file, line, column, rest) = split_location(header);
+            (Some(file), line, column, rest)
+        } else {
+            (None, None, None, header.to_string())
+        };
+
+        let rest = rest.trim();
+        let (rule, message) = match rest.split_once(char::is_whitespace) {
+            Some((code, message)) if rule_code_regex().is_match(code) => (
+                Some(code.trim_end_matches(':').to_string()),
+                message.trim_start_matches("[*]").trim(),
+            ),
+            _ => (None, rest),
+        };
+
+        Self {
+            file,
+            line,
+            column,
+            rule,
+            message: message.to_string(),
+            header: header.to_string(),
+        }
+    }
+
+    /// Severity from the rule code: pycodestyle/isort/pydocstyle/pep8-naming rules and
+    /// pylint conventions are style, syntax errors (E9, F63, F7), undefined names (F82)
+    /// and pylint errors are errors, everything else is a warning
+    pub fn severity(&self) -> LintSeverity {
+        let Some(rule) = &self.rule else {
+            return if self.message.starts_with("SyntaxError") {
+                LintSeverity::Error
+            } else {
+                LintSeverity::Warning
+            };
+        };
+        let prefix: String = rule.chars().take_while(char::is_ascii_uppercase).collect();
+        let digits = &rule[prefix.len()..];
+
+        // pylint: four-digit codes, e.g. E0602 or C0301
+        if digits.len() == 4 && prefix.len() == 1 {
+            return match prefix.as_str() {
+                "E" | "F" => LintSeverity::Error,
+                "C" | "R" => LintSeverity::Style,
+                _ => LintSeverity::Warning,
+            };
+        }
+
+        match prefix.as_str() {
+            "E" if digits.starts_with('9') => LintSeverity::Error,
+            "F" if ["63", "7", "82"]
+                .iter()
+                .any(|group| digits.starts_with(group)) =>
+            {
+                LintSeverity::Error
+            }
+            "E" | "W" | "I" | "D" | "N" | "Q" | "COM" | "ISC" => LintSeverity::Style,
+            _ => LintSeverity::Warning,
+        }
+    }
+
+    /// GitHub Actions workflow command that annotates the finding on the PR diff
+    pub fn to_github_annotation(&self) -> String {
+        let mut properties = Vec::new();
+        if let Some(file) = &self.file {
+            properties.push(format!("file={}", escape_github_property(file)));
+        }
+        if let Some(line) = self.line {
+            properties.push(format!("line={line}"));
+        }
+        if let Some(column) = self.column {
+            properties.push(format!("col={column}"));
+        }
+        if let Some(rule) = &self.rule {
+            properties.push(format!("title={}", escape_github_property(rule)));
+        }
+
+        format!(
+            "::error {}::{}",
+            properties.join(","),
+            escape_github_data(&self.message)
+        )
+    }
+
+    /// Whether the finding is for one of the rules, by code (`E501`, `C0301`) or by
+    /// name (`line-too-long`, `no-unused-vars`)
+    pub fn mentions_rule(&self, rules: &[String]) -> bool {
+        self.header
+            .split_whitespace()
+            .map(|token| token.trim_matches(|c: char| ":()[],".contains(c)))
+            .any(|token| rules.iter().any(|rule| rule == token))
+    }
+
+    /// Stable identity across runs: rule, file and the message with numbers masked
+    pub fn fingerprint(&self) -> String {
+        static NUMBERS: OnceLock<Regex> = OnceLock::new();
+        let numbers = NUMBERS.get_or_init(|| Regex::new(r"[0-9]+").expect("valid number regex"));
+        let message = self
+            .message
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!(
+            "{}|{}|{}",
+            self.rule.as_deref().unwrap_or(""),
+            self.file.as_deref().unwrap_or(""),
+            numbers.replace_all(&message.to_lowercase(), "#")
+        )
+    }
+}
+
+/// Findings in linter output
+pub fn parse_findings(output: &str) -> Vec<LintFinding> {
+    let mut findings = Vec::new();
+    retain_findings(output, |finding| {
+        findings.push(finding.clone());
+        true
+    });
+    findings
+}
+
+/// Drop the findings for `ignore_rules` from linter output
+pub fn filter_ignored_rules(output: &str, ignore_rules: &[String]) -> FilteredLintOutput {
+    retain_findings(output, |finding| !finding.mentions_rule(ignore_rules))
+}
+
+/// Keep only the findings `keep` accepts, along with all non-finding output
+///
+/// Understands one-line diagnostics (ruff concise, flake8, pylint, eslint compact) and
+/// ruff's full format, where a finding runs from its rule code to the next blank line.
+/// For the full format the file is taken from the finding's ` --> path:line:col` line.
+pub fn retain_findings(
+    output: &str,
+    mut keep: impl FnMut(&LintFinding) -> bool,
+) -> FilteredLintOutput {
+    let lines: Vec<&str> = output.lines().collect();
+    let mut kept = Vec::new();
+    let mut remaining = 0;
+    let mut ignored = 0;
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+        let top_level = !line.starts_with(char::is_whitespace);
+        if !(top_level && (is_diagnostic(line) || is_rule_header(line))) {
+            kept.push(line);
+            index += 1;
+            continue;
+        }
+
+        // A multi-line finding runs to the next blank line or finding
+        let mut end = index + 1;
+        if is_rule_header(line) {
+            while end < lines.len()
+                && !lines[end].trim().is_empty()
+                && !(!lines[end].starts_with(char::is_whitespace)
+                    && (is_diagnostic(lines[end]) || is_rule_header(lines[end])))
+            {
+                end += 1;
+            }
+        }
+
+        let mut finding = LintFinding::parse(line);
+        if finding.file.is_none() {
+            let location = lines[index + 1..end]
+                .iter()
+                .find_map(|line| line.trim().strip_prefix("-->"));
+            if let Some(location) = location {
+                let (file, line, column, _) = split_location(location.trim());
+                finding.file = Some(file);
+                finding.line = line;
+                finding.column = column;
+            }
+        }
+
+        if keep(&finding) {
+            remaining += 1;
+            kept.extend(&lines[index..end]);
+        } else {
+            ignored += 1;
+        }
+        index = end;
+    }
+
+    FilteredLintOutput {
+        output: kept.join("\n").trim().to_string(),
+        remaining,
+        ignored,
+    }
+}
+
+/// One entry of `ruff check --output-format json`
+#[derive(Debug, Deserialize)]
+struct RuffDiagnostic {
+    /// `None` for syntax errors
+    code: Option<String>,
+    message: String,
+    filename: String,
+    location: RuffLocation,
+    #[serde(default)]
+    fix: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuffLocation {
+    row: u32,
+    column: u32,
+}
+
+/// Render ruff's JSON diagnostics as concise `path:line:col: CODE message` lines (paths
+/// relative to `root`), or `None` when the output isn't ruff JSON
+pub fn render_ruff_json(output: &str, root: &Path) -> Option<String> {
+    let trimmed = output.trim();
+    if !trimmed.starts_with('[') {
+        return None;
+    }
+    let diagnostics: Vec<RuffDiagnostic> = serde_json::from_str(trimmed).ok()?;
+
+    let mut lines: Vec<String> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let file = Path::new(&diagnostic.filename);
+            let file = file.strip_prefix(root).unwrap_or(file).display();
+            let location = format!(
+                "{file}:{}:{}",
+                diagnostic.location.row, diagnostic.location.column
+            );
+            match &diagnostic.code {
+                Some(code) => {
+                    let fixable = if diagnostic.fix.is_some() { "[*] " } else { "" };
+                    format!("{location}: {code} {fixable}{}", diagnostic.message)
+                }
+                None if diagnostic.message.starts_with("SyntaxError") => {
+                    format!("{location}: {}", diagnostic.message)
+                }
+                None => format!("{location}: SyntaxError: {}", diagnostic.message),
+            }
+        })
+        .collect();
+    if !lines.is_empty() {
+        lines.push(format!("Found {} error(s).", diagnostics.len()));
+    }
+    Some(lines.join("\n"))
+}
+
+/// `2 error(s), 1 warning(s), 3 style` for the findings present
+pub fn severity_summary(findings: &[LintFinding]) -> String {
+    [
+        (LintSeverity::Error, "error(s)"),
+        (LintSeverity::Warning, "warning(s)"),
+        (LintSeverity::Style, "style"),
+    ]
+    .iter()
+    .filter_map(|(severity, label)| {
+        let count = findings
+            .iter()
+            .filter(|finding| finding.severity() == *severity)
+            .count();
+        (count > 0).then(|| format!("{count} {label}"))
+    })
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Escape the message of a GitHub workflow command
+fn escape_github_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property value of a GitHub workflow command
+fn escape_github_property(text: &str) -> String {
+    escape_github_data(text)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(rules: &[&str]) -> Vec<String> {
+        rules.iter().map(|rule| rule.to_string()).collect()
+    }
+
+    #[test]
+    fn test_filter_one_line_diagnostics() {
+        let output = "app.py:1:8: F401 [*] `os` imported but unused\n\
+                      app.py:3:89: E501 Line too long (120 > 88)\n\
+                      Found 2 errors.";
+
+        let filtered = filter_ignored_rules(output, &rules(&["E501"]));
+        assert_eq!(filtered.remaining, 1);
+        assert_eq!(filtered.ignored, 1);
+        assert!(!filtered.only_ignored());
+        assert!(!filtered.output.contains("E501"));
+        assert!(filtered.output.contains("F401"));
+
+        let filtered = filter_ignored_rules(output, &rules(&["E501", "F401"]));
+        assert!(filtered.only_ignored());
+
+        // Pylint symbolic names match too
+        let filtered = filter_ignored_rules(
+            "app.py:3:0: C0301: Line too long (120/100) (line-too-long)",
+            &rules(&["line-too-long"]),
+        );
+        assert!(filtered.only_ignored());
+    }
+
+    #[test]
+    fn test_filter_ruff_full_format() {
+        let output = "E501 Line too long (120 > 88)\n \
+                      --> app.py:3:89\n  \
+                      |\n\n\
+                      F401 [*] `os` imported but unused\n \
+                      --> app.py:1:8\n\
+                      help: Remove unused import: `os`\n\n\
+                      Found 2 errors.";
+
+        let filtered = filter_ignored_rules(output, &rules(&["E501"]));
+        assert_eq!(filtered.remaining, 1);
+        assert!(!filtered.output.contains("app.py:3:89"));
+        assert!(filtered.output.contains("help: Remove unused import"));
+        assert!(filtered.output.contains("Found 2 errors."));
+    }
+
+    #[test]
+    fn test_parse_findings() {
+        let findings = parse_findings(
+            "app.py:3:0: C0301: Line too long (120/100) (line-too-long)\n\
+             E501 Line too long (120 > 88)\n \
+             --> src/app.py:3:89\n\n\
+             Found 2 errors.",
+        );
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].file.as_deref(), Some("app.py"));
+        assert_eq!(findings[0].rule.as_deref(), Some("C0301"));
+        assert_eq!(
+            findings[0].message,
+            "Line too long (120/100) (line-too-long)"
+        );
+        assert_eq!(findings[1].file.as_deref(), Some("src/app.py"));
+        assert_eq!(findings[1].rule.as_deref(), Some("E501"));
+        assert_eq!((findings[1].line, findings[1].column), (Some(3), Some(89)));
+
+        // Fingerprints ignore positions and numbers in the message
+        let moved = parse_findings("app.py:9:0: C0301: Line too long (140/100) (line-too-long)");
+        assert_eq!(findings[0].fingerprint(), moved[0].fingerprint());
+        assert_ne!(findings[0].fingerprint(), findings[1].fingerprint());
+    }
+
+    #[test]
+    fn test_github_annotation() {
+        let findings = parse_findings("src/app.py:3:89: E501 Line too long (120 > 88)");
+        assert_eq!(
+            findings[0].to_github_annotation(),
+            "::error file=src/app.py,line=3,col=89,title=E501::Line too long (120 > 88)"
+        );
+
+        let findings = parse_findings("app.py:7: 100% sure, this is wrong");
+        assert_eq!(
+            findings[0].to_github_annotation(),
+            "::error file=app.py,line=7::100%25 sure, this is wrong"
+        );
+    }
+
+    #[test]
+    fn test_render_ruff_json() {
+        let output = r#"[
+  {"code": "F401", "message": "`os` imported but unused", "filename": "/work/app/app.py",
+   "location": {"row": 1, "column": 8}, "fix": {"applicability": "safe", "edits": []}},
+  {"code": null, "message": "Expected an expression", "filename": "/work/app/app.py",
+   "location": {"row": 4, "column": 9}, "fix": null}
+]"#;
+        let rendered = render_ruff_json(output, Path::new("/work/app")).unwrap();
+        assert_eq!(
+            rendered,
+            "app.py:1:8: F401 [*] `os` imported but unused\n\
+             app.py:4:9: SyntaxError: Expected an expression\n\
+             Found 2 error(s)."
+        );
+
+        let findings = parse_findings(&rendered);
+        assert_eq!(findings[0].rule.as_deref(), Some("F401"));
+        assert_eq!(findings[0].message, "`os` imported but unused");
+        assert_eq!(findings[1].severity(), LintSeverity::Error);
+
+        assert_eq!(render_ruff_json("[]", Path::new(".")).as_deref(), Some(""));
+        assert!(render_ruff_json("app.py:1:8: F401 unused", Path::new(".")).is_none());
+    }
+
+    #[test]
+    fn test_severity() {
+        let findings = parse_findings(
+            "app.py:1:1: F821 Undefined name `foo`\n\
+             app.py:2:1: E999 SyntaxError: invalid syntax\n\
+             app.py:3:0: E0602: Undefined variable 'foo' (undefined-variable)\n\
+             app.py:4:1: F401 `os` imported but unused\n\
+             app.py:5:1: B006 Do not use mutable data structures for argument defaults\n\
+             app.py:6:89: E501 Line too long (120 > 88)\n\
+             app.py:7:1: I001 Import block is un-sorted or un-formatted\n\
+             app.py:8:0: C0301: Line too long (120/100) (line-too-long)",
+        );
+        let severities: Vec<LintSeverity> = findings.iter().map(LintFinding::severity).collect();
+        assert_eq!(
+            severities,
+            vec![
+                LintSeverity::Error,
+                LintSeverity::Error,
+                LintSeverity::Error,
+                LintSeverity::Warning,
+                LintSeverity::Warning,
+                LintSeverity::Style,
+                LintSeverity::Style,
+                LintSeverity::Style,
+            ]
+        );
+        assert_eq!(
+            severity_summary(&findings),
+            "3 error(s), 2 warning(s), 3 style"
+        );
+    }
+
+    #[test]
+    fn test_no_findings_is_not_only_ignored() {
+        let filtered =
+            filter_ignored_rules("error: failed to parse pyproject.toml", &rules(&["E501"]));
+        assert_eq!(filtered.remaining, 0);
+        assert!(!filtered.only_ignored());
+        assert_eq!(filtered.output, "error: failed to parse pyproject.toml");
+    }
+}
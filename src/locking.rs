@@ -1,9 +1,36 @@
-use anyhow::{Context, Result};
+use crate::state::StateDir;
+use crate::{GuardrailsError, GuardrailsResult};
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a waiting hook checks whether the lock was released
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What a cooldown lock covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CooldownScope {
+    /// One run per workspace at a time; an edit suppresses checks for any other file
+    #[default]
+    Project,
+    /// Separate lock per edited file
+    File,
+}
+
+/// Snapshot of a lock: whether the next hook run would be skipped, and why
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LockStatus {
+    /// Another process is running the operation
+    pub running: bool,
+    /// Seconds until the cooldown after the last completed run expires
+    pub cooldown_remaining_seconds: u64,
+}
 
 /// Manages PID-based locking to prevent concurrent operations
 pub struct ProcessLock {
@@ -14,10 +41,26 @@ pub struct ProcessLock {
 
 impl ProcessLock {
     /// Create a new process lock for the given workspace and operation
-    pub fn new(workspace_dir: &Path, operation: &str, cooldown_seconds: u64) -> Result<Self> {
-        let workspace_hash = Self::hash_workspace(workspace_dir)?;
-        let lock_file_name = format!("claude-python-guardrails-{operation}-{workspace_hash}.lock");
-        let lock_file = PathBuf::from("/tmp").join(lock_file_name);
+    pub fn new(
+        workspace_dir: &Path,
+        operation: &str,
+        cooldown_seconds: u64,
+    ) -> GuardrailsResult<Self> {
+        Self::for_file(workspace_dir, operation, None, cooldown_seconds)
+    }
+
+    /// Create a process lock that only covers `file` when one is given
+    pub fn for_file(
+        workspace_dir: &Path,
+        operation: &str,
+        file: Option<&Path>,
+        cooldown_seconds: u64,
+    ) -> GuardrailsResult<Self> {
+        let lock_file_name = match file {
+            Some(file) => format!("{operation}-{}.lock", Self::hash_workspace(file)?),
+            None => format!("{operation}.lock"),
+        };
+        let lock_file = StateDir::for_workspace(workspace_dir)?.file(&lock_file_name);
 
         Ok(Self {
             lock_file,
@@ -27,35 +70,53 @@ impl ProcessLock {
     }
 
     /// Check if we should skip execution due to another running process or recent completion
-    pub fn should_skip(&self) -> Result<bool> {
+    pub fn should_skip(&self) -> GuardrailsResult<bool> {
+        Ok(self.is_held()? || self.in_cooldown()?)
+    }
+
+    fn read_lines(&self) -> GuardrailsResult<Vec<String>> {
         if !self.lock_file.exists() {
-            return Ok(false);
+            return Ok(vec![]);
         }
+        let lock_content = fs::read_to_string(&self.lock_file)
+            .map_err(|e| GuardrailsError::io(&self.lock_file, e))?;
+        Ok(lock_content.lines().map(str::to_string).collect())
+    }
 
-        let lock_content =
-            fs::read_to_string(&self.lock_file).context("Failed to read lock file")?;
-
-        let lines: Vec<&str> = lock_content.lines().collect();
-
-        // Check if another process is running (PID in first line)
+    /// Whether another running process holds the lock (PID in first line)
+    fn is_held(&self) -> GuardrailsResult<bool> {
+        let lines = self.read_lines()?;
         if let Some(pid_line) = lines.first() {
             if let Ok(pid) = pid_line.trim().parse::<u32>() {
                 if Self::is_process_running(pid) {
-                    log::debug!(
-                        "{} is already running (PID: {}), skipping",
-                        self.operation,
-                        pid
-                    );
+                    log::debug!("{} is already running (PID: {})", self.operation, pid);
                     return Ok(true);
                 }
             }
         }
+        Ok(false)
+    }
+
+    /// Whether the last run completed within the cooldown (timestamp in second line)
+    fn in_cooldown(&self) -> GuardrailsResult<bool> {
+        Ok(self.cooldown_remaining()? > 0)
+    }
 
-        // Check completion timestamp (second line)
+    /// Seconds left before the cooldown after the last completed run expires (0 when
+    /// there's no cooldown)
+    fn cooldown_remaining(&self) -> GuardrailsResult<u64> {
+        let lines = self.read_lines()?;
         if let Some(timestamp_line) = lines.get(1) {
             if let Ok(timestamp) = timestamp_line.trim().parse::<i64>() {
-                let completion_time = DateTime::from_timestamp(timestamp, 0)
-                    .ok_or_else(|| anyhow::anyhow!("Invalid timestamp in lock file"))?;
+                let completion_time = DateTime::from_timestamp(timestamp, 0).ok_or_else(|| {
+                    GuardrailsError::io(
+                        &self.lock_file,
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "invalid completion timestamp",
+                        ),
+                    )
+                })?;
 
                 let now = Utc::now();
                 let duration_since_completion = now.signed_duration_since(completion_time);
@@ -67,41 +128,69 @@ impl ProcessLock {
                         duration_since_completion.num_seconds(),
                         self.cooldown_seconds
                     );
-                    return Ok(true);
+                    return Ok((self.cooldown_seconds as i64
+                        - duration_since_completion.num_seconds())
+                        as u64);
                 }
             }
         }
 
-        Ok(false)
+        Ok(0)
+    }
+
+    /// Whether a run is in flight and how long its cooldown lasts, without acquiring
+    pub fn status(&self) -> GuardrailsResult<LockStatus> {
+        Ok(LockStatus {
+            running: self.is_held()?,
+            cooldown_remaining_seconds: self.cooldown_remaining()?,
+        })
+    }
+
+    /// Wait until no other process holds the lock, for at most `timeout`; returns whether
+    /// the lock was released in time
+    pub fn wait_for_release(&self, timeout: Duration) -> GuardrailsResult<bool> {
+        let deadline = Instant::now() + timeout;
+        while self.is_held()? {
+            if Instant::now() >= deadline {
+                log::debug!(
+                    "{} still running after waiting {}s, proceeding",
+                    self.operation,
+                    timeout.as_secs()
+                );
+                return Ok(false);
+            }
+            thread::sleep(LOCK_POLL_INTERVAL);
+        }
+        Ok(true)
     }
 
     /// Acquire the lock by writing our PID to the lock file
-    pub fn acquire(&self) -> Result<()> {
+    pub fn acquire(&self) -> GuardrailsResult<()> {
         let pid = process::id();
-        fs::write(&self.lock_file, pid.to_string()).context("Failed to write PID to lock file")?;
+        fs::write(&self.lock_file, pid.to_string())
+            .map_err(|e| GuardrailsError::io(&self.lock_file, e))?;
 
         log::debug!("Acquired lock for {} (PID: {})", self.operation, pid);
         Ok(())
     }
 
     /// Release the lock by clearing PID and writing completion timestamp
-    pub fn release(&self) -> Result<()> {
+    pub fn release(&self) -> GuardrailsResult<()> {
         let now = Utc::now();
         let timestamp = now.timestamp();
 
         let content = format!("\n{timestamp}");
-        fs::write(&self.lock_file, content)
-            .context("Failed to write completion timestamp to lock file")?;
+        fs::write(&self.lock_file, content).map_err(|e| GuardrailsError::io(&self.lock_file, e))?;
 
         log::debug!("Released lock for {} at {}", self.operation, now);
         Ok(())
     }
 
-    /// Generate a hash of the workspace directory for unique lock files
-    fn hash_workspace(workspace_dir: &Path) -> Result<String> {
+    /// Generate a hash of the workspace directory (or file) for unique lock files
+    pub(crate) fn hash_workspace(workspace_dir: &Path) -> GuardrailsResult<String> {
         let absolute_path = workspace_dir
             .canonicalize()
-            .context("Failed to canonicalize workspace path")?;
+            .map_err(|e| GuardrailsError::io(workspace_dir, e))?;
 
         let mut hasher = Sha256::new();
         hasher.update(absolute_path.to_string_lossy().as_bytes());
@@ -145,11 +234,38 @@ impl LockGuard {
         workspace_dir: &Path,
         operation: &str,
         cooldown_seconds: u64,
-    ) -> Result<Option<Self>> {
-        let lock = ProcessLock::new(workspace_dir, operation, cooldown_seconds)?;
+    ) -> GuardrailsResult<Option<Self>> {
+        Self::try_acquire_scoped(
+            workspace_dir,
+            operation,
+            CooldownScope::Project,
+            workspace_dir,
+            cooldown_seconds,
+            Duration::ZERO,
+        )
+    }
 
-        if lock.should_skip()? {
-            return Ok(None);
+    /// Try to acquire a lock covering the workspace or only `file`, depending on `scope`
+    ///
+    /// With a non-zero `wait`, a lock held by another run is waited for (up to `wait`)
+    /// and the cooldown is ignored, so every edit gets feedback.
+    pub fn try_acquire_scoped(
+        workspace_dir: &Path,
+        operation: &str,
+        scope: CooldownScope,
+        file: &Path,
+        cooldown_seconds: u64,
+        wait: Duration,
+    ) -> GuardrailsResult<Option<Self>> {
+        let file = (scope == CooldownScope::File).then_some(file);
+        let lock = ProcessLock::for_file(workspace_dir, operation, file, cooldown_seconds)?;
+
+        if wait.is_zero() {
+            if lock.should_skip()? {
+                return Ok(None);
+            }
+        } else {
+            lock.wait_for_release(wait)?;
         }
 
         lock.acquire()?;
@@ -168,8 +284,7 @@ impl Drop for LockGuard {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::thread;
-    use std::time::Duration;
+    use anyhow::Result;
     use tempfile::TempDir;
 
     #[test]
@@ -177,11 +292,10 @@ mod tests {
         let temp_dir = TempDir::new()?;
         let lock = ProcessLock::new(temp_dir.path(), "test", 5)?;
 
-        assert!(lock
-            .lock_file
-            .to_string_lossy()
-            .contains("claude-python-guardrails-test-"));
-        assert!(lock.lock_file.to_string_lossy().contains(".lock"));
+        assert_eq!(
+            lock.lock_file,
+            temp_dir.path().join(".claude-guardrails/test.lock")
+        );
         Ok(())
     }
 
@@ -249,6 +363,29 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_lock_status() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let lock = ProcessLock::new(temp_dir.path(), "test", 60)?;
+        assert_eq!(
+            lock.status()?,
+            LockStatus {
+                running: false,
+                cooldown_remaining_seconds: 0
+            }
+        );
+
+        lock.acquire()?;
+        assert!(lock.status()?.running);
+
+        lock.release()?;
+        let status = lock.status()?;
+        assert!(!status.running);
+        assert!((59..=60).contains(&status.cooldown_remaining_seconds));
+
+        Ok(())
+    }
+
     #[test]
     fn test_lock_guard() -> Result<()> {
         let temp_dir = TempDir::new()?;
@@ -270,4 +407,64 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_file_scoped_locks() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let file_a = temp_dir.path().join("a.py");
+        let file_b = temp_dir.path().join("b.py");
+        fs::write(&file_a, "")?;
+        fs::write(&file_b, "")?;
+
+        let acquire = |scope, file: &Path| {
+            LockGuard::try_acquire_scoped(
+                temp_dir.path(),
+                "scoped",
+                scope,
+                file,
+                10,
+                Duration::ZERO,
+            )
+        };
+
+        // File scope: a cooldown on one file doesn't suppress another
+        drop(acquire(CooldownScope::File, &file_a)?);
+        assert!(acquire(CooldownScope::File, &file_a)?.is_none());
+        assert!(acquire(CooldownScope::File, &file_b)?.is_some());
+
+        // Project scope: any run suppresses the next one
+        drop(acquire(CooldownScope::Project, &file_a)?);
+        assert!(acquire(CooldownScope::Project, &file_b)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wait_for_release() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let lock = ProcessLock::new(temp_dir.path(), "wait", 10)?;
+
+        // Held by a live process that isn't us
+        let mut holder = process::Command::new("sleep").arg("5").spawn()?;
+        fs::write(&lock.lock_file, holder.id().to_string())?;
+        let started = Instant::now();
+        assert!(!lock.wait_for_release(Duration::from_millis(300))?);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+
+        // Waiting ignores the cooldown and proceeds once the holder is gone
+        holder.kill()?;
+        holder.wait()?;
+        lock.release()?;
+        let guard = LockGuard::try_acquire_scoped(
+            temp_dir.path(),
+            "wait",
+            CooldownScope::Project,
+            temp_dir.path(),
+            10,
+            Duration::from_secs(1),
+        )?;
+        assert!(guard.is_some());
+
+        Ok(())
+    }
 }
@@ -1,10 +1,19 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use claude_python_guardrails::testgen::default_test_path;
+use claude_python_guardrails::update;
 use claude_python_guardrails::{
-    default_config, AutomationConfig, AutomationRunner, CerebrasConfig, ExclusionAnalysis,
-    GuardrailsChecker, HookInput, SmartExclusionAnalyzer,
+    apply_unified_diff, crash, default_config, heuristic_analysis, messages, AiProvider,
+    AnalysisKind, AuditLog, AutomationConfig, AutomationReport, AutomationRunner, CerebrasConfig,
+    CommandInspector, ExclusionAnalysis, FileWatcher, GuardrailsChecker, GuardrailsConfig, History,
+    HookInput, HookOutput, HookRecorder, LintOutputFormat, PythonEnvironment, PythonProject,
+    RecordedEvent, ReleaseClient, ReloadableChecker, ReportFormat, RunReport,
+    SmartExclusionAnalyzer, StateDir, Suppressions, TestFramework, CONFIG_FILE_NAME, RECORD_ENV,
 };
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Claude Code Python automation hooks - AI-powered linting and testing automation
 #[derive(Parser)]
@@ -20,6 +29,14 @@ struct Cli {
     /// Verbose output
     #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// Override the AI provider from the config (cerebras, ollama, llamacpp, mock)
+    #[arg(long, global = true, value_name = "PROVIDER")]
+    ai: Option<AiProvider>,
+
+    /// Read the hook event from this file instead of stdin, to replay a captured payload
+    #[arg(long, global = true, value_name = "PAYLOAD")]
+    input_file: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -29,15 +46,219 @@ enum Commands {
         /// Output format (json or text)
         #[arg(long, default_value = "text")]
         format: String,
+
+        /// Files to analyze together instead of the file from the hook input
+        files: Vec<PathBuf>,
+
+        /// Use the file-pattern heuristics only, even when an AI provider is configured;
+        /// nothing is sent over the network
+        #[arg(long)]
+        no_ai: bool,
     },
     /// Linting automation (reads Claude Code hook JSON from stdin)
-    Lint,
+    Lint {
+        /// Output format (text or github); github prints workflow annotations
+        #[arg(long, default_value = "text")]
+        format: LintOutputFormat,
+
+        /// Lint these files directly (no formatting, auto-fix or AI) instead of the hook's file
+        files: Vec<PathBuf>,
+
+        /// Print the run's full report (steps, durations, findings, AI analyses) as JSON on
+        /// stdout instead of the hook message
+        #[arg(long)]
+        json: bool,
+    },
     /// Testing automation (reads Claude Code hook JSON from stdin)
+    Test {
+        /// Print the run's full report as JSON on stdout instead of the hook message
+        #[arg(long)]
+        json: bool,
+    },
+    /// Mutation-testing spot checks on the edited module (reads Claude Code hook JSON from
+    /// stdin; needs `automation.mutate.enabled`)
+    #[command(alias = "smart-mutate")]
+    Mutate,
+    /// Enforce the tests-updated policy when Claude finishes (Stop hook, reads hook JSON
+    /// from stdin; needs `automation.test.require_updated_tests`)
+    Stop,
+    /// Deny edits to protected paths (PreToolUse hook, reads hook JSON from stdin)
+    Protect,
+    /// Flag dangerous Bash commands (PreToolUse hook, reads hook JSON from stdin)
+    CheckCommand,
+    /// Prime Claude with project context (SessionStart hook, reads hook JSON from stdin)
+    SessionStart,
+    /// Manage lint findings remembered as false positives
+    Suppressions {
+        #[command(subcommand)]
+        action: SuppressionsAction,
+
+        /// Project directory (defaults to the current directory)
+        #[arg(long, global = true)]
+        dir: Option<PathBuf>,
+    },
+    /// Inspect AI provider usage
+    Ai {
+        #[command(subcommand)]
+        action: AiAction,
+
+        /// Project directory (defaults to the current directory)
+        #[arg(long, global = true)]
+        dir: Option<PathBuf>,
+    },
+    /// Remove the project's guardrails state (locks, history, suppressions)
+    Clean {
+        /// Project directory (defaults to the current directory)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Summarize lint/test runs as Markdown or HTML (e.g. for a PR description)
+    Report {
+        /// Output format (markdown or html)
+        #[arg(long, default_value = "markdown")]
+        format: ReportFormat,
+
+        /// Session to report on (defaults to the most recent session)
+        #[arg(long)]
+        session: Option<String>,
+
+        /// Project directory (defaults to the current directory)
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+    /// Generate tests for a module with AI and write them to a test file
+    GenerateTests {
+        /// Module to write tests for
+        file: PathBuf,
+
+        /// Test file to write (defaults to tests/test_<module>.py in the project root)
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Test framework (pytest or unittest)
+        #[arg(long, default_value = "pytest")]
+        framework: TestFramework,
+
+        /// Print the generated tests instead of writing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Overwrite an existing test file
+        #[arg(long)]
+        force: bool,
+
+        /// Check the written tests at least import (pytest --collect-only / unittest)
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Fix the latest recorded lint or test failure of a file with an AI-proposed patch
+    Fix {
+        /// File to patch
+        file: PathBuf,
+
+        /// Which failure to fix
+        #[arg(long, value_enum)]
+        from: FixSource,
+
+        /// Apply the patch without asking
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Print JSON describing what the hooks would do for a file (exclusions, tools, mapped
+    /// test file, cooldowns), for editor integrations
+    Preview {
+        /// File to describe
+        file: PathBuf,
+    },
+    /// Run hook events recorded with `GUARDRAILS_RECORD=1` through the hooks again
+    Replay {
+        /// Recordings file, or a single captured payload (defaults to this project's
+        /// recordings)
+        file: Option<PathBuf>,
+
+        /// Replay only this event (numbered as in `--list`)
+        #[arg(long)]
+        event: Option<usize>,
+
+        /// List the recorded events instead of replaying them
+        #[arg(long)]
+        list: bool,
+    },
+    /// Replace this binary with a release from GitHub, verified against its SHA-256
+    /// checksum (installs `update.pin` from guardrails.yaml when set, else the latest)
+    SelfUpdate {
+        /// Install exactly this version, e.g. `1.2.0`
+        #[arg(long, value_name = "VERSION")]
+        pin: Option<String>,
+
+        /// Only report whether another version would be installed (exit 1 if so)
+        #[arg(long)]
+        check: bool,
+
+        /// Reinstall even when the version is already installed
+        #[arg(long)]
+        force: bool,
+    },
+    /// Lint and test files as they change, outside of Claude Code
+    Watch {
+        /// Project directory to watch (defaults to the current directory)
+        path: Option<PathBuf>,
+
+        /// How long to wait for further changes before running, in milliseconds
+        #[arg(long, default_value_t = 200)]
+        debounce_ms: u64,
+    },
+}
+
+impl Commands {
+    /// Whether the command runs as a Claude Code hook, where any failure other than a
+    /// deliberate block must exit 0
+    fn is_hook(&self) -> bool {
+        match self {
+            Commands::Lint { format, files, .. } => {
+                *format != LintOutputFormat::Github && files.is_empty()
+            }
+            Commands::Test { .. }
+            | Commands::Mutate
+            | Commands::Stop
+            | Commands::Protect
+            | Commands::CheckCommand
+            | Commands::SessionStart => true,
+            _ => false,
+        }
+    }
+}
+
+/// Recorded failure the `fix` command addresses
+#[derive(Clone, Copy, ValueEnum)]
+enum FixSource {
+    Lint,
     Test,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+#[derive(Subcommand)]
+enum SuppressionsAction {
+    /// List remembered false positives
+    List,
+    /// Forget all remembered false positives
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum AiAction {
+    /// Show recent AI requests from the audit log (enable with `ai.audit_log: true`)
+    Audit {
+        /// Number of most recent requests to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+
+        /// Print records as JSON lines
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn main() -> Result<()> {
     // Initialize logging (safe to call multiple times)
     if std::env::var("RUST_LOG").is_err() {
         std::env::set_var("RUST_LOG", "info");
@@ -46,108 +267,964 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
+    // A panic must not surface as exit 101 and a backtrace, which Claude reads as a
+    // failed check: hooks log it to the state directory and do nothing instead, other
+    // commands exit 1 like any other error (2 is reserved for blocking feedback)
+    crash::install_panic_hook();
+    let hook = cli.command.is_hook();
+    let outcome = crash::catch_panic(|| {
+        if let Ok(root) = project_root(None) {
+            crash::set_workspace(&root);
+        }
+        #[cfg(debug_assertions)]
+        if std::env::var_os("GUARDRAILS_DEBUG_PANIC").is_some() {
+            panic!("GUARDRAILS_DEBUG_PANIC is set");
+        }
+        tokio::runtime::Runtime::new()
+            .context("Failed to start async runtime")?
+            .block_on(run(cli))
+    });
+    match outcome {
+        Ok(result) => result,
+        Err(caught) => {
+            eprintln!(
+                "{}",
+                messages::internal_error(&caught.message, caught.log_path.as_deref())
+            );
+            std::process::exit(if hook { 0 } else { 1 })
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
     match cli.command {
-        Commands::Analyze { ref format } => handle_analyze_command(&cli, format).await,
+        Commands::Analyze {
+            ref format,
+            ref files,
+            no_ai,
+        } => handle_analyze_command(&cli, format, files, no_ai).await,
 
-        Commands::Lint => {
-            let result = handle_smart_automation(&cli, "lint").await?;
-            if let Some(message) = result.message() {
-                eprintln!("{message}");
-            }
-            std::process::exit(result.exit_code());
+        Commands::Lint {
+            format, ref files, ..
+        } if format == LintOutputFormat::Github || !files.is_empty() => {
+            handle_lint_findings_command(&cli, format, files)
         }
 
-        Commands::Test => {
-            let result = handle_smart_automation(&cli, "test").await?;
-            if let Some(message) = result.message() {
-                eprintln!("{message}");
-            }
-            std::process::exit(result.exit_code());
+        Commands::Lint { json, .. } => {
+            let report = handle_smart_automation(&cli, "lint").await?;
+            exit_with_report(&report, json)
         }
+
+        Commands::Test { json } => {
+            let report = handle_smart_automation(&cli, "test").await?;
+            exit_with_report(&report, json)
+        }
+
+        Commands::Mutate => {
+            let report = handle_smart_automation(&cli, "mutate").await?;
+            exit_with_result(&report.result)
+        }
+
+        Commands::Stop => {
+            let report = handle_smart_automation(&cli, "stop").await?;
+            exit_with_result(&report.result)
+        }
+
+        Commands::Protect => handle_protect_command(&cli),
+
+        Commands::CheckCommand => handle_check_command(&cli),
+
+        Commands::SessionStart => handle_session_start_command(&cli),
+
+        Commands::Suppressions {
+            ref action,
+            ref dir,
+        } => handle_suppressions_command(action, dir.as_deref()),
+
+        Commands::Ai {
+            ref action,
+            ref dir,
+        } => handle_ai_command(action, dir.as_deref()),
+
+        Commands::Clean { ref dir } => handle_clean_command(dir.as_deref()),
+
+        Commands::Report {
+            format,
+            ref session,
+            ref dir,
+        } => handle_report_command(format, session.as_deref(), dir.as_deref()),
+
+        Commands::GenerateTests {
+            ref file,
+            ref output,
+            framework,
+            dry_run,
+            force,
+            verify,
+        } => {
+            let options = GenerateTestsOptions {
+                output: output.as_deref(),
+                framework,
+                dry_run,
+                force,
+                verify,
+            };
+            handle_generate_tests_command(&cli, file, options).await
+        }
+
+        Commands::Fix {
+            ref file,
+            from,
+            yes,
+        } => handle_fix_command(&cli, file, from, yes).await,
+
+        Commands::Preview { ref file } => handle_preview_command(&cli, file),
+
+        Commands::Replay {
+            ref file,
+            event,
+            list,
+        } => handle_replay_command(file.as_deref(), event, list),
+
+        Commands::SelfUpdate {
+            ref pin,
+            check,
+            force,
+        } => handle_self_update_command(pin.as_deref(), check, force).await,
+
+        Commands::Watch {
+            ref path,
+            debounce_ms,
+        } => handle_watch_command(&cli, path.as_deref(), debounce_ms).await,
     }
 }
 
-fn get_default_checker() -> GuardrailsChecker {
-    // Always use hardcoded default configuration for hooks
-    GuardrailsChecker::from_config(default_config())
-        .expect("Default configuration should always be valid")
+/// Checker for the project `target` (the file or directory being processed) belongs to,
+/// see [`load_config`]
+fn checker_for(cli: &Cli, target: Option<&Path>) -> GuardrailsChecker {
+    let mut config = load_config(target);
+    if let Some(provider) = cli.ai {
+        config.ai.provider = Some(provider);
+    }
+
+    match GuardrailsChecker::from_config(config) {
+        Ok(checker) => checker,
+        Err(e) => {
+            eprintln!("⚠️  Ignoring invalid {CONFIG_FILE_NAME}: {e:#}");
+            GuardrailsChecker::from_config(default_config())
+                .expect("Default configuration should always be valid")
+        }
+    }
 }
 
-async fn handle_smart_automation(
-    _cli: &Cli,
-    operation: &str,
-) -> Result<claude_python_guardrails::AutomationResult> {
-    use claude_python_guardrails::AutomationResult;
+/// Configuration for `target` (an edited file, or the directory a command runs in; the
+/// working directory when `None`): the nearest guardrails.yaml between it and its project
+/// root
+///
+/// Hooks always pass their file or hook working directory, so the config follows the
+/// edited project rather than wherever Claude launched the hook. A project without a
+/// guardrails.yaml (or with an invalid one) explicitly gets the built-in defaults; a
+/// config elsewhere, e.g. in the process working directory, is never picked up.
+fn load_config(target: Option<&Path>) -> GuardrailsConfig {
+    let Some((config_path, root)) = config_path_for(target) else {
+        return default_config();
+    };
+    // Reuse the config parsed by a previous invocation while the file is unchanged
+    let loaded = match StateDir::for_workspace(&root) {
+        Ok(state) => GuardrailsConfig::from_file_cached(&config_path, &state),
+        Err(_) => GuardrailsConfig::from_file(&config_path),
+    };
+    loaded.unwrap_or_else(|e| {
+        eprintln!("⚠️  Ignoring invalid {}: {e:#}", config_path.display());
+        default_config()
+    })
+}
 
-    let checker = get_default_checker();
-    let automation_config = AutomationConfig::from(&checker.config().automation);
-    let runner = AutomationRunner::new(automation_config, checker);
+/// guardrails.yaml in `target`'s directory or an ancestor up to its project root, with
+/// that root
+fn config_path_for(target: Option<&Path>) -> Option<(PathBuf, PathBuf)> {
+    let cwd = std::env::current_dir().ok()?;
+    let target = target.map_or_else(|| cwd.clone(), |target| cwd.join(target));
+    let dir = existing_dir(&target)?;
+    let root = project_root(Some(dir)).ok()?;
+    let config_path = dir
+        .ancestors()
+        .take_while(|ancestor| ancestor.starts_with(&root))
+        .map(|ancestor| ancestor.join(CONFIG_FILE_NAME))
+        .find(|config_path| config_path.is_file())?;
+    Some((config_path, root))
+}
 
-    match operation {
-        "lint" => runner.handle_smart_lint().await,
-        "test" => runner.handle_smart_test().await,
-        _ => Ok(AutomationResult::NoAction),
+/// Hook event from `--input-file` or stdin; an unreadable input file is reported, not
+/// treated like an empty stdin
+fn read_hook_input(cli: &Cli) -> Result<HookInput> {
+    let Some(path) = &cli.input_file else {
+        let mut raw = String::new();
+        std::io::stdin()
+            .read_to_string(&mut raw)
+            .context("Failed to read from stdin")?;
+        if HookRecorder::enabled() && !raw.trim().is_empty() {
+            record_hook_input(&raw);
+        }
+        return Ok(HookInput::from_json(&raw)?);
+    };
+    let input = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))
+        .and_then(|file| Ok(HookInput::from_reader(file)?));
+    if let Err(e) = &input {
+        eprintln!(
+            "❌ Could not read hook input from {}: {e:#}",
+            path.display()
+        );
     }
+    input
 }
 
-async fn handle_analyze_command(cli: &Cli, format: &str) -> Result<()> {
-    // Read JSON input from stdin (Claude Code hook format)
-    let hook_input = match HookInput::from_stdin() {
+/// Archive a payload for `replay` (record mode); failures only cost the recording
+fn record_hook_input(raw: &str) {
+    let recorded = project_root(None).and_then(|root| {
+        let working_dir = std::env::current_dir()?;
+        let event = RecordedEvent::new(std::env::args().skip(1).collect(), working_dir, raw);
+        HookRecorder::for_workspace(&root)?.record(&event)
+    });
+    if let Err(e) = recorded {
+        eprintln!("⚠️  Could not record hook input: {e:#}");
+    }
+}
+
+fn handle_protect_command(cli: &Cli) -> Result<()> {
+    let hook_input = match read_hook_input(cli) {
         Ok(input) => input,
-        Err(_) => {
-            if cli.verbose {
-                eprintln!("ℹ️  No JSON input available on stdin.");
-            }
-            std::process::exit(0);
+        Err(_) => std::process::exit(0),
+    };
+
+    if !hook_input.is_pre_edit() {
+        if cli.verbose {
+            eprintln!("{}", messages::ignored_event(&hook_input.hook_event_name));
         }
+        std::process::exit(0);
+    }
+
+    let Some(file_path) = hook_input.file_path() else {
+        std::process::exit(0);
     };
 
-    // Only process PostToolUse events for edit tools
-    if !hook_input.should_process() {
+    // Patterns are relative to the root of the project the config came from
+    let target = hook_input.working_dir()?.join(&file_path);
+    let root = project_root(existing_dir(&target))?;
+    let checker = checker_for(cli, Some(&target));
+    if checker.is_protected_in(&target, &root) {
+        let reason = messages::render(
+            &messages::protected_path(&file_path, CONFIG_FILE_NAME),
+            checker.config().output.style,
+        );
+        println!("{}", HookOutput::deny(&reason).to_json()?);
+    }
+
+    std::process::exit(0);
+}
+
+fn handle_check_command(cli: &Cli) -> Result<()> {
+    let hook_input = match read_hook_input(cli) {
+        Ok(input) => input,
+        Err(_) => std::process::exit(0),
+    };
+
+    if !hook_input.is_pre_bash() {
         if cli.verbose {
-            eprintln!("ℹ️  Ignoring event type: {}", hook_input.hook_event_name);
+            eprintln!("{}", messages::ignored_event(&hook_input.hook_event_name));
         }
         std::process::exit(0);
     }
 
-    // Extract file path from hook input
-    let file_path = match hook_input.file_path() {
-        Some(path) => path,
-        None => {
+    let Some(command) = hook_input.command() else {
+        std::process::exit(0);
+    };
+
+    let repo_root = hook_input.working_dir()?;
+    let checker = checker_for(cli, Some(&repo_root));
+    let inspector = CommandInspector::from_config(&checker.config().commands)?;
+
+    if let Some(violation) = inspector.inspect(command, &repo_root) {
+        let reason = messages::render(
+            &messages::command_violation(&violation.policy, &violation.message),
+            checker.config().output.style,
+        );
+        println!(
+            "{}",
+            HookOutput::permission(violation.decision.as_str(), &reason).to_json()?
+        );
+    }
+
+    std::process::exit(0);
+}
+
+fn handle_session_start_command(cli: &Cli) -> Result<()> {
+    let hook_input = match read_hook_input(cli) {
+        Ok(input) => input,
+        Err(_) => std::process::exit(0),
+    };
+
+    if !hook_input.is_session_start() {
+        if cli.verbose {
+            eprintln!("{}", messages::ignored_event(&hook_input.hook_event_name));
+        }
+        std::process::exit(0);
+    }
+
+    let cwd = hook_input.working_dir()?;
+    let checker = checker_for(cli, Some(&cwd));
+    let style = checker.config().output.style;
+    let automation_config = AutomationConfig::from(&checker.config().automation);
+    let runner = AutomationRunner::new(automation_config, checker, &cwd);
+
+    match runner.session_context(&cwd) {
+        Ok(context) => println!(
+            "{}",
+            HookOutput::additional_context("SessionStart", &messages::render(&context, style))
+                .to_json()?
+        ),
+        Err(e) => {
             if cli.verbose {
-                eprintln!("❌ No file path found in hook input");
+                eprintln!("⚠️  Could not build session context: {e}");
             }
-            std::process::exit(0);
         }
+    }
+
+    std::process::exit(0);
+}
+
+/// Lint files directly and print each finding, exiting 1 when any remain
+fn handle_lint_findings_command(
+    cli: &Cli,
+    format: LintOutputFormat,
+    files: &[PathBuf],
+) -> Result<()> {
+    let files = if files.is_empty() {
+        // No files given: lint the file from the hook input
+        match read_hook_input(cli) {
+            Ok(input) if input.should_process() => input.file_path().into_iter().collect(),
+            _ => vec![],
+        }
+    } else {
+        files.to_vec()
     };
 
-    // Check if file exists
-    if !file_path.exists() {
-        if cli.verbose {
-            eprintln!("❌ File does not exist: {}", file_path.display());
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+
+    let mut found = 0;
+    for file in files.iter().filter(|file| file.exists()) {
+        let file = cwd.join(file);
+        let checker = checker_for(cli, Some(&file));
+        let automation_config = AutomationConfig::from(&checker.config().automation);
+        let runner = AutomationRunner::new(automation_config, checker, &cwd);
+        for mut finding in runner.lint_findings(&file)? {
+            found += 1;
+            match format {
+                LintOutputFormat::Github => {
+                    // Annotations need paths relative to the repository checkout
+                    finding.file = finding.file.map(|path| {
+                        Path::new(&path)
+                            .strip_prefix(&cwd)
+                            .map(|relative| relative.to_string_lossy().to_string())
+                            .unwrap_or(path)
+                    });
+                    println!("{}", finding.to_github_annotation());
+                }
+                LintOutputFormat::Text => println!("{}", finding.header),
+            }
         }
-        std::process::exit(0);
     }
 
-    // Initialize Cerebras configuration
-    let cerebras_config = CerebrasConfig::default();
+    if cli.verbose {
+        eprintln!("ℹ️  {found} lint finding(s) in {} file(s)", files.len());
+    }
+    std::process::exit(if found > 0 { 1 } else { 0 });
+}
+
+/// `path` if it is a directory, else its nearest existing ancestor directory (a file about
+/// to be created may be in a directory that doesn't exist yet)
+fn existing_dir(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|ancestor| ancestor.is_dir())
+}
 
-    if !cerebras_config.enabled && cli.verbose {
-        eprintln!("⚠️  Cerebras integration disabled. Set CEREBRAS_API_KEY environment variable to enable AI analysis.");
-        eprintln!("Falling back to basic heuristic analysis...\n");
+/// Project root that lint/test runs key their per-workspace state by
+fn project_root(dir: Option<&Path>) -> Result<PathBuf> {
+    let dir = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => std::env::current_dir().context("Failed to get current directory")?,
+    };
+    Ok(PythonProject::discover(&dir)
+        .map(|project| project.root)
+        .unwrap_or(dir))
+}
+
+fn handle_clean_command(dir: Option<&Path>) -> Result<()> {
+    let state = StateDir::for_workspace(&project_root(dir)?)?;
+    let removed = state.clean()?;
+    println!(
+        "Removed {removed} guardrails state file(s) from {}",
+        state.path().display()
+    );
+    Ok(())
+}
+
+fn handle_replay_command(file: Option<&Path>, only: Option<usize>, list: bool) -> Result<()> {
+    let path = match file {
+        Some(file) => file.to_path_buf(),
+        None => HookRecorder::for_workspace(&project_root(None)?)?
+            .path()
+            .to_path_buf(),
+    };
+    if !path.exists() {
+        println!(
+            "No recorded hook events in {} (record with {RECORD_ENV}=1)",
+            path.display()
+        );
+        return Ok(());
     }
+    let events = HookRecorder::load(&path)?;
+    let exe = std::env::current_exe().context("Failed to locate this binary")?;
 
-    let analyzer = SmartExclusionAnalyzer::new(cerebras_config);
+    for (number, event) in (1..).zip(&events) {
+        if only.is_some_and(|only| only != number) {
+            continue;
+        }
+        println!(
+            "#{number} {} {}",
+            event.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+            event.summary()
+        );
+        if list {
+            continue;
+        }
+
+        let working_dir = if event.working_dir.is_dir() {
+            event.working_dir.clone()
+        } else {
+            std::env::current_dir()?
+        };
+        let mut child = std::process::Command::new(&exe)
+            .args(&event.args)
+            .current_dir(&working_dir)
+            .env_remove(RECORD_ENV)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .context("Failed to run the recorded hook")?;
+        if let Some(mut stdin) = child.stdin.take() {
+            std::io::Write::write_all(&mut stdin, event.raw_payload().as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        for stream in [&output.stdout, &output.stderr] {
+            let text = String::from_utf8_lossy(stream);
+            if !text.trim().is_empty() {
+                println!("{}", text.trim_end());
+            }
+        }
+        println!(
+            "→ exit code {}\n",
+            output
+                .status
+                .code()
+                .map_or("none".to_string(), |code| code.to_string())
+        );
+    }
+    Ok(())
+}
+
+async fn handle_self_update_command(pin: Option<&str>, check: bool, force: bool) -> Result<()> {
+    let current = update::CURRENT_VERSION;
+    let pin = pin.map(str::to_string).or(load_config(None).update.pin);
+    let client = ReleaseClient::new()?;
+    let release = client.release(pin.as_deref()).await?;
+    let version = release.version();
+
+    // A pin is installed as given, even when older; otherwise only newer releases are
+    let up_to_date = match &pin {
+        Some(_) => update::compare_versions(version, current).is_eq(),
+        None => update::compare_versions(version, current).is_le(),
+    };
+    let pinned = if pin.is_some() { " (pinned)" } else { "" };
+    if up_to_date && !force {
+        println!("✅ claude-python-guardrails v{current} is up to date{pinned}");
+        return Ok(());
+    }
+    if check {
+        println!(
+            "ℹ️  v{version}{pinned} is available (installed: v{current}); run `claude-python-guardrails self-update`"
+        );
+        std::process::exit(1);
+    }
+
+    let bytes = client.download_verified_binary(&release).await?;
+    let exe = std::env::current_exe()
+        .and_then(std::fs::canonicalize)
+        .context("Failed to locate this binary")?;
+    update::replace_binary(&exe, &bytes)?;
+    println!(
+        "✅ Updated claude-python-guardrails v{current} → v{version}{pinned} ({})",
+        exe.display()
+    );
+    Ok(())
+}
+
+fn handle_report_command(
+    format: ReportFormat,
+    session: Option<&str>,
+    dir: Option<&Path>,
+) -> Result<()> {
+    let root = project_root(dir)?;
+    let history = History::load(&root)?;
+    let report = RunReport::from_history(history.entries(), session, &root);
+    print!("{}", report.render(format));
+    Ok(())
+}
+
+fn handle_suppressions_command(action: &SuppressionsAction, dir: Option<&Path>) -> Result<()> {
+    let root = project_root(dir)?;
+    let mut suppressions = Suppressions::load(&root)?;
+
+    match action {
+        SuppressionsAction::List => {
+            if suppressions.entries().is_empty() {
+                println!("No suppressed lint findings for {}", root.display());
+            }
+            for entry in suppressions.entries() {
+                println!(
+                    "{}  {}  {}  {}",
+                    entry.created.format("%Y-%m-%d %H:%M"),
+                    entry.rule.as_deref().unwrap_or("-"),
+                    entry.file.as_deref().unwrap_or("-"),
+                    entry.message
+                );
+            }
+        }
+        SuppressionsAction::Clear => {
+            let removed = suppressions.clear()?;
+            println!("Cleared {removed} suppressed lint finding(s)");
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_ai_command(action: &AiAction, dir: Option<&Path>) -> Result<()> {
+    let root = project_root(dir)?;
+
+    match action {
+        AiAction::Audit { limit, json } => {
+            let records = AuditLog::for_workspace(&root)?.recent(*limit);
+            if records.is_empty() && !json {
+                println!("No AI requests recorded for {}", root.display());
+            }
+            for record in records {
+                if *json {
+                    println!("{}", serde_json::to_string(&record)?);
+                } else {
+                    println!(
+                        "{}  {}/{}  {}  {}ms  {} chars #{}  {}",
+                        record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                        record.provider,
+                        record.model,
+                        record.analysis,
+                        record.latency_ms,
+                        record.prompt_chars,
+                        record.prompt_hash,
+                        record.decision
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Flags of the `generate-tests` command
+struct GenerateTestsOptions<'a> {
+    output: Option<&'a Path>,
+    framework: TestFramework,
+    dry_run: bool,
+    force: bool,
+    verify: bool,
+}
+
+async fn handle_generate_tests_command(
+    cli: &Cli,
+    file: &Path,
+    options: GenerateTestsOptions<'_>,
+) -> Result<()> {
+    let source = file
+        .canonicalize()
+        .with_context(|| format!("File does not exist: {}", file.display()))?;
+    let root = project_root(source.parent())?;
+
+    let checker = checker_for(cli, Some(&source));
+    let analyzer =
+        SmartExclusionAnalyzer::new(CerebrasConfig::from_ai_config(&checker.config().ai))
+            .with_workspace(&root);
+    if cli.verbose {
+        eprintln!(
+            "🔍 Generating {} tests for {}",
+            options.framework.name(),
+            source.display()
+        );
+    }
+    let tests = analyzer
+        .generate_tests(&source, &root, options.framework)
+        .await?;
+
+    if options.dry_run {
+        for case in &tests.test_cases {
+            eprintln!("- {}: {}", case.name, case.description);
+        }
+        print!("{}", tests.test_code);
+        return Ok(());
+    }
+
+    let path = options
+        .output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| default_test_path(&source, &root));
+    if path.exists() && !options.force {
+        anyhow::bail!(
+            "{} already exists; pass --force to overwrite it or --output to write elsewhere",
+            path.display()
+        );
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, &tests.test_code)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!(
+        "✨ Wrote {} test(s) to {}",
+        tests.test_cases.len(),
+        path.display()
+    );
+    for case in &tests.test_cases {
+        println!("  - {}: {}", case.name, case.description);
+    }
+
+    if options.verify {
+        let environment = PythonEnvironment::detect(&root);
+        let path_arg = path.to_string_lossy().to_string();
+        let (program, args) = match options.framework {
+            TestFramework::Pytest => environment.command(
+                "pytest",
+                vec!["--collect-only".to_string(), "-q".to_string(), path_arg],
+            ),
+            TestFramework::Unittest => environment.command(
+                "python",
+                vec!["-m".to_string(), "unittest".to_string(), path_arg],
+            ),
+        };
+        let output = std::process::Command::new(&program)
+            .args(&args)
+            .current_dir(&root)
+            .output()
+            .with_context(|| format!("Failed to run {program}"))?;
+        if !output.status.success() {
+            eprintln!(
+                "⛔ The generated tests don't import cleanly:\n{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+            std::process::exit(1);
+        }
+        println!("✨ The generated tests import cleanly");
+    }
+
+    Ok(())
+}
+
+/// Ask for a patch fixing the file's latest recorded failure, show it and apply it on
+/// confirmation
+async fn handle_fix_command(cli: &Cli, file: &Path, from: FixSource, yes: bool) -> Result<()> {
+    let file = file
+        .canonicalize()
+        .with_context(|| format!("File does not exist: {}", file.display()))?;
+    let root = project_root(file.parent())?;
+    let (operation, kind) = match from {
+        FixSource::Lint => ("lint", AnalysisKind::Lint),
+        FixSource::Test => ("test", AnalysisKind::Test),
+    };
+
+    let history = History::load(&root)?;
+    let Some(failure) = history.last_failure(operation, &file) else {
+        anyhow::bail!(
+            "No failed {operation} run is recorded for {}; the latest {operation} run passed or never ran",
+            file.display()
+        );
+    };
+
+    let checker = checker_for(cli, Some(&file));
+    let analyzer =
+        SmartExclusionAnalyzer::new(CerebrasConfig::from_ai_config(&checker.config().ai))
+            .with_workspace(&root);
+    let fix = analyzer.propose_fix(&file, kind, &failure.message).await?;
+    if fix.diff.trim().is_empty() {
+        println!("ℹ️  No patch proposed: {}", fix.explanation);
+        return Ok(());
+    }
+
+    let original = std::fs::read_to_string(&file)?;
+    let patched = apply_unified_diff(&original, &fix.diff)
+        .context("The proposed patch doesn't apply to the current file")?;
+    println!("💡 {}\n\n{}", fix.explanation, fix.diff.trim_end());
+
+    if !yes {
+        eprint!("\nApply this patch to {}? [y/N] ", file.display());
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes") {
+            println!("Patch not applied");
+            return Ok(());
+        }
+    }
+
+    std::fs::write(&file, patched)
+        .with_context(|| format!("Failed to write {}", file.display()))?;
+    println!("✨ Applied the patch to {}", file.display());
+    Ok(())
+}
+
+/// Print what the lint and test hooks would do for a file as JSON
+fn handle_preview_command(cli: &Cli, file: &Path) -> Result<()> {
+    let file = file
+        .canonicalize()
+        .with_context(|| format!("File does not exist: {}", file.display()))?;
+
+    let checker = checker_for(cli, Some(&file));
+    let automation_config = AutomationConfig::from(&checker.config().automation);
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let runner = AutomationRunner::new(automation_config, checker, &cwd);
+
+    let preview = runner.preview(&file)?;
+    println!("{}", serde_json::to_string_pretty(&preview)?);
+    Ok(())
+}
+
+/// Run the hook pipeline on every changed file until interrupted
+async fn handle_watch_command(cli: &Cli, path: Option<&Path>, debounce_ms: u64) -> Result<()> {
+    let root = project_root(path)?;
+    // guardrails.yaml and pyproject.toml edits swap in a rebuilt checker between runs
+    let ai_provider = cli.ai;
+    let reloadable = Arc::new(ReloadableChecker::load_with(&root, move |config| {
+        if let Some(provider) = ai_provider {
+            config.ai.provider = Some(provider);
+        }
+    })?);
+    let _config_watch = reloadable.watch()?;
+    // Directories are walked with the same limits as the test file search
+    let mut watcher = FileWatcher::new(&root, &reloadable.checker().config().automation.search)?;
+    println!(
+        "👀 Watching {} for changes (Ctrl-C to stop)",
+        root.display()
+    );
+
+    loop {
+        let changed = tokio::task::block_in_place(|| {
+            watcher.changed(Duration::from_secs(1), Duration::from_millis(debounce_ms))
+        });
+        let checker = reloadable.checker();
+        let limits = &checker.config().automation.search;
+        if limits != watcher.limits() {
+            watcher = FileWatcher::new(&root, limits)?;
+        }
+        if changed.is_empty() {
+            continue;
+        }
+
+        let runner = AutomationRunner::with_shared_checker(
+            AutomationConfig::from(&checker.config().automation),
+            &checker,
+            &root,
+        );
+        for file in changed {
+            let relative = file.strip_prefix(&root).unwrap_or(&file).display();
+            println!("\n📝 {relative}");
+            for (operation, result) in [
+                ("lint", runner.lint_file(&file, None).await),
+                ("test", runner.test_file(&file, None).await),
+            ] {
+                match result.map(|result| result.render(runner.output_style())) {
+                    Ok(result) => match result.message() {
+                        Some(message) => println!("{message}"),
+                        None if cli.verbose => println!("ℹ️  No {operation} action"),
+                        None => {}
+                    },
+                    Err(e) => eprintln!("⛔ {operation} failed: {e:#}"),
+                }
+            }
+            // Formatters and auto-fixes rewrite the file; don't treat that as a new change
+            watcher.refresh(&file);
+        }
+    }
+}
+
+/// Report an automation result the way Claude Code expects: messages on stderr with exit
+/// code 2, advice as PostToolUse additional context on stdout with exit code 0
+fn exit_with_result(result: &claude_python_guardrails::AutomationResult) -> ! {
+    use claude_python_guardrails::AutomationResult;
+
+    match result {
+        AutomationResult::Advice(message) => {
+            match HookOutput::additional_context("PostToolUse", message).to_json() {
+                Ok(json) => println!("{json}"),
+                Err(e) => eprintln!("⚠️  Could not serialize advice: {e}"),
+            }
+        }
+        _ => {
+            if let Some(message) = result.message() {
+                eprintln!("{message}");
+            }
+        }
+    }
+    std::process::exit(result.exit_code());
+}
+
+/// Print a lint/test report as JSON (`--json`), exiting with the hook's exit code, or
+/// report its result like any other hook
+fn exit_with_report(report: &AutomationReport, json: bool) -> ! {
+    if !json {
+        exit_with_result(&report.result);
+    }
+    match serde_json::to_string(report) {
+        Ok(json) => println!("{json}"),
+        Err(e) => eprintln!("⚠️  Could not serialize report: {e}"),
+    }
+    std::process::exit(report.exit_code());
+}
+
+async fn handle_smart_automation(cli: &Cli, operation: &str) -> Result<AutomationReport> {
+    let hook_input = match read_hook_input(cli) {
+        Ok(input) => input,
+        Err(_) => {
+            if cli.verbose {
+                eprintln!("ℹ️  No JSON hook input available.");
+            }
+            return Ok(AutomationReport::no_action(operation));
+        }
+    };
+
+    let working_dir = hook_input.working_dir()?;
+    let target = match hook_input.file_path() {
+        Some(file_path) => working_dir.join(file_path),
+        None => working_dir.clone(),
+    };
+    let checker = checker_for(cli, Some(&target));
+    let style = checker.config().output.style;
+    let automation_config = AutomationConfig::from(&checker.config().automation);
+    // State, history and AI bookkeeping belong to the session's project, not this process's
+    let runner = AutomationRunner::new(automation_config, checker, &working_dir);
+
+    let mut report = match operation {
+        "lint" => runner.handle_smart_lint(&hook_input).await?,
+        "test" => runner.handle_smart_test(&hook_input).await?,
+        "mutate" => AutomationReport::new(
+            operation,
+            None,
+            runner.handle_smart_mutate(&hook_input).await?,
+        ),
+        "stop" => AutomationReport::new(operation, None, runner.handle_stop(&hook_input).await?),
+        _ => AutomationReport::no_action(operation),
+    };
+    report.result = report.result.render(style);
+    Ok(report)
+}
+
+async fn handle_analyze_command(
+    cli: &Cli,
+    format: &str,
+    files: &[PathBuf],
+    no_ai: bool,
+) -> Result<()> {
+    let files = if files.is_empty() {
+        vec![hook_file_to_analyze(cli)]
+    } else {
+        files.to_vec()
+    };
+
+    // Files analyzed together come from one project; its config applies to all of them
+    let checker = checker_for(cli, files.first().map(PathBuf::as_path));
+    // Minified/bundled files would only waste tokens
+    let files: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|file| {
+            if !file.exists() {
+                if cli.verbose {
+                    eprintln!("❌ File does not exist: {}", file.display());
+                }
+                return false;
+            }
+            if checker.exceeds_max_line_length(file) {
+                if cli.verbose {
+                    eprintln!(
+                        "ℹ️  Skipping analysis, file has extremely long lines: {}",
+                        file.display()
+                    );
+                }
+                return false;
+            }
+            true
+        })
+        .collect();
+    if files.is_empty() {
+        std::process::exit(0);
+    }
 
     if cli.verbose {
-        eprintln!("🔍 Analyzing file: {}", file_path.display());
+        for file in &files {
+            eprintln!("🔍 Analyzing file: {}", file.display());
+        }
         eprintln!("Output format: {}", format);
         eprintln!();
     }
 
-    match analyzer.analyze_file(&file_path).await {
-        Ok(analysis) => {
-            display_analysis(&file_path, &analysis, format, cli.verbose)?;
+    let analyses = if no_ai {
+        Ok(files.iter().map(|file| heuristic_analysis(file)).collect())
+    } else {
+        // Initialize Cerebras configuration
+        let cerebras_config = CerebrasConfig::from_ai_config(&checker.config().ai);
+
+        if !cerebras_config.enabled && cli.verbose {
+            eprintln!("⚠️  Cerebras integration disabled. Set CEREBRAS_API_KEY environment variable to enable AI analysis.");
+            eprintln!("Falling back to basic heuristic analysis...\n");
+        }
+
+        let mut analyzer = SmartExclusionAnalyzer::new(cerebras_config);
+        if let Ok(workspace) = project_root(None) {
+            analyzer = analyzer.with_workspace(&workspace);
+        }
+        analyzer.analyze_files(&files).await
+    };
+
+    match analyses {
+        Ok(analyses) => {
+            if let ([file], [analysis]) = (files.as_slice(), analyses.as_slice()) {
+                display_analysis(file, analysis, format, cli.verbose)?;
+            } else if format.eq_ignore_ascii_case("json") {
+                let entries: Vec<serde_json::Value> = files
+                    .iter()
+                    .zip(&analyses)
+                    .map(|(file, analysis)| {
+                        let mut entry = serde_json::to_value(analysis)?;
+                        entry["file_path"] = file.display().to_string().into();
+                        Ok(entry)
+                    })
+                    .collect::<Result<_>>()?;
+                let json = serde_json::to_string_pretty(&entries)
+                    .context("Failed to serialize analysis to JSON")?;
+                println!("{}", json);
+            } else {
+                for (file, analysis) in files.iter().zip(&analyses) {
+                    display_text_format(file, analysis, cli.verbose);
+                    println!();
+                }
+            }
 
             // Analysis completed successfully - exit 0 regardless of exclusion decision
             // The exclusion recommendation is communicated through the output content
@@ -160,6 +1237,39 @@ async fn handle_analyze_command(cli: &Cli, format: &str) -> Result<()> {
     }
 }
 
+/// File named by the hook input on stdin, exiting quietly when there is none
+fn hook_file_to_analyze(cli: &Cli) -> PathBuf {
+    // Read JSON hook input (Claude Code hook format)
+    let hook_input = match read_hook_input(cli) {
+        Ok(input) => input,
+        Err(_) => {
+            if cli.verbose {
+                eprintln!("ℹ️  No JSON hook input available.");
+            }
+            std::process::exit(0);
+        }
+    };
+
+    // Only process PostToolUse events for edit tools
+    if !hook_input.should_process() {
+        if cli.verbose {
+            eprintln!("{}", messages::ignored_event(&hook_input.hook_event_name));
+        }
+        std::process::exit(0);
+    }
+
+    // Extract file path from hook input
+    match hook_input.file_path() {
+        Some(path) => path,
+        None => {
+            if cli.verbose {
+                eprintln!("❌ No file path found in hook input");
+            }
+            std::process::exit(0);
+        }
+    }
+}
+
 fn display_analysis(
     file: &Path,
     analysis: &ExclusionAnalysis,
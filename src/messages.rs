@@ -0,0 +1,289 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How hook messages are decorated (`output.style`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStyle {
+    /// Emoji markers (⛔, 👉, 💡, ...) as written
+    #[default]
+    Emoji,
+    /// ASCII tags such as `[BLOCK]` and `->`, for terminals and logs that mangle emoji
+    Ascii,
+    /// No markers at all; bullets and arrows become ASCII
+    Minimal,
+}
+
+/// Decorations used in messages and their ASCII replacements; `None` for markers that
+/// carry no meaning of their own
+const DECORATIONS: &[(&str, Option<&str>)] = &[
+    ("⛔", Some("[BLOCK]")),
+    ("⚠️", Some("[WARN]")),
+    ("⚠", Some("[WARN]")),
+    ("❌", Some("[FAIL]")),
+    ("🛑", Some("[STOP]")),
+    ("🚫", Some("[DENIED]")),
+    ("🔒", Some("[LOCKED]")),
+    ("✅", Some("[OK]")),
+    ("✨", Some("[OK]")),
+    ("💡", Some("[HINT]")),
+    ("👉", Some("->")),
+    ("ℹ️", Some("[INFO]")),
+    ("🤔", Some("[NOTE]")),
+    ("📝", Some("[NOTE]")),
+    ("⏱️", Some("[TIME]")),
+    ("⏰", Some("[TIME]")),
+    ("♻️", Some("[CACHED]")),
+    ("🔁", Some("[REPEAT]")),
+    ("🔀", Some("[SORTED]")),
+    ("🔎", Some("[TYPES]")),
+    ("🔍", Some("[CHECK]")),
+    ("🔧", Some("[FIX]")),
+    ("🧬", Some("[MUTATION]")),
+    ("📸", Some("[SNAPSHOT]")),
+    ("🛡️", Some("[GUARD]")),
+    ("👀", Some("[WATCH]")),
+    ("📋", None),
+    ("🎯", None),
+    ("📊", None),
+    ("📄", None),
+    ("📁", None),
+    ("🧩", None),
+    ("🐍", None),
+    ("🙁", None),
+];
+
+/// Punctuation replaced in both ASCII and minimal styles
+const PUNCTUATION: &[(&str, &str)] = &[
+    ("•", "-"),
+    ("→", "->"),
+    ("—", "-"),
+    ("…", "..."),
+    ("═", "="),
+];
+
+/// Render a message in the configured style
+///
+/// Messages are written with emoji; the other styles rewrite the known markers, leaving
+/// any other non-ASCII text (file content, tool output) untouched.
+pub fn render(text: &str, style: OutputStyle) -> String {
+    if style == OutputStyle::Emoji {
+        return text.to_string();
+    }
+
+    let mut rendered = text.to_string();
+    for (emoji, ascii) in DECORATIONS {
+        match (style, ascii) {
+            (OutputStyle::Ascii, Some(ascii)) => rendered = rendered.replace(emoji, ascii),
+            // A dropped marker takes its separating space with it
+            _ => {
+                for dropped in [format!("{emoji}  "), format!("{emoji} "), emoji.to_string()] {
+                    rendered = rendered.replace(&dropped, "");
+                }
+            }
+        }
+    }
+    for (symbol, ascii) in PUNCTUATION {
+        rendered = rendered.replace(symbol, ascii);
+    }
+    if style == OutputStyle::Minimal {
+        rendered = rendered.replace("**", "");
+    }
+    rendered.replace('\u{fe0f}', "")
+}
+
+/// Placeholders `output.templates` may use
+pub const TEMPLATE_PLACEHOLDERS: &[&str] = &[
+    "file",
+    "tool",
+    "operation",
+    "count",
+    "summary",
+    "details",
+    "analysis",
+    "message",
+];
+
+/// Custom lint/test hook messages (`output.templates`)
+///
+/// Templates use Handlebars-style placeholders: `{{file}}` (relative to the project
+/// root), `{{tool}}`, `{{operation}}` (`lint` or `test`), `{{count}}` (lint findings or
+/// failed tests), `{{summary}}` (the built-in message's first line), `{{details}}` (the
+/// rest of it), `{{analysis}}` (its AI analysis paragraph, empty without one) and
+/// `{{message}}` (all of it). Unset templates keep the built-in message.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MessageTemplates {
+    #[serde(default)]
+    pub lint_success: Option<String>,
+    #[serde(default)]
+    pub lint_failure: Option<String>,
+    #[serde(default)]
+    pub test_success: Option<String>,
+    #[serde(default)]
+    pub test_failure: Option<String>,
+}
+
+impl MessageTemplates {
+    /// Template for an operation's passing (advice included) or failing runs
+    pub fn for_outcome(&self, operation: &str, failed: bool) -> Option<&str> {
+        let template = match (operation, failed) {
+            ("lint", false) => &self.lint_success,
+            ("lint", true) => &self.lint_failure,
+            ("test", false) => &self.test_success,
+            ("test", true) => &self.test_failure,
+            _ => return None,
+        };
+        template.as_deref()
+    }
+
+    /// First placeholder not in `TEMPLATE_PLACEHOLDERS`, checked when the config loads
+    pub fn unknown_placeholder(&self) -> Option<String> {
+        [
+            &self.lint_success,
+            &self.lint_failure,
+            &self.test_success,
+            &self.test_failure,
+        ]
+        .into_iter()
+        .flatten()
+        .flat_map(|template| placeholders(template))
+        .find(|name| !TEMPLATE_PLACEHOLDERS.contains(&name.as_str()))
+    }
+}
+
+/// Names of the `{{name}}` placeholders in a template
+fn placeholders(template: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        names.push(rest[start + 2..start + 2 + end].trim().to_string());
+        rest = &rest[start + 2 + end + 2..];
+    }
+    names
+}
+
+/// Substitute `{{name}}` placeholders, leaving unknown ones as written
+pub fn fill_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let placeholder = &rest[start..start + 2 + end + 2];
+        let name = placeholder[2..placeholder.len() - 2].trim();
+        filled.push_str(&rest[..start]);
+        match values.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => filled.push_str(value),
+            None => filled.push_str(placeholder),
+        }
+        rest = &rest[start + placeholder.len()..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// The `{{summary}}`, `{{details}}` and `{{analysis}}` parts of a built-in message
+pub fn message_parts(message: &str) -> [(&'static str, String); 3] {
+    let message = message.trim();
+    let (summary, details) = message.split_once('\n').unwrap_or((message, ""));
+    let analysis = message
+        .split("\n\n")
+        .find(|paragraph| paragraph.contains("**Analysis") || paragraph.contains("Analysis Result"))
+        .unwrap_or_default();
+    [
+        ("summary", summary.trim().to_string()),
+        ("details", details.trim().to_string()),
+        ("analysis", analysis.trim().to_string()),
+    ]
+}
+
+/// Reason the PreToolUse `protect` hook denies an edit with
+pub fn protected_path(path: &Path, config_file: &str) -> String {
+    format!(
+        "🔒 {} is a protected path (see `protect` in {config_file}). Do not modify it; ask the user to make this change.",
+        path.display()
+    )
+}
+
+/// Reason the `check-command` hook denies or asks about a Bash command with
+pub fn command_violation(policy: &str, message: &str) -> String {
+    format!("🛑 [{policy}] {message}")
+}
+
+/// Note on stderr when a command panicked and the hook fell back to doing nothing
+pub fn internal_error(message: &str, log_path: Option<&Path>) -> String {
+    let details = match log_path {
+        Some(path) => format!("; details in {}", path.display()),
+        None => String::new(),
+    };
+    format!("⚠️  Guardrails hit an internal error and skipped this check: {message}{details}")
+}
+
+/// Note for a hook event the command doesn't handle (verbose mode)
+pub fn ignored_event(event: &str) -> String {
+    format!("ℹ️  Ignoring event type: {event}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_styles() {
+        let message = "⛔ LINT ERRORS FOUND:\n\n  • app.py:1: F821\n\n💡 **Fix**: define `y` → rerun\n👉 Continue with your task.";
+
+        assert_eq!(render(message, OutputStyle::Emoji), message);
+        assert_eq!(
+            render(message, OutputStyle::Ascii),
+            "[BLOCK] LINT ERRORS FOUND:\n\n  - app.py:1: F821\n\n[HINT] **Fix**: define `y` -> rerun\n-> Continue with your task."
+        );
+        assert_eq!(
+            render(message, OutputStyle::Minimal),
+            "LINT ERRORS FOUND:\n\n  - app.py:1: F821\n\nFix: define `y` -> rerun\nContinue with your task."
+        );
+
+        // Text that isn't a known marker is left alone
+        assert_eq!(
+            render("⚠️ naïve café", OutputStyle::Ascii),
+            "[WARN] naïve café"
+        );
+        assert!(render(
+            &protected_path(Path::new("db.py"), "guardrails.yaml"),
+            OutputStyle::Ascii
+        )
+        .starts_with("[LOCKED] db.py is a protected path"));
+    }
+
+    #[test]
+    fn test_fill_template() {
+        let message = "⛔ LINT ISSUES FOUND:\n\napp.py:1:1: E501\n\n💡 **Analysis:**\nReal issue";
+        let mut values = vec![("file", "app.py".to_string()), ("count", "1".to_string())];
+        values.extend(message_parts(message));
+
+        assert_eq!(
+            fill_template("{{ file }}: {{count}} issue(s) - {{summary}}", &values),
+            "app.py: 1 issue(s) - ⛔ LINT ISSUES FOUND:"
+        );
+        assert_eq!(
+            fill_template("{{analysis}}|{{tool}}|{{", &values),
+            "💡 **Analysis:**\nReal issue|{{tool}}|{{"
+        );
+        assert!(fill_template("{{details}}", &values).starts_with("app.py:1:1: E501"));
+
+        let templates = MessageTemplates {
+            lint_failure: Some("{{file}} {{nope}}".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(templates.unknown_placeholder().as_deref(), Some("nope"));
+        assert_eq!(
+            templates.for_outcome("lint", true),
+            Some("{{file}} {{nope}}")
+        );
+        assert_eq!(templates.for_outcome("lint", false), None);
+        assert_eq!(MessageTemplates::default().unknown_placeholder(), None);
+    }
+}
@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+
+/// Surviving mutants listed in a `smart-mutate` report; the rest are only counted
+pub const MAX_REPORTED_MUTANTS: usize = 5;
+
+/// Mutation testing tool behind `smart-mutate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MutationTool {
+    Mutmut,
+    CosmicRay,
+}
+
+impl MutationTool {
+    pub fn command(self) -> &'static str {
+        match self {
+            MutationTool::Mutmut => "mutmut",
+            MutationTool::CosmicRay => "cosmic-ray",
+        }
+    }
+
+    /// The configured tool, or the first one installed
+    pub fn detect(preferred: Option<MutationTool>) -> Option<MutationTool> {
+        preferred.or_else(|| {
+            [MutationTool::Mutmut, MutationTool::CosmicRay]
+                .into_iter()
+                .find(|tool| which::which(tool.command()).is_ok())
+        })
+    }
+}
+
+/// A mutant the tests didn't catch
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurvivingMutant {
+    /// Tool-specific mutant id (`mutmut show <id>`, cosmic-ray job id)
+    pub id: String,
+    /// Mutated line, when the tool reports it
+    pub line: Option<usize>,
+    /// What the mutation changed
+    pub description: String,
+}
+
+/// Ids of the surviving mutants in `mutmut results` output
+///
+/// mutmut 2 lists id ranges (`4, 7-8`) under a "Survived" heading; mutmut 3 prints one
+/// `<id>: survived` line per mutant.
+pub fn parse_mutmut_survivors(results: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut in_survived = false;
+    for line in results.lines() {
+        let trimmed = line.trim();
+        if let Some(id) = trimmed.strip_suffix(": survived") {
+            ids.push(id.trim().to_string());
+            continue;
+        }
+        if trimmed.starts_with("Survived") {
+            in_survived = true;
+            continue;
+        }
+        if !in_survived || trimmed.is_empty() || trimmed.starts_with("----") {
+            continue;
+        }
+        if !trimmed.starts_with(|c: char| c.is_ascii_digit()) {
+            // The next status heading ends the section
+            in_survived = false;
+            continue;
+        }
+        for part in trimmed.split(',') {
+            let part = part.trim();
+            match part.split_once('-') {
+                Some((start, end)) => {
+                    if let (Ok(start), Ok(end)) = (start.parse::<u64>(), end.parse::<u64>()) {
+                        ids.extend((start..=end).map(|id| id.to_string()));
+                    }
+                }
+                None if part.parse::<u64>().is_ok() => ids.push(part.to_string()),
+                None => {}
+            }
+        }
+    }
+    ids
+}
+
+/// A surviving mutant described by its `mutmut show <id>` diff
+pub fn parse_mutant_diff(id: &str, diff: &str) -> SurvivingMutant {
+    let line = diff
+        .lines()
+        .find_map(|line| line.strip_prefix("@@ -"))
+        .and_then(|range| range.split([',', ' ']).next())
+        .and_then(|start| start.parse::<usize>().ok());
+    let changed = |prefix: char, header: &str| {
+        diff.lines()
+            .find(|line| line.starts_with(prefix) && !line.starts_with(header))
+            .map(|line| line[1..].trim().to_string())
+    };
+    let description = match (changed('-', "---"), changed('+', "+++")) {
+        (Some(original), Some(mutated)) => format!("`{original}` → `{mutated}`"),
+        (Some(original), None) => format!("`{original}` removed"),
+        _ => format!("mutant {id}"),
+    };
+    // The hunk header counts context lines before the change
+    let offset = diff
+        .lines()
+        .skip_while(|line| !line.starts_with("@@"))
+        .skip(1)
+        .take_while(|line| line.starts_with(' '))
+        .count();
+    SurvivingMutant {
+        id: id.to_string(),
+        line: line.map(|line| line + offset),
+        description,
+    }
+}
+
+/// Number of mutants and the surviving ones in `cr-report` output
+pub fn parse_cosmic_ray_report(report: &str) -> (Option<usize>, Vec<SurvivingMutant>) {
+    let mut survivors = Vec::new();
+    let mut job_id = String::new();
+    let mut description = String::new();
+    let mut total = None;
+    for line in report.lines() {
+        let trimmed = line.trim();
+        if let Some(id) = trimmed.strip_prefix("[job-id]") {
+            job_id = id.trim().to_string();
+            description.clear();
+        } else if let Some(jobs) = trimmed.strip_prefix("total jobs:") {
+            total = jobs.trim().parse().ok();
+        } else if trimmed.contains("test outcome: survived") {
+            survivors.push(SurvivingMutant {
+                id: job_id.clone(),
+                line: None,
+                description: description.clone(),
+            });
+        } else if !job_id.is_empty() && description.is_empty() && !trimmed.contains("outcome:") {
+            description = trimmed.to_string();
+        }
+    }
+    (total, survivors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mutmut_survivors() {
+        let mutmut2 = "To apply a mutant on disk:\n    mutmut apply <id>\n\nTimed out ⏰ (1)\n\n---- calc.py (1) ----\n\n2\n\nSurvived 🙁 (3)\n\n---- calc.py (3) ----\n\n4, 7-8\n\nSuspicious 🤔 (1)\n\n---- calc.py (1) ----\n\n9\n";
+        assert_eq!(parse_mutmut_survivors(mutmut2), ["4", "7", "8"]);
+
+        let mutmut3 = "    calc.x_add__mutmut_1: killed\n    calc.x_add__mutmut_2: survived\n";
+        assert_eq!(parse_mutmut_survivors(mutmut3), ["calc.x_add__mutmut_2"]);
+    }
+
+    #[test]
+    fn test_parse_mutant_diff() {
+        let diff = "--- calc.py\n+++ calc.py\n@@ -3,2 +3,2 @@\n def add(a, b):\n-    return a + b\n+    return a - b\n";
+        assert_eq!(
+            parse_mutant_diff("4", diff),
+            SurvivingMutant {
+                id: "4".to_string(),
+                line: Some(4),
+                description: "`return a + b` → `return a - b`".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_cosmic_ray_report() {
+        let report = "[job-id] 1a2b\ncalc.py core/ReplaceBinaryOperator_Add_Sub 0\nworker outcome: normal, test outcome: survived\n[job-id] 3c4d\ncalc.py core/NumberReplacer 1\nworker outcome: normal, test outcome: killed\ntotal jobs: 2\ncomplete: 2 (100.00%)\nsurviving mutants: 1 (50.00%)\n";
+        let (total, survivors) = parse_cosmic_ray_report(report);
+        assert_eq!(total, Some(2));
+        assert_eq!(
+            survivors,
+            [SurvivingMutant {
+                id: "1a2b".to_string(),
+                line: None,
+                description: "calc.py core/ReplaceBinaryOperator_Add_Sub 0".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use crate::cerebras::full_confidence;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// A patch the AI provider proposed for a lint or test failure (`fix`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposedFix {
+    /// What the patch changes and why
+    pub explanation: String,
+    /// Unified diff against the file
+    pub diff: String,
+    /// Model's confidence in the patch, 0.0-1.0
+    #[serde(default = "full_confidence")]
+    pub confidence: f32,
+}
+
+/// One `@@ -a,b +c,d @@` section of a unified diff
+#[derive(Debug, Default)]
+struct Hunk {
+    old_start: usize,
+    /// Context and removed lines, as they must appear in the original
+    old_lines: Vec<String>,
+    /// Context and added lines that replace them
+    new_lines: Vec<String>,
+}
+
+/// Apply a single-file unified diff to `original`
+///
+/// Hunks are located by their content rather than trusting the line numbers, which
+/// generated patches often get wrong; the match closest to the stated position wins.
+pub fn apply_unified_diff(original: &str, diff: &str) -> Result<String> {
+    let hunks = parse_hunks(diff)?;
+    if hunks.is_empty() {
+        bail!("The patch has no hunks");
+    }
+
+    let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+    // Lines added or removed by earlier hunks shift later ones
+    let mut shift: isize = 0;
+    for (index, hunk) in hunks.iter().enumerate() {
+        let expected = (hunk.old_start.saturating_sub(1) as isize + shift).max(0) as usize;
+        let Some(at) = find_hunk(&lines, &hunk.old_lines, expected) else {
+            bail!(
+                "Hunk {} (line {}) doesn't match the current file",
+                index + 1,
+                hunk.old_start
+            );
+        };
+        lines.splice(
+            at..at + hunk.old_lines.len(),
+            hunk.new_lines.iter().cloned(),
+        );
+        shift += hunk.new_lines.len() as isize - hunk.old_lines.len() as isize;
+    }
+
+    let mut patched = lines.join("\n");
+    if original.ends_with('\n') || original.is_empty() {
+        patched.push('\n');
+    }
+    Ok(patched)
+}
+
+/// Start of the occurrence of `needle` in `lines` nearest to `expected`
+fn find_hunk(lines: &[String], needle: &[String], expected: usize) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(expected.min(lines.len()));
+    }
+    (0..=lines.len().checked_sub(needle.len())?)
+        .filter(|&start| lines[start..start + needle.len()] == *needle)
+        .min_by_key(|&start| start.abs_diff(expected))
+}
+
+fn parse_hunks(diff: &str) -> Result<Vec<Hunk>> {
+    let mut hunks = Vec::new();
+    let mut current: Option<Hunk> = None;
+    for line in diff.lines() {
+        if let Some(header) = line.strip_prefix("@@ ") {
+            hunks.extend(current.take());
+            let old_start = header
+                .split_whitespace()
+                .find_map(|part| part.strip_prefix('-'))
+                .and_then(|range| range.split(',').next())
+                .and_then(|start| start.parse().ok())
+                .unwrap_or(1);
+            current = Some(Hunk {
+                old_start,
+                ..Hunk::default()
+            });
+            continue;
+        }
+        let Some(hunk) = current.as_mut() else {
+            // File headers and any explanation before the first hunk
+            continue;
+        };
+        if line.starts_with("--- ") || line.starts_with("+++ ") || line.starts_with('\\') {
+            continue;
+        }
+        match line.chars().next() {
+            Some('+') => hunk.new_lines.push(line[1..].to_string()),
+            Some('-') => hunk.old_lines.push(line[1..].to_string()),
+            Some(' ') => {
+                hunk.old_lines.push(line[1..].to_string());
+                hunk.new_lines.push(line[1..].to_string());
+            }
+            // Editors and models often strip the space of empty context lines
+            None => {
+                hunk.old_lines.push(String::new());
+                hunk.new_lines.push(String::new());
+            }
+            Some(_) => bail!("Unexpected line in patch: {line}"),
+        }
+    }
+    hunks.extend(current);
+    Ok(hunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_unified_diff() {
+        let original = "import os\n\n\ndef add(a, b):\n    return a - b\n\n\ndef sub(a, b):\n    return a - b\n";
+        // Line numbers are off by two, as generated patches often are
+        let diff = r#"--- a/calc.py
++++ b/calc.py
+@@ -1,3 +1,2 @@
+-import os
+
+
+@@ -6,2 +5,2 @@
+ def add(a, b):
+-    return a - b
++    return a + b
+"#;
+        assert_eq!(
+            apply_unified_diff(original, diff).unwrap(),
+            "\n\ndef add(a, b):\n    return a + b\n\n\ndef sub(a, b):\n    return a - b\n"
+        );
+
+        let stale = "@@ -1,1 +1,1 @@\n-import sys\n+import os\n";
+        let error = apply_unified_diff(original, stale).unwrap_err();
+        assert!(error.to_string().contains("doesn't match"));
+        assert!(apply_unified_diff(original, "no changes").is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use crate::error::GuardrailsResult;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// Files an automation command runs for (`automation.<command>.paths`, `skip_paths` and
+/// the section-wide `automation.skip_paths`), independent of the exclusion lists the
+/// check subcommands use
+///
+/// Patterns are globs, or plain directories, matched against the file path relative to
+/// the project root. An empty `paths` list covers every file; `skip_paths` wins over it.
+///
+/// Scopes are checked after `overrides` are merged: a file runs a command only when the
+/// merged `enabled` is true and the merged scope allows it. An override block setting
+/// `enabled: false` therefore turns the command off even for files in scope, and one
+/// setting `paths`/`skip_paths` replaces the top-level lists for the files it matches.
+#[derive(Debug, Clone, Default)]
+pub struct PathScope {
+    paths: Vec<String>,
+    skip_paths: Vec<String>,
+    path_globs: GlobSet,
+    skip_globs: GlobSet,
+}
+
+impl PartialEq for PathScope {
+    fn eq(&self, other: &Self) -> bool {
+        self.paths == other.paths && self.skip_paths == other.skip_paths
+    }
+}
+
+impl PathScope {
+    pub fn new(paths: Vec<String>, skip_paths: Vec<String>) -> Self {
+        Self {
+            path_globs: build_globset(&paths),
+            skip_globs: build_globset(&skip_paths),
+            paths,
+            skip_paths,
+        }
+    }
+
+    /// Like [`PathScope::new`], but an invalid pattern is an error instead of a warning
+    pub fn try_new(paths: Vec<String>, skip_paths: Vec<String>) -> GuardrailsResult<Self> {
+        Ok(Self {
+            path_globs: crate::build_globset(&paths)?,
+            skip_globs: crate::build_globset(&skip_paths)?,
+            paths,
+            skip_paths,
+        })
+    }
+
+    /// Whether the scope covers every file
+    pub fn is_unrestricted(&self) -> bool {
+        self.paths.is_empty() && self.skip_paths.is_empty()
+    }
+
+    /// Whether `file` is in scope; files outside `project_root` are matched as given
+    /// (invalid globs are logged and never match)
+    pub fn allows(&self, file: &Path, project_root: &Path) -> bool {
+        let relative = file.strip_prefix(project_root).unwrap_or(file);
+        (self.paths.is_empty() || matches_any(&self.paths, &self.path_globs, relative))
+            && !matches_any(&self.skip_paths, &self.skip_globs, relative)
+    }
+}
+
+/// Compile the valid patterns into one set, logging the invalid ones
+fn build_globset(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match Glob::new(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => log::warn!("Invalid automation path pattern {}: {}", pattern, e),
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        log::warn!("Invalid automation path patterns: {}", e);
+        GlobSet::empty()
+    })
+}
+
+/// Whether `relative` is under one of the directory `patterns` or matches their globs
+fn matches_any(patterns: &[String], globs: &GlobSet, relative: &Path) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| relative.starts_with(pattern.trim_end_matches('/')))
+        || globs.is_match(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_scope() {
+        let root = Path::new("/project");
+        let allows = |scope: &PathScope, path: &str| scope.allows(&root.join(path), root);
+
+        let everything = PathScope::default();
+        assert!(everything.is_unrestricted());
+        assert!(allows(&everything, "examples/demo.py"));
+
+        let scope = PathScope::new(
+            vec!["src/**".to_string(), "scripts".to_string()],
+            vec!["src/generated/**".to_string(), "[".to_string()],
+        );
+        assert!(!scope.is_unrestricted());
+        assert!(allows(&scope, "src/app/models.py"));
+        assert!(allows(&scope, "scripts/deploy.py"));
+        assert!(!allows(&scope, "src/generated/api.py"));
+        assert!(!allows(&scope, "tests/test_models.py"));
+        assert!(!allows(&scope, "scriptsx/deploy.py"));
+
+        // Outside the project the path is matched as given
+        let skip_tests = PathScope::new(vec![], vec!["tests/**".to_string()]);
+        assert!(!skip_tests.allows(Path::new("tests/test_app.py"), root));
+        assert!(skip_tests.allows(Path::new("/elsewhere/tests/test_app.py"), root));
+
+        assert!(PathScope::try_new(vec!["src/**".to_string()], vec![]).is_ok());
+        assert!(PathScope::try_new(vec![], vec!["[".to_string()]).is_err());
+    }
+}
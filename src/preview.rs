@@ -0,0 +1,59 @@
+use crate::discovery::ToolVersion;
+use crate::locking::LockStatus;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// What the lint and test hooks would do for one file, printed as JSON by `preview` so
+/// editor plugins can show guardrails status inline
+#[derive(Debug, Clone, Serialize)]
+pub struct FilePreview {
+    pub file: PathBuf,
+    /// Project the hooks would run in, when one was found
+    pub project_root: Option<PathBuf>,
+    /// `exclude.patterns` and file rules (size, binary, generated, vendored)
+    pub excluded: bool,
+    pub exclusion_reason: Option<String>,
+    /// Formatter run before linting
+    pub formatter: Option<String>,
+    /// Test file the test hook maps the file to
+    pub test_file: Option<PathBuf>,
+    /// Versions of the project's linter, formatter and test runner, to compare with CI
+    pub tool_versions: Vec<ToolVersion>,
+    pub lint: OperationPreview,
+    pub test: OperationPreview,
+}
+
+/// One hook's view of the file
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationPreview {
+    /// `automation.<operation>.enabled`
+    pub enabled: bool,
+    /// Excluded for this operation (`lint_skip` / `test_skip` included)
+    pub excluded: bool,
+    pub exclusion_reason: Option<String>,
+    /// Command the hook would run, when a tool was found
+    pub command: Option<String>,
+    /// Lock state for the file; a running operation or remaining cooldown skips the hook
+    pub lock: Option<LockStatus>,
+    /// Whether an edit right now would run `command`
+    pub would_run: bool,
+}
+
+impl OperationPreview {
+    pub fn new(
+        enabled: bool,
+        exclusion_reason: Option<String>,
+        command: Option<String>,
+        lock: Option<LockStatus>,
+    ) -> Self {
+        let idle = lock.is_none_or(|lock| !lock.running && lock.cooldown_remaining_seconds == 0);
+        Self {
+            enabled,
+            excluded: exclusion_reason.is_some(),
+            would_run: enabled && exclusion_reason.is_none() && command.is_some() && idle,
+            exclusion_reason,
+            command,
+            lock,
+        }
+    }
+}
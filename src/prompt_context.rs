@@ -0,0 +1,446 @@
+use crate::discovery::SearchLimits;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Files marking a project root, for any supported language
+const ROOT_MARKERS: &[&str] = &[
+    "pyproject.toml",
+    "setup.py",
+    "setup.cfg",
+    "requirements.txt",
+    "Pipfile",
+    "poetry.lock",
+    "uv.lock",
+    "tox.ini",
+    "noxfile.py",
+    "pytest.ini",
+    "manage.py",
+    "Cargo.toml",
+    "package.json",
+    "tsconfig.json",
+    "go.mod",
+];
+
+/// Directories named like this hold a project's tests
+const TEST_DIRS: &[&str] = &["tests", "test"];
+
+/// Depth of the directory tree excerpt below the root
+const TREE_DEPTH: usize = 2;
+
+/// Lines of the directory tree excerpt
+const MAX_TREE_ENTRIES: usize = 40;
+
+/// Dependency names listed
+const MAX_DEPENDENCIES: usize = 30;
+
+/// Depth searched for test files
+const TEST_SEARCH_DEPTH: usize = 4;
+
+/// What an AI prompt is told about the project a file belongs to: root markers, a
+/// directory tree excerpt, declared dependencies and where tests live
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectSnapshot {
+    pub root: PathBuf,
+    /// Marker files present in the root
+    pub markers: Vec<String>,
+    /// Indented tree excerpt, directories suffixed with `/`
+    pub tree: Vec<String>,
+    pub dependencies: Vec<String>,
+    /// Test directories (relative to the root) with their number of test files
+    pub test_dirs: Vec<(String, usize)>,
+    /// conftest.py files, relative to the root
+    pub conftests: Vec<String>,
+}
+
+impl ProjectSnapshot {
+    /// Snapshot of the nearest ancestor of `path` with a root marker, or of the
+    /// directory of `path` when there is none
+    pub fn for_path(path: &Path) -> Self {
+        let dir = if path.is_file() {
+            path.parent().unwrap_or(path)
+        } else {
+            path
+        };
+        let root = dir
+            .ancestors()
+            .find(|ancestor| {
+                ROOT_MARKERS
+                    .iter()
+                    .any(|marker| ancestor.join(marker).is_file())
+            })
+            .unwrap_or(dir);
+        Self::of_root(root)
+    }
+
+    fn of_root(root: &Path) -> Self {
+        let markers = ROOT_MARKERS
+            .iter()
+            .filter(|marker| root.join(marker).is_file())
+            .map(|marker| marker.to_string())
+            .collect();
+
+        let mut tree = Vec::new();
+        collect_tree(root, 0, &mut tree);
+
+        let dependencies = declared_dependencies(root);
+
+        let mut test_files = Vec::new();
+        let mut conftests = Vec::new();
+        collect_tests(root, root, 0, &mut test_files, &mut conftests);
+        let mut test_dirs: Vec<(String, usize)> = Vec::new();
+        for file in &test_files {
+            let dir = file
+                .parent()
+                .map(|dir| dir.display().to_string())
+                .filter(|dir| !dir.is_empty())
+                .unwrap_or_else(|| ".".to_string());
+            match test_dirs.iter_mut().find(|(name, _)| *name == dir) {
+                Some((_, count)) => *count += 1,
+                None => test_dirs.push((dir, 1)),
+            }
+        }
+        test_dirs.sort();
+        conftests.sort();
+
+        Self {
+            root: root.to_path_buf(),
+            markers,
+            tree,
+            dependencies,
+            test_dirs,
+            conftests,
+        }
+    }
+
+    /// Prompt section describing the project
+    pub fn render(&self) -> String {
+        let mut section = format!("Project root: {}", self.root.display());
+        if !self.markers.is_empty() {
+            section.push_str(&format!("\nRoot markers: {}", self.markers.join(", ")));
+        }
+        if !self.tree.is_empty() {
+            section.push_str(&format!("\nLayout:\n{}", self.tree.join("\n")));
+        }
+        if !self.dependencies.is_empty() {
+            let listed = &self.dependencies[..self.dependencies.len().min(MAX_DEPENDENCIES)];
+            section.push_str(&format!("\nDependencies: {}", listed.join(", ")));
+            if self.dependencies.len() > listed.len() {
+                section.push_str(&format!(
+                    " (and {} more)",
+                    self.dependencies.len() - listed.len()
+                ));
+            }
+        }
+        let test_layout = if self.test_dirs.is_empty() {
+            "no test files found".to_string()
+        } else {
+            self.test_dirs
+                .iter()
+                .map(|(dir, count)| {
+                    let plural = if *count == 1 { "" } else { "s" };
+                    format!("{dir} ({count} test file{plural})")
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        section.push_str(&format!("\nTest layout: {test_layout}"));
+        if !self.conftests.is_empty() {
+            section.push_str(&format!("; fixtures in {}", self.conftests.join(", ")));
+        }
+        section
+    }
+}
+
+fn sorted_entries(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| !(path.is_dir() && SearchLimits::default().skips(path)))
+        .collect();
+    // Directories first, then files, each alphabetically
+    entries.sort_by_key(|path| (!path.is_dir(), path.file_name().map(|name| name.to_owned())));
+    entries
+}
+
+fn collect_tree(dir: &Path, depth: usize, tree: &mut Vec<String>) {
+    for path in sorted_entries(dir) {
+        if tree.len() >= MAX_TREE_ENTRIES {
+            if tree.last().is_some_and(|line| line.trim() != "...") {
+                tree.push(format!("{}...", "  ".repeat(depth)));
+            }
+            return;
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if path.is_dir() {
+            tree.push(format!("{}{name}/", "  ".repeat(depth)));
+            if depth + 1 < TREE_DEPTH {
+                collect_tree(&path, depth + 1, tree);
+            }
+        } else if !name.starts_with('.') {
+            tree.push(format!("{}{name}", "  ".repeat(depth)));
+        }
+    }
+}
+
+fn collect_tests(
+    root: &Path,
+    dir: &Path,
+    depth: usize,
+    test_files: &mut Vec<PathBuf>,
+    conftests: &mut Vec<String>,
+) {
+    if depth > TEST_SEARCH_DEPTH {
+        return;
+    }
+    for path in sorted_entries(dir) {
+        let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+        if path.is_dir() {
+            collect_tests(root, &path, depth + 1, test_files, conftests);
+        } else if relative
+            .file_name()
+            .is_some_and(|name| name == "conftest.py")
+        {
+            conftests.push(relative.display().to_string());
+        } else if is_test_file(&relative) {
+            test_files.push(relative);
+        }
+    }
+}
+
+/// Test files by naming convention (`test_*.py`, `*_test.go`, `*.test.ts` and the like),
+/// plus Rust integration tests under `tests/`
+fn is_test_file(relative: &Path) -> bool {
+    let Some(name) = relative.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let Some((stem, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    if !matches!(extension, "py" | "rs" | "js" | "jsx" | "ts" | "tsx" | "go") {
+        return false;
+    }
+    stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with(".test")
+        || stem.ends_with(".spec")
+        || (extension == "rs"
+            && relative.components().any(|component| {
+                TEST_DIRS.contains(&component.as_os_str().to_string_lossy().as_ref())
+            }))
+}
+
+/// Names of the dependencies the root's manifests declare
+pub(crate) fn declared_dependencies(root: &Path) -> Vec<String> {
+    let mut dependencies = Vec::new();
+
+    for entry in fs::read_dir(root).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with("requirements") && name.ends_with(".txt") {
+            if let Ok(content) = fs::read_to_string(entry.path()) {
+                dependencies.extend(
+                    content
+                        .lines()
+                        .map(|line| line.split('#').next().unwrap_or("").trim())
+                        .filter(|line| !line.is_empty() && !line.starts_with('-'))
+                        .filter_map(requirement_name),
+                );
+            }
+        }
+    }
+
+    if let Ok(content) = fs::read_to_string(root.join("pyproject.toml")) {
+        dependencies.extend(pyproject_dependencies(&content));
+    }
+
+    if let Ok(content) = fs::read_to_string(root.join("package.json")) {
+        if let Ok(package) = serde_json::from_str::<serde_json::Value>(&content) {
+            for key in ["dependencies", "devDependencies"] {
+                if let Some(table) = package.get(key).and_then(|table| table.as_object()) {
+                    dependencies.extend(table.keys().cloned());
+                }
+            }
+        }
+    }
+
+    if let Ok(content) = fs::read_to_string(root.join("Cargo.toml")) {
+        dependencies.extend(toml_table_keys(
+            &content,
+            &["dependencies", "dev-dependencies"],
+        ));
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    dependencies.retain(|name| seen.insert(name.to_lowercase()));
+    dependencies
+}
+
+/// Distribution name of a PEP 508 requirement (`Flask[async]>=3.0` is `Flask`)
+fn requirement_name(requirement: &str) -> Option<String> {
+    let name: String = requirement
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+/// PEP 621 `[project]` dependencies, optional dependencies and Poetry dependency tables
+fn pyproject_dependencies(content: &str) -> Vec<String> {
+    let mut dependencies = Vec::new();
+    let mut section = "";
+    let mut in_array = false;
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.starts_with('[') && !in_array {
+            section = line.trim_matches(|c| c == '[' || c == ']').trim();
+            continue;
+        }
+
+        let is_poetry_table =
+            section.starts_with("tool.poetry") && section.ends_with("dependencies");
+        if is_poetry_table {
+            if let Some((key, _)) = line.split_once('=') {
+                let key = key.trim().trim_matches('"');
+                if key != "python" && !key.is_empty() {
+                    dependencies.push(key.to_string());
+                }
+            }
+            continue;
+        }
+
+        let in_pep621 = section == "project" || section == "project.optional-dependencies";
+        if !in_pep621 {
+            continue;
+        }
+        let values = if in_array {
+            line
+        } else {
+            match line.split_once('=') {
+                Some((key, value))
+                    if section == "project.optional-dependencies"
+                        || key.trim() == "dependencies" =>
+                {
+                    in_array = value.trim_start().starts_with('[');
+                    value
+                }
+                _ => continue,
+            }
+        };
+        if values.contains(']') {
+            in_array = false;
+        }
+        dependencies.extend(
+            values
+                .split(['"', '\''])
+                .skip(1)
+                .step_by(2)
+                .filter_map(requirement_name),
+        );
+    }
+    dependencies
+}
+
+/// Keys of the given top-level tables of a TOML file
+fn toml_table_keys(content: &str, tables: &[&str]) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut in_table = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_table = tables.contains(&line.trim_matches(|c| c == '[' || c == ']').trim());
+        } else if in_table {
+            if let Some((key, _)) = line.split_once('=') {
+                let key = key.trim().trim_matches('"');
+                if !key.is_empty() && !key.starts_with('#') {
+                    keys.push(key.to_string());
+                }
+            }
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_snapshot_of_nested_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("pyproject.toml"),
+            r#"[project]
+name = "shop"
+dependencies = [
+    "fastapi>=0.110",
+    "pydantic[email]~=2.6",
+]
+
+[project.optional-dependencies]
+dev = ["pytest", "ruff"]
+"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("requirements.txt"),
+            "# pinned\nFastAPI==0.110\nhttpx\n",
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("src/shop/api")).unwrap();
+        fs::create_dir_all(root.join("tests/unit")).unwrap();
+        fs::create_dir_all(root.join("node_modules/left-pad")).unwrap();
+        fs::write(root.join("src/shop/api/routes.py"), "").unwrap();
+        fs::write(root.join("tests/conftest.py"), "").unwrap();
+        fs::write(root.join("tests/unit/test_routes.py"), "").unwrap();
+        fs::write(root.join("tests/unit/test_models.py"), "").unwrap();
+
+        let snapshot = ProjectSnapshot::for_path(&root.join("src/shop/api/routes.py"));
+        assert_eq!(snapshot.root, root);
+        assert_eq!(snapshot.markers, ["pyproject.toml", "requirements.txt"]);
+        assert_eq!(
+            snapshot.dependencies,
+            ["FastAPI", "httpx", "pydantic", "pytest", "ruff"]
+        );
+        assert_eq!(snapshot.test_dirs, [("tests/unit".to_string(), 2)]);
+        assert_eq!(snapshot.conftests, ["tests/conftest.py"]);
+
+        let rendered = snapshot.render();
+        assert!(rendered
+            .contains("Layout:\nsrc/\n  shop/\ntests/\n  unit/\n  conftest.py\npyproject.toml"));
+        assert!(!rendered.contains("node_modules"));
+        assert!(rendered
+            .contains("Test layout: tests/unit (2 test files); fixtures in tests/conftest.py"));
+    }
+
+    #[test]
+    fn test_snapshot_of_other_languages() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(
+            root.join("package.json"),
+            r#"{"dependencies": {"react": "^18"}, "devDependencies": {"vitest": "^1"}}"#,
+        )
+        .unwrap();
+        fs::write(root.join("app.test.ts"), "").unwrap();
+
+        let snapshot = ProjectSnapshot::for_path(root);
+        assert_eq!(snapshot.markers, ["package.json"]);
+        assert_eq!(snapshot.dependencies, ["react", "vitest"]);
+        assert_eq!(snapshot.test_dirs, [(".".to_string(), 1)]);
+
+        assert_eq!(
+            toml_table_keys(
+                "[package]\nname = \"x\"\n[dependencies]\nserde = \"1\"\n",
+                &["dependencies"]
+            ),
+            ["serde"]
+        );
+    }
+}
@@ -1,21 +1,67 @@
-use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
-use std::io::{self, Read};
-use std::path::PathBuf;
+use crate::{GuardrailsError, GuardrailsResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming a file that raw hook JSON is appended to, for debugging
+pub const DUMP_INPUT_ENV: &str = "GUARDRAILS_DUMP_INPUT";
+
+/// Environment variable opting in to strict validation against a known protocol version
+pub const PROTOCOL_VERSION_ENV: &str = "GUARDRAILS_PROTOCOL_VERSION";
+
+/// Hook protocol versions strict validation knows about
+const KNOWN_PROTOCOL_VERSIONS: &[&str] = &["1"];
 
 /// Input structure for Claude Code hook events
-#[derive(Debug, Deserialize)]
+///
+/// Parsing is lenient: missing or oddly shaped fields fall back to their defaults so
+/// a Claude Code schema change degrades to NoAction instead of breaking every hook.
+#[derive(Debug, Default, Deserialize)]
 pub struct HookInput {
+    #[serde(default, deserialize_with = "lenient")]
     pub hook_event_name: String,
+    /// Empty for events that aren't about a tool call (e.g. SessionStart)
+    #[serde(default, deserialize_with = "lenient")]
     pub tool_name: String,
+    #[serde(default, deserialize_with = "lenient")]
     pub tool_input: ToolInput,
+    /// Claude Code session the event belongs to
+    #[serde(default, deserialize_with = "lenient")]
+    pub session_id: Option<String>,
+    /// Path to the session's conversation transcript
+    #[serde(default, deserialize_with = "lenient")]
+    pub transcript_path: Option<String>,
+    /// Working directory of the Claude Code session; relative paths resolve against it
+    #[serde(default, deserialize_with = "lenient")]
+    pub cwd: Option<String>,
+    /// Result of the tool call (PostToolUse only)
+    #[serde(default)]
+    pub tool_response: Option<serde_json::Value>,
+    /// Whether Claude is already continuing because a Stop hook blocked (Stop only)
+    #[serde(default, deserialize_with = "lenient")]
+    pub stop_hook_active: bool,
 }
 
-/// Tool input containing file paths
-#[derive(Debug, Deserialize)]
+/// Tool input containing file paths (edit tools) or a shell command (Bash)
+#[derive(Debug, Default, Deserialize)]
 pub struct ToolInput {
+    #[serde(default, deserialize_with = "lenient")]
     pub file_path: Option<String>,
+    #[serde(default, deserialize_with = "lenient")]
     pub notebook_path: Option<String>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub command: Option<String>,
+}
+
+/// Deserialize a field, falling back to its default when the value has an unexpected shape
+fn lenient<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(T::deserialize(value).unwrap_or_default())
 }
 
 /// Response structure for hook communication (not currently used, but ready for future)
@@ -25,24 +71,214 @@ pub struct HookResponse {
     pub message: Option<String>,
 }
 
+/// Structured JSON output understood by Claude Code (printed to stdout, exit 0)
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookOutput {
+    pub hook_specific_output: HookSpecificOutput,
+}
+
+/// Event-specific part of [`HookOutput`]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookSpecificOutput {
+    pub hook_event_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission_decision: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission_decision_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub additional_context: Option<String>,
+}
+
+impl HookOutput {
+    /// Deny a PreToolUse tool call with a reason shown to Claude
+    pub fn deny(reason: &str) -> Self {
+        Self::permission("deny", reason)
+    }
+
+    /// PreToolUse permission decision ("allow", "deny" or "ask") with a reason
+    pub fn permission(decision: &str, reason: &str) -> Self {
+        Self {
+            hook_specific_output: HookSpecificOutput {
+                hook_event_name: "PreToolUse".to_string(),
+                permission_decision: Some(decision.to_string()),
+                permission_decision_reason: Some(reason.to_string()),
+                additional_context: None,
+            },
+        }
+    }
+
+    /// Add context to Claude's conversation for events such as SessionStart
+    pub fn additional_context(hook_event_name: &str, context: &str) -> Self {
+        Self {
+            hook_specific_output: HookSpecificOutput {
+                hook_event_name: hook_event_name.to_string(),
+                permission_decision: None,
+                permission_decision_reason: None,
+                additional_context: Some(context.to_string()),
+            },
+        }
+    }
+
+    /// Serialize to the JSON Claude Code reads from stdout
+    pub fn to_json(&self) -> GuardrailsResult<String> {
+        serde_json::to_string(self)
+            .map_err(|e| GuardrailsError::Protocol(format!("Failed to serialize hook output: {e}")))
+    }
+}
+
 impl HookInput {
     /// Read and parse JSON input from stdin
-    pub fn from_stdin() -> Result<Self> {
+    pub fn from_stdin() -> GuardrailsResult<Self> {
+        Self::from_reader(io::stdin())
+    }
+
+    /// Read and parse hook JSON from any reader, e.g. a captured payload file
+    pub fn from_reader<R: Read>(mut reader: R) -> GuardrailsResult<Self> {
         let mut buffer = String::new();
-        io::stdin()
+        reader
             .read_to_string(&mut buffer)
-            .context("Failed to read from stdin")?;
+            .map_err(|e| GuardrailsError::Protocol(format!("Failed to read hook input: {e}")))?;
+        Self::from_json(&buffer)
+    }
+
+    /// Parse hook JSON, honoring `GUARDRAILS_DUMP_INPUT` and `GUARDRAILS_PROTOCOL_VERSION`
+    /// like input read from stdin
+    pub fn from_json(json: &str) -> GuardrailsResult<Self> {
+        if json.trim().is_empty() {
+            return Err(GuardrailsError::Protocol(
+                "No hook input available".to_string(),
+            ));
+        }
+
+        if let Ok(dump_path) = std::env::var(DUMP_INPUT_ENV) {
+            Self::dump_raw(json, &dump_path);
+        }
+
+        let strict = std::env::var(PROTOCOL_VERSION_ENV)
+            .is_ok_and(|version| KNOWN_PROTOCOL_VERSIONS.contains(&version.trim()));
+        Self::parse(json, strict)
+    }
+
+    /// Build hook input in code, e.g. `HookInput::builder("PostToolUse").tool("Edit")
+    /// .file_path("app.py").build()` in tests
+    pub fn builder(hook_event_name: &str) -> HookInputBuilder {
+        HookInputBuilder {
+            input: HookInput {
+                hook_event_name: hook_event_name.to_string(),
+                ..HookInput::default()
+            },
+        }
+    }
+
+    /// Parse hook JSON; `strict` rejects input that doesn't match the known schema
+    fn parse(json: &str, strict: bool) -> GuardrailsResult<Self> {
+        let invalid = |e: serde_json::Error| {
+            GuardrailsError::Protocol(format!("Failed to parse JSON input: {e}"))
+        };
+        let value: serde_json::Value = serde_json::from_str(json).map_err(invalid)?;
+
+        if strict {
+            Self::validate_strict(&value)?;
+        }
+
+        serde_json::from_value(value).map_err(invalid)
+    }
 
-        if buffer.trim().is_empty() {
-            return Err(anyhow::anyhow!("No input available on stdin"));
+    /// Check the fields every known event carries have the expected types
+    fn validate_strict(value: &serde_json::Value) -> GuardrailsResult<()> {
+        let invalid = |message: &str| Err(GuardrailsError::Protocol(message.to_string()));
+        let Some(object) = value.as_object() else {
+            return invalid("Hook input is not a JSON object");
+        };
+
+        if !object
+            .get("hook_event_name")
+            .is_some_and(serde_json::Value::is_string)
+        {
+            return invalid("Hook input is missing hook_event_name");
+        }
+        if object
+            .get("tool_name")
+            .is_some_and(|tool_name| !tool_name.is_string())
+        {
+            return invalid("Hook input tool_name is not a string");
+        }
+        if object
+            .get("tool_input")
+            .is_some_and(|tool_input| !tool_input.is_object())
+        {
+            return invalid("Hook input tool_input is not an object");
         }
 
-        serde_json::from_str(&buffer).context("Failed to parse JSON input")
+        Ok(())
+    }
+
+    /// Append raw hook JSON to a debug file, one event per line
+    fn dump_raw(buffer: &str, dump_path: &str) {
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dump_path)
+            .and_then(|mut file| writeln!(file, "{}", buffer.trim()));
+        if let Err(e) = written {
+            log::warn!("Failed to dump hook input to {}: {}", dump_path, e);
+        }
     }
 
     /// Check if this is a PostToolUse event we should handle
     pub fn should_process(&self) -> bool {
-        self.hook_event_name == "PostToolUse" && self.is_edit_tool()
+        self.hook_event_name == "PostToolUse" && self.is_edit_tool() && !self.tool_failed()
+    }
+
+    /// Check whether the tool response reports that the call failed or was rejected
+    pub fn tool_failed(&self) -> bool {
+        match &self.tool_response {
+            Some(serde_json::Value::Object(response)) => {
+                response.get("success") == Some(&serde_json::Value::Bool(false))
+                    || response.get("is_error") == Some(&serde_json::Value::Bool(true))
+                    || response.get("error").is_some_and(|error| !error.is_null())
+            }
+            Some(serde_json::Value::String(response)) => {
+                let response = response.trim_start();
+                response.starts_with("Error") || response.starts_with("<tool_use_error>")
+            }
+            _ => false,
+        }
+    }
+
+    /// Check if this is a PreToolUse event for an edit tool (before the edit happens)
+    pub fn is_pre_edit(&self) -> bool {
+        self.hook_event_name == "PreToolUse" && self.is_edit_tool()
+    }
+
+    /// Check if this is a SessionStart event
+    pub fn is_session_start(&self) -> bool {
+        self.hook_event_name == "SessionStart"
+    }
+
+    /// Check if this is a Stop event (Claude finished responding)
+    pub fn is_stop(&self) -> bool {
+        self.hook_event_name == "Stop"
+    }
+
+    /// Check if this is a PreToolUse event for the Bash tool
+    pub fn is_pre_bash(&self) -> bool {
+        self.hook_event_name == "PreToolUse" && self.tool_name == "Bash"
+    }
+
+    /// Check if this is a PostToolUse event for a Bash command that ran successfully
+    pub fn is_post_bash(&self) -> bool {
+        self.hook_event_name == "PostToolUse" && self.tool_name == "Bash" && !self.tool_failed()
+    }
+
+    /// Extract the shell command from Bash tool input
+    pub fn command(&self) -> Option<&str> {
+        match self.tool_name.as_str() {
+            "Bash" => self.tool_input.command.as_deref(),
+            _ => None,
+        }
     }
 
     /// Check if this is an edit-related tool
@@ -53,15 +289,90 @@ impl HookInput {
         )
     }
 
-    /// Extract the file path from the tool input
+    /// Extract the file path from the tool input, resolving relative paths against the
+    /// session's working directory
     pub fn file_path(&self) -> Option<PathBuf> {
-        match self.tool_name.as_str() {
+        let path = match self.tool_name.as_str() {
             "NotebookEdit" => self.tool_input.notebook_path.as_ref().map(PathBuf::from),
             _ => self.tool_input.file_path.as_ref().map(PathBuf::from),
+        }?;
+
+        match self.cwd() {
+            Some(cwd) if path.is_relative() => Some(cwd.join(path)),
+            _ => Some(path),
+        }
+    }
+
+    /// Session working directory from the hook payload
+    pub fn cwd(&self) -> Option<PathBuf> {
+        self.cwd
+            .as_deref()
+            .filter(|cwd| !cwd.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Session working directory, falling back to this process's working directory
+    pub fn working_dir(&self) -> GuardrailsResult<PathBuf> {
+        match self.cwd() {
+            Some(cwd) => Ok(cwd),
+            None => std::env::current_dir().map_err(|e| GuardrailsError::io(Path::new("."), e)),
         }
     }
 }
 
+/// Builder for `HookInput`, see `HookInput::builder`
+#[derive(Debug)]
+pub struct HookInputBuilder {
+    input: HookInput,
+}
+
+impl HookInputBuilder {
+    pub fn tool(mut self, tool_name: &str) -> Self {
+        self.input.tool_name = tool_name.to_string();
+        self
+    }
+
+    /// Edited file (`notebook_path` for NotebookEdit, so set the tool first)
+    pub fn file_path(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into().to_string_lossy().into_owned();
+        match self.input.tool_name.as_str() {
+            "NotebookEdit" => self.input.tool_input.notebook_path = Some(path),
+            _ => self.input.tool_input.file_path = Some(path),
+        }
+        self
+    }
+
+    /// Bash command
+    pub fn command(mut self, command: &str) -> Self {
+        self.input.tool_input.command = Some(command.to_string());
+        self
+    }
+
+    pub fn session_id(mut self, session_id: &str) -> Self {
+        self.input.session_id = Some(session_id.to_string());
+        self
+    }
+
+    pub fn cwd(mut self, cwd: impl Into<PathBuf>) -> Self {
+        self.input.cwd = Some(cwd.into().to_string_lossy().into_owned());
+        self
+    }
+
+    pub fn tool_response(mut self, response: serde_json::Value) -> Self {
+        self.input.tool_response = Some(response);
+        self
+    }
+
+    pub fn stop_hook_active(mut self, active: bool) -> Self {
+        self.input.stop_hook_active = active;
+        self
+    }
+
+    pub fn build(self) -> HookInput {
+        self.input
+    }
+}
+
 impl HookResponse {
     /// Create a continue response (no message to user)
     pub fn continue_silent() -> Self {
@@ -105,6 +416,7 @@ mod tests {
         let input: HookInput = serde_json::from_str(json).unwrap();
         assert_eq!(input.hook_event_name, "PostToolUse");
         assert_eq!(input.tool_name, "Edit");
+        assert_eq!(input.session_id, None);
         assert_eq!(input.file_path(), Some(PathBuf::from("/path/to/file.py")));
         assert!(input.should_process());
         assert!(input.is_edit_tool());
@@ -141,6 +453,166 @@ mod tests {
         let input: HookInput = serde_json::from_str(json).unwrap();
         assert!(!input.should_process());
         assert!(!input.is_edit_tool());
+        assert!(!input.is_pre_bash());
+        assert_eq!(input.command(), Some("ls -la"));
+        assert_eq!(input.file_path(), None);
+    }
+
+    #[test]
+    fn test_lenient_parsing() {
+        // Unknown shapes degrade to defaults instead of failing
+        let json = r#"{
+            "hook_event_name": "PostToolUse",
+            "tool_name": "Edit",
+            "tool_input": "unexpected",
+            "session_id": 42,
+            "new_field": {"nested": true}
+        }"#;
+        let input = HookInput::parse(json, false).unwrap();
+        assert_eq!(input.tool_name, "Edit");
+        assert_eq!(input.file_path(), None);
+        assert_eq!(input.session_id, None);
+
+        let json = r#"{"tool_input": {"file_path": ["a.py"]}}"#;
+        let input = HookInput::parse(json, false).unwrap();
+        assert_eq!(input.hook_event_name, "");
+        assert_eq!(input.file_path(), None);
+        assert!(!input.should_process());
+
+        let json = r#"{"hook_event_name": "PostToolUse", "tool_name": "FutureTool"}"#;
+        let input = HookInput::parse(json, false).unwrap();
+        assert!(!input.should_process());
+
+        assert!(HookInput::parse("not json", false).is_err());
+    }
+
+    #[test]
+    fn test_cwd_path_resolution() {
+        let json = r#"{
+            "hook_event_name": "PostToolUse",
+            "cwd": "/home/dev/project",
+            "tool_name": "Edit",
+            "tool_input": {"file_path": "src/app.py"}
+        }"#;
+        let input = HookInput::parse(json, false).unwrap();
+        assert_eq!(
+            input.file_path(),
+            Some(PathBuf::from("/home/dev/project/src/app.py"))
+        );
+        assert_eq!(
+            input.working_dir().unwrap(),
+            PathBuf::from("/home/dev/project")
+        );
+
+        // Absolute paths are kept as-is
+        let json = json.replace("src/app.py", "/tmp/other.py");
+        let input = HookInput::parse(&json, false).unwrap();
+        assert_eq!(input.file_path(), Some(PathBuf::from("/tmp/other.py")));
+    }
+
+    #[test]
+    fn test_tool_response_failure() {
+        let input = |response: &str| {
+            let json = format!(
+                r#"{{
+                    "hook_event_name": "PostToolUse",
+                    "tool_name": "Edit",
+                    "tool_input": {{"file_path": "a.py"}},
+                    "tool_response": {response}
+                }}"#
+            );
+            HookInput::parse(&json, false).unwrap()
+        };
+
+        assert!(input(r#"{"filePath": "a.py", "success": true}"#).should_process());
+        assert!(input("null").should_process());
+
+        for failed in [
+            r#"{"success": false}"#,
+            r#"{"is_error": true}"#,
+            r#"{"error": "String to replace not found in file"}"#,
+            r#""Error: File has been modified since read""#,
+            r#""<tool_use_error>User rejected the edit</tool_use_error>""#,
+        ] {
+            let input = input(failed);
+            assert!(input.tool_failed(), "{failed}");
+            assert!(!input.should_process(), "{failed}");
+        }
+    }
+
+    #[test]
+    fn test_strict_parsing() {
+        let valid = r#"{
+            "hook_event_name": "PostToolUse",
+            "tool_name": "Edit",
+            "tool_input": {"file_path": "a.py"}
+        }"#;
+        assert!(HookInput::parse(valid, true).is_ok());
+        assert!(HookInput::parse(r#"{"hook_event_name": "SessionStart"}"#, true).is_ok());
+
+        assert!(HookInput::parse(r#"{"tool_name": "Edit"}"#, true).is_err());
+        assert!(HookInput::parse(
+            r#"{"hook_event_name": "PostToolUse", "tool_input": "a.py"}"#,
+            true
+        )
+        .is_err());
+        assert!(HookInput::parse("[]", true).is_err());
+    }
+
+    #[test]
+    fn test_session_start_context_output() {
+        let json = r#"{
+            "session_id": "abc123",
+            "transcript_path": "/home/dev/.claude/projects/app/abc123.jsonl",
+            "hook_event_name": "SessionStart",
+            "source": "startup"
+        }"#;
+
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        assert!(input.is_session_start());
+        assert_eq!(input.session_id.as_deref(), Some("abc123"));
+        assert!(!input.should_process());
+        assert_eq!(input.file_path(), None);
+
+        let output: serde_json::Value = serde_json::from_str(
+            &HookOutput::additional_context("SessionStart", "ruff available")
+                .to_json()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            output["hookSpecificOutput"]["hookEventName"],
+            "SessionStart"
+        );
+        assert_eq!(
+            output["hookSpecificOutput"]["additionalContext"],
+            "ruff available"
+        );
+        assert!(output["hookSpecificOutput"]
+            .get("permissionDecision")
+            .is_none());
+    }
+
+    #[test]
+    fn test_pre_tool_use_deny_output() {
+        let json = r#"{
+            "hook_event_name": "PreToolUse",
+            "tool_name": "Write",
+            "tool_input": {
+                "file_path": "migrations/0001_initial.py"
+            }
+        }"#;
+
+        let input: HookInput = serde_json::from_str(json).unwrap();
+        assert!(input.is_pre_edit());
+        assert!(!input.should_process());
+
+        let output = HookOutput::deny("protected").to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        let specific = &value["hookSpecificOutput"];
+        assert_eq!(specific["hookEventName"], "PreToolUse");
+        assert_eq!(specific["permissionDecision"], "deny");
+        assert_eq!(specific["permissionDecisionReason"], "protected");
     }
 
     #[test]
@@ -157,4 +629,44 @@ mod tests {
         assert_eq!(success_resp.action, "continue");
         assert_eq!(success_resp.message, Some("Test success".to_string()));
     }
+
+    #[test]
+    fn test_hook_input_constructors() -> GuardrailsResult<()> {
+        let json = r#"{"hook_event_name": "PostToolUse", "tool_name": "Write", "tool_input": {"file_path": "app.py"}, "cwd": "/repo"}"#;
+
+        let from_json = HookInput::from_json(json)?;
+        assert_eq!(from_json.file_path(), Some(PathBuf::from("/repo/app.py")));
+        let from_reader = HookInput::from_reader(json.as_bytes())?;
+        assert!(from_reader.should_process());
+        assert!(HookInput::from_reader("  \n".as_bytes()).is_err());
+        assert!(matches!(
+            HookInput::from_json("not json"),
+            Err(GuardrailsError::Protocol(_))
+        ));
+
+        let built = HookInput::builder("PostToolUse")
+            .tool("Write")
+            .file_path("app.py")
+            .cwd("/repo")
+            .session_id("abc")
+            .build();
+        assert_eq!(built.file_path(), from_json.file_path());
+        assert_eq!(built.session_id.as_deref(), Some("abc"));
+
+        let notebook = HookInput::builder("PostToolUse")
+            .tool("NotebookEdit")
+            .file_path("/repo/analysis.ipynb")
+            .build();
+        assert_eq!(
+            notebook.file_path(),
+            Some(PathBuf::from("/repo/analysis.ipynb"))
+        );
+        let bash = HookInput::builder("PreToolUse")
+            .tool("Bash")
+            .command("git push --force")
+            .build();
+        assert!(bash.is_pre_bash());
+        assert_eq!(bash.command(), Some("git push --force"));
+        Ok(())
+    }
 }
@@ -0,0 +1,453 @@
+use std::fmt::Write;
+
+/// Outcome of a single pytest test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed,
+    Error,
+    Skipped,
+}
+
+impl Outcome {
+    fn parse(word: &str) -> Option<Self> {
+        match word {
+            "PASSED" | "XPASS" => Some(Outcome::Passed),
+            "FAILED" => Some(Outcome::Failed),
+            "ERROR" => Some(Outcome::Error),
+            "SKIPPED" | "XFAIL" => Some(Outcome::Skipped),
+            _ => None,
+        }
+    }
+}
+
+/// A test reported in pytest output
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestCase {
+    /// Node id, e.g. `tests/test_calc.py::TestCalc::test_add[1-2]`
+    pub node_id: String,
+    pub outcome: Outcome,
+    /// Short message from the summary line (`FAILED <id> - <message>`)
+    pub message: String,
+    /// Traceback section printed for the test, if any
+    pub details: String,
+}
+
+impl TestCase {
+    /// File part of the node id
+    pub fn file(&self) -> &str {
+        self.node_id.split("::").next().unwrap_or(&self.node_id)
+    }
+
+    /// Parametrization id, e.g. `1-2` for `test_add[1-2]`
+    pub fn param_id(&self) -> Option<&str> {
+        self.name()
+            .strip_suffix(']')
+            .and_then(|name| name.split_once('['))
+            .map(|(_, id)| id)
+    }
+
+    /// Node id without the parametrization, e.g. `tests/test_calc.py::test_add`
+    pub fn function_id(&self) -> &str {
+        match self.param_id() {
+            Some(id) => &self.node_id[..self.node_id.len() - id.len() - 2],
+            None => &self.node_id,
+        }
+    }
+
+    /// Shell command rerunning just this test (and its parametrization) verbosely
+    pub fn reproduce_command(&self, runner: &str) -> String {
+        format!("{runner} '{}' -x -vv", self.node_id.replace('\'', r"'\''"))
+    }
+
+    /// Exception type behind a failure, from the summary message (`KeyError: 'a'`) or the
+    /// traceback (`tests/test_x.py:9: AssertionError`, `E   ValueError: ...`); plain
+    /// `assert` rewrites count as `AssertionError`
+    pub fn error_type(&self) -> String {
+        let error_lines: Vec<&str> = self
+            .details
+            .lines()
+            .filter_map(|line| line.strip_prefix('E'))
+            .map(str::trim)
+            .collect();
+
+        exception_prefix(&self.message)
+            .or_else(|| self.details.lines().rev().find_map(location_exception))
+            .or_else(|| error_lines.iter().find_map(|line| exception_prefix(line)))
+            .map(str::to_string)
+            .unwrap_or_else(|| {
+                let first_error = error_lines.first().copied().unwrap_or_default();
+                if self.message.starts_with("assert") || first_error.starts_with("assert") {
+                    "AssertionError".to_string()
+                } else if self.outcome == Outcome::Error {
+                    "Error".to_string()
+                } else {
+                    "Failure".to_string()
+                }
+            })
+    }
+
+    /// Summary line message, or the first `E` line of the traceback when pytest
+    /// printed none
+    pub fn error_message(&self) -> String {
+        if !self.message.is_empty() {
+            return self.message.clone();
+        }
+        self.details
+            .lines()
+            .find_map(|line| line.strip_prefix('E'))
+            .map(|line| line.trim().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Dotted class path for JUnit, e.g. `tests.test_calc.TestCalc`
+    fn classname(&self) -> String {
+        let mut parts: Vec<&str> = self.node_id.split("::").collect();
+        parts.pop();
+        let module = parts
+            .first()
+            .map(|file| file.trim_end_matches(".py").replace('/', "."))
+            .unwrap_or_default();
+        std::iter::once(module.as_str())
+            .chain(parts.iter().skip(1).copied())
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// Test name without file and class, e.g. `test_add[1-2]`
+    fn name(&self) -> &str {
+        self.node_id.rsplit("::").next().unwrap_or(&self.node_id)
+    }
+}
+
+/// Tests and timing reconstructed from pytest's console output
+///
+/// Test names come from verbose lines (`<id> PASSED [ 50%]`) and the short test summary
+/// (`FAILED <id> - <message>`, all outcomes with `-rA`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PytestResults {
+    pub cases: Vec<TestCase>,
+    /// Session duration from the final `... in 0.12s` line
+    pub duration_seconds: Option<f64>,
+}
+
+impl PytestResults {
+    pub fn parse(output: &str) -> Self {
+        let mut results = PytestResults::default();
+
+        for line in output.lines() {
+            let line = line.trim_end();
+            if let Some(case) = parse_summary_line(line).or_else(|| parse_verbose_line(line)) {
+                results.record(case);
+            } else if line.starts_with('=') {
+                if let Some(duration) = parse_duration(line) {
+                    results.duration_seconds = Some(duration);
+                }
+            }
+        }
+
+        for case in &mut results.cases {
+            if matches!(case.outcome, Outcome::Failed | Outcome::Error) {
+                case.details = failure_section(output, case.name());
+            }
+        }
+        results
+    }
+
+    /// Add a case, letting the summary line's message complete an earlier verbose line
+    fn record(&mut self, case: TestCase) {
+        match self
+            .cases
+            .iter_mut()
+            .find(|existing| existing.node_id == case.node_id)
+        {
+            Some(existing) => {
+                existing.outcome = case.outcome;
+                if !case.message.is_empty() {
+                    existing.message = case.message;
+                }
+            }
+            None => self.cases.push(case),
+        }
+    }
+
+    /// Tests that failed or errored
+    pub fn failures(&self) -> impl Iterator<Item = &TestCase> {
+        self.cases
+            .iter()
+            .filter(|case| matches!(case.outcome, Outcome::Failed | Outcome::Error))
+    }
+
+    pub fn count(&self, outcome: Outcome) -> usize {
+        self.cases
+            .iter()
+            .filter(|case| case.outcome == outcome)
+            .count()
+    }
+
+    /// Render as a JUnit XML document with a single test suite
+    pub fn to_junit_xml(&self, suite_name: &str) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            xml,
+            "<testsuites>\n  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+            escape_xml(suite_name),
+            self.cases.len(),
+            self.count(Outcome::Failed),
+            self.count(Outcome::Error),
+            self.count(Outcome::Skipped),
+            self.duration_seconds.unwrap_or_default()
+        );
+
+        for case in &self.cases {
+            let _ = write!(
+                xml,
+                "    <testcase classname=\"{}\" name=\"{}\" file=\"{}\"",
+                escape_xml(&case.classname()),
+                escape_xml(case.name()),
+                escape_xml(case.file())
+            );
+            let element = match case.outcome {
+                Outcome::Passed => {
+                    xml.push_str("/>\n");
+                    continue;
+                }
+                Outcome::Failed => "failure",
+                Outcome::Error => "error",
+                Outcome::Skipped => "skipped",
+            };
+            let _ = writeln!(
+                xml,
+                ">\n      <{element} message=\"{}\">{}</{element}>\n    </testcase>",
+                escape_xml(&case.message),
+                escape_xml(&case.details)
+            );
+        }
+
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+}
+
+/// `FAILED tests/test_x.py::test_y - AssertionError: ...` from the short test summary
+fn parse_summary_line(line: &str) -> Option<TestCase> {
+    let (word, rest) = line.split_once(' ')?;
+    let outcome = Outcome::parse(word)?;
+    let (node_id, message) = match rest.split_once(" - ") {
+        Some((node_id, message)) => (node_id.trim(), message.trim()),
+        None => (rest.trim(), ""),
+    };
+    if !node_id.contains("::") {
+        return None;
+    }
+
+    Some(TestCase {
+        node_id: node_id.to_string(),
+        outcome,
+        message: message.to_string(),
+        details: String::new(),
+    })
+}
+
+/// `tests/test_x.py::test_y PASSED                [ 50%]` from `pytest -v`
+fn parse_verbose_line(line: &str) -> Option<TestCase> {
+    let mut words = line.split_whitespace();
+    let node_id = words.next()?;
+    let outcome = Outcome::parse(words.next()?)?;
+    if !node_id.contains("::") {
+        return None;
+    }
+
+    Some(TestCase {
+        node_id: node_id.to_string(),
+        outcome,
+        message: String::new(),
+        details: String::new(),
+    })
+}
+
+/// `KeyError` from `KeyError: 'a'` or `pkg.errors.ApiError: ...`
+fn exception_prefix(text: &str) -> Option<&str> {
+    let (name, _) = text.split_once(':')?;
+    is_exception_name(name).then_some(name)
+}
+
+/// `AssertionError` from the `tests/test_x.py:9: AssertionError` line ending a long traceback
+fn location_exception(line: &str) -> Option<&str> {
+    let (location, name) = line.trim().rsplit_once(": ")?;
+    let (_, line_number) = location.rsplit_once(':')?;
+    (line_number.parse::<u32>().is_ok() && is_exception_name(name)).then_some(name)
+}
+
+fn is_exception_name(name: &str) -> bool {
+    let last = name.rsplit('.').next().unwrap_or(name);
+    last.chars().next().is_some_and(|c| c.is_ascii_uppercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        && [
+            "Error",
+            "Exception",
+            "Exit",
+            "Interrupt",
+            "Warning",
+            "Failed",
+        ]
+        .iter()
+        .any(|suffix| last.ends_with(suffix))
+}
+
+/// `==== 1 failed, 2 passed in 0.12s ====`
+fn parse_duration(line: &str) -> Option<f64> {
+    let (_, after) = line.rsplit_once(" in ")?;
+    let seconds = after.split_whitespace().next()?.strip_suffix('s')?;
+    seconds.parse().ok()
+}
+
+/// Body of the `____ test_name ____` section pytest prints for a failing test
+fn failure_section(output: &str, name: &str) -> String {
+    let mut lines = output.lines().skip_while(|line| {
+        let title = line.trim().trim_matches('_').trim();
+        !(line.starts_with("___") && (title == name || title.ends_with(&format!(".{name}"))))
+    });
+    if lines.next().is_none() {
+        return String::new();
+    }
+
+    lines
+        .take_while(|line| !line.starts_with("___") && !line.starts_with("==="))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+============================= test session starts ==============================
+collected 3 items
+
+tests/test_calc.py::test_add PASSED                                      [ 33%]
+tests/test_calc.py::TestCalc::test_sub FAILED                            [ 66%]
+tests/test_calc.py::test_skip SKIPPED (no reason)                        [100%]
+
+=================================== FAILURES ===================================
+______________________________ TestCalc.test_sub _______________________________
+
+    def test_sub(self):
+>       assert sub(3, 1) == 1
+E       assert 2 == 1
+
+tests/test_calc.py:9: AssertionError
+=========================== short test summary info ============================
+FAILED tests/test_calc.py::TestCalc::test_sub - assert 2 == 1
+==================== 1 failed, 1 passed, 1 skipped in 0.05s ====================
+";
+
+    #[test]
+    fn test_parse_verbose_output() {
+        let results = PytestResults::parse(OUTPUT);
+        assert_eq!(results.cases.len(), 3);
+        assert_eq!(results.count(Outcome::Passed), 1);
+        assert_eq!(results.count(Outcome::Skipped), 1);
+        assert_eq!(results.duration_seconds, Some(0.05));
+
+        let failed = &results.cases[1];
+        assert_eq!(failed.outcome, Outcome::Failed);
+        assert_eq!(failed.message, "assert 2 == 1");
+        assert!(failed.details.contains("E       assert 2 == 1"));
+        assert_eq!(failed.classname(), "tests.test_calc.TestCalc");
+        assert_eq!(failed.name(), "test_sub");
+    }
+
+    #[test]
+    fn test_parse_summary_only() {
+        let results = PytestResults::parse(
+            "PASSED tests/test_a.py::test_one\nERROR tests/test_a.py::test_two - fixture 'db' not found\n",
+        );
+        assert_eq!(results.cases.len(), 2);
+        assert_eq!(results.cases[1].outcome, Outcome::Error);
+        assert_eq!(results.cases[1].message, "fixture 'db' not found");
+        assert!(results.duration_seconds.is_none());
+    }
+
+    #[test]
+    fn test_parametrized_failures() {
+        let results = PytestResults::parse(
+            "\
+tests/test_x.py::test_y[case-1] PASSED                                   [ 33%]
+tests/test_x.py::test_y[case-3] FAILED                                   [ 66%]
+tests/test_x.py::test_y[it's] FAILED                                     [100%]
+FAILED tests/test_x.py::test_y[case-3] - assert 3 == 4
+",
+        );
+        let failures: Vec<&TestCase> = results.failures().collect();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].param_id(), Some("case-3"));
+        assert_eq!(failures[0].function_id(), "tests/test_x.py::test_y");
+        assert_eq!(
+            failures[0].reproduce_command("pytest"),
+            "pytest 'tests/test_x.py::test_y[case-3]' -x -vv"
+        );
+        assert_eq!(
+            failures[1].reproduce_command("uv run pytest"),
+            r"uv run pytest 'tests/test_x.py::test_y[it'\''s]' -x -vv"
+        );
+        assert_eq!(results.cases[0].param_id(), Some("case-1"));
+        assert_eq!(
+            PytestResults::parse(OUTPUT).cases[0].function_id(),
+            "tests/test_calc.py::test_add"
+        );
+    }
+
+    #[test]
+    fn test_error_types() {
+        let failed = &PytestResults::parse(OUTPUT).cases[1];
+        assert_eq!(failed.error_type(), "AssertionError");
+        assert_eq!(failed.error_message(), "assert 2 == 1");
+
+        let short_tb = "\
+___________________________________ test_get ___________________________________
+tests/test_api.py:4: in test_get
+    client.get(\"a\")
+E   api.errors.NotFoundError: no route a
+=========================== short test summary info ============================
+FAILED tests/test_api.py::test_get
+";
+        let failed = &PytestResults::parse(short_tb).cases[0];
+        assert_eq!(failed.error_type(), "api.errors.NotFoundError");
+        assert_eq!(
+            failed.error_message(),
+            "api.errors.NotFoundError: no route a"
+        );
+
+        let results = PytestResults::parse(
+            "FAILED tests/test_a.py::test_one - KeyError: 'a'\nERROR tests/test_a.py::test_two - fixture 'db' not found\n",
+        );
+        assert_eq!(results.cases[0].error_type(), "KeyError");
+        assert_eq!(results.cases[1].error_type(), "Error");
+    }
+
+    #[test]
+    fn test_junit_xml() {
+        let xml = PytestResults::parse(OUTPUT).to_junit_xml("app <calc>");
+        assert!(xml.contains(
+            "<testsuite name=\"app &lt;calc&gt;\" tests=\"3\" failures=\"1\" errors=\"0\" skipped=\"1\" time=\"0.050\">"
+        ));
+        assert!(xml.contains(
+            "<testcase classname=\"tests.test_calc\" name=\"test_add\" file=\"tests/test_calc.py\"/>"
+        ));
+        assert!(xml.contains("<failure message=\"assert 2 == 1\">"));
+        assert!(xml.contains("&gt;       assert sub(3, 1) == 1"));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::state::StateDir;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Environment variable turning on record mode (`1`): every hook payload read from
+/// stdin is archived for `replay`
+pub const RECORD_ENV: &str = "GUARDRAILS_RECORD";
+
+/// State file recorded events are appended to
+pub const RECORDINGS_FILE: &str = "recordings.jsonl";
+
+/// Size at which recordings are rotated to `recordings.1.jsonl`
+const MAX_RECORDINGS_BYTES: u64 = 4 * 1024 * 1024;
+
+/// A hook invocation as recorded: enough to run it again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub timestamp: DateTime<Utc>,
+    /// Command line arguments after the program name, e.g. `["lint", "--ai", "mock"]`
+    pub args: Vec<String>,
+    /// Working directory the hook ran in
+    pub working_dir: PathBuf,
+    /// Raw payload; kept as a string when it wasn't valid JSON
+    pub payload: serde_json::Value,
+}
+
+impl RecordedEvent {
+    pub fn new(args: Vec<String>, working_dir: PathBuf, raw_payload: &str) -> Self {
+        let payload = serde_json::from_str(raw_payload)
+            .unwrap_or_else(|_| serde_json::Value::String(raw_payload.to_string()));
+        Self {
+            timestamp: Utc::now(),
+            args,
+            working_dir,
+            payload,
+        }
+    }
+
+    /// The payload as it was piped to the hook
+    pub fn raw_payload(&self) -> String {
+        match &self.payload {
+            serde_json::Value::String(raw) => raw.clone(),
+            payload => payload.to_string(),
+        }
+    }
+
+    /// One-line description for listings, e.g. `lint PostToolUse Edit app.py`
+    pub fn summary(&self) -> String {
+        let field = |name: &str| self.payload.get(name).and_then(|value| value.as_str());
+        let tool_input = |name: &str| {
+            self.payload
+                .get("tool_input")
+                .and_then(|input| input.get(name))
+                .and_then(|value| value.as_str())
+        };
+        [
+            self.args.first().map(String::as_str),
+            field("hook_event_name"),
+            field("tool_name"),
+            tool_input("file_path")
+                .or_else(|| tool_input("notebook_path"))
+                .or_else(|| tool_input("command")),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+}
+
+/// Archive of raw hook payloads in the workspace's state directory, written in record
+/// mode and read back by `replay`
+#[derive(Debug, Clone)]
+pub struct HookRecorder {
+    path: PathBuf,
+}
+
+impl HookRecorder {
+    pub fn for_workspace(workspace_dir: &Path) -> Result<Self> {
+        Ok(Self {
+            path: StateDir::for_workspace(workspace_dir)?.file(RECORDINGS_FILE),
+        })
+    }
+
+    /// Whether `GUARDRAILS_RECORD` turns record mode on
+    pub fn enabled() -> bool {
+        std::env::var(RECORD_ENV).is_ok_and(|value| value.trim() == "1")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an event, rotating the archive once it exceeds its size limit
+    pub fn record(&self, event: &RecordedEvent) -> Result<()> {
+        if fs::metadata(&self.path).is_ok_and(|metadata| metadata.len() >= MAX_RECORDINGS_BYTES) {
+            fs::rename(&self.path, self.path.with_extension("1.jsonl"))
+                .context("Failed to rotate hook recordings")?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("Failed to open hook recordings")?;
+        writeln!(file, "{}", serde_json::to_string(event)?)
+            .context("Failed to write hook recordings")
+    }
+
+    /// Events in a recordings file, oldest first; a file holding a single raw payload
+    /// (as captured with `GUARDRAILS_DUMP_INPUT`) is read as one `lint` event
+    pub fn load(path: &Path) -> Result<Vec<RecordedEvent>> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let events: Result<Vec<RecordedEvent>> = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Invalid recorded event in {}", path.display()))
+            })
+            .collect();
+        let Err(e) = events else {
+            return events;
+        };
+
+        let Ok(payload) = serde_json::from_str(&content) else {
+            return Err(e);
+        };
+        Ok(vec![RecordedEvent {
+            timestamp: Utc::now(),
+            args: vec!["lint".to_string()],
+            working_dir: std::env::current_dir()?,
+            payload,
+        }])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_load() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let recorder = HookRecorder::for_workspace(temp_dir.path())?;
+        let payload = r#"{"hook_event_name": "PostToolUse", "tool_name": "Edit", "tool_input": {"file_path": "app.py"}}"#;
+
+        recorder.record(&RecordedEvent::new(
+            vec!["lint".to_string()],
+            temp_dir.path().to_path_buf(),
+            payload,
+        ))?;
+        recorder.record(&RecordedEvent::new(
+            vec!["test".to_string()],
+            temp_dir.path().to_path_buf(),
+            "not json",
+        ))?;
+
+        let events = HookRecorder::load(recorder.path())?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].summary(), "lint PostToolUse Edit app.py");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&events[0].raw_payload())?,
+            serde_json::from_str::<serde_json::Value>(payload)?
+        );
+        assert_eq!(events[1].raw_payload(), "not json");
+
+        // A bare payload file replays as a single lint event
+        let single = temp_dir.path().join("payload.json");
+        fs::write(&single, payload)?;
+        let events = HookRecorder::load(&single)?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].args, ["lint"]);
+
+        fs::write(&single, "{ broken")?;
+        assert!(HookRecorder::load(&single).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{
+    GuardrailsChecker, GuardrailsConfig, GuardrailsError, GuardrailsResult, CONFIG_FILE_NAME,
+};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::SystemTime;
+
+/// Project files whose changes rebuild the checker
+const WATCHED_FILES: &[&str] = &[CONFIG_FILE_NAME, "pyproject.toml"];
+
+/// Adjustments applied to every loaded config, e.g. command-line overrides
+type PrepareConfig = Box<dyn Fn(&mut GuardrailsConfig) + Send + Sync>;
+
+/// A `GuardrailsChecker` for a long-running process that is rebuilt when the project's
+/// `guardrails.yaml` or `pyproject.toml` changes
+///
+/// Callers take a snapshot with [`checker`](Self::checker); a reload swaps in the new
+/// checker atomically, so in-flight work keeps the snapshot it started with. An invalid
+/// config is reported and the previous checker stays active.
+pub struct ReloadableChecker {
+    project_root: PathBuf,
+    prepare: PrepareConfig,
+    current: RwLock<Arc<GuardrailsChecker>>,
+    /// Modification times and sizes of `WATCHED_FILES` when the current checker was built
+    stamps: Mutex<Vec<Option<(SystemTime, u64)>>>,
+}
+
+impl ReloadableChecker {
+    /// Build the checker from the project's config (built-in defaults when absent)
+    pub fn load(project_root: &Path) -> GuardrailsResult<Self> {
+        Self::load_with(project_root, |_| {})
+    }
+
+    /// Like [`load`](Self::load), running `prepare` on the config before every build
+    pub fn load_with(
+        project_root: &Path,
+        prepare: impl Fn(&mut GuardrailsConfig) + Send + Sync + 'static,
+    ) -> GuardrailsResult<Self> {
+        let stamps = Self::stamps(project_root);
+        let prepare: PrepareConfig = Box::new(prepare);
+        let checker = Self::build(project_root, &prepare)?;
+        Ok(Self {
+            project_root: project_root.to_path_buf(),
+            prepare,
+            current: RwLock::new(Arc::new(checker)),
+            stamps: Mutex::new(stamps),
+        })
+    }
+
+    fn build(project_root: &Path, prepare: &PrepareConfig) -> GuardrailsResult<GuardrailsChecker> {
+        let mut config = GuardrailsConfig::load_from_dir(project_root)?;
+        prepare(&mut config);
+        GuardrailsChecker::from_config(config)
+    }
+
+    fn stamps(project_root: &Path) -> Vec<Option<(SystemTime, u64)>> {
+        WATCHED_FILES
+            .iter()
+            .map(|name| {
+                let metadata = std::fs::metadata(project_root.join(name)).ok()?;
+                Some((metadata.modified().ok()?, metadata.len()))
+            })
+            .collect()
+    }
+
+    /// The current checker
+    pub fn checker(&self) -> Arc<GuardrailsChecker> {
+        match self.current.read() {
+            Ok(current) => Arc::clone(&current),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner()),
+        }
+    }
+
+    /// Rebuild the checker if a watched file changed since the last build; returns
+    /// whether a new checker was swapped in
+    ///
+    /// A config that fails to load is returned as an error and not retried until the
+    /// files change again.
+    pub fn reload_if_changed(&self) -> GuardrailsResult<bool> {
+        let stamps = Self::stamps(&self.project_root);
+        {
+            let mut previous = match self.stamps.lock() {
+                Ok(previous) => previous,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+            if *previous == stamps {
+                return Ok(false);
+            }
+            *previous = stamps;
+        }
+
+        let checker = Arc::new(
+            Self::build(&self.project_root, &self.prepare)?.share_decision_cache(&self.checker()),
+        );
+        match self.current.write() {
+            Ok(mut current) => *current = checker,
+            Err(poisoned) => *poisoned.into_inner() = checker,
+        }
+        Ok(true)
+    }
+
+    /// Reload on filesystem notifications for the watched files until the returned
+    /// watcher is dropped
+    ///
+    /// The project root is watched rather than the files themselves, so editors that save
+    /// by replacing the file are seen too.
+    pub fn watch(self: &Arc<Self>) -> GuardrailsResult<RecommendedWatcher> {
+        let reloadable: Weak<Self> = Arc::downgrade(self);
+        let on_event = move |event: notify::Result<Event>| {
+            let touches_config = event.is_ok_and(|event| {
+                event.paths.iter().any(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| WATCHED_FILES.contains(&name))
+                })
+            });
+            let Some(reloadable) = reloadable.upgrade().filter(|_| touches_config) else {
+                return;
+            };
+            match reloadable.reload_if_changed() {
+                Ok(true) => log::info!(
+                    "Reloaded guardrails config for {}",
+                    reloadable.project_root.display()
+                ),
+                Ok(false) => {}
+                Err(e) => log::warn!("Keeping previous guardrails config: {e:#}"),
+            }
+        };
+
+        let watch_error =
+            |e: notify::Error| GuardrailsError::io(&self.project_root, std::io::Error::other(e));
+        let mut watcher = notify::recommended_watcher(on_event).map_err(watch_error)?;
+        watcher
+            .watch(&self.project_root, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+        Ok(watcher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::fs;
+    use std::time::{Duration, Instant};
+    use tempfile::TempDir;
+
+    /// Write a file and move its mtime forward so the change is seen even within the
+    /// filesystem's timestamp granularity
+    fn write_later(path: &Path, content: &str, seconds: u64) -> Result<()> {
+        fs::write(path, content)?;
+        let file = fs::File::options().write(true).open(path)?;
+        file.set_modified(SystemTime::now() + Duration::from_secs(seconds))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_reload_on_config_change() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        let config_path = root.join(CONFIG_FILE_NAME);
+        let vendored = root.join("vendor/lib.py");
+        fs::write(&config_path, "exclude:\n  patterns: []\n")?;
+
+        let reloadable = ReloadableChecker::load(root)?;
+        let before = reloadable.checker();
+        assert!(!before.should_exclude(&vendored)?);
+        assert!(!reloadable.reload_if_changed()?);
+
+        write_later(
+            &config_path,
+            "exclude:\n  patterns: [\"**/vendor/**\"]\n",
+            10,
+        )?;
+        assert!(reloadable.reload_if_changed()?);
+        assert!(reloadable.checker().should_exclude(&vendored)?);
+        // Earlier snapshots are unaffected
+        assert!(!before.should_exclude(&vendored)?);
+
+        // Invalid config keeps the previous checker
+        write_later(&config_path, "exclude:\n  patterns: [\"[\"]\n", 20)?;
+        assert!(reloadable.reload_if_changed().is_err());
+        assert!(reloadable.checker().should_exclude(&vendored)?);
+        assert!(!reloadable.reload_if_changed()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_watch_reloads_on_notification() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        let config_path = root.join(CONFIG_FILE_NAME);
+        let vendored = root.join("vendor/lib.py");
+        fs::write(&config_path, "exclude:\n  patterns: []\n")?;
+
+        let reloadable = Arc::new(ReloadableChecker::load_with(root, |config| {
+            config.exclude.patterns.push("*.tmp".to_string());
+        })?);
+        assert!(reloadable
+            .checker()
+            .should_exclude(Path::new("scratch.tmp"))?);
+        let _watcher = reloadable.watch()?;
+
+        write_later(
+            &config_path,
+            "exclude:\n  patterns: [\"**/vendor/**\"]\n",
+            10,
+        )?;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !reloadable.checker().should_exclude(&vendored)? {
+            assert!(Instant::now() < deadline, "config was not reloaded");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        // `prepare` runs on every reload
+        assert!(reloadable
+            .checker()
+            .should_exclude(Path::new("scratch.tmp"))?);
+
+        Ok(())
+    }
+}
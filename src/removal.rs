@@ -0,0 +1,280 @@
+use crate::checks::parse_imports;
+use crate::discovery::SearchLimits;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A Python file deleted or moved away by a tool call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovedModule {
+    /// Where the file used to be
+    pub path: PathBuf,
+    /// New location when the file was renamed rather than deleted
+    pub renamed_to: Option<PathBuf>,
+}
+
+/// Python files an `rm`, `mv`, `git rm` or `git mv` command removed, resolved against
+/// `cwd`
+///
+/// Only paths that no longer exist are returned, so failed commands and arguments that
+/// weren't files are ignored.
+pub fn removed_by_command(command: &str, cwd: &Path) -> Vec<RemovedModule> {
+    let mut removed = Vec::new();
+    for segment in command.split(['&', ';', '|', '\n']) {
+        let mut words: Vec<&str> = segment
+            .split_whitespace()
+            .map(|word| word.trim_matches(|c| c == '"' || c == '\''))
+            .collect();
+        if words.first() == Some(&"git") {
+            words.remove(0);
+        }
+        let Some((&program, args)) = words.split_first() else {
+            continue;
+        };
+        let paths: Vec<PathBuf> = args
+            .iter()
+            .filter(|arg| !arg.starts_with('-'))
+            .map(|arg| cwd.join(arg))
+            .collect();
+
+        match program {
+            "rm" => removed.extend(
+                paths
+                    .into_iter()
+                    .filter(|path| is_removed_python_file(path))
+                    .map(|path| RemovedModule {
+                        path,
+                        renamed_to: None,
+                    }),
+            ),
+            "mv" => {
+                let Some((destination, sources)) = paths.split_last() else {
+                    continue;
+                };
+                for source in sources.iter().filter(|path| is_removed_python_file(path)) {
+                    let target = match source.file_name() {
+                        Some(name) if destination.is_dir() => destination.join(name),
+                        _ => destination.clone(),
+                    };
+                    removed.push(RemovedModule {
+                        path: source.clone(),
+                        renamed_to: target.exists().then_some(target),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    removed
+}
+
+/// How git status accounts for a Python file that no longer exists: deleted, or
+/// renamed when the rename is staged (`git mv`)
+pub fn git_removal(path: &Path) -> Option<RemovedModule> {
+    let dir = path.ancestors().skip(1).find(|dir| dir.is_dir())?;
+    let toplevel = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let toplevel = PathBuf::from(String::from_utf8_lossy(&toplevel.stdout).trim());
+    let status = Command::new("git")
+        .args(["status", "--porcelain", "--untracked-files=no"])
+        .current_dir(dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let path = normalize(path);
+    parse_porcelain(&String::from_utf8_lossy(&status.stdout), &toplevel)
+        .into_iter()
+        .find(|removed| removed.path == path)
+}
+
+/// Deletions and renames in `git status --porcelain` output, as paths below `toplevel`
+fn parse_porcelain(status: &str, toplevel: &Path) -> Vec<RemovedModule> {
+    status
+        .lines()
+        .filter(|line| line.len() > 3)
+        .filter_map(|line| {
+            let (code, path) = line.split_at(3);
+            let path = path.trim_matches('"');
+            if code.contains('R') {
+                let (old, new) = path.split_once(" -> ")?;
+                Some(RemovedModule {
+                    path: toplevel.join(old.trim_matches('"')),
+                    renamed_to: Some(toplevel.join(new.trim_matches('"'))),
+                })
+            } else if code.contains('D') {
+                Some(RemovedModule {
+                    path: toplevel.join(path),
+                    renamed_to: None,
+                })
+            } else {
+                None
+            }
+        })
+        .filter(|removed| removed.path.extension().is_some_and(|ext| ext == "py"))
+        .collect()
+}
+
+/// Test files (and conftest.py) below `project_root` that still import `module`, with
+/// the line of each import
+pub fn stale_references(project_root: &Path, module: &str) -> Vec<(PathBuf, usize)> {
+    let mut references = Vec::new();
+    let mut pending = vec![project_root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+        for path in paths {
+            if path.is_dir() {
+                if !SearchLimits::default().skips(&path) {
+                    pending.push(path);
+                }
+            } else if is_test_file(&path) {
+                references.extend(
+                    import_lines(&path, module)
+                        .into_iter()
+                        .map(|line| (path.clone(), line)),
+                );
+            }
+        }
+    }
+    references
+}
+
+/// Lines where `file` imports `module` (or something from it) with an absolute import
+pub(crate) fn import_lines(file: &Path, module: &str) -> Vec<usize> {
+    let Ok(source) = std::fs::read_to_string(file) else {
+        return Vec::new();
+    };
+    parse_imports(&source)
+        .into_iter()
+        .filter(|import| import.level == 0)
+        .filter(|import| {
+            imports_module(&import.module, module)
+                || import
+                    .names
+                    .iter()
+                    .any(|name| format!("{}.{name}", import.module) == module)
+        })
+        .map(|import| import.line)
+        .collect()
+}
+
+fn imports_module(imported: &str, module: &str) -> bool {
+    imported == module
+        || imported
+            .strip_prefix(module)
+            .is_some_and(|rest| rest.starts_with('.'))
+}
+
+pub(crate) fn is_test_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| {
+            name == "conftest.py"
+                || (name.ends_with(".py")
+                    && (name.starts_with("test") || name.ends_with("_test.py")))
+        })
+}
+
+fn is_removed_python_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "py") && !path.exists()
+}
+
+/// `path` with its (still existing) directory canonicalized, to compare against git's
+/// absolute paths
+fn normalize(path: &Path) -> PathBuf {
+    match (path.parent().map(Path::canonicalize), path.file_name()) {
+        (Some(Ok(parent)), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_removed_by_command() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("shop"))?;
+        fs::write(root.join("shop/orders_v2.py"), "")?;
+        fs::write(root.join("shop/kept.py"), "")?;
+        fs::write(root.join("shop/util.py"), "")?;
+
+        let removed = removed_by_command(
+            "rm -f shop/legacy.py shop/kept.py && git mv shop/orders.py shop/orders_v2.py; mv 'util.py' shop",
+            root,
+        );
+        assert_eq!(
+            removed,
+            [
+                RemovedModule {
+                    path: root.join("shop/legacy.py"),
+                    renamed_to: None,
+                },
+                RemovedModule {
+                    path: root.join("shop/orders.py"),
+                    renamed_to: Some(root.join("shop/orders_v2.py")),
+                },
+                RemovedModule {
+                    path: root.join("util.py"),
+                    renamed_to: Some(root.join("shop/util.py")),
+                },
+            ]
+        );
+        assert!(removed_by_command("ls shop && cat shop/legacy.py", root).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_porcelain() {
+        let status = " D shop/legacy.py\nR  shop/orders.py -> shop/orders_v2.py\n M shop/kept.py\nD  README.md\n";
+        let root = Path::new("/repo");
+        assert_eq!(
+            parse_porcelain(status, root),
+            [
+                RemovedModule {
+                    path: root.join("shop/legacy.py"),
+                    renamed_to: None,
+                },
+                RemovedModule {
+                    path: root.join("shop/orders.py"),
+                    renamed_to: Some(root.join("shop/orders_v2.py")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stale_references() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("tests"))?;
+        fs::write(
+            root.join("tests/test_orders.py"),
+            "import pytest\nfrom shop.orders import total\n",
+        )?;
+        fs::write(root.join("tests/test_cart.py"), "from shop import orders\n")?;
+        fs::write(root.join("tests/test_other.py"), "import shop.orders_v2\n")?;
+        fs::write(root.join("app.py"), "import shop.orders\n")?;
+
+        assert_eq!(
+            stale_references(root, "shop.orders"),
+            [
+                (root.join("tests/test_cart.py"), 1),
+                (root.join("tests/test_orders.py"), 2),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,431 @@
+use crate::history::HistoryEntry;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of AI suggestions listed in a report
+const MAX_SUGGESTIONS: usize = 10;
+
+/// Output format of the `report` command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            "html" => Ok(ReportFormat::Html),
+            other => Err(format!(
+                "unknown report format '{other}' (expected markdown or html)"
+            )),
+        }
+    }
+}
+
+/// Where a file's lint runs ended up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintStatus {
+    NotLinted,
+    /// Never failed
+    Clean,
+    /// Failed at some point, passing now
+    Fixed,
+    /// Latest run failed
+    Remaining,
+}
+
+/// Lint and test outcomes for one file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileSummary {
+    pub file: PathBuf,
+    pub lint_runs: usize,
+    pub lint_status: LintStatus,
+    pub test_runs: usize,
+    pub tests_passed: usize,
+}
+
+/// Run count and time spent for one operation
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StepTiming {
+    pub runs: usize,
+    pub total_ms: u64,
+}
+
+/// Summary of lint/test runs, rendered for pasting into a PR description
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunReport {
+    /// Session the report covers; `None` covers the whole history
+    pub session_id: Option<String>,
+    pub files: Vec<FileSummary>,
+    /// Keyed by operation ("lint", "test")
+    pub timings: BTreeMap<String, StepTiming>,
+    /// Fix suggestions from AI analyses, deduplicated
+    pub suggestions: Vec<String>,
+}
+
+impl RunReport {
+    /// Build a report from history entries
+    ///
+    /// Covers `session_id` when given, otherwise the session of the most recent run
+    /// (or every run when no session was ever recorded). File paths are shown relative
+    /// to `project_root`.
+    pub fn from_history(
+        entries: &[HistoryEntry],
+        session_id: Option<&str>,
+        project_root: &Path,
+    ) -> Self {
+        let session_id = session_id.map(str::to_string).or_else(|| {
+            entries
+                .iter()
+                .rev()
+                .find_map(|entry| entry.session_id.clone())
+        });
+        let entries: Vec<&HistoryEntry> = entries
+            .iter()
+            .filter(|entry| session_id.is_none() || entry.session_id == session_id)
+            .collect();
+
+        let mut files: Vec<FileSummary> = Vec::new();
+        let mut timings: BTreeMap<String, StepTiming> = BTreeMap::new();
+        let mut suggestions: Vec<String> = Vec::new();
+
+        for entry in entries {
+            let timing = timings.entry(entry.operation.clone()).or_default();
+            timing.runs += 1;
+            timing.total_ms += entry.duration_ms;
+
+            for suggestion in ai_suggestions(&entry.message) {
+                if !suggestions.contains(&suggestion) {
+                    suggestions.push(suggestion);
+                }
+            }
+
+            let file = entry
+                .file
+                .strip_prefix(project_root)
+                .unwrap_or(&entry.file)
+                .to_path_buf();
+            let index = match files.iter().position(|summary| summary.file == file) {
+                Some(index) => index,
+                None => {
+                    files.push(FileSummary {
+                        file,
+                        lint_runs: 0,
+                        lint_status: LintStatus::NotLinted,
+                        test_runs: 0,
+                        tests_passed: 0,
+                    });
+                    files.len() - 1
+                }
+            };
+            let summary = &mut files[index];
+
+            match entry.operation.as_str() {
+                "lint" => {
+                    summary.lint_runs += 1;
+                    summary.lint_status = match (summary.lint_status, entry.success) {
+                        (_, false) => LintStatus::Remaining,
+                        (LintStatus::Remaining | LintStatus::Fixed, true) => LintStatus::Fixed,
+                        (_, true) => LintStatus::Clean,
+                    };
+                }
+                "test" => {
+                    summary.test_runs += 1;
+                    summary.tests_passed += usize::from(entry.success);
+                }
+                _ => {}
+            }
+        }
+        suggestions.truncate(MAX_SUGGESTIONS);
+
+        Self {
+            session_id,
+            files,
+            timings,
+            suggestions,
+        }
+    }
+
+    fn lint_count(&self, status: LintStatus) -> usize {
+        self.files
+            .iter()
+            .filter(|summary| summary.lint_status == status)
+            .count()
+    }
+
+    fn test_totals(&self) -> (usize, usize) {
+        self.files.iter().fold((0, 0), |(passed, runs), summary| {
+            (passed + summary.tests_passed, runs + summary.test_runs)
+        })
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.to_markdown(),
+            ReportFormat::Html => self.to_html(),
+        }
+    }
+
+    fn scope(&self) -> String {
+        match &self.session_id {
+            Some(session_id) => format!("Session {session_id}"),
+            None => "All recorded runs".to_string(),
+        }
+    }
+
+    fn summary_lines(&self) -> Vec<String> {
+        let (passed, runs) = self.test_totals();
+        let pass_rate = match (passed * 100).checked_div(runs) {
+            Some(percent) => format!("{percent}% ({passed}/{runs} runs)"),
+            None => "no test runs".to_string(),
+        };
+        vec![
+            format!("Files touched: {}", self.files.len()),
+            format!(
+                "Lint issues fixed in {} file(s), remaining in {} file(s)",
+                self.lint_count(LintStatus::Fixed),
+                self.lint_count(LintStatus::Remaining)
+            ),
+            format!("Test pass rate: {pass_rate}"),
+        ]
+    }
+
+    fn file_row(summary: &FileSummary) -> [String; 3] {
+        let lint = match summary.lint_status {
+            LintStatus::NotLinted => "—".to_string(),
+            LintStatus::Clean => format!("✅ clean ({} runs)", summary.lint_runs),
+            LintStatus::Fixed => format!("🔧 fixed ({} runs)", summary.lint_runs),
+            LintStatus::Remaining => format!("⛔ remaining ({} runs)", summary.lint_runs),
+        };
+        let tests = if summary.test_runs == 0 {
+            "—".to_string()
+        } else {
+            format!("{}/{} passed", summary.tests_passed, summary.test_runs)
+        };
+        [summary.file.display().to_string(), lint, tests]
+    }
+
+    fn timing_row(operation: &str, timing: &StepTiming) -> [String; 4] {
+        [
+            operation.to_string(),
+            timing.runs.to_string(),
+            format_duration(timing.total_ms),
+            format_duration(timing.total_ms / timing.runs.max(1) as u64),
+        ]
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "## 🛡️ Guardrails report\n\n_{}_\n", self.scope());
+        for line in self.summary_lines() {
+            let _ = writeln!(out, "- {line}");
+        }
+
+        if !self.files.is_empty() {
+            out.push_str("\n### Files\n\n| File | Lint | Tests |\n|---|---|---|\n");
+            for summary in &self.files {
+                let [file, lint, tests] = Self::file_row(summary);
+                let _ = writeln!(out, "| `{file}` | {lint} | {tests} |");
+            }
+        }
+
+        if !self.timings.is_empty() {
+            out.push_str(
+                "\n### Time spent\n\n| Step | Runs | Total | Average |\n|---|---|---|---|\n",
+            );
+            for (operation, timing) in &self.timings {
+                let [step, runs, total, average] = Self::timing_row(operation, timing);
+                let _ = writeln!(out, "| {step} | {runs} | {total} | {average} |");
+            }
+        }
+
+        if !self.suggestions.is_empty() {
+            out.push_str("\n### AI suggestions\n\n");
+            for suggestion in &self.suggestions {
+                let _ = writeln!(out, "- {suggestion}");
+            }
+        }
+
+        out
+    }
+
+    fn to_html(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "<h2>🛡️ Guardrails report</h2>\n<p><em>{}</em></p>\n<ul>",
+            escape_html(&self.scope())
+        );
+        for line in self.summary_lines() {
+            let _ = writeln!(out, "  <li>{}</li>", escape_html(&line));
+        }
+        out.push_str("</ul>\n");
+
+        if !self.files.is_empty() {
+            out.push_str(
+                "<h3>Files</h3>\n<table>\n  <tr><th>File</th><th>Lint</th><th>Tests</th></tr>\n",
+            );
+            for summary in &self.files {
+                let [file, lint, tests] = Self::file_row(summary).map(|cell| escape_html(&cell));
+                let _ = writeln!(
+                    out,
+                    "  <tr><td><code>{file}</code></td><td>{lint}</td><td>{tests}</td></tr>"
+                );
+            }
+            out.push_str("</table>\n");
+        }
+
+        if !self.timings.is_empty() {
+            out.push_str("<h3>Time spent</h3>\n<table>\n  <tr><th>Step</th><th>Runs</th><th>Total</th><th>Average</th></tr>\n");
+            for (operation, timing) in &self.timings {
+                let [step, runs, total, average] =
+                    Self::timing_row(operation, timing).map(|cell| escape_html(&cell));
+                let _ = writeln!(
+                    out,
+                    "  <tr><td>{step}</td><td>{runs}</td><td>{total}</td><td>{average}</td></tr>"
+                );
+            }
+            out.push_str("</table>\n");
+        }
+
+        if !self.suggestions.is_empty() {
+            out.push_str("<h3>AI suggestions</h3>\n<ul>\n");
+            for suggestion in &self.suggestions {
+                let _ = writeln!(out, "  <li>{}</li>", escape_html(suggestion));
+            }
+            out.push_str("</ul>\n");
+        }
+
+        out
+    }
+}
+
+/// Fix suggestions embedded in a run message (`💡 Fix: ...` lines and the text under
+/// `💡 **Analysis:**`)
+fn ai_suggestions(message: &str) -> Vec<String> {
+    let mut suggestions = Vec::new();
+    let mut lines = message.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        let suggestion = if let Some(fix) = line.strip_prefix("💡 Fix:") {
+            fix.trim().to_string()
+        } else if line == "💡 **Analysis:**" {
+            lines.next().unwrap_or_default().to_string()
+        } else {
+            continue;
+        };
+        if !suggestion.is_empty() {
+            suggestions.push(suggestion);
+        }
+    }
+    suggestions
+}
+
+fn format_duration(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else if ms < 60_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m {}s", ms / 60_000, ms % 60_000 / 1000)
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn entry(
+        operation: &str,
+        file: &str,
+        session: &str,
+        success: bool,
+        message: &str,
+    ) -> HistoryEntry {
+        HistoryEntry {
+            operation: operation.to_string(),
+            file: PathBuf::from(file),
+            session_id: Some(session.to_string()),
+            timestamp: Utc::now(),
+            success,
+            message: message.to_string(),
+            duration_ms: 1500,
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn test_report_from_history() {
+        let entries = vec![
+            entry("lint", "/repo/old.py", "s0", false, "E501"),
+            entry(
+                "lint",
+                "/repo/src/app.py",
+                "s1",
+                false,
+                "⛔ LINT ISSUES FOUND:\n\n💡 **Analysis:**\nRemove the unused import",
+            ),
+            entry("lint", "/repo/src/app.py", "s1", true, "ok"),
+            entry(
+                "test",
+                "/repo/src/app.py",
+                "s1",
+                false,
+                "  • test_add: AssertionError - boom\n    💡 Fix: Return a + b",
+            ),
+            entry("test", "/repo/src/app.py", "s1", true, "ok"),
+            entry("lint", "/repo/src/util.py", "s1", false, "F401"),
+        ];
+
+        let report = RunReport::from_history(&entries, None, Path::new("/repo"));
+        assert_eq!(report.session_id.as_deref(), Some("s1"));
+        assert_eq!(report.files.len(), 2);
+        assert_eq!(report.files[0].file, PathBuf::from("src/app.py"));
+        assert_eq!(report.files[0].lint_status, LintStatus::Fixed);
+        assert_eq!(report.files[0].tests_passed, 1);
+        assert_eq!(report.files[1].lint_status, LintStatus::Remaining);
+        assert_eq!(report.timings["lint"].runs, 3);
+        assert_eq!(report.timings["test"].total_ms, 3000);
+        assert_eq!(
+            report.suggestions,
+            vec!["Remove the unused import", "Return a + b"]
+        );
+
+        let markdown = report.render(ReportFormat::Markdown);
+        assert!(markdown.contains("| `src/app.py` | 🔧 fixed (2 runs) | 1/2 passed |"));
+        assert!(markdown.contains("Test pass rate: 50% (1/2 runs)"));
+        assert!(markdown.contains("| lint | 3 | 4.5s | 1.5s |"));
+
+        let html = report.render(ReportFormat::Html);
+        assert!(html.contains("<td><code>src/util.py</code></td><td>⛔ remaining (1 runs)</td>"));
+        assert!(html.contains("<li>Return a + b</li>"));
+
+        let other = RunReport::from_history(&entries, Some("s0"), Path::new("/repo"));
+        assert_eq!(other.files.len(), 1);
+    }
+
+    #[test]
+    fn test_escape_html_and_format() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+        assert_eq!("HTML".parse::<ReportFormat>(), Ok(ReportFormat::Html));
+        assert!("pdf".parse::<ReportFormat>().is_err());
+        assert_eq!(format_duration(250), "250ms");
+        assert_eq!(format_duration(90_500), "1m 30s");
+    }
+}
@@ -0,0 +1,137 @@
+use crate::state::StateDir;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of verdicts kept per workspace
+const MAX_ENTRIES: usize = 200;
+
+/// Verdict of a passing lint/test run, valid while its key still matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedVerdict {
+    /// "lint" or "test"
+    pub operation: String,
+    pub file: PathBuf,
+    /// Hash of the file content and everything else the verdict depends on
+    pub key: String,
+    /// Message shown to Claude for the run
+    pub message: String,
+    /// Whether the run was delivered as non-blocking advice
+    #[serde(default)]
+    pub advice: bool,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Per-workspace verdicts of passing runs, so re-saving a file unchanged doesn't rerun
+/// its tools; stored in the state directory's `result-cache.json`
+pub struct ResultCache {
+    path: PathBuf,
+    entries: Vec<CachedVerdict>,
+}
+
+impl ResultCache {
+    /// Load the cache for a workspace (empty when nothing was cached yet)
+    pub fn load(workspace_dir: &Path) -> Result<Self> {
+        let path = StateDir::for_workspace(workspace_dir)?.file("result-cache.json");
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Ok(Self { path, entries })
+    }
+
+    /// Cached verdict for an operation on a file, if it was recorded under `key`
+    pub fn lookup(&self, operation: &str, file: &Path, key: &str) -> Option<&CachedVerdict> {
+        self.entries
+            .iter()
+            .find(|entry| entry.operation == operation && entry.file == file && entry.key == key)
+    }
+
+    /// Remember a passing run, replacing the file's previous verdict
+    pub fn store(
+        &mut self,
+        operation: &str,
+        file: &Path,
+        key: String,
+        message: &str,
+        advice: bool,
+    ) -> Result<()> {
+        self.entries
+            .retain(|entry| !(entry.operation == operation && entry.file == file));
+        self.entries.push(CachedVerdict {
+            operation: operation.to_string(),
+            file: file.to_path_buf(),
+            key,
+            message: message.to_string(),
+            advice,
+            timestamp: Utc::now(),
+        });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+        self.save()
+    }
+
+    /// Drop the file's verdict after a run that didn't pass
+    pub fn forget(&mut self, operation: &str, file: &Path) -> Result<()> {
+        let before = self.entries.len();
+        self.entries
+            .retain(|entry| !(entry.operation == operation && entry.file == file));
+        if self.entries.len() == before {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        let content = serde_json::to_string(&self.entries)?;
+        fs::write(&self.path, content).context("Failed to write result cache")
+    }
+}
+
+/// Hash identifying a run's inputs; parts are length-prefixed so moving bytes between
+/// them changes the key
+pub fn cache_key<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_result_cache_roundtrip() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let file = temp_dir.path().join("app.py");
+        let key = cache_key([b"x = 1\n".as_slice(), b"config".as_slice()]);
+        assert_ne!(
+            key,
+            cache_key([b"x = 1\nc".as_slice(), b"onfig".as_slice()])
+        );
+
+        let mut cache = ResultCache::load(temp_dir.path())?;
+        cache.store("lint", &file, key.clone(), "👉 Lints pass.", false)?;
+
+        let cache = ResultCache::load(temp_dir.path())?;
+        let verdict = cache.lookup("lint", &file, &key).unwrap();
+        assert_eq!(verdict.message, "👉 Lints pass.");
+        assert!(cache.lookup("test", &file, &key).is_none());
+        assert!(cache.lookup("lint", &file, "other").is_none());
+
+        let mut cache = cache;
+        cache.forget("lint", &file)?;
+        assert!(ResultCache::load(temp_dir.path())?
+            .lookup("lint", &file, &key)
+            .is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use crate::state::StateDir;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Maximum number of sessions whose edits are kept per workspace
+const MAX_SESSIONS: usize = 20;
+
+/// Files edited in one Claude Code session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionFiles {
+    pub session_id: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// Files edited per session, stored in the project's state directory so the Stop hook
+/// can check the session's source changes came with test changes
+pub struct SessionEdits {
+    path: PathBuf,
+    sessions: Vec<SessionFiles>,
+}
+
+impl SessionEdits {
+    /// Load the recorded edits for a workspace (empty when none were recorded yet)
+    pub fn load(workspace_dir: &Path) -> Result<Self> {
+        let path = StateDir::for_workspace(workspace_dir)?.file("session_edits.json");
+        let sessions = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Ok(Self { path, sessions })
+    }
+
+    /// Remember that `file` was edited in a session
+    pub fn record(&mut self, session_id: &str, file: &Path) -> Result<()> {
+        match self
+            .sessions
+            .iter_mut()
+            .find(|session| session.session_id == session_id)
+        {
+            Some(session) if session.files.iter().any(|known| known == file) => return Ok(()),
+            Some(session) => session.files.push(file.to_path_buf()),
+            None => self.sessions.push(SessionFiles {
+                session_id: session_id.to_string(),
+                files: vec![file.to_path_buf()],
+            }),
+        }
+        if self.sessions.len() > MAX_SESSIONS {
+            self.sessions.drain(..self.sessions.len() - MAX_SESSIONS);
+        }
+
+        let content = serde_json::to_string(&self.sessions)?;
+        fs::write(&self.path, content).context("Failed to write session edits file")
+    }
+
+    /// Files edited in a session, in the order they were first edited
+    pub fn files(&self, session_id: &str) -> &[PathBuf] {
+        self.sessions
+            .iter()
+            .find(|session| session.session_id == session_id)
+            .map_or(&[], |session| session.files.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_session_edits_roundtrip() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut edits = SessionEdits::load(temp_dir.path())?;
+        edits.record("a", Path::new("/p/src/orders.py"))?;
+        edits.record("a", Path::new("/p/src/orders.py"))?;
+        edits.record("a", Path::new("/p/tests/test_orders.py"))?;
+        for session in 0..MAX_SESSIONS {
+            edits.record(&session.to_string(), Path::new("/p/src/cart.py"))?;
+        }
+
+        let reloaded = SessionEdits::load(temp_dir.path())?;
+        assert!(reloaded.files("a").is_empty());
+        assert_eq!(reloaded.files("0"), [PathBuf::from("/p/src/cart.py")]);
+        assert_eq!(edits.sessions.len(), MAX_SESSIONS);
+
+        let mut edits = SessionEdits::load(temp_dir.path())?;
+        edits.record("b", Path::new("/p/src/orders.py"))?;
+        edits.record("b", Path::new("/p/tests/test_orders.py"))?;
+        assert_eq!(
+            SessionEdits::load(temp_dir.path())?.files("b"),
+            [
+                PathBuf::from("/p/src/orders.py"),
+                PathBuf::from("/p/tests/test_orders.py")
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+use crate::prompt_context::declared_dependencies;
+use crate::pytest::{PytestResults, TestCase};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// pytest option both syrupy and pytest-snapshot use to rewrite stored snapshots
+pub const UPDATE_FLAG: &str = "--snapshot-update";
+
+/// Whether the test hook rewrites snapshots when the only failures are mismatches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AutoUpdateSnapshots {
+    /// Block and ask Claude to confirm the output change with the user before updating
+    #[default]
+    Prompt,
+    /// Block and leave updating to the user
+    Never,
+    /// Rerun the tests with `--snapshot-update` and report what changed
+    Always,
+}
+
+/// Snapshot (golden file) testing plugin used by a project's tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotPlugin {
+    Syrupy,
+    PytestSnapshot,
+}
+
+impl SnapshotPlugin {
+    pub fn name(self) -> &'static str {
+        match self {
+            SnapshotPlugin::Syrupy => "syrupy",
+            SnapshotPlugin::PytestSnapshot => "pytest-snapshot",
+        }
+    }
+
+    /// Directory the plugin stores snapshots in, next to the test file
+    pub fn snapshot_dir(self) -> &'static str {
+        match self {
+            SnapshotPlugin::Syrupy => "__snapshots__",
+            SnapshotPlugin::PytestSnapshot => "snapshots",
+        }
+    }
+
+    /// The plugin the project declares, or the one the test file's usage points to
+    pub fn detect(project_root: &Path, test_source: &str) -> Option<SnapshotPlugin> {
+        let declared = declared_dependencies(project_root);
+        let declares = |name: &str| {
+            declared
+                .iter()
+                .any(|dependency| dependency.eq_ignore_ascii_case(name))
+        };
+        if declares("syrupy") {
+            Some(SnapshotPlugin::Syrupy)
+        } else if declares("pytest-snapshot") || test_source.contains("snapshot.assert_match") {
+            Some(SnapshotPlugin::PytestSnapshot)
+        } else if test_source.contains("syrupy") || test_source.contains("== snapshot") {
+            Some(SnapshotPlugin::Syrupy)
+        } else {
+            None
+        }
+    }
+}
+
+/// Failed tests in pytest output, when every one of them is a snapshot mismatch or a
+/// missing snapshot (mixed failures need a regular fix first)
+pub fn snapshot_failures(output: &str) -> Option<Vec<TestCase>> {
+    let failed: Vec<TestCase> = PytestResults::parse(output).failures().cloned().collect();
+    (!failed.is_empty() && failed.iter().all(is_snapshot_failure)).then_some(failed)
+}
+
+fn is_snapshot_failure(case: &TestCase) -> bool {
+    let text = format!("{}\n{}", case.message, case.details).to_lowercase();
+    text.contains("snapshot")
+        && [
+            "[- snapshot]",
+            "does not match",
+            "does not exist",
+            "doesn't exist",
+        ]
+        .iter()
+        .any(|marker| text.contains(marker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detect_snapshot_plugin() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        assert_eq!(
+            SnapshotPlugin::detect(root, "def test_a():\n    pass\n"),
+            None
+        );
+        assert_eq!(
+            SnapshotPlugin::detect(
+                root,
+                "def test_a(snapshot):\n    assert render() == snapshot\n"
+            ),
+            Some(SnapshotPlugin::Syrupy)
+        );
+        assert_eq!(
+            SnapshotPlugin::detect(
+                root,
+                "def test_a(snapshot):\n    snapshot.assert_match(render(), 'a.txt')\n"
+            ),
+            Some(SnapshotPlugin::PytestSnapshot)
+        );
+
+        std::fs::write(root.join("requirements-dev.txt"), "pytest\nsyrupy>=4\n")?;
+        assert_eq!(
+            SnapshotPlugin::detect(root, "def test_a():\n    pass\n"),
+            Some(SnapshotPlugin::Syrupy)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_failures() {
+        let mismatch = "FAILED tests/test_render.py::test_page - AssertionError: assert [+ received] == [- snapshot]\nFAILED tests/test_render.py::test_new - AssertionError: Snapshot 'test_new' does not exist!\n";
+        let failures = snapshot_failures(mismatch).unwrap();
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].node_id, "tests/test_render.py::test_page");
+
+        let mixed = format!("{mismatch}FAILED tests/test_render.py::test_sum - assert 3 == 4\n");
+        assert!(snapshot_failures(&mixed).is_none());
+        assert!(snapshot_failures("1 passed in 0.01s\n").is_none());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::locking::ProcessLock;
+use crate::{GuardrailsError, GuardrailsResult};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory created in the project root to hold guardrails state
+pub const STATE_DIR_NAME: &str = ".claude-guardrails";
+
+/// Overrides where state directories are created (one subdirectory per project)
+pub const STATE_DIR_ENV: &str = "GUARDRAILS_STATE_DIR";
+
+/// Prefix of the files earlier versions kept directly in /tmp
+const LEGACY_PREFIX: &str = "claude-python-guardrails-";
+
+/// Per-project directory for locks, history, suppressions and caches
+///
+/// Lives in `<project>/.claude-guardrails/` (git-ignored via its own `.gitignore`),
+/// or under `$GUARDRAILS_STATE_DIR` / the XDG state directory when the project isn't
+/// writable. Files left in /tmp by earlier versions are moved in on first use.
+#[derive(Debug, Clone)]
+pub struct StateDir {
+    path: PathBuf,
+}
+
+impl StateDir {
+    /// Open (creating and migrating as needed) the state directory for a workspace
+    pub fn for_workspace(workspace_dir: &Path) -> GuardrailsResult<Self> {
+        let workspace_hash = ProcessLock::hash_workspace(workspace_dir)?;
+        let state_dir = Self {
+            path: Self::locate(workspace_dir, &workspace_hash)?,
+        };
+        state_dir.migrate_legacy_files(&workspace_hash);
+        Ok(state_dir)
+    }
+
+    fn locate(workspace_dir: &Path, workspace_hash: &str) -> GuardrailsResult<PathBuf> {
+        let create = |path: PathBuf| {
+            fs::create_dir_all(&path)
+                .map(|()| path.clone())
+                .map_err(|e| GuardrailsError::io(&path, e))
+        };
+        if let Ok(base) = std::env::var(STATE_DIR_ENV) {
+            return create(PathBuf::from(base).join(workspace_hash));
+        }
+
+        let local = workspace_dir.join(STATE_DIR_NAME);
+        if Self::create_local(&local).is_ok() {
+            return Ok(local);
+        }
+
+        let state_home = xdg_state_home().ok_or_else(|| {
+            GuardrailsError::io(
+                &local,
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "no writable state directory (set GUARDRAILS_STATE_DIR)",
+                ),
+            )
+        })?;
+        create(
+            state_home
+                .join("claude-python-guardrails")
+                .join(workspace_hash),
+        )
+    }
+
+    fn create_local(path: &Path) -> std::io::Result<()> {
+        fs::create_dir_all(path)?;
+        let gitignore = path.join(".gitignore");
+        if !gitignore.exists() {
+            fs::write(gitignore, "# Created by claude-python-guardrails\n*\n")?;
+        }
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path of a state file, e.g. `history.json`
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.path.join(name)
+    }
+
+    /// Move this workspace's legacy `/tmp/claude-python-guardrails-*-<hash>*` files in,
+    /// dropping the prefix and hash from their names
+    fn migrate_legacy_files(&self, workspace_hash: &str) {
+        for legacy in legacy_files(workspace_hash) {
+            let Some(name) = legacy.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let new_name = name.trim_start_matches(LEGACY_PREFIX).replacen(
+                &format!("-{workspace_hash}"),
+                "",
+                1,
+            );
+            let target = self.file(&new_name);
+
+            let moved = if target.exists() {
+                fs::remove_file(&legacy)
+            } else {
+                fs::rename(&legacy, &target).or_else(|_| {
+                    fs::copy(&legacy, &target)?;
+                    fs::remove_file(&legacy)
+                })
+            };
+            match moved {
+                Ok(()) => log::debug!("Migrated {} to {}", legacy.display(), target.display()),
+                Err(e) => log::debug!("Could not migrate {}: {}", legacy.display(), e),
+            }
+        }
+    }
+
+    /// Remove all state for the workspace; returns how many files were removed
+    pub fn clean(&self) -> GuardrailsResult<usize> {
+        let mut removed = 0;
+        let entries = fs::read_dir(&self.path).map_err(|e| GuardrailsError::io(&self.path, e))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| GuardrailsError::io(&self.path, e))?
+                .path();
+            if path.file_name().is_some_and(|name| name == ".gitignore") {
+                continue;
+            }
+            let removal = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            removal.map_err(|e| GuardrailsError::io(&path, e))?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+/// Files an earlier version left in /tmp for the workspace
+fn legacy_files(workspace_hash: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir("/tmp") else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(LEGACY_PREFIX) && name.contains(&format!("-{workspace_hash}"))
+                })
+        })
+        .collect()
+}
+
+/// `$XDG_STATE_HOME`, defaulting to `~/.local/state`
+fn xdg_state_home() -> Option<PathBuf> {
+    std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_state_dir_in_project() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let state = StateDir::for_workspace(temp_dir.path())?;
+
+        assert_eq!(state.path(), temp_dir.path().join(STATE_DIR_NAME));
+        assert!(state.file(".gitignore").exists());
+        assert_eq!(
+            state.file("history.json"),
+            state.path().join("history.json")
+        );
+
+        fs::write(state.file("history.json"), "[]")?;
+        assert_eq!(state.clean()?, 1);
+        assert!(!state.file("history.json").exists());
+        assert!(state.file(".gitignore").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrates_legacy_tmp_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let hash = ProcessLock::hash_workspace(temp_dir.path())?;
+        let legacy_history = PathBuf::from(format!("/tmp/{LEGACY_PREFIX}history-{hash}.json"));
+        let legacy_lock = PathBuf::from(format!("/tmp/{LEGACY_PREFIX}lint-{hash}-abcd.lock"));
+        fs::write(&legacy_history, "[]")?;
+        fs::write(&legacy_lock, "\n0")?;
+
+        let state = StateDir::for_workspace(temp_dir.path())?;
+        assert!(!legacy_history.exists());
+        assert!(!legacy_lock.exists());
+        assert_eq!(fs::read_to_string(state.file("history.json"))?, "[]");
+        assert!(state.file("lint-abcd.lock").exists());
+
+        Ok(())
+    }
+}
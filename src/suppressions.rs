@@ -0,0 +1,130 @@
+use crate::lint::{retain_findings, FilteredLintOutput, LintFinding};
+use crate::state::StateDir;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A lint finding the AI classified as a false positive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suppression {
+    /// See [`LintFinding::fingerprint`]
+    pub fingerprint: String,
+    pub rule: Option<String>,
+    pub file: Option<String>,
+    pub message: String,
+    pub created: DateTime<Utc>,
+}
+
+/// Per-workspace memory of false-positive lint findings, stored in the project's state directory
+pub struct Suppressions {
+    path: PathBuf,
+    entries: Vec<Suppression>,
+}
+
+impl Suppressions {
+    /// Load the suppressions for a workspace (empty when none were recorded yet)
+    pub fn load(workspace_dir: &Path) -> Result<Self> {
+        let path = StateDir::for_workspace(workspace_dir)?.file("suppressions.json");
+        Ok(Self::load_from(path))
+    }
+
+    fn load_from(path: PathBuf) -> Self {
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { path, entries }
+    }
+
+    pub fn entries(&self) -> &[Suppression] {
+        &self.entries
+    }
+
+    pub fn is_suppressed(&self, finding: &LintFinding) -> bool {
+        let fingerprint = finding.fingerprint();
+        self.entries
+            .iter()
+            .any(|entry| entry.fingerprint == fingerprint)
+    }
+
+    /// Drop previously suppressed findings from linter output
+    pub fn filter(&self, output: &str) -> FilteredLintOutput {
+        retain_findings(output, |finding| !self.is_suppressed(finding))
+    }
+
+    /// Remember findings as false positives; returns how many were new
+    pub fn add(&mut self, findings: &[LintFinding]) -> Result<usize> {
+        let mut added = 0;
+        for finding in findings {
+            if self.is_suppressed(finding) {
+                continue;
+            }
+            self.entries.push(Suppression {
+                fingerprint: finding.fingerprint(),
+                rule: finding.rule.clone(),
+                file: finding.file.clone(),
+                message: finding.message.clone(),
+                created: Utc::now(),
+            });
+            added += 1;
+        }
+
+        if added > 0 {
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    /// Forget all suppressions; returns how many were removed
+    pub fn clear(&mut self) -> Result<usize> {
+        let removed = self.entries.len();
+        self.entries.clear();
+        if self.path.exists() {
+            fs::remove_file(&self.path).context("Failed to remove suppressions file")?;
+        }
+        Ok(removed)
+    }
+
+    fn save(&self) -> Result<()> {
+        let content = serde_json::to_string(&self.entries)?;
+        fs::write(&self.path, content).context("Failed to write suppressions file")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::parse_findings;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_add_filter_and_clear() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("suppressions.json");
+        let output = "app.py:1:8: F401 `os` imported but unused\n\
+                      app.py:3:89: E501 Line too long (120 > 88)";
+
+        let mut suppressions = Suppressions::load_from(path.clone());
+        let findings = parse_findings(output);
+        assert_eq!(suppressions.add(&findings[1..])?, 1);
+        assert_eq!(suppressions.add(&findings[1..])?, 0);
+
+        // Same finding on another line is still suppressed after a reload
+        let mut suppressions = Suppressions::load_from(path);
+        let filtered = suppressions.filter(&format!(
+            "{}\napp.py:7:89: E501 Line too long (95 > 88)",
+            output
+        ));
+        assert_eq!(filtered.ignored, 2);
+        assert_eq!(filtered.remaining, 1);
+        assert!(filtered.output.contains("F401"));
+
+        assert_eq!(suppressions.clear()?, 1);
+        assert!(suppressions.entries().is_empty());
+        assert_eq!(suppressions.filter(output).ignored, 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Declared source → test file mappings (`automation.test.map`), consulted before the
+/// heuristic test file search for projects with unconventional layouts
+///
+/// Keys are globs matched against the source path relative to the project root; values
+/// are test paths relative to the root where `{stem}` (`models`), `{name}` (`models.py`)
+/// and `{dir}` (the source's directory, e.g. `src/app/core`) are substituted. The first
+/// matching entry, in file order, wins.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TestFileMap {
+    entries: Vec<(String, String)>,
+}
+
+impl TestFileMap {
+    pub fn new(entries: Vec<(String, String)>) -> Self {
+        Self { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Test file the first matching entry maps `source_file` to, whether or not it exists;
+    /// `None` when no entry matches (invalid globs are logged and skipped)
+    pub fn resolve(&self, source_file: &Path, project_root: &Path) -> Option<PathBuf> {
+        let relative = source_file.strip_prefix(project_root).ok()?;
+        let (_, template) =
+            self.entries
+                .iter()
+                .find(|(pattern, _)| match globset::Glob::new(pattern) {
+                    Ok(glob) => glob.compile_matcher().is_match(relative),
+                    Err(e) => {
+                        log::warn!("Invalid automation.test.map pattern {}: {}", pattern, e);
+                        false
+                    }
+                })?;
+
+        let stem = relative.file_stem()?.to_string_lossy();
+        let name = relative.file_name()?.to_string_lossy();
+        let dir = relative
+            .parent()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mapped = template
+            .replace("{stem}", &stem)
+            .replace("{name}", &name)
+            .replace("{dir}", &dir);
+        Some(project_root.join(mapped.trim_start_matches('/')))
+    }
+}
+
+// A YAML mapping, kept in file order so earlier entries take precedence
+impl Serialize for TestFileMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (pattern, template) in &self.entries {
+            map.serialize_entry(pattern, template)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for TestFileMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = TestFileMap;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a mapping of source globs to test file paths")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = access.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(TestFileMap { entries })
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_in_declared_order() {
+        let map: TestFileMap = serde_yaml::from_str(
+            r#"
+"src/app/core/*.py": "tests/core/test_{stem}.py"
+"src/**/*.py": "tests/{dir}/{stem}_test.py"
+"#,
+        )
+        .unwrap();
+        let root = Path::new("/repo");
+
+        assert_eq!(
+            map.resolve(Path::new("/repo/src/app/core/models.py"), root),
+            Some(PathBuf::from("/repo/tests/core/test_models.py"))
+        );
+        assert_eq!(
+            map.resolve(Path::new("/repo/src/app/api.py"), root),
+            Some(PathBuf::from("/repo/tests/src/app/api_test.py"))
+        );
+        assert_eq!(map.resolve(Path::new("/repo/scripts/run.py"), root), None);
+        assert_eq!(map.resolve(Path::new("/elsewhere/src/x.py"), root), None);
+
+        let yaml = serde_yaml::to_string(&map).unwrap();
+        assert_eq!(serde_yaml::from_str::<TestFileMap>(&yaml).unwrap(), map);
+        assert!(TestFileMap::default().is_empty());
+    }
+}
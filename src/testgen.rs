@@ -0,0 +1,209 @@
+use crate::cerebras::full_confidence;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Test framework generated tests are written for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TestFramework {
+    #[default]
+    Pytest,
+    Unittest,
+}
+
+impl std::str::FromStr for TestFramework {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pytest" => Ok(TestFramework::Pytest),
+            "unittest" => Ok(TestFramework::Unittest),
+            other => Err(format!(
+                "unknown test framework '{other}' (expected pytest or unittest)"
+            )),
+        }
+    }
+}
+
+impl TestFramework {
+    pub fn name(self) -> &'static str {
+        match self {
+            TestFramework::Pytest => "pytest",
+            TestFramework::Unittest => "unittest",
+        }
+    }
+}
+
+/// A test case proposed for a module
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposedTest {
+    /// Test function name
+    pub name: String,
+    /// What the test verifies
+    pub description: String,
+}
+
+/// Tests generated for a module by `generate-tests`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeneratedTests {
+    pub test_cases: Vec<ProposedTest>,
+    /// Complete content of the test file
+    pub test_code: String,
+    /// Model's confidence in these tests, 0.0-1.0 (1.0 for skeletons made without AI)
+    #[serde(default = "full_confidence")]
+    pub confidence: f32,
+}
+
+impl GeneratedTests {
+    /// One placeholder test per public top-level function, for when no AI is available
+    pub fn skeleton(source: &str, module: &str, framework: TestFramework) -> Self {
+        let functions: Vec<&str> = source
+            .lines()
+            .filter_map(|line| {
+                line.strip_prefix("def ")
+                    .or_else(|| line.strip_prefix("async def "))
+            })
+            .filter_map(|rest| rest.split('(').next())
+            .filter(|name| !name.is_empty() && !name.starts_with('_'))
+            .collect();
+
+        let test_cases: Vec<ProposedTest> = functions
+            .iter()
+            .map(|function| ProposedTest {
+                name: format!("test_{function}"),
+                description: format!("Placeholder: verify the behavior of `{function}`"),
+            })
+            .collect();
+
+        let mut test_code = match framework {
+            TestFramework::Pytest => format!("import pytest\n\nimport {module}\n"),
+            TestFramework::Unittest => {
+                format!(
+                    "import unittest\n\nimport {module}\n\n\nclass Test{}(unittest.TestCase):",
+                    class_name(module)
+                )
+            }
+        };
+        for case in &test_cases {
+            test_code.push_str(&match framework {
+                TestFramework::Pytest => format!(
+                    "\n\ndef {}():\n    pytest.skip(\"TODO: test {module}.{}\")\n",
+                    case.name,
+                    &case.name["test_".len()..]
+                ),
+                TestFramework::Unittest => format!(
+                    "\n    def {}(self):\n        self.skipTest(\"TODO: test {module}.{}\")\n",
+                    case.name,
+                    &case.name["test_".len()..]
+                ),
+            });
+        }
+        if test_cases.is_empty() && framework == TestFramework::Unittest {
+            test_code.push_str("\n    pass\n");
+        }
+        if framework == TestFramework::Unittest {
+            test_code.push_str("\n\nif __name__ == \"__main__\":\n    unittest.main()\n");
+        }
+
+        Self {
+            test_cases,
+            test_code,
+            confidence: 1.0,
+        }
+    }
+}
+
+/// Dotted import path of a module below the project root (`src/` layouts included)
+pub fn module_path(source_file: &Path, project_root: &Path) -> String {
+    let relative = source_file
+        .strip_prefix(project_root)
+        .unwrap_or(source_file)
+        .with_extension("");
+    let mut parts: Vec<String> = relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    if parts.len() > 1 && parts[0] == "src" {
+        parts.remove(0);
+    }
+    if parts.len() > 1 && parts.last().is_some_and(|last| last == "__init__") {
+        parts.pop();
+    }
+    parts.join(".")
+}
+
+/// Where the tests for `source_file` go by default: `tests/test_<module>.py` in the
+/// project root
+pub fn default_test_path(source_file: &Path, project_root: &Path) -> PathBuf {
+    let stem = source_file
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("module");
+    project_root.join("tests").join(format!("test_{stem}.py"))
+}
+
+fn class_name(module: &str) -> String {
+    module
+        .rsplit('.')
+        .next()
+        .unwrap_or(module)
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_path_and_default_test_path() {
+        let root = Path::new("/project");
+        assert_eq!(
+            module_path(Path::new("/project/src/shop/orders.py"), root),
+            "shop.orders"
+        );
+        assert_eq!(
+            module_path(Path::new("/project/shop/__init__.py"), root),
+            "shop"
+        );
+        assert_eq!(module_path(Path::new("/project/app.py"), root), "app");
+        assert_eq!(
+            default_test_path(Path::new("/project/src/shop/orders.py"), root),
+            Path::new("/project/tests/test_orders.py")
+        );
+    }
+
+    #[test]
+    fn test_skeleton() {
+        let source = "def total(order):\n    pass\n\nasync def submit():\n    pass\n\ndef _helper():\n    pass\n";
+        let pytest = GeneratedTests::skeleton(source, "shop.orders", TestFramework::Pytest);
+        let names: Vec<&str> = pytest
+            .test_cases
+            .iter()
+            .map(|case| case.name.as_str())
+            .collect();
+        assert_eq!(names, ["test_total", "test_submit"]);
+        assert!(pytest
+            .test_code
+            .starts_with("import pytest\n\nimport shop.orders\n"));
+        assert!(pytest
+            .test_code
+            .contains("def test_total():\n    pytest.skip(\"TODO: test shop.orders.total\")"));
+
+        let unittest =
+            GeneratedTests::skeleton(source, "shop.order_lines", TestFramework::Unittest);
+        assert!(unittest
+            .test_code
+            .contains("class TestOrderLines(unittest.TestCase):\n    def test_total(self):"));
+        assert!(unittest.test_code.ends_with("unittest.main()\n"));
+    }
+}
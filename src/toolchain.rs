@@ -0,0 +1,378 @@
+use std::path::{Path, PathBuf};
+use which::which;
+
+/// Languages with toolchain-driven automation (Python goes through project discovery)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Rust,
+    /// JavaScript and TypeScript
+    JavaScript,
+}
+
+/// A command a toolchain runs for one automation step
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl ToolCommand {
+    pub fn new(program: &str, args: &[&str]) -> Self {
+        Self {
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    /// Human-readable command line for messages
+    pub fn display(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Lint/format/test tooling for one language
+///
+/// All commands run from the root returned by [`LanguageToolchain::project_root`].
+pub trait LanguageToolchain: Send + Sync {
+    fn language(&self) -> Language;
+
+    /// Whether this toolchain handles the edited file
+    fn handles(&self, file: &Path) -> bool;
+
+    /// Root of the project (or crate/package) the edited file belongs to
+    fn project_root(&self, file: &Path) -> Option<PathBuf>;
+
+    /// Formatter and auto-fix commands run before linting; their failures are ignored
+    fn format_commands(&self, root: &Path, file: &Path) -> Vec<ToolCommand>;
+
+    /// Lint command whose failure blocks
+    fn lint_command(&self, root: &Path, file: &Path) -> Option<ToolCommand>;
+
+    /// Test command covering the edited file
+    fn test_command(&self, root: &Path, file: &Path) -> Option<ToolCommand>;
+}
+
+/// All built-in toolchains, in dispatch order
+pub fn toolchains() -> Vec<Box<dyn LanguageToolchain>> {
+    vec![Box::new(RustToolchain), Box::new(JavaScriptToolchain)]
+}
+
+/// First toolchain that handles the edited file
+pub fn toolchain_for(file: &Path) -> Option<Box<dyn LanguageToolchain>> {
+    toolchains()
+        .into_iter()
+        .find(|toolchain| toolchain.handles(file))
+}
+
+fn has_extension(file: &Path, extension: &str) -> bool {
+    file.extension().and_then(|ext| ext.to_str()) == Some(extension)
+}
+
+/// Rust toolchain: cargo fmt/clippy/test targeted at the crate containing the edited file
+pub struct RustToolchain;
+
+impl RustToolchain {
+    /// `-p <name>` selecting the crate at `root`, when its manifest declares a package
+    fn package_args(root: &Path) -> Vec<String> {
+        std::fs::read_to_string(root.join("Cargo.toml"))
+            .ok()
+            .and_then(|manifest| package_name(&manifest))
+            .map(|name| vec!["-p".to_string(), name])
+            .unwrap_or_default()
+    }
+
+    fn cargo(root: &Path, subcommand: &str, extra: &[&str]) -> Option<ToolCommand> {
+        which("cargo").ok()?;
+        let mut args = vec![subcommand.to_string()];
+        args.extend(Self::package_args(root));
+        args.extend(extra.iter().map(|arg| arg.to_string()));
+        Some(ToolCommand {
+            program: "cargo".to_string(),
+            args,
+        })
+    }
+}
+
+impl LanguageToolchain for RustToolchain {
+    fn language(&self) -> Language {
+        Language::Rust
+    }
+
+    fn handles(&self, file: &Path) -> bool {
+        has_extension(file, "rs")
+    }
+
+    fn project_root(&self, file: &Path) -> Option<PathBuf> {
+        file.ancestors()
+            .skip(1)
+            .find(|dir| dir.join("Cargo.toml").is_file())
+            .map(Path::to_path_buf)
+    }
+
+    fn format_commands(&self, root: &Path, _file: &Path) -> Vec<ToolCommand> {
+        Self::cargo(root, "fmt", &[]).into_iter().collect()
+    }
+
+    fn lint_command(&self, root: &Path, _file: &Path) -> Option<ToolCommand> {
+        Self::cargo(root, "clippy", &["--all-targets", "--", "-D", "warnings"])
+    }
+
+    fn test_command(&self, root: &Path, _file: &Path) -> Option<ToolCommand> {
+        Self::cargo(root, "test", &[])
+    }
+}
+
+/// JS/TS toolchain: eslint or biome, prettier, and vitest or jest, detected from the
+/// dependencies in package.json and run through npx
+pub struct JavaScriptToolchain;
+
+/// JS/TS tools declared in a package.json
+#[derive(Debug, Default, PartialEq)]
+struct JsTools {
+    biome: bool,
+    eslint: bool,
+    prettier: bool,
+    vitest: bool,
+    jest: bool,
+}
+
+impl JsTools {
+    fn from_package_json(content: &str) -> Self {
+        let Ok(package) = serde_json::from_str::<serde_json::Value>(content) else {
+            return Self::default();
+        };
+        let has = |name: &str| {
+            ["dependencies", "devDependencies"]
+                .iter()
+                .any(|section| package[section].get(name).is_some())
+        };
+
+        Self {
+            biome: has("@biomejs/biome"),
+            eslint: has("eslint"),
+            prettier: has("prettier"),
+            vitest: has("vitest"),
+            jest: has("jest"),
+        }
+    }
+}
+
+impl JavaScriptToolchain {
+    const EXTENSIONS: &'static [&'static str] =
+        &["js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts"];
+
+    /// Tools from the package.json at `root`; empty when npx isn't installed
+    fn tools(root: &Path) -> JsTools {
+        if which("npx").is_err() {
+            return JsTools::default();
+        }
+        std::fs::read_to_string(root.join("package.json"))
+            .map(|content| JsTools::from_package_json(&content))
+            .unwrap_or_default()
+    }
+
+    fn npx(args: &[&str], file: &Path) -> ToolCommand {
+        let mut command = ToolCommand::new("npx", args);
+        command.args.push(file.to_string_lossy().to_string());
+        command
+    }
+}
+
+impl LanguageToolchain for JavaScriptToolchain {
+    fn language(&self) -> Language {
+        Language::JavaScript
+    }
+
+    fn handles(&self, file: &Path) -> bool {
+        Self::EXTENSIONS
+            .iter()
+            .any(|extension| has_extension(file, extension))
+    }
+
+    fn project_root(&self, file: &Path) -> Option<PathBuf> {
+        file.ancestors()
+            .skip(1)
+            .find(|dir| dir.join("package.json").is_file())
+            .map(Path::to_path_buf)
+    }
+
+    fn format_commands(&self, root: &Path, file: &Path) -> Vec<ToolCommand> {
+        let tools = Self::tools(root);
+        let mut commands = Vec::new();
+        if tools.biome {
+            commands.push(Self::npx(&["biome", "check", "--write"], file));
+        } else {
+            if tools.prettier {
+                commands.push(Self::npx(&["prettier", "--write"], file));
+            }
+            if tools.eslint {
+                commands.push(Self::npx(&["eslint", "--fix"], file));
+            }
+        }
+        commands
+    }
+
+    fn lint_command(&self, root: &Path, file: &Path) -> Option<ToolCommand> {
+        let tools = Self::tools(root);
+        if tools.biome {
+            Some(Self::npx(&["biome", "check"], file))
+        } else if tools.eslint {
+            Some(Self::npx(&["eslint"], file))
+        } else {
+            None
+        }
+    }
+
+    fn test_command(&self, root: &Path, file: &Path) -> Option<ToolCommand> {
+        let tools = Self::tools(root);
+        if tools.vitest {
+            Some(Self::npx(&["vitest", "related", "--run"], file))
+        } else if tools.jest {
+            Some(Self::npx(&["jest", "--findRelatedTests"], file))
+        } else {
+            None
+        }
+    }
+}
+
+/// Package name from the `[package]` table of a Cargo manifest
+fn package_name(manifest: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+            continue;
+        }
+        if !in_package {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            if key.trim() == "name" {
+                return Some(value.trim().trim_matches('"').to_string());
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_toolchain_dispatch() {
+        let language = |file: &str| toolchain_for(Path::new(file)).map(|t| t.language());
+
+        // Python files go through project discovery, not a toolchain
+        assert_eq!(language("src/app.py"), None);
+        assert_eq!(language("crates/core/src/lib.rs"), Some(Language::Rust));
+        assert_eq!(language("web/src/App.tsx"), Some(Language::JavaScript));
+        assert_eq!(language("web/vite.config.mjs"), Some(Language::JavaScript));
+        assert_eq!(language("README.md"), None);
+    }
+
+    #[test]
+    fn test_rust_toolchain_targets_edited_crate() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let workspace = temp_dir.path();
+        fs::write(
+            workspace.join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/core\"]\n",
+        )?;
+        let crate_dir = workspace.join("crates/core");
+        fs::create_dir_all(crate_dir.join("src"))?;
+        fs::write(
+            crate_dir.join("Cargo.toml"),
+            "[package]\nname = \"core-lib\"\nversion = \"0.1.0\"\n\n[dependencies]\nname = \"ignored\"\n",
+        )?;
+        let file = crate_dir.join("src/lib.rs");
+
+        let toolchain = RustToolchain;
+        let root = toolchain.project_root(&file).unwrap();
+        assert_eq!(root, crate_dir);
+
+        if which("cargo").is_ok() {
+            let clippy = toolchain.lint_command(&root, &file).unwrap();
+            assert_eq!(
+                clippy.display(),
+                "cargo clippy -p core-lib --all-targets -- -D warnings"
+            );
+            let test = toolchain.test_command(&root, &file).unwrap();
+            assert_eq!(test.display(), "cargo test -p core-lib");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_js_tools_from_package_json() {
+        let tools = JsTools::from_package_json(
+            r#"{
+                "name": "frontend",
+                "dependencies": {"react": "^18.0.0"},
+                "devDependencies": {"eslint": "^9.0.0", "prettier": "^3.0.0", "vitest": "^2.0.0"}
+            }"#,
+        );
+        assert_eq!(
+            tools,
+            JsTools {
+                eslint: true,
+                prettier: true,
+                vitest: true,
+                ..JsTools::default()
+            }
+        );
+
+        let tools = JsTools::from_package_json(
+            r#"{"devDependencies": {"@biomejs/biome": "1.9.0", "jest": "^29.0.0"}}"#,
+        );
+        assert!(tools.biome && tools.jest && !tools.eslint);
+
+        assert_eq!(JsTools::from_package_json("not json"), JsTools::default());
+    }
+
+    #[test]
+    fn test_javascript_toolchain_commands() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(
+            root.join("package.json"),
+            r#"{"devDependencies": {"eslint": "^9.0.0", "jest": "^29.0.0"}}"#,
+        )?;
+        fs::create_dir_all(root.join("src"))?;
+        let file = root.join("src/api.ts");
+
+        let toolchain = JavaScriptToolchain;
+        assert_eq!(toolchain.project_root(&file).as_deref(), Some(root));
+
+        if which("npx").is_ok() {
+            let lint = toolchain.lint_command(root, &file).unwrap();
+            assert_eq!(lint.display(), format!("npx eslint {}", file.display()));
+            let test = toolchain.test_command(root, &file).unwrap();
+            assert_eq!(
+                test.display(),
+                format!("npx jest --findRelatedTests {}", file.display())
+            );
+        } else {
+            assert!(toolchain.lint_command(root, &file).is_none());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_package_name() {
+        assert_eq!(
+            package_name("[package]\nname = \"demo\"\n").as_deref(),
+            Some("demo")
+        );
+        assert_eq!(package_name("[workspace]\nmembers = []\n"), None);
+    }
+}
@@ -0,0 +1,279 @@
+use anyhow::{bail, Context, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// GitHub repository releases are published to
+pub const RELEASES_REPO: &str = "DigiBugCat/claude-python-guardrails";
+
+/// Overrides the GitHub API base URL `self-update` queries (mirrors, tests)
+pub const UPDATE_API_ENV: &str = "GUARDRAILS_UPDATE_API";
+
+/// Version of the running binary
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const GITHUB_API: &str = "https://api.github.com";
+
+/// Limit per request; binaries are a few MB
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A published release, as returned by the GitHub releases API
+#[derive(Debug, Clone, Deserialize)]
+pub struct Release {
+    /// Tag such as `v1.2.0`
+    pub tag_name: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+impl Release {
+    /// Version without the tag's `v` prefix
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    pub fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+}
+
+/// Target triple of this platform's release binary, `None` where none is published
+pub fn release_target() -> Option<&'static str> {
+    match (std::env::consts::ARCH, std::env::consts::OS) {
+        ("x86_64", "linux") => Some("x86_64-unknown-linux-gnu"),
+        ("x86_64", "macos") => Some("x86_64-apple-darwin"),
+        ("aarch64", "macos") => Some("aarch64-apple-darwin"),
+        _ => None,
+    }
+}
+
+/// Name of the release asset holding the binary for a target
+pub fn binary_asset_name(target: &str) -> String {
+    format!("claude-python-guardrails-{target}")
+}
+
+/// Release tag for a version given as `1.2.0` or `v1.2.0`
+pub fn release_tag(version: &str) -> String {
+    format!("v{}", version.trim().trim_start_matches('v'))
+}
+
+/// Compare dotted versions numerically (`1.10.0` > `1.9.2`); missing parts count as 0
+/// and non-numeric suffixes are ignored
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<u64> {
+        version
+            .trim()
+            .trim_start_matches('v')
+            .split('.')
+            .map(|part| {
+                let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse().unwrap_or(0)
+            })
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let part = |parts: &[u64]| parts.get(i).copied().unwrap_or(0);
+            part(&a).cmp(&part(&b))
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Expected SHA-256 from a `sha256sum`-style checksum file (`<hex>  <name>`)
+pub fn parse_checksum(content: &str) -> Option<String> {
+    let hash = content.split_whitespace().next()?;
+    (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| hash.to_ascii_lowercase())
+}
+
+/// Fail unless `bytes` hash to the expected SHA-256
+pub fn verify_checksum(bytes: &[u8], expected: &str) -> Result<()> {
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    if actual != expected.to_ascii_lowercase() {
+        bail!("Checksum mismatch: expected {expected}, downloaded binary hashes to {actual}");
+    }
+    Ok(())
+}
+
+/// Replace the file at `path` with `bytes` atomically: the new binary is written next
+/// to it, made executable and renamed over it, so a running hook never sees a partial
+/// file
+pub fn replace_binary(path: &Path, bytes: &[u8]) -> Result<()> {
+    let dir = path
+        .parent()
+        .context("Binary path has no parent directory")?;
+    let name = path
+        .file_name()
+        .context("Binary path has no file name")?
+        .to_string_lossy();
+    let staged = dir.join(format!(".{name}.update-{}", std::process::id()));
+
+    let written = (|| -> Result<()> {
+        let mut file = fs::File::create(&staged)
+            .with_context(|| format!("Failed to create {}", staged.display()))?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+        }
+        fs::rename(&staged, path).with_context(|| format!("Failed to replace {}", path.display()))
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&staged);
+    }
+    written
+}
+
+/// Client for the GitHub releases API
+pub struct ReleaseClient {
+    client: Client,
+    api: String,
+}
+
+impl ReleaseClient {
+    /// Client for `api.github.com`, or `$GUARDRAILS_UPDATE_API` when set
+    pub fn new() -> Result<Self> {
+        let api = std::env::var(UPDATE_API_ENV).unwrap_or_else(|_| GITHUB_API.to_string());
+        let client = Client::builder()
+            .user_agent(concat!(
+                "claude-python-guardrails/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to create HTTP client")?;
+        Ok(Self {
+            client,
+            api: api.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// The latest release, or the one for `version`
+    pub async fn release(&self, version: Option<&str>) -> Result<Release> {
+        let url = match version {
+            Some(version) => format!(
+                "{}/repos/{RELEASES_REPO}/releases/tags/{}",
+                self.api,
+                release_tag(version)
+            ),
+            None => format!("{}/repos/{RELEASES_REPO}/releases/latest", self.api),
+        };
+        let response = self
+            .client
+            .get(&url)
+            .header("Accept", "application/vnd.github+json")
+            .send()
+            .await
+            .with_context(|| format!("Failed to query {url}"))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            match version {
+                Some(version) => bail!("No release {} found", release_tag(version)),
+                None => bail!("No releases published"),
+            }
+        }
+        response
+            .error_for_status()
+            .with_context(|| format!("Failed to query {url}"))?
+            .json()
+            .await
+            .context("Invalid release metadata")
+    }
+
+    /// Download this platform's binary from a release, verified against the release's
+    /// `.sha256` checksum
+    pub async fn download_verified_binary(&self, release: &Release) -> Result<Vec<u8>> {
+        let target = release_target().with_context(|| {
+            format!(
+                "No release binaries are published for {}-{}",
+                std::env::consts::ARCH,
+                std::env::consts::OS
+            )
+        })?;
+        let name = binary_asset_name(target);
+        let binary = release
+            .asset(&name)
+            .with_context(|| format!("Release {} has no {name}", release.tag_name))?;
+        let checksum = release
+            .asset(&format!("{name}.sha256"))
+            .with_context(|| format!("Release {} has no checksum for {name}", release.tag_name))?;
+
+        let checksum = String::from_utf8_lossy(&self.download(checksum).await?).into_owned();
+        let expected = parse_checksum(&checksum)
+            .with_context(|| format!("Invalid checksum file for {name}"))?;
+        let bytes = self.download(binary).await?;
+        verify_checksum(&bytes, &expected)?;
+        Ok(bytes)
+    }
+
+    async fn download(&self, asset: &ReleaseAsset) -> Result<Vec<u8>> {
+        let bytes = self
+            .client
+            .get(&asset.browser_download_url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to download {}", asset.name))?
+            .bytes()
+            .await
+            .with_context(|| format!("Failed to download {}", asset.name))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_versions_and_checksums() {
+        assert_eq!(compare_versions("1.10.0", "1.9.2"), Ordering::Greater);
+        assert_eq!(compare_versions("v1.2", "1.2.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.2.0-rc1", "1.2.1"), Ordering::Less);
+        assert_eq!(release_tag(" v1.2.0"), "v1.2.0");
+        assert_eq!(release_tag("1.2.0"), "v1.2.0");
+
+        let hash = format!("{:x}", Sha256::digest(b"binary"));
+        let checksum = format!(
+            "{}  claude-python-guardrails-x86_64-unknown-linux-gnu\n",
+            hash.to_uppercase()
+        );
+        assert_eq!(parse_checksum(&checksum), Some(hash.clone()));
+        assert_eq!(parse_checksum("not a checksum"), None);
+        assert!(verify_checksum(b"binary", &hash).is_ok());
+        assert!(verify_checksum(b"tampered", &hash).is_err());
+    }
+
+    #[test]
+    fn test_replace_binary() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("claude-python-guardrails");
+        fs::write(&path, "old")?;
+
+        replace_binary(&path, b"new")?;
+        assert_eq!(fs::read(&path)?, b"new");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o777, 0o755);
+        }
+        // Nothing staged is left behind
+        assert_eq!(fs::read_dir(temp_dir.path())?.count(), 1);
+        Ok(())
+    }
+}
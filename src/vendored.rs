@@ -0,0 +1,177 @@
+use crate::checks::parse_imports;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory names conventionally holding vendored third-party code
+pub const VENDOR_DIR_NAMES: &[&str] = &["vendor", "vendored", "_vendor", "third_party"];
+
+/// License files marking a directory as someone else's code
+const LICENSE_FILES: &[&str] = &[
+    "LICENSE",
+    "LICENSE.txt",
+    "LICENSE.md",
+    "LICENSE.rst",
+    "COPYING",
+];
+
+/// Files marking the root of the project the edited file belongs to
+const PROJECT_MARKERS: &[&str] = &["pyproject.toml", "setup.py", "setup.cfg"];
+
+/// Vendored directory containing `file`, if any
+///
+/// Only directories below the host project root count: the nearest ancestor with a
+/// `.git`, otherwise the outermost one with a project marker. Below it, a directory is
+/// vendored when it has a conventional name (`vendor/`, `third_party/`, `_vendor/`...)
+/// or carries its own LICENSE while none of its modules import the host's packages. The
+/// license heuristic needs the host to have packages of its own, and never applies to a
+/// directory with its own project file (a monorepo workspace package, not a vendored copy).
+pub fn vendored_root(file: &Path) -> Option<PathBuf> {
+    let dirs: Vec<&Path> = file.ancestors().skip(1).collect();
+    let host = dirs
+        .iter()
+        .position(|dir| dir.join(".git").exists())
+        .or_else(|| {
+            dirs.iter().rposition(|dir| {
+                PROJECT_MARKERS
+                    .iter()
+                    .any(|marker| dir.join(marker).is_file())
+            })
+        })?;
+    let host_root = dirs[host];
+
+    // Outermost match first, so the whole vendored tree is reported
+    let inner = &dirs[..host];
+    if let Some(dir) = inner.iter().rev().find(|dir| {
+        dir.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| VENDOR_DIR_NAMES.contains(&name))
+    }) {
+        return Some(dir.to_path_buf());
+    }
+
+    let host_packages = top_level_packages(host_root);
+    if host_packages.is_empty() {
+        return None;
+    }
+    inner
+        .iter()
+        .rev()
+        .find(|dir| has_license(dir) && !is_project(dir) && !imports_any(dir, &host_packages))
+        .map(|dir| dir.to_path_buf())
+}
+
+/// Whether `dir` is a Python project of its own (`pyproject.toml` or `setup.py`)
+fn is_project(dir: &Path) -> bool {
+    ["pyproject.toml", "setup.py"]
+        .iter()
+        .any(|marker| dir.join(marker).is_file())
+}
+
+fn has_license(dir: &Path) -> bool {
+    LICENSE_FILES.iter().any(|file| dir.join(file).is_file())
+}
+
+/// Importable packages of the host project, at its root or under `src/`
+fn top_level_packages(root: &Path) -> HashSet<String> {
+    [root.to_path_buf(), root.join("src")]
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|path| path.join("__init__.py").is_file())
+        .filter_map(|path| Some(path.file_name()?.to_str()?.to_string()))
+        .collect()
+}
+
+/// Whether any module directly in `dir` imports one of `packages`
+fn imports_any(dir: &Path, packages: &HashSet<String>) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "py"))
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .any(|source| {
+            parse_imports(&source).iter().any(|import| {
+                let top = import.module.split('.').next().unwrap_or("");
+                packages.contains(top)
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_vendored_root_detection() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+        fs::create_dir_all(root.join("app"))?;
+        fs::write(root.join("app/__init__.py"), "")?;
+        fs::write(root.join("app/main.py"), "import requests\n")?;
+        assert_eq!(vendored_root(&root.join("app/main.py")), None);
+
+        // Conventional directory names
+        fs::create_dir_all(root.join("app/_vendor/six"))?;
+        fs::write(root.join("app/_vendor/six/six.py"), "import sys\n")?;
+        assert_eq!(
+            vendored_root(&root.join("app/_vendor/six/six.py")),
+            Some(root.join("app/_vendor"))
+        );
+
+        // A foreign license with no imports of the host project
+        fs::create_dir_all(root.join("libs/toml"))?;
+        fs::write(root.join("libs/toml/LICENSE"), "MIT License\n")?;
+        fs::write(root.join("libs/toml/decoder.py"), "import re\n")?;
+        assert_eq!(
+            vendored_root(&root.join("libs/toml/decoder.py")),
+            Some(root.join("libs/toml"))
+        );
+
+        // ...but a licensed subproject importing the host is the project's own code
+        fs::write(root.join("libs/toml/decoder.py"), "from app import main\n")?;
+        assert_eq!(vendored_root(&root.join("libs/toml/decoder.py")), None);
+
+        // The host project's own LICENSE doesn't count
+        fs::write(root.join("LICENSE"), "MIT License\n")?;
+        assert_eq!(vendored_root(&root.join("app/main.py")), None);
+
+        // A licensed directory with its own project file is a workspace package
+        fs::write(root.join("libs/toml/decoder.py"), "import re\n")?;
+        fs::write(
+            root.join("libs/toml/pyproject.toml"),
+            "[project]\nname = \"toml\"\n",
+        )?;
+        assert_eq!(vendored_root(&root.join("libs/toml/decoder.py")), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_monorepo_packages_are_not_vendored() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        // A monorepo root with no packages of its own
+        fs::create_dir_all(root.join(".git"))?;
+        fs::create_dir_all(root.join("packages/foo/foo"))?;
+        fs::write(root.join("packages/foo/LICENSE"), "MIT License\n")?;
+        fs::write(root.join("packages/foo/foo/core.py"), "import re\n")?;
+        assert_eq!(vendored_root(&root.join("packages/foo/foo/core.py")), None);
+
+        // Conventional vendor directories are still recognised
+        fs::create_dir_all(root.join("packages/foo/foo/_vendor"))?;
+        fs::write(root.join("packages/foo/foo/_vendor/six.py"), "")?;
+        assert_eq!(
+            vendored_root(&root.join("packages/foo/foo/_vendor/six.py")),
+            Some(root.join("packages/foo/foo/_vendor"))
+        );
+        Ok(())
+    }
+}
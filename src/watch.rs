@@ -0,0 +1,212 @@
+use crate::discovery::{PythonSourceKind, SearchLimits};
+use crate::error::{GuardrailsError, GuardrailsResult};
+use crate::toolchain::toolchain_for;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Reports source files created or modified below a project root, from filesystem
+/// notifications
+///
+/// Only files some toolchain handles are reported. Directories are watched one by one as
+/// the `automation.search` limits walk them, so skipped directories (hidden ones,
+/// virtualenvs, build output) and anything below `max_depth` are never watched.
+pub struct FileWatcher {
+    root: PathBuf,
+    limits: SearchLimits,
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    /// Modification time of each file when it was last reported or refreshed
+    stamps: HashMap<PathBuf, SystemTime>,
+}
+
+impl FileWatcher {
+    /// Start watching `root`; files that already exist are not reported until they change
+    pub fn new(root: &Path, limits: &SearchLimits) -> GuardrailsResult<Self> {
+        let (sender, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event| {
+            // The receiver is gone once the FileWatcher is dropped
+            let _ = sender.send(event);
+        })
+        .map_err(|e| watch_error(root, e))?;
+        let mut file_watcher = Self {
+            root: root.to_path_buf(),
+            limits: limits.clone(),
+            watcher,
+            events,
+            stamps: HashMap::new(),
+        };
+        for dir in file_watcher.limits.dirs(root) {
+            file_watcher.watch_dir(&dir)?;
+        }
+        Ok(file_watcher)
+    }
+
+    /// Search limits the watched directories were chosen with
+    pub fn limits(&self) -> &SearchLimits {
+        &self.limits
+    }
+
+    fn watch_dir(&mut self, dir: &Path) -> GuardrailsResult<()> {
+        self.watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| watch_error(dir, e))
+    }
+
+    /// Files created or modified since the previous call, sorted by path
+    ///
+    /// Waits up to `wait` for a first notification, then until `settle` passes without
+    /// another one, so a save that touches a file several times is reported once.
+    pub fn changed(&mut self, wait: Duration, settle: Duration) -> Vec<PathBuf> {
+        let mut touched = BTreeSet::new();
+        let mut deadline = Instant::now() + wait;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.events.recv_timeout(timeout) {
+                Ok(Ok(event)) => {
+                    self.collect(event, &mut touched);
+                    deadline = Instant::now() + settle;
+                }
+                Ok(Err(e)) => log::warn!("File watch error: {e}"),
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        touched
+            .into_iter()
+            .filter(|file| {
+                let Some(modified) = modified_time(file) else {
+                    return false;
+                };
+                self.stamps.insert(file.clone(), modified) != Some(modified)
+            })
+            .collect()
+    }
+
+    /// Add the source files an event touched to `touched`, watching new directories
+    fn collect(&mut self, event: Event, touched: &mut BTreeSet<PathBuf>) {
+        if matches!(event.kind, EventKind::Access(_) | EventKind::Remove(_)) {
+            return;
+        }
+        for path in event.paths {
+            if path.is_dir() {
+                if !self.limits.reaches(&self.root, &path) {
+                    continue;
+                }
+                // Files written before the new directory was watched are only seen here
+                for dir in self.limits.dirs(&path) {
+                    if let Err(e) = self.watch_dir(&dir) {
+                        log::warn!("{e}");
+                    }
+                    touched.extend(source_files(&dir));
+                }
+            } else if is_source_file(&path)
+                && path
+                    .parent()
+                    .is_some_and(|dir| self.limits.reaches(&self.root, dir))
+            {
+                touched.insert(path);
+            }
+        }
+    }
+
+    /// Record a file's current modification time so edits made while processing it
+    /// (formatters, auto-fixes) don't trigger another run
+    pub fn refresh(&mut self, file: &Path) {
+        if let Some(modified) = modified_time(file) {
+            self.stamps.insert(file.to_path_buf(), modified);
+        }
+    }
+}
+
+fn is_source_file(path: &Path) -> bool {
+    PythonSourceKind::of(path).is_some() || toolchain_for(path).is_some()
+}
+
+fn source_files(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_source_file(path))
+        .collect()
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn watch_error(path: &Path, error: notify::Error) -> GuardrailsError {
+    GuardrailsError::io(path, std::io::Error::other(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use tempfile::TempDir;
+
+    const WAIT: Duration = Duration::from_secs(5);
+    const SETTLE: Duration = Duration::from_millis(200);
+
+    /// Collect reported files until `expected` are all in, or `WAIT` passes
+    fn changed_until(watcher: &mut FileWatcher, expected: &[PathBuf]) -> Vec<PathBuf> {
+        let deadline = Instant::now() + WAIT;
+        let mut changed = BTreeSet::new();
+        while Instant::now() < deadline && !expected.iter().all(|file| changed.contains(file)) {
+            changed.extend(watcher.changed(WAIT, SETTLE));
+        }
+        changed.into_iter().collect()
+    }
+
+    #[test]
+    fn test_reports_changed_source_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().canonicalize()?;
+        for dir in ["src", ".venv/lib", "build/lib", "a/b/c"] {
+            fs::create_dir_all(root.join(dir))?;
+        }
+        fs::write(root.join("src/app.py"), "x = 1\n")?;
+
+        let limits = SearchLimits {
+            max_depth: 3,
+            ..SearchLimits::default()
+        };
+        let mut watcher = FileWatcher::new(&root, &limits)?;
+        assert!(watcher
+            .changed(Duration::from_millis(100), SETTLE)
+            .is_empty());
+
+        fs::write(root.join("src/app.py"), "x = 2\n")?;
+        fs::write(root.join("src/new.py"), "y = 1\n")?;
+        fs::write(root.join("notes.txt"), "not source\n")?;
+        fs::write(root.join(".venv/lib/site.py"), "z = 1\n")?;
+        fs::write(root.join("build/lib/out.py"), "z = 1\n")?;
+        fs::write(root.join("a/b/c/deep.py"), "z = 1\n")?;
+        let expected = vec![root.join("src/app.py"), root.join("src/new.py")];
+        assert_eq!(changed_until(&mut watcher, &expected), expected);
+
+        // Directories created while watching are picked up
+        fs::create_dir_all(root.join("pkg"))?;
+        fs::write(root.join("pkg/mod.py"), "w = 1\n")?;
+        let expected = vec![root.join("pkg/mod.py")];
+        assert_eq!(changed_until(&mut watcher, &expected), expected);
+        fs::write(root.join("pkg/mod.py"), "w = 2\n")?;
+        assert_eq!(changed_until(&mut watcher, &expected), expected);
+
+        // A rewrite recorded with refresh() is not reported again
+        fs::write(root.join("src/app.py"), "x = 3\n")?;
+        watcher.refresh(&root.join("src/app.py"));
+        assert!(watcher
+            .changed(Duration::from_millis(500), SETTLE)
+            .is_empty());
+
+        Ok(())
+    }
+}
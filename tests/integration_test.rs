@@ -1,6 +1,7 @@
 use anyhow::Result;
 use std::fs;
 use std::io::Write;
+use std::path::Path;
 use std::process::{Command, Stdio};
 use tempfile::TempDir;
 
@@ -29,6 +30,27 @@ fn run_cli_with_stdin(args: &[&str], stdin_input: &str) -> Result<(String, Strin
     Ok((stdout, stderr, exit_code))
 }
 
+/// Write an executable stand-in for an external tool (ruff, pytest...) into `bin_dir`
+#[cfg(unix)]
+fn fake_tool(bin_dir: &Path, name: &str, script: &str) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::create_dir_all(bin_dir)?;
+    let tool = bin_dir.join(name);
+    fs::write(&tool, script)?;
+    fs::set_permissions(&tool, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
+
+/// `PATH` with `bin_dir` searched first, so fake tools shadow installed ones
+fn path_with(bin_dir: &Path) -> String {
+    format!(
+        "{}:{}",
+        bin_dir.display(),
+        std::env::var("PATH").unwrap_or_default()
+    )
+}
+
 /// Helper to create Claude Code hook JSON for PostToolUse Edit event
 fn create_hook_json(file_path: &str) -> String {
     format!(
@@ -84,8 +106,21 @@ fn test_help_command() -> Result<()> {
     assert!(stdout.contains("test"));
     assert!(stdout.contains("analyze"));
 
+    assert!(stdout.contains("protect"));
+    assert!(stdout.contains("check-command"));
+    assert!(stdout.contains("session-start"));
+    assert!(stdout.contains("suppressions"));
+    assert!(stdout.contains("report"));
+    assert!(stdout.contains("clean"));
+    assert!(stdout.contains("watch"));
+    assert!(stdout.contains("generate-tests"));
+    assert!(stdout.contains("fix"));
+    assert!(stdout.contains("mutate"));
+    assert!(stdout.contains("stop"));
+    assert!(stdout.contains("preview"));
+
     // Should NOT contain removed commands
-    assert!(!stdout.contains("check"));
+    assert!(!stdout.contains("  check "));
     assert!(!stdout.contains("init"));
     assert!(!stdout.contains("validate"));
 
@@ -182,6 +217,43 @@ fn test_analyze_with_hook_input_json_format() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_analyze_no_ai_uses_heuristics() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("models.py"), "class UserModel: pass")?;
+    let responses = root.join("responses");
+    fs::create_dir_all(&responses)?;
+    fs::write(
+        responses.join("exclusion.json"),
+        r#"{"should_exclude_general": true, "should_exclude_lint": true, "should_exclude_test": true, "reasoning": "canned", "file_type": "python", "purpose": "Canned analysis", "exclusion_recommendation": "exclude"}"#,
+    )?;
+    fs::write(
+        root.join("guardrails.yaml"),
+        format!(
+            "exclude:\n  patterns: []\nai:\n  mock_responses: {}\n",
+            responses.display()
+        ),
+    )?;
+
+    let analyze = |extra: &[&str]| -> Result<serde_json::Value> {
+        let output = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+            .args(["--ai", "mock", "analyze", "--format", "json", "models.py"])
+            .args(extra)
+            .current_dir(root)
+            .stdin(Stdio::null())
+            .output()?;
+        assert_eq!(output.status.code(), Some(0));
+        Ok(serde_json::from_slice(&output.stdout)?)
+    };
+
+    assert_eq!(analyze(&[])?["purpose"], "Canned analysis");
+    let heuristic = analyze(&["--no-ai"])?;
+    assert_eq!(heuristic["purpose"], "Unknown (analyzed without AI)");
+    assert_eq!(heuristic["should_exclude_lint"], false);
+    Ok(())
+}
+
 #[test]
 fn test_hooks_ignore_non_edit_events() -> Result<()> {
     let temp_dir = TempDir::new()?;
@@ -252,6 +324,47 @@ fn test_hooks_handle_missing_file() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_test_hook_flags_tests_importing_deleted_module() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"calc\"\n")?;
+    fs::create_dir_all(root.join("tests"))?;
+    fs::write(
+        root.join("tests/test_calc.py"),
+        "from calc import add\n\n\ndef test_add():\n    assert add(1, 2) == 3\n",
+    )?;
+
+    // calc.py was removed by the Bash command
+    let hook_json = serde_json::json!({
+        "hook_event_name": "PostToolUse",
+        "tool_name": "Bash",
+        "tool_input": {"command": "rm calc.py"},
+        "cwd": root,
+    })
+    .to_string();
+    let (_stdout, stderr, exit_code) = run_cli_with_stdin(&["test"], &hook_json)?;
+    assert_eq!(exit_code, 2);
+    assert!(
+        stderr.contains("was deleted but tests still import `calc`"),
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("tests/test_calc.py:1"));
+
+    // Commands that remove nothing leave the test hook quiet
+    let hook_json = serde_json::json!({
+        "hook_event_name": "PostToolUse",
+        "tool_name": "Bash",
+        "tool_input": {"command": "ls tests"},
+        "cwd": root,
+    })
+    .to_string();
+    let (_stdout, _stderr, exit_code) = run_cli_with_stdin(&["test"], &hook_json)?;
+    assert_eq!(exit_code, 0);
+
+    Ok(())
+}
+
 #[test]
 fn test_hooks_without_stdin() -> Result<()> {
     // Test what happens when hooks are called without stdin input
@@ -311,3 +424,1194 @@ fn test_different_tool_types() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_protect_allows_unprotected_paths() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let py_file = temp_dir.path().join("app.py");
+
+    let pre_edit_hook = format!(
+        r#"{{
+            "hook_event_name": "PreToolUse",
+            "tool_name": "Edit",
+            "tool_input": {{
+                "file_path": "{}"
+            }}
+        }}"#,
+        py_file.to_str().unwrap()
+    );
+
+    let (stdout, _stderr, exit_code) = run_cli_with_stdin(&["protect"], &pre_edit_hook)?;
+    assert_eq!(exit_code, 0);
+    assert!(!stdout.contains("permissionDecision"));
+
+    // PostToolUse events are not protect's concern
+    let post_edit_hook = create_hook_json(py_file.to_str().unwrap());
+    let (stdout, _stderr, exit_code) = run_cli_with_stdin(&["protect"], &post_edit_hook)?;
+    assert_eq!(exit_code, 0);
+    assert!(stdout.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_protect_denies_in_ascii_style() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nprotect:\n  patterns: [\"migrations/**\"]\noutput:\n  style: ascii\n",
+    )?;
+    let hook = format!(
+        r#"{{"hook_event_name": "PreToolUse", "tool_name": "Edit", "tool_input": {{"file_path": "{}"}}}}"#,
+        root.join("migrations/0001_initial.py").display()
+    );
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .arg("protect")
+        .current_dir(root)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(hook.as_bytes())?;
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(
+        stdout.contains("\"permissionDecision\":\"deny\""),
+        "{stdout}"
+    );
+    assert!(stdout.contains("[LOCKED]"), "{stdout}");
+    assert!(stdout.is_ascii(), "{stdout}");
+
+    Ok(())
+}
+
+#[test]
+fn test_hooks_use_the_edited_projects_config() -> Result<()> {
+    let project = TempDir::new()?;
+    let elsewhere = TempDir::new()?;
+    fs::write(
+        project.path().join("pyproject.toml"),
+        "[project]\nname = \"app\"\n",
+    )?;
+    fs::write(
+        project.path().join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nprotect:\n  patterns: [\"migrations/**\"]\n",
+    )?;
+    fs::create_dir_all(project.path().join("src"))?;
+    // The hook's own working directory protects everything; it must not apply
+    fs::write(
+        elsewhere.path().join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nprotect:\n  patterns: [\"**\"]\n",
+    )?;
+
+    let protect = |file: &Path| -> Result<String> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+            .arg("protect")
+            .current_dir(elsewhere.path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let hook = format!(
+            r#"{{"hook_event_name": "PreToolUse", "tool_name": "Edit", "tool_input": {{"file_path": "{}"}}}}"#,
+            file.display()
+        );
+        child.stdin.take().unwrap().write_all(hook.as_bytes())?;
+        Ok(String::from_utf8_lossy(&child.wait_with_output()?.stdout).to_string())
+    };
+
+    assert!(protect(&project.path().join("migrations/0001_initial.py"))?.contains("deny"));
+    assert!(protect(&project.path().join("src/app.py"))?.is_empty());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_hook_state_goes_to_the_sessions_project() -> Result<()> {
+    let project = TempDir::new()?;
+    let elsewhere = TempDir::new()?;
+    let root = project.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nai:\n  provider: mock\n  audit_log: true\n",
+    )?;
+    fs::create_dir_all(root.join("src"))?;
+    fs::write(root.join("src/app.py"), "x = 1\n")?;
+    let bin_dir = root.join("bin");
+    fake_tool(&bin_dir, "ruff", "#!/bin/sh\nexit 0\n")?;
+
+    // The hook runs from another directory; the payload's cwd is the session's project
+    let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .arg("lint")
+        .current_dir(elsewhere.path())
+        .env("PATH", path_with(&bin_dir))
+        .env_remove("GUARDRAILS_STATE_DIR")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let hook = format!(
+        r#"{{"hook_event_name": "PostToolUse", "tool_name": "Edit", "cwd": "{}", "tool_input": {{"file_path": "src/app.py"}}}}"#,
+        root.display()
+    );
+    child.stdin.take().unwrap().write_all(hook.as_bytes())?;
+    let output = child.wait_with_output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("verified"), "{stderr}");
+
+    assert!(root.join(".claude-guardrails").is_dir());
+    assert!(
+        !elsewhere.path().join(".claude-guardrails").exists(),
+        "state was written to the process's working directory"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_input_file_replays_captured_payload() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nprotect:\n  patterns: [\"migrations/**\"]\n",
+    )?;
+    let payload = root.join("payload.json");
+    fs::write(
+        &payload,
+        format!(
+            r#"{{"hook_event_name": "PreToolUse", "tool_name": "Write", "tool_input": {{"file_path": "{}"}}}}"#,
+            root.join("migrations/0002_auto.py").display()
+        ),
+    )?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .args(["protect", "--input-file", payload.to_str().unwrap()])
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(
+        stdout.contains("\"permissionDecision\":\"deny\""),
+        "{stdout}"
+    );
+
+    // A missing payload file is reported instead of passing silently
+    let output = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .args(["protect", "--input-file", "missing.json"])
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .output()?;
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing.json"));
+
+    Ok(())
+}
+
+#[test]
+fn test_record_and_replay_hook_events() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nprotect:\n  patterns: [\"secrets/**\"]\n",
+    )?;
+    let payload = format!(
+        r#"{{"hook_event_name": "PreToolUse", "tool_name": "Edit", "tool_input": {{"file_path": "{}"}}}}"#,
+        root.join("secrets/keys.py").display()
+    );
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .arg("protect")
+        .current_dir(root)
+        .env("GUARDRAILS_RECORD", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(payload.as_bytes())?;
+    assert!(child.wait_with_output()?.status.success());
+
+    let replay = |args: &[&str]| -> Result<String> {
+        let output = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+            .arg("replay")
+            .args(args)
+            .current_dir(root)
+            .output()?;
+        assert!(output.status.success());
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    };
+
+    let listed = replay(&["--list"])?;
+    assert!(listed.contains("#1 "), "{listed}");
+    assert!(listed.contains("protect PreToolUse Edit"), "{listed}");
+    assert!(!listed.contains("permissionDecision"), "{listed}");
+
+    let replayed = replay(&["--event", "1"])?;
+    assert!(
+        replayed.contains("\"permissionDecision\":\"deny\""),
+        "{replayed}"
+    );
+    assert!(replayed.contains("exit code 0"), "{replayed}");
+
+    // Replaying doesn't record again
+    assert_eq!(replay(&["--list"])?.lines().count(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_panic_falls_back_to_no_action() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"demo\"\n")?;
+
+    let run = |command: &str| -> Result<std::process::Output> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+            .arg(command)
+            .current_dir(root)
+            .env("GUARDRAILS_DEBUG_PANIC", "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child.stdin.take().unwrap().write_all(b"{}")?;
+        Ok(child.wait_with_output()?)
+    };
+
+    // Hooks exit 0 with a short note instead of 101 and a backtrace
+    let output = run("lint")?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(0), "{stderr}");
+    assert!(stderr.contains("internal error"), "{stderr}");
+    assert!(stderr.contains("crash.log"), "{stderr}");
+    assert!(!stderr.contains("stack backtrace"), "{stderr}");
+
+    let log = fs::read_to_string(root.join(".claude-guardrails/crash.log"))?;
+    assert!(
+        log.contains("`lint` panicked: GUARDRAILS_DEBUG_PANIC is set"),
+        "{log}"
+    );
+
+    // Other commands report the error
+    assert_eq!(run("clean")?.status.code(), Some(1));
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_overrides_enable_lint_for_matching_files() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    fs::create_dir_all(root.join("src"))?;
+    fs::create_dir_all(root.join("scripts"))?;
+    let source = root.join("src/app.py");
+    let script = root.join("scripts/tool.py");
+    fs::write(&source, "x = y\n")?;
+    fs::write(&script, "x = y\n")?;
+
+    let bin_dir = root.join("bin");
+    fake_tool(
+        &bin_dir,
+        "ruff",
+        "#!/bin/sh\ncase \"$*\" in\n  *--fix*|format*|*--version*) exit 0 ;;\nesac\necho 'app.py:1:5: F821 Undefined name `y`'\nexit 1\n",
+    )?;
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nautomation:\n  lint:\n    enabled: false\noverrides:\n  - files: \"src/**\"\n    automation:\n      lint:\n        enabled: true\n",
+    )?;
+
+    let lint = |file: &Path| -> Result<i32> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+            .args(["lint", "--ai", "mock"])
+            .current_dir(root)
+            .env("PATH", path_with(&bin_dir))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(create_hook_json(file.to_str().unwrap()).as_bytes())?;
+        Ok(child.wait_with_output()?.status.code().unwrap_or(-1))
+    };
+
+    // Linting stays off outside the override's files
+    assert_eq!(lint(&script)?, 0);
+    assert_eq!(lint(&source)?, 2);
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_automation_skip_paths_leave_direct_lint_alone() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    fs::create_dir_all(root.join("examples"))?;
+    let example = root.join("examples/demo.py");
+    let source = root.join("app.py");
+    fs::write(&example, "x = y\n")?;
+    fs::write(&source, "x = y\n")?;
+
+    let bin_dir = root.join("bin");
+    fake_tool(
+        &bin_dir,
+        "ruff",
+        "#!/bin/sh\ncase \"$*\" in\n  *--fix*|format*|*--version*) exit 0 ;;\nesac\necho 'demo.py:1:5: F821 Undefined name `y`'\nexit 1\n",
+    )?;
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nautomation:\n  skip_paths: [\"examples/**\"]\n",
+    )?;
+
+    let run = |args: &[&str], file: &std::path::Path| -> Result<i32> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+            .args(args)
+            .current_dir(root)
+            .env("PATH", path_with(&bin_dir))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(create_hook_json(file.to_str().unwrap()).as_bytes())?;
+        Ok(child.wait_with_output()?.status.code().unwrap_or(-1))
+    };
+
+    // The hook never runs for skipped paths, but linting them directly still reports
+    assert_eq!(run(&["lint", "--ai", "mock"], &example)?, 0);
+    assert_eq!(run(&["lint", "--ai", "mock"], &source)?, 2);
+    assert_eq!(run(&["lint", "examples/demo.py"], &example)?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_check_command_denies_force_push() -> Result<()> {
+    let bash_hook = r#"{
+        "hook_event_name": "PreToolUse",
+        "tool_name": "Bash",
+        "tool_input": {
+            "command": "git push --force origin main"
+        }
+    }"#;
+
+    let (stdout, _stderr, exit_code) = run_cli_with_stdin(&["check-command"], bash_hook)?;
+    assert_eq!(exit_code, 0);
+
+    let output: serde_json::Value =
+        serde_json::from_str(stdout.trim()).expect("Output should be valid JSON");
+    assert_eq!(output["hookSpecificOutput"]["permissionDecision"], "deny");
+
+    let safe_hook = bash_hook.replace("git push --force origin main", "git status");
+    let (stdout, _stderr, exit_code) = run_cli_with_stdin(&["check-command"], &safe_hook)?;
+    assert_eq!(exit_code, 0);
+    assert!(stdout.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_session_start_emits_additional_context() -> Result<()> {
+    let session_hook = r#"{
+        "hook_event_name": "SessionStart",
+        "source": "startup"
+    }"#;
+
+    let (stdout, _stderr, exit_code) = run_cli_with_stdin(&["session-start"], session_hook)?;
+    assert_eq!(exit_code, 0);
+
+    let output: serde_json::Value =
+        serde_json::from_str(stdout.trim()).expect("Output should be valid JSON");
+    assert_eq!(
+        output["hookSpecificOutput"]["hookEventName"],
+        "SessionStart"
+    );
+    let context = output["hookSpecificOutput"]["additionalContext"]
+        .as_str()
+        .unwrap();
+    assert!(context.contains("Test runner"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_mock_provider_runs_full_test_pipeline() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"calc\"\n")?;
+    fs::write(root.join("calc.py"), "def add(a, b):\n    return a - b\n")?;
+    fs::create_dir_all(root.join("tests"))?;
+    fs::write(
+        root.join("tests/test_calc.py"),
+        "import unittest\n\nfrom calc import add\n\n\nclass CalcTests(unittest.TestCase):\n    def test_add(self):\n        self.assertEqual(add(1, 2), 3)\n",
+    )?;
+
+    // Stand-in pytest failing the test; the only tool on PATH, so it is the chosen runner
+    let bin_dir = root.join("bin");
+    fake_tool(
+        &bin_dir,
+        "pytest",
+        "#!/bin/sh\ncase \"$*\" in\n  *--version*) echo 'pytest 8.0.0'; exit 0 ;;\nesac\necho 'FAILED tests/test_calc.py::CalcTests::test_add - AssertionError: -1 != 3'\necho '1 failed in 0.01s'\nexit 1\n",
+    )?;
+
+    let source = root.join("calc.py");
+    let run = |provider: &str| -> Result<(String, i32)> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+            .args(["test", "--ai", provider])
+            .current_dir(root)
+            .env("PATH", &bin_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(create_hook_json(&source.to_string_lossy()).as_bytes())?;
+        let output = child.wait_with_output()?;
+        Ok((
+            String::from_utf8_lossy(&output.stderr).to_string(),
+            output.status.code().unwrap_or(-1),
+        ))
+    };
+
+    let (stderr, exit_code) = run("mock")?;
+    assert_eq!(exit_code, 2, "stderr: {stderr}");
+    assert!(stderr.contains("TESTS FAILED"), "stderr: {stderr}");
+    assert!(stderr.contains("Mock analysis"), "stderr: {stderr}");
+
+    let (stderr, _exit_code) = run("bogus")?;
+    assert!(stderr.contains("unknown AI provider"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_coverage_gate_skipped_without_pytest_cov() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"calc\"\n")?;
+    fs::write(root.join("calc.py"), "def add(a, b):\n    return a + b\n")?;
+    fs::create_dir_all(root.join("tests"))?;
+    fs::write(
+        root.join("tests/test_calc.py"),
+        "from calc import add\n\n\ndef test_add():\n    assert add(1, 2) == 3\n",
+    )?;
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nautomation:\n  test:\n    min_coverage_percent: 90\n",
+    )?;
+
+    // Stand-in pytest without plugins, rejecting --cov like a real one would
+    let bin_dir = root.join("bin");
+    fake_tool(
+        &bin_dir,
+        "pytest",
+        "#!/bin/sh\ncase \"$*\" in\n  *-VV*) echo 'This is pytest version 8.0.0'; exit 0 ;;\n  *--cov*) echo 'error: unrecognized arguments: --cov' >&2; exit 4 ;;\nesac\necho '1 passed in 0.01s'\nexit 0\n",
+    )?;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .args(["test", "--ai", "mock"])
+        .current_dir(root)
+        .env("PATH", &bin_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(create_hook_json(&root.join("calc.py").to_string_lossy()).as_bytes())?;
+    let output = child.wait_with_output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!stderr.contains("TESTS FAILED"), "stderr: {stderr}");
+    assert!(stderr.contains("Coverage gate skipped"), "stderr: {stderr}");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_lint_github_format_prints_annotations() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    fs::write(root.join("app.py"), "import os\n")?;
+
+    // Stand-in ruff reporting one finding for the checked file
+    let bin_dir = root.join("bin");
+    fake_tool(
+        &bin_dir,
+        "ruff",
+        "#!/bin/sh\necho \"$2:1:8: F401 \\`os\\` imported but unused\"\nexit 1\n",
+    )?;
+    let path = path_with(&bin_dir);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .args(["lint", "--format", "github", "app.py"])
+        .current_dir(root)
+        .env("PATH", path)
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(1), "stdout: {stdout}");
+    assert_eq!(
+        stdout.trim(),
+        "::error file=app.py,line=1,col=8,title=F401::`os` imported but unused"
+    );
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_lint_rolls_back_autofix_that_breaks_file() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    let original = "def run(items):\n    return [item for item in items]\n";
+    let py_file = root.join("app.py");
+    fs::write(&py_file, original)?;
+
+    // Stand-in ruff whose auto-fix truncates the file mid-expression
+    let bin_dir = root.join("bin");
+    fake_tool(
+        &bin_dir,
+        "ruff",
+        "#!/bin/sh\ncase \"$*\" in *--fix*) printf 'def run(items):\\n    return [item\\n' > \"$4\" ;; esac\nexit 0\n",
+    )?;
+    let path = path_with(&bin_dir);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .arg("lint")
+        .current_dir(root)
+        .env("PATH", path)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(create_hook_json(py_file.to_str().unwrap()).as_bytes())?;
+    let output = child.wait_with_output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(2), "stderr: {stderr}");
+    assert!(stderr.contains("ROLLED BACK"), "stderr: {stderr}");
+    assert_eq!(fs::read_to_string(&py_file)?, original);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_lint_runs_type_checker_concurrently() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nautomation:\n  typecheck:\n    enabled: true\n    tool: mypy\n",
+    )?;
+    let py_file = root.join("app.py");
+    fs::write(&py_file, "def run() -> int:\n    return \"1\"\n")?;
+
+    // Stand-in ruff and mypy that each take a second to check the file
+    let bin_dir = root.join("bin");
+    let tools = [
+        (
+            "ruff",
+            "#!/bin/sh\ncase \"$*\" in check*--output-format*) sleep 1 ;; esac\nexit 0\n",
+        ),
+        (
+            "mypy",
+            "#!/bin/sh\nsleep 1\necho \"$3:2:12: error: Incompatible return value type (got \\\"str\\\", expected \\\"int\\\")  [return-value]\"\nexit 1\n",
+        ),
+    ];
+    for (name, script) in tools {
+        fake_tool(&bin_dir, name, script)?;
+    }
+    let path = path_with(&bin_dir);
+
+    let started = std::time::Instant::now();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .arg("lint")
+        .current_dir(root)
+        .env("PATH", path)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(create_hook_json(py_file.to_str().unwrap()).as_bytes())?;
+    let output = child.wait_with_output()?;
+    let elapsed = started.elapsed();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(2), "stderr: {stderr}");
+    assert!(stderr.contains("TYPE ERRORS (mypy)"), "stderr: {stderr}");
+    assert!(stderr.contains("app.py:2:12: error"), "stderr: {stderr}");
+    assert!(elapsed.as_secs_f64() < 1.9, "took {elapsed:?}");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_lint_reuses_verdict_for_unchanged_file() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nautomation:\n  lint:\n    cooldown_seconds: 0\n",
+    )?;
+    let py_file = root.join("app.py");
+    fs::write(&py_file, "x = 1\n")?;
+
+    // Stand-in ruff logging every invocation
+    let bin_dir = root.join("bin");
+    let calls = root.join("ruff-calls.log");
+    fake_tool(
+        &bin_dir,
+        "ruff",
+        &format!("#!/bin/sh\necho \"$*\" >> {}\nexit 0\n", calls.display()),
+    )?;
+    let path = path_with(&bin_dir);
+
+    let lint = || -> Result<String> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+            .arg("lint")
+            .current_dir(root)
+            .env("PATH", &path)
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(create_hook_json(py_file.to_str().unwrap()).as_bytes())?;
+        let output = child.wait_with_output()?;
+        Ok(String::from_utf8_lossy(&output.stderr).to_string())
+    };
+    // Version queries feed the cache key; only check/format/fix runs count
+    let call_count = || {
+        fs::read_to_string(&calls)
+            .unwrap_or_default()
+            .lines()
+            .filter(|line| *line != "--version")
+            .count()
+    };
+
+    let first = lint()?;
+    assert!(first.contains("Continue with your task"), "{first}");
+    let ran = call_count();
+    assert!(ran > 0);
+
+    let second = lint()?;
+    assert!(
+        second.contains("Unchanged since the last passing lint run"),
+        "{second}"
+    );
+    assert_eq!(call_count(), ran);
+
+    fs::write(&py_file, "x = 2\n")?;
+    let third = lint()?;
+    assert!(!third.contains("Unchanged"), "{third}");
+    assert!(call_count() > ran);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_lint_delivers_passing_run_as_advice() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nautomation:\n  advice: [passing_lint]\n",
+    )?;
+    let py_file = root.join("app.py");
+    fs::write(&py_file, "x = 1\n")?;
+
+    let bin_dir = root.join("bin");
+    fake_tool(&bin_dir, "ruff", "#!/bin/sh\nexit 0\n")?;
+    let path = path_with(&bin_dir);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .arg("lint")
+        .current_dir(root)
+        .env("PATH", &path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(create_hook_json(py_file.to_str().unwrap()).as_bytes())?;
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // Advice doesn't interrupt Claude: exit 0 with the message as additional context
+    assert_eq!(output.status.code(), Some(0), "stderr: {stderr}");
+    let hook_output: serde_json::Value = serde_json::from_str(stdout.trim())?;
+    assert_eq!(
+        hook_output["hookSpecificOutput"]["hookEventName"],
+        "PostToolUse"
+    );
+    let context = hook_output["hookSpecificOutput"]["additionalContext"]
+        .as_str()
+        .unwrap();
+    assert!(context.contains("Continue with your task"), "{context}");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_lint_severity_mapping_silences_warnings() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    let py_file = root.join("app.py");
+    fs::write(&py_file, "x = 1\n")?;
+
+    // Stand-in ruff reporting a long line (a warning) or an undefined name (an error)
+    let bin_dir = root.join("bin");
+    let finding = root.join("finding.txt");
+    fake_tool(
+        &bin_dir,
+        "ruff",
+        &format!(
+            "#!/bin/sh\ncase \"$*\" in\n  *--fix*|format*|*--version*) exit 0 ;;\nesac\ncat {}\nexit 1\n",
+            finding.display()
+        ),
+    )?;
+    let path = path_with(&bin_dir);
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\nautomation:\n  lint:\n    cooldown_seconds: 0\n  severity:\n    lint_warning: silent\n",
+    )?;
+
+    let lint = || -> Result<(i32, String)> {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+            .args(["lint", "--ai", "mock"])
+            .current_dir(root)
+            .env("PATH", &path)
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(create_hook_json(py_file.to_str().unwrap()).as_bytes())?;
+        let output = child.wait_with_output()?;
+        Ok((
+            output.status.code().unwrap_or(-1),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ))
+    };
+
+    fs::write(&finding, "app.py:1:80: E501 Line too long (120 > 79)\n")?;
+    let (code, stderr) = lint()?;
+    assert_eq!(code, 0, "{stderr}");
+    assert!(!stderr.contains("LINT"), "{stderr}");
+
+    // Errors still block
+    fs::write(&finding, "app.py:1:1: F821 Undefined name `y`\n")?;
+    let (code, stderr) = lint()?;
+    assert_eq!(code, 2, "{stderr}");
+    assert!(stderr.contains("LINT ERRORS FOUND"), "{stderr}");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_lint_failure_message_template() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    let py_file = root.join("app.py");
+    fs::write(&py_file, "x = y\n")?;
+
+    let bin_dir = root.join("bin");
+    fake_tool(
+        &bin_dir,
+        "ruff",
+        "#!/bin/sh\ncase \"$*\" in\n  *--fix*|format*|*--version*) exit 0 ;;\nesac\necho 'app.py:1:5: F821 Undefined name `y`'\nexit 1\n",
+    )?;
+    fs::write(
+        root.join("guardrails.yaml"),
+        "exclude:\n  patterns: []\noutput:\n  templates:\n    lint_failure: \"[{{tool}}] {{count}} problem(s) in {{file}}\\n{{details}}\"\n",
+    )?;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .args(["lint", "--ai", "mock"])
+        .current_dir(root)
+        .env("PATH", path_with(&bin_dir))
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(create_hook_json(py_file.to_str().unwrap()).as_bytes())?;
+    let output = child.wait_with_output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(2), "{stderr}");
+    assert!(
+        stderr.starts_with("[ruff] 1 problem(s) in app.py\n"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("F821 Undefined name"), "{stderr}");
+
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn test_lint_json_report() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    let py_file = root.join("app.py");
+    fs::write(&py_file, "x = y\n")?;
+
+    let bin_dir = root.join("bin");
+    fake_tool(
+        &bin_dir,
+        "ruff",
+        "#!/bin/sh\ncase \"$*\" in\n  *--fix*|format*|*--version*) exit 0 ;;\nesac\necho 'app.py:1:5: F821 Undefined name `y`'\nexit 1\n",
+    )?;
+    fs::write(root.join("guardrails.yaml"), "exclude:\n  patterns: []\n")?;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .args(["lint", "--json", "--ai", "mock"])
+        .current_dir(root)
+        .env("PATH", path_with(&bin_dir))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(create_hook_json(py_file.to_str().unwrap()).as_bytes())?;
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(2), "{stdout}");
+    let report: serde_json::Value = serde_json::from_str(&stdout)?;
+    assert_eq!(report["operation"], "lint");
+    assert_eq!(report["cache_hit"], false);
+    assert_eq!(report["result"]["outcome"], "failure");
+    assert!(report["result"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("LINT ERRORS FOUND"));
+    assert_eq!(report["findings"][0]["rule"], "F821");
+    let steps = report["steps"].as_array().unwrap();
+    assert!(
+        steps
+            .iter()
+            .any(|step| step["program"] == "ruff" && step["success"] == false),
+        "{stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_preview_describes_hooks_for_file() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"app\"\n")?;
+    fs::write(root.join("app.py"), "def run():\n    return 1\n")?;
+    fs::create_dir_all(root.join("tests"))?;
+    fs::write(
+        root.join("tests/test_app.py"),
+        "from app import run\n\n\ndef test_run():\n    assert run() == 1\n",
+    )?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .args(["preview", "app.py"])
+        .current_dir(root)
+        .output()?;
+    assert!(output.status.success(), "{output:?}");
+
+    let preview: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(preview["excluded"], false);
+    assert!(preview["test_file"]
+        .as_str()
+        .is_some_and(|path| path.ends_with("tests/test_app.py")));
+    assert_eq!(preview["lint"]["enabled"], true);
+    assert_eq!(preview["test"]["excluded"], false);
+    assert_eq!(preview["test"]["lock"]["running"], false);
+    assert!(preview["tool_versions"].is_array());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .args(["preview", "missing.py"])
+        .current_dir(root)
+        .output()?;
+    assert!(!output.status.success());
+
+    Ok(())
+}
+
+#[test]
+fn test_generate_tests_with_mock_provider() -> Result<()> {
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"calc\"\n")?;
+    fs::create_dir_all(root.join("src/calc"))?;
+    fs::write(
+        root.join("src/calc/ops.py"),
+        "def add(a, b):\n    return a + b\n",
+    )?;
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+            .args(["generate-tests", "--ai", "mock"])
+            .args(args)
+            .current_dir(root)
+            .output()
+    };
+
+    let output = run(&["src/calc/ops.py", "--dry-run"])?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout.contains("import calc.ops"), "stdout: {stdout}");
+    assert!(stdout.contains("def test_add():"));
+    assert!(!root.join("tests/test_ops.py").exists());
+
+    let output = run(&["src/calc/ops.py"])?;
+    assert_eq!(output.status.code(), Some(0));
+    assert!(fs::read_to_string(root.join("tests/test_ops.py"))?.contains("def test_add():"));
+
+    // An existing test file is kept unless --force is given
+    let output = run(&["src/calc/ops.py"])?;
+    assert_ne!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+    let output = run(&["src/calc/ops.py", "--force", "--framework", "unittest"])?;
+    assert_eq!(output.status.code(), Some(0));
+    assert!(fs::read_to_string(root.join("tests/test_ops.py"))?.contains("class TestOps"));
+
+    Ok(())
+}
+
+#[test]
+fn test_fix_applies_mock_patch_for_recorded_failure() -> Result<()> {
+    use claude_python_guardrails::{AutomationResult, History};
+    use std::time::Duration;
+
+    let temp_dir = TempDir::new()?;
+    let root = temp_dir.path().canonicalize()?;
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"calc\"\n")?;
+    let source = root.join("calc.py");
+    fs::write(&source, "def add(a, b):\n    return a - b\n")?;
+
+    let responses = root.join("mock");
+    fs::create_dir_all(&responses)?;
+    let fix = serde_json::json!({
+        "explanation": "add subtracted instead of adding",
+        "diff": "--- a/calc.py\n+++ b/calc.py\n@@ -1,2 +1,2 @@\n def add(a, b):\n-    return a - b\n+    return a + b\n",
+    });
+    fs::write(responses.join("fix.json"), fix.to_string())?;
+    fs::write(
+        root.join("guardrails.yaml"),
+        format!(
+            "exclude:\n  patterns: []\nai:\n  provider: mock\n  mock_responses: {}\n",
+            responses.display()
+        ),
+    )?;
+
+    let run = || {
+        Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+            .args(["fix", "calc.py", "--from", "test", "--yes"])
+            .current_dir(&root)
+            .output()
+    };
+
+    // Nothing to fix before a failing run is recorded
+    let output = run()?;
+    assert_ne!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No failed test run"));
+
+    History::load(&root)?.record(
+        "test",
+        &source,
+        None,
+        &AutomationResult::Failure("FAILED test_calc.py::test_add - assert -1 == 3".to_string()),
+        Duration::from_secs(1),
+        Some("test_calc.py::test_add".to_string()),
+    )?;
+    let output = run()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("add subtracted instead of adding"));
+    assert!(stdout.contains("✨ Applied the patch"));
+    assert_eq!(
+        fs::read_to_string(&source)?,
+        "def add(a, b):\n    return a + b\n"
+    );
+
+    Ok(())
+}
+
+/// Serve canned GitHub release metadata and assets on localhost; `routes` maps paths to
+/// bodies given the server's base URL, which is returned
+fn serve_releases(routes: impl FnOnce(&str) -> Vec<(String, Vec<u8>)>) -> Result<String> {
+    use std::io::{BufRead, BufReader};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let base = format!("http://{}", listener.local_addr()?);
+    let routes = routes(&base);
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut reader = BufReader::new(&stream);
+            let mut request_line = String::new();
+            if reader.read_line(&mut request_line).is_err() {
+                continue;
+            }
+            let mut header = String::new();
+            while reader.read_line(&mut header).is_ok_and(|read| read > 2) {
+                header.clear();
+            }
+            let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+            let (status, body) = match routes.iter().find(|(route, _)| route == path) {
+                Some((_, body)) => ("200 OK", body.clone()),
+                None => ("404 Not Found", Vec::new()),
+            };
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = stream.write_all(&body);
+        }
+    });
+    Ok(base)
+}
+
+#[test]
+fn test_self_update_installs_verified_release() -> Result<()> {
+    use claude_python_guardrails::update::{binary_asset_name, release_target};
+    use sha2::{Digest, Sha256};
+
+    let Some(target) = release_target() else {
+        return Ok(());
+    };
+    let temp_dir = TempDir::new()?;
+    let exe = temp_dir.path().join("claude-python-guardrails");
+    fs::copy(env!("CARGO_BIN_EXE_claude-python-guardrails"), &exe)?;
+
+    let asset = binary_asset_name(target);
+    let new_binary = b"#!/bin/sh\necho updated\n".to_vec();
+    let checksum = format!("{:x}  {asset}\n", Sha256::digest(&new_binary));
+    let api = serve_releases(|base| {
+        let release = |tag: &str, checksum_file: &str| {
+            format!(
+                r#"{{"tag_name": "{tag}", "assets": [
+                    {{"name": "{asset}", "browser_download_url": "{base}/download/{asset}"}},
+                    {{"name": "{asset}.sha256", "browser_download_url": "{base}/{checksum_file}"}}
+                ]}}"#
+            )
+            .into_bytes()
+        };
+        let releases = "/repos/DigiBugCat/claude-python-guardrails/releases";
+        vec![
+            (
+                format!("{releases}/latest"),
+                release("v0.0.1", "good.sha256"),
+            ),
+            (
+                format!("{releases}/tags/v9.9.9"),
+                release("v9.9.9", "good.sha256"),
+            ),
+            (
+                format!("{releases}/tags/v9.9.8"),
+                release("v9.9.8", "bad.sha256"),
+            ),
+            (format!("/download/{asset}"), new_binary.clone()),
+            ("/good.sha256".to_string(), checksum.into_bytes()),
+            (
+                "/bad.sha256".to_string(),
+                format!("{}  {asset}\n", "0".repeat(64)).into_bytes(),
+            ),
+        ]
+    })?;
+
+    let self_update = |args: &[&str]| -> Result<std::process::Output> {
+        Ok(Command::new(&exe)
+            .arg("self-update")
+            .args(args)
+            .current_dir(temp_dir.path())
+            .env("GUARDRAILS_UPDATE_API", &api)
+            .output()?)
+    };
+
+    // The latest release is older than this build
+    let output = self_update(&["--check"])?;
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("up to date"));
+
+    let output = self_update(&["--pin", "9.9.9", "--check"])?;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("v9.9.9 (pinned) is available"));
+
+    // A checksum mismatch leaves the binary alone
+    let output = self_update(&["--pin", "v9.9.8"])?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Checksum mismatch"));
+    assert_ne!(fs::read(&exe)?, new_binary);
+
+    let output = self_update(&["--pin", "9.9.9"])?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(fs::read(&exe)?, new_binary);
+
+    // Missing releases are reported
+    let output = Command::new(env!("CARGO_BIN_EXE_claude-python-guardrails"))
+        .args(["self-update", "--pin", "1.0.0"])
+        .current_dir(temp_dir.path())
+        .env("GUARDRAILS_UPDATE_API", &api)
+        .output()?;
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No release v1.0.0 found"));
+    Ok(())
+}